@@ -0,0 +1,24 @@
+use entity::{escrow_keys, files};
+use serde::Serialize;
+
+/// File of the user with its key escrowed to the organization recovery key, the admin
+/// decrypts the key with the recovery private key and with it the name and the content.
+#[derive(Debug, Serialize)]
+pub struct EscrowedFile {
+    #[serde(flatten)]
+    pub file: files::Model,
+    /// File key encrypted with the organization recovery public key
+    pub escrow_key: String,
+    /// Fingerprint of the recovery key the file key is encrypted with
+    pub fingerprint: String,
+}
+
+impl From<(escrow_keys::Model, files::Model)> for EscrowedFile {
+    fn from((escrow_key, file): (escrow_keys::Model, files::Model)) -> Self {
+        Self {
+            file,
+            escrow_key: escrow_key.encrypted_key,
+            fingerprint: escrow_key.fingerprint,
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod escrowed_file;
@@ -1,2 +1,3 @@
 pub mod response;
 pub mod stats;
+pub mod verdict;
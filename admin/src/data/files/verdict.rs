@@ -0,0 +1,60 @@
+use ::error::{AppResult, Error};
+use entity::file_verdicts::Verdict;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum length of the name of the scanner
+pub const MAX_SCANNER_LENGTH: usize = 64;
+
+/// Maximum length of the details of the verdict
+pub const MAX_DETAILS_LENGTH: usize = 1_000;
+
+/// Verdict of the scanner on the file, the scanner is named in the path
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Record {
+    pub verdict: Option<Verdict>,
+
+    /// What the scanner found, the name of the signature or the rule that matched
+    pub details: Option<String>,
+}
+
+impl Validation for Record {
+    fn rules(&self) -> Vec<validr::Rule<Self>> {
+        vec![
+            Rule::new("verdict", |obj: &Self, error| {
+                if obj.verdict.is_none() {
+                    error.add("required")
+                }
+            }),
+            rule_length_max!(details, MAX_DETAILS_LENGTH),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(details)]
+    }
+}
+
+impl Record {
+    pub fn into_values(self) -> AppResult<(Verdict, Option<String>)> {
+        let data = self.validate()?;
+
+        let details = data.details.filter(|details| !details.is_empty());
+
+        Ok((data.verdict.unwrap(), details))
+    }
+}
+
+/// Scanners are named with the lowercase letters, the numbers, dashes and underscores
+pub fn scanner(name: &str) -> AppResult<String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SCANNER_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+
+    match valid {
+        true => Ok(name.to_string()),
+        false => Err(Error::as_validation("scanner", "invalid_scanner")),
+    }
+}
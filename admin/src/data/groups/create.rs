@@ -0,0 +1,42 @@
+use ::error::AppResult;
+use fs::MAX_CHUNK_SIZE_BYTES;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Create the group or replace the name and the quota of the existing one
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Create {
+    pub name: Option<String>,
+
+    /// Pooled quota of all the members, `None` means the members
+    /// are only limited by their own quotas.
+    pub quota: Option<i64>,
+}
+
+impl Validation for Create {
+    fn rules(&self) -> Vec<validr::Rule<Self>> {
+        vec![
+            rule_required!(name),
+            rule_length_max!(name, 255),
+            Rule::new("quota", |obj: &Self, error| {
+                if let Some(v) = obj.quota {
+                    if v < MAX_CHUNK_SIZE_BYTES as i64 {
+                        error.add(format!("min:{}", MAX_CHUNK_SIZE_BYTES).as_str())
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(name)]
+    }
+}
+
+impl Create {
+    pub fn into_values(self) -> AppResult<(String, Option<i64>)> {
+        let data = self.validate()?;
+
+        Ok((data.name.unwrap(), data.quota))
+    }
+}
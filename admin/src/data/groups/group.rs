@@ -0,0 +1,25 @@
+use entity::{group_members::Role, groups, users, Uuid};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Group {
+    #[serde(flatten)]
+    pub group: groups::Model,
+
+    /// Space used by all the members together
+    pub used_space: i64,
+
+    pub members: Vec<GroupMember>,
+
+    /// Folders shared with every member of the group
+    pub folders: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupMember {
+    #[serde(flatten)]
+    pub user: users::Model,
+
+    /// Role of the member in the spaces of the group
+    pub role: Role,
+}
@@ -0,0 +1,38 @@
+use ::error::AppResult;
+use entity::group_members::Role;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Role of the member in the spaces of the group
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Member {
+    /// One of `owner`, `editor` or `viewer`, members are editors by default
+    pub role: Option<String>,
+}
+
+impl Validation for Member {
+    fn rules(&self) -> Vec<validr::Rule<Self>> {
+        vec![rule_in!(
+            role,
+            Into::<Vec<String>>::into([
+                "owner".to_string(),
+                "editor".to_string(),
+                "viewer".to_string()
+            ])
+        )]
+    }
+}
+
+impl Member {
+    pub fn into_role(self) -> AppResult<Role> {
+        let data = self.validate()?;
+
+        let role = match data.role.as_deref() {
+            Some("owner") => Role::Owner,
+            Some("viewer") => Role::Viewer,
+            _ => Role::Editor,
+        };
+
+        Ok(role)
+    }
+}
@@ -0,0 +1,38 @@
+use entity::{DbErr, FromQueryResult, QueryResult};
+use serde::{Deserialize, Serialize};
+
+/// Depth of the queue for one kind of the jobs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Metrics {
+    pub kind: String,
+
+    /// Jobs waiting to be run, including the ones waiting for their next attempt
+    pub pending: i64,
+
+    /// Jobs the worker is running right now
+    pub running: i64,
+
+    /// Jobs that ran out of attempts and wait for the admin to retry or discard them
+    pub failed: i64,
+
+    /// When the job that has been waiting the longest was due
+    pub oldest_run_at: Option<i64>,
+}
+
+/// Number of the jobs of one kind in one of the states
+#[derive(Debug, Clone)]
+pub(crate) struct Count {
+    pub(crate) kind: String,
+    pub(crate) count: i64,
+    pub(crate) oldest_run_at: Option<i64>,
+}
+
+impl FromQueryResult for Count {
+    fn from_query_result(res: &QueryResult, _pre: &str) -> Result<Self, DbErr> {
+        Ok(Self {
+            kind: res.try_get_by("kind")?,
+            count: res.try_get_by("count")?,
+            oldest_run_at: res.try_get_by("oldest_run_at")?,
+        })
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Query the jobs that ran out of attempts
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Search {
+    /// Only the jobs of this kind, `purge_file` for example
+    pub kind: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+impl Validation for Search {
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(kind)]
+    }
+}
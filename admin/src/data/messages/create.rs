@@ -0,0 +1,69 @@
+use ::error::AppResult;
+use chrono::Utc;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum length of the message body
+pub const MAX_MESSAGE_LENGTH: usize = 10_000;
+
+/// Send the message to the given users, or to all the users on the platform
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Create {
+    pub title: Option<String>,
+    pub body: Option<String>,
+
+    /// Recipients of the message, the message is broadcast to everyone if not set
+    pub user_ids: Option<Vec<Uuid>>,
+
+    /// Timestamp after which the message is not shown anymore, never if not set
+    pub expires_at: Option<i64>,
+}
+
+impl Validation for Create {
+    fn rules(&self) -> Vec<validr::Rule<Self>> {
+        vec![
+            rule_required!(title),
+            rule_length_max!(title, 255),
+            rule_required!(body),
+            rule_length_max!(body, MAX_MESSAGE_LENGTH),
+            Rule::new("user_ids", |obj: &Self, error| {
+                if obj.user_ids.as_ref().map(|ids| ids.is_empty()) == Some(true) {
+                    error.add("required")
+                }
+            }),
+            Rule::new("expires_at", |obj: &Self, error| {
+                if let Some(expires_at) = obj.expires_at {
+                    if expires_at <= Utc::now().timestamp() {
+                        error.add("expires_at_in_the_past")
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(title), modifier_trim!(body)]
+    }
+}
+
+pub type CreateMessageValues = (String, String, Option<Vec<Uuid>>, Option<i64>);
+
+impl Create {
+    pub fn into_values(self) -> AppResult<CreateMessageValues> {
+        let data = self.validate()?;
+
+        let user_ids = data.user_ids.map(|mut ids| {
+            ids.sort();
+            ids.dedup();
+            ids
+        });
+
+        Ok((
+            data.title.unwrap(),
+            data.body.unwrap(),
+            user_ids,
+            data.expires_at,
+        ))
+    }
+}
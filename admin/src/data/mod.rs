@@ -1,4 +1,9 @@
+pub mod escrow;
 pub mod files;
+pub mod groups;
 pub mod invitations;
+pub mod jobs;
+pub mod messages;
+pub mod rebalances;
 pub mod sessions;
 pub mod users;
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+
+use ::error::AppResult;
+use fs::MAX_CHUNK_SIZE_BYTES;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Start moving the chunks between the shards
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Create {
+    /// Weight of each of the shard directories, the shards that are left out are
+    /// emptied. Without the layout every shard is filled to the same percentage.
+    pub layout: Option<BTreeMap<String, u64>>,
+
+    /// Limit of how fast the chunks are moved, so the disks can still serve
+    /// the users while they are being rebalanced. Unlimited if not set.
+    pub bytes_per_second: Option<i64>,
+}
+
+impl Validation for Create {
+    fn rules(&self) -> Vec<validr::Rule<Self>> {
+        vec![
+            Rule::new("layout", |obj: &Self, error| {
+                if let Some(layout) = &obj.layout {
+                    if layout.values().all(|weight| *weight == 0) {
+                        error.add("no_weights");
+                    }
+                }
+            }),
+            Rule::new("bytes_per_second", |obj: &Self, error| {
+                if let Some(v) = obj.bytes_per_second {
+                    if v < MAX_CHUNK_SIZE_BYTES as i64 {
+                        error.add(format!("min:{}", MAX_CHUNK_SIZE_BYTES).as_str())
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl Create {
+    pub fn into_values(self) -> AppResult<(Option<BTreeMap<String, u64>>, Option<i64>)> {
+        let data = self.validate()?;
+
+        Ok((data.layout, data.bytes_per_second))
+    }
+}
@@ -0,0 +1,30 @@
+use entity::rebalances;
+use fs::shards::ShardUsage;
+use serde::Serialize;
+
+/// Space taken by the chunks in one of the shard directories
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Shard {
+    pub dir: String,
+    pub chunks: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
+impl From<ShardUsage> for Shard {
+    fn from(usage: ShardUsage) -> Self {
+        Self {
+            dir: usage.dir,
+            chunks: usage.chunks,
+            used: usage.used,
+            available: usage.available,
+        }
+    }
+}
+
+/// Shards as they are now, with the rebalances that were run on them
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Shards {
+    pub shards: Vec<Shard>,
+    pub rebalances: Vec<rebalances::Model>,
+}
@@ -5,13 +5,22 @@ use validr::*;
 pub struct Update {
     pub role: Option<String>,
     pub quota: Option<i64>,
+    /// Number of the previous versions kept for each of the user's files
+    pub version_limit: Option<i32>,
 }
 
 impl Validation for Update {
     fn rules(&self) -> Vec<Rule<Self>> {
-        vec![rule_in!(
-            role,
-            Into::<Vec<String>>::into(["admin".to_string(), "user".to_string()])
-        )]
+        vec![
+            rule_in!(
+                role,
+                Into::<Vec<String>>::into(["admin".to_string(), "user".to_string()])
+            ),
+            Rule::new("version_limit", |obj: &Self, error| {
+                if obj.version_limit.map(|limit| limit < 0).unwrap_or(false) {
+                    error.add("min");
+                }
+            }),
+        ]
     }
 }
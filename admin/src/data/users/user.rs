@@ -15,6 +15,8 @@ pub struct User {
     pub email_verified_at: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub crypto_version: i32,
+    pub version_limit: Option<i32>,
     pub last_session: Option<Session>,
 }
 
@@ -36,6 +38,8 @@ impl FromQueryResult for User {
             email_verified_at: user.email_verified_at,
             created_at: user.created_at,
             updated_at: user.updated_at,
+            crypto_version: user.crypto_version,
+            version_limit: user.version_limit,
             last_session,
         })
     }
@@ -0,0 +1,50 @@
+use entity::{
+    escrow_keys, files, user_files, ColumnTrait, ConnectionTrait, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Uuid,
+};
+use error::{AppResult, Error};
+
+use crate::data::escrow::escrowed_file::EscrowedFile;
+
+use super::Repository;
+
+pub(crate) struct EscrowRepository<'ctx, T: ConnectionTrait> {
+    repository: &'ctx Repository<'ctx, T>,
+}
+
+impl<'ctx, T> EscrowRepository<'ctx, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'ctx Repository<'ctx, T>) -> Self {
+        Self { repository }
+    }
+
+    /// List the files owned by the user that have their key escrowed
+    pub(crate) async fn find(&self, user_id: Uuid) -> AppResult<Vec<EscrowedFile>> {
+        let files = escrow_keys::Entity::find()
+            .join(JoinType::InnerJoin, escrow_keys::Relation::Files.def())
+            .join(JoinType::InnerJoin, files::Relation::UserFiles.def())
+            .filter(user_files::Column::UserId.eq(user_id))
+            .filter(user_files::Column::IsOwner.eq(true))
+            .select_also(files::Entity)
+            .order_by_asc(files::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .filter_map(|(escrow_key, file)| file.map(|file| (escrow_key, file).into()))
+            .collect::<Vec<_>>();
+
+        Ok(files)
+    }
+
+    /// Get the file with the escrowed key, files without it cannot be recovered
+    pub(crate) async fn get(&self, file_id: Uuid) -> AppResult<EscrowedFile> {
+        escrow_keys::Entity::find_by_id(file_id)
+            .find_also_related(files::Entity)
+            .one(self.repository.connection())
+            .await?
+            .and_then(|(escrow_key, file)| file.map(|file| (escrow_key, file).into()))
+            .ok_or_else(|| Error::NotFound("escrowed_file_not_found".to_string()))
+    }
+}
@@ -1,11 +1,14 @@
 use crate::data::files::stats::Stats;
 
 use super::Repository;
+use chrono::Utc;
 use entity::{
-    files, user_files, ColumnTrait, ConnectionTrait, EntityTrait, Expr, IntoCondition, JoinType,
-    QueryFilter, QuerySelect, RelationTrait, Uuid,
+    audit_logs,
+    file_verdicts::{self, Verdict},
+    files, user_files, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, Expr, IntoCondition,
+    JoinType, OnConflict, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Uuid,
 };
-use error::AppResult;
+use error::{AppResult, Error};
 use fs::prelude::*;
 
 pub(crate) struct FilesRepository<'repository, T: ConnectionTrait> {
@@ -97,6 +100,145 @@ where
             .rows_affected)
     }
 
+    /// Put the legal hold on the file (or the folder with everything inside of it),
+    /// or release it. The change is recorded in the audit log.
+    pub(crate) async fn set_legal_hold(
+        &self,
+        actor_id: Uuid,
+        file_id: Uuid,
+        legal_hold: bool,
+    ) -> AppResult<files::Model> {
+        let file = files::Entity::find_by_id(file_id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        files::Entity::update(files::ActiveModel {
+            id: ActiveValue::Set(file.id),
+            legal_hold: ActiveValue::Set(legal_hold),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        let action = match legal_hold {
+            true => audit_logs::Action::HoldSet,
+            false => audit_logs::Action::HoldReleased,
+        };
+        storage::audit(self.repository.connection(), actor_id, &[file.id], action).await?;
+
+        Ok(files::Model { legal_hold, ..file })
+    }
+
+    /// Get the id of the user owning the file
+    pub(crate) async fn owner(&self, file_id: Uuid) -> AppResult<Uuid> {
+        user_files::Entity::find()
+            .filter(user_files::Column::FileId.eq(file_id))
+            .filter(user_files::Column::IsOwner.eq(true))
+            .one(self.repository.connection())
+            .await?
+            .map(|user_file| user_file.user_id)
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))
+    }
+
+    /// Get the audit log of the file, latest entries first
+    pub(crate) async fn audit_log(&self, file_id: Uuid) -> AppResult<Vec<audit_logs::Model>> {
+        let logs = audit_logs::Entity::find()
+            .filter(audit_logs::Column::FileId.eq(file_id))
+            .order_by_desc(audit_logs::Column::Id)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(logs)
+    }
+
+    /// Record the verdict of the scanner on the file, it replaces the verdict
+    /// the same scanner gave the file before. The change is recorded in the audit log.
+    pub(crate) async fn record_verdict(
+        &self,
+        actor_id: Uuid,
+        file_id: Uuid,
+        scanner: String,
+        verdict: Verdict,
+        details: Option<String>,
+    ) -> AppResult<file_verdicts::Model> {
+        let file = files::Entity::find_by_id(file_id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        if file.mime.as_str() == "dir" {
+            return Err(Error::BadRequest("folder_has_no_verdict".to_string()));
+        }
+
+        let now = Utc::now().timestamp();
+
+        file_verdicts::Entity::insert(file_verdicts::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            file_id: ActiveValue::Set(file.id),
+            scanner: ActiveValue::Set(scanner.clone()),
+            verdict: ActiveValue::Set(verdict),
+            details: ActiveValue::Set(details),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                file_verdicts::Column::FileId,
+                file_verdicts::Column::Scanner,
+            ])
+            .update_columns([
+                file_verdicts::Column::Verdict,
+                file_verdicts::Column::Details,
+                file_verdicts::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        storage::audit(
+            self.repository.connection(),
+            actor_id,
+            &[file.id],
+            audit_logs::Action::VerdictRecorded,
+        )
+        .await?;
+
+        file_verdicts::Entity::find()
+            .filter(file_verdicts::Column::FileId.eq(file.id))
+            .filter(file_verdicts::Column::Scanner.eq(scanner))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("verdict_not_found".to_string()))
+    }
+
+    /// Remove the verdict of the scanner from the file
+    pub(crate) async fn remove_verdict(
+        &self,
+        actor_id: Uuid,
+        file_id: Uuid,
+        scanner: String,
+    ) -> AppResult<()> {
+        let result = file_verdicts::Entity::delete_many()
+            .filter(file_verdicts::Column::FileId.eq(file_id))
+            .filter(file_verdicts::Column::Scanner.eq(scanner))
+            .exec(self.repository.connection())
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(Error::NotFound("verdict_not_found".to_string()));
+        }
+
+        storage::audit(
+            self.repository.connection(),
+            actor_id,
+            &[file_id],
+            audit_logs::Action::VerdictRecorded,
+        )
+        .await
+    }
+
     /// Get the available space on the storage provider
     pub(crate) async fn available_space(&self) -> AppResult<u64> {
         let fs = Fs::new(&self.repository.context().config);
@@ -0,0 +1,257 @@
+use chrono::Utc;
+use entity::{
+    files, group_folders, group_members, groups, numeric::Numeric, user_files, users, ActiveValue,
+    ColumnTrait, ConnectionTrait, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, Uuid,
+};
+use error::{AppResult, Error};
+
+use crate::data::groups::{
+    create::Create,
+    group::{Group, GroupMember},
+    member::Member,
+};
+
+use super::Repository;
+
+pub(crate) struct GroupsRepository<'ctx, T: ConnectionTrait> {
+    repository: &'ctx Repository<'ctx, T>,
+}
+
+impl<'ctx, T> GroupsRepository<'ctx, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'ctx Repository<'ctx, T>) -> Self {
+        Self { repository }
+    }
+
+    /// List all the groups on the platform
+    pub(crate) async fn find(&self) -> AppResult<Vec<groups::Model>> {
+        let groups = groups::Entity::find()
+            .order_by_asc(groups::Column::Name)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(groups)
+    }
+
+    /// Get the group with its members, folders and the space they are using
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<Group> {
+        let group = self.model(id).await?;
+
+        let members = group_members::Entity::find()
+            .filter(group_members::Column::GroupId.eq(id))
+            .join(JoinType::InnerJoin, group_members::Relation::Users.def())
+            .select_also(users::Entity)
+            .order_by_asc(users::Column::Email)
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .filter_map(|(member, user)| {
+                user.map(|user| GroupMember {
+                    user,
+                    role: member.role,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let folders = group_folders::Entity::find()
+            .filter(group_folders::Column::GroupId.eq(id))
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .map(|folder| folder.file_id)
+            .collect::<Vec<_>>();
+
+        let used_space = self
+            .used_space(members.iter().map(|member| member.user.id).collect())
+            .await?;
+
+        Ok(Group {
+            group,
+            used_space,
+            members,
+            folders,
+        })
+    }
+
+    /// Create a new group, the name of the group has to be unique
+    pub(crate) async fn create(&self, data: Create) -> AppResult<groups::Model> {
+        let (name, quota) = data.into_values()?;
+        self.unique_name(&name, None).await?;
+
+        let id = Uuid::new_v4();
+        let now = Utc::now().timestamp();
+
+        groups::Entity::insert(groups::ActiveModel {
+            id: ActiveValue::Set(id),
+            name: ActiveValue::Set(name),
+            quota: ActiveValue::Set(quota),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+        })
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        self.model(id).await
+    }
+
+    /// Update the name and the quota of the group
+    pub(crate) async fn update(&self, id: Uuid, data: Create) -> AppResult<groups::Model> {
+        let (name, quota) = data.into_values()?;
+        self.model(id).await?;
+        self.unique_name(&name, Some(id)).await?;
+
+        groups::Entity::update(groups::ActiveModel {
+            id: ActiveValue::Set(id),
+            name: ActiveValue::Set(name),
+            quota: ActiveValue::Set(quota),
+            updated_at: ActiveValue::Set(Utc::now().timestamp()),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        self.model(id).await
+    }
+
+    /// Delete the group, members keep their files
+    pub(crate) async fn delete(&self, id: Uuid) -> AppResult<()> {
+        self.model(id).await?;
+
+        groups::Entity::delete_by_id(id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Add the user to the group, adding the existing member changes their role
+    pub(crate) async fn add_member(&self, id: Uuid, user_id: Uuid, data: Member) -> AppResult<()> {
+        let role = data.into_role()?;
+        self.model(id).await?;
+
+        users::Entity::find_by_id(user_id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("User not found".to_string()))?;
+
+        let existing = group_members::Entity::find_by_id((id, user_id))
+            .one(self.repository.connection())
+            .await?;
+
+        let member = group_members::ActiveModel {
+            group_id: ActiveValue::Set(id),
+            user_id: ActiveValue::Set(user_id),
+            role: ActiveValue::Set(role),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+        };
+
+        match existing {
+            Some(existing) => {
+                group_members::Entity::update(group_members::ActiveModel {
+                    created_at: ActiveValue::Set(existing.created_at),
+                    ..member
+                })
+                .exec(self.repository.connection())
+                .await?;
+            }
+            None => {
+                group_members::Entity::insert(member)
+                    .exec_without_returning(self.repository.connection())
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the user from the group
+    pub(crate) async fn remove_member(&self, id: Uuid, user_id: Uuid) -> AppResult<()> {
+        group_members::Entity::delete_by_id((id, user_id))
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Add the folder that should be shared with every member of the group
+    pub(crate) async fn add_folder(&self, id: Uuid, file_id: Uuid) -> AppResult<()> {
+        self.model(id).await?;
+
+        let folder = files::Entity::find_by_id(file_id)
+            .one(self.repository.connection())
+            .await?
+            .filter(|file| file.mime == "dir")
+            .ok_or_else(|| Error::NotFound("directory_not_found".to_string()))?;
+
+        let existing = group_folders::Entity::find_by_id((id, folder.id))
+            .one(self.repository.connection())
+            .await?;
+
+        if existing.is_none() {
+            group_folders::Entity::insert(group_folders::ActiveModel {
+                group_id: ActiveValue::Set(id),
+                file_id: ActiveValue::Set(folder.id),
+                created_at: ActiveValue::Set(Utc::now().timestamp()),
+            })
+            .exec_without_returning(self.repository.connection())
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the folder from the default folders of the group
+    pub(crate) async fn remove_folder(&self, id: Uuid, file_id: Uuid) -> AppResult<()> {
+        group_folders::Entity::delete_by_id((id, file_id))
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn model(&self, id: Uuid) -> AppResult<groups::Model> {
+        groups::Entity::find_by_id(id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("group_not_found".to_string()))
+    }
+
+    async fn unique_name(&self, name: &str, except: Option<Uuid>) -> AppResult<()> {
+        let mut query = groups::Entity::find().filter(groups::Column::Name.eq(name));
+
+        if let Some(id) = except {
+            query = query.filter(groups::Column::Id.ne(id));
+        }
+
+        if query.one(self.repository.connection()).await?.is_some() {
+            return Err(Error::as_validation("name", "unique"));
+        }
+
+        Ok(())
+    }
+
+    /// Sum of the space used by the given users
+    async fn used_space(&self, user_ids: Vec<Uuid>) -> AppResult<i64> {
+        if user_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let used_space = user_files::Entity::find()
+            .select_only()
+            .filter(user_files::Column::UserId.is_in(user_ids))
+            .filter(user_files::Column::IsOwner.eq(true))
+            .join(JoinType::InnerJoin, user_files::Relation::Files.def())
+            .column_as(files::Column::Size.sum(), "sum_of_size")
+            .into_tuple::<Option<Numeric>>()
+            .one(self.repository.connection())
+            .await?;
+
+        Ok(used_space
+            .flatten()
+            .map(|numeric| numeric.into())
+            .unwrap_or(0))
+    }
+}
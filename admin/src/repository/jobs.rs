@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use entity::{
+    jobs, paginated::Paginated, ColumnTrait, Condition, ConnectionTrait, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Uuid,
+};
+use error::{AppResult, Error};
+use validr::Validation;
+
+use crate::data::jobs::{
+    metrics::{Count, Metrics},
+    search::Search,
+};
+
+use super::Repository;
+
+pub(crate) struct JobsRepository<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+}
+
+impl<'repository, T> JobsRepository<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>) -> Self {
+        Self { repository }
+    }
+
+    /// Queue the jobs are pushed to and picked up from
+    fn queue(&self) -> ::jobs::repository::Repository<'repository, T> {
+        ::jobs::repository::Repository::new(self.repository.connection())
+    }
+
+    /// List the jobs that ran out of attempts, the most recently failed first
+    pub(crate) async fn failed(&self, search: Search) -> AppResult<Paginated<jobs::Model>> {
+        let search = search.validate()?;
+
+        let mut query = jobs::Entity::find().filter(jobs::Column::FailedAt.is_not_null());
+
+        if let Some(kind) = search.kind.as_deref().filter(|kind| !kind.is_empty()) {
+            query = query.filter(jobs::Column::Kind.eq(kind));
+        }
+
+        let total = query.clone().count(self.repository.connection()).await?;
+
+        let jobs = query
+            .order_by_desc(jobs::Column::FailedAt)
+            .limit(search.limit.unwrap_or(15))
+            .offset(search.offset.unwrap_or(0))
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(Paginated::new(jobs, total))
+    }
+
+    /// Get any job with its payload and the error from its last attempt
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<jobs::Model> {
+        self.queue().get(id).await
+    }
+
+    /// Put the failed job back to the queue
+    pub(crate) async fn retry(&self, id: Uuid) -> AppResult<jobs::Model> {
+        self.queue().retry(id).await
+    }
+
+    /// Remove the failed job from the queue for good
+    pub(crate) async fn discard(&self, id: Uuid) -> AppResult<()> {
+        let job = self.get(id).await?;
+
+        if job.failed_at.is_none() {
+            return Err(Error::BadRequest("job_not_failed".to_string()));
+        }
+
+        jobs::Entity::delete_by_id(job.id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Depth of the queue for each kind of the jobs that are in it
+    pub(crate) async fn metrics(&self) -> AppResult<Vec<Metrics>> {
+        let waiting = Condition::all()
+            .add(jobs::Column::FailedAt.is_null())
+            .add(jobs::Column::LockedAt.is_null());
+        let running = Condition::all()
+            .add(jobs::Column::FailedAt.is_null())
+            .add(jobs::Column::LockedAt.is_not_null());
+        let failed = Condition::all().add(jobs::Column::FailedAt.is_not_null());
+
+        let mut metrics: BTreeMap<String, Metrics> = BTreeMap::new();
+
+        for count in self.count(waiting).await? {
+            let metric = metrics.entry(count.kind.clone()).or_default();
+            metric.pending = count.count;
+            metric.oldest_run_at = count.oldest_run_at;
+        }
+
+        for count in self.count(running).await? {
+            metrics.entry(count.kind.clone()).or_default().running = count.count;
+        }
+
+        for count in self.count(failed).await? {
+            metrics.entry(count.kind.clone()).or_default().failed = count.count;
+        }
+
+        Ok(metrics
+            .into_iter()
+            .map(|(kind, metric)| Metrics { kind, ..metric })
+            .collect())
+    }
+
+    /// Count the jobs matching the condition for each kind
+    async fn count(&self, condition: Condition) -> AppResult<Vec<Count>> {
+        let query = jobs::Entity::find()
+            .select_only()
+            .column_as(jobs::Column::Kind, "kind")
+            .column_as(jobs::Column::Id.count(), "count")
+            .column_as(jobs::Column::RunAt.min(), "oldest_run_at")
+            .filter(condition)
+            .group_by(jobs::Column::Kind);
+
+        let counts = query
+            .into_model::<Count>()
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(counts)
+    }
+}
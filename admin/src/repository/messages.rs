@@ -0,0 +1,99 @@
+use chrono::Utc;
+use entity::{
+    messages, users, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use crate::data::messages::create::Create;
+
+use super::Repository;
+
+pub(crate) struct MessagesRepository<'ctx, T: ConnectionTrait> {
+    repository: &'ctx Repository<'ctx, T>,
+}
+
+impl<'ctx, T> MessagesRepository<'ctx, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'ctx Repository<'ctx, T>) -> Self {
+        Self { repository }
+    }
+
+    /// List all the messages that were sent, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<messages::Model>> {
+        let messages = messages::Entity::find()
+            .order_by_desc(messages::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(messages)
+    }
+
+    /// Send the message, one message is created for each of the recipients,
+    /// or a single one for everyone when the message is broadcast.
+    pub(crate) async fn create(
+        &self,
+        sender_id: Uuid,
+        data: Create,
+    ) -> AppResult<Vec<messages::Model>> {
+        let (title, body, user_ids, expires_at) = data.into_values()?;
+
+        let recipients = match user_ids {
+            Some(user_ids) => {
+                let found = users::Entity::find()
+                    .filter(users::Column::Id.is_in(user_ids.clone()))
+                    .count(self.repository.connection())
+                    .await?;
+
+                if found != user_ids.len() as u64 {
+                    return Err(Error::NotFound("user_not_found".to_string()));
+                }
+
+                user_ids.into_iter().map(Some).collect::<Vec<_>>()
+            }
+            None => vec![None],
+        };
+
+        let created_at = Utc::now().timestamp();
+
+        let messages = recipients
+            .into_iter()
+            .map(|user_id| messages::Model {
+                id: Uuid::new_v4(),
+                sender_id: Some(sender_id),
+                user_id,
+                title: title.clone(),
+                body: body.clone(),
+                created_at,
+                expires_at,
+            })
+            .collect::<Vec<_>>();
+
+        messages::Entity::insert_many(
+            messages
+                .iter()
+                .cloned()
+                .map(messages::ActiveModel::from)
+                .collect::<Vec<_>>(),
+        )
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Delete the message, it is not shown to the users anymore
+    pub(crate) async fn delete(&self, id: Uuid) -> AppResult<()> {
+        let result = messages::Entity::delete_by_id(id)
+            .exec(self.repository.connection())
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(Error::NotFound("message_not_found".to_string()));
+        }
+
+        Ok(())
+    }
+}
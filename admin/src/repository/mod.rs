@@ -1,5 +1,10 @@
+pub(crate) mod escrow;
 pub(crate) mod files;
+pub(crate) mod groups;
 pub(crate) mod invitations;
+pub(crate) mod jobs;
+pub(crate) mod messages;
+pub(crate) mod rebalances;
 pub(crate) mod sessions;
 pub(crate) mod users;
 
@@ -30,6 +35,13 @@ where
         self.context
     }
 
+    pub(crate) fn escrow<'repository>(&'ctx self) -> escrow::EscrowRepository<'repository, T>
+    where
+        Self: 'repository,
+    {
+        escrow::EscrowRepository::new(self)
+    }
+
     pub(crate) fn files<'repository>(&'ctx self) -> files::FilesRepository<'repository, T>
     where
         Self: 'repository,
@@ -37,6 +49,13 @@ where
         files::FilesRepository::new(self)
     }
 
+    pub(crate) fn groups<'repository>(&'ctx self) -> groups::GroupsRepository<'repository, T>
+    where
+        Self: 'repository,
+    {
+        groups::GroupsRepository::new(self)
+    }
+
     pub(crate) fn invitations<'repository>(
         &'ctx self,
     ) -> invitations::InvitationsRepository<'repository, T>
@@ -46,6 +65,29 @@ where
         invitations::InvitationsRepository::new(self)
     }
 
+    pub(crate) fn jobs<'repository>(&'ctx self) -> jobs::JobsRepository<'repository, T>
+    where
+        Self: 'repository,
+    {
+        jobs::JobsRepository::new(self)
+    }
+
+    pub(crate) fn messages<'repository>(&'ctx self) -> messages::MessagesRepository<'repository, T>
+    where
+        Self: 'repository,
+    {
+        messages::MessagesRepository::new(self)
+    }
+
+    pub(crate) fn rebalances<'repository>(
+        &'ctx self,
+    ) -> rebalances::RebalancesRepository<'repository, T>
+    where
+        Self: 'repository,
+    {
+        rebalances::RebalancesRepository::new(self)
+    }
+
     pub(crate) fn sessions<'repository>(&'ctx self) -> sessions::SessionsRepository<'repository, T>
     where
         Self: 'repository,
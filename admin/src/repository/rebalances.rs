@@ -0,0 +1,167 @@
+use chrono::Utc;
+use entity::{
+    rebalances, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    Uuid,
+};
+use error::{AppResult, Error};
+use fs::{prelude::*, shards};
+use storage::jobs::{RebalanceJob, REBALANCE_STORAGE};
+
+use crate::data::rebalances::{create::Create, shard::Shard};
+
+use super::Repository;
+
+pub(crate) struct RebalancesRepository<'ctx, T: ConnectionTrait> {
+    repository: &'ctx Repository<'ctx, T>,
+}
+
+impl<'ctx, T> RebalancesRepository<'ctx, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'ctx Repository<'ctx, T>) -> Self {
+        Self { repository }
+    }
+
+    /// Measure the space the chunks take in each of the shards
+    pub(crate) async fn shards(&self) -> AppResult<Vec<Shard>> {
+        let mut usage = vec![];
+
+        for dir in self.dirs() {
+            usage.push(shards::usage(&dir).await?.into());
+        }
+
+        Ok(usage)
+    }
+
+    /// List all the rebalances, the latest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<rebalances::Model>> {
+        let rebalances = rebalances::Entity::find()
+            .order_by_desc(rebalances::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(rebalances)
+    }
+
+    /// Queue the rebalance for the background worker, only one can run at the time
+    pub(crate) async fn create(&self, data: Create) -> AppResult<rebalances::Model> {
+        let (layout, bytes_per_second) = data.into_values()?;
+        let dirs = self.dirs();
+
+        if dirs.len() < 2 {
+            return Err(Error::BadRequest("shards_not_configured".to_string()));
+        }
+
+        if let Some(layout) = layout.as_ref() {
+            if layout.keys().any(|dir| !dirs.contains(dir)) {
+                return Err(Error::as_validation("layout", "unknown_shard"));
+            }
+        }
+
+        self.none_active().await?;
+
+        let id = Uuid::new_v4();
+
+        rebalances::Entity::insert(rebalances::ActiveModel {
+            id: ActiveValue::Set(id),
+            layout: ActiveValue::Set(layout.map(serde_json::to_value).transpose()?),
+            bytes_per_second: ActiveValue::Set(bytes_per_second),
+            status: ActiveValue::Set(rebalances::Status::Pending),
+            moved_chunks: ActiveValue::Set(0),
+            moved_bytes: ActiveValue::Set(0),
+            error: ActiveValue::Set(None),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+            started_at: ActiveValue::Set(None),
+            finished_at: ActiveValue::Set(None),
+        })
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        self.queue(id).await?;
+
+        self.model(id).await
+    }
+
+    /// Stop the rebalance, the chunks that were moved stay where they are
+    pub(crate) async fn cancel(&self, id: Uuid) -> AppResult<rebalances::Model> {
+        let rebalance = self.model(id).await?;
+
+        if !rebalance.status.is_active() {
+            return Err(Error::BadRequest("rebalance_not_active".to_string()));
+        }
+
+        rebalances::Entity::update(rebalances::ActiveModel {
+            id: ActiveValue::Set(id),
+            status: ActiveValue::Set(rebalances::Status::Cancelled),
+            finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        self.model(id).await
+    }
+
+    /// Queue the failed or cancelled rebalance again, it continues from where the chunks are
+    pub(crate) async fn resume(&self, id: Uuid) -> AppResult<rebalances::Model> {
+        let rebalance = self.model(id).await?;
+
+        if !matches!(
+            rebalance.status,
+            rebalances::Status::Failed | rebalances::Status::Cancelled
+        ) {
+            return Err(Error::BadRequest("rebalance_not_stopped".to_string()));
+        }
+
+        self.none_active().await?;
+
+        rebalances::Entity::update(rebalances::ActiveModel {
+            id: ActiveValue::Set(id),
+            status: ActiveValue::Set(rebalances::Status::Pending),
+            error: ActiveValue::Set(None),
+            finished_at: ActiveValue::Set(None),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        self.queue(id).await?;
+
+        self.model(id).await
+    }
+
+    async fn queue(&self, id: Uuid) -> AppResult<()> {
+        jobs::repository::Repository::new(self.repository.connection())
+            .push(REBALANCE_STORAGE, &RebalanceJob { id })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn none_active(&self) -> AppResult<()> {
+        let active = rebalances::Entity::find()
+            .filter(
+                rebalances::Column::Status
+                    .is_in([rebalances::Status::Pending, rebalances::Status::Running]),
+            )
+            .one(self.repository.connection())
+            .await?;
+
+        match active {
+            Some(_) => Err(Error::BadRequest("rebalance_already_running".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    async fn model(&self, id: Uuid) -> AppResult<rebalances::Model> {
+        rebalances::Entity::find_by_id(id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("rebalance_not_found".to_string()))
+    }
+
+    fn dirs(&self) -> Vec<String> {
+        Fs::new(&self.repository.context().config).shard_dirs()
+    }
+}
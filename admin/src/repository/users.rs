@@ -1,6 +1,6 @@
 use chrono::Utc;
 use entity::{
-    paginated::Paginated, sessions, sort::Sortable, users, ActiveValue, ColumnTrait,
+    audit_logs, paginated::Paginated, sessions, sort::Sortable, users, ActiveValue, ColumnTrait,
     ConnectionTrait, EntityTrait, Expr, IntoCondition, JoinType, ModelTrait, PaginatorTrait,
     QueryFilter, QueryOrder, QuerySelect, RelationTrait, Select, Uuid,
 };
@@ -115,6 +115,7 @@ where
             id: ActiveValue::Set(user.id),
             role: ActiveValue::Set(update.role),
             quota: ActiveValue::Set(update.quota),
+            version_limit: ActiveValue::Set(update.version_limit),
             updated_at: ActiveValue::Set(Utc::now().timestamp()),
             ..Default::default()
         };
@@ -126,8 +127,9 @@ where
         self.get(user_id).await
     }
 
-    /// Delete the user forever and all of their linked entities
-    pub(crate) async fn delete(&self, user_id: Uuid) -> AppResult<()> {
+    /// Delete the user forever and all of their linked entities,
+    /// returns the ids of the files that were deleted with them.
+    pub(crate) async fn delete(&self, actor_id: Uuid, user_id: Uuid) -> AppResult<Vec<Uuid>> {
         let user = users::Entity::find_by_id(user_id)
             .one(self.repository.connection())
             .await?
@@ -135,14 +137,34 @@ where
 
         let files = self.repository.files().find_for(user_id).await?;
 
+        // Files under the legal hold keep their owner around until the hold is released.
+        let held = files
+            .iter()
+            .filter(|file| file.legal_hold)
+            .map(|file| file.id)
+            .collect::<Vec<_>>();
+
+        if !held.is_empty() {
+            storage::audit(
+                self.repository.connection(),
+                actor_id,
+                &held,
+                audit_logs::Action::DeleteBlocked,
+            )
+            .await?;
+
+            return Err(Error::Forbidden("file_under_legal_hold".to_string()));
+        }
+
         // We are deleting files specifically because they need
         // to run the purge on the fs as well, all other entities should
         // be automatically cascade deleted after the user is deleted.
+        let ids = files.iter().map(|file| file.id).collect();
         self.repository.files().delete_many(files).await?;
 
         user.delete(self.repository.connection()).await?;
 
-        Ok(())
+        Ok(ids)
     }
 
     /// Disable users two factor authentication
@@ -0,0 +1,45 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::{audit_logs::Action, Uuid};
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::repository::Repository;
+
+/// Download the encrypted chunk of the file with the escrowed key, the admin
+/// decrypts it with the file key recovered from the escrow. Every download
+/// is recorded in the audit log of the file.
+///
+/// Response: `application/octet-stream` encrypted chunk
+#[route("/api/admin/escrow/{file_id}/{chunk}", method = "GET")]
+pub(crate) async fn download(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let chunk = util::actix::path_var::<i64>(&req, "chunk")?;
+    let context = context.into_inner();
+
+    let escrowed = Repository::new(&context, &context.db)
+        .escrow()
+        .get(file_id)
+        .await?;
+
+    if escrowed.file.mime == "dir" {
+        return Err(Error::BadRequest("cannot_download_dir".to_string()));
+    }
+
+    let streamer = Fs::new(&context.config)
+        .stream(&escrowed.file, Some(chunk))
+        .await?;
+
+    storage::audit(&context.db, staff.claims.sub, &[file_id], Action::Recovered).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .streaming(streamer.stream()))
+}
@@ -0,0 +1,30 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// List the files of the user with their keys escrowed to the organization
+/// recovery key, used to recover the data of the user that left.
+///
+/// Response: list of [crate::data::escrow::escrowed_file::EscrowedFile]
+#[route("/api/admin/users/{id}/escrow", method = "GET")]
+pub(crate) async fn index(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    let files = Repository::new(&context, &context.db)
+        .escrow()
+        .find(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(files))
+}
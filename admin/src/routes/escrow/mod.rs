@@ -0,0 +1,5 @@
+pub mod download;
+pub mod index;
+
+pub use download::*;
+pub use index::*;
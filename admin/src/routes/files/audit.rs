@@ -0,0 +1,30 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Audit log of the file, the legal hold changes and all the refused
+/// attempts to delete the file while it was under the hold.
+///
+/// Response: list of [entity::audit_logs::Model]
+#[route("/api/admin/files/{file_id}/audit", method = "GET")]
+pub(crate) async fn audit(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let context = context.into_inner();
+
+    let logs = Repository::new(&context, &context.db)
+        .files()
+        .audit_log(file_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(logs))
+}
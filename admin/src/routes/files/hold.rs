@@ -0,0 +1,55 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Put the legal hold on the file or the folder, nothing under the hold
+/// can be deleted until the hold is released.
+///
+/// Response: [entity::files::Model]
+#[route("/api/admin/files/{file_id}/hold", method = "PUT")]
+pub(crate) async fn hold(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    set_legal_hold(req, staff, context, true).await
+}
+
+/// Release the legal hold from the file or the folder.
+///
+/// Response: [entity::files::Model]
+#[route("/api/admin/files/{file_id}/hold", method = "DELETE")]
+pub(crate) async fn release(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    set_legal_hold(req, staff, context, false).await
+}
+
+async fn set_legal_hold(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+    legal_hold: bool,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let context = context.into_inner();
+
+    let repository = Repository::new(&context, &context.db);
+    let file = repository
+        .files()
+        .set_legal_hold(staff.claims.sub, file_id, legal_hold)
+        .await?;
+
+    let owner_id = repository.files().owner(file.id).await?;
+    storage::invalidate(owner_id, &[file.id]).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
@@ -1,3 +1,9 @@
+pub mod audit;
+pub mod hold;
 pub mod index;
+pub mod verdict;
 
+pub use audit::*;
+pub use hold::*;
 pub use index::*;
+pub use verdict::*;
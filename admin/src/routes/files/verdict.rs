@@ -0,0 +1,80 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{
+    data::files::verdict::{self, Record},
+    repository::Repository,
+};
+
+/// Verdicts the scanners gave the file, the latest first
+///
+/// Response: list of [entity::file_verdicts::Model]
+#[route("/api/admin/files/{file_id}/verdicts", method = "GET")]
+pub(crate) async fn verdicts(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let verdicts = storage::verdicts(&context.db, file_id).await?;
+
+    Ok(HttpResponse::Ok().json(verdicts))
+}
+
+/// Record the verdict of the scanner on the file, the scanners integrate with
+/// the storage by calling this route with the admin credentials. Each scanner
+/// keeps a single verdict for the file, recording it again replaces it.
+/// While any of the verdicts is `infected` or `blocked` the file can't be downloaded.
+///
+/// Request: [crate::data::files::verdict::Record]
+///
+/// Response: [entity::file_verdicts::Model]
+#[route("/api/admin/files/{file_id}/verdicts/{scanner}", method = "PUT")]
+pub(crate) async fn record_verdict(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Json<Record>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let scanner = verdict::scanner(&util::actix::path_var::<String>(&req, "scanner")?)?;
+    let (verdict, details) = data.into_inner().into_values()?;
+    let context = context.into_inner();
+
+    let verdict = Repository::new(&context, &context.db)
+        .files()
+        .record_verdict(staff.claims.sub, file_id, scanner, verdict, details)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(verdict))
+}
+
+/// Remove the verdict of the scanner from the file
+///
+/// Response: 204 No Content
+#[route("/api/admin/files/{file_id}/verdicts/{scanner}", method = "DELETE")]
+pub(crate) async fn remove_verdict(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let scanner = verdict::scanner(&util::actix::path_var::<String>(&req, "scanner")?)?;
+    let context = context.into_inner();
+
+    Repository::new(&context, &context.db)
+        .files()
+        .remove_verdict(staff.claims.sub, file_id, scanner)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,30 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::{data::groups::create::Create, repository::Repository};
+
+/// Create a group, the members of the group share the group quota
+/// on top of their own quotas.
+///
+/// Request: [crate::data::groups::create::Create]
+///
+/// Response: [entity::groups::Model]
+#[route("/api/admin/groups", method = "POST")]
+pub(crate) async fn create(
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Json<Create>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+
+    let group = Repository::new(&context, &context.db)
+        .groups()
+        .create(data.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(group))
+}
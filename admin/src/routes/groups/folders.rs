@@ -0,0 +1,50 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Add the folder to the default folders of the group, the folder keys are encrypted
+/// on the clients, so the owner's client shares the folder with the members.
+#[route("/api/admin/groups/{id}/folders/{file_id}", method = "PUT")]
+pub(crate) async fn add_folder(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let context = context.into_inner();
+
+    Repository::new(&context, &context.db)
+        .groups()
+        .add_folder(id, file_id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Remove the folder from the default folders of the group
+#[route("/api/admin/groups/{id}/folders/{file_id}", method = "DELETE")]
+pub(crate) async fn remove_folder(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let file_id = util::actix::path_var::<Uuid>(&req, "file_id")?;
+    let context = context.into_inner();
+
+    Repository::new(&context, &context.db)
+        .groups()
+        .remove_folder(id, file_id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
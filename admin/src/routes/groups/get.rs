@@ -0,0 +1,29 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Get the group with its members and the space they are using together
+///
+/// Response: [crate::data::groups::group::Group]
+#[route("/api/admin/groups/{id}", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    let group = Repository::new(&context, &context.db)
+        .groups()
+        .get(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(group))
+}
@@ -0,0 +1,23 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// List all the groups on the platform
+///
+/// Response: list of [entity::groups::Model]
+#[route("/api/admin/groups", method = "GET")]
+pub(crate) async fn index(staff: Staff, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+
+    let groups = Repository::new(&context, &context.db)
+        .groups()
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(groups))
+}
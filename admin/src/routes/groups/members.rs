@@ -0,0 +1,52 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{data::groups::member::Member, repository::Repository};
+
+/// Add the user to the group or change the role of the existing member
+///
+/// Request: [crate::data::groups::member::Member]
+#[route("/api/admin/groups/{id}/members/{user_id}", method = "PUT")]
+pub(crate) async fn add_member(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Json<Member>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let user_id = util::actix::path_var::<Uuid>(&req, "user_id")?;
+    let context = context.into_inner();
+
+    Repository::new(&context, &context.db)
+        .groups()
+        .add_member(id, user_id, data.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Remove the user from the group
+#[route("/api/admin/groups/{id}/members/{user_id}", method = "DELETE")]
+pub(crate) async fn remove_member(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let user_id = util::actix::path_var::<Uuid>(&req, "user_id")?;
+    let context = context.into_inner();
+
+    Repository::new(&context, &context.db)
+        .groups()
+        .remove_member(id, user_id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,15 @@
+pub mod create;
+pub mod folders;
+pub mod get;
+pub mod index;
+pub mod members;
+pub mod remove;
+pub mod update;
+
+pub use create::*;
+pub use folders::*;
+pub use get::*;
+pub use index::*;
+pub use members::*;
+pub use remove::*;
+pub use update::*;
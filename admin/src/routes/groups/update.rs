@@ -0,0 +1,32 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{data::groups::create::Create, repository::Repository};
+
+/// Update the name and the quota of the group
+///
+/// Request: [crate::data::groups::create::Create]
+///
+/// Response: [entity::groups::Model]
+#[route("/api/admin/groups/{id}", method = "PUT")]
+pub(crate) async fn update(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Json<Create>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    let group = Repository::new(&context, &context.db)
+        .groups()
+        .update(id, data.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(group))
+}
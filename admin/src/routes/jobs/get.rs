@@ -0,0 +1,29 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Get the job with its payload and the error from its last attempt
+///
+/// Response: [entity::jobs::Model]
+#[route("/api/admin/jobs/{id}", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    let job = Repository::new(&context, &context.db)
+        .jobs()
+        .get(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
@@ -0,0 +1,29 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::{data::jobs::search::Search, repository::Repository};
+
+/// List the background jobs that ran out of attempts and are not retried anymore
+///
+/// Request: [crate::data::jobs::search::Search]
+///
+/// Response: [entity::paginated::Paginated<entity::jobs::Model>]
+#[route("/api/admin/jobs", method = "GET")]
+pub(crate) async fn index(
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Query<Search>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+
+    let response = Repository::new(&context, &context.db)
+        .jobs()
+        .failed(data.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
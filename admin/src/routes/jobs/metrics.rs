@@ -0,0 +1,23 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Number of the pending, running and failed jobs for each kind of the jobs
+///
+/// Response: list of [crate::data::jobs::metrics::Metrics]
+#[route("/api/admin/jobs/metrics", method = "GET")]
+pub(crate) async fn metrics(staff: Staff, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+
+    let metrics = Repository::new(&context, &context.db)
+        .jobs()
+        .metrics()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(metrics))
+}
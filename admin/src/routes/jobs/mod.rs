@@ -0,0 +1,11 @@
+pub mod discard;
+pub mod get;
+pub mod index;
+pub mod metrics;
+pub mod retry;
+
+pub use discard::*;
+pub use get::*;
+pub use index::*;
+pub use metrics::*;
+pub use retry::*;
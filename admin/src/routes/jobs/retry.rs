@@ -0,0 +1,29 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Put the failed job back to the queue, it gets all of its attempts again
+///
+/// Response: [entity::jobs::Model]
+#[route("/api/admin/jobs/{id}/retry", method = "POST")]
+pub(crate) async fn retry(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    let job = Repository::new(&context, &context.db)
+        .jobs()
+        .retry(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
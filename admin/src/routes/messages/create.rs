@@ -0,0 +1,30 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::{data::messages::create::Create, repository::Repository};
+
+/// Send the message to the given users or broadcast it to everyone,
+/// the users see it the next time they open the application.
+///
+/// Request: [crate::data::messages::create::Create]
+///
+/// Response: list of [entity::messages::Model]
+#[route("/api/admin/messages", method = "POST")]
+pub(crate) async fn create(
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Json<Create>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+
+    let messages = Repository::new(&context, &context.db)
+        .messages()
+        .create(staff.claims.sub, data.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(messages))
+}
@@ -0,0 +1,23 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// List all the messages sent to the users
+///
+/// Response: list of [entity::messages::Model]
+#[route("/api/admin/messages", method = "GET")]
+pub(crate) async fn index(staff: Staff, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+
+    let messages = Repository::new(&context, &context.db)
+        .messages()
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(messages))
+}
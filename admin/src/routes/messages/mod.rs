@@ -0,0 +1,7 @@
+pub mod create;
+pub mod index;
+pub mod remove;
+
+pub use create::*;
+pub use index::*;
+pub use remove::*;
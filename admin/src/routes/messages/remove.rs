@@ -0,0 +1,27 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Delete the message, the users who haven't read it yet won't see it.
+#[route("/api/admin/messages/{id}", method = "DELETE")]
+pub(crate) async fn remove(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    Repository::new(&context, &context.db)
+        .messages()
+        .delete(id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,14 +1,50 @@
+pub mod escrow;
 pub mod files;
+pub mod groups;
 pub mod invitations;
+pub mod jobs;
+pub mod messages;
+#[cfg(feature = "pprof")]
+pub mod pprof;
+pub mod rebalances;
 pub mod sessions;
 pub mod settings;
 pub mod users;
 
 pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
-    cfg.service(files::index)
+    cfg.service(escrow::index)
+        .service(escrow::download)
+        .service(files::index)
+        .service(files::hold)
+        .service(files::release)
+        .service(files::audit)
+        .service(files::verdicts)
+        .service(files::record_verdict)
+        .service(files::remove_verdict)
+        .service(groups::index)
+        .service(groups::create)
+        .service(groups::get)
+        .service(groups::update)
+        .service(groups::remove)
+        .service(groups::add_member)
+        .service(groups::remove_member)
+        .service(groups::add_folder)
+        .service(groups::remove_folder)
         .service(invitations::create)
         .service(invitations::expire)
         .service(invitations::index)
+        .service(jobs::index)
+        .service(jobs::metrics)
+        .service(jobs::get)
+        .service(jobs::retry)
+        .service(jobs::discard)
+        .service(messages::index)
+        .service(messages::create)
+        .service(messages::remove)
+        .service(rebalances::index)
+        .service(rebalances::create)
+        .service(rebalances::cancel)
+        .service(rebalances::resume)
         .service(sessions::index)
         .service(sessions::kill)
         .service(sessions::kill_for_user)
@@ -19,4 +55,9 @@ pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
         .service(settings::index)
         .service(settings::update)
         .service(users::remove_tfa);
+
+    #[cfg(feature = "pprof")]
+    cfg.service(pprof::cpu);
+    #[cfg(all(feature = "pprof", target_os = "linux"))]
+    cfg.service(pprof::heap);
 }
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use actix_web::{route, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use error::{AppResult, Error};
+
+/// Default duration of the CPU profile recording
+const DEFAULT_SECONDS: u64 = 10;
+
+/// Longest CPU profile recording we allow
+const MAX_SECONDS: u64 = 60;
+
+/// How many times per second the stack traces are sampled
+const FREQUENCY: i32 = 99;
+
+/// Record the CPU profile of the whole server for the given number of seconds
+/// and return it as a flamegraph.
+///
+/// Request:
+///  - Query: seconds: u64 - how long to record, defaults to 10, at most 60
+///
+/// Response: flamegraph
+///  - Content-Type: image/svg+xml
+#[route("/api/admin/pprof/cpu", method = "GET")]
+pub(crate) async fn cpu(req: HttpRequest, staff: Staff) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let seconds = util::actix::query_var::<u64>(&req, "seconds")
+        .unwrap_or(DEFAULT_SECONDS)
+        .clamp(1, MAX_SECONDS);
+
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| Error::BadRequest(format!("profiler_unavailable: {}", e)))?;
+
+    actix_web::rt::time::sleep(Duration::from_secs(seconds)).await;
+
+    let report = guard
+        .report()
+        .build()
+        .map_err(|e| Error::InternalError(e.to_string()))?;
+
+    let mut body = vec![];
+    report
+        .flamegraph(&mut body)
+        .map_err(|e| Error::InternalError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "image/svg+xml"))
+        .body(body))
+}
@@ -0,0 +1,36 @@
+use actix_web::{route, HttpResponse};
+use auth::data::staff::Staff;
+use error::{AppResult, Error};
+
+/// Dump the heap profile of the whole server, the allocations that are still alive
+/// and the stack traces they were made from, sampled by jemalloc since the start.
+///
+/// The profile is recorded only when the server runs with jemalloc as its allocator,
+/// the way the `hoodik` binary is built with the `pprof` feature on Linux.
+///
+/// Response: heap profile in the pprof format, read with `go tool pprof`
+///  - Content-Type: application/octet-stream
+#[route("/api/admin/pprof/heap", method = "GET")]
+pub(crate) async fn heap(staff: Staff) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let prof_ctl = jemalloc_pprof::PROF_CTL
+        .as_ref()
+        .ok_or_else(|| Error::BadRequest("profiler_unavailable: jemalloc".to_string()))?;
+
+    let mut prof_ctl = prof_ctl.lock().await;
+
+    if !prof_ctl.activated() {
+        return Err(Error::BadRequest(
+            "profiler_unavailable: heap profiling is not active".to_string(),
+        ));
+    }
+
+    let body = prof_ctl
+        .dump_pprof()
+        .map_err(|e| Error::InternalError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .body(body))
+}
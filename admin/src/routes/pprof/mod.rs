@@ -0,0 +1,12 @@
+//! # Profiling routes
+//!
+//! Only compiled in with the `pprof` feature, they let the admin record a CPU profile
+//! of the running server and dump its heap profile to find out where the time and the
+//! memory go without redeploying. The heap profile needs jemalloc, so it is Linux only.
+pub mod cpu;
+#[cfg(target_os = "linux")]
+pub mod heap;
+
+pub use cpu::*;
+#[cfg(target_os = "linux")]
+pub use heap::*;
@@ -0,0 +1,29 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Stop the rebalance after the chunk that is being moved, it can be resumed later.
+///
+/// Response: [entity::rebalances::Model]
+#[route("/api/admin/rebalances/{id}", method = "DELETE")]
+pub(crate) async fn cancel(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+
+    let rebalance = Repository::new(&context, &context.db)
+        .rebalances()
+        .cancel(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(rebalance))
+}
@@ -0,0 +1,34 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::TransactionTrait;
+use error::AppResult;
+
+use crate::{data::rebalances::create::Create, repository::Repository};
+
+/// Start moving the chunks between the shards until they match the layout,
+/// the chunks are moved by the background worker, one rebalance at the time.
+///
+/// Request: [crate::data::rebalances::create::Create]
+///
+/// Response: [entity::rebalances::Model]
+#[route("/api/admin/rebalances", method = "POST")]
+pub(crate) async fn create(
+    staff: Staff,
+    context: web::Data<Context>,
+    data: web::Json<Create>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+    let connection = context.db.begin().await?;
+
+    let rebalance = Repository::new(&context, &connection)
+        .rebalances()
+        .create(data.into_inner())
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(rebalance))
+}
@@ -0,0 +1,24 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use error::AppResult;
+
+use crate::{data::rebalances::shard::Shards, repository::Repository};
+
+/// Space the chunks take in each of the shards and the rebalances that were run on them
+///
+/// Response: [crate::data::rebalances::shard::Shards]
+#[route("/api/admin/rebalances", method = "GET")]
+pub(crate) async fn index(staff: Staff, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let context = context.into_inner();
+    let repository = Repository::new(&context, &context.db);
+
+    let shards = Shards {
+        shards: repository.rebalances().shards().await?,
+        rebalances: repository.rebalances().find().await?,
+    };
+
+    Ok(HttpResponse::Ok().json(shards))
+}
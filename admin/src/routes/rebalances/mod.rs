@@ -0,0 +1,9 @@
+pub mod cancel;
+pub mod create;
+pub mod index;
+pub mod resume;
+
+pub use cancel::*;
+pub use create::*;
+pub use index::*;
+pub use resume::*;
@@ -0,0 +1,33 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::staff::Staff;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Resume the failed or cancelled rebalance, the chunks that were
+/// already moved are left where they are.
+///
+/// Response: [entity::rebalances::Model]
+#[route("/api/admin/rebalances/{id}/resume", method = "POST")]
+pub(crate) async fn resume(
+    req: HttpRequest,
+    staff: Staff,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    staff.is_admin_or_err()?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let context = context.into_inner();
+    let connection = context.db.begin().await?;
+
+    let rebalance = Repository::new(&context, &connection)
+        .rebalances()
+        .resume(id)
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(rebalance))
+}
@@ -1,11 +1,14 @@
 use actix_web::{route, web, HttpResponse};
 use auth::data::staff::Staff;
 use context::Context;
-use error::AppResult;
+use error::{AppResult, Error};
 use settings::factory::Factory;
 
 /// Update the current settings for the platform.
 ///
+/// The organization recovery key of the key escrow has to be a valid public RSA key,
+/// and the instance profile has to be something the frontend can show.
+///
 /// Request: [settings::data::Data]
 ///
 /// Response: [settings::data::Data]
@@ -17,10 +20,15 @@ pub(crate) async fn update(
 ) -> AppResult<HttpResponse> {
     staff.is_admin_or_err()?;
 
-    context
-        .settings
-        .update(&context.config, data.into_inner())
-        .await?;
+    let data = data.into_inner();
+    data.instance.validate()?;
+
+    if let Some(public_key) = data.escrow.configured_public_key() {
+        storage::recovery_fingerprint(public_key)
+            .map_err(|_| Error::as_validation("escrow.public_key", "invalid_public_key"))?;
+    }
+
+    context.settings.update(&context.config, data).await?;
 
     Ok(HttpResponse::Ok().json(context.settings.inner().await.clone()))
 }
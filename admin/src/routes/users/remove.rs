@@ -20,10 +20,12 @@ pub(crate) async fn remove(
 
     let context = context.into_inner();
 
-    Repository::new(&context, &context.db)
+    let ids = Repository::new(&context, &context.db)
         .users()
-        .delete(id)
+        .delete(staff.claims.sub, id)
         .await?;
 
+    storage::invalidate(id, &ids).await;
+
     Ok(HttpResponse::NoContent().finish())
 }
@@ -0,0 +1,41 @@
+use chrono::Utc;
+use context::Context;
+use entity::{escrow_keys, ActiveValue, EntityTrait};
+
+#[async_std::test]
+async fn test_escrowed_files_of_the_user() {
+    let context = Context::mock_sqlite().await;
+    let repository = super::get_repo(&context).await;
+    let users = super::get_users(&context).await;
+
+    let (escrowed, _) =
+        entity::mock::create_file(&context.db, &users[0], "escrowed", "text/plain", None).await;
+    let (not_escrowed, _) =
+        entity::mock::create_file(&context.db, &users[0], "not escrowed", "text/plain", None).await;
+    let (other, _) =
+        entity::mock::create_file(&context.db, &users[1], "other", "text/plain", None).await;
+
+    for file in [&escrowed, &other] {
+        escrow_keys::Entity::insert(escrow_keys::ActiveModel {
+            file_id: ActiveValue::Set(file.id),
+            encrypted_key: ActiveValue::Set(format!("key-{}", file.id)),
+            fingerprint: ActiveValue::Set("fingerprint".to_string()),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+        })
+        .exec_without_returning(&context.db)
+        .await
+        .unwrap();
+    }
+
+    let files = repository.escrow().find(users[0].id).await.unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].file.id, escrowed.id);
+    assert_eq!(files[0].escrow_key, format!("key-{}", escrowed.id));
+    assert_eq!(files[0].fingerprint, "fingerprint");
+
+    assert_eq!(
+        repository.escrow().get(other.id).await.unwrap().file.id,
+        other.id
+    );
+    assert!(repository.escrow().get(not_escrowed.id).await.is_err());
+}
@@ -52,3 +52,43 @@ async fn test_file_stats() {
         }
     }
 }
+
+#[async_std::test]
+async fn test_legal_hold() {
+    let context = Context::mock_sqlite().await;
+    let repository = super::get_repo(&context).await;
+    let users = super::get_users(&context).await;
+    let admin = users.get(0).unwrap().clone();
+
+    let user = entity::mock::create_user(&context.db, "eleven@test.com", None).await;
+    let (file, _) =
+        entity::mock::create_file(&context.db, &user, "one", "application/json", None).await;
+
+    let held = repository
+        .files()
+        .set_legal_hold(admin.id, file.id, true)
+        .await
+        .unwrap();
+    assert!(held.legal_hold);
+
+    assert!(repository.users().delete(admin.id, user.id).await.is_err());
+
+    repository
+        .files()
+        .set_legal_hold(admin.id, file.id, false)
+        .await
+        .unwrap();
+
+    let logs = repository.files().audit_log(file.id).await.unwrap();
+    let actions = logs.iter().map(|log| log.action).collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        vec![
+            entity::audit_logs::Action::HoldReleased,
+            entity::audit_logs::Action::DeleteBlocked,
+            entity::audit_logs::Action::HoldSet,
+        ]
+    );
+
+    repository.users().delete(admin.id, user.id).await.unwrap();
+}
@@ -0,0 +1,99 @@
+use context::Context;
+use entity::group_members::Role;
+
+use crate::data::groups::{create::Create, member::Member};
+
+#[async_std::test]
+async fn test_groups() {
+    let context = Context::mock_sqlite().await;
+    let repository = super::get_repo(&context).await;
+    let users = super::get_users(&context).await;
+
+    let group = repository
+        .groups()
+        .create(Create {
+            name: Some(" team ".to_string()),
+            quota: Some(1024 * 1024 * 1024),
+        })
+        .await
+        .unwrap();
+    assert_eq!(group.name, "team");
+
+    // Name has to be unique
+    assert!(repository
+        .groups()
+        .create(Create {
+            name: Some("team".to_string()),
+            quota: None,
+        })
+        .await
+        .is_err());
+
+    let user = users.get(0).unwrap();
+    let (file, _) = entity::mock::create_file(&context.db, user, "one", "image/png", None).await;
+    let (dir, _) = entity::mock::create_file(&context.db, user, "dir", "dir", None).await;
+
+    repository
+        .groups()
+        .add_member(group.id, user.id, Member::default())
+        .await
+        .unwrap();
+    repository
+        .groups()
+        .add_member(group.id, user.id, Member::default())
+        .await
+        .unwrap();
+    repository
+        .groups()
+        .add_member(
+            group.id,
+            users.get(1).unwrap().id,
+            Member {
+                role: Some("viewer".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+    repository
+        .groups()
+        .add_folder(group.id, dir.id)
+        .await
+        .unwrap();
+    assert!(repository
+        .groups()
+        .add_folder(group.id, file.id)
+        .await
+        .is_err());
+
+    let details = repository.groups().get(group.id).await.unwrap();
+    assert_eq!(details.members.len(), 2);
+    assert_eq!(details.members[0].role, Role::Editor);
+    assert_eq!(details.members[1].role, Role::Viewer);
+    assert_eq!(details.folders, vec![dir.id]);
+    assert_eq!(details.used_space, 100);
+
+    repository
+        .groups()
+        .remove_member(group.id, user.id)
+        .await
+        .unwrap();
+    let details = repository.groups().get(group.id).await.unwrap();
+    assert_eq!(details.members.len(), 1);
+    assert_eq!(details.used_space, 0);
+
+    let updated = repository
+        .groups()
+        .update(
+            group.id,
+            Create {
+                name: Some("renamed".to_string()),
+                quota: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.quota, None);
+
+    repository.groups().delete(group.id).await.unwrap();
+    assert!(repository.groups().find().await.unwrap().is_empty());
+}
@@ -0,0 +1,67 @@
+use context::Context;
+
+use crate::data::jobs::search::Search;
+
+#[async_std::test]
+async fn test_failed_jobs_can_be_retried_or_discarded() {
+    let context = Context::mock_sqlite().await;
+    let repository = super::get_repo(&context).await;
+    let queue = ::jobs::repository::Repository::new(&context.db);
+
+    let pending = queue.push("email", &"pending").await.unwrap();
+    let webhook = queue.push("webhook", &"failing").await.unwrap();
+    let purge = queue.push("purge", &"failing").await.unwrap();
+
+    for id in [webhook, purge] {
+        let mut job = queue.get(id).await.unwrap();
+        while job.failed_at.is_none() {
+            queue.fail(&job, "connection refused").await.unwrap();
+            job = queue.get(id).await.unwrap();
+        }
+    }
+
+    let failed = repository.jobs().failed(Search::default()).await.unwrap();
+    assert_eq!(failed.total, 2);
+    assert!(failed.data.iter().all(|job| job.id != pending));
+
+    let search = Search {
+        kind: Some("webhook".to_string()),
+        ..Default::default()
+    };
+    let failed = repository.jobs().failed(search).await.unwrap();
+    assert_eq!(failed.total, 1);
+    assert_eq!(
+        failed.data[0].last_error.as_deref(),
+        Some("connection refused")
+    );
+
+    let metrics = repository.jobs().metrics().await.unwrap();
+    assert_eq!(metrics.len(), 3);
+    let email = metrics.iter().find(|m| m.kind == "email").unwrap();
+    assert_eq!(email.pending, 1);
+    assert_eq!(email.failed, 0);
+    assert!(email.oldest_run_at.is_some());
+    let webhooks = metrics.iter().find(|m| m.kind == "webhook").unwrap();
+    assert_eq!(webhooks.pending, 0);
+    assert_eq!(webhooks.failed, 1);
+
+    // Only the failed jobs can be retried or discarded
+    assert!(repository.jobs().retry(pending).await.is_err());
+    assert!(repository.jobs().discard(pending).await.is_err());
+
+    let job = repository.jobs().retry(webhook).await.unwrap();
+    assert_eq!(job.attempts, 0);
+    assert!(job.failed_at.is_none());
+
+    repository.jobs().discard(purge).await.unwrap();
+    assert!(repository.jobs().get(purge).await.is_err());
+
+    let failed = repository.jobs().failed(Search::default()).await.unwrap();
+    assert_eq!(failed.total, 0);
+
+    let metrics = repository.jobs().metrics().await.unwrap();
+    assert_eq!(metrics.len(), 2);
+    let webhooks = metrics.iter().find(|m| m.kind == "webhook").unwrap();
+    assert_eq!(webhooks.pending, 1);
+    assert_eq!(webhooks.failed, 0);
+}
@@ -0,0 +1,85 @@
+use chrono::Utc;
+use context::Context;
+use entity::Uuid;
+
+use crate::data::messages::create::Create;
+
+#[async_std::test]
+async fn test_messages() {
+    let context = Context::mock_sqlite().await;
+    let repository = super::get_repo(&context).await;
+    let users = super::get_users(&context).await;
+    let admin = users.get(0).unwrap();
+
+    let broadcast = repository
+        .messages()
+        .create(
+            admin.id,
+            Create {
+                title: Some(" Quota change ".to_string()),
+                body: Some("Your quota will be reduced next month.".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(broadcast.len(), 1);
+    assert_eq!(broadcast[0].title, "Quota change");
+    assert_eq!(broadcast[0].user_id, None);
+    assert_eq!(broadcast[0].sender_id, Some(admin.id));
+
+    // Each of the recipients gets their own message, duplicates are ignored
+    let recipients = vec![users[1].id, users[2].id, users[1].id];
+    let direct = repository
+        .messages()
+        .create(
+            admin.id,
+            Create {
+                title: Some("Hello".to_string()),
+                body: Some("Only for you".to_string()),
+                user_ids: Some(recipients),
+                expires_at: Some(Utc::now().timestamp() + 3600),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(direct.len(), 2);
+
+    // Unknown recipients, empty recipients and dates in the past are refused
+    for data in [
+        Create {
+            title: Some("Hello".to_string()),
+            body: Some("Nobody".to_string()),
+            user_ids: Some(vec![Uuid::new_v4()]),
+            ..Default::default()
+        },
+        Create {
+            title: Some("Hello".to_string()),
+            body: Some("Nobody".to_string()),
+            user_ids: Some(vec![]),
+            ..Default::default()
+        },
+        Create {
+            title: Some("Hello".to_string()),
+            body: Some("Too late".to_string()),
+            expires_at: Some(Utc::now().timestamp() - 1),
+            ..Default::default()
+        },
+        Create {
+            title: None,
+            body: Some("Untitled".to_string()),
+            ..Default::default()
+        },
+    ] {
+        assert!(repository.messages().create(admin.id, data).await.is_err());
+    }
+
+    let messages = repository.messages().find().await.unwrap();
+    assert_eq!(messages.len(), 3);
+
+    repository.messages().delete(broadcast[0].id).await.unwrap();
+    assert!(repository.messages().delete(broadcast[0].id).await.is_err());
+
+    let messages = repository.messages().find().await.unwrap();
+    assert_eq!(messages.len(), 2);
+}
@@ -2,8 +2,13 @@ use context::{Context, DatabaseConnection};
 
 use crate::repository::Repository;
 
+mod escrow;
 mod files;
+mod groups;
 mod invitations;
+mod jobs;
+mod messages;
+mod rebalances;
 mod sessions;
 mod users;
 
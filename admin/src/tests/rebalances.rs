@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use context::Context;
+use entity::rebalances::Status;
+use fs::{prelude::*, shards};
+use jobs::worker::Handler;
+use storage::jobs::{RebalanceJob, RebalanceStorage};
+
+use crate::data::rebalances::create::Create;
+
+async fn run(context: &Context, id: entity::Uuid) {
+    let payload = serde_json::to_string(&RebalanceJob { id }).unwrap();
+
+    RebalanceStorage.handle(context, &payload).await.unwrap();
+}
+
+#[async_std::test]
+async fn test_rebalance_moves_chunks_to_match_the_layout() {
+    let root = std::env::temp_dir().join(format!("hoodik-rebalance-{}", entity::Uuid::new_v4()));
+    let data_dir = root.join("data").to_str().unwrap().to_string();
+    let shard_dir = root.join("shard").to_str().unwrap().to_string();
+    std::fs::create_dir_all(&shard_dir).unwrap();
+
+    let mut context = Context::mock_with_data_dir(Some(data_dir.clone())).await;
+    let data_dir = context.config.app.data_dir.clone();
+
+    // Nothing to rebalance with only the data dir
+    {
+        let repository = super::get_repo(&context).await;
+        assert!(repository
+            .rebalances()
+            .create(Create::default())
+            .await
+            .is_err());
+    }
+
+    context.config.app.shard_dirs = vec![shard_dir.clone()];
+    let repository = super::get_repo(&context).await;
+
+    let file = Filename::new("file").with_timestamp(1);
+    for chunk in 0..4 {
+        std::fs::write(
+            format!("{}/{}", data_dir, file.clone().with_chunk(chunk)),
+            b"chunk",
+        )
+        .unwrap();
+    }
+
+    let unknown = Create {
+        layout: Some(BTreeMap::from([("/unknown".to_string(), 1)])),
+        bytes_per_second: None,
+    };
+    assert!(repository.rebalances().create(unknown).await.is_err());
+
+    let even = Create {
+        layout: Some(BTreeMap::from([
+            (data_dir.clone(), 1),
+            (shard_dir.clone(), 1),
+        ])),
+        bytes_per_second: None,
+    };
+    let rebalance = repository.rebalances().create(even.clone()).await.unwrap();
+    assert_eq!(rebalance.status, Status::Pending);
+
+    // Only one rebalance at the time
+    assert!(repository.rebalances().create(even).await.is_err());
+
+    run(&context, rebalance.id).await;
+
+    let rebalance = repository.rebalances().find().await.unwrap().remove(0);
+    assert_eq!(rebalance.status, Status::Finished);
+    assert_eq!(rebalance.moved_chunks, 2);
+    assert_eq!(rebalance.moved_bytes, 10);
+    assert_eq!(shards::chunks(&data_dir).await.unwrap().len(), 2);
+    assert_eq!(shards::chunks(&shard_dir).await.unwrap().len(), 2);
+    assert!(repository.rebalances().cancel(rebalance.id).await.is_err());
+    assert!(repository.rebalances().resume(rebalance.id).await.is_err());
+
+    // Chunks are read from whichever shard they are in
+    let fs = Fs::new(&context.config);
+    assert_eq!(
+        fs.get_uploaded_chunks(&file).await.unwrap(),
+        vec![0, 1, 2, 3]
+    );
+    assert_eq!(fs.size(&file, None).await.unwrap(), 20);
+
+    // Cancelled rebalance is resumed, emptying the data dir
+    let drain = Create {
+        layout: Some(BTreeMap::from([(shard_dir.clone(), 1)])),
+        bytes_per_second: Some(1024 * 1024 * 1024),
+    };
+    let rebalance = repository.rebalances().create(drain).await.unwrap();
+    let rebalance = repository.rebalances().cancel(rebalance.id).await.unwrap();
+    assert_eq!(rebalance.status, Status::Cancelled);
+
+    run(&context, rebalance.id).await;
+    assert_eq!(shards::chunks(&data_dir).await.unwrap().len(), 2);
+
+    repository.rebalances().resume(rebalance.id).await.unwrap();
+    run(&context, rebalance.id).await;
+
+    let shards = repository.rebalances().shards().await.unwrap();
+    assert_eq!(shards[0].chunks, 0);
+    assert_eq!(shards[1].chunks, 4);
+    assert_eq!(shards[1].used, 20);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -133,5 +133,5 @@ async fn test_delete_user() {
     let users = super::get_users(&context).await;
     let user = users.get(0).unwrap().clone();
 
-    repository.users().delete(user.id).await.unwrap();
+    repository.users().delete(user.id, user.id).await.unwrap();
 }
@@ -1,6 +1,7 @@
 use crate::contracts::{
-    account::Account, cookies::Cookies, ctx::Ctx, email::Email, register::Register,
-    repository::Repository, sessions::Sessions,
+    account::Account, alerts::Alerts, app_passwords::AppPasswords, cookies::Cookies, ctx::Ctx,
+    email::Email, messages::Messages, register::Register, repository::Repository,
+    sessions::Sessions,
 };
 use context::Context;
 
@@ -14,6 +15,9 @@ impl Register for Auth<'_> {}
 impl Repository for Auth<'_> {}
 impl Sessions for Auth<'_> {}
 impl Account for Auth<'_> {}
+impl Alerts for Auth<'_> {}
+impl AppPasswords for Auth<'_> {}
+impl Messages for Auth<'_> {}
 
 impl Ctx for Auth<'_> {
     fn ctx(&self) -> &Context {
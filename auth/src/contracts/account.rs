@@ -1,21 +1,23 @@
 use chrono::Utc;
 use entity::{
-    paginated::Paginated, sessions, sort::Sortable, users, ActiveValue, ColumnTrait, EntityTrait,
-    PaginatorTrait, QueryFilter, QuerySelect, Uuid,
+    paginated::Paginated, rewrap_jobs, sessions, sort::Sortable, users, ActiveModelTrait,
+    ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect, Statement, TransactionTrait, Uuid,
 };
 use error::{AppResult, Error};
 use validr::Validation;
 
 use crate::data::{
-    activity_query::ActivityQuery, change_password::ChangePassword, two_factor::Enable,
+    activity_query::ActivityQuery, change_password::ChangePassword, rotate_key::RotateKey,
+    two_factor::Enable,
 };
 
-use super::repository::Repository;
+use super::{alerts::Alerts, repository::Repository};
 
 #[async_trait::async_trait]
 pub(crate) trait Account
 where
-    Self: Repository,
+    Self: Repository + Alerts,
 {
     /// Verify the payload and change the users password
     async fn change_password(&self, data: ChangePassword) -> AppResult<users::Model> {
@@ -32,15 +34,83 @@ where
 
         verify_signature(&user, &new_password, signature.as_deref())?;
 
-        self.update_user(
-            user.id,
-            users::ActiveModel {
-                password: ActiveValue::Set(Some(util::password::hash(&new_password))),
-                encrypted_private_key: ActiveValue::Set(Some(encrypted_private_key)),
-                ..Default::default()
-            },
+        let user = self
+            .update_user(
+                user.id,
+                users::ActiveModel {
+                    password: ActiveValue::Set(Some(util::password::hash(&new_password))),
+                    encrypted_private_key: ActiveValue::Set(Some(encrypted_private_key)),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        self.security_alert(
+            &user,
+            "Password changed",
+            "The password of your account has been changed",
         )
-        .await
+        .await;
+
+        Ok(user)
+    }
+
+    /// Replace the key pair of the user. The keys of the files the user has are still
+    /// encrypted with the previous public key, so they are queued in the re-wrap job
+    /// for the users client to encrypt them again with the new one.
+    async fn rotate_key(&self, id: Uuid, data: RotateKey) -> AppResult<rewrap_jobs::Model> {
+        let (pubkey, fingerprint, encrypted_private_key, signature) = data.into_values()?;
+        let user = self.get_by_id(id).await?;
+
+        cryptfns::rsa::public::verify(&fingerprint, &signature, &user.pubkey)
+            .map_err(|_| Error::Unauthorized("invalid_signature".to_string()))?;
+
+        let connection = self.connection().begin().await?;
+        let now = Utc::now().timestamp();
+
+        users::ActiveModel {
+            id: ActiveValue::Set(user.id),
+            pubkey: ActiveValue::Set(pubkey),
+            fingerprint: ActiveValue::Set(fingerprint),
+            encrypted_private_key: ActiveValue::Set(Some(encrypted_private_key)),
+            updated_at: ActiveValue::Set(now),
+            ..Default::default()
+        }
+        .update(&connection)
+        .await?;
+
+        let job_id = Uuid::new_v4();
+        rewrap_jobs::Entity::insert(rewrap_jobs::ActiveModel {
+            id: ActiveValue::Set(job_id),
+            user_id: ActiveValue::Set(user.id),
+            reason: ActiveValue::Set(rewrap_jobs::Reason::KeyRotated),
+            file_id: ActiveValue::Set(None),
+            previous_encrypted_private_key: ActiveValue::Set(user.encrypted_private_key),
+            created_at: ActiveValue::Set(now),
+            completed_at: ActiveValue::Set(None),
+        })
+        .exec_without_returning(&connection)
+        .await?;
+
+        let sql = r#"
+            INSERT INTO rewrap_entries (job_id, user_file_id)
+            SELECT $1, id FROM user_files WHERE user_id = $2;
+        "#;
+
+        connection
+            .execute(Statement::from_sql_and_values(
+                connection.get_database_backend(),
+                sql,
+                [job_id.into(), user.id.into()],
+            ))
+            .await?;
+
+        connection.commit().await?;
+
+        rewrap_jobs::Entity::find_by_id(job_id)
+            .one(self.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("rewrap_job_not_found".to_string()))
     }
 
     /// Disable the two factor authentication for the user
@@ -60,6 +130,13 @@ where
         )
         .await?;
 
+        self.security_alert(
+            &user,
+            "Two factor authentication disabled",
+            "The two factor authentication has been disabled on your account",
+        )
+        .await;
+
         Ok(())
     }
 
@@ -0,0 +1,31 @@
+use entity::users;
+use notify::notification::{Kind, Notification};
+
+use super::ctx::Ctx;
+
+/// Security alerts pushed to the user when their account changes
+#[async_trait::async_trait]
+pub(crate) trait Alerts
+where
+    Self: Ctx,
+{
+    /// Push the security alert to the user, skipped without the notifier
+    async fn security_alert(&self, user: &users::Model, title: &str, message: &str) {
+        let notifier = match &self.ctx().notifier {
+            Some(n) => n,
+            None => {
+                log::debug!("No notifier configured, skipping security alert");
+
+                return;
+            }
+        };
+
+        let link = format!("{}/account", self.ctx().config.get_client_url());
+
+        notifier
+            .send(
+                Notification::new(user.id, &user.email, Kind::Security, title, message).link(&link),
+            )
+            .await;
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::Utc;
+use entity::{
+    app_passwords::{self, Scope},
+    files, sessions, user_files, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::repository::Repository;
+
+/// How many app passwords a single user can have
+const MAX_APP_PASSWORDS: u64 = 50;
+
+/// Manage the app passwords of the user
+#[async_trait::async_trait]
+pub(crate) trait AppPasswords
+where
+    Self: Repository,
+{
+    /// List the app passwords of the user, newest first
+    async fn app_passwords(&self, user_id: Uuid) -> AppResult<Vec<app_passwords::Model>> {
+        let app_passwords = app_passwords::Entity::find()
+            .filter(app_passwords::Column::UserId.eq(user_id))
+            .order_by_desc(app_passwords::Column::CreatedAt)
+            .all(self.connection())
+            .await?;
+
+        Ok(app_passwords)
+    }
+
+    /// Create the app password, returns it along with the password in plain text,
+    /// only the hash of the password is stored.
+    async fn create_app_password(
+        &self,
+        user_id: Uuid,
+        name: String,
+        scopes: Vec<Scope>,
+        folder_id: Option<Uuid>,
+    ) -> AppResult<(app_passwords::Model, String)> {
+        let count = app_passwords::Entity::find()
+            .filter(app_passwords::Column::UserId.eq(user_id))
+            .count(self.connection())
+            .await?;
+
+        if count >= MAX_APP_PASSWORDS {
+            return Err(Error::as_validation(
+                "name",
+                &format!("max_app_passwords:{}", MAX_APP_PASSWORDS),
+            ));
+        }
+
+        if let Some(folder_id) = folder_id {
+            self.owned_folder(user_id, folder_id).await?;
+        }
+
+        let id = Uuid::new_v4();
+        let password = format!("{}.{}", id.simple(), util::generate::generate_secret());
+
+        let scopes = scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        app_passwords::Entity::insert(app_passwords::ActiveModel {
+            id: ActiveValue::Set(id),
+            user_id: ActiveValue::Set(user_id),
+            name: ActiveValue::Set(name),
+            password: ActiveValue::Set(util::password::hash(&password)),
+            scopes: ActiveValue::Set(scopes),
+            folder_id: ActiveValue::Set(folder_id),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+            last_used_at: ActiveValue::Set(None),
+        })
+        .exec_without_returning(self.connection())
+        .await?;
+
+        let app_password = self.get_app_password(id, user_id).await?;
+
+        Ok((app_password, password))
+    }
+
+    /// Find the app password of the user
+    async fn get_app_password(&self, id: Uuid, user_id: Uuid) -> AppResult<app_passwords::Model> {
+        app_passwords::Entity::find_by_id(id)
+            .one(self.connection())
+            .await?
+            .filter(|app_password| app_password.user_id == user_id)
+            .ok_or_else(|| Error::NotFound("app_password_not_found".to_string()))
+    }
+
+    /// Remove the app password and end the sessions that were started with it
+    async fn revoke_app_password(&self, id: Uuid, user_id: Uuid) -> AppResult<()> {
+        let app_password = self.get_app_password(id, user_id).await?;
+
+        sessions::Entity::update_many()
+            .filter(sessions::Column::AppPasswordId.eq(app_password.id))
+            .filter(sessions::Column::Refresh.is_not_null())
+            .set(sessions::ActiveModel {
+                refresh: ActiveValue::Set(None),
+                updated_at: ActiveValue::Set(Utc::now().timestamp()),
+                expires_at: ActiveValue::Set(Utc::now().timestamp()),
+                ..Default::default()
+            })
+            .exec(self.connection())
+            .await?;
+
+        app_passwords::Entity::delete_by_id(app_password.id)
+            .exec(self.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remember when the app password was last used to log in
+    async fn touch_app_password(&self, id: Uuid) -> AppResult<()> {
+        app_passwords::Entity::update(app_passwords::ActiveModel {
+            id: ActiveValue::Set(id),
+            last_used_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .exec(self.connection())
+        .await?;
+
+        Ok(())
+    }
+
+    /// App passwords can only be limited to the folders the user owns
+    async fn owned_folder(&self, user_id: Uuid, folder_id: Uuid) -> AppResult<()> {
+        let folder = files::Entity::find_by_id(folder_id)
+            .join(
+                entity::JoinType::InnerJoin,
+                files::Relation::UserFiles.def(),
+            )
+            .filter(user_files::Column::UserId.eq(user_id))
+            .filter(user_files::Column::IsOwner.eq(true))
+            .one(self.connection())
+            .await?
+            .ok_or_else(|| Error::as_validation("folder_id", "folder_not_found"))?;
+
+        if folder.mime != "dir" {
+            return Err(Error::as_validation("folder_id", "not_a_folder"));
+        }
+
+        Ok(())
+    }
+}
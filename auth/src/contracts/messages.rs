@@ -0,0 +1,74 @@
+use chrono::Utc;
+use entity::{
+    message_reads, messages, ActiveValue, ColumnTrait, Condition, EntityTrait, Query, QueryFilter,
+    QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::repository::Repository;
+
+#[async_trait::async_trait]
+pub(crate) trait Messages
+where
+    Self: Repository,
+{
+    /// Load the messages the user hasn't read yet, newest first. Broadcasts
+    /// sent before the user registered are not shown to them.
+    async fn messages(&self, user_id: Uuid) -> AppResult<Vec<messages::Model>> {
+        let user = self.get_by_id(user_id).await?;
+        let now = Utc::now().timestamp();
+
+        let read = Query::select()
+            .column(message_reads::Column::MessageId)
+            .from(message_reads::Entity)
+            .and_where(message_reads::Column::UserId.eq(user_id))
+            .to_owned();
+
+        let messages = messages::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(messages::Column::UserId.eq(user_id))
+                    .add(
+                        Condition::all()
+                            .add(messages::Column::UserId.is_null())
+                            .add(messages::Column::CreatedAt.gte(user.created_at)),
+                    ),
+            )
+            .filter(
+                Condition::any()
+                    .add(messages::Column::ExpiresAt.is_null())
+                    .add(messages::Column::ExpiresAt.gt(now)),
+            )
+            .filter(messages::Column::Id.not_in_subquery(read))
+            .order_by_desc(messages::Column::CreatedAt)
+            .all(self.connection())
+            .await?;
+
+        Ok(messages)
+    }
+
+    /// Mark the message as read so it isn't shown to the user again
+    async fn read_message(&self, user_id: Uuid, id: Uuid) -> AppResult<()> {
+        let message = messages::Entity::find_by_id(id)
+            .one(self.connection())
+            .await?
+            .filter(|message| message.user_id.is_none() || message.user_id == Some(user_id))
+            .ok_or_else(|| Error::NotFound("message_not_found".to_string()))?;
+
+        let read = message_reads::Entity::find_by_id((message.id, user_id))
+            .one(self.connection())
+            .await?;
+
+        if read.is_none() {
+            message_reads::Entity::insert(message_reads::ActiveModel {
+                message_id: ActiveValue::Set(message.id),
+                user_id: ActiveValue::Set(user_id),
+                read_at: ActiveValue::Set(Utc::now().timestamp()),
+            })
+            .exec_without_returning(self.connection())
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
+pub(crate) mod app_passwords;
 pub(crate) mod account;
+pub(crate) mod alerts;
 pub(crate) mod cookies;
 pub(crate) mod ctx;
 pub(crate) mod email;
+pub(crate) mod messages;
 pub(crate) mod provider;
 pub(crate) mod register;
 pub(crate) mod repository;
@@ -2,8 +2,8 @@ use async_trait::async_trait;
 use chrono::Utc;
 use context::DatabaseConnection;
 use entity::{
-    invitations, sessions, users, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait,
-    PaginatorTrait, QueryFilter, Uuid,
+    app_passwords, invitations, sessions, users, ActiveModelTrait, ActiveValue, ColumnTrait,
+    EntityTrait, PaginatorTrait, QueryFilter, Uuid,
 };
 use error::{AppResult, Error};
 
@@ -88,7 +88,7 @@ where
         // always Some so we can unwrap it safely
         let (session, user) = (result.0, result.1.unwrap());
 
-        Ok(Authenticated { user, session })
+        self.with_app_password(user, session).await
     }
 
     /// Get user and session by refresh token, session must be valid
@@ -106,7 +106,7 @@ where
         // always Some so we can unwrap it safely
         let (session, user) = (result.0, result.1.unwrap());
 
-        Ok(Authenticated { user, session })
+        self.with_app_password(user, session).await
     }
 
     /// Get user and session by device id, session must be valid
@@ -125,7 +125,31 @@ where
         // always Some so we can unwrap it safely
         let (session, user) = (result.0, result.1.unwrap());
 
-        Ok(Authenticated { user, session })
+        self.with_app_password(user, session).await
+    }
+
+    /// Attach the app password the session was started with, once the
+    /// password is revoked the session can't be used anymore
+    async fn with_app_password(
+        &self,
+        user: users::Model,
+        session: sessions::Model,
+    ) -> AppResult<Authenticated> {
+        let app_password = match session.app_password_id {
+            Some(id) => Some(
+                app_passwords::Entity::find_by_id(id)
+                    .one(self.connection())
+                    .await?
+                    .ok_or_else(|| Error::Unauthorized("app_password_revoked".to_string()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Authenticated {
+            user,
+            session,
+            app_password,
+        })
     }
 
     /// Create a new user
@@ -14,12 +14,14 @@ pub(crate) trait Sessions
 where
     Self: Ctx + Repository,
 {
-    /// Generate a new session for a user
+    /// Generate a new session for a user, sessions started with
+    /// an app password are limited to the scopes of the password
     async fn generate(
         &self,
         user: &users::Model,
         user_agent: &str,
         ip: &str,
+        app_password_id: Option<Uuid>,
     ) -> AppResult<sessions::Model> {
         let expires_at = Utc::now()
             + Duration::seconds(self.ctx().config.auth.short_term_session_duration_seconds);
@@ -36,6 +38,9 @@ where
             created_at: ActiveValue::Set(Utc::now().timestamp()),
             updated_at: ActiveValue::Set(Utc::now().timestamp()),
             expires_at: ActiveValue::Set(expires_at.timestamp()),
+            app_password_id: ActiveValue::Set(app_password_id),
+            unlocked_at: ActiveValue::Set(None),
+            lock_after: ActiveValue::Set(None),
         };
 
         sessions::Entity::insert(active_model)
@@ -64,6 +69,9 @@ where
             created_at: ActiveValue::Set(session.created_at),
             updated_at: ActiveValue::Set(Utc::now().timestamp()),
             expires_at: ActiveValue::Set(expires_at.timestamp()),
+            app_password_id: ActiveValue::Set(session.app_password_id),
+            unlocked_at: ActiveValue::Set(session.unlocked_at),
+            lock_after: ActiveValue::Set(session.lock_after),
         };
 
         active_model.update(self.connection()).await?;
@@ -83,6 +91,9 @@ where
             created_at: ActiveValue::Set(session.created_at),
             updated_at: ActiveValue::Set(Utc::now().timestamp()),
             expires_at: ActiveValue::Set(Utc::now().timestamp()),
+            app_password_id: ActiveValue::Set(session.app_password_id),
+            unlocked_at: ActiveValue::Set(None),
+            lock_after: ActiveValue::Set(session.lock_after),
         };
 
         let session = active_model.update(self.connection()).await?;
@@ -123,4 +134,49 @@ where
 
         Ok(session)
     }
+
+    /// Remember the session has unlocked the private key, it stays unlocked
+    /// until it is idle for longer than `lock_after` seconds
+    async fn unlock(
+        &self,
+        session: &sessions::Model,
+        lock_after: i64,
+    ) -> AppResult<sessions::Model> {
+        self.set_unlocked(session, Some(Utc::now().timestamp()), Some(lock_after))
+            .await
+    }
+
+    /// Activity with the unlocked vault postpones the lock, once the vault
+    /// is locked the key has to be unlocked again
+    async fn touch_unlock(&self, session: &sessions::Model) -> AppResult<sessions::Model> {
+        let now = Utc::now().timestamp();
+
+        if !session.is_unlocked(now) {
+            return Err(Error::Forbidden("vault_locked".to_string()));
+        }
+
+        self.set_unlocked(session, Some(now), session.lock_after)
+            .await
+    }
+
+    /// Lock the vault of the session right away
+    async fn lock(&self, session: &sessions::Model) -> AppResult<sessions::Model> {
+        self.set_unlocked(session, None, session.lock_after).await
+    }
+
+    async fn set_unlocked(
+        &self,
+        session: &sessions::Model,
+        unlocked_at: Option<i64>,
+        lock_after: Option<i64>,
+    ) -> AppResult<sessions::Model> {
+        let active_model = sessions::ActiveModel {
+            id: ActiveValue::Set(session.id),
+            unlocked_at: ActiveValue::Set(unlocked_at),
+            lock_after: ActiveValue::Set(lock_after),
+            ..Default::default()
+        };
+
+        Ok(active_model.update(self.connection()).await?)
+    }
 }
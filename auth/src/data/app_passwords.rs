@@ -0,0 +1,189 @@
+//! # App passwords data
+//!
+//! App passwords are created by the user for the third party clients, so they don't
+//! have to be given the main credentials. Sessions started with the app password
+//! can only reach the routes its scopes allow.
+use std::collections::HashSet;
+
+use actix_web::http::Method;
+use entity::{
+    app_passwords::{self, Scope},
+    Uuid,
+};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum length of the name of the app
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// Create the app password
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateAppPassword {
+    /// Name of the app or the device the password is for
+    pub name: Option<String>,
+
+    /// What the app can do, see [entity::app_passwords::Scope]
+    pub scopes: Option<Vec<String>>,
+
+    /// Limit the app to creating the files in this folder only
+    pub folder_id: Option<Uuid>,
+}
+
+impl Validation for CreateAppPassword {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(name),
+            rule_length_max!(name, MAX_NAME_LENGTH),
+            rule_required!(scopes),
+            Rule::new("scopes", |obj: &Self, error| {
+                if let Some(scopes) = obj.scopes.as_ref() {
+                    if scopes.is_empty() {
+                        error.add("required");
+                    }
+
+                    if scopes.iter().any(|scope| scope.parse::<Scope>().is_err()) {
+                        error.add("invalid_scope");
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(name)]
+    }
+}
+
+impl CreateAppPassword {
+    /// Validated name, scopes without duplicates and the folder
+    pub fn into_tuple(self) -> error::AppResult<(String, Vec<Scope>, Option<Uuid>)> {
+        let data = self.validate()?;
+
+        let mut seen = HashSet::new();
+        let scopes = data
+            .scopes
+            .unwrap()
+            .iter()
+            .filter_map(|scope| scope.parse().ok())
+            .filter(|scope| seen.insert(*scope))
+            .collect();
+
+        Ok((data.name.unwrap(), scopes, data.folder_id))
+    }
+}
+
+/// Created app password, the password itself is only shown this once
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedAppPassword {
+    #[serde(flatten)]
+    pub app_password: app_passwords::Model,
+    pub password: String,
+}
+
+/// Scopes of the app password the session was started with
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AppClaims {
+    /// App password id
+    pub id: Uuid,
+    pub scopes: Vec<Scope>,
+    pub folder_id: Option<Uuid>,
+}
+
+impl From<&app_passwords::Model> for AppClaims {
+    fn from(app_password: &app_passwords::Model) -> Self {
+        Self {
+            id: app_password.id,
+            scopes: app_password.scopes(),
+            folder_id: app_password.folder_id,
+        }
+    }
+}
+
+impl AppClaims {
+    /// Can the session reach the route, every session can see and end itself
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+        if let ["api", "auth", "self" | "logout"] = segments.as_slice() {
+            return method == Method::POST;
+        }
+
+        self.scopes
+            .iter()
+            .any(|scope| scope_allows(*scope, method.as_str(), &segments))
+    }
+}
+
+/// Routes of the storage each of the scopes opens up, every route is listed on its own
+/// so the new routes stay closed to the app passwords until they are added here.
+/// Shares and policies of the files are left to the main credentials.
+fn scope_allows(scope: Scope, method: &str, segments: &[&str]) -> bool {
+    let segments = match segments {
+        ["api", "storage", segments @ ..] => segments,
+        ["api", "dav", ..] => return dav_allows(scope, method),
+        _ => return false,
+    };
+
+    match scope {
+        Scope::Read => read_allows(method, segments),
+        Scope::Upload => upload_allows(method, segments),
+        Scope::Write => write_allows(method, segments),
+    }
+}
+
+/// List, search and download the files
+fn read_allows(method: &str, segments: &[&str]) -> bool {
+    match (method, segments) {
+        ("GET", [] | ["tree" | "trash"]) => true,
+        ("GET" | "HEAD", [id]) => is_id(id),
+        ("GET", [id, "metadata" | "content" | "manifest" | "thumbnail"]) => is_id(id),
+        ("GET", [id, "snapshot" | "zip" | "versions" | "verdicts"]) => is_id(id),
+        ("GET", [_, "changes" | "name-hash"]) => true,
+        ("POST", ["search" | "manifest" | "name-hashes" | "stats"]) => true,
+        ("POST", [id, "snapshot", "diff"]) => is_id(id),
+        _ => false,
+    }
+}
+
+/// Create the files and upload their chunks
+fn upload_allows(method: &str, segments: &[&str]) -> bool {
+    match (method, segments) {
+        ("POST", [] | ["bulk" | "simple-upload" | "dedup" | "resume" | "tus"]) => true,
+        ("POST", ["dedup", "files"]) => true,
+        ("HEAD" | "PATCH", ["tus", id]) => is_id(id),
+        ("POST", [id] | [id, "resume" | "thumbnail"]) => is_id(id),
+        ("GET", [id, "socket"]) => is_id(id),
+        _ => false,
+    }
+}
+
+/// Rename, move, copy and delete the files, change their content and restore them
+fn write_allows(method: &str, segments: &[&str]) -> bool {
+    match (method, segments) {
+        ("POST", ["move-many" | "delete-many" | "virtual" | "batch"]) => true,
+        ("PUT" | "DELETE", [id]) => is_id(id),
+        ("PUT", [id, "rename" | "content"]) => is_id(id),
+        ("PATCH", [id, "attributes"]) => is_id(id),
+        ("POST", [id, "move" | "copy"]) => is_id(id),
+        ("DELETE", ["trash"]) => true,
+        ("DELETE", ["trash", id]) => is_id(id),
+        ("POST", ["trash", id, "restore"]) => is_id(id),
+        ("DELETE", [id, "versions", version]) => is_id(id) && is_id(version),
+        ("POST", [id, "versions", version, "restore"]) => is_id(id) && is_id(version),
+        _ => false,
+    }
+}
+
+/// WebDAV mount of the drive, the path is the plain names of the files
+fn dav_allows(scope: Scope, method: &str) -> bool {
+    matches!(
+        (scope, method),
+        (Scope::Read, "OPTIONS" | "PROPFIND" | "GET" | "HEAD")
+            | (Scope::Upload, "PUT" | "MKCOL")
+            | (Scope::Write, "MOVE" | "DELETE")
+    )
+}
+
+fn is_id(segment: &str) -> bool {
+    segment.parse::<Uuid>().is_ok()
+}
@@ -3,7 +3,7 @@
 
 use actix_web::{web, FromRequest};
 use context::Context;
-use entity::{sessions, users};
+use entity::{app_passwords, sessions, users};
 use error::Error;
 use futures_util::Future;
 use serde::{Deserialize, Serialize};
@@ -17,9 +17,20 @@ use super::claims::Claims;
 pub struct Authenticated {
     pub user: users::Model,
     pub session: sessions::Model,
+    /// App password the session was started with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_password: Option<app_passwords::Model>,
 }
 
 impl Authenticated {
+    pub fn new(user: users::Model, session: sessions::Model) -> Self {
+        Self {
+            user,
+            session,
+            app_password: None,
+        }
+    }
+
     pub fn is_expired(&self) -> bool {
         self.session.expires_at < chrono::Utc::now().timestamp()
     }
@@ -56,6 +67,10 @@ impl FromRequest for Authenticated {
             });
         }
 
+        if let Err(e) = claims.check_scope(req) {
+            return Box::pin(async { Err(e) });
+        }
+
         Box::pin(async move {
             let authenticated = match Auth::new(&context).get_by_device_id(claims.device).await {
                 Ok(a) => a,
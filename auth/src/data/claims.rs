@@ -4,12 +4,12 @@
 use actix_web::{web, FromRequest, HttpRequest};
 use context::Context;
 use entity::Uuid;
-use error::Error;
+use error::{AppResult, Error};
 use futures_util::Future;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 
-use super::{authenticated::Authenticated, extractor::Extractor};
+use super::{app_passwords::AppClaims, authenticated::Authenticated, extractor::Extractor};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Claims {
@@ -27,6 +27,9 @@ pub struct Claims {
     pub role: Option<String>,
     /// User quota for the storage
     pub quota: Option<i64>,
+    /// Scopes of the app password the session was started with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<AppClaims>,
 }
 
 impl From<&Authenticated> for Claims {
@@ -39,6 +42,7 @@ impl From<&Authenticated> for Claims {
             device: authenticated.session.device_id,
             role: authenticated.user.role.clone(),
             quota: authenticated.user.quota,
+            app: authenticated.app_password.as_ref().map(AppClaims::from),
         }
     }
 }
@@ -58,6 +62,26 @@ impl Claims {
         !self.is_expired()
     }
 
+    /// Sessions started with the app password can only reach the routes its scopes allow
+    pub fn check_scope(&self, req: &HttpRequest) -> AppResult<()> {
+        match &self.app {
+            Some(app) if !app.allows(req.method(), req.path()) => {
+                Err(Error::Forbidden("app_password_scope".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// App password limited to a folder can only create the files in that folder
+    pub fn check_folder(&self, folder_id: Option<Uuid>) -> AppResult<()> {
+        match self.app.as_ref().and_then(|app| app.folder_id) {
+            Some(allowed) if Some(allowed) != folder_id => {
+                Err(Error::Forbidden("app_password_folder".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn get_quota(&self, context: &Context) -> Option<u64> {
         match self.quota {
             Some(v) => Some(v as u64),
@@ -108,6 +132,10 @@ impl FromRequest for Claims {
             });
         }
 
+        if let Err(e) = claims.check_scope(req) {
+            return Box::pin(async { Err(e) });
+        }
+
         Box::pin(async move { Ok(claims) })
     }
 
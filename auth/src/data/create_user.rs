@@ -119,6 +119,8 @@ impl CreateUser {
             email_verified_at: ActiveValue::Set(None),
             created_at: ActiveValue::Set(Utc::now().timestamp()),
             updated_at: ActiveValue::Set(Utc::now().timestamp()),
+            crypto_version: ActiveValue::Set(cryptfns::scheme::CURRENT),
+            version_limit: ActiveValue::NotSet,
         })
     }
 }
@@ -1,12 +1,15 @@
 pub mod activity_query;
+pub mod app_passwords;
 pub mod authenticated;
 pub mod change_password;
 pub mod claims;
 pub mod create_user;
 pub mod credentials;
 pub mod resend_activation;
+pub mod rotate_key;
 pub mod signature;
 pub mod staff;
 pub mod two_factor;
+pub mod unlock;
 
 pub(crate) mod extractor;
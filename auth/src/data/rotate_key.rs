@@ -0,0 +1,55 @@
+//! # Rotate key data
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Replace the key pair of the user with a new one
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RotateKey {
+    /// New public RSA key of the user
+    pub pubkey: Option<String>,
+
+    /// Fingerprint of the new public key
+    pub fingerprint: Option<String>,
+
+    /// New private key encrypted with the users passphrase
+    pub encrypted_private_key: Option<String>,
+
+    /// Fingerprint of the new public key signed with the current private key,
+    /// it is the proof the user has access to the key that is being replaced.
+    pub signature: Option<String>,
+}
+
+impl Validation for RotateKey {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(pubkey),
+            rule_required!(fingerprint),
+            rule_required!(encrypted_private_key),
+            rule_required!(signature),
+            Rule::new("fingerprint", |obj: &Self, error| {
+                if let (Some(pubkey), Some(fingerprint)) = (&obj.pubkey, &obj.fingerprint) {
+                    match cryptfns::rsa::public::from_str(pubkey).map(cryptfns::rsa::fingerprint) {
+                        Ok(Ok(fp)) if &fp == fingerprint => {}
+                        Ok(Ok(_)) => error.add("invalid_pubkey_fingerprint"),
+                        _ => error.add("invalid_pubkey_not_pkcs8_pem"),
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl RotateKey {
+    /// Get the pubkey, fingerprint, encrypted private key and the signature
+    pub fn into_values(self) -> AppResult<(String, String, String, String)> {
+        let data = self.validate()?;
+
+        Ok((
+            data.pubkey.unwrap(),
+            data.fingerprint.unwrap(),
+            data.encrypted_private_key.unwrap(),
+            data.signature.unwrap(),
+        ))
+    }
+}
@@ -0,0 +1,70 @@
+//! # Vault unlock data
+//!
+//! Clients decrypt the private key of the user in the browser, the server never sees it.
+//! They only tell the server the session has unlocked the key, so the server can ask for
+//! the unlock again after the session was idle for too long, in every tab of the session.
+use ::error::AppResult;
+use entity::sessions;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Idle seconds after which the vault is locked when the client doesn't choose
+pub const DEFAULT_LOCK_AFTER: i64 = 15 * 60;
+
+/// Shortest idle period the client can choose
+pub const MIN_LOCK_AFTER: i64 = 60;
+
+/// Longest idle period the client can choose
+pub const MAX_LOCK_AFTER: i64 = 7 * 24 * 60 * 60;
+
+/// Register the private key was unlocked in the session
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Unlock {
+    /// Seconds without the activity after which the vault is locked,
+    /// [DEFAULT_LOCK_AFTER] if omitted
+    pub lock_after: Option<i64>,
+}
+
+impl Validation for Unlock {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("lock_after", |obj: &Self, error| {
+            if let Some(lock_after) = obj.lock_after {
+                if !(MIN_LOCK_AFTER..=MAX_LOCK_AFTER).contains(&lock_after) {
+                    error.add(&format!("range:{}-{}", MIN_LOCK_AFTER, MAX_LOCK_AFTER));
+                }
+            }
+        })]
+    }
+}
+
+impl Unlock {
+    pub fn into_value(self) -> AppResult<i64> {
+        let data = self.validate()?;
+
+        Ok(data.lock_after.unwrap_or(DEFAULT_LOCK_AFTER))
+    }
+}
+
+/// Is the vault of the session unlocked and for how long
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnlockState {
+    pub unlocked: bool,
+    /// Last activity with the unlocked vault
+    pub unlocked_at: Option<i64>,
+    pub lock_after: Option<i64>,
+    /// When the vault gets locked if there is no activity until then
+    pub locks_at: Option<i64>,
+}
+
+impl From<&sessions::Model> for UnlockState {
+    fn from(session: &sessions::Model) -> Self {
+        let unlocked = session.is_unlocked(chrono::Utc::now().timestamp());
+
+        Self {
+            unlocked,
+            unlocked_at: session.unlocked_at.filter(|_| unlocked),
+            lock_after: session.lock_after,
+            locks_at: session.locks_at().filter(|_| unlocked),
+        }
+    }
+}
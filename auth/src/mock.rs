@@ -1,6 +1,24 @@
-use crate::providers::signature::SignatureProvider;
+use actix_web::cookie::Cookie;
+use context::Context;
+
+use crate::{data::authenticated::Authenticated, providers::signature::SignatureProvider};
 
 /// Shortcut to access the non public API for integration testing
 pub fn generate_fingerprint_nonce(fingerprint: &str) -> String {
     SignatureProvider::generate_fingerprint_nonce(fingerprint)
 }
+
+/// Session cookie for the authenticated user, so the tests can call the routes
+/// without going through the login
+pub fn session_cookie(context: &Context, authenticated: &Authenticated) -> Cookie<'static> {
+    let jwt = crate::jwt::generate(
+        authenticated,
+        module_path!(),
+        &context.config.auth.jwt_secret,
+    )
+    .unwrap();
+
+    Cookie::build(context.config.auth.session_cookie.clone(), jwt)
+        .path("/")
+        .finish()
+}
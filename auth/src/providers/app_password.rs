@@ -0,0 +1,90 @@
+use crate::{
+    auth::Auth,
+    contracts::{
+        app_passwords::AppPasswords, ctx::Ctx, provider::AuthProvider, repository::Repository,
+        sessions::Sessions,
+    },
+    data::{authenticated::Authenticated, credentials::Credentials},
+};
+use entity::{app_passwords, EntityTrait, Uuid};
+use error::{AppResult, Error};
+
+/// Authentication provider for logging in with the email and the app password,
+/// the session it starts is limited to the scopes of the app password.
+/// Two factor authentication is not asked for, the app password replaces it.
+pub(crate) struct AppPasswordProvider<'ctx> {
+    auth: &'ctx Auth<'ctx>,
+    data: Credentials,
+}
+
+impl<'ctx> AppPasswordProvider<'ctx> {
+    pub(crate) fn new(auth: &'ctx Auth, data: Credentials) -> Self {
+        Self { auth, data }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'ctx> AuthProvider for AppPasswordProvider<'ctx> {
+    async fn authenticate(&self, user_agent: &str, ip: &str) -> AppResult<Authenticated> {
+        let (email, password, _) = self.data.into_tuple()?;
+
+        let invalid = || Error::Unauthorized("invalid_credentials".to_string());
+
+        // Password starts with the id of the app password, so we only verify that one
+        let id = password
+            .split_once('.')
+            .and_then(|(id, _)| Uuid::parse_str(id).ok())
+            .ok_or_else(invalid)?;
+
+        let app_password = app_passwords::Entity::find_by_id(id)
+            .one(self.auth.connection())
+            .await?
+            .ok_or_else(invalid)?;
+
+        let mut user = match self.auth.get_by_email(&email).await {
+            Ok(v) => v,
+            Err(e) => {
+                if e.is_not_found() {
+                    return Err(invalid());
+                }
+
+                return Err(e);
+            }
+        };
+
+        if app_password.user_id != user.id
+            || !util::password::verify(&password, &app_password.password)
+        {
+            return Err(invalid());
+        }
+
+        if user.quota.is_none() {
+            user.quota = self
+                .auth
+                .context
+                .settings
+                .inner()
+                .await
+                .users
+                .quota_bytes()
+                .map(|v| v as i64);
+        }
+
+        if self.auth.enforce_email_activation().await && user.email_verified_at.is_none() {
+            return Err(Error::Unauthorized("inactive_account".to_string()));
+        }
+
+        let session = self
+            .auth
+            .generate(&user, user_agent, ip, Some(app_password.id))
+            .await?;
+
+        self.auth.touch_app_password(app_password.id).await?;
+
+        Ok(Authenticated {
+            user,
+            session,
+            app_password: Some(app_password),
+        })
+    }
+}
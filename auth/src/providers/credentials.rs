@@ -61,8 +61,8 @@ impl<'ctx> AuthProvider for CredentialsProvider<'ctx> {
             return Err(Error::Unauthorized("inactive_account".to_string()));
         }
 
-        let session = self.auth.generate(&user, user_agent, ip).await?;
+        let session = self.auth.generate(&user, user_agent, ip, None).await?;
 
-        Ok(Authenticated { user, session })
+        Ok(Authenticated::new(user, session))
     }
 }
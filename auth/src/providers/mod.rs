@@ -1,2 +1 @@
-pub(crate) mod credentials;
-pub(crate) mod signature;
+pub(crate) mod app_password;
@@ -58,8 +58,8 @@ impl<'ctx> AuthProvider for SignatureProvider<'ctx> {
 
         cryptfns::rsa::public::verify(&nonce, &signature, &user.pubkey)?;
 
-        let session = self.auth.generate(&user, user_agent, ip).await?;
+        let session = self.auth.generate(&user, user_agent, ip, None).await?;
 
-        Ok(Authenticated { user, session })
+        Ok(Authenticated::new(user, session))
     }
 }
@@ -0,0 +1,36 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{auth::Auth, contracts::messages::Messages, data::claims::Claims};
+
+/// Get the messages from the administrators the user hasn't read yet
+///
+/// Response: list of [entity::messages::Model]
+#[route("/api/auth/account/messages", method = "GET")]
+pub(crate) async fn messages(
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+
+    let messages = auth.messages(claims.sub).await?;
+
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+/// Mark the message as read, it won't be shown to the user again
+#[route("/api/auth/account/messages/{id}/read", method = "POST")]
+pub(crate) async fn read_message(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+
+    auth.read_message(claims.sub, id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -2,8 +2,12 @@ pub mod activity;
 pub mod change_password;
 pub mod kill;
 pub mod kill_all;
+pub mod messages;
+pub mod rotate_key;
 
 pub use activity::*;
 pub use change_password::*;
 pub use kill::*;
 pub use kill_all::*;
+pub use messages::*;
+pub use rotate_key::*;
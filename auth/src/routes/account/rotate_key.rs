@@ -0,0 +1,29 @@
+use actix_web::{route, web, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{
+    auth::Auth, contracts::account::Account, data::authenticated::Authenticated,
+    data::rotate_key::RotateKey,
+};
+
+/// Replace the key pair of the user, the keys of all the files the user has
+/// are queued for the client to encrypt them with the new public key.
+///
+/// Request: [crate::data::rotate_key::RotateKey]
+///
+/// Response: [entity::rewrap_jobs::Model]
+#[route("/api/auth/account/rotate-key", method = "POST")]
+pub(crate) async fn rotate_key(
+    context: web::Data<Context>,
+    authenticated: Authenticated,
+    data: web::Json<RotateKey>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+
+    let job = auth
+        .rotate_key(authenticated.user.id, data.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
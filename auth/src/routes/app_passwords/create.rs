@@ -0,0 +1,37 @@
+use actix_web::{route, web, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{
+    auth::Auth,
+    contracts::app_passwords::AppPasswords,
+    data::{
+        app_passwords::{CreateAppPassword, CreatedAppPassword},
+        claims::Claims,
+    },
+};
+
+/// Create the app password for a third party client, the password
+/// is in the response and it can't be seen again afterwards.
+///
+/// Request: [crate::data::app_passwords::CreateAppPassword]
+///
+/// Response: [crate::data::app_passwords::CreatedAppPassword]
+#[route("/api/auth/app-passwords", method = "POST")]
+pub(crate) async fn create_app_password(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateAppPassword>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+    let (name, scopes, folder_id) = data.into_inner().into_tuple()?;
+
+    let (app_password, password) = auth
+        .create_app_password(claims.sub, name, scopes, folder_id)
+        .await?;
+
+    Ok(HttpResponse::Created().json(CreatedAppPassword {
+        app_password,
+        password,
+    }))
+}
@@ -0,0 +1,20 @@
+use actix_web::{route, web, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{auth::Auth, contracts::app_passwords::AppPasswords, data::claims::Claims};
+
+/// List the app passwords of the user
+///
+/// Response: list of [entity::app_passwords::Model]
+#[route("/api/auth/app-passwords", method = "GET")]
+pub(crate) async fn app_passwords(
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+
+    let app_passwords = auth.app_passwords(claims.sub).await?;
+
+    Ok(HttpResponse::Ok().json(app_passwords))
+}
@@ -0,0 +1,39 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{
+    auth::Auth,
+    contracts::{cookies::Cookies, provider::AuthProvider},
+    data::credentials::Credentials,
+    providers::app_password::AppPasswordProvider,
+};
+
+/// Log in the third party client with the email and the app password,
+/// the session can only do what the scopes of the app password allow.
+///
+/// Request: [crate::data::credentials::Credentials]
+///
+/// Response: [crate::data::authenticated::Authenticated]
+#[route("/api/auth/app-login", method = "POST")]
+pub(crate) async fn app_login(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    data: web::Json<Credentials>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+    let (user_agent, ip) = util::actix::extract_ip_ua(&req);
+
+    let provider = AppPasswordProvider::new(&auth, data.into_inner());
+
+    let authenticated = provider.authenticate(&user_agent, &ip).await?;
+
+    let mut response = HttpResponse::Ok();
+
+    let (jwt, refresh) = auth.manage_cookies(&authenticated, module_path!())?;
+
+    response.cookie(jwt);
+    response.cookie(refresh);
+
+    Ok(response.json(authenticated))
+}
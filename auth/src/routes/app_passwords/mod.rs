@@ -0,0 +1,9 @@
+pub mod create;
+pub mod index;
+pub mod login;
+pub mod revoke;
+
+pub use create::*;
+pub use index::*;
+pub use login::*;
+pub use revoke::*;
@@ -0,0 +1,21 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{auth::Auth, contracts::app_passwords::AppPasswords, data::claims::Claims};
+
+/// Revoke the app password, the app is logged out right away
+#[route("/api/auth/app-passwords/{id}", method = "DELETE")]
+pub(crate) async fn revoke_app_password(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+
+    auth.revoke_app_password(id, claims.sub).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
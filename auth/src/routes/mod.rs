@@ -5,7 +5,9 @@
 use actix_web::web;
 
 pub mod account;
+pub mod app_passwords;
 pub mod two_factor;
+pub mod unlock;
 
 pub mod action;
 pub mod authenticated_self;
@@ -23,7 +25,14 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(account::change_password);
     cfg.service(account::kill_all);
     cfg.service(account::kill);
+    cfg.service(account::messages);
+    cfg.service(account::read_message);
+    cfg.service(account::rotate_key);
     cfg.service(action::action);
+    cfg.service(app_passwords::app_login);
+    cfg.service(app_passwords::app_passwords);
+    cfg.service(app_passwords::create_app_password);
+    cfg.service(app_passwords::revoke_app_password);
     cfg.service(authenticated_self::authenticated_self);
     cfg.service(credentials::credentials);
     cfg.service(logout::logout);
@@ -33,6 +42,10 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(two_factor::disable_two_factor);
     cfg.service(two_factor::enable_two_factor);
     cfg.service(two_factor::generate_two_factor);
+    cfg.service(unlock::lock_vault);
+    cfg.service(unlock::touch_vault);
+    cfg.service(unlock::unlock_vault);
+    cfg.service(unlock::vault_state);
 
     // Refresh is defined this way because we cannot use constant as path in `web::resource` macro
     cfg.service(web::resource(crate::REFRESH_PATH).route(web::post().to(refresh::refresh)));
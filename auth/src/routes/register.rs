@@ -52,8 +52,8 @@ pub(crate) async fn register(
         return Ok(HttpResponse::NoContent().finish());
     }
 
-    let session = auth.generate(&user, &user_agent, &ip).await?;
-    let authenticated = Authenticated { user, session };
+    let session = auth.generate(&user, &user_agent, &ip, None).await?;
+    let authenticated = Authenticated::new(user, session);
 
     let mut response = HttpResponse::Created();
 
@@ -0,0 +1,18 @@
+use actix_web::{route, web, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{auth::Auth, contracts::sessions::Sessions, data::authenticated::Authenticated};
+
+/// Lock the vault of the current session, every tab has to unlock the key again
+#[route("/api/auth/unlock", method = "DELETE")]
+pub(crate) async fn lock_vault(
+    context: web::Data<Context>,
+    authenticated: Authenticated,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+
+    auth.lock(&authenticated.session).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
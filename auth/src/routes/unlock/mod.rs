@@ -0,0 +1,9 @@
+pub mod lock_vault;
+pub mod touch_vault;
+pub mod unlock_vault;
+pub mod vault_state;
+
+pub use lock_vault::*;
+pub use touch_vault::*;
+pub use unlock_vault::*;
+pub use vault_state::*;
@@ -0,0 +1,25 @@
+use actix_web::{route, web, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{
+    auth::Auth,
+    contracts::sessions::Sessions,
+    data::{authenticated::Authenticated, unlock::UnlockState},
+};
+
+/// Keep the vault unlocked while the user is active, once the session was idle
+/// for too long it responds with `403 vault_locked` and the key has to be unlocked again
+///
+/// Response: [crate::data::unlock::UnlockState]
+#[route("/api/auth/unlock", method = "PUT")]
+pub(crate) async fn touch_vault(
+    context: web::Data<Context>,
+    authenticated: Authenticated,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+
+    let session = auth.touch_unlock(&authenticated.session).await?;
+
+    Ok(HttpResponse::Ok().json(UnlockState::from(&session)))
+}
@@ -0,0 +1,31 @@
+use actix_web::{route, web, HttpResponse};
+use context::Context;
+use error::AppResult;
+
+use crate::{
+    auth::Auth,
+    contracts::sessions::Sessions,
+    data::{
+        authenticated::Authenticated,
+        unlock::{Unlock, UnlockState},
+    },
+};
+
+/// Register the private key was unlocked in the current session, the key itself is not sent
+///
+/// Request: [crate::data::unlock::Unlock]
+///
+/// Response: [crate::data::unlock::UnlockState]
+#[route("/api/auth/unlock", method = "POST")]
+pub(crate) async fn unlock_vault(
+    context: web::Data<Context>,
+    authenticated: Authenticated,
+    data: web::Json<Unlock>,
+) -> AppResult<HttpResponse> {
+    let auth = Auth::new(&context);
+    let lock_after = data.into_inner().into_value()?;
+
+    let session = auth.unlock(&authenticated.session, lock_after).await?;
+
+    Ok(HttpResponse::Ok().json(UnlockState::from(&session)))
+}
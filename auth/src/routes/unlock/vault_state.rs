@@ -0,0 +1,13 @@
+use actix_web::{route, HttpResponse};
+use error::AppResult;
+
+use crate::data::{authenticated::Authenticated, unlock::UnlockState};
+
+/// Is the vault of the current session unlocked, clients check it when they
+/// open another tab, so they don't ask for the passphrase again
+///
+/// Response: [crate::data::unlock::UnlockState]
+#[route("/api/auth/unlock", method = "GET")]
+pub(crate) async fn vault_state(authenticated: Authenticated) -> AppResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(UnlockState::from(&authenticated.session)))
+}
@@ -1,17 +1,33 @@
 use std::str::FromStr;
 
-use actix_web::{http::header, HttpResponse};
+use actix_web::{
+    http::{header, Method},
+    HttpResponse,
+};
 use chrono::{Duration, Utc};
-use context::{Context, SenderContract};
+use context::{Context, NotifierContract, SenderContract};
+use entity::{
+    app_passwords::Scope, messages, rewrap_entries, rewrap_jobs, ColumnTrait, EntityTrait,
+    QueryFilter, Uuid,
+};
 use log::debug;
 
 use crate::{
     auth::Auth,
     contracts::{
-        cookies::Cookies, provider::AuthProvider, register::Register, repository::Repository,
+        account::Account, app_passwords::AppPasswords, cookies::Cookies, messages::Messages,
+        provider::AuthProvider, register::Register, repository::Repository, sessions::Sessions,
+    },
+    data::{
+        app_passwords::AppClaims,
+        change_password::ChangePassword,
+        claims::Claims,
+        create_user::CreateUser,
+        credentials::Credentials,
+        rotate_key::RotateKey,
+        unlock::{Unlock, UnlockState, DEFAULT_LOCK_AFTER, MAX_LOCK_AFTER},
     },
-    data::{create_user::CreateUser, credentials::Credentials},
-    providers::credentials::CredentialsProvider,
+    providers::{app_password::AppPasswordProvider, credentials::CredentialsProvider},
 };
 
 fn create_lib<'ctx>(context: &'ctx Context) -> Auth<'ctx> {
@@ -380,3 +396,401 @@ async fn test_set_cookie_for_both() {
     assert_eq!(res_jwt.to_str().unwrap(), jwt.to_string());
     assert_eq!(res_refresh.to_str().unwrap(), refresh.to_string());
 }
+
+#[async_std::test]
+async fn messages_are_shown_until_read() {
+    let context = Context::mock_sqlite().await;
+    let auth = create_lib(&context);
+    let user = entity::mock::create_user(&context.db, "reader@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+    let now = Utc::now().timestamp();
+
+    let message =
+        |user_id: Option<Uuid>, created_at: i64, expires_at: Option<i64>| messages::Model {
+            id: Uuid::new_v4(),
+            sender_id: None,
+            user_id,
+            title: "Title".to_string(),
+            body: "Body".to_string(),
+            created_at,
+            expires_at,
+        };
+
+    let broadcast = message(None, now, None);
+    let direct = message(Some(user.id), now + 1, Some(now + 3600));
+
+    for model in [
+        broadcast.clone(),
+        direct.clone(),
+        // Broadcast from before the user registered
+        message(None, user.created_at - 3600, None),
+        // Expired message
+        message(Some(user.id), now, Some(now - 1)),
+        // Message for someone else
+        message(Some(other.id), now, None),
+    ] {
+        messages::Entity::insert(messages::ActiveModel::from(model))
+            .exec_without_returning(&context.db)
+            .await
+            .unwrap();
+    }
+
+    let unread = auth.messages(user.id).await.unwrap();
+    assert_eq!(
+        unread.iter().map(|m| m.id).collect::<Vec<_>>(),
+        vec![direct.id, broadcast.id]
+    );
+
+    auth.read_message(user.id, broadcast.id).await.unwrap();
+    auth.read_message(user.id, broadcast.id).await.unwrap();
+
+    let unread = auth.messages(user.id).await.unwrap();
+    assert_eq!(
+        unread.iter().map(|m| m.id).collect::<Vec<_>>(),
+        vec![direct.id]
+    );
+
+    // Reading the broadcast is tracked for each user separately
+    let unread = auth.messages(other.id).await.unwrap();
+    assert_eq!(unread.len(), 2);
+    assert!(auth.read_message(other.id, direct.id).await.is_err());
+}
+
+#[async_std::test]
+async fn rotating_key_queues_file_keys_for_rewrap() {
+    let context = Context::mock_sqlite().await;
+    let auth = create_lib(&context);
+
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let pubkey = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let pubkey = cryptfns::rsa::public::to_string(&pubkey).unwrap();
+    let private_key = cryptfns::rsa::private::to_string(&private_key).unwrap();
+
+    let user = entity::mock::create_user(&context.db, "rotate@test.com", Some(pubkey)).await;
+    let (_, user_file) =
+        entity::mock::create_file(&context.db, &user, "file", "text/plain", None).await;
+
+    let (new_pubkey, new_fingerprint) = get_pubkey_and_fingerprint();
+    let fingerprint = new_fingerprint.clone().unwrap();
+
+    let data = |signature: String| RotateKey {
+        pubkey: new_pubkey.clone(),
+        fingerprint: new_fingerprint.clone(),
+        encrypted_private_key: Some("new-encrypted-private-key".to_string()),
+        signature: Some(signature),
+    };
+
+    // Signature has to be made with the current private key
+    let other_private_key = cryptfns::rsa::private::generate().unwrap();
+    let other_private_key = cryptfns::rsa::private::to_string(&other_private_key).unwrap();
+    let forged = cryptfns::rsa::private::sign(&fingerprint, &other_private_key).unwrap();
+    assert!(auth.rotate_key(user.id, data(forged)).await.is_err());
+
+    let signature = cryptfns::rsa::private::sign(&fingerprint, &private_key).unwrap();
+    let job = auth.rotate_key(user.id, data(signature)).await.unwrap();
+
+    assert_eq!(job.user_id, user.id);
+    assert_eq!(job.reason, rewrap_jobs::Reason::KeyRotated);
+    assert!(job.completed_at.is_none());
+
+    let user = auth.get_by_id(user.id).await.unwrap();
+    assert_eq!(user.fingerprint, fingerprint);
+    assert_eq!(
+        user.encrypted_private_key.as_deref(),
+        Some("new-encrypted-private-key")
+    );
+
+    let entries = rewrap_entries::Entity::find()
+        .filter(rewrap_entries::Column::JobId.eq(job.id))
+        .all(&context.db)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].user_file_id, user_file.id);
+}
+
+#[async_std::test]
+async fn changing_password_pushes_security_alert() {
+    let context = Context::mock_sqlite().await;
+    let context = Context::add_mock_notifier(context);
+    let auth = create_lib(&context);
+    let (pubkey, fingerprint) = get_pubkey_and_fingerprint();
+
+    let create_user = CreateUser {
+        email: Some("john@doe.com".to_string()),
+        password: Some("very-strong-password".to_string()),
+        secret: None,
+        pubkey,
+        fingerprint,
+        encrypted_private_key: Some("encrypted-gibberish".to_string()),
+        token: None,
+        invitation_id: None,
+    };
+    auth.register(create_user).await.unwrap();
+
+    let notifier = context.notifier.as_ref().unwrap();
+    assert!(!notifier.has("Password changed"));
+
+    auth.change_password(ChangePassword {
+        email: Some("john@doe.com".to_string()),
+        token: None,
+        password: Some("Tu9vq-Jk2apYr7-zqLd3w".to_string()),
+        signature: None,
+        current_password: Some("very-strong-password".to_string()),
+        encrypted_private_key: Some("encrypted-gibberish-again".to_string()),
+    })
+    .await
+    .unwrap();
+
+    assert!(notifier.has("Password changed"));
+}
+
+#[async_std::test]
+async fn app_password_session_is_scoped_until_revoked() {
+    let context = Context::mock_sqlite().await;
+    let auth = create_lib(&context);
+    let user = entity::mock::create_user(&context.db, "camera@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+    let (folder, _) = entity::mock::create_file(&context.db, &user, "Camera", "dir", None).await;
+    let (others, _) = entity::mock::create_file(&context.db, &other, "Other", "dir", None).await;
+
+    let created = auth
+        .create_app_password(
+            user.id,
+            "Phone".to_string(),
+            vec![Scope::Upload],
+            Some(others.id),
+        )
+        .await;
+    assert!(created.is_err(), "Folder of another user can't be used");
+
+    let (app_password, password) = auth
+        .create_app_password(
+            user.id,
+            "Phone".to_string(),
+            vec![Scope::Upload],
+            Some(folder.id),
+        )
+        .await
+        .unwrap();
+    assert_ne!(app_password.password, password);
+
+    let login = |password: &str| {
+        AppPasswordProvider::new(
+            &auth,
+            Credentials {
+                email: Some("camera@test.com".to_string()),
+                password: Some(password.to_string()),
+                token: None,
+            },
+        )
+    };
+
+    assert!(login("wrong")
+        .authenticate("n/a", "127.0.0.1")
+        .await
+        .is_err());
+    let forged = format!("{}.forged", app_password.id.simple());
+    assert!(login(&forged)
+        .authenticate("n/a", "127.0.0.1")
+        .await
+        .is_err());
+
+    let authenticated = login(&password)
+        .authenticate("n/a", "127.0.0.1")
+        .await
+        .unwrap();
+    assert_eq!(authenticated.session.app_password_id, Some(app_password.id));
+
+    let claims = Claims::from(&authenticated);
+    let app = claims.app.as_ref().unwrap();
+    assert!(app.allows(&Method::POST, "/api/storage"));
+    assert!(app.allows(&Method::POST, &format!("/api/storage/{}", folder.id)));
+    assert!(app.allows(&Method::POST, "/api/auth/self"));
+    assert!(!app.allows(&Method::GET, "/api/storage"));
+    assert!(!app.allows(&Method::POST, "/api/storage/delete-many"));
+    assert!(!app.allows(&Method::GET, "/api/auth/app-passwords"));
+    assert!(claims.check_folder(Some(folder.id)).is_ok());
+    assert!(claims.check_folder(None).is_err());
+
+    let read = AppClaims {
+        id: app_password.id,
+        scopes: vec![Scope::Read],
+        folder_id: None,
+    };
+    assert!(read.allows(&Method::GET, "/api/storage"));
+    assert!(read.allows(&Method::POST, "/api/storage/search"));
+    assert!(!read.allows(&Method::POST, "/api/storage"));
+
+    let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+    assert!(read.allows(&propfind, "/api/dav/"));
+    assert!(read.allows(&Method::GET, "/api/dav/Documents/notes.txt"));
+    assert!(!read.allows(&Method::PUT, "/api/dav/notes.txt"));
+    assert!(!read.allows(&Method::DELETE, "/api/dav/notes.txt"));
+
+    let listed = auth.app_passwords(user.id).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].last_used_at.is_some());
+
+    assert!(auth
+        .revoke_app_password(app_password.id, other.id)
+        .await
+        .is_err());
+    auth.revoke_app_password(app_password.id, user.id)
+        .await
+        .unwrap();
+
+    assert!(auth
+        .get_by_device_id(authenticated.session.device_id)
+        .await
+        .is_err());
+    assert!(login(&password)
+        .authenticate("n/a", "127.0.0.1")
+        .await
+        .is_err());
+}
+
+#[test]
+fn app_password_scopes_open_each_storage_route() {
+    use Scope::{Read, Upload, Write};
+
+    let id = Uuid::new_v4();
+    let version_id = Uuid::new_v4();
+    let file = |path: &str| format!("/api/storage/{}{}", id, path);
+    let version = |path: &str| file(&format!("/versions/{}{}", version_id, path));
+
+    // Route and the only scope that opens it, `None` for the ones no app password reaches
+    let routes = [
+        ("GET", "/api/storage".to_string(), Some(Read)),
+        ("GET", "/api/storage/tree".to_string(), Some(Read)),
+        ("GET", "/api/storage/trash".to_string(), Some(Read)),
+        ("GET", file(""), Some(Read)),
+        ("HEAD", file(""), Some(Read)),
+        ("GET", file("/metadata"), Some(Read)),
+        ("GET", file("/content"), Some(Read)),
+        ("GET", file("/manifest"), Some(Read)),
+        ("GET", file("/thumbnail"), Some(Read)),
+        ("GET", file("/snapshot"), Some(Read)),
+        ("GET", file("/zip"), Some(Read)),
+        ("GET", file("/versions"), Some(Read)),
+        ("GET", file("/verdicts"), Some(Read)),
+        ("GET", file("/changes"), Some(Read)),
+        ("GET", "/api/storage/root/changes".to_string(), Some(Read)),
+        ("GET", "/api/storage/hash/name-hash".to_string(), Some(Read)),
+        ("POST", "/api/storage/search".to_string(), Some(Read)),
+        ("POST", "/api/storage/manifest".to_string(), Some(Read)),
+        ("POST", "/api/storage/name-hashes".to_string(), Some(Read)),
+        ("POST", "/api/storage/stats".to_string(), Some(Read)),
+        ("POST", file("/snapshot/diff"), Some(Read)),
+        ("POST", "/api/storage".to_string(), Some(Upload)),
+        ("POST", "/api/storage/bulk".to_string(), Some(Upload)),
+        (
+            "POST",
+            "/api/storage/simple-upload".to_string(),
+            Some(Upload),
+        ),
+        ("POST", "/api/storage/dedup".to_string(), Some(Upload)),
+        ("POST", "/api/storage/dedup/files".to_string(), Some(Upload)),
+        ("POST", "/api/storage/resume".to_string(), Some(Upload)),
+        ("POST", "/api/storage/tus".to_string(), Some(Upload)),
+        ("HEAD", format!("/api/storage/tus/{}", id), Some(Upload)),
+        ("PATCH", format!("/api/storage/tus/{}", id), Some(Upload)),
+        ("POST", file(""), Some(Upload)),
+        ("POST", file("/resume"), Some(Upload)),
+        ("POST", file("/thumbnail"), Some(Upload)),
+        ("GET", file("/socket"), Some(Upload)),
+        ("POST", "/api/storage/move-many".to_string(), Some(Write)),
+        ("POST", "/api/storage/delete-many".to_string(), Some(Write)),
+        ("POST", "/api/storage/virtual".to_string(), Some(Write)),
+        ("POST", "/api/storage/batch".to_string(), Some(Write)),
+        ("PUT", file(""), Some(Write)),
+        ("DELETE", file(""), Some(Write)),
+        ("PUT", file("/rename"), Some(Write)),
+        ("PUT", file("/content"), Some(Write)),
+        ("PATCH", file("/attributes"), Some(Write)),
+        ("POST", file("/move"), Some(Write)),
+        ("POST", file("/copy"), Some(Write)),
+        ("DELETE", "/api/storage/trash".to_string(), Some(Write)),
+        ("DELETE", format!("/api/storage/trash/{}", id), Some(Write)),
+        (
+            "POST",
+            format!("/api/storage/trash/{}/restore", id),
+            Some(Write),
+        ),
+        ("DELETE", version(""), Some(Write)),
+        ("POST", version("/restore"), Some(Write)),
+        ("GET", file("/shares"), None),
+        ("POST", file("/shares"), None),
+        ("POST", file("/shares/groups"), None),
+        ("PUT", file(&format!("/shares/{}", id)), None),
+        ("DELETE", file(&format!("/shares/{}", id)), None),
+        ("GET", file("/policy"), None),
+        ("PUT", file("/policy"), None),
+        ("DELETE", file("/policy"), None),
+    ];
+
+    for scope in [Read, Upload, Write] {
+        let app = AppClaims {
+            id,
+            scopes: vec![scope],
+            folder_id: None,
+        };
+
+        for (method, path, opened_by) in routes.iter() {
+            let method = Method::from_str(method).unwrap();
+
+            assert_eq!(
+                app.allows(&method, path),
+                *opened_by == Some(scope),
+                "{} {} with the {} scope",
+                method,
+                path,
+                scope.as_str()
+            );
+        }
+    }
+}
+
+#[async_std::test]
+async fn vault_is_locked_after_the_idle_period() {
+    let context = Context::mock_sqlite().await;
+    let auth = create_lib(&context);
+
+    let user = entity::mock::create_user(&context.db, "vault@test.com", None).await;
+    let session = entity::mock::create_session(&context.db, &user, None, None, false).await;
+    assert!(!UnlockState::from(&session).unlocked);
+
+    assert_eq!(Unlock::default().into_value().unwrap(), DEFAULT_LOCK_AFTER);
+    assert!(Unlock {
+        lock_after: Some(MAX_LOCK_AFTER + 1)
+    }
+    .into_value()
+    .is_err());
+
+    // Activity is only kept once the vault was unlocked
+    assert!(auth.touch_unlock(&session).await.is_err());
+
+    let session = auth.unlock(&session, DEFAULT_LOCK_AFTER).await.unwrap();
+    let state = UnlockState::from(&session);
+    assert!(state.unlocked);
+    assert_eq!(
+        state.locks_at,
+        Some(session.unlocked_at.unwrap() + DEFAULT_LOCK_AFTER)
+    );
+
+    let session = auth.touch_unlock(&session).await.unwrap();
+    assert!(session.is_unlocked(Utc::now().timestamp()));
+    assert!(!session.is_unlocked(Utc::now().timestamp() + DEFAULT_LOCK_AFTER + 1));
+
+    // Refreshing the session keeps the vault unlocked, the logout locks it
+    let authenticated = auth.refresh(&session).await.unwrap();
+    assert!(UnlockState::from(&authenticated.session).unlocked);
+
+    let session = auth.lock(&authenticated.session).await.unwrap();
+    assert!(!UnlockState::from(&session).unlocked);
+    assert!(auth.touch_unlock(&session).await.is_err());
+
+    let session = auth.unlock(&session, DEFAULT_LOCK_AFTER).await.unwrap();
+    let authenticated = auth.destroy(&session).await.unwrap();
+    assert!(!UnlockState::from(&authenticated.session).unlocked);
+}
@@ -14,6 +14,24 @@ pub struct AppConfig {
     /// *required*
     pub data_dir: String,
 
+    /// REPLICA_DIR where every uploaded chunk is mirrored to, ideally on another disk.
+    /// When a chunk goes missing from the DATA_DIR it is served from the replica and
+    /// copied back by the background worker.
+    ///
+    /// *optional*
+    ///
+    /// default: chunks are stored only in the DATA_DIR
+    pub replica_dir: Option<String>,
+
+    /// SHARD_DIRS comma separated directories the chunks are spread across together
+    /// with the DATA_DIR, ideally each on its own disk. New chunks are written to
+    /// the one with the most available space, and the admin can rebalance them.
+    ///
+    /// *optional*
+    ///
+    /// default: chunks are stored only in the DATA_DIR
+    pub shard_dirs: Vec<String>,
+
     /// DATABASE_URL connection info for Postgres database
     ///
     /// *optional*
@@ -77,6 +95,8 @@ pub struct AppConfig {
 impl AppConfig {
     pub(crate) fn new(vars: &mut Vars) -> Self {
         let data_dir = vars.var_default::<String>("DATA_DIR", "".to_string());
+        let replica_dir = vars.maybe_var("REPLICA_DIR");
+        let shard_dirs = vars.maybe_var::<String>("SHARD_DIRS");
         let database_url = vars.maybe_var("DATABASE_URL");
         let address = vars
             .var_default("HTTP_ADDRESS", "localhost".to_string())
@@ -99,6 +119,16 @@ impl AppConfig {
             port,
             address,
             data_dir: data_dir.get(),
+            replica_dir: replica_dir.maybe_get(),
+            shard_dirs: shard_dirs
+                .maybe_get()
+                .map(|dirs| {
+                    dirs.split(',')
+                        .map(|dir| remove_trailing_slash(dir.to_string()))
+                        .filter(|dir| !dir.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
             database_url: database_url.maybe_get(),
             name: name.get(),
             version: version.get(),
@@ -121,30 +151,20 @@ impl AppConfig {
 
     /// Once the application starts, this will make sure the data_dir actually
     /// exists and its writeable, it will try to create it if possible, but if
-    /// it fails it will panic.
+    /// it fails it will panic. Same goes for the replica_dir and the shard_dirs.
     pub fn ensure_data_dir(&mut self, data_dir: Option<String>) {
         let data_dir = data_dir.unwrap_or_else(|| self.data_dir.clone());
 
-        let data_dir = absolute_path(&data_dir)
-            .unwrap_or_else(|| panic!("Couldn't get absolute path for '{}'", &data_dir));
-
-        match DirBuilder::new().recursive(true).create(&data_dir) {
-            Ok(_) => (),
-            Err(e) => println!("Error creating directory: {:?}", e),
-        };
-
-        let metadata = fs::metadata(&data_dir).unwrap_or_else(|e| {
-            panic!(
-                "Got error when attempting to get metadata of a data dir '{}': {}",
-                data_dir, e
-            )
-        });
-
-        if metadata.permissions().readonly() {
-            panic!("DATA_DIR is not writeable to the application, aborting...")
-        }
-
-        self.data_dir = remove_trailing_slash(data_dir);
+        self.data_dir = ensure_dir(&data_dir, "DATA_DIR");
+        self.replica_dir = self
+            .replica_dir
+            .as_deref()
+            .map(|replica_dir| ensure_dir(replica_dir, "REPLICA_DIR"));
+        self.shard_dirs = self
+            .shard_dirs
+            .iter()
+            .map(|shard_dir| ensure_dir(shard_dir, "SHARD_DIRS"))
+            .collect();
     }
 
     /// Set database url in the env if it wasn't already for the migration
@@ -161,3 +181,27 @@ impl AppConfig {
         self
     }
 }
+
+/// Make sure the directory exists and it is writeable, returns its absolute path
+fn ensure_dir(dir: &str, name: &str) -> String {
+    let dir =
+        absolute_path(dir).unwrap_or_else(|| panic!("Couldn't get absolute path for '{}'", dir));
+
+    match DirBuilder::new().recursive(true).create(&dir) {
+        Ok(_) => (),
+        Err(e) => println!("Error creating directory: {:?}", e),
+    };
+
+    let metadata = fs::metadata(&dir).unwrap_or_else(|e| {
+        panic!(
+            "Got error when attempting to get metadata of a {} '{}': {}",
+            name, dir, e
+        )
+    });
+
+    if metadata.permissions().readonly() {
+        panic!("{} is not writeable to the application, aborting...", name)
+    }
+
+    remove_trailing_slash(dir)
+}
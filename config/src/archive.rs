@@ -0,0 +1,76 @@
+#![allow(rustdoc::invalid_html_tags)]
+
+use crate::vars::Vars;
+
+/// Cold archive configuration holder, when the bucket is configured the chunks
+/// of the files nobody has touched for a while are moved into it.
+///
+/// To archive the chunks you need to set the following environment variables:
+/// ARCHIVE_URL=https://s3.eu-west-1.amazonaws.com/bucket/hoodik
+/// ARCHIVE_REGION=eu-west-1 # optional
+/// ARCHIVE_ACCESS_KEY_ID=key
+/// ARCHIVE_SECRET_ACCESS_KEY=secret
+/// ARCHIVE_STORAGE_CLASS=GLACIER # optional
+/// ARCHIVE_AFTER_MONTHS=6 # optional
+/// ARCHIVE_RESTORE_TIER=Standard # optional
+/// ARCHIVE_RESTORE_DAYS=1 # optional
+#[derive(Debug, Clone)]
+pub enum ArchiveConfig {
+    Bucket(ArchiveBucket),
+    None,
+}
+
+/// S3 bucket with the archive tier storage class the chunks are moved to.
+///
+/// Restored copy of the chunk is kept by S3 for the `restore_days`, the chunks
+/// are copied back to the storage long before that, so a day is plenty.
+#[derive(Debug, Clone)]
+pub struct ArchiveBucket {
+    pub url: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub storage_class: String,
+    pub after_months: u32,
+    pub restore_tier: String,
+    pub restore_days: u32,
+}
+
+impl ArchiveBucket {
+    fn new(vars: &mut Vars, url: String) -> Box<dyn FnOnce() -> Self> {
+        let region = vars.var_default("ARCHIVE_REGION", "us-east-1".to_string());
+        let access_key_id = vars.var::<String>("ARCHIVE_ACCESS_KEY_ID");
+        let secret_access_key = vars.var::<String>("ARCHIVE_SECRET_ACCESS_KEY");
+        let storage_class = vars.var_default("ARCHIVE_STORAGE_CLASS", "GLACIER".to_string());
+        let after_months = vars.var_default::<u32>("ARCHIVE_AFTER_MONTHS", 6);
+        let restore_tier = vars.var_default("ARCHIVE_RESTORE_TIER", "Standard".to_string());
+        let restore_days = vars.var_default::<u32>("ARCHIVE_RESTORE_DAYS", 1);
+
+        Box::new(move || Self {
+            url: url.trim_end_matches('/').to_string(),
+            region: region.get(),
+            access_key_id: access_key_id.get(),
+            secret_access_key: secret_access_key.get(),
+            storage_class: storage_class.get(),
+            after_months: after_months.get().max(1),
+            restore_tier: restore_tier.get(),
+            restore_days: restore_days.get().max(1),
+        })
+    }
+}
+
+impl ArchiveConfig {
+    pub(crate) fn new(vars: &mut Vars) -> Self {
+        let url = vars.var_default("ARCHIVE_URL", "".to_string()).get();
+
+        if url.is_empty() {
+            return Self::None;
+        }
+
+        let bucket = ArchiveBucket::new(vars, url);
+
+        vars.panic_if_errors("ArchiveConfig");
+
+        Self::Bucket(bucket())
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{app::AppConfig, email::EmailConfig, ssl::SslConfig, vars::Vars};
+use crate::{
+    app::AppConfig, archive::ArchiveConfig, email::EmailConfig, mqtt::MqttConfig,
+    notify::NotifyConfig, server::ServerConfig, ssl::SslConfig, storage::StorageConfig, vars::Vars,
+};
 
 /// Config struct that holds all the loaded configuration
 /// from the env and arguments.
@@ -28,6 +31,26 @@ pub struct Config {
     /// Email configuration holder, there are couple of options for this configuration,
     /// see more details in the [crate::email::EmailConfig] struct.
     pub mailer: crate::email::EmailConfig,
+
+    /// Worker and thread topology of the server
+    /// see more details in the [crate::server::ServerConfig] struct.
+    pub server: crate::server::ServerConfig,
+
+    /// Broker the file events are published to, if any
+    /// see more details in the [crate::mqtt::MqttConfig] struct.
+    pub mqtt: crate::mqtt::MqttConfig,
+
+    /// Transport the push notifications are sent through, if any
+    /// see more details in the [crate::notify::NotifyConfig] struct.
+    pub notify: crate::notify::NotifyConfig,
+
+    /// Bucket the chunks of the cold files are archived to, if any
+    /// see more details in the [crate::archive::ArchiveConfig] struct.
+    pub archive: crate::archive::ArchiveConfig,
+
+    /// Storage the chunks of the files are kept in
+    /// see more details in the [crate::storage::StorageConfig] struct.
+    pub storage: crate::storage::StorageConfig,
 }
 
 impl From<Vars> for Config {
@@ -41,6 +64,11 @@ impl From<Vars> for Config {
 
         let mailer = EmailConfig::new(&mut vars);
         let auth = crate::auth::AuthConfig::new(&app, &mut vars);
+        let server = ServerConfig::new(&mut vars);
+        let mqtt = MqttConfig::new(&mut vars);
+        let notify = NotifyConfig::new(&mut vars);
+        let archive = ArchiveConfig::new(&mut vars);
+        let storage = StorageConfig::new(&mut vars);
 
         vars.panic_if_errors("Config");
 
@@ -49,6 +77,11 @@ impl From<Vars> for Config {
             app,
             auth,
             mailer,
+            server,
+            mqtt,
+            notify,
+            archive,
+            storage,
         }
     }
 }
@@ -1,9 +1,14 @@
 pub mod app;
+pub mod archive;
 pub mod auth;
 pub mod config;
 pub mod email;
 pub(crate) mod helpers;
+pub mod mqtt;
+pub mod notify;
+pub mod server;
 pub mod ssl;
+pub mod storage;
 pub mod vars;
 
 use helpers::remove_trailing_slash;
@@ -41,6 +46,33 @@ impl Config {
 
         println!("-- Using data_dir: {}", self.app.data_dir);
 
+        match &self.storage {
+            storage::StorageConfig::S3(bucket) => {
+                println!("-- Storing file chunks in s3 bucket {}", bucket.url);
+            }
+            storage::StorageConfig::Azure(container) => {
+                println!(
+                    "-- Storing file chunks in azure container {}",
+                    container.url
+                );
+            }
+            storage::StorageConfig::Gcs(bucket) => {
+                println!("-- Storing file chunks in gcs bucket {}", bucket.bucket);
+            }
+            storage::StorageConfig::Tiered(tiered) => {
+                println!(
+                    "-- Keeping file chunks used in the last {} days locally, the rest in {}",
+                    tiered.hot_days,
+                    tiered.cold.provider()
+                );
+            }
+            storage::StorageConfig::Local => {}
+        }
+
+        if !self.app.shard_dirs.is_empty() {
+            println!("-- Using shard_dirs: {}", self.app.shard_dirs.join(", "));
+        }
+
         if self.ssl.disabled {
             println!("-- SSL is disabled");
         } else {
@@ -48,6 +80,36 @@ impl Config {
             println!("-- Using ssl key: {}", self.ssl.key_file);
         }
 
+        println!(
+            "-- Using {} workers with {} blocking threads each, storage io concurrency: {}",
+            self.server.workers, self.server.blocking_threads, self.server.io_concurrency
+        );
+
+        if let mqtt::MqttConfig::Broker(broker) = &self.mqtt {
+            println!(
+                "-- Publishing file events to mqtt broker {}:{}",
+                broker.host, broker.port
+            );
+        }
+
+        match &self.notify {
+            notify::NotifyConfig::Ntfy(ntfy) => {
+                println!("-- Pushing notifications to ntfy {}", ntfy.url)
+            }
+            notify::NotifyConfig::Gotify(gotify) => {
+                println!("-- Pushing notifications to gotify {}", gotify.url)
+            }
+            notify::NotifyConfig::Webhook(_) => println!("-- Pushing notifications to the webhook"),
+            notify::NotifyConfig::None => {}
+        }
+
+        if let archive::ArchiveConfig::Bucket(bucket) = &self.archive {
+            println!(
+                "-- Archiving files untouched for {} months to {}",
+                bucket.after_months, bucket.url
+            );
+        }
+
         println!("-- RUST_LOG={:?}", std::env::var("RUST_LOG").ok());
         println!("------------------------------------------");
     }
@@ -0,0 +1,68 @@
+#![allow(rustdoc::invalid_html_tags)]
+
+use crate::vars::Vars;
+
+/// MQTT configuration holder, when the broker is configured the
+/// file events are published to it (requires the `mqtt` feature).
+///
+/// To publish the events you need to set the following environment variables:
+/// MQTT_HOST=mqtt.example.com
+/// MQTT_PORT=1883 # optional
+/// MQTT_USERNAME=example # optional
+/// MQTT_PASSWORD=secret # optional
+/// MQTT_CLIENT_ID=hoodik # optional
+/// MQTT_TOPIC_PREFIX=hoodik # optional
+#[derive(Debug, Clone)]
+pub enum MqttConfig {
+    Broker(MqttBroker),
+    None,
+}
+
+/// MQTT broker connection holder.
+///
+/// Events are published to the `<MQTT_TOPIC_PREFIX>/<user_id>/<folder_id>` topics,
+/// where the folder id is `root` for the files in the root of the user storage.
+#[derive(Debug, Clone)]
+pub struct MqttBroker {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: String,
+    pub topic_prefix: String,
+}
+
+impl MqttBroker {
+    fn new(vars: &mut Vars, host: String) -> Box<dyn FnOnce() -> Self> {
+        let port = vars.var_default::<u16>("MQTT_PORT", 1883);
+        let username = vars.maybe_var::<String>("MQTT_USERNAME");
+        let password = vars.maybe_var::<String>("MQTT_PASSWORD");
+        let client_id = vars.var_default("MQTT_CLIENT_ID", "hoodik".to_string());
+        let topic_prefix = vars.var_default("MQTT_TOPIC_PREFIX", "hoodik".to_string());
+
+        Box::new(move || Self {
+            host,
+            port: port.get(),
+            username: username.maybe_get(),
+            password: password.maybe_get(),
+            client_id: client_id.get(),
+            topic_prefix: topic_prefix.get().trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl MqttConfig {
+    pub(crate) fn new(vars: &mut Vars) -> Self {
+        let host = vars.var_default("MQTT_HOST", "".to_string()).get();
+
+        if host.is_empty() {
+            return Self::None;
+        }
+
+        let broker = MqttBroker::new(vars, host);
+
+        vars.panic_if_errors("MqttConfig");
+
+        Self::Broker(broker())
+    }
+}
@@ -0,0 +1,124 @@
+#![allow(rustdoc::invalid_html_tags)]
+
+use crate::vars::Vars;
+
+/// Push notification configuration holder, the security and share alerts
+/// are pushed to the phones of the users through it, with or without SMTP.
+///
+/// To push the notifications you need to set the `NOTIFY_TYPE` to one of:
+/// NOTIFY_TYPE=ntfy
+/// NOTIFY_TYPE=gotify
+/// NOTIFY_TYPE=webhook
+///
+/// and the variables of the chosen transport, see the structs below.
+#[derive(Debug, Clone)]
+pub enum NotifyConfig {
+    Ntfy(NtfyConfig),
+    Gotify(GotifyConfig),
+    Webhook(WebhookConfig),
+    None,
+}
+
+/// ntfy server the notifications are published to.
+///
+/// Each user gets their own topic `<NTFY_TOPIC_PREFIX>-<user_id>`,
+/// they subscribe to it in the ntfy app on their phone.
+///
+/// NTFY_URL=https://ntfy.sh # optional
+/// NTFY_TOPIC_PREFIX=hoodik # optional
+/// NTFY_TOKEN=tk_secret # optional, access token for the protected topics
+#[derive(Debug, Clone)]
+pub struct NtfyConfig {
+    pub url: String,
+    pub topic_prefix: String,
+    pub token: Option<String>,
+}
+
+impl NtfyConfig {
+    fn new(vars: &mut Vars) -> Box<dyn FnOnce() -> Self> {
+        let url = vars.var_default("NTFY_URL", "https://ntfy.sh".to_string());
+        let topic_prefix = vars.var_default("NTFY_TOPIC_PREFIX", "hoodik".to_string());
+        let token = vars.maybe_var::<String>("NTFY_TOKEN");
+
+        Box::new(move || Self {
+            url: url.get().trim_end_matches('/').to_string(),
+            topic_prefix: topic_prefix.get(),
+            token: token.maybe_get(),
+        })
+    }
+}
+
+/// Gotify server the notifications are pushed to with the application token.
+///
+/// Gotify delivers the messages of an application to the user that owns it,
+/// so it suits the instances where everyone shares the same Gotify user.
+///
+/// GOTIFY_URL=https://gotify.example.com
+/// GOTIFY_TOKEN=application-token
+#[derive(Debug, Clone)]
+pub struct GotifyConfig {
+    pub url: String,
+    pub token: String,
+}
+
+impl GotifyConfig {
+    fn new(vars: &mut Vars) -> Box<dyn FnOnce() -> Self> {
+        let url = vars.var::<String>("GOTIFY_URL");
+        let token = vars.var::<String>("GOTIFY_TOKEN");
+
+        Box::new(move || Self {
+            url: url.get().trim_end_matches('/').to_string(),
+            token: token.get(),
+        })
+    }
+}
+
+/// Any URL the notifications are posted to as JSON, to be routed anywhere from there.
+///
+/// WEBHOOK_URL=https://hooks.example.com/hoodik
+/// WEBHOOK_SECRET=secret # optional, sent as the bearer token
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+impl WebhookConfig {
+    fn new(vars: &mut Vars) -> Box<dyn FnOnce() -> Self> {
+        let url = vars.var::<String>("WEBHOOK_URL");
+        let secret = vars.maybe_var::<String>("WEBHOOK_SECRET");
+
+        Box::new(move || Self {
+            url: url.get(),
+            secret: secret.maybe_get(),
+        })
+    }
+}
+
+impl NotifyConfig {
+    pub(crate) fn new(vars: &mut Vars) -> Self {
+        let transport = vars.var_default("NOTIFY_TYPE", "".to_string()).get();
+
+        match transport.as_str() {
+            "ntfy" => {
+                let config = NtfyConfig::new(vars);
+                vars.panic_if_errors("NotifyConfig");
+
+                Self::Ntfy(config())
+            }
+            "gotify" => {
+                let config = GotifyConfig::new(vars);
+                vars.panic_if_errors("NotifyConfig");
+
+                Self::Gotify(config())
+            }
+            "webhook" => {
+                let config = WebhookConfig::new(vars);
+                vars.panic_if_errors("NotifyConfig");
+
+                Self::Webhook(config())
+            }
+            _ => Self::None,
+        }
+    }
+}
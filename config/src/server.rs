@@ -0,0 +1,453 @@
+use std::net::IpAddr;
+
+use crate::vars::Vars;
+
+/// How many blocking threads actix-web gives to all the workers together by default
+const BLOCKING_THREADS_TOTAL: usize = 512;
+
+/// Largest file that can be read and written in a single request by default
+const QUICK_EDIT_MAX_SIZE: u64 = 1024 * 1024;
+
+/// How many idle connections to the remote storage are kept for each host by default
+const STORAGE_POOL_MAX_IDLE: usize = 32;
+
+/// How many seconds the idle connection to the remote storage is kept open by default
+const STORAGE_POOL_IDLE_TIMEOUT: u64 = 90;
+
+/// How many times the failing remote storage operation is attempted by default
+const STORAGE_RETRY_ATTEMPTS: u32 = 3;
+
+/// Milliseconds before the first retry of the remote storage operation by default
+const STORAGE_RETRY_BASE_DELAY: u64 = 100;
+
+/// Longest wait in milliseconds between the retries by default
+const STORAGE_RETRY_MAX_DELAY: u64 = 5000;
+
+/// Percent of the remote storage operations that can be retried by default
+const STORAGE_RETRY_BUDGET: u64 = 20;
+
+/// How many chunks are read in advance while the whole file is downloaded by default
+const STORAGE_READ_AHEAD: usize = 4;
+
+/// Longest encrypted name or key of the file accepted by default
+const STORAGE_METADATA_MAX_LENGTH: usize = 8 * 1024;
+
+/// Longest encrypted thumbnail of the file accepted by default
+const STORAGE_THUMBNAIL_MAX_LENGTH: usize = 512 * 1024;
+
+/// Most search tokens a single file can have by default
+const STORAGE_SEARCH_TOKENS_MAX: usize = 512;
+
+/// How many days the deleted files stay in the trash by default
+const STORAGE_TRASH_RETENTION: u64 = 30;
+
+/// How many previous versions are kept for each file by default
+const STORAGE_VERSIONS_MAX: u32 = 10;
+
+/// How many downloads of the public links a single address can run at once by default
+const LINKS_CONNECTIONS_PER_IP: usize = 4;
+
+/// How many downloads of a single public link can run at once by default
+const LINKS_CONNECTIONS_PER_LINK: usize = 32;
+
+/// How many seconds the download of the public link waits for a free connection by default
+const LINKS_QUEUE_TIMEOUT: u64 = 10;
+
+/// How many seconds the client has to send the request headers by default
+const HTTP_REQUEST_TIMEOUT: u64 = 5;
+
+/// How many seconds the idle keep-alive connection is kept open by default
+const HTTP_KEEP_ALIVE: u64 = 5;
+
+/// How many seconds the upload or the download can go without any progress by default
+const HTTP_STREAM_TIMEOUT: u64 = 60;
+
+/// Slowest average upload or download speed in bytes per second allowed by default
+const HTTP_MIN_THROUGHPUT: u64 = 1024;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// HTTP_WORKERS: Number of HTTP worker threads that will be handling the requests,
+    /// each of them runs its own async runtime.
+    ///
+    /// *optional*
+    ///
+    /// default: number of available CPUs
+    pub workers: usize,
+
+    /// HTTP_BLOCKING_THREADS: Maximum number of threads each of the workers
+    /// can use for blocking operations (file system, hashing etc.).
+    ///
+    /// *optional*
+    ///
+    /// default: 512 / HTTP_WORKERS
+    pub blocking_threads: usize,
+
+    /// STORAGE_IO_CONCURRENCY: Maximum number of storage provider operations (chunk reads and writes)
+    /// that can run at the same time across the whole application. Lower it on slow disks
+    /// or on machines with little memory, because every running operation holds a chunk in memory.
+    ///
+    /// *optional*
+    ///
+    /// default: 4 * number of available CPUs
+    pub io_concurrency: usize,
+
+    /// STORAGE_POOL_MAX_IDLE: Most of the idle connections to the remote storage (S3, Azure,
+    /// GCS) kept open for each host, so the chunk operations don't have to connect again.
+    ///
+    /// *optional*
+    ///
+    /// default: 32
+    pub pool_max_idle: usize,
+
+    /// STORAGE_POOL_IDLE_TIMEOUT: Seconds the idle connection to the remote storage is kept
+    /// open for, before it is closed.
+    ///
+    /// *optional*
+    ///
+    /// default: 90
+    pub pool_idle_timeout: u64,
+
+    /// STORAGE_RETRY_ATTEMPTS: Attempts of the remote storage (S3, Azure, GCS) operation that
+    /// fails with the error likely to go away on its own (throttling, 5xx, dropped connection),
+    /// the first one included. Set it to 1 to never retry.
+    ///
+    /// *optional*
+    ///
+    /// default: 3
+    pub retry_attempts: u32,
+
+    /// STORAGE_RETRY_BASE_DELAY: Milliseconds to wait before the first retry, doubled for each
+    /// next one. The actual wait is picked at random up to it, so the retries are spread out.
+    ///
+    /// *optional*
+    ///
+    /// default: 100
+    pub retry_base_delay: u64,
+
+    /// STORAGE_RETRY_MAX_DELAY: Longest wait in milliseconds between the retries.
+    ///
+    /// *optional*
+    ///
+    /// default: 5000
+    pub retry_max_delay: u64,
+
+    /// STORAGE_RETRY_BUDGET: Percent of the remote storage operations that can be retried,
+    /// so the retries can't multiply the load on the storage that is down.
+    ///
+    /// *optional*
+    ///
+    /// default: 20
+    pub retry_budget: u64,
+
+    /// STORAGE_READ_AHEAD: Number of the chunks that are read in advance while the whole file
+    /// is downloaded, so the next chunks are ready by the time the client is done with
+    /// the current one. Every chunk read in advance is held in memory, set it to 0
+    /// to read the chunks one by one in small pieces instead.
+    ///
+    /// *optional*
+    ///
+    /// default: 4
+    pub read_ahead: usize,
+
+    /// STORAGE_METADATA_MAX_LENGTH: Longest encrypted name and encrypted key (in characters)
+    /// of the file the clients can send, so nobody can stuff megabytes into them.
+    ///
+    /// *optional*
+    ///
+    /// default: 8192
+    pub metadata_max_length: usize,
+
+    /// STORAGE_THUMBNAIL_MAX_LENGTH: Longest encrypted thumbnail (in characters) of the file
+    /// the clients can send.
+    ///
+    /// *optional*
+    ///
+    /// default: 524288
+    pub thumbnail_max_length: usize,
+
+    /// STORAGE_SEARCH_TOKENS_MAX: Most search tokens a single file can be searched by.
+    ///
+    /// *optional*
+    ///
+    /// default: 512
+    pub search_tokens_max: usize,
+
+    /// STORAGE_GLOBAL_DEDUP: Let the clients skip the upload of the content that any user
+    /// on the platform has already uploaded, not only themselves. Only enable it when the
+    /// clients derive the file keys from the content, and keep in mind that it tells the
+    /// users which content is already stored on the server.
+    ///
+    /// *optional*
+    ///
+    /// default: false
+    pub global_dedup: bool,
+
+    /// STORAGE_QUICK_EDIT_MAX_SIZE: Largest file (in bytes) whose whole content can be read
+    /// and written in a single request, so the clients can edit small text files without
+    /// uploading them chunk by chunk. Files that don't fit into a single chunk never can.
+    ///
+    /// *optional*
+    ///
+    /// default: 1048576 (1 MiB)
+    pub quick_edit_max_size: u64,
+
+    /// STORAGE_TRASH_RETENTION: Number of days the deleted files and folders stay in the trash,
+    /// where they can be restored from, before they are purged for good. Set it to 0
+    /// to purge the files as soon as they are deleted, without the trash.
+    ///
+    /// *optional*
+    ///
+    /// default: 30
+    pub trash_retention: u64,
+
+    /// STORAGE_CONTENT_ADDRESSED: Store each chunk only once by the hash of its content,
+    /// the files only keep the list of the hashes of their chunks. Identical chunks are
+    /// stored once and the copies of the files cost nothing. Files uploaded before it
+    /// was enabled keep their chunks as they are and are read the same as before.
+    ///
+    /// *optional*
+    ///
+    /// default: false
+    pub content_addressed: bool,
+
+    /// STORAGE_VERSIONS_MAX: Number of the previous versions kept for each file when a file
+    /// with the same name is uploaded into the same folder, the oldest ones are deleted
+    /// once there are more. The admin can set another number for each user. Set it to 0
+    /// to refuse the uploads of the files whose name is already taken.
+    ///
+    /// *optional*
+    ///
+    /// default: 10
+    pub versions_max: u32,
+
+    /// STORAGE_CACHE: Keep the folder listings, the file metadata and the link lookups
+    /// in memory for a short while, so they are not loaded from the database on every
+    /// request. Only enable it when a single instance of the server is running, other
+    /// instances don't know when to drop the entries of the files changed elsewhere.
+    ///
+    /// *optional*
+    ///
+    /// default: false
+    pub cache: bool,
+
+    /// LINKS_CONNECTIONS_PER_IP: Maximum number of the public link downloads a single
+    /// address can run at the same time, so one client can't take all the workers.
+    ///
+    /// *optional*
+    ///
+    /// default: 4
+    pub links_connections_per_ip: usize,
+
+    /// LINKS_CONNECTIONS_PER_LINK: Maximum number of downloads of a single public link
+    /// running at the same time, so one popular link can't take all the workers.
+    ///
+    /// *optional*
+    ///
+    /// default: 32
+    pub links_connections_per_link: usize,
+
+    /// LINKS_QUEUE_TIMEOUT: Number of seconds the download over the limits waits in the queue
+    /// for a free connection before it is refused with `429 Too Many Requests`,
+    /// set it to 0 to refuse it right away.
+    ///
+    /// *optional*
+    ///
+    /// default: 10
+    pub links_queue_timeout: u64,
+
+    /// LINKS_GEOIP_DB: Path to the MaxMind GeoIP2 (or GeoLite2) country database used to find
+    /// the country of the address downloading the public link. Links can only be limited
+    /// to the countries when it is set, limits to the address ranges work without it.
+    ///
+    /// *optional*
+    pub links_geoip_db: Option<String>,
+
+    /// TRUSTED_PROXIES: Comma separated addresses of the reverse proxies in front of the
+    /// server. The address of the client is taken from the forwarded headers only when
+    /// the request comes from one of them, otherwise the address of the peer is used.
+    ///
+    /// *optional*
+    ///
+    /// default: none
+    pub trusted_proxies: Vec<IpAddr>,
+
+    /// HTTP_REQUEST_TIMEOUT: Number of seconds the client has to send the request
+    /// headers after connecting, set it to 0 to wait forever.
+    ///
+    /// *optional*
+    ///
+    /// default: 5
+    pub request_timeout: u64,
+
+    /// HTTP_KEEP_ALIVE: Number of seconds the idle connection is kept open
+    /// for the next request, set it to 0 to close it after each request.
+    ///
+    /// *optional*
+    ///
+    /// default: 5
+    pub keep_alive: u64,
+
+    /// HTTP_STREAM_TIMEOUT: Number of seconds the upload or the download of the file
+    /// can go without sending a single byte before the connection is dropped,
+    /// set it to 0 to wait forever.
+    ///
+    /// *optional*
+    ///
+    /// default: 60
+    pub stream_timeout: u64,
+
+    /// HTTP_MIN_THROUGHPUT: Slowest average speed of the upload or the download in bytes
+    /// per second, checked once the stream is running for HTTP_STREAM_TIMEOUT seconds.
+    /// Clients that are slower are disconnected, set it to 0 to allow any speed.
+    ///
+    /// *optional*
+    ///
+    /// default: 1024
+    pub min_throughput: u64,
+
+    /// HTTP3_PORT: UDP port of the HTTP/3 (QUIC) listener for the API, it runs side by side
+    /// with the HTTP_PORT listener on the same address and with the same certificate.
+    /// Clients on the lossy mobile networks get their uploads through faster over it.
+    /// Only available when the server is built with the `http3` feature and the SSL enabled.
+    ///
+    /// *optional*
+    pub http3_port: Option<u16>,
+}
+
+impl ServerConfig {
+    pub(crate) fn new(vars: &mut Vars) -> Self {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+
+        let workers = vars.var_default("HTTP_WORKERS", cpus).get().max(1);
+        let blocking_threads = vars
+            .var_default("HTTP_BLOCKING_THREADS", BLOCKING_THREADS_TOTAL / workers)
+            .get()
+            .max(1);
+        let io_concurrency = vars
+            .var_default("STORAGE_IO_CONCURRENCY", cpus * 4)
+            .get()
+            .max(1);
+        let pool_max_idle = vars
+            .var_default("STORAGE_POOL_MAX_IDLE", STORAGE_POOL_MAX_IDLE)
+            .get();
+        let pool_idle_timeout = vars
+            .var_default("STORAGE_POOL_IDLE_TIMEOUT", STORAGE_POOL_IDLE_TIMEOUT)
+            .get();
+        let retry_attempts = vars
+            .var_default("STORAGE_RETRY_ATTEMPTS", STORAGE_RETRY_ATTEMPTS)
+            .get()
+            .max(1);
+        let retry_base_delay = vars
+            .var_default("STORAGE_RETRY_BASE_DELAY", STORAGE_RETRY_BASE_DELAY)
+            .get();
+        let retry_max_delay = vars
+            .var_default("STORAGE_RETRY_MAX_DELAY", STORAGE_RETRY_MAX_DELAY)
+            .get();
+        let retry_budget = vars
+            .var_default("STORAGE_RETRY_BUDGET", STORAGE_RETRY_BUDGET)
+            .get();
+        let read_ahead = vars
+            .var_default("STORAGE_READ_AHEAD", STORAGE_READ_AHEAD)
+            .get();
+        let metadata_max_length = vars
+            .var_default("STORAGE_METADATA_MAX_LENGTH", STORAGE_METADATA_MAX_LENGTH)
+            .get();
+        let thumbnail_max_length = vars
+            .var_default("STORAGE_THUMBNAIL_MAX_LENGTH", STORAGE_THUMBNAIL_MAX_LENGTH)
+            .get();
+        let search_tokens_max = vars
+            .var_default("STORAGE_SEARCH_TOKENS_MAX", STORAGE_SEARCH_TOKENS_MAX)
+            .get();
+        let global_dedup = vars.var_default("STORAGE_GLOBAL_DEDUP", false).get();
+        let quick_edit_max_size = vars
+            .var_default("STORAGE_QUICK_EDIT_MAX_SIZE", QUICK_EDIT_MAX_SIZE)
+            .get();
+        let trash_retention = vars
+            .var_default("STORAGE_TRASH_RETENTION", STORAGE_TRASH_RETENTION)
+            .get();
+        let content_addressed = vars.var_default("STORAGE_CONTENT_ADDRESSED", false).get();
+        let versions_max = vars
+            .var_default("STORAGE_VERSIONS_MAX", STORAGE_VERSIONS_MAX)
+            .get();
+        let cache = vars.var_default("STORAGE_CACHE", false).get();
+        let links_connections_per_ip = vars
+            .var_default("LINKS_CONNECTIONS_PER_IP", LINKS_CONNECTIONS_PER_IP)
+            .get()
+            .max(1);
+        let links_connections_per_link = vars
+            .var_default("LINKS_CONNECTIONS_PER_LINK", LINKS_CONNECTIONS_PER_LINK)
+            .get()
+            .max(1);
+        let links_queue_timeout = vars
+            .var_default("LINKS_QUEUE_TIMEOUT", LINKS_QUEUE_TIMEOUT)
+            .get();
+        let links_geoip_db = vars.maybe_var::<String>("LINKS_GEOIP_DB").maybe_get();
+        let trusted_proxies = vars
+            .maybe_var::<String>("TRUSTED_PROXIES")
+            .maybe_get()
+            .map(|proxies| {
+                proxies
+                    .split(',')
+                    .map(|proxy| proxy.trim())
+                    .filter(|proxy| !proxy.is_empty())
+                    .filter_map(|proxy| match proxy.parse() {
+                        Ok(ip) => Some(ip),
+                        Err(_) => {
+                            log::warn!("Ignoring invalid trusted proxy address: {}", proxy);
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let request_timeout = vars
+            .var_default("HTTP_REQUEST_TIMEOUT", HTTP_REQUEST_TIMEOUT)
+            .get();
+        let keep_alive = vars.var_default("HTTP_KEEP_ALIVE", HTTP_KEEP_ALIVE).get();
+        let stream_timeout = vars
+            .var_default("HTTP_STREAM_TIMEOUT", HTTP_STREAM_TIMEOUT)
+            .get();
+        let min_throughput = vars
+            .var_default("HTTP_MIN_THROUGHPUT", HTTP_MIN_THROUGHPUT)
+            .get();
+        let http3_port = vars.maybe_var::<u16>("HTTP3_PORT").maybe_get();
+
+        vars.panic_if_errors("ServerConfig");
+
+        Self {
+            workers,
+            blocking_threads,
+            io_concurrency,
+            pool_max_idle,
+            pool_idle_timeout,
+            retry_attempts,
+            retry_base_delay,
+            retry_max_delay,
+            retry_budget,
+            read_ahead,
+            metadata_max_length,
+            thumbnail_max_length,
+            search_tokens_max,
+            global_dedup,
+            quick_edit_max_size,
+            trash_retention,
+            content_addressed,
+            versions_max,
+            cache,
+            links_connections_per_ip,
+            links_connections_per_link,
+            links_queue_timeout,
+            links_geoip_db,
+            trusted_proxies,
+            request_timeout,
+            keep_alive,
+            stream_timeout,
+            min_throughput,
+            http3_port,
+        }
+    }
+}
@@ -0,0 +1,291 @@
+#![allow(rustdoc::invalid_html_tags)]
+
+use crate::vars::Vars;
+
+/// Values the STORAGE_PROVIDER can be set to
+pub const PROVIDERS: [&str; 5] = ["fs", "s3", "azure", "gcs", "tiered"];
+
+/// Remote providers the STORAGE_TIERED_COLD can be set to
+pub const REMOTE_PROVIDERS: [&str; 3] = ["s3", "azure", "gcs"];
+
+/// Days the file stays on the local disk after it was last used, by default
+const TIERED_HOT_DAYS: u64 = 30;
+
+/// Storage the chunks of the files are kept in, the local file system
+/// in the DATA_DIR (and the SHARD_DIRS) unless another provider is configured.
+///
+/// The provider is picked with the STORAGE_PROVIDER, one of: fs, s3, azure, gcs, tiered.
+/// Without it the provider is the one whose section below is set, so the existing
+/// setups keep working, and the local file system when none of them is.
+/// STORAGE_PROVIDER=s3 # optional
+///
+/// To keep the chunks in the S3 compatible bucket (AWS, MinIO, Wasabi...) set:
+/// STORAGE_S3_URL=https://s3.eu-west-1.amazonaws.com/bucket/hoodik
+/// STORAGE_S3_REGION=eu-west-1 # optional
+/// STORAGE_S3_ACCESS_KEY_ID=key
+/// STORAGE_S3_SECRET_ACCESS_KEY=secret
+///
+/// To keep the chunks in the Azure Blob Storage container set:
+/// STORAGE_AZURE_URL=https://account.blob.core.windows.net/container/hoodik
+/// STORAGE_AZURE_SAS_TOKEN=sv=...&sig=... # without it the managed identity is used
+/// STORAGE_AZURE_CLIENT_ID=id # optional, the user assigned managed identity
+///
+/// To keep the chunks in the Google Cloud Storage bucket set:
+/// STORAGE_GCS_BUCKET=bucket/hoodik
+/// STORAGE_GCS_CREDENTIALS=/path/to/service-account.json
+/// STORAGE_GCS_URL=https://storage.googleapis.com # optional
+///
+/// To keep the recently used chunks on the local disk and the rest in one of the
+/// remote providers above (with its section set) set:
+/// STORAGE_PROVIDER=tiered
+/// STORAGE_TIERED_COLD=s3
+/// STORAGE_TIERED_HOT_DAYS=30 # optional
+#[derive(Debug, Clone)]
+pub enum StorageConfig {
+    S3(S3Storage),
+    Azure(AzureStorage),
+    Gcs(GcsStorage),
+    Tiered(TieredStorage),
+    Local,
+}
+
+/// Local disk for the chunks of the files used in the last `hot_days`,
+/// the remote `cold` provider for the rest of them.
+#[derive(Debug, Clone)]
+pub struct TieredStorage {
+    /// Remote provider, never the local or another tiered one
+    pub cold: Box<StorageConfig>,
+    pub hot_days: u64,
+}
+
+/// S3 compatible bucket the chunks are stored in, the URL has to be in the path
+/// style, with the bucket and optionally the prefix the chunks are stored under.
+#[derive(Debug, Clone)]
+pub struct S3Storage {
+    pub url: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl S3Storage {
+    fn new(vars: &mut Vars) -> Box<dyn FnOnce() -> Self> {
+        let url = vars.var::<String>("STORAGE_S3_URL");
+        let region = vars.var_default("STORAGE_S3_REGION", "us-east-1".to_string());
+        let access_key_id = vars.var::<String>("STORAGE_S3_ACCESS_KEY_ID");
+        let secret_access_key = vars.var::<String>("STORAGE_S3_SECRET_ACCESS_KEY");
+
+        Box::new(move || Self {
+            url: url.get().trim_end_matches('/').to_string(),
+            region: region.get(),
+            access_key_id: access_key_id.get(),
+            secret_access_key: secret_access_key.get(),
+        })
+    }
+}
+
+/// Azure Blob Storage container the chunks are stored in, the URL has the container
+/// and optionally the prefix the chunks are stored under.
+#[derive(Debug, Clone)]
+pub struct AzureStorage {
+    pub url: String,
+    pub auth: AzureAuth,
+}
+
+/// How the requests to the container are authorized
+#[derive(Debug, Clone)]
+pub enum AzureAuth {
+    /// Shared access signature appended to each of the requests
+    Sas(String),
+    /// Token of the managed identity of the machine the application runs on,
+    /// the client id selects the user assigned identity.
+    ManagedIdentity { client_id: Option<String> },
+}
+
+impl AzureStorage {
+    fn new(vars: &mut Vars) -> Box<dyn FnOnce() -> Self> {
+        let url = vars.var::<String>("STORAGE_AZURE_URL");
+        let sas_token = vars
+            .maybe_var::<String>("STORAGE_AZURE_SAS_TOKEN")
+            .maybe_get();
+        let client_id = vars
+            .maybe_var::<String>("STORAGE_AZURE_CLIENT_ID")
+            .maybe_get();
+
+        let auth = match sas_token {
+            Some(token) => AzureAuth::Sas(token.trim_start_matches('?').to_string()),
+            None => AzureAuth::ManagedIdentity { client_id },
+        };
+
+        Box::new(move || Self {
+            url: url.get().trim_end_matches('/').to_string(),
+            auth,
+        })
+    }
+}
+
+/// Google Cloud Storage bucket the chunks are stored in, the bucket name can be
+/// followed by the prefix the chunks are stored under.
+#[derive(Debug, Clone)]
+pub struct GcsStorage {
+    pub bucket: String,
+    /// Path to the JSON key of the service account
+    pub credentials: String,
+    /// Address of the storage API, only changed for the emulators
+    pub url: String,
+}
+
+impl GcsStorage {
+    fn new(vars: &mut Vars) -> Box<dyn FnOnce() -> Self> {
+        let bucket = vars.var::<String>("STORAGE_GCS_BUCKET");
+        let credentials = vars.var::<String>("STORAGE_GCS_CREDENTIALS");
+        let url = vars.var_default(
+            "STORAGE_GCS_URL",
+            "https://storage.googleapis.com".to_string(),
+        );
+
+        Box::new(move || Self {
+            bucket: bucket.get().trim_matches('/').to_string(),
+            credentials: credentials.get(),
+            url: url.get().trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl StorageConfig {
+    pub(crate) fn new(vars: &mut Vars) -> Self {
+        let provider = match vars.maybe_var::<String>("STORAGE_PROVIDER").maybe_get() {
+            Some(provider) => provider.trim().to_lowercase(),
+            None => Self::detect(vars).to_string(),
+        };
+
+        let storage: Box<dyn FnOnce() -> Self> = match provider.as_str() {
+            "fs" => Box::new(|| Self::Local),
+            "tiered" => {
+                let cold = vars
+                    .var_default("STORAGE_TIERED_COLD", "".to_string())
+                    .get();
+                let hot_days = vars.var_default("STORAGE_TIERED_HOT_DAYS", TIERED_HOT_DAYS);
+
+                match Self::remote(vars, &cold.trim().to_lowercase()) {
+                    Some(cold) => Box::new(move || {
+                        Self::Tiered(TieredStorage {
+                            cold: Box::new(cold()),
+                            hot_days: hot_days.get().max(1),
+                        })
+                    }),
+                    None => {
+                        vars.invalid("STORAGE_TIERED_COLD", &cold, &REMOTE_PROVIDERS);
+
+                        Box::new(|| Self::Local)
+                    }
+                }
+            }
+            provider => match Self::remote(vars, provider) {
+                Some(storage) => storage,
+                None => {
+                    vars.invalid("STORAGE_PROVIDER", provider, &PROVIDERS);
+
+                    Box::new(|| Self::Local)
+                }
+            },
+        };
+
+        vars.panic_if_errors("StorageConfig");
+
+        storage()
+    }
+
+    /// Remote provider with the given name, nothing if there is no such provider
+    fn remote(vars: &mut Vars, provider: &str) -> Option<Box<dyn FnOnce() -> Self>> {
+        match provider {
+            "s3" => {
+                let bucket = S3Storage::new(vars);
+
+                Some(Box::new(move || Self::S3(bucket())))
+            }
+            "azure" => {
+                let container = AzureStorage::new(vars);
+
+                Some(Box::new(move || Self::Azure(container())))
+            }
+            "gcs" => {
+                let bucket = GcsStorage::new(vars);
+
+                Some(Box::new(move || Self::Gcs(bucket())))
+            }
+            _ => None,
+        }
+    }
+
+    /// Provider of the setups from before the STORAGE_PROVIDER, the first one
+    /// whose section is set, in the order the providers were added in.
+    fn detect(vars: &mut Vars) -> &'static str {
+        let sections = [
+            ("STORAGE_S3_URL", "s3"),
+            ("STORAGE_AZURE_URL", "azure"),
+            ("STORAGE_GCS_BUCKET", "gcs"),
+        ];
+
+        for (name, provider) in sections {
+            if vars.maybe_var::<String>(name).maybe_get().is_some() {
+                return provider;
+            }
+        }
+
+        "fs"
+    }
+
+    /// Name of the provider, as it is set in the STORAGE_PROVIDER
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Self::S3(_) => "s3",
+            Self::Azure(_) => "azure",
+            Self::Gcs(_) => "gcs",
+            Self::Tiered(_) => "tiered",
+            Self::Local => "fs",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StorageConfig;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_provider_is_selected_by_name() {
+        std::env::set_var("STORAGE_S3_URL", "http://localhost:9000/bucket");
+        std::env::set_var("STORAGE_S3_ACCESS_KEY_ID", "key");
+        std::env::set_var("STORAGE_S3_SECRET_ACCESS_KEY", "secret");
+
+        let mut vars = Vars::create("test", "0.1.0", "test");
+        assert_eq!(StorageConfig::new(&mut vars).provider(), "s3");
+
+        std::env::set_var("STORAGE_PROVIDER", "fs");
+        let mut vars = Vars::create("test", "0.1.0", "test");
+        assert_eq!(StorageConfig::new(&mut vars).provider(), "fs");
+
+        std::env::set_var("STORAGE_PROVIDER", "GCS");
+        std::env::set_var("STORAGE_GCS_BUCKET", "bucket/hoodik");
+        std::env::set_var("STORAGE_GCS_CREDENTIALS", "/etc/hoodik/gcs.json");
+        let mut vars = Vars::create("test", "0.1.0", "test");
+
+        match StorageConfig::new(&mut vars) {
+            StorageConfig::Gcs(bucket) => assert_eq!(bucket.bucket, "bucket/hoodik"),
+            storage => panic!("expected gcs, got {}", storage.provider()),
+        }
+
+        std::env::set_var("STORAGE_PROVIDER", "tiered");
+        std::env::set_var("STORAGE_TIERED_COLD", "s3");
+        let mut vars = Vars::create("test", "0.1.0", "test");
+
+        match StorageConfig::new(&mut vars) {
+            StorageConfig::Tiered(tiered) => {
+                assert_eq!(tiered.cold.provider(), "s3");
+                assert_eq!(tiered.hot_days, 30);
+            }
+            storage => panic!("expected tiered, got {}", storage.provider()),
+        }
+    }
+}
@@ -181,6 +181,16 @@ impl Vars {
         Getter::<MaybeVar<T>>::fallible(None)
     }
 
+    /// Record the variable that is set to a value outside of the expected ones
+    pub(crate) fn invalid(&mut self, name: &str, value: &str, expected: &[&str]) {
+        self.errors.push(format!(
+            "{}: '{}' is not one of: {}",
+            name,
+            value,
+            expected.join(", ")
+        ));
+    }
+
     /// Maybe get variable from the env
     fn maybe_env_var<T: GetterType>(&mut self, name: &str) -> Getter<MaybeVar<T>> {
         let value = std::env::var(name);
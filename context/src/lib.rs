@@ -1,12 +1,14 @@
 use config::Config;
 use email::Sender;
 use error::AppResult;
+use notify::Notifier;
 use sea_orm::Database;
 
 /// Re-export the database connection type
 pub use sea_orm::DatabaseConnection;
 
 pub use email::contract::SenderContract;
+pub use notify::contract::NotifierContract;
 use settings::{factory::Factory, Settings};
 
 /// Holder of the application context
@@ -17,6 +19,7 @@ pub struct Context {
     pub config: Config,
     pub db: DatabaseConnection,
     pub sender: Option<Sender>,
+    pub notifier: Option<Notifier>,
     pub settings: Settings,
 }
 
@@ -44,6 +47,7 @@ impl Clone for Context {
                 }
             },
             sender: self.sender.clone(),
+            notifier: self.notifier.clone(),
             settings: self.settings.clone(),
         }
     }
@@ -59,6 +63,7 @@ impl Context {
         };
 
         let sender = email::Sender::new(&config)?;
+        let notifier = Notifier::new(&config);
 
         let settings = Settings::default().create(&config).await?;
 
@@ -66,6 +71,7 @@ impl Context {
             config,
             db,
             sender,
+            notifier,
             settings,
         })
     }
@@ -79,6 +85,7 @@ impl Context {
             config,
             db,
             sender: None,
+            notifier: None,
             settings,
         }
     }
@@ -92,6 +99,7 @@ impl Context {
             config,
             db: DatabaseConnection::Disconnected,
             sender: None,
+            notifier: None,
             settings,
         }
     }
@@ -114,6 +122,7 @@ impl Context {
             config,
             db,
             sender: None,
+            notifier: None,
             settings,
         };
 
@@ -139,6 +148,7 @@ impl Context {
             config,
             db,
             sender: None,
+            notifier: None,
             settings,
         };
 
@@ -156,6 +166,15 @@ impl Context {
         context
     }
 
+    #[cfg(feature = "mock")]
+    pub fn add_mock_notifier(mut context: Context) -> Context {
+        let notifier = Notifier::mock();
+
+        context.notifier = Some(notifier);
+
+        context
+    }
+
     #[cfg(feature = "mock")]
     pub fn is_disconnected(&self) -> bool {
         matches!(self.db, DatabaseConnection::Disconnected)
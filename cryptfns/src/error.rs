@@ -24,6 +24,8 @@ pub enum Error {
     AsconError(AsconError),
     RandomError(RandomError),
     TokenizersError(TokenizersError),
+    /// Version of the encryption scheme the server doesn't know about
+    UnsupportedScheme(i32),
 }
 
 impl std::fmt::Display for Error {
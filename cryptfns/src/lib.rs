@@ -4,6 +4,7 @@ pub mod chacha;
 pub mod crc;
 pub mod error;
 pub mod rsa;
+pub mod scheme;
 pub mod tokenizer;
 
 pub use hex;
@@ -0,0 +1,95 @@
+//! Versions of the scheme the file contents are encrypted with.
+//!
+//! Every file and user records the version it was created with, so the format can
+//! evolve without breaking the old files: the server keeps decrypting every version
+//! listed in [SUPPORTED] and new files are created with the [CURRENT] one.
+use crate::error::{CryptoResult, Error};
+
+/// Version new files and users are created with
+pub const CURRENT: i32 = 1;
+
+/// Versions the server is still able to work with
+pub const SUPPORTED: &[i32] = &[1];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    /// Chunks are encrypted with the AEAD cipher from the [crate::aes] module
+    V1,
+}
+
+impl Scheme {
+    /// Get the scheme for the version stored with the file
+    pub fn from_version(version: i32) -> CryptoResult<Self> {
+        match version {
+            1 => Ok(Self::V1),
+            _ => Err(Error::UnsupportedScheme(version)),
+        }
+    }
+
+    /// Scheme that is used for everything new
+    pub fn current() -> Self {
+        Self::V1
+    }
+
+    pub fn version(&self) -> i32 {
+        match self {
+            Self::V1 => 1,
+        }
+    }
+
+    /// Encrypt the chunk of the file with the given key
+    pub fn encrypt(&self, key: Vec<u8>, plaintext: Vec<u8>) -> CryptoResult<Vec<u8>> {
+        match self {
+            Self::V1 => crate::aes::encrypt(key, plaintext),
+        }
+    }
+
+    /// Decrypt the chunk of the file with the given key
+    pub fn decrypt(&self, key: Vec<u8>, ciphertext: Vec<u8>) -> CryptoResult<Vec<u8>> {
+        match self {
+            Self::V1 => crate::aes::decrypt(key, ciphertext),
+        }
+    }
+}
+
+/// Check if the server can work with the given version
+pub fn is_supported(version: i32) -> bool {
+    SUPPORTED.contains(&version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_every_supported_version_has_a_scheme() {
+        for version in SUPPORTED {
+            let scheme = Scheme::from_version(*version).unwrap();
+
+            assert_eq!(scheme.version(), *version);
+        }
+
+        assert!(is_supported(CURRENT));
+        assert_eq!(Scheme::current().version(), CURRENT);
+    }
+
+    #[test]
+    fn test_unknown_version_is_refused() {
+        assert!(!is_supported(0));
+        assert!(Scheme::from_version(0).is_err());
+        assert!(Scheme::from_version(CURRENT + 1).is_err());
+    }
+
+    #[test]
+    fn test_scheme_encrypt_and_decrypt() {
+        let plaintext = b"plaintext message".to_vec();
+        let key = b"very secret key.very secret key.".to_vec();
+        let scheme = Scheme::current();
+
+        let encrypted = scheme.encrypt(key.clone(), plaintext.clone()).unwrap();
+        let decrypted = scheme.decrypt(key.clone(), encrypted.clone()).unwrap();
+
+        assert_eq!(plaintext, decrypted);
+        assert_eq!(crate::aes::decrypt(key, encrypted).unwrap(), plaintext);
+    }
+}
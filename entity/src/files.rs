@@ -21,6 +21,26 @@ pub struct Model {
     pub file_modified_at: i64,
     pub created_at: i64,
     pub finished_upload_at: Option<i64>,
+    /// Hash of the file content calculated by the client, used by the sync clients
+    /// to find out if the local copy of the file is the same without downloading it.
+    pub sha256: Option<String>,
+    /// File this file is a conflicted copy of, set when a client uploaded different
+    /// content under a name that was already taken by another version of the file.
+    pub conflict_of: Option<Uuid>,
+    /// Name of the device (client) that uploaded the conflicted copy.
+    pub conflict_device: Option<String>,
+    /// File or folder is under the legal hold set by the admin, it cannot be deleted
+    /// and neither can anything inside of it until the hold is released.
+    pub legal_hold: bool,
+    /// Version of the scheme the content of the file is encrypted with,
+    /// see `cryptfns::scheme` for the versions the server understands.
+    pub crypto_version: i32,
+    /// Content of the virtual file (bookmark or note) encrypted with the file key,
+    /// virtual files have no chunks, this is all there is to them.
+    pub encrypted_payload: Option<String>,
+    /// File or folder was moved to the trash at this time, it is purged for good
+    /// once the trash retention passes unless it is restored before that.
+    pub deleted_at: Option<i64>,
 }
 
 impl IntoFilename for Model {
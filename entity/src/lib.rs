@@ -1,14 +1,45 @@
+pub mod access_keys;
+pub mod activities;
+pub mod app_passwords;
+pub mod audit_logs;
+pub mod blobs;
+pub mod escrow_keys;
+pub mod exports;
+pub mod external_exports;
+pub mod file_chunks;
+pub mod file_lifecycles;
+pub mod file_request_files;
+pub mod file_requests;
 pub mod file_tokens;
+pub mod file_verdicts;
 pub mod files;
+pub mod folder_policies;
+pub mod folder_templates;
+pub mod group_folders;
+pub mod group_members;
+pub mod groups;
+pub mod imports;
 pub mod invitations;
+pub mod jobs;
+pub mod link_files;
 pub mod links;
+pub mod message_reads;
+pub mod messages;
 pub mod paginated;
 pub mod prelude;
+pub mod rebalances;
+pub mod rewrap_entries;
+pub mod rewrap_jobs;
+pub mod s3_objects;
 pub mod sessions;
+pub mod spaces;
 pub mod tokens;
+pub mod transfers;
+pub mod tus_uploads;
 pub mod user_actions;
 pub mod user_files;
 pub mod users;
+pub mod versions;
 
 pub mod join;
 pub mod sort;
@@ -22,8 +53,8 @@ pub use sea_orm::{
     entity::prelude::Uuid,
     entity::{ActiveModelTrait, ColumnTrait, EntityTrait, RelationTrait},
     sea_query::{
-        Alias, Expr, IntoCondition, Query, SelectStatement, SimpleExpr, SubQueryOper,
-        SubQueryStatement, UnionType,
+        Alias, DynIden, Expr, IntoCondition, OnConflict, Query, SelectStatement, SimpleExpr,
+        SubQueryOper, SubQueryStatement, UnionType,
     },
     ActiveValue, Condition, ConnectionTrait, DbBackend, DbConn, DbErr, EntityOrSelect,
     FromQueryResult, Identity, JoinType, JsonValue, ModelTrait, Order, PaginatorTrait, QueryFilter,
@@ -46,10 +46,39 @@ pub struct Model {
     /// Date when the link was created
     pub created_at: i64,
 
+    /// Date when the file can be downloaded through the link,
+    /// it can be downloaded right away if not set.
+    pub starts_at: Option<i64>,
+
     /// Date when the link will expire, automated cron job
     /// will periodically empty out the expired links of all the
     /// file metadata and encrypted file key.
     pub expires_at: Option<i64>,
+
+    /// Text overlaid on the previews served through the link, `{ip}`, `{date}`
+    /// and `{link_id}` are replaced when the preview is served. No watermark if not set.
+    pub watermark: Option<String>,
+
+    /// Comma separated ISO codes of the countries the file can be downloaded from
+    /// through the link, from anywhere if not set.
+    pub allowed_countries: Option<String>,
+
+    /// Comma separated address ranges in the CIDR notation the file can be downloaded
+    /// from through the link, from any address if not set.
+    pub allowed_networks: Option<String>,
+
+    /// Hash of the password the link is protected with, the file can be downloaded
+    /// through the link only with the password. Not protected if not set.
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+
+    /// Number of times the file can be downloaded through the link, once the downloads
+    /// reach it the link is gone. The file can be downloaded any number of times if not set.
+    pub max_downloads: Option<i32>,
+
+    /// Short name the link can be opened by instead of its id, chosen by the
+    /// owner of the link and unique across all the links. No slug if not set.
+    pub slug: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -27,6 +27,8 @@ pub async fn create_user<T: super::ConnectionTrait>(
         email_verified_at: ActiveValue::Set(Some(Utc::now().timestamp())),
         created_at: ActiveValue::Set(Utc::now().timestamp()),
         updated_at: ActiveValue::Set(Utc::now().timestamp()),
+        crypto_version: ActiveValue::NotSet,
+        version_limit: ActiveValue::NotSet,
     };
 
     crate::users::Entity::insert(user)
@@ -75,6 +77,9 @@ pub async fn create_session<T: super::ConnectionTrait>(
         created_at: ActiveValue::Set((Utc::now().naive_utc() - Duration::minutes(5)).timestamp()),
         updated_at: ActiveValue::Set((Utc::now().naive_utc() - Duration::minutes(5)).timestamp()),
         expires_at: ActiveValue::Set(expires_at),
+        app_password_id: ActiveValue::Set(None),
+        unlocked_at: ActiveValue::Set(None),
+        lock_after: ActiveValue::Set(None),
     };
 
     super::sessions::Entity::insert(session)
@@ -119,6 +124,13 @@ pub async fn create_file<T: super::ConnectionTrait>(
         file_modified_at: ActiveValue::Set(Utc::now().timestamp()),
         created_at: ActiveValue::Set(Utc::now().timestamp()),
         finished_upload_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+        sha256: ActiveValue::NotSet,
+        conflict_of: ActiveValue::NotSet,
+        conflict_device: ActiveValue::NotSet,
+        legal_hold: ActiveValue::Set(false),
+        crypto_version: ActiveValue::NotSet,
+        encrypted_payload: ActiveValue::NotSet,
+        deleted_at: ActiveValue::NotSet,
     };
 
     crate::files::Entity::insert(file)
@@ -139,7 +151,10 @@ pub async fn create_file<T: super::ConnectionTrait>(
         is_owner: ActiveValue::Set(true),
         encrypted_key: ActiveValue::Set(name.to_string()),
         created_at: ActiveValue::Set(Utc::now().timestamp()),
+        starts_at: ActiveValue::NotSet,
         expires_at: ActiveValue::NotSet,
+        attributes: ActiveValue::NotSet,
+        permission: ActiveValue::NotSet,
     };
 
     user_files::Entity::insert(user_file)
@@ -156,6 +171,40 @@ pub async fn create_file<T: super::ConnectionTrait>(
     (file, user_file)
 }
 
+/// Share the file with the user, the way it is shared from the owner's client
+pub async fn create_share<T: super::ConnectionTrait>(
+    db: &T,
+    file_id: Uuid,
+    user: &User,
+    encrypted_key: &str,
+) -> UserFile {
+    let id = Uuid::new_v4();
+
+    let user_file = UserFileActiveModel {
+        id: ActiveValue::Set(id),
+        file_id: ActiveValue::Set(file_id),
+        user_id: ActiveValue::Set(user.id),
+        is_owner: ActiveValue::Set(false),
+        encrypted_key: ActiveValue::Set(encrypted_key.to_string()),
+        created_at: ActiveValue::Set(Utc::now().timestamp()),
+        starts_at: ActiveValue::NotSet,
+        expires_at: ActiveValue::NotSet,
+        attributes: ActiveValue::NotSet,
+        permission: ActiveValue::NotSet,
+    };
+
+    user_files::Entity::insert(user_file)
+        .exec_without_returning(db)
+        .await
+        .unwrap();
+
+    user_files::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
 /// Create invitation for the user in database
 pub async fn create_invitation<T: super::ConnectionTrait>(
     db: &T,
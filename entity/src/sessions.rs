@@ -24,6 +24,27 @@ pub struct Model {
     pub created_at: i64,
     pub updated_at: i64,
     pub expires_at: i64,
+    /// App password the session was started with, its scopes limit what the session can do.
+    pub app_password_id: Option<Uuid>,
+    /// Last time the session used the unlocked private key of the user, the key
+    /// itself never reaches the server. Nothing when the vault is locked.
+    pub unlocked_at: Option<i64>,
+    /// Seconds without the activity after which the vault of the session is locked
+    pub lock_after: Option<i64>,
+}
+
+impl Model {
+    /// When the vault of the session gets locked if nothing happens until then
+    pub fn locks_at(&self) -> Option<i64> {
+        Some(self.unlocked_at? + self.lock_after?)
+    }
+
+    /// The vault is unlocked and it wasn't idle for longer than the session allows
+    pub fn is_unlocked(&self, now: i64) -> bool {
+        self.locks_at()
+            .map(|locks_at| now < locks_at)
+            .unwrap_or(false)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -13,7 +13,43 @@ pub struct Model {
     pub encrypted_key: String,
     pub is_owner: bool,
     pub created_at: i64,
+    /// Date when the share gives the user access to the file, right away if not set
+    pub starts_at: Option<i64>,
     pub expires_at: Option<i64>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub attributes: Option<Json>,
+    /// What the user the file is shared with can do with it, the owner can do anything
+    pub permission: Permission,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Only reads the shared files.
+    #[default]
+    #[sea_orm(string_value = "read")]
+    Read,
+    /// Creates, uploads and changes the files in the shared folder.
+    #[sea_orm(string_value = "write")]
+    Write,
+    /// Changes the files and shares them further with other users.
+    #[sea_orm(string_value = "manage")]
+    Manage,
+}
+
+impl Permission {
+    /// User can change the content of the shared file or folder
+    pub fn can_write(&self) -> bool {
+        matches!(self, Self::Write | Self::Manage)
+    }
+
+    /// User can share the file or the folder with other users
+    pub fn can_manage(&self) -> bool {
+        matches!(self, Self::Manage)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
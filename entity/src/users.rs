@@ -28,6 +28,11 @@ pub struct Model {
     pub email_verified_at: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
+    /// Version of the encryption scheme the user's keys were created with
+    pub crypto_version: i32,
+    /// Number of the previous versions kept for each of the user's files,
+    /// without it the STORAGE_VERSIONS_MAX applies
+    pub version_limit: Option<i32>,
 }
 
 impl Model {
@@ -1,5 +1,8 @@
 use actix_multipart::MultipartError;
-use actix_web::{HttpResponse, HttpResponseBuilder, ResponseError};
+use actix_web::{
+    error::{JsonPayloadError, QueryPayloadError},
+    HttpRequest, HttpResponse, HttpResponseBuilder, ResponseError,
+};
 use base64::DecodeError;
 use cryptfns::error::Error as CryptoError;
 use glob::{GlobError, PatternError};
@@ -55,6 +58,9 @@ pub enum Error {
     HandlebarsRenderError(RenderError),
     HandlebarsTemplateError(TemplateError),
     TooManyRequests(String),
+    RequestTimeout(String),
+    Conflict(String),
+    Gone(String),
 }
 
 impl Error {
@@ -404,6 +410,21 @@ impl From<&Error> for ErrorResponse {
                 message: message.to_string(),
                 context: None,
             },
+            Error::RequestTimeout(message) => ErrorResponse {
+                status: 408,
+                message: message.to_string(),
+                context: None,
+            },
+            Error::Conflict(message) => ErrorResponse {
+                status: 409,
+                message: message.to_string(),
+                context: None,
+            },
+            Error::Gone(message) => ErrorResponse {
+                status: 410,
+                message: message.to_string(),
+                context: None,
+            },
         }
     }
 }
@@ -416,3 +437,22 @@ impl ResponseError for Error {
             .json(payload)
     }
 }
+
+/// Body of the request that can't be read into the expected data is answered
+/// the same way as the data that doesn't pass the validation
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::Deserialize(err) => Error::as_validation("body", &err.to_string()),
+        err => Error::BadRequest(err.to_string()),
+    }
+    .into()
+}
+
+/// Same as [json_error_handler], for the query of the request
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match err {
+        QueryPayloadError::Deserialize(err) => Error::as_validation("query", &err.to_string()),
+        err => Error::BadRequest(err.to_string()),
+    }
+    .into()
+}
@@ -0,0 +1,75 @@
+//! # Content addressed storage
+//!
+//! With the STORAGE_CONTENT_ADDRESSED the chunks are stored only once under the hash
+//! of their content (the blobs), the files are only the lists of the blobs their chunks
+//! are made of. The lists and the number of the references to each blob are kept by the
+//! [ChunkIndex] the application registers at the start, the storage provider only keeps
+//! the content of the blobs.
+//!
+//! Files whose chunks were stored before it was enabled are not in the index, their
+//! chunks are still read from and written to the storage provider the same as before.
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use error::{AppResult, Error};
+use futures_util::future::BoxFuture;
+
+static INDEX: OnceLock<Arc<dyn ChunkIndex>> = OnceLock::new();
+
+/// Blob the chunk of the file points to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkBlob {
+    pub chunk: i64,
+    /// SHA256 of the content, the blob is stored under it
+    pub hash: String,
+    /// Size of the content in bytes
+    pub size: u64,
+}
+
+/// Lists of the blobs the files are made of, the files are identified with the same
+/// names their chunks would have in the storage provider.
+#[async_trait]
+pub trait ChunkIndex: Send + Sync {
+    /// Blobs of the file ordered by the chunk number, empty if the file is not in the index
+    async fn chunks(&self, name: &str) -> AppResult<Vec<ChunkBlob>>;
+
+    /// Point the chunk of the file to the blob, the blob is added with its first reference
+    /// if it is not there. Returns the hashes of the blobs nothing points to anymore.
+    async fn insert(&self, name: &str, blob: &ChunkBlob) -> AppResult<Vec<String>>;
+
+    /// Point the chunks of the `to` file to the same blobs as the chunks of the
+    /// `from` file, returns the chunks that were linked.
+    async fn link(&self, from: &str, to: &str) -> AppResult<Vec<i64>>;
+
+    /// Remove the chunks of the file, returns the hashes of the blobs nothing points to anymore
+    async fn remove(&self, name: &str) -> AppResult<Vec<String>>;
+
+    /// Take the reference to the blob before its content is written, so it can't be purged
+    /// meanwhile, the blob is added if it is not there. The reference is given back with
+    /// [ChunkIndex::dereference] once the chunk points to the blob.
+    async fn reference(&self, blob: &ChunkBlob) -> AppResult<()>;
+
+    /// Give back the references taken with [ChunkIndex::reference], returns the hashes
+    /// of the blobs nothing points to anymore.
+    async fn dereference(&self, hashes: Vec<String>) -> AppResult<Vec<String>>;
+
+    /// Forget the blob nothing points to, its content is purged while the blob is still
+    /// locked in the index, so no new reference can be taken before the content is gone.
+    /// Returns false without purging when the blob was referenced again in the meantime.
+    async fn forget(&self, hash: &str, purge: BoxFuture<'_, AppResult<()>>) -> AppResult<bool>;
+}
+
+/// Set the index the content addressed storage uses, it can only be set once
+pub fn register<T: ChunkIndex + 'static>(index: T) {
+    if INDEX.set(Arc::new(index)).is_err() {
+        log::warn!("Chunk index is already registered");
+    }
+}
+
+/// Index registered by the application
+pub(crate) fn index() -> AppResult<Arc<dyn ChunkIndex>> {
+    INDEX
+        .get()
+        .cloned()
+        .ok_or_else(|| Error::StorageError("chunk_index_not_registered".to_string()))
+}
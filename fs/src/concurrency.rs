@@ -0,0 +1,21 @@
+//! # Storage I/O concurrency
+//!
+//! Every running storage operation holds a chunk in memory, so the number of them
+//! running at once is limited across the whole application to the configured
+//! `STORAGE_IO_CONCURRENCY`. The limit is set the first time the [crate::prelude::Fs] is created.
+use std::sync::OnceLock;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+static PERMITS: OnceLock<Semaphore> = OnceLock::new();
+
+/// Set the limit of concurrent operations, it can only be set once
+pub(crate) fn init(limit: usize) {
+    PERMITS.get_or_init(|| Semaphore::new(limit.max(1)));
+}
+
+/// Wait for the permit to run one storage operation, the permit is released when dropped.
+/// Without the limit set (providers used directly in tests) there is no waiting.
+pub(crate) async fn permit() -> Option<SemaphorePermit<'static>> {
+    PERMITS.get()?.acquire().await.ok()
+}
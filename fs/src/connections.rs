@@ -0,0 +1,143 @@
+//! # Connections to the remote storages
+//!
+//! Each remote storage keeps its client for the whole time the application runs, so the
+//! connections to the storage are kept open and reused by the chunk operations instead
+//! of connecting for every one of them. At most `STORAGE_POOL_MAX_IDLE` idle connections
+//! are kept for each host, they are closed after `STORAGE_POOL_IDLE_TIMEOUT` seconds.
+//!
+//! Connections that went bad are evicted, when the requests keep failing to connect
+//! or time out one after another the client is replaced with a new one, which drops
+//! all the connections the old one had pooled.
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        OnceLock, RwLock,
+    },
+    time::Duration,
+};
+
+use error::AppResult;
+use reqwest::{Client, IntoUrl, Method, RequestBuilder, Response};
+
+/// Failed requests in a row after which the pooled connections are dropped
+const EVICT_AFTER_FAILURES: u32 = 3;
+
+/// Seconds to wait for the connection to the storage to open
+const CONNECT_TIMEOUT: u64 = 10;
+
+/// Seconds between the keepalive probes of the open connections
+const TCP_KEEPALIVE: u64 = 30;
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    max_idle: usize,
+    idle_timeout: u64,
+}
+
+/// Set the limits of the pooled connections, they can only be set once. Without
+/// them (providers used directly in tests) the defaults of the client are used.
+pub(crate) fn init(max_idle: usize, idle_timeout: u64) {
+    SETTINGS.get_or_init(|| Settings {
+        max_idle,
+        idle_timeout,
+    });
+}
+
+/// Client of the remote storage with its pooled connections
+pub(crate) struct ClientPool {
+    client: RwLock<Client>,
+    failures: AtomicU32,
+}
+
+impl ClientPool {
+    pub(crate) fn new() -> Self {
+        Self {
+            client: RwLock::new(build()),
+            failures: AtomicU32::new(0),
+        }
+    }
+
+    /// Start the request with the current client, its connections are reused
+    pub(crate) fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        self.client().request(method, url)
+    }
+
+    /// Send the request and keep track of the health of the connections
+    pub(crate) async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        match request.send().await {
+            Ok(response) => {
+                self.failures.store(0, Ordering::Relaxed);
+
+                Ok(response)
+            }
+            Err(e) => {
+                if e.is_connect() || e.is_timeout() {
+                    self.failed();
+                }
+
+                Err(e.into())
+            }
+        }
+    }
+
+    fn client(&self) -> Client {
+        match self.client.read() {
+            Ok(client) => client.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Replace the client once there were too many failures in a row
+    fn failed(&self) {
+        if self.failures.fetch_add(1, Ordering::Relaxed) + 1 < EVICT_AFTER_FAILURES {
+            return;
+        }
+
+        self.failures.store(0, Ordering::Relaxed);
+
+        if let Ok(mut client) = self.client.write() {
+            log::warn!("Remote storage keeps failing, dropping its pooled connections");
+            *client = build();
+        }
+    }
+}
+
+/// Client with the configured pool of the connections
+fn build() -> Client {
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT))
+        .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE));
+
+    if let Some(settings) = SETTINGS.get() {
+        builder = builder
+            .pool_max_idle_per_host(settings.max_idle)
+            .pool_idle_timeout(Duration::from_secs(settings.idle_timeout));
+    }
+
+    builder.build().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::Ordering;
+
+    use super::{ClientPool, EVICT_AFTER_FAILURES};
+
+    #[test]
+    fn test_client_is_replaced_after_failures_in_a_row() {
+        let pool = ClientPool::new();
+
+        for _ in 0..EVICT_AFTER_FAILURES - 1 {
+            pool.failed();
+        }
+        assert_eq!(
+            pool.failures.load(Ordering::Relaxed),
+            EVICT_AFTER_FAILURES - 1
+        );
+
+        pool.failed();
+        assert_eq!(pool.failures.load(Ordering::Relaxed), 0);
+    }
+}
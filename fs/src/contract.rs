@@ -1,4 +1,7 @@
-use crate::{filename::IntoFilename, streamer::Streamer};
+use crate::{
+    filename::IntoFilename,
+    streamer::{FilesStream, Streamer},
+};
 use error::AppResult;
 
 use async_trait::async_trait;
@@ -33,14 +36,49 @@ pub trait FsProviderContract {
     /// Purge all the parts for a file from the storage provider.
     async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()>;
 
+    /// Make all the parts of the `from` file available under the `to` file without
+    /// transferring the data again, returns the chunks that were linked.
+    async fn link<T: IntoFilename, U: IntoFilename>(&self, from: &T, to: &U)
+        -> AppResult<Vec<i64>>;
+
     /// Get a vector of chunk indexes that were already uploaded so we can resume
     /// the upload process on the frontend without doing the double work.
     async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>>;
 
+    /// Same as `get_uploaded_chunks`, but for many files at once with a single listing
+    /// of the storage, the result has the same order as the given filenames.
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>>;
+
     /// Return stream of either one file chunk, or all chunks if no file chunk is specified.
+    /// The content is streamed in pieces that don't have to follow the chunks.
     async fn stream<T: IntoFilename>(
         &self,
         filename: &T,
         chunk: Option<i64>,
     ) -> AppResult<Streamer>;
+
+    /// Total size in bytes of either one file chunk, or all the chunks if no chunk is specified.
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64>;
+
+    /// Same as `stream`, but starts at the `start` byte and stops after the `end` byte (inclusive).
+    /// Chunks that are entirely before the `start` are never opened or read, so resuming
+    /// a dropped download costs the same no matter how far the client got.
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer>;
+
+    /// Stream all the chunks of many files one after another, in order. While one chunk
+    /// is being sent out, up to `read_ahead` of the next chunks are already being read.
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream>;
 }
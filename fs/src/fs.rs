@@ -1,11 +1,20 @@
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use tokio::fs::File;
 
 use config::Config;
-use error::AppResult;
+use error::{AppResult, Error};
 
 use crate::{
-    contract::FsProviderContract, filename::IntoFilename, providers::fs, streamer::Streamer,
+    concurrency, connections,
+    contract::FsProviderContract,
+    filename::IntoFilename,
+    providers::{
+        fs,
+        retry::{self, RetryPolicy},
+        Provider,
+    },
+    streamer::{FilesStream, Streamer},
 };
 
 pub struct Fs<'ctx> {
@@ -14,6 +23,15 @@ pub struct Fs<'ctx> {
 
 impl<'ctx> Fs<'ctx> {
     pub fn new(config: &'ctx Config) -> Self {
+        concurrency::init(config.server.io_concurrency);
+        connections::init(config.server.pool_max_idle, config.server.pool_idle_timeout);
+        retry::init(RetryPolicy {
+            attempts: config.server.retry_attempts,
+            base_delay: config.server.retry_base_delay,
+            max_delay: config.server.retry_max_delay,
+            budget: config.server.retry_budget,
+        });
+
         Self { config }
     }
 
@@ -39,56 +57,177 @@ impl<'ctx> Fs<'ctx> {
 
     /// Default storage provider for rw operations on either local, or any
     /// other provider that the application configuration specifies.
-    fn provider<'provider>(&self) -> impl FsProviderContract + 'provider
+    fn provider<'provider>(&self) -> AppResult<Provider<'provider>>
     where
         'ctx: 'provider,
     {
-        // TODO: Use the config to decide which provider we will be using
-        // for file storage. Once S3 is implemented...
-        self.local_in(&self.config.app.data_dir)
+        Provider::new(self.config)
+    }
+
+    /// Directories the chunks of the default provider are spread across,
+    /// the DATA_DIR is always the first one, followed by the SHARD_DIRS.
+    pub fn shard_dirs(&self) -> Vec<String> {
+        std::iter::once(self.config.app.data_dir.clone())
+            .chain(self.config.app.shard_dirs.iter().cloned())
+            .collect()
+    }
+
+    /// Provider every chunk is mirrored to, if the replica is configured
+    pub fn replica<'provider>(&self) -> Option<impl FsProviderContract + 'provider>
+    where
+        'ctx: 'provider,
+    {
+        self.config
+            .app
+            .replica_dir
+            .as_deref()
+            .map(|replica_dir| self.local_in(replica_dir))
+    }
+
+    /// Find the chunks that can't be served from the default provider but are
+    /// intact in the replica, those are missing or have a different size than
+    /// their replica. Without the replica there is nothing to compare with.
+    pub async fn damaged<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunks: &[i64],
+    ) -> AppResult<Vec<i64>> {
+        let replica = match self.replica() {
+            Some(replica) => replica,
+            None => return Ok(vec![]),
+        };
+
+        let mut damaged = vec![];
+
+        for chunk in chunks {
+            let expected = match replica.size(filename, Some(*chunk)).await {
+                Ok(size) => size,
+                Err(_) => continue,
+            };
+
+            let size = self.provider()?.size(filename, Some(*chunk)).await.ok();
+
+            if size != Some(expected) {
+                damaged.push(*chunk);
+            }
+        }
+
+        Ok(damaged)
+    }
+
+    /// Copy the chunk from the replica back to the default provider
+    pub async fn restore<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<()> {
+        let replica = self
+            .replica()
+            .ok_or_else(|| Error::StorageError("replica_not_configured".to_string()))?;
+
+        let data = replica.pull(filename, chunk).await?;
+
+        self.provider()?.push(filename, chunk, &data).await
+    }
+
+    /// Stream the whole chunks of the file one by one, for the consumers that decrypt
+    /// the content chunk by chunk. The pieces [FsProviderContract::stream] yields don't
+    /// follow the chunks, so they can't be decrypted on their own.
+    pub async fn stream_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Streamer> {
+        let stream = self
+            .stream_files(std::slice::from_ref(filename), 1)
+            .await?
+            .map(|item| item.map(|(_, data)| data));
+
+        Ok(Streamer::new(stream))
+    }
+
+    /// Move the chunks of the file from the local disk to the remote storage when the
+    /// storage is tiered, returns the chunks that were moved. Other storages keep every
+    /// chunk in the same place, so nothing is moved.
+    pub async fn demote<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
+        match self.provider()? {
+            Provider::Tiered(provider) => provider.demote(filename).await,
+            _ => Ok(vec![]),
+        }
     }
 }
 
 #[async_trait]
 impl<'ctx> FsProviderContract for Fs<'ctx> {
     async fn read<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<u8>> {
-        self.provider().read(filename).await
+        self.provider()?.read(filename).await
     }
 
     async fn write<T: IntoFilename>(&self, filename: &T, data: &[u8]) -> AppResult<()> {
-        self.provider().write(filename, data).await
+        self.provider()?.write(filename, data).await?;
+
+        if let Some(replica) = self.replica() {
+            replica.write(filename, data).await?;
+        }
+
+        Ok(())
     }
 
     async fn available_space(&self) -> AppResult<u64> {
-        self.provider().available_space().await
+        self.provider()?.available_space().await
     }
 
     async fn exists<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<bool> {
-        self.provider().exists(filename, chunk).await
+        self.provider()?.exists(filename, chunk).await
     }
 
     async fn get<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<File> {
-        self.provider().get(filename, chunk).await
+        self.provider()?.get(filename, chunk).await
     }
 
     async fn all<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<File>> {
-        self.provider().all(filename).await
+        self.provider()?.all(filename).await
     }
 
     async fn push<T: IntoFilename>(&self, filename: &T, chunk: i64, data: &[u8]) -> AppResult<()> {
-        self.provider().push(filename, chunk, data).await
+        self.provider()?.push(filename, chunk, data).await?;
+
+        if let Some(replica) = self.replica() {
+            replica.push(filename, chunk, data).await?;
+        }
+
+        Ok(())
     }
 
     async fn pull<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<Vec<u8>> {
-        self.provider().pull(filename, chunk).await
+        self.provider()?.pull(filename, chunk).await
     }
 
     async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()> {
-        self.provider().purge(filename).await
+        self.provider()?.purge(filename).await?;
+
+        if let Some(replica) = self.replica() {
+            replica.purge(filename).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn link<T: IntoFilename, U: IntoFilename>(
+        &self,
+        from: &T,
+        to: &U,
+    ) -> AppResult<Vec<i64>> {
+        let chunks = self.provider()?.link(from, to).await?;
+
+        if let Some(replica) = self.replica() {
+            replica.link(from, to).await?;
+        }
+
+        Ok(chunks)
     }
 
     async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
-        self.provider().get_uploaded_chunks(filename).await
+        self.provider()?.get_uploaded_chunks(filename).await
+    }
+
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>> {
+        self.provider()?.get_uploaded_chunks_many(filenames).await
     }
 
     async fn stream<T: IntoFilename>(
@@ -96,6 +235,30 @@ impl<'ctx> FsProviderContract for Fs<'ctx> {
         filename: &T,
         chunk: Option<i64>,
     ) -> AppResult<Streamer> {
-        self.provider().stream(filename, chunk).await
+        self.provider()?.stream(filename, chunk).await
+    }
+
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64> {
+        self.provider()?.size(filename, chunk).await
+    }
+
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer> {
+        self.provider()?
+            .stream_range(filename, chunk, start, end)
+            .await
+    }
+
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream> {
+        self.provider()?.stream_files(filenames, read_ahead).await
     }
 }
@@ -1,8 +1,16 @@
+pub mod cas;
+mod concurrency;
+mod connections;
 mod contract;
 mod filename;
 mod fs;
+mod partial;
+pub mod pool;
 mod providers;
+pub mod s3;
+pub mod shards;
 mod streamer;
+pub mod watchdog;
 
 pub use filename::IntoFilename;
 
@@ -15,5 +23,5 @@ pub mod prelude {
     pub use super::contract::FsProviderContract;
     pub use super::filename::{Filename, IntoFilename};
     pub use super::fs::Fs;
-    pub use super::streamer::Streamer;
+    pub use super::streamer::{FilesStream, Streamer};
 }
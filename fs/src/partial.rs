@@ -0,0 +1,116 @@
+//! # Partial writes
+//!
+//! Operations of the providers run for as long as the request that started them, when the
+//! client goes away the request is dropped and the operation stops wherever it was. Data is
+//! therefore never written straight into the chunk, it is written into the partial file next
+//! to it first and moved into its place once it is complete. A partial file that is dropped
+//! before that is removed, so an aborted upload leaves neither a half written chunk that would
+//! count as uploaded nor a leftover file behind.
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::Utc;
+use error::AppResult;
+use tokio::fs::{rename, File};
+
+/// Tells apart the partial files created in the same nanosecond
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// File that is removed when dropped, unless it was persisted
+pub(crate) struct Partial {
+    path: PathBuf,
+    done: bool,
+}
+
+impl Partial {
+    /// Partial file for the given target, it is hidden in the same directory so the chunk
+    /// listings don't pick it up and it can be renamed into the target without copying.
+    pub(crate) async fn create(target: &str) -> AppResult<(Self, File)> {
+        let target = Path::new(target);
+        let name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let path = target.with_file_name(format!(".{}.{}.partial", name, unique()));
+
+        Self::open(path).await
+    }
+
+    /// Temporary file with the given name, it is gone once the guard is dropped
+    pub(crate) async fn temporary(name: &str) -> AppResult<(Self, File)> {
+        let path =
+            std::env::temp_dir().join(format!("hoodik-{}-{}", unique(), name.replace('/', "_")));
+
+        Self::open(path).await
+    }
+
+    async fn open(path: PathBuf) -> AppResult<(Self, File)> {
+        let file = File::create(&path).await?;
+
+        Ok((Self { path, done: false }, file))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Move the complete file into its place
+    pub(crate) async fn persist(mut self, target: &str) -> AppResult<()> {
+        rename(&self.path, target).await?;
+        self.done = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for Partial {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn unique() -> String {
+    format!(
+        "{}-{}",
+        Utc::now().timestamp_nanos(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::AsyncWriteExt;
+
+    use super::Partial;
+
+    #[tokio::test]
+    async fn test_dropped_partial_file_is_removed() {
+        let dir = std::env::temp_dir().join(format!("hoodik-partial-{}", super::unique()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("chunk.part.0").to_string_lossy().to_string();
+
+        let (partial, mut file) = Partial::create(&target).await.unwrap();
+        file.write_all(b"half of the").await.unwrap();
+        let path = partial.path().to_path_buf();
+        assert!(path.exists());
+
+        drop(partial);
+        assert!(!path.exists());
+        assert!(!std::path::Path::new(&target).exists());
+
+        let (partial, mut file) = Partial::create(&target).await.unwrap();
+        file.write_all(b"whole chunk").await.unwrap();
+        file.flush().await.unwrap();
+        partial.persist(&target).await.unwrap();
+
+        assert_eq!(std::fs::read(&target).unwrap(), b"whole chunk");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
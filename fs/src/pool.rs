@@ -0,0 +1,128 @@
+//! # Buffer pool
+//!
+//! Uploads and downloads move whole chunks (up to [crate::MAX_CHUNK_SIZE_BYTES]) through memory,
+//! allocating a fresh buffer for each of them puts a lot of pressure on the allocator
+//! when there are many transfers running at once. Buffers taken from the pool are returned
+//! to it when dropped, so the same allocations are reused between requests.
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Mutex, OnceLock},
+};
+
+use actix_web::web::BytesMut;
+
+use crate::MAX_CHUNK_SIZE_BYTES;
+
+/// How many idle buffers the shared pool keeps around, anything above
+/// is freed so the idle memory usage stays low on small machines.
+const MAX_IDLE_BUFFERS: usize = 8;
+
+/// Pool of reusable byte buffers
+pub struct BufferPool {
+    buffers: Mutex<Vec<BytesMut>>,
+    buffer_size: usize,
+    max_idle: usize,
+}
+
+impl BufferPool {
+    pub fn new(buffer_size: usize, max_idle: usize) -> Self {
+        Self {
+            buffers: Mutex::new(vec![]),
+            buffer_size,
+            max_idle,
+        }
+    }
+
+    /// Take an empty buffer with at least `buffer_size` capacity from the pool
+    pub fn get(&self) -> PooledBuffer<'_> {
+        let buffer = self.buffers.lock().ok().and_then(|mut b| b.pop());
+
+        let mut buffer = buffer.unwrap_or_default();
+        buffer.clear();
+
+        // Bytes frozen from this buffer earlier have been dropped by now in most cases,
+        // in which case reserving reclaims the original allocation instead of allocating again.
+        buffer.reserve(self.buffer_size);
+
+        PooledBuffer {
+            pool: self,
+            inner: Some(buffer),
+        }
+    }
+
+    /// Number of idle buffers waiting in the pool
+    pub fn idle(&self) -> usize {
+        self.buffers.lock().map(|b| b.len()).unwrap_or_default()
+    }
+
+    fn put(&self, buffer: BytesMut) {
+        if let Ok(mut buffers) = self.buffers.lock() {
+            if buffers.len() < self.max_idle {
+                buffers.push(buffer);
+            }
+        }
+    }
+}
+
+/// Shared pool sized for the file chunks
+pub fn chunks() -> &'static BufferPool {
+    static POOL: OnceLock<BufferPool> = OnceLock::new();
+
+    POOL.get_or_init(|| BufferPool::new(MAX_CHUNK_SIZE_BYTES as usize, MAX_IDLE_BUFFERS))
+}
+
+/// Buffer that goes back to its pool when dropped
+pub struct PooledBuffer<'pool> {
+    pool: &'pool BufferPool,
+    inner: Option<BytesMut>,
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.inner.take() {
+            self.pool.put(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BufferPool;
+
+    #[test]
+    fn test_buffers_are_returned_and_reused() {
+        let pool = BufferPool::new(1024, 1);
+
+        let mut first = pool.get();
+        first.extend_from_slice(b"hello");
+        let frozen = first.split().freeze();
+
+        let second = pool.get();
+        assert!(second.capacity() >= 1024);
+
+        drop(first);
+        drop(second);
+        assert_eq!(pool.idle(), 1);
+
+        drop(frozen);
+
+        let buffer = pool.get();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 1024);
+        assert_eq!(pool.idle(), 0);
+    }
+}
@@ -0,0 +1,381 @@
+//! # Azure Blob storage
+//!
+//! Keeps the chunks as the block blobs in the Azure Blob Storage container. Requests are
+//! authorized either with the shared access signature (SAS) or with the token of the
+//! managed identity of the machine, taken from the instance metadata service.
+use std::sync::{Arc, OnceLock};
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use chrono::Utc;
+use config::storage::{AzureAuth, AzureStorage};
+use error::{AppResult, Error};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use super::remote::ObjectStore;
+use crate::{
+    connections::ClientPool,
+    s3::{encode_path, xml_text, xml_texts},
+};
+
+/// Version of the Blob service REST API the requests are made with
+const API_VERSION: &str = "2021-08-06";
+
+/// Most of the blobs Azure lists in a single page
+const LIST_PAGE_SIZE: &str = "5000";
+
+/// Instance metadata service endpoint handing out the managed identity tokens
+const IDENTITY_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+
+/// Token is refreshed this many seconds before it expires
+const TOKEN_REFRESH_MARGIN: i64 = 5 * 60;
+
+static CONTAINER: OnceLock<Arc<AzureContainer>> = OnceLock::new();
+
+pub(crate) struct AzureContainer {
+    client: ClientPool,
+    /// URL of the container itself
+    container: Url,
+    /// Prefix the chunks are stored under in the container, empty or ending with a slash
+    prefix: String,
+    auth: AzureAuth,
+    /// Managed identity token with the time it expires at
+    token: Mutex<Option<(String, i64)>>,
+}
+
+#[derive(Deserialize)]
+struct IdentityToken {
+    access_token: String,
+    expires_on: String,
+}
+
+impl AzureContainer {
+    pub(crate) fn new(storage: &AzureStorage) -> AppResult<Self> {
+        let mut container = Url::parse(&storage.url)
+            .map_err(|_| Error::StorageError("invalid_azure_url".to_string()))?;
+        container.set_query(None);
+
+        let path = container.path().trim_matches('/').to_string();
+        let (name, prefix) = match path.split_once('/') {
+            Some((name, prefix)) => (name.to_string(), format!("{}/", prefix)),
+            None => (path, String::new()),
+        };
+
+        if name.is_empty() {
+            return Err(Error::StorageError("azure_container_missing".to_string()));
+        }
+
+        container.set_path(&format!("/{}", name));
+
+        Ok(Self {
+            client: ClientPool::new(),
+            container,
+            prefix,
+            auth: storage.auth.clone(),
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Container is created once, so the connections and the token are reused
+    pub(crate) fn shared(storage: &AzureStorage) -> AppResult<Arc<Self>> {
+        if let Some(container) = CONTAINER.get() {
+            return Ok(container.clone());
+        }
+
+        let container = Arc::new(Self::new(storage)?);
+
+        Ok(CONTAINER.get_or_init(|| container).clone())
+    }
+
+    /// URL of the blob with the given key, without the key the URL of the container
+    fn url(&self, key: Option<&str>, query: &[(&str, &str)]) -> Url {
+        let mut url = self.container.clone();
+
+        if let Some(key) = key {
+            url.set_path(&format!("{}/{}", self.container.path(), self.blob(key)));
+        }
+
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        if let AzureAuth::Sas(token) = &self.auth {
+            let query = match url.query() {
+                Some(query) => format!("{}&{}", query, token),
+                None => token.clone(),
+            };
+
+            url.set_query(Some(&query));
+        }
+
+        url
+    }
+
+    /// Path of the blob in the container, encoded for the URL
+    fn blob(&self, key: &str) -> String {
+        encode_path(&format!("{}{}", self.prefix, key))
+    }
+
+    /// Authorized request for the blob with the given key, without the key
+    /// the request is for the container itself.
+    async fn request(
+        &self,
+        method: Method,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+    ) -> AppResult<RequestBuilder> {
+        let request = self
+            .client
+            .request(method, self.url(key, query))
+            .header("x-ms-version", API_VERSION)
+            .header(
+                "x-ms-date",
+                Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            );
+
+        Ok(match self.bearer().await? {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
+    }
+
+    /// Token of the managed identity, nothing when the SAS is used
+    async fn bearer(&self) -> AppResult<Option<String>> {
+        let client_id = match &self.auth {
+            AzureAuth::Sas(_) => return Ok(None),
+            AzureAuth::ManagedIdentity { client_id } => client_id,
+        };
+
+        let mut token = self.token.lock().await;
+        let now = Utc::now().timestamp();
+
+        if let Some((token, expires_at)) = token.as_ref() {
+            if *expires_at - TOKEN_REFRESH_MARGIN > now {
+                return Ok(Some(token.clone()));
+            }
+        }
+
+        let mut query = vec![
+            ("api-version", "2018-02-01"),
+            ("resource", "https://storage.azure.com/"),
+        ];
+
+        if let Some(client_id) = client_id.as_deref() {
+            query.push(("client_id", client_id));
+        }
+
+        let request = self
+            .client
+            .request(Method::GET, IDENTITY_ENDPOINT)
+            .query(&query)
+            .header("Metadata", "true");
+        let identity = self
+            .client
+            .send(request)
+            .await?
+            .error_for_status()?
+            .json::<IdentityToken>()
+            .await?;
+
+        let expires_at = identity.expires_on.parse().unwrap_or(now);
+        *token = Some((identity.access_token.clone(), expires_at));
+
+        Ok(Some(identity.access_token))
+    }
+}
+
+/// Azure explains what went wrong in the body, or in the header for the HEAD requests
+async fn checked(response: Response) -> AppResult<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status().as_u16();
+    let header = response
+        .headers()
+        .get("x-ms-error-code")
+        .and_then(|code| code.to_str().ok())
+        .map(|code| code.to_string());
+    let body = response.text().await.unwrap_or_default();
+    let code = header
+        .or_else(|| xml_text(&body, "Code"))
+        .unwrap_or_default();
+
+    Err(Error::StorageError(format!(
+        "azure_status:{}:{}",
+        status, code
+    )))
+}
+
+#[async_trait]
+impl ObjectStore for AzureContainer {
+    async fn get(&self, key: &str, range: Option<(u64, u64)>) -> AppResult<Response> {
+        let mut request = self.request(Method::GET, Some(key), &[]).await?;
+
+        if let Some((start, end)) = range {
+            request = request.header("x-ms-range", format!("bytes={}-{}", start, end));
+        }
+
+        checked(self.client.send(request).await?).await
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> AppResult<()> {
+        let request = self
+            .request(Method::PUT, Some(key), &[])
+            .await?
+            .header("x-ms-blob-type", "BlockBlob")
+            .body(data);
+        let response = self.client.send(request).await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        let request = self.request(Method::HEAD, Some(key), &[]).await?;
+        let response = self.client.send(request).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let size = checked(response)
+            .await?
+            .headers()
+            .get("Content-Length")
+            .and_then(|size| size.to_str().ok())
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(0);
+
+        Ok(Some(size))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        let request = self.request(Method::DELETE, Some(key), &[]).await?;
+        let response = self.client.send(request).await?;
+
+        // Blob that is already gone is what we wanted
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    /// Put Blob From URL copies the blob synchronously, unlike the Copy Blob
+    async fn copy(&self, from: &str, to: &str) -> AppResult<()> {
+        let mut request = self
+            .request(Method::PUT, Some(to), &[])
+            .await?
+            .header("x-ms-blob-type", "BlockBlob")
+            .header("x-ms-copy-source", self.url(Some(from), &[]).to_string());
+
+        if let Some(token) = self.bearer().await? {
+            request = request.header(
+                "x-ms-copy-source-authorization",
+                format!("Bearer {}", token),
+            );
+        }
+
+        checked(self.client.send(request).await?).await?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> AppResult<Vec<(String, u64)>> {
+        let prefix = format!("{}{}", self.prefix, prefix);
+        let mut blobs = vec![];
+        let mut marker: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("restype", "container"),
+                ("comp", "list"),
+                ("maxresults", LIST_PAGE_SIZE),
+                ("prefix", prefix.as_str()),
+            ];
+
+            if let Some(marker) = marker.as_deref() {
+                query.push(("marker", marker));
+            }
+
+            let request = self.request(Method::GET, None, &query).await?;
+            let response = self.client.send(request).await?;
+            let body = checked(response).await?.text().await?;
+
+            let names = xml_texts(&body, "Name");
+            let sizes = xml_texts(&body, "Content-Length");
+
+            for (name, size) in names.into_iter().zip(sizes) {
+                let key = name.strip_prefix(&self.prefix).unwrap_or(&name).to_string();
+                blobs.push((key, size.parse().unwrap_or(0)));
+            }
+
+            marker = xml_text(&body, "NextMarker");
+
+            if marker.is_none() {
+                return Ok(blobs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use config::storage::{AzureAuth, AzureStorage};
+
+    use super::AzureContainer;
+
+    fn storage(url: &str, auth: AzureAuth) -> AzureStorage {
+        AzureStorage {
+            url: url.to_string(),
+            auth,
+        }
+    }
+
+    #[test]
+    fn test_sas_token_is_appended_to_the_urls() {
+        let container = AzureContainer::new(&storage(
+            "https://account.blob.core.windows.net/container/hoodik",
+            AzureAuth::Sas("sv=2021&sig=abc".to_string()),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            container.url(Some("file.part.0"), &[]).as_str(),
+            "https://account.blob.core.windows.net/container/hoodik/file.part.0?sv=2021&sig=abc"
+        );
+        assert_eq!(
+            container.url(None, &[("comp", "list")]).as_str(),
+            "https://account.blob.core.windows.net/container?comp=list&sv=2021&sig=abc"
+        );
+    }
+
+    #[test]
+    fn test_container_is_required() {
+        let auth = AzureAuth::ManagedIdentity { client_id: None };
+
+        assert!(AzureContainer::new(&storage(
+            "https://account.blob.core.windows.net",
+            auth.clone()
+        ))
+        .is_err());
+
+        let container = AzureContainer::new(&storage(
+            "https://account.blob.core.windows.net/container",
+            auth,
+        ))
+        .unwrap();
+
+        assert_eq!(container.prefix, "");
+        assert_eq!(
+            container.url(Some("file.part.0"), &[]).as_str(),
+            "https://account.blob.core.windows.net/container/file.part.0"
+        );
+    }
+}
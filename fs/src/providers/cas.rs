@@ -0,0 +1,310 @@
+//! # Content addressed provider
+//!
+//! Keeps the chunks in the configured provider as the blobs named after the hash of their
+//! content, see [crate::cas]. Blob is written only if the provider doesn't have it yet, and
+//! purged once the last chunk pointing to it is gone. Linking the files only adds the
+//! references to the same blobs, nothing is copied in the provider.
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use error::{AppResult, Error};
+use futures_util::StreamExt;
+use tokio::fs::File;
+
+use super::{remote::ranges, Provider};
+use crate::{
+    cas::{ChunkBlob, ChunkIndex},
+    contract::FsProviderContract,
+    filename::{Filename, IntoFilename},
+    streamer::{FilesStream, Streamer},
+};
+
+pub(crate) struct CasProvider<'provider> {
+    inner: Box<Provider<'provider>>,
+    index: Arc<dyn ChunkIndex>,
+}
+
+impl<'provider> CasProvider<'provider> {
+    pub(crate) fn new(inner: Provider<'provider>, index: Arc<dyn ChunkIndex>) -> Self {
+        Self {
+            inner: Box::new(inner),
+            index,
+        }
+    }
+
+    /// Blobs of the file, nothing if the file was stored before
+    /// the storage was content addressed.
+    async fn blobs<T: IntoFilename>(&self, filename: &T) -> AppResult<Option<Vec<ChunkBlob>>> {
+        let blobs = self.index.chunks(&filename.filename()?.to_string()).await?;
+
+        Ok(Some(blobs).filter(|blobs| !blobs.is_empty()))
+    }
+
+    /// Blob of the single chunk of the file
+    async fn blob<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<ChunkBlob> {
+        self.blobs(filename)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .find(|blob| blob.chunk == chunk)
+            .ok_or_else(|| Error::NotFound("chunk_not_found".to_string()))
+    }
+
+    /// Purge the blobs nothing points to anymore from the provider, unless
+    /// they were referenced again before the index could forget them.
+    async fn purge_blobs(&self, hashes: Vec<String>) -> AppResult<()> {
+        for hash in hashes {
+            let name = blob_name(&hash);
+
+            self.index
+                .forget(&hash, Box::pin(self.inner.purge(&name)))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the content of the blob, unless the provider already has it
+    async fn store(&self, name: &Filename, data: &[u8]) -> AppResult<()> {
+        if !self.inner.exists(name, 0).await? {
+            self.inner.push(name, 0, data).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Name the blob is stored under in the provider, always as its only chunk
+fn blob_name(hash: &str) -> Filename {
+    Filename::new(format!("blob-{}", hash))
+}
+
+/// Blobs of the requested chunk, or all of them
+fn select(blobs: Vec<ChunkBlob>, chunk: Option<i64>) -> AppResult<Vec<ChunkBlob>> {
+    let chunk = match chunk {
+        Some(chunk) => chunk,
+        None => return Ok(blobs),
+    };
+
+    let blob = blobs
+        .into_iter()
+        .find(|blob| blob.chunk == chunk)
+        .ok_or_else(|| Error::NotFound("chunk_not_found".to_string()))?;
+
+    Ok(vec![blob])
+}
+
+#[async_trait]
+impl<'provider> FsProviderContract for CasProvider<'provider> {
+    async fn available_space(&self) -> AppResult<u64> {
+        self.inner.available_space().await
+    }
+
+    async fn read<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<u8>> {
+        self.inner.read(filename).await
+    }
+
+    async fn write<T: IntoFilename>(&self, filename: &T, data: &[u8]) -> AppResult<()> {
+        self.inner.write(filename, data).await
+    }
+
+    async fn exists<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<bool> {
+        match self.blobs(filename).await? {
+            Some(blobs) => Ok(blobs.iter().any(|blob| blob.chunk == chunk)),
+            None => self.inner.exists(filename, chunk).await,
+        }
+    }
+
+    async fn get<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<File> {
+        match self.blobs(filename).await? {
+            Some(_) => {
+                let blob = self.blob(filename, chunk).await?;
+
+                self.inner.get(&blob_name(&blob.hash), 0).await
+            }
+            None => self.inner.get(filename, chunk).await,
+        }
+    }
+
+    async fn all<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<File>> {
+        let blobs = match self.blobs(filename).await? {
+            Some(blobs) => blobs,
+            None => return self.inner.all(filename).await,
+        };
+
+        let mut files = vec![];
+
+        for blob in blobs {
+            files.push(self.inner.get(&blob_name(&blob.hash), 0).await?);
+        }
+
+        Ok(files)
+    }
+
+    /// Blob is written before the chunk points to it, so the chunk never points
+    /// to the content that isn't there. Content the provider already has is not
+    /// written again, unless it went missing from the provider. The reference to
+    /// the blob is taken before checking, so it can't be purged in the meantime.
+    async fn push<T: IntoFilename>(&self, filename: &T, chunk: i64, data: &[u8]) -> AppResult<()> {
+        let blob = ChunkBlob {
+            chunk,
+            hash: cryptfns::sha256::digest(data),
+            size: data.len() as u64,
+        };
+        let name = blob_name(&blob.hash);
+        let filename = filename.filename()?.to_string();
+
+        self.index.reference(&blob).await?;
+
+        let inserted = match self.store(&name, data).await {
+            Ok(()) => self.index.insert(&filename, &blob).await,
+            Err(e) => Err(e),
+        };
+
+        let mut unused = self.index.dereference(vec![blob.hash.clone()]).await?;
+        unused.extend(inserted?);
+
+        self.purge_blobs(unused).await
+    }
+
+    async fn pull<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<Vec<u8>> {
+        match self.blobs(filename).await? {
+            Some(_) => {
+                let blob = self.blob(filename, chunk).await?;
+
+                self.inner.pull(&blob_name(&blob.hash), 0).await
+            }
+            None => self.inner.pull(filename, chunk).await,
+        }
+    }
+
+    async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()> {
+        if self.blobs(filename).await?.is_none() {
+            return self.inner.purge(filename).await;
+        }
+
+        let unused = self.index.remove(&filename.filename()?.to_string()).await?;
+
+        self.purge_blobs(unused).await
+    }
+
+    /// Copies of the files only point to the same blobs
+    async fn link<T: IntoFilename, U: IntoFilename>(
+        &self,
+        from: &T,
+        to: &U,
+    ) -> AppResult<Vec<i64>> {
+        if self.blobs(from).await?.is_none() {
+            return self.inner.link(from, to).await;
+        }
+
+        self.index
+            .link(&from.filename()?.to_string(), &to.filename()?.to_string())
+            .await
+    }
+
+    async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
+        match self.blobs(filename).await? {
+            Some(blobs) => Ok(blobs.into_iter().map(|blob| blob.chunk).collect()),
+            None => self.inner.get_uploaded_chunks(filename).await,
+        }
+    }
+
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>> {
+        let mut chunks = vec![];
+
+        for filename in filenames {
+            chunks.push(self.get_uploaded_chunks(filename).await?);
+        }
+
+        Ok(chunks)
+    }
+
+    async fn stream<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+    ) -> AppResult<Streamer> {
+        self.stream_range(filename, chunk, 0, None).await
+    }
+
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64> {
+        match self.blobs(filename).await? {
+            Some(blobs) => Ok(select(blobs, chunk)?.iter().map(|blob| blob.size).sum()),
+            None => self.inner.size(filename, chunk).await,
+        }
+    }
+
+    /// Only the blobs that cover the range are read, each of them as a whole
+    /// and cut down to the part of the range it covers.
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer> {
+        let blobs = match self.blobs(filename).await? {
+            Some(blobs) => select(blobs, chunk)?,
+            None => return self.inner.stream_range(filename, chunk, start, end).await,
+        };
+
+        let parts = ranges(
+            blobs
+                .into_iter()
+                .map(|blob| (blob.hash, blob.size))
+                .collect(),
+            start,
+            end,
+        );
+        let names = parts
+            .iter()
+            .map(|(hash, _)| blob_name(hash))
+            .collect::<Vec<_>>();
+
+        let stream = self.inner.stream_files(&names, 1).await?.map(move |item| {
+            let (index, data) = item?;
+            let (start, end) = parts[index].1;
+            let end = (end as usize + 1).min(data.len());
+
+            Ok(data.slice((start as usize).min(end)..end))
+        });
+
+        Ok(Streamer::new(stream))
+    }
+
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream> {
+        let mut owners = vec![];
+        let mut names = vec![];
+
+        for (index, filename) in filenames.iter().enumerate() {
+            match self.blobs(filename).await? {
+                Some(blobs) => {
+                    for blob in blobs {
+                        owners.push(index);
+                        names.push(blob_name(&blob.hash));
+                    }
+                }
+                None => {
+                    owners.push(index);
+                    names.push(filename.filename()?);
+                }
+            }
+        }
+
+        let stream = self
+            .inner
+            .stream_files(&names, read_ahead)
+            .await?
+            .map(move |item| item.map(|(index, data)| (owners[index], data)));
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,329 @@
+//! # Flaky provider
+//!
+//! Test double of the remote storage that keeps the objects in memory and fails its
+//! operations on demand, the way the real storages fail: slow responses, errors with the
+//! status the storage would answer with, writes that stop half way and downloads that are
+//! cut off in the middle of the body. Faults are queued for each of the operations and the
+//! calls take them one by one, the calls after the queue runs out succeed.
+//!
+//! It sits under the [super::retry::RetryStore] and the [super::remote::RemoteProvider]
+//! just like the real storages, so the tests go through the same retries and streaming.
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use error::{AppResult, Error};
+use reqwest::Response;
+
+use super::remote::ObjectStore;
+
+/// Operations of the storage the faults are injected into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Operation {
+    Get,
+    Put,
+    Size,
+    Delete,
+    Copy,
+    List,
+}
+
+/// What goes wrong with the next call of the operation
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Fault {
+    /// The call is answered only after the delay
+    Latency(Duration),
+    /// The call fails with the status, without touching the objects
+    Status(u16),
+    /// Only the given number of bytes is written or downloaded before the call fails
+    Partial(usize),
+}
+
+/// Storage that keeps the objects in memory and fails with the queued faults
+#[derive(Default)]
+pub(crate) struct FlakyProvider {
+    objects: Mutex<HashMap<String, Bytes>>,
+    faults: Mutex<HashMap<Operation, VecDeque<Fault>>>,
+    calls: Mutex<HashMap<Operation, usize>>,
+}
+
+impl FlakyProvider {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the fault for the next call of the operation that doesn't have one yet
+    pub(crate) fn inject(&self, operation: Operation, fault: Fault) -> &Self {
+        self.faults
+            .lock()
+            .unwrap()
+            .entry(operation)
+            .or_default()
+            .push_back(fault);
+
+        self
+    }
+
+    /// How many times the operation was called, the failed calls included
+    pub(crate) fn calls(&self, operation: Operation) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .get(&operation)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Content of the object as it is in the storage
+    pub(crate) fn object(&self, key: &str) -> Option<Bytes> {
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    /// Count the call and take its fault, the latency is waited out right away
+    async fn call(&self, operation: Operation) -> AppResult<Option<usize>> {
+        *self.calls.lock().unwrap().entry(operation).or_default() += 1;
+
+        let fault = self
+            .faults
+            .lock()
+            .unwrap()
+            .get_mut(&operation)
+            .and_then(|faults| faults.pop_front());
+
+        match fault {
+            Some(Fault::Latency(delay)) => {
+                tokio::time::sleep(delay).await;
+                Ok(None)
+            }
+            Some(Fault::Status(status)) => Err(failure(status)),
+            Some(Fault::Partial(bytes)) => Ok(Some(bytes)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Error the way the storages report their statuses, so the transient ones are retried
+fn failure(status: u16) -> Error {
+    Error::StorageError(format!("flaky_status:{}:InjectedFault", status))
+}
+
+#[async_trait]
+impl ObjectStore for FlakyProvider {
+    /// Partial download sends the first bytes of the body and then breaks the connection
+    async fn get(&self, key: &str, range: Option<(u64, u64)>) -> AppResult<Response> {
+        let partial = self.call(Operation::Get).await?;
+
+        let mut data = self.object(key).ok_or_else(|| failure(404))?;
+
+        if let Some((start, end)) = range {
+            let end = (end as usize + 1).min(data.len());
+            data = data.slice((start as usize).min(end)..end);
+        }
+
+        let body = match partial {
+            Some(bytes) => {
+                let head = data.slice(..bytes.min(data.len()));
+                let broken = std::io::Error::new(
+                    std::io::ErrorKind::ConnectionReset,
+                    "connection reset by the flaky provider",
+                );
+
+                reqwest::Body::wrap_stream(futures_util::stream::iter(vec![Ok(head), Err(broken)]))
+            }
+            None => reqwest::Body::from(data),
+        };
+
+        Ok(Response::from(http::Response::new(body)))
+    }
+
+    /// Partial write leaves the first bytes in the object, the way a storage without
+    /// the atomic writes would, and fails as if the connection dropped
+    async fn put(&self, key: &str, data: Bytes) -> AppResult<()> {
+        let partial = self.call(Operation::Put).await?;
+
+        let mut objects = self.objects.lock().unwrap();
+
+        match partial {
+            Some(bytes) => {
+                objects.insert(key.to_string(), data.slice(..bytes.min(data.len())));
+                Err(failure(503))
+            }
+            None => {
+                objects.insert(key.to_string(), data);
+                Ok(())
+            }
+        }
+    }
+
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        self.call(Operation::Size).await?;
+
+        Ok(self.object(key).map(|data| data.len() as u64))
+    }
+
+    /// Deleting the object that isn't there succeeds, same as with the storages
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        self.call(Operation::Delete).await?;
+
+        self.objects.lock().unwrap().remove(key);
+
+        Ok(())
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> AppResult<()> {
+        self.call(Operation::Copy).await?;
+
+        let data = self.object(from).ok_or_else(|| failure(404))?;
+        self.objects.lock().unwrap().insert(to.to_string(), data);
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> AppResult<Vec<(String, u64)>> {
+        self.call(Operation::List).await?;
+
+        let mut objects = self
+            .objects
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, data)| (key.clone(), data.len() as u64))
+            .collect::<Vec<_>>();
+
+        objects.sort();
+
+        Ok(objects)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use futures_util::StreamExt;
+
+    use super::{Fault, FlakyProvider, Operation};
+    use crate::{
+        contract::FsProviderContract,
+        filename::Filename,
+        providers::{remote::RemoteProvider, retry::RetryStore},
+    };
+
+    /// Remote provider over the flaky storage, with the retries the real storages have
+    fn provider() -> (Arc<FlakyProvider>, RemoteProvider) {
+        let store = Arc::new(FlakyProvider::new());
+        let provider = RemoteProvider::new(Arc::new(RetryStore::new(store.clone())));
+
+        (store, provider)
+    }
+
+    #[tokio::test]
+    async fn test_upload_is_retried_over_the_transient_failures() {
+        let (store, provider) = provider();
+        let filename = Filename::new("upload-retry");
+
+        store
+            .inject(Operation::Put, Fault::Status(503))
+            .inject(Operation::Put, Fault::Latency(Duration::from_millis(20)));
+
+        provider.push(&filename, 0, b"chunk").await.unwrap();
+
+        assert_eq!(store.calls(Operation::Put), 2);
+        assert_eq!(provider.pull(&filename, 0).await.unwrap(), b"chunk");
+    }
+
+    #[tokio::test]
+    async fn test_partial_write_is_replaced_by_the_retry() {
+        let (store, provider) = provider();
+        let filename = Filename::new("partial-write");
+
+        store.inject(Operation::Put, Fault::Partial(2));
+
+        provider.push(&filename, 0, b"chunk").await.unwrap();
+
+        assert_eq!(store.calls(Operation::Put), 2);
+        assert_eq!(
+            store.object("partial-write.part.0").unwrap().as_ref(),
+            b"chunk"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_is_not_retried() {
+        let (store, provider) = provider();
+        let filename = Filename::new("permanent-failure");
+
+        store.inject(Operation::Put, Fault::Status(403));
+
+        assert!(provider.push(&filename, 0, b"chunk").await.is_err());
+        assert_eq!(store.calls(Operation::Put), 1);
+        assert!(!provider.exists(&filename, 0).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_ends_the_stream_with_an_error() {
+        let (store, provider) = provider();
+        let filename = Filename::new("interrupted-download");
+
+        provider.push(&filename, 0, b"first").await.unwrap();
+        provider.push(&filename, 1, b"second").await.unwrap();
+
+        store
+            .inject(Operation::Get, Fault::Latency(Duration::from_millis(20)))
+            .inject(Operation::Get, Fault::Partial(3));
+
+        let items = provider
+            .stream(&filename, None)
+            .await
+            .unwrap()
+            .stream()
+            .collect::<Vec<_>>()
+            .await;
+
+        let received = items
+            .iter()
+            .filter_map(|item| item.as_ref().ok())
+            .flat_map(|data| data.to_vec())
+            .collect::<Vec<_>>();
+
+        // The whole first chunk and the start of the second one, then the error,
+        // the download is never passed off as complete with the missing bytes
+        assert_eq!(received, b"firstsec");
+        assert!(items.last().unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_can_be_repeated_after_it_failed() {
+        let (store, provider) = provider();
+        let filename = Filename::new("purge-twice");
+
+        for chunk in 0..3 {
+            provider.push(&filename, chunk, b"chunk").await.unwrap();
+        }
+
+        store
+            .inject(Operation::Delete, Fault::Latency(Duration::from_millis(20)))
+            .inject(Operation::Delete, Fault::Status(403));
+
+        assert!(provider.purge(&filename).await.is_err());
+        assert_eq!(
+            provider.get_uploaded_chunks(&filename).await.unwrap(),
+            [1, 2]
+        );
+
+        provider.purge(&filename).await.unwrap();
+        assert!(provider
+            .get_uploaded_chunks(&filename)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Nothing left to purge is not a failure
+        provider.purge(&filename).await.unwrap();
+    }
+}
@@ -1,84 +1,244 @@
-use actix_web::web::Bytes;
+use std::collections::{HashMap, HashSet};
+
+use actix_web::web::{Bytes, BytesMut};
 use async_trait::async_trait;
 use error::{AppResult, Error};
 use fs4::available_space;
+use futures_util::StreamExt;
 use tokio::{
-    fs::{remove_file, File, OpenOptions},
-    io::{AsyncReadExt, AsyncWriteExt},
+    fs::{copy, hard_link, metadata, read_dir, remove_file, File, OpenOptions},
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom, Take},
 };
 
 use crate::{
+    concurrency,
     contract::FsProviderContract,
     filename::{Filename, IntoFilename},
-    streamer::Streamer,
+    partial::Partial,
+    pool,
+    streamer::{FilesStream, Streamer},
 };
 
+/// Size of the pieces the chunks are streamed in
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Chunks of fewer files than this are looked up file by file, the chunks
+/// of more files are found in a single pass over every directory.
+const SCAN_MIN_FILES: usize = 16;
+
 pub(crate) struct FsProvider<'provider> {
     data_dir: &'provider str,
+    shard_dirs: &'provider [String],
 }
 
 impl<'provider> FsProvider<'provider> {
     pub(crate) fn new(data_dir: &'provider str) -> Self {
-        Self { data_dir }
+        Self {
+            data_dir,
+            shard_dirs: &[],
+        }
+    }
+
+    /// Provider that spreads the chunks across the data_dir and the shard_dirs,
+    /// every chunk is kept in only one of them.
+    pub(crate) fn sharded(data_dir: &'provider str, shard_dirs: &'provider [String]) -> Self {
+        Self {
+            data_dir,
+            shard_dirs,
+        }
+    }
+
+    /// All the directories the chunks can be in, the data_dir is always the first one
+    fn dirs(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.data_dir).chain(self.shard_dirs.iter().map(|dir| dir.as_str()))
     }
 
-    /// Get full path of a file for the chunk
+    /// Get full path of a file in the data_dir
     fn full_path(&self, filename: &Filename) -> String {
         format!("{}/{}", self.data_dir, filename)
     }
 
-    /// Create the inner streaming method that is then passed into the streamer for
-    /// better readeability of the code.
-    async fn inner_stream<T: IntoFilename>(
+    /// Get full path of the chunk in the directory it is in, chunks
+    /// that don't exist anywhere yet are looked for in the data_dir.
+    fn chunk_path(&self, filename: &Filename) -> String {
+        if self.shard_dirs.is_empty() {
+            return self.full_path(filename);
+        }
+
+        self.dirs()
+            .map(|dir| format!("{}/{}", dir, filename))
+            .find(|path| std::path::Path::new(path).exists())
+            .unwrap_or_else(|| self.full_path(filename))
+    }
+
+    /// Path the chunk should be written to, the chunk that already exists is
+    /// overwritten where it is, the new one goes to the directory with the most space.
+    fn placement(&self, filename: &Filename) -> String {
+        if self.shard_dirs.is_empty() {
+            return self.full_path(filename);
+        }
+
+        if let Some(path) = self
+            .dirs()
+            .map(|dir| format!("{}/{}", dir, filename))
+            .find(|path| std::path::Path::new(path).exists())
+        {
+            return path;
+        }
+
+        let dir = self
+            .dirs()
+            .max_by_key(|dir| available_space(dir).unwrap_or(0))
+            .unwrap_or(self.data_dir);
+
+        format!("{}/{}", dir, filename)
+    }
+
+    /// All the uploaded chunks of the file with the directories they are in, ordered
+    /// by the chunk number. Chunk that is found in more than one directory, because
+    /// it was being moved between them, is listed only from the first directory.
+    fn chunk_dirs(&self, filename: &Filename) -> AppResult<Vec<(i64, &str)>> {
+        let pattern = filename.clone().with_chunk("*");
+        let mut chunks: Vec<(i64, &str)> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for dir in self.dirs() {
+            for path in glob::glob(&format!("{}/{}", dir, pattern))? {
+                let chunk = path?
+                    .to_str()
+                    .and_then(|path| path.rsplit_once(".part."))
+                    .and_then(|(_, chunk)| chunk.parse::<i64>().ok())
+                    .ok_or_else(|| {
+                        Error::InternalError(
+                            "Failed to parse chunk number while getting uploaded chunks"
+                                .to_string(),
+                        )
+                    })?;
+
+                if seen.insert(chunk) {
+                    chunks.push((chunk, dir));
+                }
+            }
+        }
+
+        chunks.sort_by_key(|(chunk, _)| *chunk);
+
+        Ok(chunks)
+    }
+
+    /// Paths of the requested chunk, or all the uploaded chunks, with their sizes.
+    pub(crate) async fn chunk_paths<T: IntoFilename>(
         &self,
         filename: &T,
         chunk: Option<i64>,
-    ) -> impl futures_util::Stream<Item = AppResult<actix_web::web::Bytes>> {
-        let mut files: Vec<File> = match chunk {
-            Some(chunk) => match self.get(filename, chunk).await {
-                Ok(file) => vec![file],
-                Err(e) => {
-                    log::error!("Got error when trying to create inner stream: {:#?}", e);
-                    vec![]
-                }
-            },
-            None => match self.all(filename).await {
-                Ok(files) => files,
-                Err(e) => {
-                    log::error!("Got error when trying to create inner stream: {:#?}", e);
-                    vec![]
-                }
-            },
+    ) -> AppResult<Vec<(String, u64)>> {
+        let filename = filename.filename()?;
+
+        let chunks = match chunk {
+            Some(chunk) => vec![self.chunk_path(&filename.clone().with_chunk(chunk))],
+            None => self
+                .chunk_dirs(&filename)?
+                .into_iter()
+                .map(|(chunk, dir)| format!("{}/{}", dir, filename.clone().with_chunk(chunk)))
+                .collect(),
         };
 
-        // Reverse the files so we can pop them from the end
-        files.reverse();
+        let mut paths = vec![];
 
-        // We are passing the Vec<File> here because those files are not read yet..
-        // but in the future if we want to create another FsProvider, for example S3, this would
-        // would only have the chunk number and file name passed, or construct of both and then the
-        // file getting would be happening inside the closure itself and not before.
-        futures_util::stream::unfold(files as Vec<File>, |mut files: Vec<File>| async move {
-            let mut file = files.pop()?;
+        for path in chunks {
+            let size = metadata(&path).await?.len();
 
-            let mut data = vec![];
+            paths.push((path, size));
+        }
 
-            match file.read_to_end(&mut data).await {
-                Ok(_) => (),
-                Err(e) => return Some((Err(Error::from(e)), files)),
-            };
+        Ok(paths)
+    }
+}
+
+/// Stream the parts of the chunk files, given by their path, offset and the number of bytes
+/// to read, in pieces of at most [STREAM_BUFFER_SIZE]. Only the piece that is being sent
+/// is held in memory, so the size of the chunks doesn't matter for the memory usage.
+fn stream_parts(
+    mut parts: Vec<(String, u64, Option<u64>)>,
+) -> impl futures_util::Stream<Item = AppResult<Bytes>> {
+    // Reverse the parts so we can pop them from the end
+    parts.reverse();
+
+    futures_util::stream::try_unfold(
+        (parts, None::<Take<File>>),
+        |(mut parts, mut reader)| async move {
+            loop {
+                let mut file = match reader.take() {
+                    Some(file) => file,
+                    None => match parts.pop() {
+                        Some((path, offset, limit)) => open_part(&path, offset, limit).await?,
+                        None => return Ok(None),
+                    },
+                };
+
+                let mut buffer = BytesMut::with_capacity(STREAM_BUFFER_SIZE);
+                let _permit = concurrency::permit().await;
+
+                while buffer.len() < STREAM_BUFFER_SIZE && file.read_buf(&mut buffer).await? > 0 {}
+
+                // Part has been read to the end, continue with the next one
+                if buffer.is_empty() {
+                    continue;
+                }
+
+                return Ok(Some((buffer.freeze(), (parts, Some(file)))));
+            }
+        },
+    )
+}
+
+/// Open the chunk file at the offset, reading at most `limit` bytes from it.
+async fn open_part(path: &str, offset: u64, limit: Option<u64>) -> AppResult<Take<File>> {
+    let mut file = File::open(path).await?;
+
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset)).await?;
+    }
 
-            let data = Bytes::from(data);
+    Ok(file.take(limit.unwrap_or(u64::MAX)))
+}
+
+/// Read one chunk file starting at the offset, reading at most `limit` bytes.
+pub(crate) async fn read_part(path: &str, offset: u64, limit: Option<u64>) -> AppResult<Bytes> {
+    let _permit = concurrency::permit().await;
+    let mut file = File::open(path).await?;
 
-            Some((Ok(data), files))
-        })
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset)).await?;
     }
+
+    let mut buffer = pool::chunks().get();
+
+    match limit {
+        Some(limit) => read_to_end(&mut file.take(limit), &mut buffer).await?,
+        None => read_to_end(&mut file, &mut buffer).await?,
+    };
+
+    Ok(buffer.split().freeze())
+}
+
+/// Read everything from the reader into the pooled buffer
+async fn read_to_end<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut BytesMut) -> AppResult<()> {
+    while reader.read_buf(buffer).await? > 0 {}
+
+    Ok(())
 }
 
 #[async_trait]
 impl<'ctx> FsProviderContract for FsProvider<'ctx> {
     async fn available_space(&self) -> AppResult<u64> {
-        available_space(self.data_dir).map_err(Error::from)
+        let mut space = 0;
+
+        for dir in self.dirs() {
+            space += available_space(dir)?;
+        }
+
+        Ok(space)
     }
 
     /// Direct read of the file data
@@ -98,21 +258,22 @@ impl<'ctx> FsProviderContract for FsProvider<'ctx> {
     async fn write<T: IntoFilename>(&self, filename: &T, data: &[u8]) -> AppResult<()> {
         let filename = filename.filename()?;
 
-        let file = File::create(self.full_path(&filename)).await?;
+        let target = self.full_path(&filename);
+        let (partial, file) = Partial::create(&target).await?;
 
         let mut writer = tokio::io::BufWriter::new(file);
         writer.write_all(data).await?;
         writer.flush().await?;
 
-        Ok(())
+        partial.persist(&target).await
     }
 
     async fn exists<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<bool> {
-        Ok(std::path::Path::new(
-            self.full_path(&filename.filename()?.with_chunk(chunk))
-                .as_str(),
-        )
-        .exists())
+        let filename = filename.filename()?.with_chunk(chunk);
+
+        Ok(self
+            .dirs()
+            .any(|dir| std::path::Path::new(&format!("{}/{}", dir, filename)).exists()))
     }
 
     async fn get<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<File> {
@@ -121,7 +282,7 @@ impl<'ctx> FsProviderContract for FsProvider<'ctx> {
         OpenOptions::new()
             .read(true)
             .write(true)
-            .open(self.full_path(&filename))
+            .open(self.chunk_path(&filename))
             .await
             .map_err(Error::from)
     }
@@ -141,20 +302,23 @@ impl<'ctx> FsProviderContract for FsProvider<'ctx> {
 
     async fn push<T: IntoFilename>(&self, filename: &T, chunk: i64, data: &[u8]) -> AppResult<()> {
         let filename = filename.filename()?.with_chunk(chunk);
+        let _permit = concurrency::permit().await;
 
-        let file = File::create(self.full_path(&filename)).await?;
+        let target = self.placement(&filename);
+        let (partial, file) = Partial::create(&target).await?;
 
         let mut writer = tokio::io::BufWriter::new(file);
         writer.write_all(data).await?;
         writer.flush().await?;
 
-        Ok(())
+        partial.persist(&target).await
     }
 
     async fn pull<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<Vec<u8>> {
         let filename = filename.filename()?.with_chunk(chunk);
+        let _permit = concurrency::permit().await;
 
-        let mut file = File::open(self.full_path(&filename)).await?;
+        let mut file = File::open(self.chunk_path(&filename)).await?;
 
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer).await?;
@@ -165,40 +329,97 @@ impl<'ctx> FsProviderContract for FsProvider<'ctx> {
     async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()> {
         let filename = filename.filename()?;
 
-        let chunks = self.get_uploaded_chunks(&filename).await?;
+        for dir in self.dirs() {
+            let pattern = format!("{}/{}", dir, filename.clone().with_chunk("*"));
 
-        for chunk in chunks {
-            remove_file(self.full_path(&filename.clone().with_chunk(chunk))).await?;
+            for path in glob::glob(&pattern)? {
+                remove_file(path?).await?;
+            }
         }
 
         Ok(())
     }
 
+    /// Chunks are hard linked, so they don't take any additional space on the disk,
+    /// they are copied only when the link can't be created (other file system).
+    /// Linked chunks stay in the same directory as the chunks they were linked from.
+    async fn link<T: IntoFilename, U: IntoFilename>(
+        &self,
+        from: &T,
+        to: &U,
+    ) -> AppResult<Vec<i64>> {
+        let from = from.filename()?;
+        let to = to.filename()?;
+
+        let chunks = self.chunk_dirs(&from)?;
+
+        for (chunk, dir) in chunks.iter() {
+            let source = format!("{}/{}", dir, from.clone().with_chunk(chunk));
+            let target = format!("{}/{}", dir, to.clone().with_chunk(chunk));
+            let _permit = concurrency::permit().await;
+
+            if let Err(e) = hard_link(&source, &target).await {
+                log::debug!("Copying the chunk instead of linking it: {}", e);
+                copy(&source, &target).await?;
+            }
+        }
+
+        Ok(chunks.into_iter().map(|(chunk, _)| chunk).collect())
+    }
+
     async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
-        let filename = filename.filename()?.with_chunk("*");
-        let pattern = self.full_path(&filename);
-        let paths = glob::glob(&pattern)?;
+        let chunks = self.chunk_dirs(&filename.filename()?)?;
+
+        Ok(chunks.into_iter().map(|(chunk, _)| chunk).collect())
+    }
 
-        let mut chunks = Vec::new();
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>> {
+        let mut chunks = vec![vec![]; filenames.len()];
 
-        for path in paths {
-            let path_str = path?.to_str().unwrap_or_default().replace(".part", "");
+        if filenames.is_empty() {
+            return Ok(chunks);
+        }
 
-            let chunk = path_str
-                .split('.')
-                .last()
-                .unwrap_or_default()
-                .parse::<i64>()
-                .map_err(|_| {
-                    Error::InternalError(
-                        "Failed to parse chunk number while getting uploaded chunks".to_string(),
-                    )
-                })?;
+        // Reading every chunk on the instance doesn't pay off for a handful of files
+        if filenames.len() < SCAN_MIN_FILES {
+            for (index, filename) in filenames.iter().enumerate() {
+                chunks[index] = self.get_uploaded_chunks(filename).await?;
+            }
 
-            chunks.push(chunk);
+            return Ok(chunks);
         }
 
-        chunks.sort();
+        let mut indexes = HashMap::new();
+        for (index, filename) in filenames.iter().enumerate() {
+            indexes.insert(filename.filename()?.to_string(), index);
+        }
+
+        // One pass over each directory instead of a glob for each of the files
+        for dir in self.dirs() {
+            let mut entries = read_dir(dir).await?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name();
+                let name = name.to_str().unwrap_or_default();
+
+                let (base, chunk) = match name.rsplit_once(".part.") {
+                    Some((base, chunk)) => (base, chunk),
+                    None => continue,
+                };
+
+                if let (Some(index), Ok(chunk)) = (indexes.get(base), chunk.parse::<i64>()) {
+                    chunks[*index].push(chunk);
+                }
+            }
+        }
+
+        for file_chunks in chunks.iter_mut() {
+            file_chunks.sort();
+            file_chunks.dedup();
+        }
 
         Ok(chunks)
     }
@@ -208,9 +429,241 @@ impl<'ctx> FsProviderContract for FsProvider<'ctx> {
         filename: &T,
         chunk: Option<i64>,
     ) -> AppResult<Streamer> {
-        let filename = filename.filename()?;
-        let stream = self.inner_stream(&filename, chunk).await;
+        let parts = self
+            .chunk_paths(filename, chunk)
+            .await?
+            .into_iter()
+            .map(|(path, _)| (path, 0, None))
+            .collect();
+
+        Ok(Streamer::new(stream_parts(parts)))
+    }
+
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64> {
+        let paths = self.chunk_paths(filename, chunk).await?;
+
+        Ok(paths.iter().map(|(_, size)| size).sum())
+    }
+
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer> {
+        let mut parts = vec![];
+        let mut position = 0;
+        let mut remaining = end.map(|end| (end + 1).saturating_sub(start));
+
+        // Only the sizes of the chunks are needed to find where to continue,
+        // chunks before the start are skipped without being opened.
+        for (path, size) in self.chunk_paths(filename, chunk).await? {
+            let offset = start.saturating_sub(position);
+            position += size;
+
+            if offset >= size || remaining == Some(0) {
+                continue;
+            }
+
+            let limit = remaining.map(|remaining| remaining.min(size - offset));
+            remaining = remaining.map(|remaining| remaining - limit.unwrap_or(0));
+
+            parts.push((path, offset, limit));
+        }
+
+        Ok(Streamer::new(stream_parts(parts)))
+    }
+
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream> {
+        let mut parts = vec![];
+
+        for (index, filename) in filenames.iter().enumerate() {
+            for (path, _) in self.chunk_paths(filename, None).await? {
+                parts.push((index, path));
+            }
+        }
+
+        // Bounded pipeline, `buffered` keeps at most `read_ahead` reads running
+        // and still yields the chunks in the order they were queued.
+        let stream = futures_util::stream::iter(parts)
+            .map(|(index, path)| async move {
+                read_part(&path, 0, None).await.map(|data| (index, data))
+            })
+            .buffered(read_ahead.max(1));
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures_util::StreamExt;
+
+    use super::{FsProvider, SCAN_MIN_FILES, STREAM_BUFFER_SIZE};
+    use crate::{contract::FsProviderContract, filename::Filename};
+
+    #[tokio::test]
+    async fn test_stream_files_keeps_the_order() {
+        let dir = std::env::temp_dir().join(format!(
+            "hoodik-stream-files-{}",
+            chrono::Utc::now().timestamp_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let provider = FsProvider::new(&data_dir);
+
+        let first = Filename::new("first");
+        let second = Filename::new("second");
+
+        for chunk in 0..3 {
+            let data = format!("first-{}", chunk);
+            provider.push(&first, chunk, data.as_bytes()).await.unwrap();
+        }
+        provider.push(&second, 0, b"second-0").await.unwrap();
+
+        let chunks = provider
+            .stream_files(&[first, second], 2)
+            .await
+            .unwrap()
+            .map(|item| item.unwrap())
+            .map(|(index, data)| (index, String::from_utf8(data.to_vec()).unwrap()))
+            .collect::<Vec<_>>()
+            .await;
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![
+                (0, "first-0".to_string()),
+                (0, "first-1".to_string()),
+                (0, "first-2".to_string()),
+                (1, "second-0".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_yields_fixed_size_pieces() {
+        let dir = std::env::temp_dir().join(format!(
+            "hoodik-stream-pieces-{}",
+            chrono::Utc::now().timestamp_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let provider = FsProvider::new(&data_dir);
+
+        let filename = Filename::new("large");
+        let chunks = [vec![1u8; STREAM_BUFFER_SIZE * 2 + 10], vec![2u8; 100]];
+
+        for (chunk, data) in chunks.iter().enumerate() {
+            provider.push(&filename, chunk as i64, data).await.unwrap();
+        }
+
+        let pieces = provider
+            .stream(&filename, None)
+            .await
+            .unwrap()
+            .stream()
+            .map(|piece| piece.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        let sizes = pieces.iter().map(|piece| piece.len()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![STREAM_BUFFER_SIZE, STREAM_BUFFER_SIZE, 10, 100]);
+        assert_eq!(pieces.concat(), chunks.concat());
+
+        // Range across the end of the first chunk and into the second one
+        let start = STREAM_BUFFER_SIZE as u64 * 2;
+        let range = provider
+            .stream_range(&filename, None, start, Some(start + 14))
+            .await
+            .unwrap()
+            .stream()
+            .map(|piece| piece.unwrap())
+            .collect::<Vec<_>>()
+            .await
+            .concat();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(range, [vec![1u8; 10], vec![2u8; 5]].concat());
+    }
+
+    #[tokio::test]
+    async fn test_get_uploaded_chunks_many() {
+        let dir = std::env::temp_dir().join(format!(
+            "hoodik-uploaded-chunks-{}",
+            chrono::Utc::now().timestamp_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let provider = FsProvider::new(&data_dir);
+
+        let first = Filename::new("first").with_timestamp(1);
+        let second = Filename::new("second").with_timestamp(1);
+        let empty = Filename::new("empty").with_timestamp(1);
+
+        for chunk in [2, 0, 10] {
+            provider.push(&first, chunk, b"data").await.unwrap();
+        }
+        provider.push(&second, 1, b"data").await.unwrap();
+
+        let many = provider
+            .get_uploaded_chunks_many(&[first.clone(), empty, second.clone()])
+            .await
+            .unwrap();
+
+        assert_eq!(many, vec![vec![0, 2, 10], vec![], vec![1]]);
+        assert_eq!(provider.get_uploaded_chunks(&first).await.unwrap(), many[0]);
+
+        // Enough files to find their chunks in a single pass over the directory
+        let mut filenames = vec![first, second];
+        filenames.extend(
+            (0..SCAN_MIN_FILES).map(|i| Filename::new(format!("other-{}", i)).with_timestamp(1)),
+        );
+
+        let scanned = provider.get_uploaded_chunks_many(&filenames).await.unwrap();
+
+        assert_eq!(scanned.len(), SCAN_MIN_FILES + 2);
+        assert_eq!(scanned[0], vec![0, 2, 10]);
+        assert_eq!(scanned[1], vec![1]);
+        assert!(scanned[2..].iter().all(|chunks| chunks.is_empty()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_link_keeps_the_chunks_after_the_source_is_purged() {
+        let dir = std::env::temp_dir().join(format!(
+            "hoodik-link-{}",
+            chrono::Utc::now().timestamp_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let provider = FsProvider::new(&data_dir);
+
+        let source = Filename::new("source");
+        let target = Filename::new("target");
+
+        provider.push(&source, 0, b"first").await.unwrap();
+        provider.push(&source, 1, b"second").await.unwrap();
+
+        let chunks = provider.link(&source, &target).await.unwrap();
+        provider.purge(&source).await.unwrap();
+
+        let first = provider.pull(&target, 0).await.unwrap();
+        let second = provider.pull(&target, 1).await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
 
-        Ok(Streamer::new(stream))
+        assert_eq!(chunks, vec![0, 1]);
+        assert_eq!(first, b"first");
+        assert_eq!(second, b"second");
     }
 }
@@ -0,0 +1,357 @@
+//! # Google Cloud Storage
+//!
+//! Keeps the chunks as the objects of the Google Cloud Storage bucket through its JSON API.
+//! Requests are authorized with the access token of the service account, the token is
+//! requested with the JWT signed by the key of the service account and reused until it expires.
+use std::sync::{Arc, OnceLock};
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use chrono::Utc;
+use config::storage::GcsStorage;
+use error::{AppResult, Error};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use percent_encoding::utf8_percent_encode;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::remote::ObjectStore;
+use crate::{connections::ClientPool, s3::UNRESERVED};
+
+/// Access the service account asks for, reading and writing the objects
+const SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// Longest time the signed JWT is valid for, Google refuses longer ones
+const ASSERTION_LIFETIME: i64 = 60 * 60;
+
+/// Token is refreshed this many seconds before it expires
+const TOKEN_REFRESH_MARGIN: i64 = 5 * 60;
+
+static BUCKET: OnceLock<Arc<GcsBucket>> = OnceLock::new();
+
+/// Fields of the service account JSON key that are needed to get the tokens
+#[derive(Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+struct Object {
+    size: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Objects {
+    #[serde(default)]
+    items: Vec<ListedObject>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListedObject {
+    name: String,
+    size: String,
+}
+
+pub(crate) struct GcsBucket {
+    client: ClientPool,
+    /// Address of the storage API
+    url: String,
+    bucket: String,
+    /// Prefix the chunks are stored under in the bucket, empty or ending with a slash
+    prefix: String,
+    account: ServiceAccount,
+    key: EncodingKey,
+    /// Access token with the time it expires at
+    token: Mutex<Option<(String, i64)>>,
+}
+
+impl GcsBucket {
+    pub(crate) fn new(storage: &GcsStorage) -> AppResult<Self> {
+        let account = std::fs::read_to_string(&storage.credentials)
+            .map_err(|_| Error::StorageError("gcs_credentials_not_found".to_string()))?;
+        let account = serde_json::from_str::<ServiceAccount>(&account)
+            .map_err(|_| Error::StorageError("invalid_gcs_credentials".to_string()))?;
+
+        Self::with_account(storage, account)
+    }
+
+    fn with_account(storage: &GcsStorage, account: ServiceAccount) -> AppResult<Self> {
+        let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())
+            .map_err(|_| Error::StorageError("invalid_gcs_private_key".to_string()))?;
+
+        let (bucket, prefix) = match storage.bucket.split_once('/') {
+            Some((bucket, prefix)) => (bucket.to_string(), format!("{}/", prefix)),
+            None => (storage.bucket.clone(), String::new()),
+        };
+
+        if bucket.is_empty() {
+            return Err(Error::StorageError("gcs_bucket_missing".to_string()));
+        }
+
+        Ok(Self {
+            client: ClientPool::new(),
+            url: storage.url.clone(),
+            bucket,
+            prefix,
+            account,
+            key,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Bucket is created once, so the connections and the token are reused
+    pub(crate) fn shared(storage: &GcsStorage) -> AppResult<Arc<Self>> {
+        if let Some(bucket) = BUCKET.get() {
+            return Ok(bucket.clone());
+        }
+
+        let bucket = Arc::new(Self::new(storage)?);
+
+        Ok(BUCKET.get_or_init(|| bucket).clone())
+    }
+
+    /// Name of the object in the bucket, encoded as a single segment of the URL
+    fn object(&self, key: &str) -> String {
+        utf8_percent_encode(&format!("{}{}", self.prefix, key), UNRESERVED).to_string()
+    }
+
+    /// URL of the object with the given key in the JSON API
+    fn object_url(&self, key: &str) -> String {
+        format!(
+            "{}/storage/v1/b/{}/o/{}",
+            self.url,
+            self.bucket,
+            self.object(key)
+        )
+    }
+
+    /// Authorized request to the storage API
+    async fn request(&self, method: Method, url: &str) -> AppResult<RequestBuilder> {
+        let token = self.token().await?;
+
+        Ok(self.client.request(method, url).bearer_auth(token))
+    }
+
+    /// Access token of the service account
+    async fn token(&self) -> AppResult<String> {
+        let mut token = self.token.lock().await;
+        let now = Utc::now().timestamp();
+
+        if let Some((token, expires_at)) = token.as_ref() {
+            if *expires_at - TOKEN_REFRESH_MARGIN > now {
+                return Ok(token.clone());
+            }
+        }
+
+        let claims = Claims {
+            iss: &self.account.client_email,
+            scope: SCOPE,
+            aud: &self.account.token_uri,
+            iat: now,
+            exp: now + ASSERTION_LIFETIME,
+        };
+        let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)?;
+
+        let request = self
+            .client
+            .request(Method::POST, &self.account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ]);
+        let response = self.client.send(request).await?;
+        let access = checked(response).await?.json::<Token>().await?;
+
+        *token = Some((access.access_token.clone(), now + access.expires_in));
+
+        Ok(access.access_token)
+    }
+}
+
+/// Google explains what went wrong in the JSON body
+async fn checked(response: Response) -> AppResult<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status().as_u16();
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .unwrap_or_default();
+    let message = body["error"]["message"]
+        .as_str()
+        .or_else(|| body["error"].as_str())
+        .unwrap_or_default();
+
+    Err(Error::StorageError(format!(
+        "gcs_status:{}:{}",
+        status, message
+    )))
+}
+
+#[async_trait]
+impl ObjectStore for GcsBucket {
+    async fn get(&self, key: &str, range: Option<(u64, u64)>) -> AppResult<Response> {
+        let url = format!("{}?alt=media", self.object_url(key));
+        let mut request = self.request(Method::GET, &url).await?;
+
+        if let Some((start, end)) = range {
+            request = request.header("Range", format!("bytes={}-{}", start, end));
+        }
+
+        checked(self.client.send(request).await?).await
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> AppResult<()> {
+        let url = format!("{}/upload/storage/v1/b/{}/o", self.url, self.bucket);
+        let name = format!("{}{}", self.prefix, key);
+
+        let request = self
+            .request(Method::POST, &url)
+            .await?
+            .query(&[("uploadType", "media"), ("name", name.as_str())])
+            .header("Content-Type", "application/octet-stream")
+            .body(data);
+        let response = self.client.send(request).await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        let request = self.request(Method::GET, &self.object_url(key)).await?;
+        let response = self.client.send(request).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let object = checked(response).await?.json::<Object>().await?;
+
+        Ok(Some(object.size.parse().unwrap_or(0)))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        let request = self.request(Method::DELETE, &self.object_url(key)).await?;
+        let response = self.client.send(request).await?;
+
+        // Object that is already gone is what we wanted
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> AppResult<()> {
+        let url = format!(
+            "{}/copyTo/b/{}/o/{}",
+            self.object_url(from),
+            self.bucket,
+            self.object(to)
+        );
+
+        let request = self.request(Method::POST, &url).await?;
+        let response = self.client.send(request).await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> AppResult<Vec<(String, u64)>> {
+        let url = format!("{}/storage/v1/b/{}/o", self.url, self.bucket);
+        let prefix = format!("{}{}", self.prefix, prefix);
+        let mut objects = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("prefix", prefix.as_str()),
+                ("fields", "items(name,size),nextPageToken"),
+            ];
+
+            if let Some(page_token) = page_token.as_deref() {
+                query.push(("pageToken", page_token));
+            }
+
+            let request = self.request(Method::GET, &url).await?.query(&query);
+            let response = self.client.send(request).await?;
+            let page = checked(response).await?.json::<Objects>().await?;
+
+            for object in page.items {
+                let key = object
+                    .name
+                    .strip_prefix(&self.prefix)
+                    .unwrap_or(&object.name)
+                    .to_string();
+                objects.push((key, object.size.parse().unwrap_or(0)));
+            }
+
+            page_token = page.next_page_token;
+
+            if page_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use config::storage::GcsStorage;
+
+    use super::{GcsBucket, Objects};
+
+    #[test]
+    fn test_listing_is_parsed() {
+        let page = serde_json::from_str::<Objects>(
+            r#"{"items": [{"name": "hoodik/file.part.0", "size": "42"}], "nextPageToken": "next"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(page.items[0].name, "hoodik/file.part.0");
+        assert_eq!(page.items[0].size, "42");
+        assert_eq!(page.next_page_token.as_deref(), Some("next"));
+
+        let page = serde_json::from_str::<Objects>("{}").unwrap();
+
+        assert!(page.items.is_empty());
+        assert!(page.next_page_token.is_none());
+    }
+
+    #[test]
+    fn test_missing_credentials_are_refused() {
+        let storage = GcsStorage {
+            bucket: "bucket/hoodik".to_string(),
+            credentials: "/nonexistent/service-account.json".to_string(),
+            url: "https://storage.googleapis.com".to_string(),
+        };
+
+        assert!(GcsBucket::new(&storage).is_err());
+    }
+}
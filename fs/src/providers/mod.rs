@@ -1 +1,173 @@
+pub(crate) mod azure;
+pub(crate) mod cas;
+#[cfg(test)]
+pub(crate) mod flaky;
 pub(crate) mod fs;
+pub(crate) mod gcs;
+pub(crate) mod remote;
+pub(crate) mod retry;
+pub(crate) mod s3;
+pub(crate) mod tiered;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use config::{storage::StorageConfig, Config};
+use error::{AppResult, Error};
+use tokio::fs::File;
+
+use crate::{
+    contract::FsProviderContract,
+    filename::IntoFilename,
+    streamer::{FilesStream, Streamer},
+};
+
+/// Provider the application configuration picked for storing the chunks
+pub(crate) enum Provider<'provider> {
+    Fs(fs::FsProvider<'provider>),
+    Remote(remote::RemoteProvider),
+    Tiered(tiered::TieredProvider<'provider>),
+    ContentAddressed(cas::CasProvider<'provider>),
+}
+
+impl<'provider> Provider<'provider> {
+    /// Provider picked by the STORAGE_PROVIDER, the remote storages are created once
+    /// and shared, the local one is only a view over the configured directories.
+    /// With the STORAGE_CONTENT_ADDRESSED it keeps the blobs instead of the chunks.
+    pub(crate) fn new(config: &'provider Config) -> AppResult<Self> {
+        let local = || fs::FsProvider::sharded(&config.app.data_dir, &config.app.shard_dirs);
+
+        let provider = match &config.storage {
+            StorageConfig::Tiered(storage) => {
+                Self::Tiered(tiered::TieredProvider::new(local(), remote(&storage.cold)?))
+            }
+            StorageConfig::Local => Self::Fs(local()),
+            storage => Self::Remote(remote(storage)?),
+        };
+
+        if !config.server.content_addressed {
+            return Ok(provider);
+        }
+
+        Ok(Self::ContentAddressed(cas::CasProvider::new(
+            provider,
+            crate::cas::index()?,
+        )))
+    }
+}
+
+/// Remote provider of the storage with its operations retried, the local storage has none
+fn remote(storage: &StorageConfig) -> AppResult<remote::RemoteProvider> {
+    let store: Arc<dyn remote::ObjectStore> = match storage {
+        StorageConfig::S3(storage) => s3::S3Bucket::shared(storage)?,
+        StorageConfig::Azure(storage) => azure::AzureContainer::shared(storage)?,
+        StorageConfig::Gcs(storage) => gcs::GcsBucket::shared(storage)?,
+        StorageConfig::Tiered(_) | StorageConfig::Local => {
+            return Err(Error::StorageError("storage_not_remote".to_string()))
+        }
+    };
+
+    Ok(remote::RemoteProvider::new(Arc::new(
+        retry::RetryStore::new(store),
+    )))
+}
+
+/// Call the same method on whichever provider is configured
+macro_rules! delegate {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self {
+            Provider::Fs(provider) => provider.$method($($arg),*).await,
+            Provider::Remote(provider) => provider.$method($($arg),*).await,
+            Provider::Tiered(provider) => provider.$method($($arg),*).await,
+            Provider::ContentAddressed(provider) => provider.$method($($arg),*).await,
+        }
+    };
+}
+
+#[async_trait]
+impl<'provider> FsProviderContract for Provider<'provider> {
+    async fn available_space(&self) -> AppResult<u64> {
+        delegate!(self.available_space())
+    }
+
+    async fn read<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<u8>> {
+        delegate!(self.read(filename))
+    }
+
+    async fn write<T: IntoFilename>(&self, filename: &T, data: &[u8]) -> AppResult<()> {
+        delegate!(self.write(filename, data))
+    }
+
+    async fn exists<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<bool> {
+        delegate!(self.exists(filename, chunk))
+    }
+
+    async fn get<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<File> {
+        delegate!(self.get(filename, chunk))
+    }
+
+    async fn all<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<File>> {
+        delegate!(self.all(filename))
+    }
+
+    async fn push<T: IntoFilename>(&self, filename: &T, chunk: i64, data: &[u8]) -> AppResult<()> {
+        delegate!(self.push(filename, chunk, data))
+    }
+
+    async fn pull<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<Vec<u8>> {
+        delegate!(self.pull(filename, chunk))
+    }
+
+    async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()> {
+        delegate!(self.purge(filename))
+    }
+
+    async fn link<T: IntoFilename, U: IntoFilename>(
+        &self,
+        from: &T,
+        to: &U,
+    ) -> AppResult<Vec<i64>> {
+        delegate!(self.link(from, to))
+    }
+
+    async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
+        delegate!(self.get_uploaded_chunks(filename))
+    }
+
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>> {
+        delegate!(self.get_uploaded_chunks_many(filenames))
+    }
+
+    async fn stream<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+    ) -> AppResult<Streamer> {
+        delegate!(self.stream(filename, chunk))
+    }
+
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64> {
+        delegate!(self.size(filename, chunk))
+    }
+
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer> {
+        delegate!(self.stream_range(filename, chunk, start, end))
+    }
+
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream> {
+        delegate!(self.stream_files(filenames, read_ahead))
+    }
+}
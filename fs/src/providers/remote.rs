@@ -0,0 +1,383 @@
+//! # Remote provider
+//!
+//! Keeps the chunks as the objects of a remote object storage (S3 bucket, Azure container...)
+//! under the same names they have on the disk. Everything about the chunks is done here,
+//! the storages only know how to read, write, list and copy the objects, see [ObjectStore].
+//!
+//! Downloads are streamed straight from the responses of the storage, so a chunk is never
+//! held in memory as a whole while it is being sent out. The files the contract hands out
+//! are downloaded into the temporary files, the same way, without holding the chunk.
+use std::sync::Arc;
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use error::{AppResult, Error};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use reqwest::Response;
+use tokio::{fs::File, io::AsyncWriteExt};
+
+use crate::{
+    concurrency,
+    contract::FsProviderContract,
+    filename::IntoFilename,
+    partial::Partial,
+    streamer::{FilesStream, Streamer},
+};
+
+/// Objects of the remote storage, the keys are relative to the place
+/// the chunks are kept in (bucket or container with the optional prefix).
+#[async_trait]
+pub(crate) trait ObjectStore: Send + Sync {
+    /// Response with the content of the object, or only the given inclusive byte range of it
+    async fn get(&self, key: &str, range: Option<(u64, u64)>) -> AppResult<Response>;
+
+    /// Create or replace the object
+    async fn put(&self, key: &str, data: Bytes) -> AppResult<()>;
+
+    /// Size of the object, or nothing if there is no such object
+    async fn size(&self, key: &str) -> AppResult<Option<u64>>;
+
+    async fn delete(&self, key: &str) -> AppResult<()>;
+
+    /// Copy the object inside of the storage, without downloading it
+    async fn copy(&self, from: &str, to: &str) -> AppResult<()>;
+
+    /// Keys and sizes of all the objects whose key starts with the prefix
+    async fn list(&self, prefix: &str) -> AppResult<Vec<(String, u64)>>;
+}
+
+#[derive(Clone)]
+pub(crate) struct RemoteProvider {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl RemoteProvider {
+    pub(crate) fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    pub(crate) async fn pull_object(&self, key: &str) -> AppResult<Bytes> {
+        let _permit = concurrency::permit().await;
+
+        Ok(self.store.get(key, None).await?.bytes().await?)
+    }
+
+    async fn push_object(&self, key: &str, data: &[u8]) -> AppResult<()> {
+        let _permit = concurrency::permit().await;
+
+        self.store.put(key, Bytes::copy_from_slice(data)).await
+    }
+
+    /// Stream of the object content as it is received from the storage
+    async fn stream_object(
+        &self,
+        key: &str,
+        range: Option<(u64, u64)>,
+    ) -> AppResult<impl Stream<Item = AppResult<Bytes>>> {
+        let response = self.store.get(key, range).await?;
+
+        Ok(response.bytes_stream().map_err(Error::from))
+    }
+
+    /// Uploaded chunks of the file with their keys and sizes, ordered by the chunk number
+    pub(crate) async fn chunks<T: IntoFilename>(
+        &self,
+        filename: &T,
+    ) -> AppResult<Vec<(i64, String, u64)>> {
+        let base = filename.filename()?.to_string();
+
+        let mut chunks = self
+            .store
+            .list(&format!("{}.part.", base))
+            .await?
+            .into_iter()
+            .filter_map(|(key, size)| {
+                let (name, chunk) = key.rsplit_once(".part.")?;
+
+                match name == base {
+                    true => Some((chunk.parse::<i64>().ok()?, key, size)),
+                    false => None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        chunks.sort_by_key(|(chunk, _, _)| *chunk);
+
+        Ok(chunks)
+    }
+
+    /// Keys and sizes of the requested chunk, or all the uploaded chunks
+    async fn chunk_keys<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+    ) -> AppResult<Vec<(String, u64)>> {
+        let chunk = match chunk {
+            Some(chunk) => chunk,
+            None => {
+                return Ok(self
+                    .chunks(filename)
+                    .await?
+                    .into_iter()
+                    .map(|(_, key, size)| (key, size))
+                    .collect())
+            }
+        };
+
+        let key = filename.filename()?.with_chunk(chunk).to_string();
+        let size = self
+            .store
+            .size(&key)
+            .await?
+            .ok_or_else(|| Error::NotFound("chunk_not_found".to_string()))?;
+
+        Ok(vec![(key, size)])
+    }
+}
+
+/// Inclusive byte ranges of the chunks that cover the bytes from the `start` to the `end`
+pub(crate) fn ranges(
+    chunks: Vec<(String, u64)>,
+    start: u64,
+    end: Option<u64>,
+) -> Vec<(String, (u64, u64))> {
+    let mut parts = vec![];
+    let mut position = 0;
+    let mut remaining = end.map(|end| (end + 1).saturating_sub(start));
+
+    for (key, size) in chunks {
+        let offset = start.saturating_sub(position);
+        position += size;
+
+        if offset >= size || remaining == Some(0) {
+            continue;
+        }
+
+        let length = match remaining {
+            Some(remaining) => remaining.min(size - offset),
+            None => size - offset,
+        };
+
+        remaining = remaining.map(|remaining| remaining - length);
+        parts.push((key, (offset, offset + length - 1)));
+    }
+
+    parts
+}
+
+#[async_trait]
+impl FsProviderContract for RemoteProvider {
+    /// Object storages have no limit on the space
+    async fn available_space(&self) -> AppResult<u64> {
+        Ok(u64::MAX)
+    }
+
+    async fn read<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<u8>> {
+        let key = filename.filename()?.to_string();
+
+        Ok(self.pull_object(&key).await?.to_vec())
+    }
+
+    async fn write<T: IntoFilename>(&self, filename: &T, data: &[u8]) -> AppResult<()> {
+        let key = filename.filename()?.to_string();
+
+        self.push_object(&key, data).await
+    }
+
+    async fn exists<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<bool> {
+        let key = filename.filename()?.with_chunk(chunk).to_string();
+
+        Ok(self.store.size(&key).await?.is_some())
+    }
+
+    /// The chunk is downloaded into the temporary file, the file is removed
+    /// right away and is gone once the returned handle is closed. Download
+    /// that is dropped half way removes the temporary file as well.
+    async fn get<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<File> {
+        let key = filename.filename()?.with_chunk(chunk).to_string();
+
+        let mut stream = Box::pin(self.stream_object(&key, None).await?);
+        let (temporary, mut file) = Partial::temporary(&format!("remote-{}", key)).await?;
+
+        while let Some(data) = stream.next().await {
+            file.write_all(&data?).await?;
+        }
+
+        file.flush().await?;
+
+        Ok(File::open(temporary.path()).await?)
+    }
+
+    async fn all<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<File>> {
+        let filename = filename.filename()?;
+        let mut files = vec![];
+
+        for chunk in self.get_uploaded_chunks(&filename).await? {
+            files.push(self.get(&filename, chunk).await?);
+        }
+
+        Ok(files)
+    }
+
+    async fn push<T: IntoFilename>(&self, filename: &T, chunk: i64, data: &[u8]) -> AppResult<()> {
+        let key = filename.filename()?.with_chunk(chunk).to_string();
+
+        self.push_object(&key, data).await
+    }
+
+    async fn pull<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<Vec<u8>> {
+        let key = filename.filename()?.with_chunk(chunk).to_string();
+
+        Ok(self.pull_object(&key).await?.to_vec())
+    }
+
+    async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()> {
+        for (_, key, _) in self.chunks(filename).await? {
+            self.store.delete(&key).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Chunks are copied inside of the storage, the content never leaves it
+    async fn link<T: IntoFilename, U: IntoFilename>(
+        &self,
+        from: &T,
+        to: &U,
+    ) -> AppResult<Vec<i64>> {
+        let to = to.filename()?;
+        let chunks = self.chunks(from).await?;
+
+        for (chunk, key, _) in chunks.iter() {
+            let target = to.clone().with_chunk(chunk).to_string();
+            let _permit = concurrency::permit().await;
+
+            self.store.copy(key, &target).await?;
+        }
+
+        Ok(chunks.into_iter().map(|(chunk, _, _)| chunk).collect())
+    }
+
+    async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
+        let chunks = self.chunks(filename).await?;
+
+        Ok(chunks.into_iter().map(|(chunk, _, _)| chunk).collect())
+    }
+
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>> {
+        let mut chunks = Vec::with_capacity(filenames.len());
+
+        for filename in filenames {
+            chunks.push(self.get_uploaded_chunks(filename).await?);
+        }
+
+        Ok(chunks)
+    }
+
+    async fn stream<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+    ) -> AppResult<Streamer> {
+        let keys = self.chunk_keys(filename, chunk).await?;
+        let provider = self.clone();
+
+        let stream = futures_util::stream::iter(keys)
+            .then(move |(key, _)| {
+                let provider = provider.clone();
+
+                async move { provider.stream_object(&key, None).await }
+            })
+            .try_flatten();
+
+        Ok(Streamer::new(stream))
+    }
+
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64> {
+        let keys = self.chunk_keys(filename, chunk).await?;
+
+        Ok(keys.iter().map(|(_, size)| size).sum())
+    }
+
+    /// Only the ranges of the chunks that are needed are requested from the storage
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer> {
+        let parts = ranges(self.chunk_keys(filename, chunk).await?, start, end);
+        let provider = self.clone();
+
+        let stream = futures_util::stream::iter(parts)
+            .then(move |(key, range)| {
+                let provider = provider.clone();
+
+                async move { provider.stream_object(&key, Some(range)).await }
+            })
+            .try_flatten();
+
+        Ok(Streamer::new(stream))
+    }
+
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream> {
+        let mut parts = vec![];
+
+        for (index, filename) in filenames.iter().enumerate() {
+            for (_, key, _) in self.chunks(filename).await? {
+                parts.push((index, key));
+            }
+        }
+
+        let provider = self.clone();
+
+        // Same bounded pipeline as the local provider, the chunks come in the queued order
+        let stream = futures_util::stream::iter(parts)
+            .map(move |(index, key)| {
+                let provider = provider.clone();
+
+                async move { provider.pull_object(&key).await.map(|data| (index, data)) }
+            })
+            .buffered(read_ahead.max(1));
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ranges;
+
+    #[test]
+    fn test_ranges_skip_the_chunks_before_the_start() {
+        let chunks = vec![
+            ("a.part.0".to_string(), 10),
+            ("a.part.1".to_string(), 10),
+            ("a.part.2".to_string(), 10),
+        ];
+
+        assert_eq!(
+            ranges(chunks.clone(), 15, None),
+            vec![
+                ("a.part.1".to_string(), (5, 9)),
+                ("a.part.2".to_string(), (0, 9))
+            ]
+        );
+        assert_eq!(
+            ranges(chunks.clone(), 5, Some(12)),
+            vec![
+                ("a.part.0".to_string(), (5, 9)),
+                ("a.part.1".to_string(), (0, 2))
+            ]
+        );
+        assert_eq!(ranges(chunks, 30, None), vec![]);
+    }
+}
@@ -0,0 +1,254 @@
+//! # Retries of the remote storage operations
+//!
+//! Remote storages fail now and then for reasons that go away on their own, throttling,
+//! a node that is restarting or a connection that dropped. These failures are retried
+//! with the exponential backoff and the full jitter, so a single 503 during the upload
+//! doesn't fail the chunk. Failures that would only happen again (missing object, denied
+//! access...) are returned right away.
+//!
+//! Retries are limited by the budget shared by the whole application, every operation
+//! adds `STORAGE_RETRY_BUDGET` percent of a retry to it and every retry takes a whole
+//! one. When the storage is down for good the retries can't multiply the load on it.
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use error::{AppResult, Error};
+use rand::Rng;
+use reqwest::Response;
+
+use super::remote::ObjectStore;
+
+/// Retries (in thousandths) the budget starts with, so the first failures are retried
+const BUDGET_RESERVE: u64 = 10_000;
+
+/// Most retries (in thousandths) the budget can save up
+const BUDGET_MAX: u64 = 100_000;
+
+/// Statuses of the storage responses worth another try
+const TRANSIENT_STATUSES: [u16; 6] = [408, 429, 500, 502, 503, 504];
+
+static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+static BUDGET: AtomicU64 = AtomicU64::new(BUDGET_RESERVE);
+
+/// How the failed operations are retried
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+    /// Attempts of each operation, the first one included
+    pub(crate) attempts: u32,
+    /// Milliseconds to wait before the first retry, doubled for each next one
+    pub(crate) base_delay: u64,
+    /// Longest wait between the attempts in milliseconds
+    pub(crate) max_delay: u64,
+    /// Percent of the operations that can be retried
+    pub(crate) budget: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: 100,
+            max_delay: 5000,
+            budget: 20,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Longest wait before the given retry (counted from 0), the actual
+    /// wait is picked at random up to it.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u64.saturating_pow(retry))
+            .min(self.max_delay);
+
+        Duration::from_millis(delay)
+    }
+}
+
+/// Set the policy of the retries, it can only be set once. Without it
+/// (providers used directly in tests) the default policy is used.
+pub(crate) fn init(policy: RetryPolicy) {
+    POLICY.get_or_init(|| policy);
+}
+
+fn policy() -> RetryPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// The error is likely gone on the next attempt
+pub(crate) fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::ReqwestError(e) => {
+            e.is_connect()
+                || e.is_timeout()
+                || e.status()
+                    .map(|status| TRANSIENT_STATUSES.contains(&status.as_u16()))
+                    .unwrap_or(false)
+        }
+        // Storages fail with `<storage>_status:<status>:<code>`
+        Error::StorageError(message) => {
+            let mut parts = message.splitn(3, ':');
+
+            match (parts.next(), parts.next()) {
+                (Some(kind), Some(status)) if kind.ends_with("_status") => status
+                    .parse::<u16>()
+                    .map(|status| TRANSIENT_STATUSES.contains(&status))
+                    .unwrap_or(false),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Add the part of the retry the operation earns to the budget
+fn deposit(percent: u64) {
+    let _ = BUDGET.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tokens| {
+        Some((tokens + percent * 10).min(BUDGET_MAX))
+    });
+}
+
+/// Take a retry from the budget, false when there is none left
+fn withdraw() -> bool {
+    BUDGET
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tokens| {
+            tokens.checked_sub(1000)
+        })
+        .is_ok()
+}
+
+/// Run the operation, retrying it while it fails with the transient errors
+pub(crate) async fn retry<T, F, Fut>(mut operation: F) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    let policy = policy();
+    let mut retries = 0;
+
+    deposit(policy.budget);
+
+    loop {
+        match operation().await {
+            Err(e) if retries + 1 < policy.attempts && is_transient(&e) && withdraw() => {
+                let backoff = policy.backoff(retries).as_millis() as u64;
+                let delay = rand::thread_rng().gen_range(0..=backoff);
+
+                log::debug!("Retrying the storage operation in {}ms: {}", delay, e);
+
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Remote storage with its operations retried
+pub(crate) struct RetryStore {
+    inner: Arc<dyn ObjectStore>,
+}
+
+impl RetryStore {
+    pub(crate) fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RetryStore {
+    async fn get(&self, key: &str, range: Option<(u64, u64)>) -> AppResult<Response> {
+        retry(|| self.inner.get(key, range)).await
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> AppResult<()> {
+        retry(|| self.inner.put(key, data.clone())).await
+    }
+
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        retry(|| self.inner.size(key)).await
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        retry(|| self.inner.delete(key)).await
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> AppResult<()> {
+        retry(|| self.inner.copy(from, to)).await
+    }
+
+    async fn list(&self, prefix: &str) -> AppResult<Vec<(String, u64)>> {
+        retry(|| self.inner.list(prefix)).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use error::Error;
+
+    use super::{is_transient, retry, RetryPolicy};
+
+    #[test]
+    fn test_only_transient_statuses_are_retried() {
+        let error = |message: &str| Error::StorageError(message.to_string());
+
+        assert!(is_transient(&error("s3_status:503:SlowDown")));
+        assert!(is_transient(&error("azure_status:500:InternalError")));
+        assert!(is_transient(&error("gcs_status:429:rateLimitExceeded")));
+        assert!(!is_transient(&error("s3_status:404:NoSuchKey")));
+        assert!(!is_transient(&error("s3_status:403:AccessDenied")));
+        assert!(!is_transient(&error("chunk_not_found")));
+        assert!(!is_transient(&Error::NotFound("503".to_string())));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(0).as_millis(), 100);
+        assert_eq!(policy.backoff(1).as_millis(), 200);
+        assert_eq!(policy.backoff(2).as_millis(), 400);
+        assert_eq!(policy.backoff(10).as_millis(), 5000);
+        assert_eq!(policy.backoff(u32::MAX).as_millis(), 5000);
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_is_retried() {
+        let attempts = AtomicU32::new(0);
+
+        let result = retry(|| async {
+            match attempts.fetch_add(1, Ordering::Relaxed) {
+                0 => Err(Error::StorageError("s3_status:503:SlowDown".to_string())),
+                _ => Ok(()),
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+
+        let attempts = AtomicU32::new(0);
+        let result = retry(|| async {
+            attempts.fetch_add(1, Ordering::Relaxed);
+
+            Err::<(), _>(Error::StorageError("s3_status:404:NoSuchKey".to_string()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+    }
+}
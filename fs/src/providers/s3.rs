@@ -0,0 +1,294 @@
+//! # S3 storage
+//!
+//! Keeps the chunks in any S3 compatible bucket (AWS, MinIO, Wasabi...),
+//! the URL of the bucket has to be in the path style.
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, OnceLock},
+};
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use chrono::Utc;
+use config::storage::S3Storage;
+use error::{AppResult, Error};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+
+use super::remote::ObjectStore;
+use crate::{
+    connections::ClientPool,
+    s3::{encode_path, host, xml_text, xml_texts, Signer, UNSIGNED_PAYLOAD},
+};
+
+/// Most of the objects S3 lists in a single page
+const LIST_PAGE_SIZE: &str = "1000";
+
+static BUCKET: OnceLock<Arc<S3Bucket>> = OnceLock::new();
+
+pub(crate) struct S3Bucket {
+    client: ClientPool,
+    /// URL of the bucket itself
+    bucket: Url,
+    /// Prefix the chunks are stored under in the bucket, empty or ending with a slash
+    prefix: String,
+    signer: Signer,
+}
+
+impl S3Bucket {
+    pub(crate) fn new(storage: &S3Storage) -> AppResult<Self> {
+        let mut bucket = Url::parse(&storage.url)
+            .map_err(|_| Error::StorageError("invalid_s3_url".to_string()))?;
+        bucket.set_query(None);
+
+        let path = bucket.path().trim_matches('/').to_string();
+        let (name, prefix) = match path.split_once('/') {
+            Some((name, prefix)) => (name.to_string(), format!("{}/", prefix)),
+            None => (path, String::new()),
+        };
+
+        if name.is_empty() {
+            return Err(Error::StorageError("s3_bucket_missing".to_string()));
+        }
+
+        bucket.set_path(&format!("/{}", name));
+
+        Ok(Self {
+            client: ClientPool::new(),
+            bucket,
+            prefix,
+            signer: Signer {
+                access_key_id: storage.access_key_id.clone(),
+                secret_access_key: storage.secret_access_key.clone(),
+                region: storage.region.clone(),
+            },
+        })
+    }
+
+    /// Bucket is created once, so the connections to it are reused
+    pub(crate) fn shared(storage: &S3Storage) -> AppResult<Arc<Self>> {
+        if let Some(bucket) = BUCKET.get() {
+            return Ok(bucket.clone());
+        }
+
+        let bucket = Arc::new(Self::new(storage)?);
+
+        Ok(BUCKET.get_or_init(|| bucket).clone())
+    }
+
+    /// Signed request for the object with the given key, without the key the request
+    /// is for the bucket itself. Additional headers are signed along.
+    fn request(
+        &self,
+        method: Method,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        extra: &[(&str, &str)],
+    ) -> RequestBuilder {
+        let mut url = self.bucket.clone();
+
+        if let Some(key) = key {
+            url.set_path(&format!("{}/{}", self.bucket.path(), self.object(key)));
+        }
+
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = BTreeMap::from([
+            ("host".to_string(), host(&url)),
+            (
+                "x-amz-content-sha256".to_string(),
+                UNSIGNED_PAYLOAD.to_string(),
+            ),
+            ("x-amz-date".to_string(), amz_date),
+        ]);
+
+        for (name, value) in extra {
+            headers.insert(name.to_lowercase(), value.to_string());
+        }
+
+        let authorization = self.signer.authorization(method.as_str(), &url, &headers);
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("Authorization", authorization);
+
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+
+        request
+    }
+
+    /// Path of the object in the bucket, encoded for the URL
+    fn object(&self, key: &str) -> String {
+        encode_path(&format!("{}{}", self.prefix, key))
+    }
+}
+
+/// S3 explains what went wrong in the body
+async fn checked(response: Response) -> AppResult<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    let code = xml_text(&body, "Code").unwrap_or_default();
+
+    Err(Error::StorageError(format!(
+        "s3_status:{}:{}",
+        status, code
+    )))
+}
+
+#[async_trait]
+impl ObjectStore for S3Bucket {
+    async fn get(&self, key: &str, range: Option<(u64, u64)>) -> AppResult<Response> {
+        let range = range.map(|(start, end)| format!("bytes={}-{}", start, end));
+        let extra = match range.as_deref() {
+            Some(range) => vec![("range", range)],
+            None => vec![],
+        };
+
+        let request = self.request(Method::GET, Some(key), &[], &extra);
+        let response = self.client.send(request).await?;
+
+        checked(response).await
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> AppResult<()> {
+        let request = self.request(Method::PUT, Some(key), &[], &[]).body(data);
+        let response = self.client.send(request).await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        let request = self.request(Method::HEAD, Some(key), &[], &[]);
+        let response = self.client.send(request).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let size = checked(response)
+            .await?
+            .headers()
+            .get("Content-Length")
+            .and_then(|size| size.to_str().ok())
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(0);
+
+        Ok(Some(size))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        let request = self.request(Method::DELETE, Some(key), &[], &[]);
+        let response = self.client.send(request).await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> AppResult<()> {
+        let source = format!("{}/{}", self.bucket.path(), self.object(from));
+
+        let request = self.request(
+            Method::PUT,
+            Some(to),
+            &[],
+            &[("x-amz-copy-source", source.as_str())],
+        );
+        let response = self.client.send(request).await?;
+        let body = checked(response).await?.text().await?;
+
+        // Copying can fail after the response has already started with 200
+        match xml_text(&body, "Code") {
+            Some(code) => Err(Error::StorageError(format!("s3_error:{}", code))),
+            None => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> AppResult<Vec<(String, u64)>> {
+        let prefix = format!("{}{}", self.prefix, prefix);
+        let mut objects = vec![];
+        let mut token: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("list-type", "2"),
+                ("max-keys", LIST_PAGE_SIZE),
+                ("prefix", prefix.as_str()),
+            ];
+
+            if let Some(token) = token.as_deref() {
+                query.push(("continuation-token", token));
+            }
+
+            let request = self.request(Method::GET, None, &query, &[]);
+            let response = self.client.send(request).await?;
+            let body = checked(response).await?.text().await?;
+
+            let keys = xml_texts(&body, "Key");
+            let sizes = xml_texts(&body, "Size");
+
+            for (key, size) in keys.into_iter().zip(sizes) {
+                let key = key.strip_prefix(&self.prefix).unwrap_or(&key).to_string();
+                objects.push((key, size.parse().unwrap_or(0)));
+            }
+
+            token = match xml_text(&body, "IsTruncated").as_deref() {
+                Some("true") => xml_text(&body, "NextContinuationToken"),
+                _ => None,
+            };
+
+            if token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use config::storage::S3Storage;
+
+    use super::S3Bucket;
+
+    fn storage(url: &str) -> S3Storage {
+        S3Storage {
+            url: url.to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: "key".to_string(),
+            secret_access_key: "secret".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bucket_and_prefix_are_split() {
+        let bucket = S3Bucket::new(&storage("http://localhost:9000/bucket/hoodik/chunks")).unwrap();
+
+        assert_eq!(bucket.bucket.as_str(), "http://localhost:9000/bucket");
+        assert_eq!(bucket.prefix, "hoodik/chunks/");
+        assert_eq!(
+            bucket.object("file name.part.1"),
+            "hoodik/chunks/file%20name.part.1"
+        );
+
+        let bucket = S3Bucket::new(&storage("http://localhost:9000/bucket")).unwrap();
+
+        assert_eq!(bucket.prefix, "");
+        assert_eq!(bucket.object("file.part.0"), "file.part.0");
+    }
+
+    #[test]
+    fn test_bucket_is_required() {
+        assert!(S3Bucket::new(&storage("http://localhost:9000")).is_err());
+        assert!(S3Bucket::new(&storage("not a url")).is_err());
+    }
+}
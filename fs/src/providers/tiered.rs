@@ -0,0 +1,245 @@
+//! # Tiered provider
+//!
+//! Keeps the recently used chunks on the local disk, where they are downloaded the fastest,
+//! and the rest in the remote storage. New chunks are always written to the local disk,
+//! the files nobody has used for a while are moved to the remote storage in the background,
+//! see [TieredProvider::demote].
+//!
+//! The file is on the local disk as long as any of its chunks are, otherwise it is read
+//! from the remote storage. Single chunks are looked for on the local disk first, so the
+//! file that is being moved is readable the whole time.
+use async_trait::async_trait;
+use error::AppResult;
+use futures_util::StreamExt;
+use tokio::fs::File;
+
+use super::{
+    fs::{read_part, FsProvider},
+    remote::RemoteProvider,
+};
+use crate::{
+    contract::FsProviderContract,
+    filename::IntoFilename,
+    streamer::{FilesStream, Streamer},
+};
+
+pub(crate) struct TieredProvider<'provider> {
+    hot: FsProvider<'provider>,
+    cold: RemoteProvider,
+}
+
+impl<'provider> TieredProvider<'provider> {
+    pub(crate) fn new(hot: FsProvider<'provider>, cold: RemoteProvider) -> Self {
+        Self { hot, cold }
+    }
+
+    /// Are any of the chunks of the file on the local disk
+    async fn is_hot<T: IntoFilename>(&self, filename: &T) -> AppResult<bool> {
+        Ok(!self.hot.get_uploaded_chunks(filename).await?.is_empty())
+    }
+
+    /// Move the chunks of the file from the local disk to the remote storage, returns
+    /// the chunks that were moved. Chunks are removed from the disk only once all of them
+    /// are stored remotely, so the file that fails half way is still whole on the disk.
+    pub(crate) async fn demote<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
+        let chunks = self.hot.get_uploaded_chunks(filename).await?;
+
+        for chunk in chunks.iter() {
+            let data = self.hot.pull(filename, *chunk).await?;
+            self.cold.push(filename, *chunk, &data).await?;
+        }
+
+        self.hot.purge(filename).await?;
+
+        Ok(chunks)
+    }
+}
+
+#[async_trait]
+impl<'provider> FsProviderContract for TieredProvider<'provider> {
+    /// Uploads land on the local disk, so its space is the one that runs out
+    async fn available_space(&self) -> AppResult<u64> {
+        self.hot.available_space().await
+    }
+
+    /// Files that are not chunks are never moved, they stay on the local disk
+    async fn read<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<u8>> {
+        self.hot.read(filename).await
+    }
+
+    async fn write<T: IntoFilename>(&self, filename: &T, data: &[u8]) -> AppResult<()> {
+        self.hot.write(filename, data).await
+    }
+
+    async fn exists<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<bool> {
+        Ok(self.hot.exists(filename, chunk).await? || self.cold.exists(filename, chunk).await?)
+    }
+
+    async fn get<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<File> {
+        match self.hot.exists(filename, chunk).await? {
+            true => self.hot.get(filename, chunk).await,
+            false => self.cold.get(filename, chunk).await,
+        }
+    }
+
+    async fn all<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<File>> {
+        match self.is_hot(filename).await? {
+            true => self.hot.all(filename).await,
+            false => self.cold.all(filename).await,
+        }
+    }
+
+    async fn push<T: IntoFilename>(&self, filename: &T, chunk: i64, data: &[u8]) -> AppResult<()> {
+        self.hot.push(filename, chunk, data).await
+    }
+
+    async fn pull<T: IntoFilename>(&self, filename: &T, chunk: i64) -> AppResult<Vec<u8>> {
+        match self.hot.exists(filename, chunk).await? {
+            true => self.hot.pull(filename, chunk).await,
+            false => self.cold.pull(filename, chunk).await,
+        }
+    }
+
+    async fn purge<T: IntoFilename>(&self, filename: &T) -> AppResult<()> {
+        self.hot.purge(filename).await?;
+        self.cold.purge(filename).await
+    }
+
+    /// Chunks are linked in the tier they are in
+    async fn link<T: IntoFilename, U: IntoFilename>(
+        &self,
+        from: &T,
+        to: &U,
+    ) -> AppResult<Vec<i64>> {
+        let mut chunks = self.hot.link(from, to).await?;
+        chunks.extend(self.cold.link(from, to).await?);
+        chunks.sort();
+        chunks.dedup();
+
+        Ok(chunks)
+    }
+
+    async fn get_uploaded_chunks<T: IntoFilename>(&self, filename: &T) -> AppResult<Vec<i64>> {
+        let mut chunks = self.hot.get_uploaded_chunks(filename).await?;
+        chunks.extend(self.cold.get_uploaded_chunks(filename).await?);
+        chunks.sort();
+        chunks.dedup();
+
+        Ok(chunks)
+    }
+
+    async fn get_uploaded_chunks_many<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+    ) -> AppResult<Vec<Vec<i64>>> {
+        let hot = self.hot.get_uploaded_chunks_many(filenames).await?;
+        let cold = self.cold.get_uploaded_chunks_many(filenames).await?;
+
+        Ok(hot
+            .into_iter()
+            .zip(cold)
+            .map(|(mut chunks, cold)| {
+                chunks.extend(cold);
+                chunks.sort();
+                chunks.dedup();
+                chunks
+            })
+            .collect())
+    }
+
+    async fn stream<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+    ) -> AppResult<Streamer> {
+        let hot = match chunk {
+            Some(chunk) => self.hot.exists(filename, chunk).await?,
+            None => self.is_hot(filename).await?,
+        };
+
+        match hot {
+            true => self.hot.stream(filename, chunk).await,
+            false => self.cold.stream(filename, chunk).await,
+        }
+    }
+
+    async fn size<T: IntoFilename>(&self, filename: &T, chunk: Option<i64>) -> AppResult<u64> {
+        let hot = match chunk {
+            Some(chunk) => self.hot.exists(filename, chunk).await?,
+            None => self.is_hot(filename).await?,
+        };
+
+        match hot {
+            true => self.hot.size(filename, chunk).await,
+            false => self.cold.size(filename, chunk).await,
+        }
+    }
+
+    async fn stream_range<T: IntoFilename>(
+        &self,
+        filename: &T,
+        chunk: Option<i64>,
+        start: u64,
+        end: Option<u64>,
+    ) -> AppResult<Streamer> {
+        let hot = match chunk {
+            Some(chunk) => self.hot.exists(filename, chunk).await?,
+            None => self.is_hot(filename).await?,
+        };
+
+        match hot {
+            true => self.hot.stream_range(filename, chunk, start, end).await,
+            false => self.cold.stream_range(filename, chunk, start, end).await,
+        }
+    }
+
+    /// Chunks are read from the tier their file is in, through the same bounded
+    /// pipeline as the other providers, so the chunks come in the queued order.
+    async fn stream_files<T: IntoFilename>(
+        &self,
+        filenames: &[T],
+        read_ahead: usize,
+    ) -> AppResult<FilesStream> {
+        let mut parts = vec![];
+
+        for (index, filename) in filenames.iter().enumerate() {
+            match self.is_hot(filename).await? {
+                true => {
+                    for (path, _) in self.hot.chunk_paths(filename, None).await? {
+                        parts.push((index, Part::Hot(path)));
+                    }
+                }
+                false => {
+                    for (_, key, _) in self.cold.chunks(filename).await? {
+                        parts.push((index, Part::Cold(key)));
+                    }
+                }
+            }
+        }
+
+        let cold = self.cold.clone();
+
+        let stream = futures_util::stream::iter(parts)
+            .map(move |(index, part)| {
+                let cold = cold.clone();
+
+                async move {
+                    let data = match part {
+                        Part::Hot(path) => read_part(&path, 0, None).await?,
+                        Part::Cold(key) => cold.pull_object(&key).await?,
+                    };
+
+                    Ok((index, data))
+                }
+            })
+            .buffered(read_ahead.max(1));
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Chunk as it is found in one of the tiers, by its path on the disk or its remote key
+enum Part {
+    Hot(String),
+    Cold(String),
+}
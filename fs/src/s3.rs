@@ -0,0 +1,160 @@
+//! # S3 requests
+//!
+//! Signing of the requests to any S3 compatible provider with the AWS signature version 4
+//! and reading of the XML responses, shared by the S3 storage provider, the exports
+//! and the archive. The payload is never signed, so the content can be sent as it is read.
+
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use quick_xml::{events::Event, Reader};
+use reqwest::Url;
+use sha2::Sha256;
+
+/// Characters that are left as they are in the path segments, everything
+/// else is percent encoded, the same set S3 expects when signing requests
+pub const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Value of the `x-amz-content-sha256` header for the requests with the payload that isn't signed
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Signs the requests with the AWS signature version 4
+#[derive(Clone, Debug)]
+pub struct Signer {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub region: String,
+}
+
+impl Signer {
+    /// Value of the `Authorization` header for the request with the given headers,
+    /// the header names have to be in lower case and the `x-amz-date` has to be set.
+    pub fn authorization(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &BTreeMap<String, String>,
+    ) -> String {
+        let amz_date = headers.get("x-amz-date").cloned().unwrap_or_default();
+        let date = amz_date.get(..8).unwrap_or_default();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+
+        let mut query = url
+            .query_pairs()
+            .map(|(key, value)| {
+                (
+                    utf8_percent_encode(&key, UNRESERVED).to_string(),
+                    utf8_percent_encode(&value, UNRESERVED).to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        query.sort();
+
+        let canonical_query = query
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let canonical_headers = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect::<String>();
+
+        let signed_headers = headers.keys().cloned().collect::<Vec<_>>().join(";");
+        let payload_hash = headers
+            .get("x-amz-content-sha256")
+            .map(|hash| hash.as_str())
+            .unwrap_or(UNSIGNED_PAYLOAD);
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            url.path(),
+            canonical_query,
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            cryptfns::sha256::digest(canonical_request.as_str())
+        );
+
+        let key = [date, self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_access_key).into_bytes(),
+                |key, part| hmac(&key, part.as_bytes()),
+            );
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            scope,
+            signed_headers,
+            cryptfns::hex::encode(hmac(&key, string_to_sign.as_bytes()))
+        )
+    }
+}
+
+/// Percent encode each of the segments of the path, the slashes are kept
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, UNRESERVED).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Host header, the port is included only when it isn't the default one for the scheme
+pub fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// Text of the first element with the given name in the response
+pub fn xml_text(xml: &str, name: &str) -> Option<String> {
+    xml_texts(xml, name).into_iter().next()
+}
+
+/// Text of all the elements with the given name in the response, in the order they appear
+pub fn xml_texts(xml: &str, name: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut texts = vec![];
+    let mut inside = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => inside = e.local_name().as_ref() == name.as_bytes(),
+            Ok(Event::Text(text)) if inside => {
+                if let Ok(text) = text.unescape() {
+                    texts.push(text.to_string());
+                }
+            }
+            Ok(Event::End(_)) => inside = false,
+            Ok(Event::Eof) | Err(_) => return texts,
+            _ => {}
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,211 @@
+//! # Shards
+//!
+//! Chunks of the default provider are spread across the DATA_DIR and the SHARD_DIRS,
+//! new chunks always go to the directory with the most space, so the disks that were
+//! added later, or are bigger, fill up unevenly. The helpers here let the rebalancer
+//! move the chunks between the shards until they match the target layout.
+//!
+//! Chunk is moved by copying it next to the target under a temporary name, renaming
+//! it once it is all there and only then removing it from the source. The chunk is
+//! readable the whole time, and the move that was interrupted can be cleaned up.
+
+use std::collections::HashSet;
+
+use error::{AppResult, Error};
+use fs4::available_space;
+use tokio::fs::{copy, metadata, read_dir, remove_file, rename};
+
+use crate::concurrency;
+
+/// Prefix of the chunk copies that are still being moved to the shard,
+/// they are never matched when looking for the chunks of the file.
+const MOVING_PREFIX: &str = ".moving-";
+
+/// Chunk file in one of the shards
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardChunk {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Space taken by the chunks in the shard
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardUsage {
+    pub dir: String,
+    pub chunks: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
+/// List all the chunks in the shard
+pub async fn chunks(dir: &str) -> AppResult<Vec<ShardChunk>> {
+    let mut chunks = vec![];
+    let mut entries = read_dir(dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = match entry.file_name().to_str() {
+            Some(name) if is_chunk(name) => name.to_string(),
+            _ => continue,
+        };
+
+        chunks.push(ShardChunk {
+            name,
+            size: entry.metadata().await?.len(),
+        });
+    }
+
+    Ok(chunks)
+}
+
+/// Count the chunks in the shard and the space they take
+pub async fn usage(dir: &str) -> AppResult<ShardUsage> {
+    let chunks = chunks(dir).await?;
+
+    Ok(ShardUsage {
+        dir: dir.to_string(),
+        chunks: chunks.len() as u64,
+        used: chunks.iter().map(|chunk| chunk.size).sum(),
+        available: available_space(dir)?,
+    })
+}
+
+/// Move the chunk from one shard to the other, the chunk
+/// that is already in the target is only removed from the source.
+pub async fn move_chunk(from: &str, to: &str, chunk: &ShardChunk) -> AppResult<()> {
+    let source = format!("{}/{}", from, chunk.name);
+    let target = format!("{}/{}", to, chunk.name);
+    let _permit = concurrency::permit().await;
+
+    let existing = metadata(&target).await.ok().map(|m| m.len());
+
+    if existing != Some(chunk.size) {
+        let moving = format!("{}/{}{}", to, MOVING_PREFIX, chunk.name);
+
+        copy(&source, &moving).await?;
+
+        if metadata(&moving).await?.len() != chunk.size {
+            remove_file(&moving).await?;
+
+            return Err(Error::StorageError(format!(
+                "chunk_changed_while_moving:{}",
+                chunk.name
+            )));
+        }
+
+        rename(&moving, &target).await?;
+    }
+
+    remove_file(&source).await?;
+
+    Ok(())
+}
+
+/// Clean up after the moves that were interrupted, the unfinished copies are removed
+/// and so are the chunks that made it to the target, but were not removed from the
+/// source. Those are kept in the first directory they are found in, the same one
+/// they are read from. Returns the number of the removed files.
+pub async fn cleanup(dirs: &[String]) -> AppResult<u64> {
+    let mut seen = HashSet::new();
+    let mut removed = 0;
+
+    for dir in dirs {
+        let mut entries = read_dir(dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = match entry.file_name().to_str() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            if name.starts_with(MOVING_PREFIX) || (is_chunk(&name) && !seen.insert(name)) {
+                remove_file(entry.path()).await?;
+                removed += 1;
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+/// How many bytes each of the shards should hold once the `total` is
+/// spread across them by their weights, the rounding leftover goes to the first one.
+pub fn targets(total: u64, weights: &[u64]) -> Vec<u64> {
+    let sum: u64 = weights.iter().sum();
+
+    if sum == 0 {
+        return vec![0; weights.len()];
+    }
+
+    let mut targets = weights
+        .iter()
+        .map(|weight| (total as u128 * *weight as u128 / sum as u128) as u64)
+        .collect::<Vec<_>>();
+
+    let leftover = total - targets.iter().sum::<u64>();
+
+    if let Some(first) = targets.iter_mut().zip(weights).find(|(_, w)| **w > 0) {
+        *first.0 += leftover;
+    }
+
+    targets
+}
+
+/// Chunk files are named `<timestamp>-<id>.part.<chunk>`
+fn is_chunk(name: &str) -> bool {
+    match name.rsplit_once(".part.") {
+        Some((_, chunk)) => !name.starts_with(MOVING_PREFIX) && chunk.parse::<i64>().is_ok(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_targets_follow_the_weights() {
+        assert_eq!(targets(100, &[1, 1]), vec![50, 50]);
+        assert_eq!(targets(100, &[1, 3]), vec![25, 75]);
+        assert_eq!(targets(100, &[0, 1, 2]), vec![0, 34, 66]);
+        assert_eq!(targets(100, &[0, 0]), vec![0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_move_chunk_and_cleanup() {
+        let root = std::env::temp_dir().join(format!(
+            "hoodik-shards-{}",
+            chrono::Utc::now().timestamp_nanos()
+        ));
+        let from = root.join("from").to_str().unwrap().to_string();
+        let to = root.join("to").to_str().unwrap().to_string();
+        std::fs::create_dir_all(&from).unwrap();
+        std::fs::create_dir_all(&to).unwrap();
+
+        std::fs::write(format!("{}/1-a.part.0", from), b"first").unwrap();
+        std::fs::write(format!("{}/1-a.part.1", from), b"second").unwrap();
+        std::fs::write(format!("{}/settings.json", from), b"{}").unwrap();
+
+        let listed = chunks(&from).await.unwrap();
+        assert_eq!(listed.len(), 2);
+
+        let chunk = listed.iter().find(|c| c.name == "1-a.part.0").unwrap();
+        move_chunk(&from, &to, chunk).await.unwrap();
+
+        assert_eq!(std::fs::read(format!("{}/1-a.part.0", to)).unwrap(), b"first");
+        assert!(!std::path::Path::new(&format!("{}/1-a.part.0", from)).exists());
+        assert_eq!(usage(&to).await.unwrap().used, 5);
+
+        // Interrupted moves, one before the rename and one before removing the source
+        std::fs::write(format!("{}/{}1-a.part.1", to, MOVING_PREFIX), b"sec").unwrap();
+        std::fs::write(format!("{}/1-a.part.0", from), b"first").unwrap();
+
+        let removed = cleanup(&[from.clone(), to.clone()]).await.unwrap();
+
+        assert_eq!(removed, 2);
+        assert!(std::path::Path::new(&format!("{}/1-a.part.0", from)).exists());
+        assert!(!std::path::Path::new(&format!("{}/1-a.part.0", to)).exists());
+        assert_eq!(chunks(&to).await.unwrap(), vec![]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -3,6 +3,11 @@ use error::AppResult;
 use futures_util::stream::StreamExt;
 use std::pin::Pin;
 
+/// Stream of chunks of many files, each chunk comes with the index of the file it belongs to,
+/// so the consumer (archive builder for example) knows where one file ends and another begins.
+pub type FilesStream =
+    Pin<Box<dyn futures_util::Stream<Item = AppResult<(usize, actix_web::web::Bytes)>>>>;
+
 /// Wrapper around a stream of bytes.
 pub struct Streamer {
     pub(crate) inner: Pin<Box<dyn futures_util::Stream<Item = AppResult<actix_web::web::Bytes>>>>,
@@ -0,0 +1,164 @@
+//! # Slow client watchdog
+//!
+//! Uploads and downloads keep the worker and a chunk in memory for as long as they run,
+//! so a client that sends or reads the data a few bytes at a time could hold them for hours.
+//! The watchdog wraps the stream of the data and ends it with `408 Request Timeout` once it
+//! goes without any progress for the `HTTP_STREAM_TIMEOUT`, or once its average speed
+//! drops under the `HTTP_MIN_THROUGHPUT` after it has been running for that long.
+//!
+//! Downloads are only checked when the server gets to send the next part of the response,
+//! a client that stops reading altogether is left to the TCP timeouts of the system.
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_web::web::Bytes;
+use config::server::ServerConfig;
+use error::{AppResult, Error};
+use futures_util::Stream;
+use pin_project::pin_project;
+use tokio::time::{sleep, Sleep};
+
+#[pin_project]
+pub struct Watchdog<S> {
+    #[pin]
+    inner: S,
+    /// Fires when the stream goes without progress for too long
+    stall: Option<Pin<Box<Sleep>>>,
+    timeout: Duration,
+    min_throughput: u64,
+    started: Instant,
+    bytes: u64,
+    done: bool,
+}
+
+impl<S> Watchdog<S>
+where
+    S: Stream<Item = AppResult<Bytes>>,
+{
+    pub fn new(inner: S, config: &ServerConfig) -> Self {
+        Self::with_limits(
+            inner,
+            Duration::from_secs(config.stream_timeout),
+            config.min_throughput,
+        )
+    }
+
+    /// Watchdog with the given limits, zero timeout turns both of the checks off
+    pub fn with_limits(inner: S, timeout: Duration, min_throughput: u64) -> Self {
+        Self {
+            inner,
+            stall: (!timeout.is_zero()).then(|| Box::pin(sleep(timeout))),
+            timeout,
+            min_throughput,
+            started: Instant::now(),
+            bytes: 0,
+            done: false,
+        }
+    }
+}
+
+/// Check if the average speed is under the minimum, the stream gets the whole
+/// timeout to get going before it is checked.
+fn too_slow(elapsed: Duration, timeout: Duration, bytes: u64, min_throughput: u64) -> bool {
+    if min_throughput == 0 || timeout.is_zero() || elapsed < timeout {
+        return false;
+    }
+
+    (bytes as f64 / elapsed.as_secs_f64()) < min_throughput as f64
+}
+
+impl<S> Stream for Watchdog<S>
+where
+    S: Stream<Item = AppResult<Bytes>>,
+{
+    type Item = AppResult<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        let item = match this.inner.poll_next(cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => {
+                let stalled = this
+                    .stall
+                    .as_mut()
+                    .map_or(false, |stall| stall.as_mut().poll(cx).is_ready());
+
+                if !stalled {
+                    return Poll::Pending;
+                }
+
+                *this.done = true;
+
+                return Poll::Ready(Some(Err(Error::RequestTimeout(
+                    "stream_stalled".to_string(),
+                ))));
+            }
+        };
+
+        if let Some(Ok(bytes)) = item.as_ref() {
+            *this.bytes += bytes.len() as u64;
+
+            if let Some(stall) = this.stall.as_mut() {
+                let deadline = tokio::time::Instant::now() + *this.timeout;
+                stall.as_mut().reset(deadline);
+            }
+
+            let elapsed = this.started.elapsed();
+
+            if too_slow(elapsed, *this.timeout, *this.bytes, *this.min_throughput) {
+                *this.done = true;
+
+                return Poll::Ready(Some(Err(Error::RequestTimeout(
+                    "stream_too_slow".to_string(),
+                ))));
+            }
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use actix_web::web::Bytes;
+    use futures_util::StreamExt;
+
+    use super::{too_slow, Watchdog};
+
+    #[test]
+    fn test_throughput_is_checked_after_the_timeout() {
+        let timeout = Duration::from_secs(60);
+
+        assert!(!too_slow(Duration::from_secs(30), timeout, 0, 1024));
+        assert!(too_slow(Duration::from_secs(60), timeout, 1024, 1024));
+        assert!(!too_slow(Duration::from_secs(60), timeout, 60 * 1024, 1024));
+        assert!(!too_slow(Duration::from_secs(600), timeout, 0, 0));
+        assert!(!too_slow(Duration::from_secs(600), Duration::ZERO, 0, 1024));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_is_ended() {
+        let stream = futures_util::stream::once(async { Ok(Bytes::from_static(b"data")) })
+            .chain(futures_util::stream::pending());
+
+        let mut watchdog = Box::pin(Watchdog::with_limits(stream, Duration::from_millis(50), 0));
+
+        assert_eq!(
+            watchdog.next().await.unwrap().unwrap(),
+            Bytes::from_static(b"data")
+        );
+        assert!(watchdog.next().await.unwrap().is_err());
+        assert!(watchdog.next().await.is_none());
+    }
+}
@@ -0,0 +1,279 @@
+//! # Fixtures
+//!
+//! Users and their sessions are seeded straight into the database, the tests are about
+//! the storage and not about the login. Files and directories are created through the
+//! routes the clients use, so they end up on the disk the same way, while the shares are
+//! inserted the way the owner's client leaves them after encrypting the key for the user.
+use actix_http::Request;
+use actix_web::{cookie::Cookie, dev::Service, test::TestRequest};
+use auth::data::authenticated::Authenticated;
+use entity::{links, sessions, user_files, users, ActiveModelTrait, ActiveValue, Uuid};
+use serde_json::json;
+use storage::data::app_file::AppFile;
+
+use crate::{Harness, Response};
+
+/// User with an active session, the requests it builds carry its session cookie
+pub struct TestUser {
+    pub model: users::Model,
+    pub session: sessions::Model,
+    pub cookie: Cookie<'static>,
+}
+
+impl TestUser {
+    pub fn id(&self) -> Uuid {
+        self.model.id
+    }
+
+    pub fn get(&self, uri: &str) -> TestRequest {
+        self.request(TestRequest::get(), uri)
+    }
+
+    pub fn head(&self, uri: &str) -> TestRequest {
+        self.request(
+            TestRequest::default().method(actix_web::http::Method::HEAD),
+            uri,
+        )
+    }
+
+    pub fn post(&self, uri: &str) -> TestRequest {
+        self.request(TestRequest::post(), uri)
+    }
+
+    pub fn put(&self, uri: &str) -> TestRequest {
+        self.request(TestRequest::put(), uri)
+    }
+
+    pub fn patch(&self, uri: &str) -> TestRequest {
+        self.request(TestRequest::patch(), uri)
+    }
+
+    pub fn delete(&self, uri: &str) -> TestRequest {
+        self.request(TestRequest::delete(), uri)
+    }
+
+    /// Request with any method, like the WebDAV `PROPFIND` or `MKCOL`
+    pub fn method(&self, method: &str, uri: &str) -> TestRequest {
+        let method = actix_web::http::Method::from_bytes(method.as_bytes()).unwrap();
+
+        self.request(TestRequest::default().method(method), uri)
+    }
+
+    fn request(&self, request: TestRequest, uri: &str) -> TestRequest {
+        request.uri(uri).cookie(self.cookie.clone())
+    }
+}
+
+/// File or directory that is going to be created by its owner
+pub struct FileBuilder<'h, S> {
+    harness: &'h Harness<S>,
+    owner: &'h TestUser,
+    name: String,
+    mime: String,
+    parent: Option<Uuid>,
+    content: Option<Vec<u8>>,
+    chunks: usize,
+    hashed: bool,
+}
+
+impl<'h, S> FileBuilder<'h, S>
+where
+    S: Service<Request, Response = Response, Error = actix_web::Error>,
+{
+    /// Create it inside of the given directory instead of the root
+    pub fn parent(mut self, parent: Uuid) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    pub fn mime(mut self, mime: &str) -> Self {
+        self.mime = mime.to_string();
+        self
+    }
+
+    /// Content uploaded as the only chunk of the file, defaults to its name
+    pub fn content(mut self, content: &[u8]) -> Self {
+        self.content = Some(content.to_vec());
+        self
+    }
+
+    /// Split the content into the number of chunks, each uploaded on its own
+    pub fn chunks(mut self, chunks: usize) -> Self {
+        self.chunks = chunks;
+        self
+    }
+
+    /// Send the hash of the content with the file, so the same content can be deduplicated
+    pub fn hashed(mut self) -> Self {
+        self.hashed = true;
+        self
+    }
+
+    /// Leave the file without any of its chunks uploaded
+    pub fn unfinished(mut self) -> Self {
+        self.content = None;
+        self
+    }
+
+    /// Create the file and upload its content, panics when any of the requests fails
+    pub async fn create(self) -> AppFile {
+        let is_dir = self.mime == "dir";
+
+        let mut data = json!({
+            "encrypted_key": cryptfns::base64::encode(&self.name),
+            "encrypted_name": cryptfns::hex::encode(&self.name),
+            "name_hash": cryptfns::sha256::digest(self.name.as_bytes()),
+            "mime": self.mime,
+            "file_id": self.parent,
+        });
+
+        if !is_dir {
+            let size = self.content.as_ref().map(|c| c.len()).unwrap_or(100);
+            data["size"] = json!(size);
+            data["chunks"] = json!(self.chunks);
+        }
+
+        if let Some(content) = self.content.as_ref().filter(|_| self.hashed && !is_dir) {
+            data["sha256"] = json!(cryptfns::sha256::digest(content.as_slice()));
+        }
+
+        let request = self.owner.post("/api/storage").set_json(&data).to_request();
+        let file = self.expect_file(request).await;
+
+        let content = match self.content.as_ref() {
+            Some(content) if !is_dir => content.clone(),
+            _ => return file,
+        };
+
+        let chunk_size = content.len().div_ceil(self.chunks).max(1);
+        let mut file = file;
+
+        for (chunk, content) in content.chunks(chunk_size).enumerate() {
+            let request = self
+                .owner
+                .post(&format!("/api/storage/{}?chunk={}", file.id, chunk))
+                .insert_header(("Content-Type", "application/octet-stream"))
+                .set_payload(content.to_vec())
+                .to_request();
+
+            file = self.expect_file(request).await;
+        }
+
+        file
+    }
+
+    async fn expect_file(&self, request: Request) -> AppFile {
+        let (status, body) = self.harness.bytes(request).await;
+
+        assert!(
+            status.is_success(),
+            "Failed creating the file '{}' ({}): {}",
+            self.name,
+            status,
+            String::from_utf8_lossy(&body)
+        );
+
+        serde_json::from_slice(&body).unwrap()
+    }
+}
+
+impl<S> Harness<S>
+where
+    S: Service<Request, Response = Response, Error = actix_web::Error>,
+{
+    /// Create the user with a session that is valid for the next few minutes
+    pub async fn user(&self, email: &str) -> TestUser {
+        let model = entity::mock::create_user(&self.context.db, email, None).await;
+
+        self.login(model).await
+    }
+
+    /// Create the user with the admin role
+    pub async fn admin(&self, email: &str) -> TestUser {
+        let model = entity::mock::create_user(&self.context.db, email, None).await;
+
+        let mut active_model: users::ActiveModel = model.into();
+        active_model.role = ActiveValue::Set(Some("admin".to_string()));
+        let model = active_model.update(&self.context.db).await.unwrap();
+
+        self.login(model).await
+    }
+
+    /// Start building the file of the user, with its name as the content
+    pub fn file<'h>(&'h self, owner: &'h TestUser, name: &str) -> FileBuilder<'h, S> {
+        FileBuilder {
+            harness: self,
+            owner,
+            name: name.to_string(),
+            mime: "text/plain".to_string(),
+            parent: None,
+            content: Some(name.as_bytes().to_vec()),
+            chunks: 1,
+            hashed: false,
+        }
+    }
+
+    /// Start building the directory of the user
+    pub fn dir<'h>(&'h self, owner: &'h TestUser, name: &str) -> FileBuilder<'h, S> {
+        FileBuilder {
+            mime: "dir".to_string(),
+            content: None,
+            ..self.file(owner, name)
+        }
+    }
+
+    /// Share the file with the user
+    pub async fn share(&self, file: &AppFile, user: &TestUser) -> user_files::Model {
+        let encrypted_key = cryptfns::base64::encode(user.model.email.as_str());
+
+        entity::mock::create_share(&self.context.db, file.id, &user.model, &encrypted_key).await
+    }
+
+    /// Public link of the file, inserted the way the owner's client leaves it after
+    /// encrypting the keys with the link key, without the password or any of the limits
+    pub async fn link(&self, file: &AppFile, owner: &TestUser) -> links::Model {
+        links::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            user_id: ActiveValue::Set(owner.id()),
+            file_id: ActiveValue::Set(file.id),
+            signature: ActiveValue::Set("signature".to_string()),
+            downloads: ActiveValue::Set(0),
+            encrypted_name: ActiveValue::Set(cryptfns::hex::encode(file.id.to_string())),
+            encrypted_link_key: ActiveValue::Set("encrypted-link-key".to_string()),
+            encrypted_thumbnail: ActiveValue::Set(None),
+            encrypted_file_key: ActiveValue::Set(Some("encrypted-file-key".to_string())),
+            created_at: ActiveValue::Set(now()),
+            starts_at: ActiveValue::Set(None),
+            expires_at: ActiveValue::Set(None),
+            watermark: ActiveValue::Set(None),
+            allowed_countries: ActiveValue::Set(None),
+            allowed_networks: ActiveValue::Set(None),
+            password: ActiveValue::Set(None),
+            max_downloads: ActiveValue::Set(None),
+            slug: ActiveValue::Set(None),
+        }
+        .insert(&self.context.db)
+        .await
+        .unwrap()
+    }
+
+    async fn login(&self, model: users::Model) -> TestUser {
+        let session =
+            entity::mock::create_session(&self.context.db, &model, None, None, false).await;
+        let authenticated = Authenticated::new(model.clone(), session.clone());
+        let cookie = auth::mock::session_cookie(&self.context, &authenticated);
+
+        TestUser {
+            model,
+            session,
+            cookie,
+        }
+    }
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,104 @@
+//! # Route test harness
+//!
+//! Starts the whole application the way the server runs it, with all of its routes and
+//! middlewares, on an in-memory SQLite database and a temporary data directory where the
+//! file chunks are stored. Route tests call it with the regular test requests and check
+//! the responses, the users, files and shares they need are seeded with the [fixtures].
+//!
+//! ```ignore
+//! let harness = harness::start().await;
+//! let user = harness.user("john@doe.com").await;
+//! let dir = harness.dir(&user, "documents").create().await;
+//!
+//! let (status, body) = harness.json(user.get("/api/storage").to_request()).await;
+//! ```
+pub mod fixtures;
+
+use std::path::PathBuf;
+
+use actix_http::Request;
+use actix_web::{
+    body::{BoxBody, EitherBody},
+    dev::{Service, ServiceResponse},
+    http::StatusCode,
+    test,
+    web::Bytes,
+};
+use context::Context;
+use entity::Uuid;
+use serde_json::Value;
+
+/// Response of the application service
+pub type Response = ServiceResponse<EitherBody<BoxBody>>;
+
+/// Running application with the context it was started with
+pub struct Harness<S> {
+    pub context: Context,
+    pub app: S,
+    data_dir: PathBuf,
+}
+
+/// Start the application on a fresh database and its own data directory,
+/// so the tests can run at the same time without seeing each other's files
+pub async fn start() -> Harness<impl Service<Request, Response = Response, Error = actix_web::Error>>
+{
+    let data_dir = std::env::temp_dir().join(format!("hoodik-harness-{}", Uuid::new_v4()));
+
+    let context = Context::mock_with_data_dir(Some(data_dir.to_string_lossy().to_string())).await;
+    let app = test::init_service(hoodik::server::app(context.clone())).await;
+
+    Harness {
+        context,
+        app,
+        data_dir,
+    }
+}
+
+impl<S> Harness<S>
+where
+    S: Service<Request, Response = Response, Error = actix_web::Error>,
+{
+    /// Call the application with the request
+    pub async fn call(&self, req: Request) -> Response {
+        test::call_service(&self.app, req).await
+    }
+
+    /// Call the application and only get the status of the response
+    pub async fn status(&self, req: Request) -> StatusCode {
+        self.call(req).await.status()
+    }
+
+    /// Call the application and read the whole body of the response
+    pub async fn bytes(&self, req: Request) -> (StatusCode, Bytes) {
+        let response = self.call(req).await;
+        let status = response.status();
+
+        (status, test::read_body(response).await)
+    }
+
+    /// Call the application and read the body as JSON, empty body is read as `null`
+    pub async fn json(&self, req: Request) -> (StatusCode, Value) {
+        let (status, body) = self.bytes(req).await;
+
+        if body.is_empty() {
+            return (status, Value::Null);
+        }
+
+        let value = serde_json::from_slice(&body).unwrap_or_else(|e| {
+            panic!(
+                "Response with status {} is not JSON ({}): {}",
+                status,
+                e,
+                String::from_utf8_lossy(&body)
+            )
+        });
+
+        (status, value)
+    }
+}
+
+impl<S> Drop for Harness<S> {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
@@ -0,0 +1,66 @@
+use actix_web::{http::StatusCode, test::TestRequest};
+use entity::{links, ActiveModelTrait, ActiveValue, EntityTrait};
+use serde_json::json;
+
+#[actix_web::test]
+async fn test_landing_page_download_is_counted_once() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let file = harness
+        .file(&user, "report.txt")
+        .content(b"first-second-third")
+        .chunks(3)
+        .create()
+        .await;
+    assert_eq!(file.chunks, Some(3));
+
+    let link = harness.link(&file, &user).await;
+    let mut active_model: links::ActiveModel = link.into();
+    active_model.max_downloads = ActiveValue::Set(Some(1));
+    let link = active_model.update(&harness.context.db).await.unwrap();
+
+    let chunk = |chunk: i64| {
+        TestRequest::post()
+            .uri(&format!("/api/links/{}/chunks/{}", link.id, chunk))
+            .set_json(json!({}))
+    };
+
+    let response = harness.call(chunk(0).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let token = response
+        .headers()
+        .get("X-Download-Token")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        actix_web::test::read_body(response).await.as_ref(),
+        b"first-"
+    );
+
+    // The rest of the chunks belong to the download that used up the link
+    let (status, _) = harness.bytes(chunk(1).to_request()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    for (number, content) in [(1, b"second"), (2, b"-third")] {
+        let request = chunk(number)
+            .insert_header(("X-Download-Token", token.as_str()))
+            .to_request();
+        let (status, body) = harness.bytes(request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_ref(), content);
+    }
+
+    let link = links::Entity::find_by_id(link.id)
+        .one(&harness.context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(link.downloads, 1);
+
+    // Another download starts without the token and the link has none left
+    let (status, _) = harness.bytes(chunk(0).to_request()).await;
+    assert_eq!(status, StatusCode::GONE);
+}
@@ -0,0 +1,34 @@
+//! Profiling routes are only there with the `pprof` feature:
+//! `cargo test -p harness --features pprof`
+#![cfg(feature = "pprof")]
+
+use actix_web::{http::StatusCode, test::TestRequest};
+
+#[actix_web::test]
+async fn test_profiles_are_for_the_admins_only() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let mut uris = vec!["/api/admin/pprof/cpu?seconds=1"];
+
+    if cfg!(target_os = "linux") {
+        uris.push("/api/admin/pprof/heap");
+    }
+
+    for uri in uris {
+        assert_eq!(
+            harness.status(user.get(uri).to_request()).await,
+            StatusCode::FORBIDDEN,
+            "{}",
+            uri
+        );
+
+        let request = TestRequest::get().uri(uri).to_request();
+        assert_eq!(
+            harness.status(request).await,
+            StatusCode::UNAUTHORIZED,
+            "{}",
+            uri
+        );
+    }
+}
@@ -0,0 +1,2070 @@
+use actix_web::http::StatusCode;
+use entity::{users, ActiveModelTrait, ActiveValue};
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn test_listing_the_directories() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let dir = harness.dir(&user, "documents").create().await;
+    let file = harness
+        .file(&user, "notes.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+
+    let (status, root) = harness.json(user.get("/api/storage").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(root["children"].as_array().unwrap().len(), 1);
+    assert_eq!(root["children"][0]["id"], json!(dir.id));
+
+    let uri = format!("/api/storage?dir_id={}", dir.id);
+    let (status, listed) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["parents"][0]["id"], json!(dir.id));
+    assert_eq!(listed["children"][0]["id"], json!(file.id));
+    assert_eq!(listed["children"][0]["chunks_stored"], json!(1));
+
+    let other = harness.user("jane@doe.com").await;
+    let (status, root) = harness.json(other.get("/api/storage").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(root["children"].as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_listing_and_searching_by_the_page() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    harness.dir(&user, "documents").create().await;
+    harness.file(&user, "notes.txt").create().await;
+    harness.file(&user, "todo.txt").create().await;
+
+    let (status, page) = harness
+        .json(
+            user.get("/api/storage?sort=name_hash&limit=1&offset=1")
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["total"], json!(3));
+    assert_eq!(page["children"].as_array().unwrap().len(), 1);
+
+    let (status, dirs) = harness
+        .json(user.get("/api/storage?mime=dir").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dirs["total"], json!(1));
+
+    let (status, found) = harness
+        .json(
+            user.post("/api/storage/search")
+                .set_json(json!({
+                    "search_tokens_hashed": ["missing:1"],
+                    "limit": 10,
+                    "offset": 0,
+                }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found["total"], json!(0));
+    assert!(found["files"].as_array().unwrap().is_empty());
+
+    let (status, found) = harness
+        .json(
+            user.post("/api/storage/search")
+                .set_json(json!({ "kind": "dir" }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found["total"], json!(1));
+
+    let (status, _) = harness
+        .json(
+            user.post("/api/storage/search")
+                .set_json(json!({ "kind": "spreadsheet" }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _) = harness
+        .json(user.get("/api/storage?limit=0").to_request())
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_web::test]
+async fn test_malformed_requests_are_validation_errors() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let errors = |body: &Value, field: &str| body["context"]["errors"][field]["errors"].clone();
+
+    let (status, body) = harness
+        .json(user.get("/api/storage?dir_id=nope").to_request())
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(errors(&body, "dir_id"), json!(["invalid_uuid"]));
+
+    let (status, body) = harness
+        .json(user.get("/api/storage?order_by=name").to_request())
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!errors(&body, "order_by").is_null());
+
+    let (status, body) = harness
+        .json(user.get("/api/storage?dirs_only=maybe").to_request())
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!errors(&body, "query").is_null());
+
+    let (status, body) = harness
+        .json(
+            user.post("/api/storage")
+                .set_json(json!({ "size": "big" }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!errors(&body, "body").is_null());
+
+    let (status, body) = harness
+        .json(
+            user.post("/api/storage")
+                .set_json(json!({
+                    "encrypted_key": "key",
+                    "encrypted_name": "name",
+                    "name_hash": "hash",
+                    "mime": "dir",
+                    "file_id": "nope",
+                }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(errors(&body, "file_id"), json!(["invalid_uuid"]));
+
+    let (status, body) = harness
+        .json(
+            user.post("/api/storage/search")
+                .insert_header(("Accept", "application/x-ndjson"))
+                .set_json(json!({ "dir_id": "nope" }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(errors(&body, "dir_id"), json!(["invalid_uuid"]));
+}
+
+#[actix_web::test]
+async fn test_creating_and_downloading_the_file() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let request = user.post("/api/storage").set_json(json!({})).to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let file = harness
+        .file(&user, "notes.txt")
+        .content(b"encrypted notes")
+        .create()
+        .await;
+    assert!(file.finished_upload_at.is_some());
+
+    let uri = format!("/api/storage/{}", file.id);
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"encrypted notes");
+
+    assert_eq!(
+        harness.status(user.head(&uri).to_request()).await,
+        StatusCode::OK
+    );
+
+    let (status, metadata) = harness
+        .json(user.get(&format!("{}/metadata", uri)).to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(metadata["id"], json!(file.id));
+
+    let other = harness.user("jane@doe.com").await;
+    assert_eq!(
+        harness.status(other.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        harness
+            .status(other.get(&format!("{}/metadata", uri)).to_request())
+            .await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_replacing_the_content() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness
+        .file(&user, "todo.txt")
+        .content(b"first")
+        .create()
+        .await;
+
+    let uri = format!("/api/storage/{}/content", file.id);
+    let response = harness.call(user.get(&uri).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let version = response.headers().get("ETag").unwrap().to_str().unwrap();
+    let version = version.trim_matches('"').to_string();
+    let body = actix_web::test::read_body(response).await;
+    assert_eq!(body.as_ref(), b"first");
+
+    let request = user
+        .put(&format!("{}?size=6", uri))
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .set_payload("second")
+        .to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let request = user
+        .put(&format!("{}?size=6&version={}", uri, version))
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .set_payload("second")
+        .to_request();
+    let (status, content) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content["file"]["id"], json!(file.id));
+
+    let (_, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(body.as_ref(), b"second");
+
+    // Writing over the content someone else already changed
+    let request = user
+        .put(&format!("{}?size=5&version={}", uri, version))
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .set_payload("third")
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::CONFLICT);
+
+    let uri = format!("/api/storage/{}/versions", file.id);
+    let (status, versions) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(versions.as_array().unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn test_renaming_and_tagging_the_file() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness.file(&user, "draft.txt").create().await;
+
+    let renames = [
+        (format!("/api/storage/{}", file.id), "final.txt"),
+        (
+            format!("/api/storage/{}/rename", file.id),
+            "final-final.txt",
+        ),
+    ];
+
+    for (uri, name) in renames {
+        let rename = json!({
+            "name_hash": cryptfns::sha256::digest(name.as_bytes()),
+            "encrypted_name": cryptfns::hex::encode(name),
+        });
+
+        let (status, renamed) = harness
+            .json(user.put(&uri).set_json(&rename).to_request())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(renamed["name_hash"], rename["name_hash"]);
+    }
+
+    let uri = format!("/api/storage/{}/attributes", file.id);
+    let request = user
+        .patch(&uri)
+        .set_json(json!({ "attributes": { "color": "red" } }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_looking_up_the_name_hashes() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    let file = harness
+        .file(&user, "notes.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+
+    let uri = format!(
+        "/api/storage/{}/name-hash?parent_id={}",
+        file.name_hash, dir.id
+    );
+    let (status, found) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found["id"], json!(file.id));
+
+    let request = user
+        .post("/api/storage/name-hashes")
+        .set_json(json!({ "parent_id": dir.id, "name_hashes": [file.name_hash] }))
+        .to_request();
+    let (status, matches) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(matches[0]["id"], json!(file.id));
+}
+
+#[actix_web::test]
+async fn test_moving_and_copying_the_files() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let from = harness.dir(&user, "from").create().await;
+    let to = harness.dir(&user, "to").create().await;
+    let first = harness
+        .file(&user, "first.txt")
+        .parent(from.id)
+        .create()
+        .await;
+    let second = harness
+        .file(&user, "second.txt")
+        .parent(from.id)
+        .create()
+        .await;
+
+    let request = user
+        .post(&format!("/api/storage/{}/move", first.id))
+        .set_json(json!({ "file_id": to.id }))
+        .to_request();
+    let (status, moved) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(moved["file_id"], json!(to.id));
+
+    let request = user
+        .post("/api/storage/move-many")
+        .set_json(json!({ "ids": [second.id], "file_id": to.id }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+
+    let request = user
+        .post(&format!("/api/storage/{}/copy", to.id))
+        .set_json(json!({ "file_id": from.id }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    let uri = format!("/api/storage?dir_id={}", to.id);
+    let (_, listed) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(listed["children"].as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn test_running_the_batch() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    let file = harness.file(&user, "notes.txt").create().await;
+    let other = harness.file(&user, "other.txt").create().await;
+
+    let request = user
+        .post("/api/storage/batch")
+        .set_json(json!({
+            "actions": [
+                { "action": "move", "ids": [file.id], "file_id": dir.id },
+                { "action": "delete", "ids": [other.id] },
+            ]
+        }))
+        .to_request();
+    let (status, results) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results.as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn test_deleting_and_restoring_from_the_trash() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness.file(&user, "notes.txt").create().await;
+    let others = [
+        harness.file(&user, "first.txt").create().await,
+        harness.file(&user, "second.txt").create().await,
+    ];
+
+    let uri = format!("/api/storage/{}", file.id);
+    assert_eq!(
+        harness.status(user.delete(&uri).to_request()).await,
+        StatusCode::ACCEPTED
+    );
+
+    let (status, trash) = harness
+        .json(user.get("/api/storage/trash").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(trash[0]["id"], json!(file.id));
+
+    let restore = format!("/api/storage/trash/{}/restore", file.id);
+    assert_eq!(
+        harness.status(user.post(&restore).to_request()).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::OK
+    );
+
+    harness.status(user.delete(&uri).to_request()).await;
+    let purge = format!("/api/storage/trash/{}", file.id);
+    assert_eq!(
+        harness.status(user.delete(&purge).to_request()).await,
+        StatusCode::ACCEPTED
+    );
+
+    let request = user
+        .post("/api/storage/delete-many")
+        .set_json(json!({ "ids": [others[0].id, others[1].id] }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::ACCEPTED);
+
+    assert_eq!(
+        harness
+            .status(user.delete("/api/storage/trash").to_request())
+            .await,
+        StatusCode::ACCEPTED
+    );
+
+    let (_, trash) = harness
+        .json(user.get("/api/storage/trash").to_request())
+        .await;
+    assert!(trash.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_sharing_the_file() {
+    let harness = harness::start().await;
+    let owner = harness.user("john@doe.com").await;
+    let user = harness.user("jane@doe.com").await;
+    let file = harness
+        .file(&owner, "shared.txt")
+        .content(b"shared content")
+        .create()
+        .await;
+
+    harness.share(&file, &user).await;
+
+    let uri = format!("/api/storage/{}", file.id);
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"shared content");
+
+    let shares = format!("/api/storage/{}/shares", file.id);
+    let (status, listed) = harness.json(owner.get(&shares).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed[0]["user_id"], json!(user.id()));
+
+    // Only the owner can delete the file
+    assert_eq!(
+        harness.status(user.delete(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+
+    let revoke = format!("{}/{}", shares, user.id());
+    assert_eq!(
+        harness.status(owner.delete(&revoke).to_request()).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_uploading_into_the_shared_folder() {
+    let harness = harness::start().await;
+    let owner = harness.user("john@doe.com").await;
+    let writer = harness.user("jane@doe.com").await;
+    let reader = harness.user("jim@doe.com").await;
+    let dir = harness.dir(&owner, "team").create().await;
+
+    let shares = format!("/api/storage/{}/shares", dir.id);
+
+    for (user, permission) in [(&writer, "write"), (&reader, "read")] {
+        let data = json!({
+            "user_id": user.id(),
+            "encrypted_key": "key",
+            "permission": permission,
+        });
+        let (status, share) = harness
+            .json(owner.post(&shares).set_json(&data).to_request())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(share["permission"], json!(permission));
+    }
+
+    let file = harness
+        .file(&writer, "notes.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+    assert!(file.finished_upload_at.is_some());
+
+    // Reading users can't upload into the folder or share it further
+    let data = json!({
+        "encrypted_key": cryptfns::base64::encode("report"),
+        "encrypted_name": cryptfns::hex::encode("report"),
+        "name_hash": cryptfns::sha256::digest(b"report"),
+        "mime": "text/plain",
+        "size": 10,
+        "chunks": 1,
+        "file_id": dir.id,
+    });
+    assert_eq!(
+        harness
+            .status(reader.post("/api/storage").set_json(&data).to_request())
+            .await,
+        StatusCode::BAD_REQUEST
+    );
+
+    let data = json!({ "user_id": owner.id(), "encrypted_key": "key" });
+    assert_eq!(
+        harness
+            .status(writer.post(&shares).set_json(&data).to_request())
+            .await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_following_the_changes() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    harness
+        .file(&user, "notes.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+
+    for uri in [
+        "/api/storage/root/changes".to_string(),
+        format!("/api/storage/{}/changes", dir.id),
+        "/api/journal".to_string(),
+    ] {
+        let (status, body) = harness.json(user.get(&uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert!(body.is_object(), "{}", uri);
+    }
+}
+
+#[actix_web::test]
+async fn test_directory_snapshot_manifest_and_zip() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    let file = harness
+        .file(&user, "notes.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+
+    let snapshot = format!("/api/storage/{}/snapshot", dir.id);
+    assert_eq!(
+        harness.status(user.get(&snapshot).to_request()).await,
+        StatusCode::OK
+    );
+
+    let manifest = format!("/api/storage/{}/manifest", file.id);
+    assert_eq!(
+        harness.status(user.get(&manifest).to_request()).await,
+        StatusCode::OK
+    );
+
+    let request = user
+        .post("/api/storage/manifest")
+        .set_json(json!({ "ids": [file.id] }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    let zip = format!("/api/storage/{}/zip", dir.id);
+    let (status, body) = harness.bytes(user.get(&zip).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.starts_with(b"PK"));
+}
+
+#[actix_web::test]
+async fn test_folder_policy() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    let file = harness.file(&user, "notes.txt").create().await;
+
+    let uri = format!("/api/storage/{}/policy", dir.id);
+    let request = user
+        .put(&uri)
+        .set_json(json!({ "links_disabled": true, "link_expires_in_days": 7 }))
+        .to_request();
+    let (status, policy) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(policy["effective"]["links_disabled"], json!(true));
+
+    let (status, policy) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(policy["effective"]["link_expires_in_days"], json!(7));
+
+    assert_eq!(
+        harness.status(user.delete(&uri).to_request()).await,
+        StatusCode::NO_CONTENT
+    );
+
+    let uri = format!("/api/storage/{}/policy", file.id);
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+
+    // Users who manage the shared file can't share it any further
+    let manager = harness.user("jane@doe.com").await;
+    let other = harness.user("jim@doe.com").await;
+    let inner = harness
+        .file(&user, "todo.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+    let shares = format!("/api/storage/{}/shares", inner.id);
+    let share = |to: &harness::fixtures::TestUser, permission: &str| {
+        json!({
+            "user_id": to.id(),
+            "encrypted_key": cryptfns::base64::encode(to.model.email.as_str()),
+            "permission": permission,
+        })
+    };
+
+    let request = user
+        .post(&shares)
+        .set_json(share(&manager, "manage"))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    let uri = format!("/api/storage/{}/policy", dir.id);
+    let request = user
+        .put(&uri)
+        .set_json(json!({ "links_require_password": true, "resharing_disabled": true }))
+        .to_request();
+    let (status, policy) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(policy["effective"]["links_require_password"], json!(true));
+
+    let request = manager
+        .post(&shares)
+        .set_json(share(&other, "read"))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::FORBIDDEN);
+
+    let request = user
+        .post(&shares)
+        .set_json(share(&other, "read"))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_thumbnails_and_versions() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness
+        .file(&user, "photo.jpg")
+        .mime("image/jpeg")
+        .create()
+        .await;
+
+    let uri = format!("/api/storage/{}/thumbnail", file.id);
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+
+    let request = user.post(&uri).set_payload("thumbnail").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"thumbnail");
+
+    let uri = format!("/api/storage/{}/versions", file.id);
+    let (status, versions) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(versions.is_array());
+}
+
+#[actix_web::test]
+async fn test_file_is_replaced_when_the_upload_under_its_name_finishes() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let existing = harness
+        .file(&user, "report.txt")
+        .content(b"first")
+        .create()
+        .await;
+
+    // Abandoned upload under the same name leaves the existing file as it was
+    let abandoned = harness
+        .file(&user, "report.txt")
+        .unfinished()
+        .create()
+        .await;
+    assert_ne!(abandoned.id, existing.id);
+
+    let uri = format!("/api/storage/{}", abandoned.id);
+    harness.status(user.delete(&uri).to_request()).await;
+    let purge = format!("/api/storage/trash/{}", abandoned.id);
+    assert_eq!(
+        harness.status(user.delete(&purge).to_request()).await,
+        StatusCode::ACCEPTED
+    );
+
+    let content = format!("/api/storage/{}/content", existing.id);
+    let (status, body) = harness.bytes(user.get(&content).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"first");
+
+    // Finished upload takes the place of the existing file
+    let file = harness
+        .file(&user, "report.txt")
+        .content(b"second")
+        .create()
+        .await;
+
+    let (_, root) = harness.json(user.get("/api/storage").to_request()).await;
+    let children = root["children"].as_array().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0]["id"], json!(file.id));
+
+    let uri = format!("/api/storage/{}/versions", file.id);
+    let (status, versions) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(versions[0]["id"], json!(existing.id));
+}
+
+#[actix_web::test]
+async fn test_virtual_files_search_dedup_and_stats() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "bookmarks").create().await;
+
+    let request = user
+        .post("/api/storage/virtual")
+        .set_json(json!({
+            "kind": "url",
+            "encrypted_key": cryptfns::base64::encode("key"),
+            "name_hash": cryptfns::sha256::digest("hoodik".as_bytes()),
+            "encrypted_name": cryptfns::hex::encode("hoodik"),
+            "encrypted_payload": cryptfns::base64::encode("https://hoodik.io"),
+            "file_id": dir.id,
+        }))
+        .to_request();
+    let (status, link) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(link["file_id"], json!(dir.id));
+
+    let request = user
+        .post("/api/storage/search")
+        .set_json(json!({ "dir_id": dir.id }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    let request = user
+        .post("/api/storage/dedup")
+        .set_json(json!({ "sha256": cryptfns::sha256::digest("missing".as_bytes()), "size": 7 }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NOT_FOUND);
+
+    let (status, stats) = harness
+        .json(user.post("/api/storage/stats").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(stats["stats"].is_array());
+}
+
+#[actix_web::test]
+async fn test_file_requests() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "inbox").create().await;
+
+    let request = user
+        .post("/api/file-requests")
+        .set_json(json!({ "file_id": dir.id }))
+        .to_request();
+    let (status, created) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    let id = created["request"]["id"].as_str().unwrap().to_string();
+
+    let (status, listed) = harness
+        .json(user.get("/api/file-requests").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    // The request is public, it is opened by the ones that upload to it
+    let request = actix_web::test::TestRequest::get()
+        .uri(&format!("/api/file-requests/{}", id))
+        .to_request();
+    let (status, public) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(public["open"], json!(true));
+
+    let uri = format!("/api/file-requests/{}", id);
+    assert_eq!(
+        harness.status(user.delete(&uri).to_request()).await,
+        StatusCode::OK
+    );
+}
+
+#[actix_web::test]
+async fn test_folder_templates() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let request = user
+        .post("/api/folder-templates")
+        .set_json(json!({ "encrypted_name": "name", "encrypted_tree": "tree" }))
+        .to_request();
+    let (status, template) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, listed) = harness
+        .json(user.get("/api/folder-templates").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed[0]["id"], template["id"]);
+
+    let uri = format!("/api/folder-templates/{}", template["id"].as_str().unwrap());
+    assert_eq!(
+        harness.status(user.delete(&uri).to_request()).await,
+        StatusCode::NO_CONTENT
+    );
+}
+
+#[actix_web::test]
+async fn test_exports_and_the_network_test() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let (status, exports) = harness.json(user.get("/api/exports").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(exports.as_array().unwrap().is_empty());
+
+    let (status, body) = harness
+        .bytes(user.get("/api/net-test?size=1024").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.len(), 1024);
+
+    let request = actix_web::test::TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/api/storage/tus")
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+}
+
+#[actix_web::test]
+async fn test_mounting_the_drive_with_webdav() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    let file = harness
+        .file(&user, "notes.txt")
+        .parent(dir.id)
+        .content(b"encrypted notes")
+        .create()
+        .await;
+
+    let request = user.method("OPTIONS", "/api/dav/").to_request();
+    let response = harness.call(request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("DAV").unwrap(), "1");
+
+    let request = user
+        .method("PROPFIND", "/api/dav/")
+        .insert_header(("Depth", "1"))
+        .to_request();
+    let (status, body) = harness.bytes(request).await;
+    let body = String::from_utf8_lossy(&body);
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(body.contains(&format!("<d:href>/api/dav/{}/</d:href>", dir.id)));
+
+    let request = user
+        .method("PROPFIND", "/api/dav/documents")
+        .insert_header(("Depth", "1"))
+        .to_request();
+    let (status, body) = harness.bytes(request).await;
+    let body = String::from_utf8_lossy(&body);
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(body.contains(&format!("<d:href>/api/dav/documents/{}</d:href>", file.id)));
+    assert!(body.contains("<d:getcontentlength>15</d:getcontentlength>"));
+
+    let (status, body) = harness
+        .bytes(user.get("/api/dav/documents/notes.txt").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"encrypted notes");
+
+    assert_eq!(
+        harness
+            .status(user.get("/api/dav/documents").to_request())
+            .await,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+
+    // Moving keeps the name, the new name couldn't be encrypted
+    let request = user
+        .method("MOVE", "/api/dav/documents/notes.txt")
+        .insert_header(("Destination", "http://localhost/api/dav/todo.txt"))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+    let request = user
+        .method("MOVE", "/api/dav/documents/notes.txt")
+        .insert_header(("Destination", "http://localhost/api/dav/notes.txt"))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::CREATED);
+    assert_eq!(
+        harness
+            .status(user.get(&format!("/api/dav/{}", file.id)).to_request())
+            .await,
+        StatusCode::OK
+    );
+
+    let request = user.delete("/api/dav/notes.txt").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+    assert_eq!(
+        harness
+            .status(user.get("/api/dav/notes.txt").to_request())
+            .await,
+        StatusCode::NOT_FOUND
+    );
+
+    // New files and folders are encrypted with the public key of the user
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let mut model: users::ActiveModel = user.model.clone().into();
+    model.pubkey = ActiveValue::Set(cryptfns::rsa::public::to_string(&public_key).unwrap());
+    model.update(&harness.context.db).await.unwrap();
+
+    let request = user.method("MKCOL", "/api/dav/photos").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::CREATED);
+    let request = user.method("MKCOL", "/api/dav/photos").to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+
+    let request = user.put("/api/dav/photos/cat.txt").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::LENGTH_REQUIRED);
+
+    let request = user
+        .put("/api/dav/photos/cat.txt")
+        .insert_header(("Content-Length", "4"))
+        .set_payload("meow")
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::CREATED);
+
+    let (status, body) = harness
+        .bytes(user.get("/api/dav/photos/cat.txt").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(body.as_ref(), b"meow");
+
+    let request = user.head("/api/dav/photos/cat.txt").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    // Named by its plain name the file is replaced, the old one goes to the trash
+    let request = user
+        .put("/api/dav/photos/cat.txt")
+        .insert_header(("Content-Length", "4"))
+        .set_payload("purr")
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+
+    let (status, trash) = harness
+        .json(user.get("/api/storage/trash").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(trash.as_array().unwrap().len(), 2);
+
+    let request = user.method("MOVE", "/api/dav/photos/cat.txt").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+    let request = user
+        .method("MOVE", "/api/dav/photos/cat.txt")
+        .insert_header(("Destination", "http://localhost/api/dav/missing/cat.txt"))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::CONFLICT);
+
+    let request = user.method("PROPFIND", "/api/dav/missing").to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_transfers_are_counted_and_limited() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness
+        .file(&user, "notes.txt")
+        .content(b"encrypted notes")
+        .create()
+        .await;
+
+    let uri = format!("/api/storage/{}", file.id);
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"encrypted notes");
+
+    // The transfer is recorded in the background once the response is sent
+    let mut downloaded = Value::Null;
+    for _ in 0..50 {
+        let (status, stats) = harness
+            .json(user.post("/api/storage/stats").to_request())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        downloaded = stats["transfers"][0]["downloaded_bytes"].clone();
+        if downloaded != Value::Null {
+            break;
+        }
+
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(downloaded, json!(15));
+
+    harness.context.settings.inner().await.users = serde_json::from_value(json!({
+        "transfer_bytes": 10,
+        "allow_register": true,
+        "enforce_email_activation": false,
+    }))
+    .unwrap();
+
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // Only the content is limited, the rest of the routes keep working
+    let (status, stats) = harness
+        .json(user.post("/api/storage/stats").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["transfer_limit"], json!(10));
+}
+
+#[actix_web::test]
+async fn test_uploading_in_a_single_request() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "photos").create().await;
+
+    let boundary = "harness-boundary";
+    let multipart = |parts: &[(&str, Vec<u8>)]| {
+        let mut body = vec![];
+
+        for (name, data) in parts {
+            body.extend(format!("--{}\r\n", boundary).into_bytes());
+            body.extend(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n",
+                    name, name
+                )
+                .into_bytes(),
+            );
+            body.extend(data);
+            body.extend(b"\r\n");
+        }
+
+        body.extend(format!("--{}--\r\n", boundary).into_bytes());
+
+        user.post("/api/storage/simple-upload")
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .set_payload(body)
+            .to_request()
+    };
+
+    let metadata = serde_json::to_vec(&json!({
+        "encrypted_key": cryptfns::base64::encode("cat.jpg"),
+        "encrypted_name": cryptfns::hex::encode("cat.jpg"),
+        "name_hash": cryptfns::sha256::digest("cat.jpg".as_bytes()),
+        "mime": "image/jpeg",
+        "size": 4,
+        "chunks": 1,
+        "file_id": dir.id,
+    }))
+    .unwrap();
+
+    let request = multipart(&[("file", metadata.clone())]);
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let request = multipart(&[("file", metadata), ("content", b"meow".to_vec())]);
+    let (status, file) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(file["file_id"], json!(dir.id));
+    assert!(file["finished_upload_at"].is_number());
+
+    let uri = format!("/api/storage/{}", file["id"].as_str().unwrap());
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"meow");
+}
+
+#[actix_web::test]
+async fn test_resuming_the_upload_with_tus() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness.file(&user, "notes.txt").unfinished().create().await;
+    let content = b"encrypted notes";
+
+    let metadata = [("file_id", file.id.to_string()), ("chunk", "0".to_string())]
+        .iter()
+        .map(|(key, value)| format!("{} {}", key, cryptfns::base64::encode(value)))
+        .collect::<Vec<_>>()
+        .join(",");
+    let create = || {
+        user.post("/api/storage/tus")
+            .insert_header(("Upload-Length", content.len().to_string()))
+            .insert_header(("Upload-Metadata", metadata.as_str()))
+    };
+
+    // Only the version of the protocol the server speaks
+    let request = create().to_request();
+    assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+    let request = create()
+        .insert_header(("Tus-Resumable", "1.0.0"))
+        .to_request();
+    let response = harness.call(request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers().get("Location").unwrap();
+    let location = location.to_str().unwrap().to_string();
+
+    let patch = |offset: usize, data: &[u8]| {
+        user.patch(&location)
+            .insert_header(("Tus-Resumable", "1.0.0"))
+            .insert_header(("Content-Type", "application/offset+octet-stream"))
+            .insert_header(("Upload-Offset", offset.to_string()))
+            .set_payload(data.to_vec())
+            .to_request()
+    };
+    let head = |user: &harness::fixtures::TestUser| {
+        user.head(&location)
+            .insert_header(("Tus-Resumable", "1.0.0"))
+            .to_request()
+    };
+
+    let response = harness.call(patch(0, &content[..9])).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers().get("Upload-Offset").unwrap(), "9");
+
+    // Same bytes sent again are refused
+    assert_eq!(
+        harness.status(patch(0, &content[..9])).await,
+        StatusCode::CONFLICT
+    );
+
+    let request = user
+        .patch(&location)
+        .insert_header(("Tus-Resumable", "1.0.0"))
+        .insert_header(("Upload-Offset", "9"))
+        .set_payload(content[9..].to_vec())
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+    let response = harness.call(head(&user)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("Upload-Offset").unwrap(), "9");
+    assert_eq!(response.headers().get("Upload-Length").unwrap(), "15");
+
+    let other = harness.user("jane@doe.com").await;
+    assert_eq!(harness.status(head(&other)).await, StatusCode::NOT_FOUND);
+
+    assert_eq!(
+        harness.status(patch(9, &content[9..])).await,
+        StatusCode::NO_CONTENT
+    );
+
+    // Finished upload is gone, the chunk is stored with the file
+    assert_eq!(harness.status(head(&user)).await, StatusCode::NOT_FOUND);
+
+    let uri = format!("/api/storage/{}", file.id);
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), content);
+}
+
+#[actix_web::test]
+async fn test_uploading_over_the_socket() {
+    use actix_codec::{Decoder, Encoder};
+    use actix_http::ws::{Codec, Frame, Message};
+    use actix_web::web::BytesMut;
+
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness.file(&user, "notes.txt").unfinished().create().await;
+    let uri = format!("/api/storage/{}/socket", file.id);
+
+    let handshake = |user: &harness::fixtures::TestUser| {
+        user.get(&uri)
+            .insert_header(("Upgrade", "websocket"))
+            .insert_header(("Connection", "Upgrade"))
+            .insert_header(("Sec-WebSocket-Version", "13"))
+            .insert_header(("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+    };
+
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    let other = harness.user("jane@doe.com").await;
+    assert_eq!(
+        harness.status(handshake(&other).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+
+    let messages = vec![
+        Message::Text(json!({ "chunk": 0 }).to_string().into()),
+        Message::Binary(b"encrypted notes".to_vec().into()),
+        Message::Close(None),
+    ];
+
+    let mut client = Codec::new().client_mode();
+    let mut payload = BytesMut::new();
+    for message in messages {
+        client.encode(message, &mut payload).unwrap();
+    }
+
+    let request = handshake(&user).set_payload(payload.freeze()).to_request();
+    let (status, body) = harness.bytes(request).await;
+    assert_eq!(status, StatusCode::SWITCHING_PROTOCOLS);
+
+    let mut body = BytesMut::from(&body[..]);
+    let mut acks = vec![];
+
+    while let Some(frame) = client.decode(&mut body).unwrap() {
+        match frame {
+            Frame::Text(text) => acks.push(serde_json::from_slice::<Value>(&text).unwrap()),
+            Frame::Close(_) => break,
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+
+    assert_eq!(acks.len(), 1);
+    assert_eq!(acks[0]["chunk"], json!(0));
+    assert!(acks[0]["file"]["finished_upload_at"].is_number());
+
+    let uri = format!("/api/storage/{}", file.id);
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"encrypted notes");
+}
+
+#[actix_web::test]
+async fn test_group_spaces() {
+    let harness = harness::start().await;
+    let admin = harness.admin("admin@doe.com").await;
+    let owner = harness.user("john@doe.com").await;
+    let viewer = harness.user("jane@doe.com").await;
+
+    let (status, group) = harness
+        .json(
+            admin
+                .post("/api/admin/groups")
+                .set_json(json!({ "name": "team" }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let group_id = group["id"].as_str().unwrap().to_string();
+
+    for (user, role) in [(&owner, "owner"), (&viewer, "viewer")] {
+        let uri = format!("/api/admin/groups/{}/members/{}", group_id, user.id());
+        let request = admin
+            .put(&uri)
+            .set_json(json!({ "role": role }))
+            .to_request();
+        assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+    }
+
+    let dir = harness.dir(&owner, "team").create().await;
+    let data = json!({ "group_id": group_id, "file_id": dir.id });
+
+    let request = owner.post("/api/spaces").set_json(json!({})).to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    // Only the owners of the group create its spaces
+    let request = viewer.post("/api/spaces").set_json(&data).to_request();
+    assert_eq!(harness.status(request).await, StatusCode::FORBIDDEN);
+
+    let request = owner.post("/api/spaces").set_json(&data).to_request();
+    let (status, space) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(space["file_id"], json!(dir.id));
+    assert_eq!(space["role"], json!("owner"));
+
+    let request = owner.post("/api/spaces").set_json(&data).to_request();
+    assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+    let (status, spaces) = harness.json(viewer.get("/api/spaces").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(spaces[0]["id"], space["id"]);
+    assert_eq!(spaces[0]["group_name"], json!("team"));
+    assert_eq!(spaces[0]["role"], json!("viewer"));
+
+    let uri = format!("/api/spaces/{}", space["id"].as_str().unwrap());
+    assert_eq!(
+        harness.status(viewer.delete(&uri).to_request()).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        harness.status(owner.delete(&uri).to_request()).await,
+        StatusCode::NO_CONTENT
+    );
+
+    let (_, spaces) = harness.json(owner.get("/api/spaces").to_request()).await;
+    assert!(spaces.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_imports_and_external_exports() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let other = harness.user("jane@doe.com").await;
+    let dir = harness.dir(&user, "backup").create().await;
+
+    for (uri, data) in [
+        (
+            "/api/imports",
+            json!({ "source": "webdav", "location": "https://cloud.example.com/dav" }),
+        ),
+        (
+            "/api/external-exports",
+            json!({
+                "target": "s3",
+                "location": "https://s3.example.com/bucket",
+                "access_key_id": "key",
+                "secret_access_key": "secret",
+                "file_id": dir.id,
+            }),
+        ),
+    ] {
+        let request = user
+            .post(uri)
+            .set_json(json!({ "location": "nope" }))
+            .to_request();
+        assert_eq!(
+            harness.status(request).await,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            uri
+        );
+
+        // Jobs are run by the background worker, they wait here until they are cancelled
+        let (status, job) = harness
+            .json(user.post(uri).set_json(&data).to_request())
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(job["status"], json!("pending"));
+        assert!(job.get("credentials").is_none());
+
+        let request = user.post(uri).set_json(&data).to_request();
+        assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+        let (status, jobs) = harness.json(user.get(uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(jobs[0]["id"], job["id"]);
+
+        let job_uri = format!("{}/{}", uri, job["id"].as_str().unwrap());
+        assert_eq!(
+            harness.status(other.get(&job_uri).to_request()).await,
+            StatusCode::NOT_FOUND
+        );
+
+        let (status, cancelled) = harness.json(user.delete(&job_uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cancelled["status"], json!("cancelled"));
+
+        let (status, job) = harness.json(user.get(&job_uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(job["status"], json!("cancelled"));
+
+        assert_eq!(
+            harness.status(user.delete(&job_uri).to_request()).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_access_keys() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+
+    let request = user
+        .post("/api/access-keys")
+        .set_json(json!({}))
+        .to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let (status, created) = harness
+        .json(
+            user.post("/api/access-keys")
+                .set_json(json!({ "name": "rclone" }))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!created["secret_access_key"].as_str().unwrap().is_empty());
+
+    // Secret is only in the response of the create
+    let (status, keys) = harness
+        .json(user.get("/api/access-keys").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(keys[0]["id"], created["access_key"]["id"]);
+    assert_eq!(keys[0]["name"], json!("rclone"));
+    assert!(keys[0].get("secret_access_key").is_none());
+
+    let uri = format!(
+        "/api/access-keys/{}",
+        created["access_key"]["id"].as_str().unwrap()
+    );
+    let other = harness.user("jane@doe.com").await;
+    assert_eq!(
+        harness.status(other.delete(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        harness.status(user.delete(&uri).to_request()).await,
+        StatusCode::NO_CONTENT
+    );
+
+    let (_, keys) = harness
+        .json(user.get("/api/access-keys").to_request())
+        .await;
+    assert!(keys.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_rewrapping_the_keys_after_the_revoke() {
+    let harness = harness::start().await;
+    let owner = harness.user("john@doe.com").await;
+    let kept = harness.user("jane@doe.com").await;
+    let revoked = harness.user("jack@doe.com").await;
+    let file = harness.file(&owner, "shared.txt").create().await;
+
+    harness.share(&file, &kept).await;
+    harness.share(&file, &revoked).await;
+
+    let uri = format!("/api/storage/{}/shares/{}", file.id, revoked.id());
+    let (status, job) = harness.json(owner.delete(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        harness
+            .status(
+                revoked
+                    .get(&format!("/api/storage/{}", file.id))
+                    .to_request()
+            )
+            .await,
+        StatusCode::NOT_FOUND
+    );
+
+    let (status, jobs) = harness
+        .json(owner.get("/api/rewrap-jobs").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(jobs[0]["id"], job["id"]);
+    assert_eq!(jobs[0]["pending"], json!(2));
+
+    let uri = format!("/api/rewrap-jobs/{}", job["id"].as_str().unwrap());
+    assert_eq!(
+        harness.status(kept.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+
+    let (status, worklist) = harness.json(owner.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = worklist["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries
+        .iter()
+        .all(|entry| entry["user_id"] != json!(revoked.id())));
+
+    let request = owner.post(&uri).set_json(json!({})).to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let rewrapped = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "user_file_id": entry["user_file_id"],
+                "encrypted_key": "rewrapped",
+            })
+        })
+        .collect::<Vec<_>>();
+    let request = owner
+        .post(&uri)
+        .set_json(json!({ "entries": rewrapped }))
+        .to_request();
+    let (status, done) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(done["pending"], json!(0));
+    assert!(done["completed_at"].is_number());
+
+    let (_, jobs) = harness
+        .json(owner.get("/api/rewrap-jobs").to_request())
+        .await;
+    assert!(jobs.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_uploading_many_files_at_once() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "photos").create().await;
+
+    let boundary = "harness-boundary";
+    let multipart = |parts: &[(&str, Vec<u8>)]| {
+        let mut body = vec![];
+
+        for (name, data) in parts {
+            body.extend(format!("--{}\r\n", boundary).into_bytes());
+            body.extend(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n",
+                    name, name
+                )
+                .into_bytes(),
+            );
+            body.extend(data);
+            body.extend(b"\r\n");
+        }
+
+        body.extend(format!("--{}--\r\n", boundary).into_bytes());
+
+        user.post("/api/storage/bulk")
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .set_payload(body)
+            .to_request()
+    };
+    let file = |name: &str| {
+        json!({
+            "encrypted_key": cryptfns::base64::encode(name),
+            "encrypted_name": cryptfns::hex::encode(name),
+            "name_hash": cryptfns::sha256::digest(name.as_bytes()),
+            "mime": "image/jpeg",
+            "size": 4,
+            "chunks": 1,
+            "file_id": dir.id,
+        })
+    };
+
+    let files =
+        serde_json::to_vec(&json!({ "files": [file("cat.jpg"), file("dog.jpg")] })).unwrap();
+
+    // Every file has to receive its content
+    let request = multipart(&[("files", files.clone()), ("0", b"meow".to_vec())]);
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let (_, listed) = harness
+        .json(
+            user.get(&format!("/api/storage?dir_id={}", dir.id))
+                .to_request(),
+        )
+        .await;
+    assert!(listed["children"].as_array().unwrap().is_empty());
+
+    let request = multipart(&[
+        ("files", files),
+        ("0", b"meow".to_vec()),
+        ("1", b"woof".to_vec()),
+    ]);
+    let (status, created) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    let created = created.as_array().unwrap();
+    assert_eq!(created.len(), 2);
+
+    for (file, content) in created.iter().zip([b"meow", b"woof"]) {
+        assert_eq!(file["file_id"], json!(dir.id));
+        assert!(file["finished_upload_at"].is_number());
+
+        let uri = format!("/api/storage/{}", file["id"].as_str().unwrap());
+        let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_ref(), content);
+    }
+}
+
+#[actix_web::test]
+async fn test_resuming_the_upload_on_another_device() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness.file(&user, "notes.txt").unfinished().create().await;
+
+    let uri = format!("/api/storage/{}/resume", file.id);
+    let (status, state) = harness.json(user.post(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state["file"]["id"], json!(file.id));
+    assert_eq!(state["missing_chunks"], json!([0]));
+    let token = state["token"].as_str().unwrap().to_string();
+
+    // The token is redeemed with another session of the same user only
+    let other = harness.user("jane@doe.com").await;
+    let redeem = |user: &harness::fixtures::TestUser| {
+        user.post("/api/storage/resume")
+            .set_json(json!({ "token": token }))
+            .to_request()
+    };
+    assert_eq!(
+        harness.status(redeem(&other)).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    let request = user
+        .post("/api/storage/resume")
+        .set_json(json!({}))
+        .to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let (status, redeemed) = harness.json(redeem(&user)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(redeemed["file"]["id"], json!(file.id));
+    assert_eq!(redeemed["missing_chunks"], json!([0]));
+    assert_eq!(redeemed["device"], state["device"]);
+
+    let request = user
+        .post(&format!("/api/storage/{}?chunk=0", file.id))
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .set_payload(b"notes".to_vec())
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    // Finished upload has nothing to resume
+    assert_eq!(harness.status(redeem(&user)).await, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        harness.status(user.post(&uri).to_request()).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn test_restoring_and_deleting_the_versions() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let first = harness
+        .file(&user, "report.txt")
+        .content(b"first")
+        .create()
+        .await;
+    let second = harness
+        .file(&user, "report.txt")
+        .content(b"second")
+        .create()
+        .await;
+
+    let other = harness.user("jane@doe.com").await;
+    let restore = format!("/api/storage/{}/versions/{}/restore", second.id, first.id);
+    assert_eq!(
+        harness.status(other.post(&restore).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+
+    let (status, restored) = harness.json(user.post(&restore).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(restored["id"], json!(first.id));
+
+    let uri = format!("/api/storage/{}", first.id);
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"first");
+
+    // Replaced file is kept as the version of the restored one
+    let versions = format!("/api/storage/{}/versions", first.id);
+    let (_, listed) = harness.json(user.get(&versions).to_request()).await;
+    assert_eq!(listed[0]["id"], json!(second.id));
+
+    let version = format!("/api/storage/{}/versions/{}", first.id, second.id);
+    assert_eq!(
+        harness.status(other.delete(&version).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        harness.status(user.delete(&version).to_request()).await,
+        StatusCode::ACCEPTED
+    );
+
+    let (_, listed) = harness.json(user.get(&versions).to_request()).await;
+    assert!(listed.as_array().unwrap().is_empty());
+    assert_eq!(
+        harness.status(user.delete(&version).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_listing_the_verdicts_of_the_file() {
+    let harness = harness::start().await;
+    let admin = harness.admin("admin@doe.com").await;
+    let user = harness.user("john@doe.com").await;
+    let file = harness.file(&user, "invoice.pdf").create().await;
+
+    let uri = format!("/api/storage/{}/verdicts", file.id);
+    let (status, verdicts) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(verdicts.as_array().unwrap().is_empty());
+
+    let request = admin
+        .put(&format!("/api/admin/files/{}/verdicts/clamav", file.id))
+        .set_json(json!({ "verdict": "infected", "details": "Eicar-Signature" }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::OK);
+
+    let (status, verdicts) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdicts[0]["scanner"], json!("clamav"));
+    assert_eq!(verdicts[0]["verdict"], json!("infected"));
+    assert_eq!(verdicts[0]["details"], json!("Eicar-Signature"));
+
+    // Only the owner sees why the file is blocked
+    let reader = harness.user("jane@doe.com").await;
+    harness.share(&file, &reader).await;
+    assert_eq!(
+        harness.status(reader.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_comparing_the_snapshots() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "backup").create().await;
+    let inner = harness.dir(&user, "photos").parent(dir.id).create().await;
+    harness
+        .file(&user, "notes.txt")
+        .parent(dir.id)
+        .create()
+        .await;
+
+    let (status, snapshot) = harness
+        .json(
+            user.get(&format!("/api/storage/{}/snapshot", dir.id))
+                .to_request(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let uri = format!("/api/storage/{}/snapshot/diff", dir.id);
+    let diff = |snapshot: &Value| {
+        user.post(&uri)
+            .set_json(json!({
+                "digest": snapshot["digest"],
+                "folders": snapshot["folders"],
+            }))
+            .to_request()
+    };
+
+    let (status, unchanged) = harness.json(diff(&snapshot)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(unchanged["changed"], json!(false));
+    assert!(unchanged["folders"].as_array().unwrap().is_empty());
+
+    harness
+        .file(&user, "cat.jpg")
+        .parent(inner.id)
+        .create()
+        .await;
+
+    // Only the folders on the way to the change have to be walked
+    let (status, changed) = harness.json(diff(&snapshot)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(changed["changed"], json!(true));
+    let mut folders = changed["folders"].as_array().unwrap().clone();
+    folders.sort_by_key(|id| id.as_str().unwrap().to_string());
+    let mut expected = vec![json!(dir.id), json!(inner.id)];
+    expected.sort_by_key(|id| id.as_str().unwrap().to_string());
+    assert_eq!(folders, expected);
+
+    let request = user.post(&uri).set_json(json!({})).to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let other = harness.user("jane@doe.com").await;
+    let request = other
+        .post(&uri)
+        .set_json(json!({ "digest": snapshot["digest"], "folders": [] }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_sharing_with_the_group() {
+    let harness = harness::start().await;
+    let admin = harness.admin("admin@doe.com").await;
+    let owner = harness.user("john@doe.com").await;
+    let member = harness.user("jane@doe.com").await;
+
+    let (_, group) = harness
+        .json(
+            admin
+                .post("/api/admin/groups")
+                .set_json(json!({ "name": "team" }))
+                .to_request(),
+        )
+        .await;
+    let group_id = group["id"].as_str().unwrap().to_string();
+
+    for (user, role) in [(&owner, "owner"), (&member, "viewer")] {
+        let uri = format!("/api/admin/groups/{}/members/{}", group_id, user.id());
+        let request = admin
+            .put(&uri)
+            .set_json(json!({ "role": role }))
+            .to_request();
+        assert_eq!(harness.status(request).await, StatusCode::NO_CONTENT);
+    }
+
+    let file = harness
+        .file(&owner, "plan.txt")
+        .content(b"the plan")
+        .create()
+        .await;
+    let uri = format!("/api/storage/{}/shares/groups", file.id);
+
+    // Keys have to be encrypted for every other member of the group
+    let request = owner
+        .post(&uri)
+        .set_json(json!({ "group_id": group_id, "members": [] }))
+        .to_request();
+    assert_eq!(
+        harness.status(request).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let request = owner
+        .post(&uri)
+        .set_json(json!({
+            "group_id": group_id,
+            "members": [{
+                "user_id": member.id(),
+                "encrypted_key": cryptfns::base64::encode(member.model.email.as_str()),
+            }],
+        }))
+        .to_request();
+    let (status, shares) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(shares.as_array().unwrap().len(), 1);
+    assert_eq!(shares[0]["user_id"], json!(member.id()));
+
+    let download = format!("/api/storage/{}", file.id);
+    let (status, body) = harness.bytes(member.get(&download).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"the plan");
+
+    // Users outside of the group can't share with it
+    let outsider = harness.user("jim@doe.com").await;
+    let other = harness.file(&outsider, "notes.txt").create().await;
+    let request = outsider
+        .post(&format!("/api/storage/{}/shares/groups", other.id))
+        .set_json(json!({ "group_id": group_id, "members": [] }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_creating_the_file_from_the_stored_content() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let source = harness
+        .file(&user, "movie.mkv")
+        .content(b"encrypted movie")
+        .hashed()
+        .create()
+        .await;
+    let sha256 = cryptfns::sha256::digest(b"encrypted movie".as_slice());
+
+    let request = user
+        .post("/api/storage/dedup")
+        .set_json(json!({ "sha256": sha256, "size": 15 }))
+        .to_request();
+    let (status, found) = harness.json(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found["id"], json!(source.id));
+
+    let dir = harness.dir(&user, "copies").create().await;
+    let create = |sha256: &str| {
+        user.post("/api/storage/dedup/files")
+            .set_json(json!({
+                "source_id": source.id,
+                "encrypted_key": source.encrypted_key,
+                "encrypted_name": cryptfns::hex::encode("movie.mkv"),
+                "name_hash": cryptfns::sha256::digest("movie.mkv".as_bytes()),
+                "mime": "video/x-matroska",
+                "size": 15,
+                "chunks": 1,
+                "sha256": sha256,
+                "file_id": dir.id,
+            }))
+            .to_request()
+    };
+
+    let request = create(&cryptfns::sha256::digest("other".as_bytes()));
+    assert_eq!(harness.status(request).await, StatusCode::BAD_REQUEST);
+
+    let (status, file) = harness.json(create(&sha256)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(file["file_id"], json!(dir.id));
+    assert!(file["finished_upload_at"].is_number());
+
+    let uri = format!("/api/storage/{}", file["id"].as_str().unwrap());
+    let (status, body) = harness.bytes(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_ref(), b"encrypted movie");
+
+    // Name is taken now
+    assert_eq!(
+        harness.status(create(&sha256)).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // Content of the other users is not theirs to link
+    let other = harness.user("jane@doe.com").await;
+    let request = other
+        .post("/api/storage/dedup/files")
+        .set_json(json!({
+            "source_id": source.id,
+            "encrypted_key": source.encrypted_key,
+            "encrypted_name": cryptfns::hex::encode("movie.mkv"),
+            "name_hash": cryptfns::sha256::digest("movie.mkv".as_bytes()),
+            "mime": "video/x-matroska",
+            "size": 15,
+            "chunks": 1,
+            "sha256": sha256,
+        }))
+        .to_request();
+    assert_eq!(harness.status(request).await, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_loading_the_folder_tree() {
+    let harness = harness::start().await;
+    let user = harness.user("john@doe.com").await;
+    let dir = harness.dir(&user, "documents").create().await;
+    let inner = harness.dir(&user, "invoices").parent(dir.id).create().await;
+    let file = harness
+        .file(&user, "2024.pdf")
+        .parent(inner.id)
+        .create()
+        .await;
+    let notes = harness.file(&user, "notes.txt").create().await;
+
+    let ids = |tree: &Value| {
+        tree["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["id"].clone())
+            .collect::<Vec<_>>()
+    };
+
+    let (status, tree) = harness
+        .json(user.get("/api/storage/tree").to_request())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(tree["root"].is_null());
+    let found = ids(&tree);
+    assert_eq!(found.len(), 4);
+    for id in [dir.id, inner.id, file.id, notes.id] {
+        assert!(found.contains(&json!(id)));
+    }
+
+    let uri = format!("/api/storage/tree?root={}&depth=1", dir.id);
+    let (status, tree) = harness.json(user.get(&uri).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tree["root"]["id"], json!(dir.id));
+    assert_eq!(ids(&tree), vec![json!(inner.id)]);
+
+    let uri = format!("/api/storage/tree?root={}", notes.id);
+    assert_eq!(
+        harness.status(user.get(&uri).to_request()).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    assert_eq!(
+        harness
+            .status(user.get("/api/storage/tree?depth=0").to_request())
+            .await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let other = harness.user("jane@doe.com").await;
+    let uri = format!("/api/storage/tree?root={}", dir.id);
+    assert_eq!(
+        harness.status(other.get(&uri).to_request()).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_routes_require_the_session() {
+    let harness = harness::start().await;
+
+    for uri in [
+        "/api/storage",
+        "/api/storage/trash",
+        "/api/journal",
+        "/api/spaces",
+        "/api/imports",
+        "/api/external-exports",
+        "/api/access-keys",
+        "/api/rewrap-jobs",
+    ] {
+        let request = actix_web::test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(
+            harness.status(request).await,
+            StatusCode::UNAUTHORIZED,
+            "{}",
+            uri
+        );
+    }
+}
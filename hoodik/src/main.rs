@@ -2,6 +2,17 @@ use error::AppResult;
 use hoodik::{Config, Context};
 use migration::{Migrator, MigratorTrait};
 
+#[cfg(all(feature = "pprof", target_os = "linux"))]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Heap profiling is active from the start, so the profile dumped by the admin
+/// has all the allocations that are still alive
+#[cfg(all(feature = "pprof", target_os = "linux"))]
+#[allow(non_upper_case_globals)]
+#[export_name = "malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:19\0";
+
 #[actix_web::main]
 async fn main() -> AppResult<()> {
     // Catch any panic from any thread running and dump it here
@@ -0,0 +1,57 @@
+//! # Server capabilities
+//!
+//! Lets the clients find out what the server supports before they start working
+//! with it, most importantly which versions of the encryption scheme it understands.
+use actix_web::{get, web, HttpResponse};
+use context::Context;
+use serde::{Deserialize, Serialize};
+
+/// Versions of the encryption scheme
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CryptoVersions {
+    /// Version the new files and users are created with
+    pub current: i32,
+    /// Every version the server can still work with
+    pub supported: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub version: String,
+    pub crypto: CryptoVersions,
+    /// Maximum size of a single uploaded chunk
+    pub max_chunk_size: u64,
+    /// Largest file whose whole content can be read and written in a single request
+    pub max_content_size: u64,
+    /// Upload can be skipped for the content stored by other users
+    pub global_dedup: bool,
+    /// Organization recovery public key, when it is set the key of every new
+    /// file has to be sent encrypted with it as well (key escrow)
+    pub escrow_key: Option<String>,
+}
+
+/// Get the capabilities of the server, this route is not authenticated.
+///
+/// Response: [Capabilities]
+#[get("/api/capabilities")]
+pub(crate) async fn capabilities(context: web::Data<Context>) -> HttpResponse {
+    let escrow_key = context
+        .settings
+        .inner()
+        .await
+        .escrow
+        .public_key()
+        .map(|public_key| public_key.to_string());
+
+    HttpResponse::Ok().json(Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        crypto: CryptoVersions {
+            current: cryptfns::scheme::CURRENT,
+            supported: cryptfns::scheme::SUPPORTED.to_vec(),
+        },
+        max_chunk_size: fs::MAX_CHUNK_SIZE_BYTES,
+        max_content_size: storage::data::content::max_size(&context.config),
+        global_dedup: context.config.server.global_dedup,
+        escrow_key,
+    })
+}
@@ -10,6 +10,7 @@ pub fn setup() -> Cors {
         "x-csrf-token",
         "authorization",
         "access-control-allow-origin",
+        "x-download-token",
     ];
 
     Cors::default()
@@ -24,6 +25,7 @@ pub fn setup() -> Cors {
             http::header::ORIGIN,
             http::header::AUTHORIZATION,
             http::header::HeaderName::from_str("X-Csrf-Token").unwrap(),
+            http::header::HeaderName::from_str("X-Download-Token").unwrap(),
         ])
         .max_age(3600)
 }
@@ -0,0 +1,252 @@
+//! # HTTP/3 listener
+//!
+//! Optional listener (feature `http3`) that accepts the API requests over QUIC next to the
+//! regular HTTP/1 and HTTP/2 listener. The requests are handed over to the same application
+//! the other listener serves, so every route, middleware and limit works the same way.
+//!
+//! Request body is streamed to the application as it arrives, with the same slow client
+//! [Watchdog] the uploads have on the regular listener, so it is never buffered whole.
+//! Response body is streamed back as the application produces it. The number of the
+//! connections served at once is limited, the connections over it wait to be accepted.
+
+use std::{io, net::SocketAddr, pin::Pin, rc::Rc, sync::Arc, time::Duration};
+
+use actix_http::{error::PayloadError, BoxedPayloadStream, Payload, Request, Version};
+use actix_service::IntoServiceFactory;
+use actix_web::{
+    body::MessageBody,
+    dev::{AppConfig, Service, ServiceFactory},
+    middleware::Logger,
+    web::Bytes,
+};
+use bytes::Buf;
+use config::server::ServerConfig;
+use context::Context;
+use error::{AppResult, Error};
+use fs::watchdog::Watchdog;
+use futures::{
+    channel::mpsc,
+    future::{poll_fn, select, Either},
+    pin_mut, SinkExt, StreamExt,
+};
+use h3::server::RequestStream;
+use tokio::sync::Semaphore;
+
+use super::{app, LOG_FORMAT};
+
+type Stream = RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>;
+
+/// Maximum number of the HTTP/3 connections served at the same time
+const MAX_CONNECTIONS: usize = 1024;
+
+/// Parts of the request body waiting for the application to read them
+const BODY_BUFFER: usize = 4;
+
+/// Bind the UDP socket and serve the HTTP/3 connections until the server stops,
+/// it has to run on the actix runtime because the application is not `Send`.
+pub async fn listen(
+    context: Context,
+    mut tls: rustls::ServerConfig,
+    address: SocketAddr,
+) -> AppResult<()> {
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+
+    let mut server = quinn::ServerConfig::with_crypto(Arc::new(tls));
+    server.transport_config(Arc::new(transport(&context.config.server)?));
+
+    let endpoint = quinn::Endpoint::server(server, address)?;
+    let config = Rc::new(context.config.server.clone());
+
+    // Same as the regular listener has it, the connection is secure and the host is
+    // the address the server is bound to, unless the request says otherwise.
+    let service = app(context)
+        .wrap(Logger::new(LOG_FORMAT))
+        .into_factory()
+        .new_service(AppConfig::new(true, address.to_string(), address))
+        .await
+        .map_err(|_| Error::InternalError("http3_service_init_failed".to_string()))?;
+    let service = Rc::new(service);
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+
+    log::info!("Listening for HTTP/3 connections on udp://{}", address);
+
+    loop {
+        let permit = connections
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| Error::InternalError(e.to_string()))?;
+
+        let connecting = match endpoint.accept().await {
+            Some(connecting) => connecting,
+            None => break,
+        };
+
+        let service = service.clone();
+        let config = config.clone();
+
+        actix_web::rt::spawn(async move {
+            if let Err(e) = connection(connecting, service, config).await {
+                log::debug!("HTTP/3 connection closed: {}", e);
+            }
+
+            drop(permit);
+        });
+    }
+
+    Ok(())
+}
+
+/// Idle connections are closed after the keep alive, same as on the regular listener
+fn transport(config: &ServerConfig) -> AppResult<quinn::TransportConfig> {
+    let mut transport = quinn::TransportConfig::default();
+
+    let idle_timeout = match config.keep_alive {
+        0 => None,
+        seconds => Some(
+            Duration::from_secs(seconds)
+                .try_into()
+                .map_err(|_| Error::InternalError("invalid_http3_idle_timeout".to_string()))?,
+        ),
+    };
+    transport.max_idle_timeout(idle_timeout);
+
+    Ok(transport)
+}
+
+/// Accept the requests of a single connection, each of them is handled on its own
+async fn connection<S, B>(
+    connecting: quinn::Connecting,
+    service: Rc<S>,
+    config: Rc<ServerConfig>,
+) -> AppResult<()>
+where
+    S: Service<Request, Response = actix_web::dev::ServiceResponse<B>, Error = actix_web::Error>
+        + 'static,
+    B: MessageBody + 'static,
+{
+    let connection = connecting
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    let peer_addr = connection.remote_address();
+
+    let mut connection = h3::server::Connection::new(h3_quinn::Connection::new(connection))
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    while let Some((request, stream)) = connection
+        .accept()
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?
+    {
+        let service = service.clone();
+        let config = config.clone();
+
+        actix_web::rt::spawn(async move {
+            if let Err(e) = handle(request, stream, peer_addr, service, &config).await {
+                log::debug!("HTTP/3 request failed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Pass the request to the application and send its response back over the stream,
+/// the body is fed to the application while it is handling the request.
+async fn handle<S, B>(
+    request: http::Request<()>,
+    mut stream: Stream,
+    peer_addr: SocketAddr,
+    service: Rc<S>,
+    config: &ServerConfig,
+) -> AppResult<()>
+where
+    S: Service<Request, Response = actix_web::dev::ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+{
+    let (sender, receiver) = mpsc::channel(BODY_BUFFER);
+    let payload: BoxedPayloadStream = Box::pin(receiver);
+
+    let (parts, _) = request.into_parts();
+    let mut request = Request::with_payload(Payload::from(payload));
+    let head = request.head_mut();
+    head.method = parts.method;
+    head.uri = parts.uri;
+    head.version = Version::HTTP_3;
+    head.headers = parts.headers.into();
+    head.peer_addr = Some(peer_addr);
+
+    // The application can respond before it has read the whole body, the rest of it
+    // is not received anymore once it did, the stream is needed to send the response.
+    let response = {
+        let call = service.call(request);
+        let body = receive(&mut stream, sender, config);
+        pin_mut!(call, body);
+
+        match select(call, body).await {
+            Either::Left((response, _)) => response,
+            Either::Right((_, call)) => call.await,
+        }
+    };
+
+    let response = match response {
+        Ok(response) => response.map_into_boxed_body().into_parts().1,
+        Err(e) => e.error_response(),
+    };
+
+    let mut builder = http::Response::builder().status(response.status());
+    for (name, value) in response.headers() {
+        builder = builder.header(name, value);
+    }
+
+    let head = builder
+        .body(())
+        .map_err(|e| Error::InternalError(e.to_string()))?;
+    stream.send_response(head).await.map_err(stream_error)?;
+
+    let mut body = response.into_body();
+
+    while let Some(data) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+        let data = data.map_err(|e| Error::InternalError(e.to_string()))?;
+
+        if !data.is_empty() {
+            stream.send_data(data).await.map_err(stream_error)?;
+        }
+    }
+
+    stream.finish().await.map_err(stream_error)
+}
+
+/// Receive the request body and pass it on to the application as it arrives, the client
+/// that sends it too slowly gets the error instead of the rest of the body.
+async fn receive(
+    stream: &mut Stream,
+    mut sender: mpsc::Sender<Result<Bytes, PayloadError>>,
+    config: &ServerConfig,
+) {
+    let body = futures::stream::unfold(Some(stream), |stream| async move {
+        let stream = stream?;
+
+        match stream.recv_data().await {
+            Ok(Some(mut data)) => Some((Ok(data.copy_to_bytes(data.remaining())), Some(stream))),
+            Ok(None) => None,
+            Err(e) => Some((Err(stream_error(e)), None)),
+        }
+    });
+    let mut body = Box::pin(Watchdog::new(body, config));
+
+    while let Some(data) = body.next().await {
+        let data =
+            data.map_err(|e| PayloadError::Io(io::Error::new(io::ErrorKind::Other, e.to_string())));
+        let failed = data.is_err();
+
+        if sender.send(data).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
+fn stream_error(e: h3::Error) -> Error {
+    Error::BadRequest(e.to_string())
+}
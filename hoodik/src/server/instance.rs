@@ -0,0 +1,35 @@
+//! # Instance profile
+//!
+//! Branding of the instance the admin has set in the settings, the frontend
+//! shows it in place of its own, so the white-label deployments don't fork it.
+use actix_web::{get, web, HttpResponse};
+use context::Context;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceProfile {
+    pub name: String,
+    pub logo_url: Option<String>,
+    /// Color of the buttons and links, in the `#rrggbb` or `#rgb` form
+    pub accent_color: Option<String>,
+    pub terms_url: Option<String>,
+    /// Anyone can register, otherwise only the invited and whitelisted users can
+    pub registration_open: bool,
+}
+
+/// Get the profile of the instance, this route is not authenticated.
+///
+/// Response: [InstanceProfile]
+#[get("/api/instance")]
+pub(crate) async fn instance(context: web::Data<Context>) -> HttpResponse {
+    let settings = context.settings.inner().await;
+    let instance = &settings.instance;
+
+    HttpResponse::Ok().json(InstanceProfile {
+        name: instance.name().to_string(),
+        logo_url: instance.logo_url().map(|url| url.to_string()),
+        accent_color: instance.accent_color().map(|color| color.to_string()),
+        terms_url: instance.terms_url().map(|url| url.to_string()),
+        registration_open: settings.users.allow_register(),
+    })
+}
@@ -4,17 +4,30 @@
 //! This module and its sub-modules will give you a good idea of the application endpoints
 //! and endpoint Request and Response structs.
 
+use std::time::Duration;
+
 use actix_web::{
     body::{BoxBody, EitherBody},
     dev::{ServiceFactory, ServiceRequest, ServiceResponse},
-    middleware::Logger,
+    http::KeepAlive,
+    middleware::{Condition, DefaultHeaders, Logger},
     web, App, HttpServer,
 };
 use context::Context;
 use error::{AppResult, Error};
 
+pub mod capabilities;
 pub mod client;
 pub mod cors;
+#[cfg(feature = "http3")]
+pub mod http3;
+pub mod instance;
+
+/// Format of the access log lines
+pub(crate) const LOG_FORMAT: &str = "%a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T";
+
+/// Maximum payload size is 1.1x of a single file chunk we are expecting to be uploaded
+pub(crate) const MAX_PAYLOAD_SIZE: usize = (fs::MAX_CHUNK_SIZE_BYTES as f32 * 1.1) as usize;
 
 /// Inject the application modules into the server
 fn configure(cfg: &mut web::ServiceConfig) {
@@ -37,12 +50,11 @@ pub fn app(
     >,
 > {
     App::new()
-        // Set the maximum payload size to 1.1x of a single file chunk
-        // we are expecting to be uploaded
-        .app_data(web::PayloadConfig::new(
-            (fs::MAX_CHUNK_SIZE_BYTES as f32 * 1.1) as usize,
-        ))
+        .app_data(web::PayloadConfig::new(MAX_PAYLOAD_SIZE))
+        .app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+        .app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
         .app_data(web::Data::new(context))
+        .wrap(storage::transfers::Metering)
         .wrap(cors::setup())
         .configure(configure)
         .route(
@@ -66,21 +78,153 @@ pub fn app(
                     .json(serde_json::json!({"METHOD": "HEAD", "message": "I am alive"}))
             }),
         )
+        .service(capabilities::capabilities)
+        .service(instance::instance)
         .service(client::client)
 }
 
+/// Start the background worker with the handlers for all the jobs the modules push
+fn start_worker(context: Context) {
+    jobs::worker::Worker::new(context)
+        .handler(storage::jobs::PURGE_FILES, storage::jobs::PurgeFiles)
+        .handler(
+            storage::jobs::CLOSE_FILE_REQUEST,
+            storage::jobs::CloseFileRequests,
+        )
+        .handler(storage::jobs::EXPIRE_SHARE, storage::jobs::ExpireShares)
+        .handler(storage::jobs::REMIND_SHARE, storage::jobs::RemindShares)
+        .handler(
+            storage::jobs::EXPORT_USER_DATA,
+            storage::jobs::ExportUserData,
+        )
+        .handler(storage::jobs::REPAIR_CHUNKS, storage::jobs::RepairChunks)
+        .handler(storage::jobs::SWAP_CONTENT, storage::jobs::SwapContent)
+        .handler(storage::jobs::IMPORT_FILES, storage::jobs::ImportFiles)
+        .handler(
+            storage::jobs::EXPORT_EXTERNAL,
+            storage::jobs::ExportExternal,
+        )
+        .handler(
+            storage::jobs::REBALANCE_STORAGE,
+            storage::jobs::RebalanceStorage,
+        )
+        .handler(
+            storage::jobs::RESTORE_ARCHIVED,
+            storage::jobs::RestoreArchived,
+        )
+        .handler(
+            storage::jobs::EXPIRE_TUS_UPLOAD,
+            storage::jobs::ExpireTusUploads,
+        )
+        .handler(storage::jobs::EMPTY_TRASH, storage::jobs::EmptyTrash)
+        .spawn();
+}
+
+/// Start archiving the cold files if the archive bucket is configured
+fn start_archive(context: Context) {
+    if let Some(sweeper) = storage::archive::Sweeper::new(context) {
+        sweeper.spawn();
+    }
+}
+
+/// Start moving the cooled down files to the remote storage if the storage is tiered
+fn start_tiering(context: Context) {
+    if let Some(sweeper) = storage::tiering::Sweeper::new(context) {
+        sweeper.spawn();
+    }
+}
+
+/// Start publishing the file events if the MQTT broker is configured
+#[cfg(feature = "mqtt")]
+fn start_events(context: Context) {
+    if let Some(publisher) = storage::events::Publisher::new(context) {
+        publisher.spawn();
+    }
+}
+
+/// Port of the HTTP/3 listener, only set when the listener can run
+fn http3_port(context: &Context) -> Option<u16> {
+    let port = context.config.server.http3_port?;
+
+    if !cfg!(feature = "http3") {
+        log::warn!("HTTP3_PORT is set, but the server is built without the `http3` feature");
+        return None;
+    }
+
+    if context.config.ssl.disabled {
+        log::warn!("HTTP3_PORT is set, but HTTP/3 can't run with the SSL disabled");
+        return None;
+    }
+
+    Some(port)
+}
+
+/// Start the HTTP/3 listener next to the regular one, on the same address
+#[cfg(feature = "http3")]
+fn start_http3(context: Context, tls: rustls::ServerConfig, port: u16) -> AppResult<()> {
+    use std::net::ToSocketAddrs;
+
+    let address = (context.config.app.address.as_str(), port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::InternalError("invalid_http3_address".to_string()))?;
+
+    actix_web::rt::spawn(async move {
+        if let Err(e) = http3::listen(context, tls, address).await {
+            log::error!("HTTP/3 listener stopped: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
 /// Start the server
 pub async fn engage(context: Context) -> AppResult<()> {
+    storage::blobs::BlobIndex::register(&context);
+
+    start_worker(context.clone());
+    start_archive(context.clone());
+    start_tiering(context.clone());
+
+    #[cfg(feature = "mqtt")]
+    start_events(context.clone());
+
     let bind_address = context.config.get_full_bind_address();
     let disabled = context.config.ssl.disabled;
     let app_url = context.config.get_app_url();
     let config = context.config.ssl.build_rustls_config(vec![app_url])?;
+
+    // Clients find out about the HTTP/3 listener from the responses of the regular one
+    let http3_port = http3_port(&context);
+    let alt_svc = http3_port
+        .map(|port| format!("h3=\":{}\"; ma=86400", port))
+        .unwrap_or_default();
+
+    #[cfg(feature = "http3")]
+    if let Some(port) = http3_port {
+        start_http3(context.clone(), config.clone(), port)?;
+    }
     
+    let workers = context.config.server.workers;
+    let blocking_threads = context.config.server.blocking_threads;
+    let request_timeout = Duration::from_secs(context.config.server.request_timeout);
+    let keep_alive = match context.config.server.keep_alive {
+        0 => KeepAlive::Disabled,
+        seconds => KeepAlive::Timeout(Duration::from_secs(seconds)),
+    };
+
     let server = HttpServer::new(move || {
-        app(context.clone()).wrap(Logger::new(
-            "%a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
-        ))
-    });
+        app(context.clone())
+            .wrap(Logger::new(LOG_FORMAT))
+            .wrap(Condition::new(
+                http3_port.is_some(),
+                DefaultHeaders::new().add(("Alt-Svc", alt_svc.as_str())),
+            ))
+    })
+    .workers(workers)
+    .worker_max_blocking_threads(blocking_threads)
+    .client_request_timeout(request_timeout)
+    .keep_alive(keep_alive);
 
     if disabled {
         server.bind(&bind_address)?.run().await.map_err(Error::from)
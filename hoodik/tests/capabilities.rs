@@ -0,0 +1,25 @@
+use actix_web::test;
+use hoodik::server::{self, capabilities::Capabilities};
+
+#[actix_web::test]
+async fn test_capabilities_list_the_crypto_versions() {
+    let context = context::Context::mock_sqlite().await;
+    let app = test::init_service(server::app(context)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/capabilities")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let capabilities: Capabilities = test::read_body_json(resp).await;
+
+    assert_eq!(capabilities.crypto.current, cryptfns::scheme::CURRENT);
+    assert!(capabilities
+        .crypto
+        .supported
+        .contains(&capabilities.crypto.current));
+    assert!(capabilities.max_content_size <= capabilities.max_chunk_size);
+    assert!(!capabilities.global_dedup);
+    assert!(capabilities.escrow_key.is_none());
+}
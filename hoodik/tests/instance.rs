@@ -0,0 +1,45 @@
+use actix_web::test;
+use hoodik::server::{self, instance::InstanceProfile};
+
+#[actix_web::test]
+async fn test_instance_profile_is_public() {
+    let context = context::Context::mock_sqlite().await;
+    let app = test::init_service(server::app(context)).await;
+
+    let req = test::TestRequest::get().uri("/api/instance").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let profile: InstanceProfile = test::read_body_json(resp).await;
+
+    assert_eq!(profile.name, "Hoodik");
+    assert!(profile.logo_url.is_none());
+    assert!(profile.accent_color.is_none());
+    assert!(profile.registration_open);
+}
+
+#[test]
+fn test_instance_profile_is_validated() {
+    let instance = |value: serde_json::Value| {
+        serde_json::from_value::<settings::data::Instance>(value).unwrap()
+    };
+
+    assert!(
+        instance(serde_json::json!({"name": "Acme", "accent_color": "#ff8800"}))
+            .validate()
+            .is_ok()
+    );
+    assert!(
+        instance(serde_json::json!({"name": "Acme", "accent_color": "orange"}))
+            .validate()
+            .is_err()
+    );
+    assert!(
+        instance(serde_json::json!({"name": "Acme", "logo_url": "javascript:alert(1)"}))
+            .validate()
+            .is_err()
+    );
+    assert!(instance(serde_json::json!({"name": " "}))
+        .validate()
+        .is_err());
+}
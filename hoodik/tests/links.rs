@@ -45,8 +45,9 @@ async fn test_creating_and_downloading_link() {
     assert_eq!(data.len(), size as usize / CHUNK_SIZE_BYTES as usize);
 
     let random_file = storage::data::create_file::CreateFile {
-        encrypted_key: Some("encrypted-gibberish".to_string()),
-        encrypted_name: Some("name".to_string()),
+        id: None,
+        encrypted_key: Some(cryptfns::base64::encode("encrypted-gibberish")),
+        encrypted_name: Some(cryptfns::hex::encode("name")),
         encrypted_thumbnail: None,
         search_tokens_hashed: None,
         name_hash: Some(checksum.clone()),
@@ -56,6 +57,13 @@ async fn test_creating_and_downloading_link() {
         file_id: None,
         // Date of the file creation from the disk, if not provided we set it to now
         file_modified_at: None,
+        sha256: None,
+        conflict_name_hash: None,
+        encrypted_conflict_name: None,
+        device: None,
+        crypto_version: None,
+        escrow_key: None,
+        magic: None,
     };
 
     let req = test::TestRequest::post()
@@ -126,7 +134,16 @@ async fn test_creating_and_downloading_link() {
         encrypted_link_key: Some(link_key_rsa_enc),
         encrypted_thumbnail: None,
         encrypted_file_key: Some(file_key_hex_aes_enc_hex),
+        items: None,
+        starts_at: None,
         expires_at: None,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
     };
     let req = test::TestRequest::post()
         .uri("/api/links")
@@ -137,8 +154,38 @@ async fn test_creating_and_downloading_link() {
     let body = test::call_and_read_body(&mut app, req).await;
     let link: AppLink = serde_json::from_slice(&body).unwrap();
 
+    // Public URL of the link gives its metadata without the session
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/links/{}", link.id))
+        .to_request();
+    let public: AppLink =
+        serde_json::from_slice(&test::call_and_read_body(&app, req).await).unwrap();
+    assert_eq!(public.id, link.id);
+    assert_eq!(public.encrypted_name, link.encrypted_name);
+
+    // Link with the slug is opened through the redirect to its page
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/links/{}/slug", link.id))
+        .cookie(jwt.clone())
+        .set_json(serde_json::json!({ "slug": "Quarterly-Report" }))
+        .to_request();
+    let slugged: AppLink =
+        serde_json::from_slice(&test::call_and_read_body(&app, req).await).unwrap();
+    assert_eq!(slugged.slug.as_deref(), Some("quarterly-report"));
+
+    let req = test::TestRequest::get()
+        .uri("/s/quarterly-report")
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+    assert_eq!(
+        response.headers().get("Location").unwrap(),
+        format!("/l/{}", link.id).as_str()
+    );
+
     let download_linked_file = links::data::download::Download {
         link_key: Some(link_key_hex),
+        password: None,
     };
     let uri = format!("/api/links/{}", link.id);
     let req = test::TestRequest::post()
@@ -75,8 +75,9 @@ async fn test_creating_file_and_uploading_chunks() {
     let checksum = calculate_checksum(data.clone());
 
     let random_file = storage::data::create_file::CreateFile {
-        encrypted_key: Some("encrypted-gibberish".to_string()),
-        encrypted_name: Some("name".to_string()),
+        id: None,
+        encrypted_key: Some(cryptfns::base64::encode("encrypted-gibberish")),
+        encrypted_name: Some(cryptfns::hex::encode("name")),
         encrypted_thumbnail: None,
         search_tokens_hashed: None,
         name_hash: Some(checksum.clone()),
@@ -86,6 +87,13 @@ async fn test_creating_file_and_uploading_chunks() {
         file_id: None,
         // Date of the file creation from the disk, if not provided we set it to now
         file_modified_at: None,
+        sha256: None,
+        conflict_name_hash: None,
+        encrypted_conflict_name: None,
+        device: None,
+        crypto_version: None,
+        escrow_key: None,
+        magic: None,
     };
 
     let req = test::TestRequest::post()
@@ -151,6 +159,85 @@ async fn test_creating_file_and_uploading_chunks() {
     let content_len = contents.len();
     let file_checksum = cryptfns::sha256::digest(contents.as_slice());
 
+    // Reading the chunks one by one gives the same content as reading them ahead
+    let mut unbuffered = context.clone();
+    unbuffered.config.server.read_ahead = 0;
+    let unbuffered_app = test::init_service(server::app(unbuffered)).await;
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let unbuffered_contents = test::call_and_read_body(&unbuffered_app, req)
+        .await
+        .to_vec();
+    assert_eq!(unbuffered_contents, contents);
+
+    // Resume the download in the middle of the third chunk
+    let start = CHUNK_SIZE_BYTES as usize * 2 + 10;
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .insert_header(("Range", format!("bytes={}-", start)))
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers().get("Content-Range").unwrap(),
+        format!("bytes {}-{}/{}", start, content_len - 1, content_len).as_str()
+    );
+    let partial = test::read_body(response).await.to_vec();
+    assert_eq!(partial, contents[start..]);
+
+    // Range that spans over two chunks
+    let (start, end) = (CHUNK_SIZE_BYTES as usize - 5, CHUNK_SIZE_BYTES as usize + 5);
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .insert_header(("Range", format!("bytes={}-{}", start, end)))
+        .to_request();
+
+    let partial = test::call_and_read_body(&app, req).await.to_vec();
+    assert_eq!(partial, contents[start..=end]);
+
+    // Suffix range, the last bytes of the file as the players ask for them when seeking
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .insert_header(("Range", "bytes=-10"))
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers().get("Accept-Ranges").unwrap(), "bytes");
+    let partial = test::read_body(response).await.to_vec();
+    assert_eq!(partial, contents[content_len - 10..]);
+
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::HEAD)
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("Accept-Ranges").unwrap(), "bytes");
+    assert_eq!(
+        response.headers().get("Content-Length").unwrap(),
+        content_len.to_string().as_str()
+    );
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .insert_header(("Range", format!("bytes={}-", content_len)))
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+
     for i in 0..CHUNKS {
         let f = format!(
             "{}/{}",
@@ -187,3 +274,785 @@ async fn test_creating_file_and_uploading_chunks() {
 
     context.config.app.cleanup();
 }
+
+#[actix_web::test]
+async fn test_index_and_search_query_count_does_not_grow_with_files() {
+    use entity::{ColumnTrait, EntityTrait, QueryFilter};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let mut context =
+        context::Context::mock_with_data_dir(Some("../data-test-queries".to_string())).await;
+
+    let queries = Arc::new(AtomicUsize::new(0));
+    let counter = queries.clone();
+    context.db.set_metric_callback(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    let private = cryptfns::rsa::private::generate().unwrap();
+    let public = cryptfns::rsa::public::from_private(&private).unwrap();
+    let public_string = cryptfns::rsa::public::to_string(&public).unwrap();
+    let fingerprint = cryptfns::rsa::fingerprint(public).unwrap();
+
+    let app = test::init_service(server::app(context.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/auth/register")
+        .set_json(CreateUser {
+            email: Some("john@doe.com".to_string()),
+            password: Some("not-4-weak-password-for-god-sakes!".to_string()),
+            secret: None,
+            token: None,
+            pubkey: Some(public_string),
+            fingerprint: Some(fingerprint),
+            encrypted_private_key: Some("some-random-encrypted-secret".to_string()),
+            invitation_id: None,
+        })
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let (jwt, _) = helpers::extract_cookies(resp.headers());
+    let jwt = jwt.unwrap();
+
+    let user = entity::users::Entity::find()
+        .filter(entity::users::Column::Email.eq("john@doe.com"))
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Count the queries for listing and searching a directory with the given number of files
+    let count_queries = |files: usize| {
+        let context = context.clone();
+        let queries = queries.clone();
+        let user = user.clone();
+        let jwt = jwt.clone();
+        let app = &app;
+
+        async move {
+            let dir = storage::mock::create_file(&context, &user, "dir", None, Some("dir"))
+                .await
+                .unwrap();
+
+            for i in 0..files {
+                let name = format!("file {} {}", files, i);
+                storage::mock::create_file(
+                    &context,
+                    &user,
+                    &name,
+                    Some(dir.id),
+                    Some("text/plain"),
+                )
+                .await
+                .unwrap();
+            }
+
+            queries.store(0, Ordering::SeqCst);
+            let req = test::TestRequest::get()
+                .uri(format!("/api/storage?dir_id={}", dir.id).as_str())
+                .cookie(jwt.clone())
+                .to_request();
+            let response: storage::data::response::Response =
+                test::call_and_read_body_json(app, req).await;
+            assert_eq!(response.children.len(), files);
+            let index = queries.load(Ordering::SeqCst);
+
+            queries.store(0, Ordering::SeqCst);
+            let req = test::TestRequest::post()
+                .uri("/api/storage/search")
+                .cookie(jwt)
+                .set_json(storage::data::search::Search {
+                    dir_id: Some(dir.id.to_string()),
+                    search_tokens_hashed: Some(vec![format!("{}:1", files)]),
+                    ..Default::default()
+                })
+                .to_request();
+            let results: Vec<AppFile> = test::call_and_read_body_json(app, req).await;
+            assert_eq!(results.len(), files);
+            let search = queries.load(Ordering::SeqCst);
+
+            (index, search)
+        }
+    };
+
+    let few = count_queries(2).await;
+    let many = count_queries(20).await;
+
+    assert!(few.0 > 0 && few.1 > 0);
+    assert_eq!(few, many);
+
+    context.config.app.cleanup();
+}
+
+#[actix_web::test]
+async fn test_bulk_upload_of_small_files() {
+    let context = context::Context::mock_with_data_dir(Some("../data-test-bulk".to_string())).await;
+
+    let private = cryptfns::rsa::private::generate().unwrap();
+    let public = cryptfns::rsa::public::from_private(&private).unwrap();
+    let public_string = cryptfns::rsa::public::to_string(&public).unwrap();
+    let fingerprint = cryptfns::rsa::fingerprint(public).unwrap();
+
+    let app = test::init_service(server::app(context.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/auth/register")
+        .set_json(CreateUser {
+            email: Some("john@doe.com".to_string()),
+            password: Some("not-4-weak-password-for-god-sakes!".to_string()),
+            secret: None,
+            token: None,
+            pubkey: Some(public_string),
+            fingerprint: Some(fingerprint),
+            encrypted_private_key: Some("some-random-encrypted-secret".to_string()),
+            invitation_id: None,
+        })
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let (jwt, _) = helpers::extract_cookies(resp.headers());
+    let jwt = jwt.unwrap();
+
+    let contents = (0..3)
+        .map(|i| format!("photo-{}", i).repeat(100).into_bytes())
+        .collect::<Vec<_>>();
+
+    let files = contents
+        .iter()
+        .enumerate()
+        .map(|(i, content)| storage::data::create_file::CreateFile {
+            id: None,
+            encrypted_key: Some(cryptfns::base64::encode("encrypted-gibberish")),
+            encrypted_name: Some(cryptfns::hex::encode(format!("photo-{}", i))),
+            encrypted_thumbnail: None,
+            search_tokens_hashed: None,
+            name_hash: Some(cryptfns::sha256::digest(format!("photo-{}", i))),
+            mime: Some("image/jpeg".to_string()),
+            size: Some(content.len() as i64),
+            chunks: Some(1),
+            file_id: None,
+            file_modified_at: None,
+            sha256: None,
+            conflict_name_hash: None,
+            encrypted_conflict_name: None,
+            device: None,
+            crypto_version: None,
+            escrow_key: None,
+            magic: None,
+        })
+        .collect::<Vec<_>>();
+
+    let boundary = "bulk-upload-boundary";
+    let multipart = |parts: &[(String, Vec<u8>)]| {
+        let mut body = vec![];
+
+        for (name, data) in parts {
+            body.extend(format!("--{}\r\n", boundary).into_bytes());
+            body.extend(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n",
+                    name, name
+                )
+                .into_bytes(),
+            );
+            body.extend(data);
+            body.extend(b"\r\n");
+        }
+
+        body.extend(format!("--{}--\r\n", boundary).into_bytes());
+        body
+    };
+
+    let metadata = serde_json::to_vec(&serde_json::json!({ "files": files })).unwrap();
+    let mut parts = vec![("files".to_string(), metadata)];
+
+    // Content of the last file is missing, nothing is created
+    for (i, content) in contents.iter().enumerate().take(2) {
+        parts.push((i.to_string(), content.clone()));
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/api/storage/bulk")
+        .cookie(jwt.clone())
+        .insert_header((
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", boundary),
+        ))
+        .set_payload(multipart(&parts))
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    parts.push(("2".to_string(), contents[2].clone()));
+
+    let req = test::TestRequest::post()
+        .uri("/api/storage/bulk")
+        .cookie(jwt.clone())
+        .insert_header((
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", boundary),
+        ))
+        .set_payload(multipart(&parts))
+        .to_request();
+
+    let created: Vec<AppFile> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(created.len(), 3);
+
+    for (file, content) in created.iter().zip(contents.iter()) {
+        assert!(file.finished_upload_at.is_some());
+        assert_eq!(file.chunks_stored, Some(1));
+
+        let req = test::TestRequest::get()
+            .uri(format!("/api/storage/{}", &file.id).as_str())
+            .cookie(jwt.clone())
+            .to_request();
+
+        let downloaded = test::call_and_read_body(&app, req).await.to_vec();
+        assert_eq!(&downloaded, content);
+    }
+
+    context.config.app.cleanup();
+}
+
+#[actix_web::test]
+async fn test_simple_upload_of_small_file() {
+    let context =
+        context::Context::mock_with_data_dir(Some("../data-test-simple".to_string())).await;
+
+    let private = cryptfns::rsa::private::generate().unwrap();
+    let public = cryptfns::rsa::public::from_private(&private).unwrap();
+    let public_string = cryptfns::rsa::public::to_string(&public).unwrap();
+    let fingerprint = cryptfns::rsa::fingerprint(public).unwrap();
+
+    let app = test::init_service(server::app(context.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/auth/register")
+        .set_json(CreateUser {
+            email: Some("john@doe.com".to_string()),
+            password: Some("not-4-weak-password-for-god-sakes!".to_string()),
+            secret: None,
+            token: None,
+            pubkey: Some(public_string),
+            fingerprint: Some(fingerprint),
+            encrypted_private_key: Some("some-random-encrypted-secret".to_string()),
+            invitation_id: None,
+        })
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let (jwt, _) = helpers::extract_cookies(resp.headers());
+    let jwt = jwt.unwrap();
+
+    let content = "shared-photo".repeat(100).into_bytes();
+    let id = entity::Uuid::new_v4();
+
+    let boundary = "simple-upload-boundary";
+    let multipart = |checksum: &str| {
+        let metadata = serde_json::json!({
+            "id": id,
+            "encrypted_key": cryptfns::base64::encode("encrypted-gibberish"),
+            "encrypted_name": cryptfns::hex::encode("shared-photo"),
+            "name_hash": cryptfns::sha256::digest("shared-photo"),
+            "mime": "image/jpeg",
+            "size": content.len(),
+            "chunks": 1,
+            "checksum": checksum,
+            "checksum_function": "sha256",
+        });
+        let parts = [
+            ("file", serde_json::to_vec(&metadata).unwrap()),
+            ("content", content.clone()),
+        ];
+        let mut body = vec![];
+
+        for (name, data) in parts {
+            body.extend(format!("--{}\r\n", boundary).into_bytes());
+            body.extend(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n",
+                    name, name
+                )
+                .into_bytes(),
+            );
+            body.extend(data);
+            body.extend(b"\r\n");
+        }
+
+        body.extend(format!("--{}--\r\n", boundary).into_bytes());
+        body
+    };
+
+    let request = |body: Vec<u8>| {
+        test::TestRequest::post()
+            .uri("/api/storage/simple-upload")
+            .cookie(jwt.clone())
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .set_payload(body)
+            .to_request()
+    };
+
+    // Content that doesn't match the checksum is refused before the file is created
+    let response = test::call_service(&app, request(multipart("0".repeat(64).as_str()))).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let checksum = cryptfns::sha256::digest(content.as_slice());
+    let file: AppFile =
+        test::call_and_read_body_json(&app, request(multipart(checksum.as_str()))).await;
+    assert_eq!(file.id, id);
+    assert!(file.finished_upload_at.is_some());
+    assert_eq!(file.chunks_stored, Some(1));
+
+    // Sending the same request again returns the uploaded file
+    let again: AppFile =
+        test::call_and_read_body_json(&app, request(multipart(checksum.as_str()))).await;
+    assert_eq!(again.id, id);
+    assert!(again.finished_upload_at.is_some());
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let downloaded = test::call_and_read_body(&app, req).await.to_vec();
+    assert_eq!(downloaded, content);
+
+    context.config.app.cleanup();
+}
+
+#[actix_web::test]
+async fn test_quick_edit_of_small_file_content() {
+    let context =
+        context::Context::mock_with_data_dir(Some("../data-test-content".to_string())).await;
+
+    let private = cryptfns::rsa::private::generate().unwrap();
+    let public = cryptfns::rsa::public::from_private(&private).unwrap();
+    let public_string = cryptfns::rsa::public::to_string(&public).unwrap();
+    let fingerprint = cryptfns::rsa::fingerprint(public).unwrap();
+
+    let app = test::init_service(server::app(context.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/auth/register")
+        .set_json(CreateUser {
+            email: Some("john@doe.com".to_string()),
+            password: Some("not-4-weak-password-for-god-sakes!".to_string()),
+            secret: None,
+            token: None,
+            pubkey: Some(public_string),
+            fingerprint: Some(fingerprint),
+            encrypted_private_key: Some("some-random-encrypted-secret".to_string()),
+            invitation_id: None,
+        })
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let (jwt, _) = helpers::extract_cookies(resp.headers());
+    let jwt = jwt.unwrap();
+
+    let content = b"first version of the note".to_vec();
+
+    let req = test::TestRequest::post()
+        .uri("/api/storage")
+        .cookie(jwt.clone())
+        .set_json(&storage::data::create_file::CreateFile {
+            id: None,
+            encrypted_key: Some(cryptfns::base64::encode("encrypted-gibberish")),
+            encrypted_name: Some(cryptfns::hex::encode("note")),
+            encrypted_thumbnail: None,
+            search_tokens_hashed: None,
+            name_hash: Some(cryptfns::sha256::digest("note")),
+            mime: Some("text/plain".to_string()),
+            size: Some(content.len() as i64),
+            chunks: Some(1),
+            file_id: None,
+            file_modified_at: None,
+            sha256: None,
+            conflict_name_hash: None,
+            encrypted_conflict_name: None,
+            device: None,
+            crypto_version: None,
+            escrow_key: None,
+            magic: None,
+        })
+        .to_request();
+
+    let file: AppFile = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::post()
+        .uri(format!("/api/storage/{}?chunk=0", &file.id).as_str())
+        .cookie(jwt.clone())
+        .append_header(("Content-Type", "application/octet-stream"))
+        .set_payload(content.clone())
+        .to_request();
+
+    let file: AppFile = test::call_and_read_body_json(&app, req).await;
+    assert!(file.finished_upload_at.is_some());
+
+    let uri = format!("/api/storage/{}/content", &file.id);
+
+    let req = test::TestRequest::get()
+        .uri(uri.as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let version = resp
+        .headers()
+        .get("ETag")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .trim_matches('"')
+        .to_string();
+    assert_eq!(test::read_body(resp).await.to_vec(), content);
+
+    let edited = b"second, a bit longer version of the note".to_vec();
+
+    let req = test::TestRequest::put()
+        .uri(format!("{}?size={}&version={}", uri, edited.len(), version).as_str())
+        .cookie(jwt.clone())
+        .append_header(("Content-Type", "application/octet-stream"))
+        .set_payload(edited.clone())
+        .to_request();
+
+    let written: storage::data::content::Content = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(written.file.size, Some(edited.len() as i64));
+    assert_ne!(written.version.to_string(), version);
+
+    // Writing over the version that is not the latest anymore is refused
+    let req = test::TestRequest::put()
+        .uri(format!("{}?size={}&version={}", uri, edited.len(), version).as_str())
+        .cookie(jwt.clone())
+        .append_header(("Content-Type", "application/octet-stream"))
+        .set_payload(edited.clone())
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let req = test::TestRequest::get()
+        .uri(uri.as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    assert_eq!(test::call_and_read_body(&app, req).await.to_vec(), edited);
+
+    // Previous content is kept as the version of the file
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}/versions", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let versions: Vec<AppFile> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions[0].size, Some(content.len() as i64));
+
+    context.config.app.cleanup();
+}
+
+#[actix_web::test]
+async fn test_tus_upload_resumes_after_interruption() {
+    let context = context::Context::mock_with_data_dir(Some("../data-test-tus".to_string())).await;
+
+    let private = cryptfns::rsa::private::generate().unwrap();
+    let public = cryptfns::rsa::public::from_private(&private).unwrap();
+    let public_string = cryptfns::rsa::public::to_string(&public).unwrap();
+    let fingerprint = cryptfns::rsa::fingerprint(public).unwrap();
+
+    let app = test::init_service(server::app(context.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/auth/register")
+        .set_json(CreateUser {
+            email: Some("john@doe.com".to_string()),
+            password: Some("not-4-weak-password-for-god-sakes!".to_string()),
+            secret: None,
+            token: None,
+            pubkey: Some(public_string),
+            fingerprint: Some(fingerprint),
+            encrypted_private_key: Some("some-random-encrypted-secret".to_string()),
+            invitation_id: None,
+        })
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let (jwt, _) = helpers::extract_cookies(resp.headers());
+    let jwt = jwt.unwrap();
+
+    let chunks = [
+        "first-chunk".repeat(100).into_bytes(),
+        "second-chunk".repeat(50).into_bytes(),
+    ];
+
+    let req = test::TestRequest::post()
+        .uri("/api/storage")
+        .cookie(jwt.clone())
+        .set_json(serde_json::json!({
+            "encrypted_key": cryptfns::base64::encode("encrypted-gibberish"),
+            "encrypted_name": cryptfns::hex::encode("tus-file"),
+            "name_hash": cryptfns::sha256::digest("tus-file"),
+            "mime": "text/plain",
+            "size": chunks[0].len() + chunks[1].len(),
+            "chunks": 2,
+        }))
+        .to_request();
+
+    let file: AppFile = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/api/storage/tus")
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.headers().get("Tus-Version").unwrap(), "1.0.0");
+    assert_eq!(
+        response.headers().get("Tus-Extension").unwrap(),
+        "creation,expiration"
+    );
+
+    let mut locations = vec![];
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let metadata = [
+            ("file_id", file.id.to_string()),
+            ("chunk", i.to_string()),
+            ("checksum", cryptfns::sha256::digest(chunk.as_slice())),
+            ("checksum_function", "sha256".to_string()),
+            ("filename", "ignored.txt".to_string()),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{} {}", key, cryptfns::base64::encode(value)))
+        .collect::<Vec<_>>()
+        .join(",");
+
+        let req = test::TestRequest::post()
+            .uri("/api/storage/tus")
+            .cookie(jwt.clone())
+            .insert_header(("Tus-Resumable", "1.0.0"))
+            .insert_header(("Upload-Length", chunk.len().to_string()))
+            .insert_header(("Upload-Metadata", metadata))
+            .to_request();
+
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(response.headers().get("Upload-Expires").is_some());
+
+        let location = response.headers().get("Location").unwrap();
+        locations.push(location.to_str().unwrap().to_string());
+    }
+
+    let patch = |location: &str, offset: usize, data: &[u8]| {
+        test::TestRequest::patch()
+            .uri(location)
+            .cookie(jwt.clone())
+            .insert_header(("Tus-Resumable", "1.0.0"))
+            .insert_header(("Content-Type", "application/offset+octet-stream"))
+            .insert_header(("Upload-Offset", offset.to_string()))
+            .set_payload(data.to_vec())
+            .to_request()
+    };
+
+    let head = |location: &str| {
+        test::TestRequest::default()
+            .method(actix_web::http::Method::HEAD)
+            .uri(location)
+            .cookie(jwt.clone())
+            .insert_header(("Tus-Resumable", "1.0.0"))
+            .to_request()
+    };
+
+    // First chunk is interrupted half way through
+    let half = chunks[0].len() / 2;
+    let response = test::call_service(&app, patch(&locations[0], 0, &chunks[0][..half])).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers().get("Upload-Offset").unwrap(),
+        half.to_string().as_str()
+    );
+
+    // Same bytes sent again are refused
+    let response = test::call_service(&app, patch(&locations[0], 0, &chunks[0][..half])).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Client asks where to continue from
+    let response = test::call_service(&app, head(&locations[0])).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("Upload-Offset").unwrap(),
+        half.to_string().as_str()
+    );
+    assert_eq!(
+        response.headers().get("Upload-Length").unwrap(),
+        chunks[0].len().to_string().as_str()
+    );
+
+    let response = test::call_service(&app, patch(&locations[0], half, &chunks[0][half..])).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Finished upload is gone, the chunk is stored
+    let response = test::call_service(&app, head(&locations[0])).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = test::call_service(&app, patch(&locations[1], 0, &chunks[1])).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}/metadata", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let uploaded: AppFile = test::call_and_read_body_json(&app, req).await;
+    assert!(uploaded.finished_upload_at.is_some());
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let downloaded = test::call_and_read_body(&app, req).await.to_vec();
+    assert_eq!(downloaded, [chunks[0].clone(), chunks[1].clone()].concat());
+
+    context.config.app.cleanup();
+}
+
+#[actix_web::test]
+async fn test_socket_upload_acknowledges_every_chunk() {
+    use actix_codec::{Decoder, Encoder};
+    use actix_http::ws::{Codec, Frame, Message};
+
+    let context =
+        context::Context::mock_with_data_dir(Some("../data-test-socket".to_string())).await;
+
+    let private = cryptfns::rsa::private::generate().unwrap();
+    let public = cryptfns::rsa::public::from_private(&private).unwrap();
+    let public_string = cryptfns::rsa::public::to_string(&public).unwrap();
+    let fingerprint = cryptfns::rsa::fingerprint(public).unwrap();
+
+    let app = test::init_service(server::app(context.clone())).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/auth/register")
+        .set_json(CreateUser {
+            email: Some("john@doe.com".to_string()),
+            password: Some("not-4-weak-password-for-god-sakes!".to_string()),
+            secret: None,
+            token: None,
+            pubkey: Some(public_string),
+            fingerprint: Some(fingerprint),
+            encrypted_private_key: Some("some-random-encrypted-secret".to_string()),
+            invitation_id: None,
+        })
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let (jwt, _) = helpers::extract_cookies(resp.headers());
+    let jwt = jwt.unwrap();
+
+    let chunks = [
+        "first-chunk".repeat(100).into_bytes(),
+        "second-chunk".repeat(50).into_bytes(),
+    ];
+
+    let req = test::TestRequest::post()
+        .uri("/api/storage")
+        .cookie(jwt.clone())
+        .set_json(serde_json::json!({
+            "encrypted_key": cryptfns::base64::encode("encrypted-gibberish"),
+            "encrypted_name": cryptfns::hex::encode("socket-file"),
+            "name_hash": cryptfns::sha256::digest("socket-file"),
+            "mime": "text/plain",
+            "size": chunks[0].len() + chunks[1].len(),
+            "chunks": 2,
+        }))
+        .to_request();
+
+    let file: AppFile = test::call_and_read_body_json(&app, req).await;
+
+    let meta = |chunk: usize, data: &[u8]| {
+        Message::Text(
+            serde_json::json!({
+                "chunk": chunk,
+                "checksum": cryptfns::sha256::digest(data),
+                "checksum_function": "sha256",
+            })
+            .to_string()
+            .into(),
+        )
+    };
+
+    // Second chunk is sent with the checksum of the first one and sent again
+    let messages = vec![
+        meta(0, &chunks[0]),
+        Message::Binary(chunks[0].clone().into()),
+        meta(1, &chunks[0]),
+        Message::Binary(chunks[1].clone().into()),
+        meta(1, &chunks[1]),
+        Message::Binary(chunks[1].clone().into()),
+        Message::Close(None),
+    ];
+
+    let mut client = Codec::new().client_mode();
+    let mut payload = actix_web::web::BytesMut::new();
+    for message in messages {
+        client.encode(message, &mut payload).unwrap();
+    }
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}/socket", &file.id).as_str())
+        .cookie(jwt.clone())
+        .insert_header(("Upgrade", "websocket"))
+        .insert_header(("Connection", "Upgrade"))
+        .insert_header(("Sec-WebSocket-Version", "13"))
+        .insert_header(("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+        .set_payload(payload.freeze())
+        .to_request();
+
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(
+        response.headers().get("Sec-WebSocket-Accept").unwrap(),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+
+    let mut body = actix_web::web::BytesMut::from(&test::read_body(response).await[..]);
+    let mut acks = vec![];
+
+    while let Some(frame) = client.decode(&mut body).unwrap() {
+        match frame {
+            Frame::Text(text) => {
+                acks.push(serde_json::from_slice::<serde_json::Value>(&text).unwrap())
+            }
+            Frame::Close(_) => break,
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+
+    assert_eq!(acks.len(), 3);
+    assert_eq!(acks[0]["chunk"], 0);
+    assert_eq!(acks[0]["file"]["chunks_stored"], 1);
+    assert_eq!(acks[1]["chunk"], 1);
+    assert_eq!(acks[1]["status"], 422);
+    assert!(acks[1]["file"].is_null());
+    assert_eq!(acks[2]["chunk"], 1);
+    assert!(acks[2]["file"]["finished_upload_at"].is_number());
+
+    let req = test::TestRequest::get()
+        .uri(format!("/api/storage/{}", &file.id).as_str())
+        .cookie(jwt.clone())
+        .to_request();
+
+    let downloaded = test::call_and_read_body(&app, req).await.to_vec();
+    assert_eq!(downloaded, [chunks[0].clone(), chunks[1].clone()].concat());
+
+    context.config.app.cleanup();
+}
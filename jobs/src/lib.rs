@@ -0,0 +1,12 @@
+//! # Background jobs
+//!
+//! Jobs are stored in the database so they survive restarts, and they are
+//! picked up by the [worker::Worker] that is started together with the server.
+//! Modules push jobs with [repository::Repository::push] and register
+//! a [worker::Handler] for each kind of job they push.
+
+pub mod repository;
+pub mod worker;
+
+#[cfg(test)]
+mod test;
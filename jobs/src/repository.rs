@@ -0,0 +1,185 @@
+use entity::{
+    jobs, ActiveValue, ColumnTrait, Condition, ConnectionTrait, EntityTrait, Expr, QueryFilter,
+    QueryOrder, QuerySelect, Uuid,
+};
+use error::{AppResult, Error};
+use serde::Serialize;
+
+/// How many times the job is attempted before it is marked as failed
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry of the failed job, doubled with each attempt
+const RETRY_DELAY_SECONDS: i64 = 30;
+
+/// Longest delay between two attempts of the same job
+const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+
+/// Job that was locked for longer than this is considered abandoned
+/// (server stopped while running it) and can be picked up again.
+const LOCK_TIMEOUT_SECONDS: i64 = 3600;
+
+pub struct Repository<'ctx, T: ConnectionTrait> {
+    connection: &'ctx T,
+}
+
+impl<'ctx, T> Repository<'ctx, T>
+where
+    T: ConnectionTrait,
+{
+    pub fn new(connection: &'ctx T) -> Self {
+        Self { connection }
+    }
+
+    /// Push a new job to the queue, it will be picked up on the next worker run.
+    pub async fn push<P: Serialize>(&self, kind: &str, payload: &P) -> AppResult<Uuid> {
+        self.push_at(kind, payload, chrono::Utc::now().timestamp())
+            .await
+    }
+
+    /// Push a new job to the queue that is not picked up before the given timestamp.
+    pub async fn push_at<P: Serialize>(
+        &self,
+        kind: &str,
+        payload: &P,
+        run_at: i64,
+    ) -> AppResult<Uuid> {
+        let id = Uuid::new_v4();
+        let now = chrono::Utc::now().timestamp();
+
+        let job = jobs::ActiveModel {
+            id: ActiveValue::Set(id),
+            kind: ActiveValue::Set(kind.to_string()),
+            payload: ActiveValue::Set(serde_json::to_string(payload)?),
+            attempts: ActiveValue::Set(0),
+            max_attempts: ActiveValue::Set(DEFAULT_MAX_ATTEMPTS),
+            last_error: ActiveValue::Set(None),
+            run_at: ActiveValue::Set(run_at),
+            locked_at: ActiveValue::Set(None),
+            failed_at: ActiveValue::Set(None),
+            created_at: ActiveValue::Set(now),
+        };
+
+        jobs::Entity::insert(job)
+            .exec_without_returning(self.connection)
+            .await?;
+
+        Ok(id)
+    }
+
+    /// Get a job by its id
+    pub async fn get(&self, id: Uuid) -> AppResult<jobs::Model> {
+        jobs::Entity::find_by_id(id)
+            .one(self.connection)
+            .await?
+            .ok_or_else(|| Error::NotFound("job_not_found".to_string()))
+    }
+
+    /// Load the jobs that should be run now, oldest first.
+    pub async fn due(&self, limit: u64) -> AppResult<Vec<jobs::Model>> {
+        let now = chrono::Utc::now().timestamp();
+
+        let jobs = jobs::Entity::find()
+            .filter(jobs::Column::FailedAt.is_null())
+            .filter(jobs::Column::RunAt.lte(now))
+            .filter(
+                Condition::any()
+                    .add(jobs::Column::LockedAt.is_null())
+                    .add(jobs::Column::LockedAt.lt(now - LOCK_TIMEOUT_SECONDS)),
+            )
+            .order_by_asc(jobs::Column::RunAt)
+            .order_by_asc(jobs::Column::CreatedAt)
+            .limit(limit)
+            .all(self.connection)
+            .await?;
+
+        Ok(jobs)
+    }
+
+    /// Lock the job for running and count the attempt, returns false
+    /// if somebody else has already taken it.
+    pub async fn lock(&self, job: &jobs::Model) -> AppResult<bool> {
+        let now = chrono::Utc::now().timestamp();
+
+        let mut condition = Condition::all()
+            .add(jobs::Column::Id.eq(job.id))
+            .add(jobs::Column::Attempts.eq(job.attempts));
+
+        condition = match job.locked_at {
+            Some(locked_at) => condition.add(jobs::Column::LockedAt.eq(locked_at)),
+            None => condition.add(jobs::Column::LockedAt.is_null()),
+        };
+
+        let result = jobs::Entity::update_many()
+            .col_expr(jobs::Column::LockedAt, Expr::value(now))
+            .col_expr(
+                jobs::Column::Attempts,
+                Expr::col(jobs::Column::Attempts).add(1),
+            )
+            .filter(condition)
+            .exec(self.connection)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// The job has finished successfully, remove it from the queue.
+    pub async fn complete(&self, id: Uuid) -> AppResult<()> {
+        jobs::Entity::delete_by_id(id).exec(self.connection).await?;
+
+        Ok(())
+    }
+
+    /// The job has failed, schedule it for another attempt with an increasing delay,
+    /// or mark it as failed when it runs out of attempts.
+    ///
+    /// The `attempts` are taken from the job as it was before it was locked.
+    pub async fn fail(&self, job: &jobs::Model, error: &str) -> AppResult<()> {
+        let now = chrono::Utc::now().timestamp();
+        let attempts = job.attempts + 1;
+
+        let mut update = jobs::Entity::update_many()
+            .col_expr(jobs::Column::LockedAt, Expr::value(Option::<i64>::None))
+            .col_expr(jobs::Column::LastError, Expr::value(error));
+
+        if attempts >= job.max_attempts {
+            update = update.col_expr(jobs::Column::FailedAt, Expr::value(now));
+        } else {
+            let delay = RETRY_DELAY_SECONDS
+                .saturating_mul(1 << (attempts - 1).min(16))
+                .min(MAX_RETRY_DELAY_SECONDS);
+
+            update = update.col_expr(jobs::Column::RunAt, Expr::value(now + delay));
+        }
+
+        update
+            .filter(jobs::Column::Id.eq(job.id))
+            .exec(self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Put the failed job back to the queue with all of its attempts, it is picked up on
+    /// the next worker run. The error from its last attempt is kept until it runs again.
+    pub async fn retry(&self, id: Uuid) -> AppResult<jobs::Model> {
+        let job = self.get(id).await?;
+
+        if job.failed_at.is_none() {
+            return Err(Error::BadRequest("job_not_failed".to_string()));
+        }
+
+        jobs::Entity::update_many()
+            .col_expr(jobs::Column::Attempts, Expr::value(0))
+            .col_expr(jobs::Column::FailedAt, Expr::value(Option::<i64>::None))
+            .col_expr(jobs::Column::LockedAt, Expr::value(Option::<i64>::None))
+            .col_expr(
+                jobs::Column::RunAt,
+                Expr::value(chrono::Utc::now().timestamp()),
+            )
+            .filter(jobs::Column::Id.eq(job.id))
+            .exec(self.connection)
+            .await?;
+
+        self.get(id).await
+    }
+}
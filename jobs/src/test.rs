@@ -0,0 +1,144 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use context::Context;
+use entity::{jobs, ActiveValue, EntityTrait};
+use error::{AppResult, Error};
+
+use crate::{
+    repository::{Repository, DEFAULT_MAX_ATTEMPTS},
+    worker::{Handler, Worker},
+};
+
+/// Handler that fails until it was called the given number of times
+struct Flaky {
+    calls: Arc<AtomicUsize>,
+    fail_times: usize,
+}
+
+#[async_trait]
+impl Handler for Flaky {
+    async fn handle(&self, _context: &Context, payload: &str) -> AppResult<()> {
+        assert_eq!(payload, "{\"hello\":\"world\"}");
+
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.fail_times {
+            return Err(Error::StorageError("provider_unavailable".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Make the job due right away, skipping the retry delay
+async fn make_due(context: &Context, job: jobs::Model) {
+    let now = chrono::Utc::now().timestamp();
+
+    jobs::Entity::update(jobs::ActiveModel {
+        run_at: ActiveValue::Set(now),
+        ..job.into()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+}
+
+#[actix_web::test]
+async fn test_job_is_retried_and_completed() {
+    let context = Context::mock_sqlite().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let repository = Repository::new(&context.db);
+
+    let id = repository
+        .push("flaky", &serde_json::json!({"hello": "world"}))
+        .await
+        .unwrap();
+
+    let worker = Worker::new(context.clone()).handler(
+        "flaky",
+        Flaky {
+            calls: calls.clone(),
+            fail_times: 1,
+        },
+    );
+
+    assert_eq!(worker.run().await.unwrap(), 0);
+
+    let job = repository.get(id).await.unwrap();
+    assert_eq!(job.attempts, 1);
+    assert!(job.locked_at.is_none());
+    assert!(job.failed_at.is_none());
+    assert!(job.run_at > chrono::Utc::now().timestamp());
+    assert!(job.last_error.unwrap().contains("provider_unavailable"));
+
+    // Not due yet, so the worker leaves it alone
+    assert_eq!(worker.run().await.unwrap(), 0);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    make_due(&context, repository.get(id).await.unwrap()).await;
+
+    assert_eq!(worker.run().await.unwrap(), 1);
+    assert!(repository.get(id).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_job_is_marked_failed_after_max_attempts() {
+    let context = Context::mock_sqlite().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let repository = Repository::new(&context.db);
+
+    let id = repository
+        .push("flaky", &serde_json::json!({"hello": "world"}))
+        .await
+        .unwrap();
+
+    let worker = Worker::new(context.clone()).handler(
+        "flaky",
+        Flaky {
+            calls: calls.clone(),
+            fail_times: usize::MAX,
+        },
+    );
+
+    for _ in 0..DEFAULT_MAX_ATTEMPTS {
+        make_due(&context, repository.get(id).await.unwrap()).await;
+        worker.run().await.unwrap();
+    }
+
+    let job = repository.get(id).await.unwrap();
+    assert_eq!(job.attempts, DEFAULT_MAX_ATTEMPTS);
+    assert!(job.failed_at.is_some());
+
+    make_due(&context, job).await;
+    worker.run().await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), DEFAULT_MAX_ATTEMPTS as usize);
+
+    // Retried job gets all of its attempts back and is picked up again
+    let job = repository.retry(id).await.unwrap();
+    assert_eq!(job.attempts, 0);
+    assert!(job.failed_at.is_none());
+    assert!(job.last_error.is_some());
+    assert!(repository.retry(id).await.is_err());
+
+    worker.run().await.unwrap();
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        DEFAULT_MAX_ATTEMPTS as usize + 1
+    );
+}
+
+#[actix_web::test]
+async fn test_job_without_handler_fails() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+
+    let id = repository.push("unknown", &()).await.unwrap();
+
+    Worker::new(context.clone()).run().await.unwrap();
+
+    let job = repository.get(id).await.unwrap();
+    assert!(job.last_error.unwrap().contains("unknown_job_kind"));
+}
@@ -0,0 +1,92 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use context::Context;
+use entity::jobs;
+use error::{AppResult, Error};
+
+use crate::repository::Repository;
+
+/// How often the worker looks for new jobs
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many jobs are loaded from the database on each run
+const BATCH_SIZE: u64 = 50;
+
+/// Runs one kind of jobs
+#[async_trait]
+pub trait Handler: Send + Sync {
+    /// Run the job with its JSON encoded payload, returning an error
+    /// will schedule the job to be retried later.
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()>;
+}
+
+/// Background worker that picks up the due jobs and runs them with the registered handlers
+pub struct Worker {
+    context: Context,
+    handlers: HashMap<String, Box<dyn Handler>>,
+}
+
+impl Worker {
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Register the handler for the given kind of jobs
+    pub fn handler<H: Handler + 'static>(mut self, kind: &str, handler: H) -> Self {
+        self.handlers.insert(kind.to_string(), Box::new(handler));
+
+        self
+    }
+
+    /// Start the worker loop in the background
+    pub fn spawn(self) {
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = self.run().await {
+                    log::error!("Failed running background jobs: {}", e);
+                }
+
+                actix_web::rt::time::sleep(POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Run all the jobs that are due, returns the number of jobs that finished successfully.
+    pub async fn run(&self) -> AppResult<usize> {
+        let repository = Repository::new(&self.context.db);
+        let mut finished = 0;
+
+        for job in repository.due(BATCH_SIZE).await? {
+            if !repository.lock(&job).await? {
+                continue;
+            }
+
+            match self.handle(&job).await {
+                Ok(()) => {
+                    repository.complete(job.id).await?;
+                    finished += 1;
+                }
+                Err(e) => {
+                    log::warn!("Job {} ({}) failed: {}", job.id, job.kind, e);
+                    repository.fail(&job, &e.to_string()).await?;
+                }
+            }
+        }
+
+        Ok(finished)
+    }
+
+    /// Find the handler for the job and run it
+    async fn handle(&self, job: &jobs::Model) -> AppResult<()> {
+        let handler = self
+            .handlers
+            .get(&job.kind)
+            .ok_or_else(|| Error::InternalError(format!("unknown_job_kind: {}", job.kind)))?;
+
+        handler.handle(&self.context, &job.payload).await
+    }
+}
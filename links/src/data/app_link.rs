@@ -3,6 +3,11 @@ use error::{AppResult, Error};
 use fs::{prelude::Filename, IntoFilename};
 use serde::{Deserialize, Serialize};
 
+use crate::restriction;
+
+/// Longest password of the link
+pub const MAX_LINK_PASSWORD_LENGTH: usize = 72;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppLink {
     pub id: Uuid,
@@ -12,14 +17,19 @@ pub struct AppLink {
     pub owner_pubkey: String,
     pub file_size: Option<i64>,
     pub file_mime: String,
+    /// Number of the chunks the content of the file is split into
+    pub file_chunks: Option<i64>,
+    /// Version of the scheme the file content is encrypted with
+    pub file_crypto_version: i32,
     /// Signature that the user created when the link was initially created.
     ///
     /// The signature is made using a shared file_id
     pub signature: String,
-    /// Number of times the file has been downloaded,
-    /// this counts every attempt to download the file,
-    /// it doesn't wait for the file to be downloaded in order to increment.
+    /// Number of times the file has been downloaded, the download is counted
+    /// when it starts and given back if it doesn't finish.
     pub downloads: i32,
+    /// Number of times the file can be downloaded through the link, any if not set
+    pub max_downloads: Option<i32>,
     /// Name of the file encrypted with the link key. If the file is
     /// renamed after the link is created, that change won't be reflected
     /// in the link.
@@ -37,10 +47,26 @@ pub struct AppLink {
     pub encrypted_file_key: Option<String>,
     pub created_at: i64,
     pub file_modified_at: i64,
+    /// Date when the file can be downloaded through the link,
+    /// until then only the metadata of the link is available.
+    pub starts_at: Option<i64>,
     /// Date when the link will expire, automated cron job
     /// will periodically empty out the expired links of all the
     /// file metadata and encrypted file key.
     pub expires_at: Option<i64>,
+    /// Text overlaid on the previews served through the link
+    pub watermark: Option<String>,
+    /// Countries the file can be downloaded from through the link, anywhere if empty
+    pub allowed_countries: Vec<String>,
+    /// Address ranges the file can be downloaded from through the link, any if empty
+    pub allowed_networks: Vec<String>,
+    /// The file can be downloaded through the link only with the password
+    pub has_password: bool,
+    /// Short name the link can be opened by instead of its id, see [crate::slug]
+    pub slug: Option<String>,
+    /// Hash of the password of the link, it never leaves the server
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
 }
 
 impl AppLink {
@@ -75,6 +101,61 @@ impl AppLink {
             .map(|expires_at| expires_at < now)
             .unwrap_or(false)
     }
+
+    /// Let us know if the link has started so the file can't be downloaded before it.
+    pub fn is_started(&self) -> bool {
+        let now = chrono::Utc::now().timestamp();
+
+        self.starts_at
+            .map(|starts_at| starts_at <= now)
+            .unwrap_or(true)
+    }
+
+    /// Let us know if the file was downloaded through the link as many times as it can be.
+    pub fn is_exhausted(&self) -> bool {
+        self.max_downloads
+            .map(|max_downloads| self.downloads >= max_downloads)
+            .unwrap_or(false)
+    }
+
+    /// Throw an error if the file can't be downloaded through the link right now.
+    pub fn verify_available(&self) -> AppResult<()> {
+        self.verify_open()?;
+
+        if self.is_exhausted() {
+            return Err(Error::Gone("link_downloads_exhausted".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Throw an error if the link expired or hasn't started yet, the downloads it had
+    /// don't matter for the download that was already counted, see [crate::quota].
+    pub fn verify_open(&self) -> AppResult<()> {
+        if self.is_expired() {
+            return Err(Error::Unauthorized("link_expired".to_string()));
+        }
+
+        if !self.is_started() {
+            return Err(Error::Unauthorized("link_not_started".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Throw an error unless the link has no password or the given one is right.
+    pub fn verify_password(&self, password: Option<&str>) -> AppResult<()> {
+        let hash = match self.password.as_deref() {
+            Some(hash) => hash,
+            None => return Ok(()),
+        };
+
+        match password {
+            Some(password) if util::password::verify(password, hash) => Ok(()),
+            Some(_) => Err(Error::Unauthorized("invalid_link_password".to_string())),
+            None => Err(Error::Unauthorized("link_password_required".to_string())),
+        }
+    }
 }
 
 impl FromQueryResult for AppLink {
@@ -88,15 +169,25 @@ impl FromQueryResult for AppLink {
             file_id: file.id,
             file_size: file.size,
             file_mime: file.mime,
+            file_chunks: file.chunks,
+            file_crypto_version: file.crypto_version,
             signature: link.signature,
             downloads: link.downloads,
+            max_downloads: link.max_downloads,
             encrypted_name: link.encrypted_name,
             encrypted_link_key: link.encrypted_link_key,
             encrypted_thumbnail: link.encrypted_thumbnail,
             encrypted_file_key: link.encrypted_file_key,
             created_at: link.created_at,
             file_modified_at: file.created_at,
+            starts_at: link.starts_at,
             expires_at: link.expires_at,
+            watermark: link.watermark,
+            allowed_countries: restriction::split(link.allowed_countries.as_deref()),
+            allowed_networks: restriction::split(link.allowed_networks.as_deref()),
+            slug: link.slug,
+            has_password: link.password.is_some(),
+            password: link.password,
             owner_id: user.id,
             owner_email: user.email,
             owner_pubkey: user.pubkey,
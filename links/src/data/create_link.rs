@@ -4,6 +4,16 @@ use entity::{links::ActiveModel, ActiveValue, Uuid};
 use serde::{Deserialize, Serialize};
 use validr::*;
 
+use super::{
+    app_link::MAX_LINK_PASSWORD_LENGTH,
+    gallery::{GalleryItem, MAX_GALLERY_IMAGES},
+};
+use crate::{
+    restriction::{self, Network, MAX_RESTRICTIONS},
+    slug,
+    watermark::MAX_WATERMARK_LENGTH,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateLink {
     /// Id of the file that will be shared.
@@ -21,11 +31,42 @@ pub struct CreateLink {
     /// If the file has a thumbnail it is encrypted with the link key.
     pub encrypted_thumbnail: Option<String>,
 
-    /// AES key for the file encrypted with a link AES key.
+    /// AES key for the file encrypted with a link AES key,
+    /// required for the files, folders have the keys of their images instead.
     pub encrypted_file_key: Option<String>,
 
+    /// Images of the folder shown in the gallery, required for the folders.
+    pub items: Option<Vec<GalleryItem>>,
+
+    /// Optional date when the file can be downloaded through the link,
+    /// so the release can be prepared ahead and published at the set time.
+    pub starts_at: Option<i64>,
+
     /// Optional date when the link will expire.
     pub expires_at: Option<i64>,
+
+    /// Optional text overlaid on the previews served through the link.
+    pub watermark: Option<String>,
+
+    /// Optional two letter codes of the countries the file can be downloaded from.
+    pub allowed_countries: Option<Vec<String>>,
+
+    /// Optional address ranges in the CIDR notation the file can be downloaded from.
+    pub allowed_networks: Option<Vec<String>>,
+
+    /// Optional password the file can be downloaded through the link with.
+    pub password: Option<String>,
+
+    /// Optional number of times the file can be downloaded through the link.
+    pub max_downloads: Option<i32>,
+
+    /// Key of the file encrypted with the organization recovery key, required when
+    /// the key escrow is enabled and the key of the file isn't escrowed yet.
+    pub escrow_key: Option<String>,
+
+    /// Optional short name the link can be opened by, see [crate::slug].
+    #[serde(default)]
+    pub slug: Option<String>,
 }
 
 impl Validation for CreateLink {
@@ -34,15 +75,102 @@ impl Validation for CreateLink {
             rule_required!(file_id),
             rule_required!(encrypted_name),
             rule_required!(encrypted_link_key),
-            rule_required!(encrypted_file_key),
+            Rule::new("starts_at", |obj: &Self, error| {
+                if let (Some(starts_at), Some(expires_at)) = (obj.starts_at, obj.expires_at) {
+                    if starts_at >= expires_at {
+                        error.add("starts_at_after_expires_at");
+                    }
+                }
+            }),
+            Rule::new("watermark", |obj: &Self, error| {
+                if let Some(watermark) = obj.watermark.as_deref() {
+                    if watermark.chars().count() > MAX_WATERMARK_LENGTH {
+                        error.add(format!("max:{}", MAX_WATERMARK_LENGTH).as_str());
+                    }
+                }
+            }),
+            Rule::new("allowed_countries", |obj: &Self, error| {
+                if let Some(countries) = obj.allowed_countries.as_ref() {
+                    if countries.len() > MAX_RESTRICTIONS {
+                        error.add(format!("max:{}", MAX_RESTRICTIONS).as_str());
+                    }
+
+                    if !countries.iter().all(|c| restriction::is_country(c)) {
+                        error.add("invalid_country");
+                    }
+                }
+            }),
+            Rule::new("allowed_networks", |obj: &Self, error| {
+                if let Some(networks) = obj.allowed_networks.as_ref() {
+                    if networks.len() > MAX_RESTRICTIONS {
+                        error.add(format!("max:{}", MAX_RESTRICTIONS).as_str());
+                    }
+
+                    if networks.iter().any(|n| n.parse::<Network>().is_err()) {
+                        error.add("invalid_network");
+                    }
+                }
+            }),
+            Rule::new("password", |obj: &Self, error| {
+                if let Some(password) = obj.password.as_deref() {
+                    if password.len() > MAX_LINK_PASSWORD_LENGTH {
+                        error.add(format!("max:{}", MAX_LINK_PASSWORD_LENGTH).as_str());
+                    }
+                }
+            }),
+            Rule::new("max_downloads", |obj: &Self, error| {
+                if let Some(max_downloads) = obj.max_downloads {
+                    if max_downloads < 1 {
+                        error.add("min:1");
+                    }
+                }
+            }),
+            Rule::new("slug", |obj: &Self, error| {
+                if let Some(slug) = obj.slug.as_deref().filter(|s| !s.is_empty()) {
+                    if let Err(message) = slug::verify(slug) {
+                        error.add(&message);
+                    }
+                }
+            }),
+            Rule::new("items", |obj: &Self, error| {
+                if let Some(items) = obj.items.as_ref() {
+                    if items.len() > MAX_GALLERY_IMAGES {
+                        error.add(format!("max:{}", MAX_GALLERY_IMAGES).as_str());
+                    }
+
+                    if items.iter().any(|item| item.clone().validate().is_err()) {
+                        error.add("invalid_item");
+                    }
+                }
+            }),
         ]
     }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(slug), modifier_lowercase!(slug)]
+    }
 }
 
 impl CreateLink {
-    pub fn into_active_model(self, user_id: Uuid) -> AppResult<(ActiveModel, String, Uuid)> {
+    pub fn into_active_model(
+        self,
+        user_id: Uuid,
+    ) -> AppResult<(ActiveModel, String, Uuid, Vec<GalleryItem>)> {
         let data = self.validate()?;
 
+        let allowed_countries = data
+            .allowed_countries
+            .unwrap_or_default()
+            .iter()
+            .map(|country| country.to_uppercase())
+            .collect::<Vec<_>>();
+        let allowed_networks = data
+            .allowed_networks
+            .unwrap_or_default()
+            .iter()
+            .map(|network| network.parse::<Network>().map(|n| n.to_string()))
+            .collect::<AppResult<Vec<_>>>()?;
+
         let file_id = match data.file_id.as_deref() {
             Some(v) => Uuid::parse_str(v)?,
             None => {
@@ -62,10 +190,22 @@ impl CreateLink {
                 encrypted_thumbnail: ActiveValue::Set(data.encrypted_thumbnail),
                 encrypted_file_key: ActiveValue::Set(data.encrypted_file_key),
                 created_at: ActiveValue::Set(Utc::now().timestamp()),
+                starts_at: ActiveValue::Set(data.starts_at),
                 expires_at: ActiveValue::Set(data.expires_at),
+                watermark: ActiveValue::Set(data.watermark.filter(|w| !w.trim().is_empty())),
+                allowed_countries: ActiveValue::Set(restriction::join(&allowed_countries)),
+                allowed_networks: ActiveValue::Set(restriction::join(&allowed_networks)),
+                password: ActiveValue::Set(
+                    data.password
+                        .filter(|p| !p.is_empty())
+                        .map(util::password::hash),
+                ),
+                max_downloads: ActiveValue::Set(data.max_downloads),
+                slug: ActiveValue::Set(data.slug.filter(|s| !s.is_empty())),
             },
             data.signature.unwrap(),
             file_id,
+            data.items.unwrap_or_default(),
         ))
     }
 }
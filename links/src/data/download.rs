@@ -5,6 +5,10 @@ use validr::*;
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Download {
     pub link_key: Option<String>,
+
+    /// Password of the link, required only for the links protected with one.
+    #[serde(default)]
+    pub password: Option<String>,
 }
 
 impl Validation for Download {
@@ -14,9 +18,11 @@ impl Validation for Download {
 }
 
 impl Download {
-    pub fn into_value(self) -> AppResult<Vec<u8>> {
+    /// Decoded link key, together with the password sent for the link
+    pub fn into_value(self) -> AppResult<(Vec<u8>, Option<String>)> {
         let data = self.validate()?;
+        let link_key = cryptfns::hex::decode(data.link_key.unwrap())?;
 
-        Ok(cryptfns::hex::decode(data.link_key.unwrap())?)
+        Ok((link_key, data.password.filter(|p| !p.is_empty())))
     }
 }
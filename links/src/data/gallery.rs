@@ -0,0 +1,97 @@
+//! Gallery mode of the folder links, the images of the folder are listed
+//! page by page with their thumbnails and downloaded one by one.
+use ::error::{AppResult, Error};
+use entity::{files, link_files, Uuid};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum number of images a single gallery link can show
+pub const MAX_GALLERY_IMAGES: usize = 1000;
+
+/// Image of the folder added to the gallery, everything is encrypted with the link key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GalleryItem {
+    pub file_id: Option<Uuid>,
+    pub encrypted_name: Option<String>,
+    pub encrypted_thumbnail: Option<String>,
+    pub encrypted_file_key: Option<String>,
+}
+
+impl Validation for GalleryItem {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(file_id),
+            rule_required!(encrypted_name),
+            rule_required!(encrypted_file_key),
+        ]
+    }
+}
+
+/// Page of the gallery
+#[derive(Debug, Clone, Deserialize)]
+pub struct GalleryPage {
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+impl Validation for GalleryPage {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("limit", |obj: &Self, error| {
+            if let Some(limit) = obj.limit {
+                if limit == 0 || limit > 100 {
+                    error.add("between:1:100")
+                }
+            }
+        })]
+    }
+}
+
+impl GalleryPage {
+    pub fn into_value(self) -> AppResult<(u64, u64)> {
+        let data = self.validate()?;
+
+        Ok((data.offset.unwrap_or(0), data.limit.unwrap_or(30)))
+    }
+}
+
+/// Image in the gallery page
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryImage {
+    pub file_id: Uuid,
+    pub mime: String,
+    pub size: Option<i64>,
+    pub encrypted_name: String,
+    pub encrypted_thumbnail: Option<String>,
+    /// Where the image is downloaded from with the link key
+    pub download_url: String,
+}
+
+impl GalleryImage {
+    pub fn new(item: link_files::Model, file: files::Model) -> Self {
+        Self {
+            download_url: format!("/api/links/{}/gallery/{}", item.link_id, item.file_id),
+            file_id: item.file_id,
+            mime: file.mime,
+            size: file.size,
+            encrypted_name: item.encrypted_name,
+            encrypted_thumbnail: item.encrypted_thumbnail,
+        }
+    }
+}
+
+/// Decrypt the name of the gallery image with the AES link key
+pub fn decrypt_name(item: &link_files::Model, link_key: &[u8]) -> AppResult<String> {
+    let ciphertext = cryptfns::hex::decode(&item.encrypted_name)?;
+    let plaintext = cryptfns::aes::decrypt(link_key.to_vec(), ciphertext)?;
+
+    String::from_utf8(plaintext).map_err(Error::from)
+}
+
+/// Decrypt the file key of the gallery image with the AES link key
+pub fn file_key(item: &link_files::Model, link_key: &[u8]) -> AppResult<Vec<u8>> {
+    let ciphertext = cryptfns::hex::decode(&item.encrypted_file_key)?;
+    let plaintext = cryptfns::aes::decrypt(link_key.to_vec(), ciphertext)?;
+    let file_key = cryptfns::hex::decode(String::from_utf8(plaintext)?)?;
+
+    Ok(file_key)
+}
@@ -0,0 +1,71 @@
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_link::{AppLink, MAX_LINK_PASSWORD_LENGTH};
+
+/// Open the landing page of the link, only the links protected
+/// with the password need it, the link key never leaves the page.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct Landing {
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl Validation for Landing {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![rule_length_max!(password, MAX_LINK_PASSWORD_LENGTH)]
+    }
+}
+
+impl Landing {
+    pub fn into_value(self) -> AppResult<Option<String>> {
+        let data = self.validate()?;
+
+        Ok(data.password.filter(|p| !p.is_empty()))
+    }
+}
+
+/// Chunks the encrypted content of the file is stored in
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChunkMap {
+    /// Number of the chunks the file is split into
+    pub chunks: i64,
+    /// Size of the plain chunk, every chunk but the last one has it
+    pub chunk_size: u64,
+    /// Chunks that are in the storage, the ones that are missing can't be downloaded
+    pub stored: Vec<i64>,
+}
+
+/// Everything the landing page needs to decrypt the file in the browser
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LandingPage {
+    #[serde(flatten)]
+    pub link: AppLink,
+    /// Key of the file encrypted with the link key, the page decrypts it with the key
+    /// from the URL. Links with the watermark don't have it, the watermark is put
+    /// on the file on the server, so they are only downloaded through the link.
+    pub encrypted_file_key: Option<String>,
+    pub chunk_map: ChunkMap,
+}
+
+impl LandingPage {
+    pub fn new(link: AppLink, stored: Vec<i64>) -> Self {
+        let encrypted_file_key = match link.watermark {
+            Some(_) => None,
+            None => link.encrypted_file_key.clone(),
+        };
+
+        let chunk_map = ChunkMap {
+            chunks: link.file_chunks.unwrap_or_default(),
+            chunk_size: fs::MAX_CHUNK_SIZE_BYTES,
+            stored,
+        };
+
+        Self {
+            link,
+            encrypted_file_key,
+            chunk_map,
+        }
+    }
+}
@@ -2,4 +2,7 @@ pub mod app_link;
 pub mod create_link;
 pub mod download;
 pub mod find;
+pub mod gallery;
+pub mod landing;
+pub mod slug;
 pub mod update;
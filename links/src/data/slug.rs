@@ -0,0 +1,38 @@
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use crate::slug;
+
+/// Set the slug of the link, replacing the one it had
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetSlug {
+    pub slug: Option<String>,
+}
+
+impl Validation for SetSlug {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(slug),
+            Rule::new("slug", |obj: &Self, error| {
+                if let Some(slug) = obj.slug.as_deref().filter(|s| !s.is_empty()) {
+                    if let Err(message) = slug::verify(slug) {
+                        error.add(&message);
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(slug), modifier_lowercase!(slug)]
+    }
+}
+
+impl SetSlug {
+    pub fn into_value(self) -> AppResult<String> {
+        let data = self.validate()?;
+
+        Ok(data.slug.unwrap())
+    }
+}
@@ -2,21 +2,74 @@ use ::error::AppResult;
 use serde::Deserialize;
 use validr::*;
 
+use super::app_link::MAX_LINK_PASSWORD_LENGTH;
+use crate::watermark::MAX_WATERMARK_LENGTH;
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Update {
+    /// New expiry of the link as a unix timestamp, zero removes it and
+    /// the expiry is kept as it is if not sent.
     pub expires_at: Option<i64>,
+
+    /// New watermark of the link, empty text removes it and
+    /// the watermark is kept as it is if not sent.
+    pub watermark: Option<String>,
+
+    /// New password of the link, empty text removes it and
+    /// the password is kept as it is if not sent.
+    pub password: Option<String>,
+
+    /// New number of times the file can be downloaded through the link, counting
+    /// the downloads it already had, zero removes the limit and it is kept if not sent.
+    pub max_downloads: Option<i32>,
 }
 
 impl Validation for Update {
     fn rules(&self) -> Vec<Rule<Self>> {
-        vec![]
+        vec![
+            Rule::new("expires_at", |obj: &Self, error| {
+                if let Some(expires_at) = obj.expires_at {
+                    if expires_at < 0 {
+                        error.add("min:0");
+                    }
+                }
+            }),
+            Rule::new("watermark", |obj: &Self, error| {
+                if let Some(watermark) = obj.watermark.as_deref() {
+                    if watermark.chars().count() > MAX_WATERMARK_LENGTH {
+                        error.add(format!("max:{}", MAX_WATERMARK_LENGTH).as_str());
+                    }
+                }
+            }),
+            Rule::new("password", |obj: &Self, error| {
+                if let Some(password) = obj.password.as_deref() {
+                    if password.len() > MAX_LINK_PASSWORD_LENGTH {
+                        error.add(format!("max:{}", MAX_LINK_PASSWORD_LENGTH).as_str());
+                    }
+                }
+            }),
+            Rule::new("max_downloads", |obj: &Self, error| {
+                if let Some(max_downloads) = obj.max_downloads {
+                    if max_downloads < 0 {
+                        error.add("min:0");
+                    }
+                }
+            }),
+        ]
     }
 }
 
 impl Update {
-    pub fn into_value(self) -> AppResult<Option<i64>> {
+    pub fn into_value(
+        self,
+    ) -> AppResult<(Option<i64>, Option<String>, Option<String>, Option<i32>)> {
         let data = self.validate()?;
 
-        Ok(data.expires_at)
+        Ok((
+            data.expires_at,
+            data.watermark,
+            data.password,
+            data.max_downloads,
+        ))
     }
 }
@@ -1,5 +1,10 @@
 pub mod data;
+pub mod limiter;
+pub mod quota;
+pub mod restriction;
 pub mod routes;
+pub mod slug;
+pub mod watermark;
 
 pub(crate) mod repository;
 
@@ -0,0 +1,128 @@
+//! # Connection limits
+//!
+//! Downloads of the public links are not authenticated and each of them keeps a worker
+//! busy for as long as the file is being streamed, so a single client or a single popular
+//! link could take all of them. The number of downloads running at once is limited for
+//! each address and each link, the download over the limits waits in the queue for
+//! the configured time and is refused with `429 Too Many Requests` after that.
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::SocketAddr,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use config::server::ServerConfig;
+use entity::Uuid;
+use error::{AppResult, Error};
+
+/// How often the queued download checks for a free connection
+const QUEUE_POLL: Duration = Duration::from_millis(100);
+
+/// Number of the running downloads for each of the addresses and the links
+#[derive(Default)]
+struct Connections {
+    ips: HashMap<String, usize>,
+    links: HashMap<Uuid, usize>,
+}
+
+fn connections() -> &'static Mutex<Connections> {
+    static CONNECTIONS: OnceLock<Mutex<Connections>> = OnceLock::new();
+
+    CONNECTIONS.get_or_init(|| Mutex::new(Connections::default()))
+}
+
+/// Running download of the link, the connection is released when it is dropped,
+/// so it has to be kept until the whole file is sent.
+#[derive(Debug)]
+pub struct Connection {
+    link_id: Uuid,
+    ip: Option<String>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let mut connections = match connections().lock() {
+            Ok(connections) => connections,
+            Err(e) => e.into_inner(),
+        };
+
+        if let Some(ip) = self.ip.as_ref() {
+            release(&mut connections.ips, ip);
+        }
+
+        release(&mut connections.links, &self.link_id);
+    }
+}
+
+/// Take the connection for the download of the link, waits in the queue
+/// when the address or the link is over its limit.
+pub async fn acquire(
+    config: &ServerConfig,
+    link_id: Uuid,
+    ip: Option<&str>,
+) -> AppResult<Connection> {
+    let ip = ip.map(address);
+    let deadline = Instant::now() + Duration::from_secs(config.links_queue_timeout);
+
+    loop {
+        if let Some(connection) = try_acquire(config, link_id, ip.as_deref()) {
+            return Ok(connection);
+        }
+
+        if Instant::now() >= deadline {
+            return Err(Error::TooManyRequests("too_many_connections".to_string()));
+        }
+
+        actix_web::rt::time::sleep(QUEUE_POLL).await;
+    }
+}
+
+/// Take the connection if neither the address nor the link is over its limit
+pub fn try_acquire(config: &ServerConfig, link_id: Uuid, ip: Option<&str>) -> Option<Connection> {
+    let mut connections = match connections().lock() {
+        Ok(connections) => connections,
+        Err(e) => e.into_inner(),
+    };
+
+    let ip_count = ip.and_then(|ip| connections.ips.get(ip)).copied();
+    let link_count = connections.links.get(&link_id).copied().unwrap_or(0);
+
+    if ip_count.unwrap_or(0) >= config.links_connections_per_ip
+        || link_count >= config.links_connections_per_link
+    {
+        return None;
+    }
+
+    if let Some(ip) = ip {
+        *connections.ips.entry(ip.to_string()).or_default() += 1;
+    }
+
+    *connections.links.entry(link_id).or_default() += 1;
+
+    Some(Connection {
+        link_id,
+        ip: ip.map(|ip| ip.to_string()),
+    })
+}
+
+/// Release one connection of the address or the link, counts that drop to zero are removed
+fn release<K: Hash + Eq>(counts: &mut HashMap<K, usize>, key: &K) {
+    if let Some(count) = counts.get_mut(key) {
+        *count = count.saturating_sub(1);
+
+        if *count == 0 {
+            counts.remove(key);
+        }
+    }
+}
+
+/// Address of the client without the port, the peer address comes with it
+/// when the application isn't behind a proxy.
+pub(crate) fn address(ip: &str) -> String {
+    match ip.parse::<SocketAddr>() {
+        Ok(addr) => addr.ip().to_string(),
+        Err(_) => ip.to_string(),
+    }
+}
@@ -0,0 +1,119 @@
+//! # Download quota
+//!
+//! Link can be limited to the number of times its file is downloaded, for the files that
+//! are handed over only once. The download is counted when it starts, in the same statement
+//! that checks the limit, so the downloads running at once can't get past it together.
+//! Download that doesn't finish, because the client went away or the stream failed, gives
+//! its count back once it is dropped, so only the completed downloads use up the link.
+//!
+//! The landing page downloads the file chunk by chunk, the download is counted with the
+//! first chunk and the response carries the token of the download in [TOKEN_HEADER].
+//! The rest of the chunks are requested with the token, they belong to the download that
+//! is already counted, so they are served even when it used up the last download.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::repository::Repository;
+
+/// Header the token of the counted download is sent back and forth in
+pub const TOKEN_HEADER: &str = "X-Download-Token";
+
+/// How long the rest of the chunks can be requested after the first one
+const SESSION_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Downloads of the landing page that were counted, by their token
+fn sessions() -> &'static Mutex<HashMap<String, (Uuid, Instant)>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, (Uuid, Instant)>>> = OnceLock::new();
+
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Download counted against the link, the count is given back when it is dropped
+/// before it is completed, so it has to be kept until the whole file is sent.
+pub struct Claim {
+    context: Arc<Context>,
+    link_id: Uuid,
+    completed: bool,
+}
+
+impl Claim {
+    /// The whole file was sent, the download stays counted
+    pub fn complete(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+
+        let context = self.context.clone();
+        let link_id = self.link_id;
+
+        actix_web::rt::spawn(async move {
+            if let Err(e) = Repository::new(&context).release_download(link_id).await {
+                log::error!(
+                    "Failed to give back the download of the link {}: {}",
+                    link_id,
+                    e
+                );
+            }
+        });
+    }
+}
+
+/// Count the download of the link, refused with `410 Gone` when the link has no downloads left
+pub async fn claim(context: Arc<Context>, link_id: Uuid) -> AppResult<Claim> {
+    Repository::new(&context)
+        .increment_downloads(link_id)
+        .await?;
+
+    Ok(Claim {
+        context,
+        link_id,
+        completed: false,
+    })
+}
+
+/// Remember the counted download of the link, returns the token its chunks are requested with
+pub fn start_session(link_id: Uuid) -> String {
+    let token = util::generate::generate_secret();
+    let now = Instant::now();
+
+    let mut sessions = match sessions().lock() {
+        Ok(sessions) => sessions,
+        Err(e) => e.into_inner(),
+    };
+
+    sessions.retain(|_, (_, started_at)| now.duration_since(*started_at) < SESSION_TTL);
+    sessions.insert(token.clone(), (link_id, now));
+
+    token
+}
+
+/// Check the token belongs to the download of the link that was already counted
+pub fn in_session(token: Option<&str>, link_id: Uuid) -> bool {
+    let token = match token {
+        Some(token) => token,
+        None => return false,
+    };
+
+    let sessions = match sessions().lock() {
+        Ok(sessions) => sessions,
+        Err(e) => e.into_inner(),
+    };
+
+    matches!(
+        sessions.get(token),
+        Some((id, started_at)) if *id == link_id && started_at.elapsed() < SESSION_TTL
+    )
+}
@@ -1,13 +1,22 @@
+use cached::{proc_macro::cached, Cached, TimedSizedCache};
+use chrono::Utc;
 use context::Context;
 use entity::{
-    files,
+    activities::{self, Action},
+    files, link_files,
     links::{self},
-    user_files, users, ColumnTrait, ConnectionTrait, EntityTrait, Expr, IntoCondition, JoinType,
-    QueryFilter, QuerySelect, RelationTrait, Statement, Uuid,
+    paginated::Paginated,
+    user_files, users, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, Expr, IntoCondition,
+    JoinType, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Statement, Uuid,
 };
 use error::{AppResult, Error};
+use storage::data::virtual_file::VIRTUAL_MIME_PREFIX;
 
-use crate::data::{app_link::AppLink, create_link::CreateLink};
+use crate::data::{
+    app_link::AppLink,
+    create_link::CreateLink,
+    gallery::{GalleryImage, GalleryItem},
+};
 
 pub(crate) struct Repository<'ctx> {
     context: &'ctx Context,
@@ -22,33 +31,91 @@ impl<'ctx> Repository<'ctx> {
     /// Before creating:
     /// - verify the passed signature is valid.
     /// - verify the user is the owner of the file.
+    /// - apply the sharing policy of the folders the file is in.
+    ///
+    /// Link for a folder is a gallery of the images in it, the images are sent with the link.
     pub(crate) async fn create(
         &self,
         create_link: CreateLink,
         user: &entity::users::Model,
     ) -> AppResult<AppLink> {
-        let (data, signature, file_id) = create_link.into_active_model(user.id)?;
+        let escrow_key = create_link.escrow_key.clone();
+        let limits_countries = create_link
+            .allowed_countries
+            .as_ref()
+            .is_some_and(|countries| !countries.is_empty());
+
+        if limits_countries && self.context.config.server.links_geoip_db.is_none() {
+            return Err(Error::as_validation(
+                "allowed_countries",
+                "geoip_not_configured",
+            ));
+        }
+
+        let (mut data, signature, file_id, items) = create_link.into_active_model(user.id)?;
 
         cryptfns::rsa::public::verify(file_id.to_string().as_str(), &signature, &user.pubkey)?;
 
-        let (_file, user_file) = self.get_file_with_owner(file_id).await?;
+        let (file, user_file) = self.get_file_with_owner(file_id).await?;
 
         if user_file.user_id != user.id {
             return Err(Error::Forbidden("cannot_share_not_owner".to_string()));
         }
 
+        if file.mime.starts_with(VIRTUAL_MIME_PREFIX) {
+            return Err(Error::BadRequest("cannot_share_virtual_file".to_string()));
+        }
+
+        if file.mime == "dir" {
+            self.verify_gallery(&file, user.id, &items).await?;
+        } else if !matches!(data.encrypted_file_key, ActiveValue::Set(Some(_))) {
+            return Err(Error::as_validation("encrypted_file_key", "required"));
+        }
+
+        if file.mime != "dir" {
+            let recovery_key = storage::recovery_key(self.context).await?;
+            storage::escrow_if_missing(
+                &self.context.db,
+                recovery_key.as_ref(),
+                file.id,
+                escrow_key.as_deref(),
+            )
+            .await?;
+        }
+
+        if let ActiveValue::Set(Some(slug)) = &data.slug {
+            self.verify_slug_free(slug, None).await?;
+        }
+
+        let policy = storage::effective_policy(&self.context.db, file_id).await?;
+        let expires_at = data.expires_at.clone().unwrap();
+        data.expires_at = entity::ActiveValue::Set(policy.link_expires_at(expires_at)?);
+        policy.check_link_password(matches!(data.password, ActiveValue::Set(Some(_))))?;
+
         let id = entity::active_value_to_uuid(data.id.clone()).ok_or(Error::as_wrong_id("link"))?;
 
         links::Entity::insert(data)
             .exec_without_returning(&self.context.db)
             .await?;
 
+        if file.mime == "dir" {
+            self.add_gallery_items(id, items).await?;
+        }
+
+        self.record(user.id, &file, Action::Shared).await?;
+
         self.get_by_id(id).await
     }
 
     /// Get a link by id and verify it is not expired.
+    /// Public links are opened a lot, so the lookup goes through a short lived cache
+    /// when the cache is enabled.
     pub(crate) async fn get(&self, id: Uuid) -> AppResult<AppLink> {
-        let app_link = self.get_by_id(id).await?;
+        if !self.context.config.server.cache {
+            return self.get_by_id(id).await;
+        }
+
+        let app_link = get_cached(self.context, id).await?;
 
         Ok(app_link)
     }
@@ -66,17 +133,30 @@ impl<'ctx> Repository<'ctx> {
             .exec(&self.context.db)
             .await?;
 
+        forget(id).await;
+
+        let (file, _user_file) = self.get_file_with_owner(link.file_id).await?;
+        self.record(user_id, &file, Action::Unshared).await?;
+
         Ok(())
     }
 
-    /// Update the expires_at field for a link.
+    /// Update the expires_at, the watermark, the password and the download limit of a link.
     /// If the expires at is set to before now, the link will be purged
     /// from the database when the cron service runs next time.
-    pub(crate) async fn update_expires_at(
+    /// The sharing policy of the folders the file is in limits how late it can be,
+    /// and whether the password can be removed.
+    ///
+    /// Expiry, watermark, password and the download limit are kept if they are not
+    /// given, empty ones remove them. The kept expiry is still checked against the policy.
+    pub(crate) async fn update(
         &self,
         id: Uuid,
         user_id: Uuid,
         expires_at: Option<i64>,
+        watermark: Option<String>,
+        password: Option<String>,
+        max_downloads: Option<i32>,
     ) -> AppResult<AppLink> {
         let link = links::Entity::find_by_id(id)
             .one(&self.context.db)
@@ -87,28 +167,148 @@ impl<'ctx> Repository<'ctx> {
             return Err(Error::Forbidden("cannot_update_not_owner".to_string()));
         }
 
+        let policy = storage::effective_policy(&self.context.db, link.file_id).await?;
+        let expires_at = match expires_at {
+            Some(expires_at) => policy.link_expires_at(Some(expires_at).filter(|e| *e > 0))?,
+            None => policy.link_expires_at(link.expires_at)?,
+        };
+
+        let watermark = match watermark {
+            Some(watermark) => Some(watermark).filter(|w| !w.trim().is_empty()),
+            None => link.watermark.clone(),
+        };
+
+        let password = match password {
+            Some(password) => Some(password)
+                .filter(|p| !p.is_empty())
+                .map(util::password::hash),
+            None => link.password.clone(),
+        };
+
+        policy.check_link_password(password.is_some())?;
+
+        let max_downloads = match max_downloads {
+            Some(max_downloads) => Some(max_downloads).filter(|m| *m > 0),
+            None => link.max_downloads,
+        };
+
         let link = links::ActiveModel {
             expires_at: entity::ActiveValue::Set(expires_at),
+            watermark: entity::ActiveValue::Set(watermark),
+            password: entity::ActiveValue::Set(password),
+            max_downloads: entity::ActiveValue::Set(max_downloads),
             ..link.into()
         };
 
-        links::Entity::update(link).exec(&self.context.db).await?;
+        let link = links::Entity::update(link).exec(&self.context.db).await?;
+
+        forget(id).await;
+
+        let (file, _user_file) = self.get_file_with_owner(link.file_id).await?;
+        self.record(user_id, &file, Action::Shared).await?;
 
         self.get_by_id(id).await
     }
 
-    /// Increment file downloads counter.
+    /// Set the slug of the link, or remove it when none is given.
+    /// The slug has to be validated already, see [crate::slug].
+    pub(crate) async fn set_slug(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        slug: Option<String>,
+    ) -> AppResult<AppLink> {
+        let link = links::Entity::find_by_id(id)
+            .one(&self.context.db)
+            .await?
+            .ok_or_else(|| Error::as_not_found("link"))?;
+
+        if link.user_id != user_id {
+            return Err(Error::Forbidden("cannot_update_not_owner".to_string()));
+        }
+
+        if let Some(slug) = slug.as_deref() {
+            self.verify_slug_free(slug, Some(id)).await?;
+        }
+
+        links::Entity::update(links::ActiveModel {
+            id: ActiveValue::Set(id),
+            slug: ActiveValue::Set(slug),
+            ..Default::default()
+        })
+        .exec(&self.context.db)
+        .await?;
+
+        forget(id).await;
+
+        self.get_by_id(id).await
+    }
+
+    /// Find the id of the link with the slug
+    pub(crate) async fn find_by_slug(&self, slug: &str) -> AppResult<Uuid> {
+        let link = links::Entity::find()
+            .filter(links::Column::Slug.eq(slug))
+            .one(&self.context.db)
+            .await?
+            .ok_or_else(|| Error::as_not_found("link"))?;
+
+        Ok(link.id)
+    }
+
+    /// Slugs are unique across all the links, the link can keep its own slug
+    async fn verify_slug_free(&self, slug: &str, except: Option<Uuid>) -> AppResult<()> {
+        let mut query = links::Entity::find().filter(links::Column::Slug.eq(slug));
+
+        if let Some(id) = except {
+            query = query.filter(links::Column::Id.ne(id));
+        }
+
+        match query.count(&self.context.db).await? {
+            0 => Ok(()),
+            _ => Err(Error::Conflict("slug_taken".to_string())),
+        }
+    }
+
+    /// Increment file downloads counter, unless the link already has
+    /// as many downloads as it is limited to.
     pub(crate) async fn increment_downloads(&self, id: Uuid) -> AppResult<()> {
-        self.context
+        let result = self
+            .context
             .db
             .execute(Statement::from_sql_and_values(
                 self.context.db.get_database_backend(),
                 r"UPDATE links
                     SET downloads = downloads + 1
-                    WHERE id = $1;",
+                    WHERE id = $1
+                    AND (max_downloads IS NULL OR downloads < max_downloads);",
+                [id.into()],
+            ))
+            .await?;
+
+        forget(id).await;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::Gone("link_downloads_exhausted".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Give back the download that didn't finish, see [crate::quota].
+    pub(crate) async fn release_download(&self, id: Uuid) -> AppResult<()> {
+        self.context
+            .db
+            .execute(Statement::from_sql_and_values(
+                self.context.db.get_database_backend(),
+                r"UPDATE links
+                    SET downloads = downloads - 1
+                    WHERE id = $1 AND downloads > 0;",
                 [id.into()],
             ))
             .await?;
+
+        forget(id).await;
+
         Ok(())
     }
 
@@ -140,6 +340,157 @@ impl<'ctx> Repository<'ctx> {
         Ok(links)
     }
 
+    /// Get the page of the images in the gallery of the folder link.
+    pub(crate) async fn gallery(
+        &self,
+        id: Uuid,
+        offset: u64,
+        limit: u64,
+    ) -> AppResult<Paginated<GalleryImage>> {
+        self.gallery_link(id).await?;
+
+        let query = link_files::Entity::find().filter(link_files::Column::LinkId.eq(id));
+        let total = query.clone().count(&self.context.db).await?;
+
+        let images = query
+            .find_also_related(files::Entity)
+            .order_by_asc(link_files::Column::Position)
+            .offset(offset)
+            .limit(limit)
+            .all(&self.context.db)
+            .await?
+            .into_iter()
+            .filter_map(|(item, file)| file.map(|file| GalleryImage::new(item, file)))
+            .collect();
+
+        Ok(Paginated::new(images, total))
+    }
+
+    /// Get the single image from the gallery of the folder link, together with the link.
+    pub(crate) async fn gallery_image(
+        &self,
+        id: Uuid,
+        file_id: Uuid,
+    ) -> AppResult<(AppLink, link_files::Model, files::Model)> {
+        let link = self.gallery_link(id).await?;
+
+        let (item, file) = link_files::Entity::find()
+            .filter(link_files::Column::LinkId.eq(id))
+            .filter(link_files::Column::FileId.eq(file_id))
+            .find_also_related(files::Entity)
+            .one(&self.context.db)
+            .await?
+            .and_then(|(item, file)| file.map(|file| (item, file)))
+            .ok_or_else(|| Error::NotFound(format!("file_not_found:{}", file_id)))?;
+
+        Ok((link, item, file))
+    }
+
+    /// Load the link that is available and make sure it is a gallery
+    async fn gallery_link(&self, id: Uuid) -> AppResult<AppLink> {
+        let link = self.get(id).await?;
+
+        link.verify_available()?;
+
+        if link.file_mime != "dir" {
+            return Err(Error::NotFound("gallery_not_found".to_string()));
+        }
+
+        Ok(link)
+    }
+
+    /// Gallery can only show the images from the folder itself that belong to the user
+    async fn verify_gallery(
+        &self,
+        folder: &files::Model,
+        user_id: Uuid,
+        items: &[GalleryItem],
+    ) -> AppResult<()> {
+        if items.is_empty() {
+            return Err(Error::as_validation("items", "required"));
+        }
+
+        let mut ids = items
+            .iter()
+            .filter_map(|item| item.file_id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+
+        if ids.len() != items.len() {
+            return Err(Error::as_validation("items", "duplicate_file"));
+        }
+
+        let images = files::Entity::find()
+            .filter(files::Column::Id.is_in(ids.clone()))
+            .filter(files::Column::FileId.eq(folder.id))
+            .filter(files::Column::Mime.starts_with("image/"))
+            .filter(files::Column::DeletedAt.is_null())
+            .join(
+                JoinType::InnerJoin,
+                files::Relation::UserFiles
+                    .def()
+                    .on_condition(move |_left, right| {
+                        Expr::col((right, user_files::Column::UserId))
+                            .eq(user_id)
+                            .and(user_files::Column::IsOwner.eq(true))
+                            .into_condition()
+                    }),
+            )
+            .count(&self.context.db)
+            .await?;
+
+        if images != ids.len() as u64 {
+            return Err(Error::as_validation("items", "not_an_image_in_folder"));
+        }
+
+        Ok(())
+    }
+
+    /// Store the images of the gallery in the order they were sent
+    async fn add_gallery_items(&self, link_id: Uuid, items: Vec<GalleryItem>) -> AppResult<()> {
+        let created_at = chrono::Utc::now().timestamp();
+
+        let models = items
+            .into_iter()
+            .enumerate()
+            .map(|(position, item)| link_files::ActiveModel {
+                id: ActiveValue::Set(Uuid::new_v4()),
+                link_id: ActiveValue::Set(link_id),
+                file_id: ActiveValue::Set(item.file_id.unwrap()),
+                position: ActiveValue::Set(position as i32),
+                encrypted_name: ActiveValue::Set(item.encrypted_name.unwrap()),
+                encrypted_thumbnail: ActiveValue::Set(item.encrypted_thumbnail),
+                encrypted_file_key: ActiveValue::Set(item.encrypted_file_key.unwrap()),
+                created_at: ActiveValue::Set(created_at),
+            })
+            .collect::<Vec<_>>();
+
+        link_files::Entity::insert_many(models)
+            .exec_without_returning(&self.context.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record the change of the file share in the activity journal of the owner
+    async fn record(&self, user_id: Uuid, file: &files::Model, action: Action) -> AppResult<()> {
+        let activity = activities::ActiveModel {
+            id: entity::ActiveValue::NotSet,
+            user_id: entity::ActiveValue::Set(user_id),
+            file_id: entity::ActiveValue::Set(file.id),
+            parent_id: entity::ActiveValue::Set(file.file_id),
+            action: entity::ActiveValue::Set(action),
+            created_at: entity::ActiveValue::Set(Utc::now().timestamp()),
+        };
+
+        activities::Entity::insert(activity)
+            .exec_without_returning(&self.context.db)
+            .await?;
+
+        Ok(())
+    }
+
     /// Load the link, file and user from the database and pack it into `AppLink`.
     async fn get_by_id(&self, id: Uuid) -> AppResult<AppLink> {
         let mut selector = links::Entity::find().select_only();
@@ -152,6 +503,8 @@ impl<'ctx> Repository<'ctx> {
             .filter(links::Column::Id.eq(id))
             .join(JoinType::InnerJoin, links::Relation::Users.def())
             .join(JoinType::InnerJoin, links::Relation::Files.def())
+            // Links of the files in the trash are gone until the files are restored
+            .filter(files::Column::DeletedAt.is_null())
             .into_model::<AppLink>()
             .one(&self.context.db)
             .await?
@@ -165,6 +518,7 @@ impl<'ctx> Repository<'ctx> {
     async fn get_file_with_owner(&self, id: Uuid) -> AppResult<(files::Model, user_files::Model)> {
         let (file, user_file) = files::Entity::find()
             .filter(files::Column::Id.eq(id))
+            .filter(files::Column::DeletedAt.is_null())
             .join(
                 JoinType::InnerJoin,
                 files::Relation::UserFiles
@@ -184,3 +538,21 @@ impl<'ctx> Repository<'ctx> {
         Ok((file, user_file.unwrap()))
     }
 }
+
+/// Cached version of the link lookup, entries live only for a short while
+/// because links are also removed when the file they point to is deleted.
+#[cached(
+    name = "REPOSITORY_GET_LINK",
+    type = "TimedSizedCache<Uuid, AppLink>",
+    create = "{ TimedSizedCache::with_size_and_lifespan(1000, 30) }",
+    convert = r#"{ id }"#,
+    result = true
+)]
+async fn get_cached(context: &Context, id: Uuid) -> AppResult<AppLink> {
+    Repository::new(context).get_by_id(id).await
+}
+
+/// Remove the link from the cache after it was changed.
+async fn forget(id: Uuid) {
+    REPOSITORY_GET_LINK.lock().await.cache_remove(&id);
+}
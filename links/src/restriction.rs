@@ -0,0 +1,219 @@
+//! # Location restrictions
+//!
+//! Owner of the link can limit the downloads to the address ranges (in the CIDR notation)
+//! and to the countries. The address is allowed when it is in any of the ranges or when
+//! it is from any of the countries, the link without either of them is open to everyone.
+//!
+//! Country of the address is looked up in the MaxMind GeoIP2 database set with the
+//! LINKS_GEOIP_DB, without it the links can't be limited to the countries. Address whose
+//! country can't be found is not from any of them, so the limited link refuses it.
+//!
+//! Every refused download is written into the audit log of the file.
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use chrono::Utc;
+use config::server::ServerConfig;
+use context::Context;
+use entity::{audit_logs, ActiveValue, EntityTrait};
+use error::{AppResult, Error};
+use maxminddb::{geoip2, Reader};
+
+use crate::{data::app_link::AppLink, limiter};
+
+/// Most of the countries or the address ranges a single link can be limited to
+pub const MAX_RESTRICTIONS: usize = 100;
+
+/// Range of the addresses in the CIDR notation, a single address is the range of one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Network {
+    address: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    /// Is the address inside of the range, the IPv4 addresses mapped into the IPv6
+    /// (the way the dual stack sockets report them) are matched as the IPv4 ones.
+    pub fn contains(&self, address: IpAddr) -> bool {
+        let address = match address {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(address),
+            address => address,
+        };
+
+        match (self.address, address) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                masked(address, self.prefix) == self.address
+            }
+            _ => false,
+        }
+    }
+}
+
+/// First address of the range the address is in
+fn masked(address: IpAddr, prefix: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(address) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+
+            IpAddr::V4(Ipv4Addr::from(u32::from(address) & mask))
+        }
+        IpAddr::V6(address) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+
+            IpAddr::V6(Ipv6Addr::from(u128::from(address) & mask))
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::BadRequest(format!("invalid_network:{}", value));
+        let (address, prefix) = match value.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value.trim(), None),
+        };
+
+        let address = address.parse::<IpAddr>().map_err(|_| invalid())?;
+        let max = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| invalid())?,
+            None => max,
+        };
+
+        if prefix > max {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            address: masked(address, prefix),
+            prefix,
+        })
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix)
+    }
+}
+
+/// Country is the two letter ISO 3166-1 code, the way GeoIP2 has it
+pub fn is_country(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Values as they are kept in the database, nothing when there are none
+pub(crate) fn join(values: &[String]) -> Option<String> {
+    match values.is_empty() {
+        true => None,
+        false => Some(values.join(",")),
+    }
+}
+
+/// Values kept in the database, split back into the list
+pub(crate) fn split(values: Option<&str>) -> Vec<String> {
+    values
+        .map(|values| {
+            values
+                .split(',')
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Database the countries are looked up in, it is opened the first time it is needed.
+/// Database that can't be opened is reported once and the countries are never found.
+fn geoip(config: &ServerConfig) -> Option<Arc<Reader<Vec<u8>>>> {
+    static GEOIP: OnceLock<Option<Arc<Reader<Vec<u8>>>>> = OnceLock::new();
+
+    GEOIP
+        .get_or_init(|| {
+            let path = config.links_geoip_db.as_ref()?;
+
+            match Reader::open_readfile(path) {
+                Ok(reader) => Some(Arc::new(reader)),
+                Err(e) => {
+                    log::error!("Failed to open the GeoIP database {}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .clone()
+}
+
+/// Two letter code of the country the address is from, if it can be found
+pub fn country(config: &ServerConfig, address: IpAddr) -> Option<String> {
+    let reader = geoip(config)?;
+    let country = reader.lookup::<geoip2::Country>(address).ok()?;
+
+    country
+        .country
+        .and_then(|country| country.iso_code)
+        .map(|code| code.to_uppercase())
+}
+
+/// Is the address allowed to download through the link
+pub fn allows(config: &ServerConfig, link: &AppLink, address: Option<IpAddr>) -> bool {
+    if link.allowed_networks.is_empty() && link.allowed_countries.is_empty() {
+        return true;
+    }
+
+    let address = match address {
+        Some(address) => address,
+        None => return false,
+    };
+
+    let in_network = link
+        .allowed_networks
+        .iter()
+        .filter_map(|network| network.parse::<Network>().ok())
+        .any(|network| network.contains(address));
+
+    if in_network {
+        return true;
+    }
+
+    if link.allowed_countries.is_empty() {
+        return false;
+    }
+
+    match country(config, address) {
+        Some(country) => link.allowed_countries.contains(&country),
+        None => false,
+    }
+}
+
+/// Refuse the download from the address the link is not limited to,
+/// the refused download is written into the audit log of the file.
+pub(crate) async fn verify(context: &Context, link: &AppLink, ip: Option<&str>) -> AppResult<()> {
+    let address = ip.and_then(|ip| limiter::address(ip).parse::<IpAddr>().ok());
+
+    if allows(&context.config.server, link, address) {
+        return Ok(());
+    }
+
+    let log = audit_logs::ActiveModel {
+        id: ActiveValue::NotSet,
+        actor_id: ActiveValue::Set(link.id),
+        file_id: ActiveValue::Set(link.file_id),
+        action: ActiveValue::Set(audit_logs::Action::LinkDenied),
+        created_at: ActiveValue::Set(Utc::now().timestamp()),
+    };
+
+    audit_logs::Entity::insert(log)
+        .exec_without_returning(&context.db)
+        .await?;
+
+    Err(Error::Forbidden("link_location_not_allowed".to_string()))
+}
@@ -1,25 +1,94 @@
+use std::{sync::Arc, task::Poll};
+
 use actix_web::{
     route,
     web::{self, Bytes},
-    HttpRequest, HttpResponse,
+    HttpRequest, HttpResponse, HttpResponseBuilder,
 };
+use config::server::ServerConfig;
 use context::Context;
+use cryptfns::scheme::Scheme;
 use entity::Uuid;
 use error::{AppResult, Error};
-use fs::prelude::*;
+use fs::{prelude::*, watchdog::Watchdog};
+use futures::StreamExt;
 
-use crate::{data::download::Download, repository::Repository};
+use crate::{
+    data::download::Download,
+    limiter::{self, Connection},
+    quota::{self, Claim},
+    repository::Repository,
+    restriction,
+    watermark::{self, Mark, Watermark},
+};
 
-/// Map futures download stream so it can decrypt the file while it is being downloaded.
-fn map_chunk(chunk: Result<web::Bytes, Error>, file_key: Vec<u8>) -> Result<Bytes, Error> {
+/// Map futures download stream so it can decrypt the file while it is being downloaded,
+/// the chunks are decrypted with the scheme the file was encrypted with.
+pub(crate) fn map_chunk(
+    chunk: Result<web::Bytes, Error>,
+    scheme: Scheme,
+    file_key: Vec<u8>,
+) -> Result<Bytes, Error> {
     match chunk {
-        Ok(chunk) => cryptfns::aes::decrypt(file_key, chunk.to_vec())
+        Ok(chunk) => scheme
+            .decrypt(file_key, chunk.to_vec())
             .map_err(|_| Error::Unauthorized("invalid_file_key".to_string()))
             .map(Bytes::from),
         Err(err) => Err(err),
     }
 }
 
+/// Send the decrypted file, when the link has a watermark for the file the whole file
+/// is collected in memory and sent with the mark on it, otherwise it is streamed.
+///
+/// The connection is held until the file is sent, the stream keeps it until it is dropped.
+/// Clients that read the streamed file too slowly are dropped by the [Watchdog].
+/// The download is completed once the stream ends, see [crate::quota].
+pub(crate) async fn respond(
+    mut response: HttpResponseBuilder,
+    streamer: Streamer,
+    size: Option<i64>,
+    watermark: Option<(Arc<dyn Watermark>, Mark)>,
+    connection: Connection,
+    claim: Claim,
+    server: &ServerConfig,
+) -> AppResult<HttpResponse> {
+    let (transform, mark) = match watermark {
+        Some(watermark) => watermark,
+        None => {
+            let mut claim = Some(claim);
+            let stream = Watchdog::new(streamer.stream(), server)
+                .map(move |chunk| {
+                    let _connection = &connection;
+                    chunk
+                })
+                .chain(futures::stream::poll_fn(move |_| {
+                    if let Some(claim) = claim.take() {
+                        claim.complete();
+                    }
+
+                    Poll::Ready(None)
+                }));
+
+            return Ok(response
+                .insert_header(("Content-Length", size.unwrap_or(0)))
+                .streaming(stream));
+        }
+    };
+
+    let mut content = Vec::with_capacity(size.unwrap_or(0) as usize);
+    let mut stream = Box::pin(streamer.stream());
+
+    while let Some(chunk) = stream.next().await {
+        content.extend_from_slice(&chunk?);
+    }
+
+    let content = transform.apply(content, &mark)?;
+    claim.complete();
+
+    Ok(response.body(content))
+}
+
 /// Download file from a shareable link.
 ///
 /// This route is not authenticated, and anyone
@@ -28,6 +97,23 @@ fn map_chunk(chunk: Result<web::Bytes, Error>, file_key: Vec<u8>) -> Result<Byte
 /// But before downloading starts the signature is verified so it
 /// matches the user that supposedly created the link for that file.
 ///
+/// If the link has a watermark, it is put on the files it can be put on.
+/// Link that is scheduled to start later can't be downloaded before it starts.
+/// Link protected with the password needs it sent together with the link key.
+/// Link limited to the number of downloads answers with `410 Gone` once they are used up,
+/// see [crate::quota].
+/// Link limited to the address ranges or the countries refuses the other addresses
+/// with `403 Forbidden`, see [crate::restriction].
+///
+/// Downloads running at once are limited for each address and each link, the download
+/// over the limits waits for a free connection and gets `429 Too Many Requests` if none
+/// is freed in time.
+///
+/// File that any of the scanners gave the blocking verdict can't be downloaded.
+///
+/// When the file is archived the restore is queued and the owner of the link
+/// is notified once it is done, until then the download answers with `202 Accepted`.
+///
 /// Request: [crate::data::download::Download]
 ///
 /// Response: [actix_web::web::Bytes]
@@ -40,32 +126,61 @@ pub(crate) async fn download(
     let context = context.into_inner();
     let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
     let repository = Repository::new(&context);
-    let link_key = data.into_inner().into_value()?;
+    let (link_key, password) = data.into_inner().into_value()?;
 
     let link = repository.get(link_id).await?;
+    let ip = util::actix::client_ip(&req, &context.config.server.trusted_proxies);
 
-    if link.is_expired() {
-        return Err(Error::Unauthorized("link_expired".to_string()));
-    }
+    link.verify_available()?;
+    link.verify_password(password.as_deref())?;
+    restriction::verify(&context, &link, ip.as_deref()).await?;
 
     let filename = link.decrypt_name(&link_key)?;
     let file_key = link.file_key(&link_key)?;
+    let scheme = Scheme::from_version(link.file_crypto_version)?;
+
+    storage::guard_download(&context.db, link.id, link.file_id).await?;
 
-    repository.increment_downloads(link.id).await?;
+    if let Some(restoring) =
+        storage::archive::restoring(&context, link.file_id, link.owner_id).await?
+    {
+        return Ok(restoring);
+    }
+
+    let connection = limiter::acquire(&context.config.server, link.id, ip.as_deref()).await?;
+    let claim = quota::claim(context.clone(), link.id).await?;
+
+    let watermark = watermark::prepare(
+        link.watermark.as_deref(),
+        link.id,
+        &link.file_mime,
+        link.file_size,
+        ip.as_deref(),
+    );
 
     let streamer = Fs::new(&context.config)
-        .stream(&link, None)
+        .stream_chunks(&link)
         .await?
-        .map(move |chunk| map_chunk(chunk, file_key.clone()));
+        .map(move |chunk| map_chunk(chunk, scheme, file_key.clone()));
 
-    Ok(HttpResponse::Ok()
+    let mut response = HttpResponse::Ok();
+    response
         .insert_header(("Content-Type", link.file_mime))
-        .insert_header(("Content-Length", link.file_size.unwrap_or(0)))
         .insert_header((
             "Content-Disposition",
             format!("attachment; filename=\"{}\"", filename),
-        ))
-        .streaming(streamer.stream()))
+        ));
+
+    respond(
+        response,
+        streamer,
+        link.file_size,
+        watermark,
+        connection,
+        claim,
+        &context.config.server,
+    )
+    .await
 }
 
 /// Get HEAD information about the file, this route does everything as the one
@@ -83,13 +198,14 @@ pub(crate) async fn head(
     let context = context.into_inner();
     let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
     let repository = Repository::new(&context);
-    let link_key = data.into_inner().into_value()?;
+    let (link_key, password) = data.into_inner().into_value()?;
 
     let link = repository.get(link_id).await?;
+    let ip = util::actix::client_ip(&req, &context.config.server.trusted_proxies);
 
-    if link.is_expired() {
-        return Err(Error::Unauthorized("link_expired".to_string()));
-    }
+    link.verify_available()?;
+    link.verify_password(password.as_deref())?;
+    restriction::verify(&context, &link, ip.as_deref()).await?;
 
     let filename = link.decrypt_name(&link_key)?;
 
@@ -0,0 +1,110 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use context::Context;
+use cryptfns::scheme::Scheme;
+use entity::Uuid;
+use error::AppResult;
+use fs::prelude::*;
+
+use crate::{
+    data::{
+        download::Download,
+        gallery::{decrypt_name, file_key, GalleryPage},
+    },
+    limiter, quota,
+    repository::Repository,
+    restriction, watermark,
+};
+
+use super::download::{map_chunk, respond};
+
+/// List the page of the images in the gallery of the folder link, the names
+/// and the thumbnails are decrypted on the client with the link key.
+///
+/// This route is not authenticated, anyone with the link can see the gallery.
+///
+/// Request: [crate::data::gallery::GalleryPage]
+///
+/// Response: [entity::paginated::Paginated<crate::data::gallery::GalleryImage>]
+#[route("/api/links/{link_id}/gallery", method = "GET")]
+pub(crate) async fn index(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    data: web::Query<GalleryPage>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
+    let (offset, limit) = data.into_inner().into_value()?;
+
+    let gallery = Repository::new(&context)
+        .gallery(link_id, offset, limit)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(gallery))
+}
+
+/// Download the single image from the gallery of the folder link,
+/// it is decrypted while it is being downloaded, same as the file links,
+/// and watermarked if the link has a watermark. It counts against the same
+/// connection limits, the location restrictions, the password and the download limit
+/// as the download of the link.
+///
+/// Request: [crate::data::download::Download]
+///
+/// Response: [actix_web::web::Bytes]
+#[route("/api/links/{link_id}/gallery/{file_id}", method = "POST")]
+pub(crate) async fn download(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    data: web::Either<web::Json<Download>, web::Form<Download>>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let repository = Repository::new(&context);
+    let (link_key, password) = data.into_inner().into_value()?;
+
+    let (link, item, file) = repository.gallery_image(link_id, file_id).await?;
+    let ip = util::actix::client_ip(&req, &context.config.server.trusted_proxies);
+
+    link.verify_password(password.as_deref())?;
+    restriction::verify(&context, &link, ip.as_deref()).await?;
+
+    let filename = decrypt_name(&item, &link_key)?;
+    let file_key = file_key(&item, &link_key)?;
+    let scheme = Scheme::from_version(file.crypto_version)?;
+
+    let connection = limiter::acquire(&context.config.server, link.id, ip.as_deref()).await?;
+    let claim = quota::claim(context.clone(), link.id).await?;
+
+    let watermark = watermark::prepare(
+        link.watermark.as_deref(),
+        link.id,
+        &file.mime,
+        file.size,
+        ip.as_deref(),
+    );
+
+    let streamer = Fs::new(&context.config)
+        .stream_chunks(&file)
+        .await?
+        .map(move |chunk| map_chunk(chunk, scheme, file_key.clone()));
+
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header(("Content-Type", file.mime.clone()))
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ));
+
+    respond(
+        response,
+        streamer,
+        file.size,
+        watermark,
+        connection,
+        claim,
+        &context.config.server,
+    )
+    .await
+}
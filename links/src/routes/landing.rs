@@ -0,0 +1,123 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::landing::{Landing, LandingPage},
+    limiter, quota,
+    repository::Repository,
+    restriction,
+};
+
+/// Everything the landing page of the link needs at once, the link itself, the key of
+/// the file encrypted with the link key and the chunks of the file, so the page can
+/// decrypt the file in the browser and the link key never reaches the server.
+///
+/// This route is not authenticated, it is checked the same way as the download,
+/// the link has to be available, the password right and the location allowed.
+///
+/// Request: [crate::data::landing::Landing]
+///
+/// Response: [crate::data::landing::LandingPage]
+#[route("/api/links/{link_id}/landing", method = "POST")]
+pub(crate) async fn landing(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    data: web::Json<Landing>,
+) -> AppResult<HttpResponse> {
+    let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
+    let password = data.into_inner().into_value()?;
+
+    let link = Repository::new(&context).get(link_id).await?;
+    let ip = util::actix::client_ip(&req, &context.config.server.trusted_proxies);
+
+    link.verify_available()?;
+    link.verify_password(password.as_deref())?;
+    restriction::verify(&context, &link, ip.as_deref()).await?;
+
+    let stored = Fs::new(&context.config).get_uploaded_chunks(&link).await?;
+
+    Ok(HttpResponse::Ok().json(LandingPage::new(link, stored)))
+}
+
+/// Single chunk of the file as it is stored, encrypted, for the landing page that
+/// decrypts the file in the browser. The download is counted with the first chunk,
+/// its response carries the token the rest of the chunks are requested with, so they
+/// are served even when the first chunk used up the last download, see [crate::quota].
+///
+/// Links with the watermark can't be downloaded this way, the watermark is put
+/// on the file on the server, they are only downloaded through the link.
+///
+/// Request: [crate::data::landing::Landing]
+///
+/// Response: [actix_web::web::Bytes]
+#[route("/api/links/{link_id}/chunks/{chunk}", method = "POST")]
+pub(crate) async fn chunk(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    data: web::Json<Landing>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
+    let chunk: i64 = util::actix::path_var(&req, "chunk")?;
+    let password = data.into_inner().into_value()?;
+
+    let link = Repository::new(&context).get(link_id).await?;
+    let ip = util::actix::client_ip(&req, &context.config.server.trusted_proxies);
+
+    let token = req
+        .headers()
+        .get(quota::TOKEN_HEADER)
+        .and_then(|token| token.to_str().ok());
+    let counted = quota::in_session(token, link.id);
+
+    match counted {
+        true => link.verify_open()?,
+        false => link.verify_available()?,
+    };
+    link.verify_password(password.as_deref())?;
+    restriction::verify(&context, &link, ip.as_deref()).await?;
+
+    if link.watermark.is_some() {
+        return Err(Error::Forbidden("link_has_watermark".to_string()));
+    }
+
+    if chunk < 0 || chunk >= link.file_chunks.unwrap_or_default() {
+        return Err(Error::NotFound("chunk_not_found".to_string()));
+    }
+
+    if chunk > 0 && !counted {
+        return Err(Error::Unauthorized("download_token_required".to_string()));
+    }
+
+    storage::guard_download(&context.db, link.id, link.file_id).await?;
+
+    if let Some(restoring) =
+        storage::archive::restoring(&context, link.file_id, link.owner_id).await?
+    {
+        return Ok(restoring);
+    }
+
+    let _connection = limiter::acquire(&context.config.server, link.id, ip.as_deref()).await?;
+    let claim = match counted {
+        true => None,
+        false => Some(quota::claim(context.clone(), link.id).await?),
+    };
+
+    let content = Fs::new(&context.config).pull(&link, chunk).await?;
+
+    let token = match claim {
+        Some(claim) => {
+            claim.complete();
+            quota::start_session(link.id)
+        }
+        None => token.unwrap_or_default().to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .insert_header((quota::TOKEN_HEADER, token))
+        .body(content))
+}
@@ -1,7 +1,7 @@
 use actix_web::{route, web, HttpRequest, HttpResponse};
 use context::Context;
 use entity::Uuid;
-use error::AppResult;
+use error::{AppResult, Error};
 
 use crate::repository::Repository;
 
@@ -14,10 +14,32 @@ pub(crate) async fn metadata(
     req: HttpRequest,
     context: web::Data<Context>,
 ) -> AppResult<HttpResponse> {
-    let context = context.into_inner();
-    let link_id: Uuid = util::actix::path_var(&req, "link_id")?;
-    let repository = Repository::new(&context);
+    respond(&req, &context).await
+}
+
+/// Get application link by the id from its public URL, the same as the metadata.
+/// The file itself is downloaded with the `POST` to the same URL, the link key
+/// is sent in the body so it never ends up in the logs together with the URL.
+///
+/// Response: [crate::data::app_link::AppLink]
+#[route("/api/links/{link_id}", method = "GET")]
+pub(crate) async fn show(req: HttpRequest, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    respond(&req, &context).await
+}
+
+async fn respond(req: &HttpRequest, context: &Context) -> AppResult<HttpResponse> {
+    let link_id: Uuid = util::actix::path_var(req, "link_id")?;
+    let repository = Repository::new(context);
     let link = repository.get(link_id).await?;
 
+    // Metadata of the scheduled link is shown before it starts, but not after it expires
+    if link.is_expired() {
+        return Err(Error::Unauthorized("link_expired".to_string()));
+    }
+
+    if link.is_exhausted() {
+        return Err(Error::Gone("link_downloads_exhausted".to_string()));
+    }
+
     Ok(HttpResponse::Ok().json(link))
 }
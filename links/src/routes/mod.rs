@@ -1,8 +1,11 @@
 pub mod create;
 pub mod delete;
 pub mod download;
+pub mod gallery;
 pub mod index;
+pub mod landing;
 pub mod metadata;
+pub mod slug;
 pub mod update;
 
 /// Register the links routes
@@ -11,8 +14,16 @@ pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
     cfg.service(create::create);
     cfg.service(delete::delete);
     cfg.service(download::download);
+    cfg.service(gallery::index);
+    cfg.service(gallery::download);
     cfg.service(metadata::metadata);
+    cfg.service(metadata::show);
     cfg.service(download::head);
     cfg.service(index::index);
+    cfg.service(landing::landing);
+    cfg.service(landing::chunk);
+    cfg.service(slug::set);
+    cfg.service(slug::remove);
+    cfg.service(slug::redirect);
     cfg.service(update::update);
 }
@@ -0,0 +1,74 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::authenticated::Authenticated;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+
+use crate::{data::slug::SetSlug, repository::Repository, slug};
+
+/// Give the link a short slug it can be opened by, see [crate::slug].
+/// The slug the link had before is freed for the other links.
+///
+/// Request: [crate::data::slug::SetSlug]
+///
+/// Response: [crate::data::app_link::AppLink]
+#[route("/api/links/{link_id}/slug", method = "PUT")]
+pub(crate) async fn set(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    authenticated: Authenticated,
+    data: web::Json<SetSlug>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let id: Uuid = util::actix::path_var(&req, "link_id")?;
+    let slug = data.into_inner().into_value()?;
+
+    let link = Repository::new(&context)
+        .set_slug(id, authenticated.user.id, Some(slug))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(link))
+}
+
+/// Remove the slug from the link, it can be opened only by its id again
+///
+/// Response: [crate::data::app_link::AppLink]
+#[route("/api/links/{link_id}/slug", method = "DELETE")]
+pub(crate) async fn remove(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    authenticated: Authenticated,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let id: Uuid = util::actix::path_var(&req, "link_id")?;
+
+    let link = Repository::new(&context)
+        .set_slug(id, authenticated.user.id, None)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(link))
+}
+
+/// Redirect from the slug to the page of the link. The link key is in the fragment
+/// of the URL, it never reaches the server and the browsers keep it over the redirect.
+///
+/// Response: `302 Found` with the `Location` of the link page
+#[route("/s/{slug}", method = "GET")]
+pub(crate) async fn redirect(
+    req: HttpRequest,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let slug: String = util::actix::path_var(&req, "slug")?;
+    let slug = slug.to_lowercase();
+
+    if slug::verify(&slug).is_err() {
+        return Err(Error::as_not_found("link"));
+    }
+
+    let id = Repository::new(&context).find_by_slug(&slug).await?;
+
+    Ok(HttpResponse::Found()
+        .insert_header(("Location", format!("/l/{}", id)))
+        .insert_header(("Cache-Control", "no-store"))
+        .finish())
+}
@@ -6,7 +6,10 @@ use error::AppResult;
 
 use crate::{data::update::Update, repository::Repository};
 
-/// Update app link properties
+/// Update app link properties, the expiry, the watermark, the password
+/// and the number of times the file can be downloaded
+///
+/// Request: [crate::data::update::Update]
 ///
 /// Response: [crate::data::app_link::AppLink]
 #[route("/api/links/{link_id}", method = "PUT")]
@@ -18,12 +21,19 @@ pub(crate) async fn update(
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
     let repository = Repository::new(&context);
-    let expires_at = data.into_inner().into_value()?;
+    let (expires_at, watermark, password, max_downloads) = data.into_inner().into_value()?;
 
     let id: Uuid = util::actix::path_var(&req, "link_id")?;
 
     let response = repository
-        .update_expires_at(id, authenticated.user.id, expires_at)
+        .update(
+            id,
+            authenticated.user.id,
+            expires_at,
+            watermark,
+            password,
+            max_downloads,
+        )
         .await?;
 
     Ok(HttpResponse::Ok().json(response))
@@ -0,0 +1,89 @@
+//! # Custom slugs
+//!
+//! Links are opened by their ids, which can't be read out over the phone. Owner of
+//! the link can give it a short slug instead, `/s/{slug}` redirects to the link and
+//! the browsers carry the link key in the fragment over the redirect. Slugs are
+//! unique across all the links, so they are first come, first served.
+//!
+//! Slugs are made of the lowercase letters, the numbers and the single dashes between
+//! them, so they can be spelled out. The names of the pages of the application are
+//! reserved and the slugs with the offensive words in them are refused.
+
+/// The shortest slug, the shorter ones would be guessed too easily
+pub const MIN_SLUG_LENGTH: usize = 4;
+
+/// The longest slug
+pub const MAX_SLUG_LENGTH: usize = 64;
+
+/// Slugs that would be mistaken for the pages of the application or the official links
+const RESERVED: [&str; 20] = [
+    "about",
+    "account",
+    "admin",
+    "api",
+    "auth",
+    "download",
+    "downloads",
+    "help",
+    "hoodik",
+    "links",
+    "login",
+    "logout",
+    "official",
+    "register",
+    "root",
+    "security",
+    "settings",
+    "share",
+    "support",
+    "system",
+];
+
+/// Words the slugs can't have in them, compared with each of the words of the slug
+const OFFENSIVE: [&str; 12] = [
+    "anal", "bitch", "cunt", "dick", "fuck", "fucker", "fucking", "nazi", "nigger", "porn", "shit",
+    "whore",
+];
+
+/// Check the slug, it is trimmed and lowercased before it is checked.
+/// The error is the validation message for the slug.
+pub fn verify(slug: &str) -> Result<(), String> {
+    let length = slug.chars().count();
+
+    if length < MIN_SLUG_LENGTH {
+        return Err(format!("min:{}", MIN_SLUG_LENGTH));
+    }
+
+    if length > MAX_SLUG_LENGTH {
+        return Err(format!("max:{}", MAX_SLUG_LENGTH));
+    }
+
+    let words = slug.split('-').collect::<Vec<_>>();
+
+    let valid = words.iter().all(|word| {
+        !word.is_empty()
+            && word
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+
+    if !valid {
+        return Err("invalid_slug".to_string());
+    }
+
+    if RESERVED.contains(&slug) {
+        return Err("slug_reserved".to_string());
+    }
+
+    let joined = words.concat();
+
+    if words
+        .iter()
+        .chain(std::iter::once(&joined.as_str()))
+        .any(|word| OFFENSIVE.contains(word))
+    {
+        return Err("slug_offensive".to_string());
+    }
+
+    Ok(())
+}
@@ -1,8 +1,16 @@
 use context::Context;
+use entity::{ColumnTrait, EntityTrait, QueryFilter};
 
 use crate::{
-    data::{app_link::AppLink, create_link::CreateLink},
+    data::{
+        app_link::AppLink,
+        create_link::CreateLink,
+        landing::{Landing, LandingPage},
+        slug::SetSlug,
+    },
+    limiter, quota,
     repository::Repository,
+    restriction, watermark,
 };
 
 async fn create_link(
@@ -25,7 +33,16 @@ async fn create_link(
         encrypted_link_key: Some("test-link-key".to_string()),
         encrypted_thumbnail: None,
         encrypted_file_key: Some("test-file-key".to_string()),
+        items: None,
+        starts_at: None,
         expires_at: None,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
     };
 
     repository.create(create_link, &user).await.unwrap()
@@ -60,7 +77,16 @@ async fn test_create_link() {
         encrypted_link_key: Some("test-link-key".to_string()),
         encrypted_thumbnail: None,
         encrypted_file_key: Some("test-file-key".to_string()),
+        items: None,
+        starts_at: None,
         expires_at: None,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
     };
 
     let res = repository.create(create_link, &user).await;
@@ -138,7 +164,16 @@ async fn test_getting_multiple_links_from_the_all_method() {
         encrypted_link_key: Some("test-link-key".to_string()),
         encrypted_thumbnail: None,
         encrypted_file_key: Some("test-file-key".to_string()),
+        items: None,
+        starts_at: None,
         expires_at: None,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
     };
 
     let res = repository.create(create_link, &user).await;
@@ -156,3 +191,802 @@ async fn test_getting_multiple_links_from_the_all_method() {
 
     assert_eq!(link.downloads, 1);
 }
+
+#[actix_web::test]
+async fn test_cached_link_is_refreshed_after_changes() {
+    let mut context = Context::mock_sqlite().await;
+    context.config.server.cache = true;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+
+    let link = create_link(&context, &user, &private_key_string, "cached-file").await;
+    let repository = Repository::new(&context);
+
+    assert_eq!(repository.get(link.id).await.unwrap().downloads, 0);
+
+    repository.increment_downloads(link.id).await.unwrap();
+    assert_eq!(repository.get(link.id).await.unwrap().downloads, 1);
+
+    let expires_at = chrono::Utc::now().timestamp() + 3600;
+    repository
+        .update(link.id, user.id, Some(expires_at), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        repository.get(link.id).await.unwrap().expires_at,
+        Some(expires_at)
+    );
+
+    // Expiry is kept when only the watermark is updated
+    let updated = repository
+        .update(
+            link.id,
+            user.id,
+            None,
+            Some("Shared".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.watermark.as_deref(), Some("Shared"));
+    assert_eq!(
+        repository.get(link.id).await.unwrap().expires_at,
+        Some(expires_at)
+    );
+
+    // Zero removes the expiry
+    repository
+        .update(link.id, user.id, Some(0), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(repository.get(link.id).await.unwrap().expires_at, None);
+
+    repository.delete(link.id, user.id).await.unwrap();
+    assert!(repository.get(link.id).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_link_changes_are_recorded_in_the_journal() {
+    use entity::{activities, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+
+    let link = create_link(&context, &user, &private_key_string, "journal-file").await;
+    let repository = Repository::new(&context);
+
+    repository.delete(link.id, user.id).await.unwrap();
+
+    let entries = activities::Entity::find()
+        .filter(activities::Column::UserId.eq(user.id))
+        .order_by_asc(activities::Column::Id)
+        .all(&context.db)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        entries.iter().map(|e| e.action).collect::<Vec<_>>(),
+        vec![activities::Action::Shared, activities::Action::Unshared]
+    );
+    assert!(entries.iter().all(|e| e.file_id == link.file_id));
+}
+
+#[actix_web::test]
+async fn test_folder_policy_is_applied_to_new_links() {
+    use entity::{folder_policies, ActiveValue, EntityTrait};
+
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+
+    let (dir, _) = entity::mock::create_file(&context.db, &user, "dir", "dir", None).await;
+    let (file, _) =
+        entity::mock::create_file(&context.db, &user, "file", "text/plain", Some(dir.id)).await;
+
+    let policy = |links_disabled, links_require_password| folder_policies::ActiveModel {
+        file_id: ActiveValue::Set(dir.id),
+        user_id: ActiveValue::Set(user.id),
+        links_disabled: ActiveValue::Set(links_disabled),
+        link_expires_in_days: ActiveValue::Set(Some(7)),
+        links_require_password: ActiveValue::Set(links_require_password),
+        resharing_disabled: ActiveValue::Set(false),
+        created_at: ActiveValue::Set(0),
+        updated_at: ActiveValue::Set(0),
+    };
+
+    folder_policies::Entity::insert(policy(false, false))
+        .exec_without_returning(&context.db)
+        .await
+        .unwrap();
+
+    let signature =
+        cryptfns::rsa::private::sign(&file.id.to_string(), &private_key_string).unwrap();
+    let create_link = |expires_at| CreateLink {
+        file_id: Some(file.id.to_string()),
+        signature: Some(signature.clone()),
+        encrypted_name: Some("file".to_string()),
+        encrypted_link_key: Some("test-link-key".to_string()),
+        encrypted_thumbnail: None,
+        encrypted_file_key: Some("test-file-key".to_string()),
+        items: None,
+        starts_at: None,
+        expires_at,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
+    };
+
+    let repository = Repository::new(&context);
+    let latest = chrono::Utc::now().timestamp() + 7 * 24 * 60 * 60;
+
+    let link = repository.create(create_link(None), &user).await.unwrap();
+    assert!(link.expires_at.unwrap() >= latest);
+
+    let too_late = create_link(Some(latest + 3600));
+    assert!(repository.create(too_late, &user).await.is_err());
+
+    folder_policies::Entity::update(policy(false, true))
+        .exec(&context.db)
+        .await
+        .unwrap();
+
+    assert!(repository.create(create_link(None), &user).await.is_err());
+
+    let protected = CreateLink {
+        password: Some("open sesame".to_string()),
+        ..create_link(None)
+    };
+    let protected = repository.create(protected, &user).await.unwrap();
+    assert!(protected.has_password);
+
+    // Password can't be removed while the policy requires it
+    let removed = repository
+        .update(protected.id, user.id, None, None, Some(String::new()), None)
+        .await;
+    assert!(removed.is_err());
+
+    let kept = repository
+        .update(protected.id, user.id, None, None, None, None)
+        .await
+        .unwrap();
+    assert!(kept.has_password);
+
+    folder_policies::Entity::update(policy(true, false))
+        .exec(&context.db)
+        .await
+        .unwrap();
+
+    assert!(repository.create(create_link(None), &user).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_folder_link_is_a_gallery_of_its_images() {
+    use crate::data::gallery::GalleryItem;
+
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+    let (dir, _) = entity::mock::create_file(&context.db, &user, "photos", "dir", None).await;
+    let (first, _) =
+        entity::mock::create_file(&context.db, &user, "first", "image/jpeg", Some(dir.id)).await;
+    let (second, _) =
+        entity::mock::create_file(&context.db, &user, "second", "image/png", Some(dir.id)).await;
+    let (notes, _) =
+        entity::mock::create_file(&context.db, &user, "notes", "text/plain", Some(dir.id)).await;
+
+    let item = |file_id| GalleryItem {
+        file_id: Some(file_id),
+        encrypted_name: Some("name".to_string()),
+        encrypted_thumbnail: Some("thumbnail".to_string()),
+        encrypted_file_key: Some("file-key".to_string()),
+    };
+
+    let signature = cryptfns::rsa::private::sign(&dir.id.to_string(), &private_key_string).unwrap();
+    let create_link = |items| CreateLink {
+        file_id: Some(dir.id.to_string()),
+        signature: Some(signature.clone()),
+        encrypted_name: Some("photos".to_string()),
+        encrypted_link_key: Some("test-link-key".to_string()),
+        encrypted_thumbnail: None,
+        encrypted_file_key: None,
+        items,
+        starts_at: None,
+        expires_at: None,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
+    };
+
+    let repository = Repository::new(&context);
+
+    // Gallery shows only the images from the folder
+    assert!(repository.create(create_link(None), &user).await.is_err());
+    assert!(repository
+        .create(
+            create_link(Some(vec![item(first.id), item(notes.id)])),
+            &user
+        )
+        .await
+        .is_err());
+
+    let link = repository
+        .create(
+            create_link(Some(vec![item(second.id), item(first.id)])),
+            &user,
+        )
+        .await
+        .unwrap();
+
+    let page = repository.gallery(link.id, 0, 1).await.unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.data.len(), 1);
+    assert_eq!(page.data[0].file_id, second.id);
+    assert_eq!(
+        page.data[0].download_url,
+        format!("/api/links/{}/gallery/{}", link.id, second.id)
+    );
+
+    let (_, image, file) = repository.gallery_image(link.id, first.id).await.unwrap();
+    assert_eq!(image.position, 1);
+    assert_eq!(file.mime, "image/jpeg");
+    assert!(repository.gallery_image(link.id, notes.id).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_link_watermark_is_put_on_the_previews() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+
+    let link = create_link(&context, &user, &private_key_string, "watermarked").await;
+    let repository = Repository::new(&context);
+    assert!(link.watermark.is_none());
+
+    let link = repository
+        .update(
+            link.id,
+            user.id,
+            None,
+            Some("Shared with {ip}".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(link.watermark.as_deref(), Some("Shared with {ip}"));
+
+    // Watermark is kept when the link is updated without it
+    let link = repository
+        .update(link.id, user.id, None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(link.watermark.as_deref(), Some("Shared with {ip}"));
+
+    assert!(watermark::prepare(
+        link.watermark.as_deref(),
+        link.id,
+        "application/json",
+        Some(10),
+        None
+    )
+    .is_none());
+    assert!(watermark::prepare(
+        link.watermark.as_deref(),
+        link.id,
+        "image/svg+xml",
+        Some(watermark::MAX_WATERMARK_FILE_SIZE + 1),
+        None
+    )
+    .is_none());
+
+    let (transform, mark) = watermark::prepare(
+        link.watermark.as_deref(),
+        link.id,
+        "image/svg+xml",
+        Some(10),
+        Some("10.0.0.1"),
+    )
+    .unwrap();
+    assert_eq!(mark.text, "Shared with 10.0.0.1");
+
+    let svg = b"<svg xmlns=\"http://www.w3.org/2000/svg\"><rect/></svg>".to_vec();
+    let marked = String::from_utf8(transform.apply(svg, &mark).unwrap()).unwrap();
+    assert!(marked.contains(">Shared with 10.0.0.1</text></svg>"));
+
+    let link = repository
+        .update(link.id, user.id, None, Some("".to_string()), None, None)
+        .await
+        .unwrap();
+    assert!(link.watermark.is_none());
+}
+
+#[actix_web::test]
+async fn test_link_downloads_are_limited() {
+    let mut config = Context::mock_sqlite().await.config.server;
+    config.links_connections_per_ip = 2;
+    config.links_connections_per_link = 3;
+    config.links_queue_timeout = 0;
+
+    let link_id = entity::Uuid::new_v4();
+
+    let first = limiter::try_acquire(&config, link_id, Some("10.0.1.1")).unwrap();
+    let _second = limiter::try_acquire(&config, link_id, Some("10.0.1.1")).unwrap();
+
+    // Address is over its limit, the port is not a part of the address
+    assert!(limiter::try_acquire(&config, link_id, Some("10.0.1.1")).is_none());
+    assert!(limiter::acquire(&config, link_id, Some("10.0.1.1:4321"))
+        .await
+        .is_err());
+
+    // Link is over its limit for everyone
+    let _third = limiter::try_acquire(&config, link_id, Some("10.0.1.2")).unwrap();
+    assert!(limiter::try_acquire(&config, link_id, Some("10.0.1.3")).is_none());
+    assert!(limiter::try_acquire(&config, entity::Uuid::new_v4(), Some("10.0.1.3")).is_some());
+
+    // Finished download frees the connection
+    drop(first);
+    limiter::acquire(&config, link_id, Some("10.0.1.1"))
+        .await
+        .unwrap();
+}
+
+#[actix_web::test]
+async fn test_scheduled_link_is_available_once_it_starts() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+    let (file, _user_file) =
+        entity::mock::create_file(&context.db, &user, "press-kit", "application/zip", None).await;
+
+    let signature =
+        cryptfns::rsa::private::sign(&file.id.to_string(), &private_key_string).unwrap();
+
+    let repository = Repository::new(&context);
+    let starts_at = chrono::Utc::now().timestamp() + 3600;
+
+    let create_link = |starts_at, expires_at| CreateLink {
+        file_id: Some(file.id.to_string()),
+        signature: Some(signature.clone()),
+        encrypted_name: Some("press-kit".to_string()),
+        encrypted_link_key: Some("test-link-key".to_string()),
+        encrypted_thumbnail: None,
+        encrypted_file_key: Some("test-file-key".to_string()),
+        items: None,
+        starts_at,
+        expires_at,
+        watermark: None,
+        allowed_countries: None,
+        allowed_networks: None,
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
+    };
+
+    // Link can't start after it expires
+    assert!(repository
+        .create(create_link(Some(starts_at), Some(starts_at - 60)), &user)
+        .await
+        .is_err());
+
+    let link = repository
+        .create(create_link(Some(starts_at), None), &user)
+        .await
+        .unwrap();
+    assert_eq!(link.starts_at, Some(starts_at));
+
+    let link = repository.get(link.id).await.unwrap();
+    assert!(!link.is_started());
+    assert!(link.verify_available().is_err());
+
+    let link = AppLink {
+        starts_at: Some(chrono::Utc::now().timestamp() - 1),
+        ..link
+    };
+    assert!(link.is_started());
+    link.verify_available().unwrap();
+}
+
+#[actix_web::test]
+async fn test_link_is_limited_to_the_allowed_locations() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+    let (file, _user_file) =
+        entity::mock::create_file(&context.db, &user, "report", "application/pdf", None).await;
+
+    let signature =
+        cryptfns::rsa::private::sign(&file.id.to_string(), &private_key_string).unwrap();
+
+    let repository = Repository::new(&context);
+
+    let create_link = |countries: Option<Vec<&str>>, networks: Option<Vec<&str>>| CreateLink {
+        file_id: Some(file.id.to_string()),
+        signature: Some(signature.clone()),
+        encrypted_name: Some("report".to_string()),
+        encrypted_link_key: Some("test-link-key".to_string()),
+        encrypted_thumbnail: None,
+        encrypted_file_key: Some("test-file-key".to_string()),
+        items: None,
+        starts_at: None,
+        expires_at: None,
+        watermark: None,
+        allowed_countries: countries.map(|c| c.iter().map(|c| c.to_string()).collect()),
+        allowed_networks: networks.map(|n| n.iter().map(|n| n.to_string()).collect()),
+        password: None,
+        max_downloads: None,
+        escrow_key: None,
+        slug: None,
+    };
+
+    // Ranges have to be valid and the countries need the GeoIP database
+    assert!(repository
+        .create(create_link(None, Some(vec!["10.0.0.0/33"])), &user)
+        .await
+        .is_err());
+    assert!(repository
+        .create(create_link(Some(vec!["de"]), None), &user)
+        .await
+        .is_err());
+
+    let link = repository
+        .create(
+            create_link(None, Some(vec!["10.1.2.3/16", "2001:db8::/32"])),
+            &user,
+        )
+        .await
+        .unwrap();
+    assert_eq!(link.allowed_networks, vec!["10.1.0.0/16", "2001:db8::/32"]);
+
+    restriction::verify(&context, &link, Some("10.1.200.7:4321"))
+        .await
+        .unwrap();
+    restriction::verify(&context, &link, Some("::ffff:10.1.0.9"))
+        .await
+        .unwrap();
+    restriction::verify(&context, &link, Some("2001:db8:1::1"))
+        .await
+        .unwrap();
+    assert!(restriction::verify(&context, &link, Some("10.2.0.1"))
+        .await
+        .is_err());
+    assert!(restriction::verify(&context, &link, None).await.is_err());
+
+    let logs = entity::audit_logs::Entity::find()
+        .filter(entity::audit_logs::Column::FileId.eq(file.id))
+        .all(&context.db)
+        .await
+        .unwrap();
+    assert_eq!(logs.len(), 2);
+    assert!(logs.iter().all(|log| {
+        log.action == entity::audit_logs::Action::LinkDenied && log.actor_id == link.id
+    }));
+}
+
+#[actix_web::test]
+async fn test_link_password_is_required_until_it_is_removed() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+    let (file, _user_file) =
+        entity::mock::create_file(&context.db, &user, "contract", "application/pdf", None).await;
+
+    let signature =
+        cryptfns::rsa::private::sign(&file.id.to_string(), &private_key_string).unwrap();
+
+    let repository = Repository::new(&context);
+
+    let link = repository
+        .create(
+            CreateLink {
+                file_id: Some(file.id.to_string()),
+                signature: Some(signature),
+                encrypted_name: Some("contract".to_string()),
+                encrypted_link_key: Some("test-link-key".to_string()),
+                encrypted_thumbnail: None,
+                encrypted_file_key: Some("test-file-key".to_string()),
+                items: None,
+                starts_at: None,
+                expires_at: None,
+                watermark: None,
+                allowed_countries: None,
+                allowed_networks: None,
+                password: Some("open sesame".to_string()),
+                max_downloads: None,
+                escrow_key: None,
+                slug: None,
+            },
+            &user,
+        )
+        .await
+        .unwrap();
+
+    assert!(link.has_password);
+    assert!(link
+        .password
+        .as_deref()
+        .is_some_and(|hash| hash.starts_with("$argon2id$")));
+
+    // Hash of the password is never sent out with the link
+    let json = serde_json::to_value(&link).unwrap();
+    assert!(json.get("password").is_none());
+
+    let link = repository.get(link.id).await.unwrap();
+    assert!(link.verify_password(None).is_err());
+    assert!(link.verify_password(Some("open barley")).is_err());
+    link.verify_password(Some("open sesame")).unwrap();
+
+    // Password is kept when it is not sent, the empty one removes it
+    let link = repository
+        .update(link.id, user.id, None, None, None, None)
+        .await
+        .unwrap();
+    assert!(link.has_password);
+
+    let link = repository
+        .update(link.id, user.id, None, None, Some(String::new()), None)
+        .await
+        .unwrap();
+    assert!(!link.has_password);
+    link.verify_password(None).unwrap();
+}
+
+#[actix_web::test]
+async fn test_link_downloads_are_used_up() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+    let (file, _user_file) =
+        entity::mock::create_file(&context.db, &user, "handoff", "application/pdf", None).await;
+
+    let signature =
+        cryptfns::rsa::private::sign(&file.id.to_string(), &private_key_string).unwrap();
+
+    let repository = Repository::new(&context);
+
+    let link = repository
+        .create(
+            CreateLink {
+                file_id: Some(file.id.to_string()),
+                signature: Some(signature),
+                encrypted_name: Some("handoff".to_string()),
+                encrypted_link_key: Some("test-link-key".to_string()),
+                encrypted_thumbnail: None,
+                encrypted_file_key: Some("test-file-key".to_string()),
+                items: None,
+                starts_at: None,
+                expires_at: None,
+                watermark: None,
+                allowed_countries: None,
+                allowed_networks: None,
+                password: None,
+                max_downloads: Some(2),
+                escrow_key: None,
+                slug: None,
+            },
+            &user,
+        )
+        .await
+        .unwrap();
+    assert_eq!(link.max_downloads, Some(2));
+
+    let shared = std::sync::Arc::new(context.clone());
+    quota::claim(shared.clone(), link.id)
+        .await
+        .unwrap()
+        .complete();
+
+    // Download that didn't finish is given back
+    repository.increment_downloads(link.id).await.unwrap();
+    repository.release_download(link.id).await.unwrap();
+    assert_eq!(repository.get(link.id).await.unwrap().downloads, 1);
+
+    quota::claim(shared.clone(), link.id)
+        .await
+        .unwrap()
+        .complete();
+
+    let link = repository.get(link.id).await.unwrap();
+    assert_eq!(link.downloads, 2);
+    assert!(matches!(
+        link.verify_available(),
+        Err(error::Error::Gone(message)) if message == "link_downloads_exhausted"
+    ));
+    assert!(matches!(
+        quota::claim(shared.clone(), link.id).await,
+        Err(error::Error::Gone(_))
+    ));
+
+    // Raising the limit lets the link be downloaded again, zero removes it
+    let link = repository
+        .update(link.id, user.id, None, None, None, Some(3))
+        .await
+        .unwrap();
+    link.verify_available().unwrap();
+
+    let link = repository
+        .update(link.id, user.id, None, None, None, Some(0))
+        .await
+        .unwrap();
+    assert!(link.max_downloads.is_none());
+}
+
+#[actix_web::test]
+async fn test_landing_page_has_everything_to_decrypt_the_file() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+
+    let link = create_link(&context, &user, &private_key_string, "landing").await;
+    assert_eq!(link.file_chunks, Some(1));
+
+    let page = LandingPage::new(link.clone(), vec![0]);
+    assert_eq!(page.encrypted_file_key.as_deref(), Some("test-file-key"));
+    assert_eq!(page.chunk_map.chunks, 1);
+    assert_eq!(page.chunk_map.chunk_size, fs::MAX_CHUNK_SIZE_BYTES);
+    assert_eq!(page.chunk_map.stored, vec![0]);
+
+    let json = serde_json::to_value(&page).unwrap();
+    assert_eq!(json["id"], serde_json::json!(link.id));
+    assert_eq!(
+        json["encrypted_file_key"],
+        serde_json::json!("test-file-key")
+    );
+    assert!(json.get("password").is_none());
+
+    // Watermark is put on the server, the key is not handed out for it
+    let link = Repository::new(&context)
+        .update(
+            link.id,
+            user.id,
+            None,
+            Some("Shared with {ip}".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(LandingPage::new(link, vec![0]).encrypted_file_key.is_none());
+
+    let empty = Landing {
+        password: Some(String::new()),
+    };
+    assert!(empty.into_value().unwrap().is_none());
+
+    let long = Landing {
+        password: Some("a".repeat(73)),
+    };
+    assert!(long.into_value().is_err());
+}
+
+#[actix_web::test]
+async fn test_link_is_opened_by_its_custom_slug() {
+    let context = Context::mock_sqlite().await;
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let private_key_string = cryptfns::rsa::private::to_string(&private_key).unwrap();
+    let public_key_string = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "john@test.com", Some(public_key_string)).await;
+    let other = entity::mock::create_user(&context.db, "jane@test.com", None).await;
+
+    let first = create_link(&context, &user, &private_key_string, "first").await;
+    let second = create_link(&context, &user, &private_key_string, "second").await;
+    assert!(first.slug.is_none());
+
+    let repository = Repository::new(&context);
+
+    let set = |slug: &str| SetSlug {
+        slug: Some(slug.to_string()),
+    };
+    let slug = set("  Summer-Photos ").into_value().unwrap();
+    assert_eq!(slug, "summer-photos");
+
+    let link = repository
+        .set_slug(first.id, user.id, Some(slug.clone()))
+        .await
+        .unwrap();
+    assert_eq!(link.slug.as_deref(), Some("summer-photos"));
+    assert_eq!(repository.find_by_slug(&slug).await.unwrap(), first.id);
+
+    // Link can keep its own slug, but the other links can't take it
+    repository
+        .set_slug(first.id, user.id, Some(slug.clone()))
+        .await
+        .unwrap();
+    assert_eq!(
+        repository
+            .set_slug(second.id, user.id, Some(slug.clone()))
+            .await
+            .unwrap_err(),
+        error::Error::Conflict("slug_taken".to_string())
+    );
+    assert!(repository.set_slug(first.id, other.id, None).await.is_err());
+
+    // Removed slug is free for the other links
+    repository.set_slug(first.id, user.id, None).await.unwrap();
+    assert!(repository.find_by_slug(&slug).await.is_err());
+    repository
+        .set_slug(second.id, user.id, Some(slug.clone()))
+        .await
+        .unwrap();
+    assert_eq!(repository.find_by_slug(&slug).await.unwrap(), second.id);
+
+    for invalid in [
+        "abc",
+        "summer photos",
+        "-summer",
+        "summer--photos",
+        "sommer-fotos-ä",
+        "admin",
+        "holy-shit",
+        "sh-it",
+    ] {
+        assert!(set(invalid).into_value().is_err(), "{} is valid", invalid);
+    }
+
+    for valid in ["summer-2024", "scunthorpe", "analysis", "a1b2"] {
+        assert!(set(valid).into_value().is_ok(), "{} is invalid", valid);
+    }
+}
@@ -0,0 +1,141 @@
+//! # Watermarks
+//!
+//! Previews served through the public links can carry a watermark, so a leaked copy
+//! points back to the link and the address it was served to. The transformations are
+//! pluggable, each one handles some mime types and the last registered one that handles
+//! the mime type of the file is used. Only SVG images are watermarked out of the box,
+//! raster images and PDF documents need a transformation registered with [register].
+//!
+//! Watermarking needs the whole decrypted file in memory, so the files larger than
+//! [MAX_WATERMARK_FILE_SIZE] are served as they are.
+use std::sync::{Arc, OnceLock, RwLock};
+
+use chrono::Utc;
+use entity::Uuid;
+use error::{AppResult, Error};
+
+/// The longest watermark text of a link
+pub const MAX_WATERMARK_LENGTH: usize = 200;
+
+/// The largest file that is watermarked, bigger files are served as they are
+pub const MAX_WATERMARK_FILE_SIZE: i64 = 50 * 1024 * 1024;
+
+/// Mark put on the preview, the text of the link watermark
+/// with the details of the request it was served to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mark {
+    pub link_id: Uuid,
+    /// Address of the client the preview is served to
+    pub ip: Option<String>,
+    /// Watermark text with the placeholders replaced
+    pub text: String,
+}
+
+impl Mark {
+    /// Create the mark from the watermark text of the link, `{ip}`, `{date}`
+    /// and `{link_id}` placeholders are replaced with the request details.
+    pub fn new(template: &str, link_id: Uuid, ip: Option<&str>) -> Self {
+        let date = util::datetime::from_timestamp(Utc::now().timestamp())
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+
+        let text = template
+            .replace("{ip}", ip.unwrap_or("unknown"))
+            .replace("{date}", &date)
+            .replace("{link_id}", &link_id.to_string());
+
+        Self {
+            link_id,
+            ip: ip.map(|ip| ip.to_string()),
+            text,
+        }
+    }
+}
+
+/// Transformation that puts the mark on the decrypted content of the file
+pub trait Watermark: Send + Sync {
+    /// The transformation can watermark the files of the given mime type
+    fn supports(&self, mime: &str) -> bool;
+
+    /// Put the mark on the content of the file
+    fn apply(&self, content: Vec<u8>, mark: &Mark) -> AppResult<Vec<u8>>;
+}
+
+fn registry() -> &'static RwLock<Vec<Arc<dyn Watermark>>> {
+    static REGISTRY: OnceLock<RwLock<Vec<Arc<dyn Watermark>>>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| RwLock::new(vec![Arc::new(Svg)]))
+}
+
+/// Register the transformation, it takes precedence over the ones
+/// registered before it for the mime types it supports.
+pub fn register<W: Watermark + 'static>(watermark: W) {
+    if let Ok(mut registry) = registry().write() {
+        registry.push(Arc::new(watermark));
+    }
+}
+
+/// Find the transformation for the mime type
+pub fn find(mime: &str) -> Option<Arc<dyn Watermark>> {
+    registry()
+        .read()
+        .ok()?
+        .iter()
+        .rev()
+        .find(|watermark| watermark.supports(mime))
+        .cloned()
+}
+
+/// Get the transformation and the mark for the preview, nothing if the preview
+/// is served as it is: the link has no watermark, no transformation handles
+/// the mime type or the file is too big to be watermarked in memory.
+pub fn prepare(
+    watermark: Option<&str>,
+    link_id: Uuid,
+    mime: &str,
+    size: Option<i64>,
+    ip: Option<&str>,
+) -> Option<(Arc<dyn Watermark>, Mark)> {
+    let template = watermark?;
+
+    if size.unwrap_or(0) > MAX_WATERMARK_FILE_SIZE {
+        return None;
+    }
+
+    Some((find(mime)?, Mark::new(template, link_id, ip)))
+}
+
+/// Overlay the mark in the middle of the SVG image
+pub struct Svg;
+
+impl Watermark for Svg {
+    fn supports(&self, mime: &str) -> bool {
+        mime == "image/svg+xml"
+    }
+
+    fn apply(&self, content: Vec<u8>, mark: &Mark) -> AppResult<Vec<u8>> {
+        let mut svg = String::from_utf8(content)?;
+
+        let end = svg
+            .rfind("</svg>")
+            .ok_or_else(|| Error::BadRequest("invalid_svg".to_string()))?;
+
+        let overlay = format!(
+            r##"<text x="50%" y="50%" text-anchor="middle" dominant-baseline="middle" font-family="sans-serif" font-size="24" fill="#808080" fill-opacity="0.5" pointer-events="none">{}</text>"##,
+            escape(&mark.text)
+        );
+
+        svg.insert_str(end, &overlay);
+
+        Ok(svg.into_bytes())
+    }
+}
+
+/// Escape the text so it can be put inside of the XML element
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -9,6 +9,47 @@ pub(crate) mod m20230429_091730_create_tokens;
 pub(crate) mod m20230429_101730_create_file_tokens;
 pub(crate) mod m20230521_074334_create_links;
 pub(crate) mod m20230612_074334_create_invitations;
+pub(crate) mod m20230901_080000_create_jobs;
+pub(crate) mod m20230910_080000_create_activities;
+pub(crate) mod m20230915_080000_add_files_sha256;
+pub(crate) mod m20230920_080000_add_files_conflict;
+pub(crate) mod m20230925_080000_create_folder_policies;
+pub(crate) mod m20230930_080000_add_legal_hold;
+pub(crate) mod m20231005_080000_create_groups;
+pub(crate) mod m20231010_080000_create_spaces;
+pub(crate) mod m20231015_080000_create_link_files;
+pub(crate) mod m20231020_080000_create_file_requests;
+pub(crate) mod m20231025_080000_add_links_watermark;
+pub(crate) mod m20231030_080000_create_exports;
+pub(crate) mod m20231105_080000_create_messages;
+pub(crate) mod m20231110_080000_add_crypto_version;
+pub(crate) mod m20231115_080000_create_escrow_keys;
+pub(crate) mod m20231120_080000_create_rewrap_jobs;
+pub(crate) mod m20231125_080000_add_encrypted_payload;
+pub(crate) mod m20231130_080000_create_folder_templates;
+pub(crate) mod m20231205_080000_add_user_files_attributes;
+pub(crate) mod m20231210_080000_create_imports;
+pub(crate) mod m20231215_080000_create_external_exports;
+pub(crate) mod m20231220_080000_create_rebalances;
+pub(crate) mod m20231225_080000_create_file_lifecycles;
+pub(crate) mod m20231230_080000_add_starts_at;
+pub(crate) mod m20240104_080000_add_links_restrictions;
+pub(crate) mod m20240108_080000_add_file_lifecycles_demoted_at;
+pub(crate) mod m20240112_080000_create_app_passwords;
+pub(crate) mod m20240116_080000_create_tus_uploads;
+pub(crate) mod m20240120_080000_add_files_deleted_at;
+mod m20240124_080000_create_blobs;
+mod m20240128_080000_create_versions;
+mod m20240201_080000_add_links_password;
+mod m20240205_080000_add_links_max_downloads;
+mod m20240209_080000_add_user_files_permission;
+mod m20240213_080000_create_access_keys;
+mod m20240217_080000_create_transfers;
+mod m20240221_080000_add_sessions_unlocked;
+mod m20240225_080000_create_file_verdicts;
+mod m20240229_080000_add_links_slug;
+mod m20240304_080000_add_activities_sequence;
+mod m20240308_080000_add_folder_policies_rules;
 
 pub struct Migrator;
 
@@ -25,6 +66,47 @@ impl MigratorTrait for Migrator {
             Box::new(m20230429_101730_create_file_tokens::Migration),
             Box::new(m20230521_074334_create_links::Migration),
             Box::new(m20230612_074334_create_invitations::Migration),
+            Box::new(m20230901_080000_create_jobs::Migration),
+            Box::new(m20230910_080000_create_activities::Migration),
+            Box::new(m20230915_080000_add_files_sha256::Migration),
+            Box::new(m20230920_080000_add_files_conflict::Migration),
+            Box::new(m20230925_080000_create_folder_policies::Migration),
+            Box::new(m20230930_080000_add_legal_hold::Migration),
+            Box::new(m20231005_080000_create_groups::Migration),
+            Box::new(m20231010_080000_create_spaces::Migration),
+            Box::new(m20231015_080000_create_link_files::Migration),
+            Box::new(m20231020_080000_create_file_requests::Migration),
+            Box::new(m20231025_080000_add_links_watermark::Migration),
+            Box::new(m20231030_080000_create_exports::Migration),
+            Box::new(m20231105_080000_create_messages::Migration),
+            Box::new(m20231110_080000_add_crypto_version::Migration),
+            Box::new(m20231115_080000_create_escrow_keys::Migration),
+            Box::new(m20231120_080000_create_rewrap_jobs::Migration),
+            Box::new(m20231125_080000_add_encrypted_payload::Migration),
+            Box::new(m20231130_080000_create_folder_templates::Migration),
+            Box::new(m20231205_080000_add_user_files_attributes::Migration),
+            Box::new(m20231210_080000_create_imports::Migration),
+            Box::new(m20231215_080000_create_external_exports::Migration),
+            Box::new(m20231220_080000_create_rebalances::Migration),
+            Box::new(m20231225_080000_create_file_lifecycles::Migration),
+            Box::new(m20231230_080000_add_starts_at::Migration),
+            Box::new(m20240104_080000_add_links_restrictions::Migration),
+            Box::new(m20240108_080000_add_file_lifecycles_demoted_at::Migration),
+            Box::new(m20240112_080000_create_app_passwords::Migration),
+            Box::new(m20240116_080000_create_tus_uploads::Migration),
+            Box::new(m20240120_080000_add_files_deleted_at::Migration),
+            Box::new(m20240124_080000_create_blobs::Migration),
+            Box::new(m20240128_080000_create_versions::Migration),
+            Box::new(m20240201_080000_add_links_password::Migration),
+            Box::new(m20240205_080000_add_links_max_downloads::Migration),
+            Box::new(m20240209_080000_add_user_files_permission::Migration),
+            Box::new(m20240213_080000_create_access_keys::Migration),
+            Box::new(m20240217_080000_create_transfers::Migration),
+            Box::new(m20240221_080000_add_sessions_unlocked::Migration),
+            Box::new(m20240225_080000_create_file_verdicts::Migration),
+            Box::new(m20240229_080000_add_links_slug::Migration),
+            Box::new(m20240304_080000_add_activities_sequence::Migration),
+            Box::new(m20240308_080000_add_folder_policies_rules::Migration),
         ]
     }
 }
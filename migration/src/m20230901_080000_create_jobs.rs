@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jobs::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Jobs::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Jobs::Kind).string().not_null())
+                    .col(ColumnDef::new(Jobs::Payload).text().not_null())
+                    .col(
+                        ColumnDef::new(Jobs::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Jobs::MaxAttempts).integer().not_null())
+                    .col(ColumnDef::new(Jobs::LastError).text())
+                    .col(ColumnDef::new(Jobs::RunAt).big_integer().not_null())
+                    .col(ColumnDef::new(Jobs::LockedAt).big_integer())
+                    .col(ColumnDef::new(Jobs::FailedAt).big_integer())
+                    .col(ColumnDef::new(Jobs::CreatedAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Jobs::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Jobs {
+    Table,
+    Id,
+    Kind,
+    Payload,
+    Attempts,
+    MaxAttempts,
+    LastError,
+    RunAt,
+    LockedAt,
+    FailedAt,
+    CreatedAt,
+}
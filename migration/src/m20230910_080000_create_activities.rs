@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only allows auto increment on the `INTEGER` primary key,
+        // which is 64 bit there anyway, other databases need the big integer.
+        let mut id = ColumnDef::new(Activities::Id);
+        match manager.get_database_backend() {
+            sea_orm::DbBackend::Sqlite => id.integer(),
+            _ => id.big_integer(),
+        };
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Activities::Table)
+                    .if_not_exists()
+                    .col(id.not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(Activities::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Activities::FileId).uuid().not_null())
+                    .col(ColumnDef::new(Activities::ParentId).uuid())
+                    .col(ColumnDef::new(Activities::Action).string().not_null())
+                    .col(
+                        ColumnDef::new(Activities::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("activities_user_id_parent_id_id")
+                    .table(Activities::Table)
+                    .col(Activities::UserId)
+                    .col(Activities::ParentId)
+                    .col(Activities::Id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Activities::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Activities {
+    Table,
+    Id,
+    UserId,
+    FileId,
+    ParentId,
+    Action,
+    CreatedAt,
+}
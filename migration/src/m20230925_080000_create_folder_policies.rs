@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(FolderPolicies::Table, FolderPolicies::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(FolderPolicies::Table, FolderPolicies::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FolderPolicies::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FolderPolicies::FileId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FolderPolicies::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(FolderPolicies::LinksDisabled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(FolderPolicies::LinkExpiresInDays).integer())
+                    .col(
+                        ColumnDef::new(FolderPolicies::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FolderPolicies::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FolderPolicies::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum FolderPolicies {
+    Table,
+    FileId,
+    UserId,
+    LinksDisabled,
+    LinkExpiresInDays,
+    CreatedAt,
+    UpdatedAt,
+}
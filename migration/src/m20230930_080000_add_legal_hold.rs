@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Files::Table)
+                    .add_column(
+                        ColumnDef::new(Files::LegalHold)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite only allows auto increment on the `INTEGER` primary key,
+        // which is 64 bit there anyway, other databases need the big integer.
+        let mut id = ColumnDef::new(AuditLogs::Id);
+        match manager.get_database_backend() {
+            sea_orm::DbBackend::Sqlite => id.integer(),
+            _ => id.big_integer(),
+        };
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLogs::Table)
+                    .if_not_exists()
+                    .col(id.not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(AuditLogs::ActorId).uuid().not_null())
+                    .col(ColumnDef::new(AuditLogs::FileId).uuid().not_null())
+                    .col(ColumnDef::new(AuditLogs::Action).string().not_null())
+                    .col(
+                        ColumnDef::new(AuditLogs::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("audit_logs_file_id_id")
+                    .table(AuditLogs::Table)
+                    .col(AuditLogs::FileId)
+                    .col(AuditLogs::Id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLogs::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Files::Table)
+                    .drop_column(Files::LegalHold)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Files {
+    Table,
+    LegalHold,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum AuditLogs {
+    Table,
+    Id,
+    ActorId,
+    FileId,
+    Action,
+    CreatedAt,
+}
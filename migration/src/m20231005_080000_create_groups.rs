@@ -0,0 +1,153 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Groups::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Groups::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Groups::Name).string().not_null())
+                    .col(ColumnDef::new(Groups::Quota).big_integer())
+                    .col(ColumnDef::new(Groups::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Groups::UpdatedAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("groups_name")
+                    .table(Groups::Table)
+                    .col(Groups::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_group_id = ForeignKey::create();
+        foreign_key_group_id
+            .from(GroupMembers::Table, GroupMembers::GroupId)
+            .to(Groups::Table, Groups::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(GroupMembers::Table, GroupMembers::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GroupMembers::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GroupMembers::GroupId).uuid().not_null())
+                    .col(ColumnDef::new(GroupMembers::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(GroupMembers::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(GroupMembers::GroupId)
+                            .col(GroupMembers::UserId),
+                    )
+                    .foreign_key(&mut foreign_key_group_id)
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_group_id = ForeignKey::create();
+        foreign_key_group_id
+            .from(GroupFolders::Table, GroupFolders::GroupId)
+            .to(Groups::Table, Groups::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(GroupFolders::Table, GroupFolders::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GroupFolders::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GroupFolders::GroupId).uuid().not_null())
+                    .col(ColumnDef::new(GroupFolders::FileId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(GroupFolders::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(GroupFolders::GroupId)
+                            .col(GroupFolders::FileId),
+                    )
+                    .foreign_key(&mut foreign_key_group_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GroupFolders::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(GroupMembers::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Groups::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Groups {
+    Table,
+    Id,
+    Name,
+    Quota,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum GroupMembers {
+    Table,
+    GroupId,
+    UserId,
+    CreatedAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum GroupFolders {
+    Table,
+    GroupId,
+    FileId,
+    CreatedAt,
+}
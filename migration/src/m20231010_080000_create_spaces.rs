@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20230409_091730_create_files::Files, m20231005_080000_create_groups::Groups};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupMembers::Table)
+                    .add_column(
+                        ColumnDef::new(GroupMembers::Role)
+                            .string()
+                            .not_null()
+                            .default("editor"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_group_id = ForeignKey::create();
+        foreign_key_group_id
+            .from(Spaces::Table, Spaces::GroupId)
+            .to(Groups::Table, Groups::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(Spaces::Table, Spaces::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Spaces::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Spaces::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Spaces::GroupId).uuid().not_null())
+                    .col(ColumnDef::new(Spaces::FileId).uuid().not_null())
+                    .col(ColumnDef::new(Spaces::CreatedAt).big_integer().not_null())
+                    .foreign_key(&mut foreign_key_group_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("spaces_file_id")
+                    .table(Spaces::Table)
+                    .col(Spaces::FileId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Spaces::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupMembers::Table)
+                    .drop_column(GroupMembers::Role)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum GroupMembers {
+    Table,
+    Role,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Spaces {
+    Table,
+    Id,
+    GroupId,
+    FileId,
+    CreatedAt,
+}
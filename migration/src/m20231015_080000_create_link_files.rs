@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20230409_091730_create_files::Files, m20230521_074334_create_links::Links};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_link_id = ForeignKey::create();
+        foreign_key_link_id
+            .from(LinkFiles::Table, LinkFiles::LinkId)
+            .to(Links::Table, Links::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(LinkFiles::Table, LinkFiles::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LinkFiles::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(LinkFiles::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(LinkFiles::LinkId).uuid().not_null())
+                    .col(ColumnDef::new(LinkFiles::FileId).uuid().not_null())
+                    .col(ColumnDef::new(LinkFiles::Position).integer().not_null())
+                    .col(ColumnDef::new(LinkFiles::EncryptedName).string().not_null())
+                    .col(ColumnDef::new(LinkFiles::EncryptedThumbnail).text())
+                    .col(ColumnDef::new(LinkFiles::EncryptedFileKey).text().not_null())
+                    .col(
+                        ColumnDef::new(LinkFiles::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_link_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("link_files_link_id_position")
+                    .table(LinkFiles::Table)
+                    .col(LinkFiles::LinkId)
+                    .col(LinkFiles::Position)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LinkFiles::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum LinkFiles {
+    Table,
+    Id,
+    LinkId,
+    FileId,
+    Position,
+    EncryptedName,
+    EncryptedThumbnail,
+    EncryptedFileKey,
+    CreatedAt,
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(FileRequests::Table, FileRequests::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(FileRequests::Table, FileRequests::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FileRequests::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FileRequests::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FileRequests::UserId).uuid().not_null())
+                    .col(ColumnDef::new(FileRequests::FileId).uuid().not_null())
+                    .col(ColumnDef::new(FileRequests::Deadline).big_integer())
+                    .col(ColumnDef::new(FileRequests::ClosedAt).big_integer())
+                    .col(ColumnDef::new(FileRequests::NotifiedAt).big_integer())
+                    .col(
+                        ColumnDef::new(FileRequests::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_request_id = ForeignKey::create();
+        foreign_key_request_id
+            .from(FileRequestFiles::Table, FileRequestFiles::FileRequestId)
+            .to(FileRequests::Table, FileRequests::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_received_file_id = ForeignKey::create();
+        foreign_key_received_file_id
+            .from(FileRequestFiles::Table, FileRequestFiles::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FileRequestFiles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FileRequestFiles::FileRequestId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FileRequestFiles::FileId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(FileRequestFiles::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(FileRequestFiles::FileRequestId)
+                            .col(FileRequestFiles::FileId),
+                    )
+                    .foreign_key(&mut foreign_key_request_id)
+                    .foreign_key(&mut foreign_key_received_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FileRequestFiles::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(FileRequests::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum FileRequests {
+    Table,
+    Id,
+    UserId,
+    FileId,
+    Deadline,
+    ClosedAt,
+    NotifiedAt,
+    CreatedAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum FileRequestFiles {
+    Table,
+    FileRequestId,
+    FileId,
+    CreatedAt,
+}
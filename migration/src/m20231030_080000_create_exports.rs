@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(Exports::Table, Exports::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Exports::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Exports::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Exports::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Exports::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(Exports::IncludeFiles)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Exports::Size).big_integer())
+                    .col(ColumnDef::new(Exports::Error).text())
+                    .col(ColumnDef::new(Exports::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Exports::FinishedAt).big_integer())
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Exports::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Exports {
+    Table,
+    Id,
+    UserId,
+    Status,
+    IncludeFiles,
+    Size,
+    Error,
+    CreatedAt,
+    FinishedAt,
+}
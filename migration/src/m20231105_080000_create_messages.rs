@@ -0,0 +1,123 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_sender_id = ForeignKey::create();
+        foreign_key_sender_id
+            .from(Messages::Table, Messages::SenderId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::SetNull)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(Messages::Table, Messages::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Messages::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Messages::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Messages::SenderId).uuid())
+                    .col(ColumnDef::new(Messages::UserId).uuid())
+                    .col(ColumnDef::new(Messages::Title).string().not_null())
+                    .col(ColumnDef::new(Messages::Body).text().not_null())
+                    .col(ColumnDef::new(Messages::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Messages::ExpiresAt).big_integer())
+                    .foreign_key(&mut foreign_key_sender_id)
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("messages_user_id_created_at")
+                    .table(Messages::Table)
+                    .col(Messages::UserId)
+                    .col(Messages::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_message_id = ForeignKey::create();
+        foreign_key_message_id
+            .from(MessageReads::Table, MessageReads::MessageId)
+            .to(Messages::Table, Messages::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_reader_id = ForeignKey::create();
+        foreign_key_reader_id
+            .from(MessageReads::Table, MessageReads::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageReads::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(MessageReads::MessageId).uuid().not_null())
+                    .col(ColumnDef::new(MessageReads::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(MessageReads::ReadAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(MessageReads::MessageId)
+                            .col(MessageReads::UserId),
+                    )
+                    .foreign_key(&mut foreign_key_message_id)
+                    .foreign_key(&mut foreign_key_reader_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageReads::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Messages::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Messages {
+    Table,
+    Id,
+    SenderId,
+    UserId,
+    Title,
+    Body,
+    CreatedAt,
+    ExpiresAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum MessageReads {
+    Table,
+    MessageId,
+    UserId,
+    ReadAt,
+}
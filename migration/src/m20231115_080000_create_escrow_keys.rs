@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230409_091730_create_files::Files;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(EscrowKeys::Table, EscrowKeys::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(EscrowKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EscrowKeys::FileId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(EscrowKeys::EncryptedKey).text().not_null())
+                    .col(ColumnDef::new(EscrowKeys::Fingerprint).string().not_null())
+                    .col(
+                        ColumnDef::new(EscrowKeys::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EscrowKeys::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum EscrowKeys {
+    Table,
+    FileId,
+    EncryptedKey,
+    Fingerprint,
+    CreatedAt,
+}
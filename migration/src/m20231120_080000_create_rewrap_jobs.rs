@@ -0,0 +1,131 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20220101_000001_create_users::Users, m20230409_091730_create_files::Files,
+    m20230409_101730_create_user_files::UserFiles,
+};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(RewrapJobs::Table, RewrapJobs::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(RewrapJobs::Table, RewrapJobs::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RewrapJobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RewrapJobs::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RewrapJobs::UserId).uuid().not_null())
+                    .col(ColumnDef::new(RewrapJobs::Reason).string().not_null())
+                    .col(ColumnDef::new(RewrapJobs::FileId).uuid())
+                    .col(ColumnDef::new(RewrapJobs::PreviousEncryptedPrivateKey).text())
+                    .col(
+                        ColumnDef::new(RewrapJobs::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RewrapJobs::CompletedAt).big_integer())
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("rewrap_jobs_user_id_completed_at")
+                    .table(RewrapJobs::Table)
+                    .col(RewrapJobs::UserId)
+                    .col(RewrapJobs::CompletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_job_id = ForeignKey::create();
+        foreign_key_job_id
+            .from(RewrapEntries::Table, RewrapEntries::JobId)
+            .to(RewrapJobs::Table, RewrapJobs::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_user_file_id = ForeignKey::create();
+        foreign_key_user_file_id
+            .from(RewrapEntries::Table, RewrapEntries::UserFileId)
+            .to(UserFiles::Table, UserFiles::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RewrapEntries::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RewrapEntries::JobId).uuid().not_null())
+                    .col(ColumnDef::new(RewrapEntries::UserFileId).uuid().not_null())
+                    .col(ColumnDef::new(RewrapEntries::CompletedAt).big_integer())
+                    .primary_key(
+                        Index::create()
+                            .col(RewrapEntries::JobId)
+                            .col(RewrapEntries::UserFileId),
+                    )
+                    .foreign_key(&mut foreign_key_job_id)
+                    .foreign_key(&mut foreign_key_user_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RewrapEntries::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RewrapJobs::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum RewrapJobs {
+    Table,
+    Id,
+    UserId,
+    Reason,
+    FileId,
+    PreviousEncryptedPrivateKey,
+    CreatedAt,
+    CompletedAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum RewrapEntries {
+    Table,
+    JobId,
+    UserFileId,
+    CompletedAt,
+}
@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(FolderTemplates::Table, FolderTemplates::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FolderTemplates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FolderTemplates::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FolderTemplates::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(FolderTemplates::EncryptedName)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FolderTemplates::EncryptedTree)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FolderTemplates::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FolderTemplates::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("folder_templates_user_id")
+                    .table(FolderTemplates::Table)
+                    .col(FolderTemplates::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FolderTemplates::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum FolderTemplates {
+    Table,
+    Id,
+    UserId,
+    EncryptedName,
+    EncryptedTree,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(Imports::Table, Imports::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(Imports::Table, Imports::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Imports::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Imports::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Imports::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Imports::FileId).uuid())
+                    .col(ColumnDef::new(Imports::Source).string().not_null())
+                    .col(ColumnDef::new(Imports::Location).text().not_null())
+                    .col(ColumnDef::new(Imports::Credentials).text())
+                    .col(ColumnDef::new(Imports::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(Imports::ImportedFiles)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Imports::ImportedBytes)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Imports::SkippedFiles)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Imports::FailedFiles)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Imports::Errors).json_binary())
+                    .col(ColumnDef::new(Imports::Error).text())
+                    .col(ColumnDef::new(Imports::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Imports::StartedAt).big_integer())
+                    .col(ColumnDef::new(Imports::FinishedAt).big_integer())
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Imports::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Imports {
+    Table,
+    Id,
+    UserId,
+    FileId,
+    Source,
+    Location,
+    Credentials,
+    Status,
+    ImportedFiles,
+    ImportedBytes,
+    SkippedFiles,
+    FailedFiles,
+    Errors,
+    Error,
+    CreatedAt,
+    StartedAt,
+    FinishedAt,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(ExternalExports::Table, ExternalExports::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(ExternalExports::Table, ExternalExports::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExternalExports::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ExternalExports::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ExternalExports::UserId).uuid().not_null())
+                    .col(ColumnDef::new(ExternalExports::FileId).uuid())
+                    .col(ColumnDef::new(ExternalExports::Target).string().not_null())
+                    .col(ColumnDef::new(ExternalExports::Location).text().not_null())
+                    .col(ColumnDef::new(ExternalExports::Credentials).text())
+                    .col(ColumnDef::new(ExternalExports::Mode).string().not_null())
+                    .col(ColumnDef::new(ExternalExports::Keys).text())
+                    .col(ColumnDef::new(ExternalExports::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(ExternalExports::ExportedFiles)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ExternalExports::ExportedBytes)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ExternalExports::FailedFiles)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(ExternalExports::Errors).json_binary())
+                    .col(ColumnDef::new(ExternalExports::Error).text())
+                    .col(
+                        ColumnDef::new(ExternalExports::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ExternalExports::StartedAt).big_integer())
+                    .col(ColumnDef::new(ExternalExports::FinishedAt).big_integer())
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ExternalExports::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum ExternalExports {
+    Table,
+    Id,
+    UserId,
+    FileId,
+    Target,
+    Location,
+    Credentials,
+    Mode,
+    Keys,
+    Status,
+    ExportedFiles,
+    ExportedBytes,
+    FailedFiles,
+    Errors,
+    Error,
+    CreatedAt,
+    StartedAt,
+    FinishedAt,
+}
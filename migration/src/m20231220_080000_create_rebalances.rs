@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Rebalances::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Rebalances::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Rebalances::Layout).json_binary())
+                    .col(ColumnDef::new(Rebalances::BytesPerSecond).big_integer())
+                    .col(ColumnDef::new(Rebalances::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(Rebalances::MovedChunks)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Rebalances::MovedBytes)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Rebalances::Error).text())
+                    .col(
+                        ColumnDef::new(Rebalances::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Rebalances::StartedAt).big_integer())
+                    .col(ColumnDef::new(Rebalances::FinishedAt).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Rebalances::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Rebalances {
+    Table,
+    Id,
+    Layout,
+    BytesPerSecond,
+    Status,
+    MovedChunks,
+    MovedBytes,
+    Error,
+    CreatedAt,
+    StartedAt,
+    FinishedAt,
+}
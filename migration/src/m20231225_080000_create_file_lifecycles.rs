@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(FileLifecycles::Table, FileLifecycles::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_restore_requested_by = ForeignKey::create();
+        foreign_key_restore_requested_by
+            .from(FileLifecycles::Table, FileLifecycles::RestoreRequestedBy)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::SetNull)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FileLifecycles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FileLifecycles::FileId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FileLifecycles::AccessedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FileLifecycles::ArchivedAt).big_integer())
+                    .col(ColumnDef::new(FileLifecycles::RestoreRequestedAt).big_integer())
+                    .col(ColumnDef::new(FileLifecycles::RestoreRequestedBy).uuid())
+                    .foreign_key(&mut foreign_key_file_id)
+                    .foreign_key(&mut foreign_key_restore_requested_by)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("file_lifecycles_archived_at")
+                    .table(FileLifecycles::Table)
+                    .col(FileLifecycles::ArchivedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FileLifecycles::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum FileLifecycles {
+    Table,
+    FileId,
+    AccessedAt,
+    ArchivedAt,
+    RestoreRequestedAt,
+    RestoreRequestedBy,
+}
@@ -0,0 +1,108 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(AppPasswords::Table, AppPasswords::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_folder_id = ForeignKey::create();
+        foreign_key_folder_id
+            .from(AppPasswords::Table, AppPasswords::FolderId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AppPasswords::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AppPasswords::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AppPasswords::UserId).uuid().not_null())
+                    .col(ColumnDef::new(AppPasswords::Name).string().not_null())
+                    .col(ColumnDef::new(AppPasswords::Password).string().not_null())
+                    .col(ColumnDef::new(AppPasswords::Scopes).string().not_null())
+                    .col(ColumnDef::new(AppPasswords::FolderId).uuid())
+                    .col(
+                        ColumnDef::new(AppPasswords::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AppPasswords::LastUsedAt).big_integer())
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_folder_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("app_passwords_user_id")
+                    .table(AppPasswords::Table)
+                    .col(AppPasswords::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sessions::Table)
+                    .add_column(ColumnDef::new(Sessions::AppPasswordId).uuid().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sessions::Table)
+                    .drop_column(Sessions::AppPasswordId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(AppPasswords::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum AppPasswords {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Password,
+    Scopes,
+    FolderId,
+    CreatedAt,
+    LastUsedAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Sessions {
+    Table,
+    AppPasswordId,
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(TusUploads::Table, TusUploads::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(TusUploads::Table, TusUploads::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TusUploads::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TusUploads::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TusUploads::UserId).uuid().not_null())
+                    .col(ColumnDef::new(TusUploads::FileId).uuid().not_null())
+                    .col(ColumnDef::new(TusUploads::Chunk).big_integer().not_null())
+                    .col(ColumnDef::new(TusUploads::Length).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(TusUploads::Offset)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(TusUploads::Checksum).string())
+                    .col(ColumnDef::new(TusUploads::ChecksumFunction).string())
+                    .col(
+                        ColumnDef::new(TusUploads::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TusUploads::ExpiresAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_user_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("tus_uploads_file_id_chunk")
+                    .table(TusUploads::Table)
+                    .col(TusUploads::FileId)
+                    .col(TusUploads::Chunk)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TusUploads::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum TusUploads {
+    Table,
+    Id,
+    UserId,
+    FileId,
+    Chunk,
+    Length,
+    Offset,
+    Checksum,
+    ChecksumFunction,
+    CreatedAt,
+    ExpiresAt,
+}
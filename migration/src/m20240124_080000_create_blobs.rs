@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Blobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Blobs::Hash)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Blobs::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Blobs::ReferenceCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Blobs::CreatedAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_hash = ForeignKey::create();
+        foreign_key_hash
+            .from(FileChunks::Table, FileChunks::Hash)
+            .to(Blobs::Table, Blobs::Hash)
+            .on_delete(ForeignKeyAction::Restrict)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FileChunks::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(FileChunks::Name).string().not_null())
+                    .col(ColumnDef::new(FileChunks::Chunk).big_integer().not_null())
+                    .col(ColumnDef::new(FileChunks::Hash).string().not_null())
+                    .primary_key(Index::create().col(FileChunks::Name).col(FileChunks::Chunk))
+                    .foreign_key(&mut foreign_key_hash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("file_chunks_hash")
+                    .table(FileChunks::Table)
+                    .col(FileChunks::Hash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FileChunks::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Blobs::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Blobs {
+    Table,
+    Hash,
+    Size,
+    ReferenceCount,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub(crate) enum FileChunks {
+    Table,
+    Name,
+    Chunk,
+    Hash,
+}
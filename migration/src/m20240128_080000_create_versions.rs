@@ -0,0 +1,99 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230409_091730_create_files::Files;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_id = ForeignKey::create();
+        foreign_key_id
+            .from(Versions::Table, Versions::Id)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(Versions::Table, Versions::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(Versions::Table, Versions::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Versions::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Versions::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Versions::FileId).uuid().not_null())
+                    .col(ColumnDef::new(Versions::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Versions::CreatedAt).big_integer().not_null())
+                    .foreign_key(&mut foreign_key_id)
+                    .foreign_key(&mut foreign_key_file_id)
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("versions_file_id")
+                    .table(Versions::Table)
+                    .col(Versions::FileId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::VersionLimit).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::VersionLimit)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Versions::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Versions {
+    Table,
+    Id,
+    FileId,
+    UserId,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub(crate) enum Users {
+    Table,
+    Id,
+    VersionLimit,
+}
@@ -0,0 +1,158 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_users::Users, m20230409_091730_create_files::Files};
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(AccessKeys::Table, AccessKeys::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccessKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AccessKeys::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AccessKeys::UserId).uuid().not_null())
+                    .col(ColumnDef::new(AccessKeys::Name).string().not_null())
+                    .col(ColumnDef::new(AccessKeys::AccessKeyId).string().not_null())
+                    .col(
+                        ColumnDef::new(AccessKeys::SecretAccessKey)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AccessKeys::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AccessKeys::LastUsedAt).big_integer())
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("access_keys_access_key_id")
+                    .table(AccessKeys::Table)
+                    .col(AccessKeys::AccessKeyId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("access_keys_user_id")
+                    .table(AccessKeys::Table)
+                    .col(AccessKeys::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(S3Objects::Table, S3Objects::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_bucket_id = ForeignKey::create();
+        foreign_key_bucket_id
+            .from(S3Objects::Table, S3Objects::BucketId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        let mut foreign_key_access_key_id = ForeignKey::create();
+        foreign_key_access_key_id
+            .from(S3Objects::Table, S3Objects::AccessKeyId)
+            .to(AccessKeys::Table, AccessKeys::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(S3Objects::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(S3Objects::FileId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(S3Objects::AccessKeyId).uuid().not_null())
+                    .col(ColumnDef::new(S3Objects::BucketId).uuid().not_null())
+                    .col(ColumnDef::new(S3Objects::Key).text().not_null())
+                    .col(ColumnDef::new(S3Objects::EncryptedKey).text().not_null())
+                    .foreign_key(&mut foreign_key_file_id)
+                    .foreign_key(&mut foreign_key_bucket_id)
+                    .foreign_key(&mut foreign_key_access_key_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("s3_objects_access_key_id_bucket_id_key")
+                    .table(S3Objects::Table)
+                    .col(S3Objects::AccessKeyId)
+                    .col(S3Objects::BucketId)
+                    .col(S3Objects::Key)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(S3Objects::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(AccessKeys::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum AccessKeys {
+    Table,
+    Id,
+    UserId,
+    Name,
+    AccessKeyId,
+    SecretAccessKey,
+    CreatedAt,
+    LastUsedAt,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum S3Objects {
+    Table,
+    FileId,
+    AccessKeyId,
+    BucketId,
+    Key,
+    EncryptedKey,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_user_id = ForeignKey::create();
+        foreign_key_user_id
+            .from(Transfers::Table, Transfers::UserId)
+            .to(Users::Table, Users::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Transfers::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Transfers::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Transfers::Day).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Transfers::UploadedBytes)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Transfers::DownloadedBytes)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .primary_key(Index::create().col(Transfers::UserId).col(Transfers::Day))
+                    .foreign_key(&mut foreign_key_user_id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Transfers::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum Transfers {
+    Table,
+    UserId,
+    Day,
+    UploadedBytes,
+    DownloadedBytes,
+}
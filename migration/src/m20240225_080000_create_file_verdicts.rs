@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230409_091730_create_files::Files;
+
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut foreign_key_file_id = ForeignKey::create();
+        foreign_key_file_id
+            .from(FileVerdicts::Table, FileVerdicts::FileId)
+            .to(Files::Table, Files::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::NoAction);
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FileVerdicts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FileVerdicts::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FileVerdicts::FileId).uuid().not_null())
+                    .col(ColumnDef::new(FileVerdicts::Scanner).string().not_null())
+                    .col(ColumnDef::new(FileVerdicts::Verdict).string().not_null())
+                    .col(ColumnDef::new(FileVerdicts::Details).string())
+                    .col(
+                        ColumnDef::new(FileVerdicts::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FileVerdicts::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(&mut foreign_key_file_id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("file_verdicts_file_id_scanner")
+                    .table(FileVerdicts::Table)
+                    .col(FileVerdicts::FileId)
+                    .col(FileVerdicts::Scanner)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("file_verdicts_verdict")
+                    .table(FileVerdicts::Table)
+                    .col(FileVerdicts::Verdict)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FileVerdicts::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub(crate) enum FileVerdicts {
+    Table,
+    Id,
+    FileId,
+    Scanner,
+    Verdict,
+    Details,
+    CreatedAt,
+    UpdatedAt,
+}
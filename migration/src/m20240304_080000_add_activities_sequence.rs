@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+/// Concurrent transactions on Postgres take the ids of the activities from the sequence
+/// in one order and commit them in another, a client that already saw the later id
+/// would never see the earlier one. The trigger takes the next id only after the lock
+/// that is held until the commit, so the ids become visible in the order they were taken.
+///
+/// SQLite runs a single writing transaction at a time and needs nothing of the sort.
+#[derive(DeriveMigrationName)]
+pub(crate) struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != sea_orm::DbBackend::Postgres {
+            return Ok(());
+        }
+
+        let connection = manager.get_connection();
+
+        connection
+            .execute_unprepared(
+                r"CREATE OR REPLACE FUNCTION activities_sequence() RETURNS trigger AS $$
+                BEGIN
+                    PERFORM pg_advisory_xact_lock(hashtext('activities_sequence'));
+                    NEW.id := nextval(pg_get_serial_sequence('activities', 'id'));
+                    RETURN NEW;
+                END;
+                $$ LANGUAGE plpgsql;",
+            )
+            .await?;
+
+        connection
+            .execute_unprepared(
+                r"CREATE TRIGGER activities_sequence BEFORE INSERT ON activities
+                FOR EACH ROW EXECUTE FUNCTION activities_sequence();",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != sea_orm::DbBackend::Postgres {
+            return Ok(());
+        }
+
+        let connection = manager.get_connection();
+
+        connection
+            .execute_unprepared("DROP TRIGGER IF EXISTS activities_sequence ON activities;")
+            .await?;
+
+        connection
+            .execute_unprepared("DROP FUNCTION IF EXISTS activities_sequence();")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use crate::notification::Notification;
+use error::AppResult;
+
+/// Notifier contract that will be setup on the
+/// context in order to enable pushing notifications
+#[async_trait::async_trait]
+pub trait NotifierContract
+where
+    Self: Send + Sync,
+{
+    /// Push the notification to the user
+    async fn notify(&self, notification: &Notification) -> AppResult<()>;
+
+    /// Clone the inner notifier
+    fn boxed_clone(&self) -> Box<dyn NotifierContract>;
+
+    #[cfg(feature = "mock")]
+    /// Check if the mock notifier has pushed the notification with the title,
+    /// this can be used when testing if the notification has been sent
+    fn has(&self, _title: &str) -> bool {
+        false
+    }
+}
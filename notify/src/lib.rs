@@ -0,0 +1,75 @@
+use config::notify::NotifyConfig;
+use error::AppResult;
+use transports::{gotify::GotifyNotifier, ntfy::NtfyNotifier, webhook::WebhookNotifier};
+
+#[cfg(feature = "mock")]
+use crate::transports::mock::MockNotifier;
+
+pub mod contract;
+pub mod notification;
+pub mod transports;
+
+/// Push notification sender that can be instantiated by using the
+/// application config, it picks the transport the config asks for.
+///
+/// Notifications are pushed next to the emails, so the self-hosters
+/// without an SMTP server still get the alerts on their phones.
+pub struct Notifier {
+    inner: Box<dyn contract::NotifierContract>,
+}
+
+impl Clone for Notifier {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.boxed_clone(),
+        }
+    }
+}
+
+impl Notifier {
+    pub fn new(config: &config::Config) -> Option<Self> {
+        let inner: Box<dyn contract::NotifierContract> = match &config.notify {
+            NotifyConfig::Ntfy(c) => Box::new(NtfyNotifier::new(c)),
+            NotifyConfig::Gotify(c) => Box::new(GotifyNotifier::new(c)),
+            NotifyConfig::Webhook(c) => Box::new(WebhookNotifier::new(c)),
+            NotifyConfig::None => return None,
+        };
+
+        Some(Self { inner })
+    }
+
+    #[cfg(feature = "mock")]
+    pub fn mock() -> Self {
+        Self {
+            inner: Box::new(MockNotifier::new()),
+        }
+    }
+
+    /// Push the notification, the failure is only logged because the
+    /// notifications are sent after the action they are about is done
+    pub async fn send(&self, notification: notification::Notification) {
+        if let Err(e) = self.inner.notify(&notification).await {
+            log::error!(
+                "Failed pushing the notification to {}: {}",
+                notification.user_id,
+                e
+            );
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl contract::NotifierContract for Notifier {
+    async fn notify(&self, notification: &notification::Notification) -> AppResult<()> {
+        self.inner.notify(notification).await
+    }
+
+    fn boxed_clone(&self) -> Box<dyn contract::NotifierContract> {
+        self.inner.boxed_clone()
+    }
+
+    #[cfg(feature = "mock")]
+    fn has(&self, title: &str) -> bool {
+        self.inner.has(title)
+    }
+}
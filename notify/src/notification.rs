@@ -0,0 +1,58 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+/// How urgently the user should be told about the notification
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Default,
+    /// Security alerts, the phone should make a sound
+    High,
+}
+
+/// What the notification is about, so the webhook receivers can route them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Security,
+    Share,
+    FileRequest,
+    Restore,
+}
+
+/// Notification pushed to the user through the configured transport
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    pub user_id: Uuid,
+    pub email: String,
+    pub kind: Kind,
+    pub priority: Priority,
+    pub title: String,
+    pub message: String,
+    /// Link the notification opens in the client
+    pub link: Option<String>,
+}
+
+impl Notification {
+    pub fn new(user_id: Uuid, email: &str, kind: Kind, title: &str, message: &str) -> Self {
+        let priority = match kind {
+            Kind::Security => Priority::High,
+            Kind::Share | Kind::FileRequest | Kind::Restore => Priority::Default,
+        };
+
+        Self {
+            user_id,
+            email: email.to_string(),
+            kind,
+            priority,
+            title: title.to_string(),
+            message: message.to_string(),
+            link: None,
+        }
+    }
+
+    pub fn link(mut self, link: &str) -> Self {
+        self.link = Some(link.to_string());
+        self
+    }
+}
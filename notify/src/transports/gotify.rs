@@ -0,0 +1,61 @@
+use config::notify::GotifyConfig;
+use error::AppResult;
+use reqwest::Client;
+
+use super::checked;
+use crate::{
+    contract::NotifierContract,
+    notification::{Notification, Priority},
+};
+
+/// Pushes the notifications as the messages of the Gotify application
+#[derive(Clone)]
+pub struct GotifyNotifier {
+    client: Client,
+    config: GotifyConfig,
+}
+
+impl GotifyNotifier {
+    pub fn new(config: &GotifyConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifierContract for GotifyNotifier {
+    async fn notify(&self, notification: &Notification) -> AppResult<()> {
+        let priority = match notification.priority {
+            Priority::Default => 5,
+            Priority::High => 8,
+        };
+
+        let mut body = serde_json::json!({
+            "title": notification.title,
+            "message": notification.message,
+            "priority": priority,
+        });
+
+        if let Some(link) = notification.link.as_deref() {
+            body["extras"] = serde_json::json!({
+                "client::notification": { "click": { "url": link } }
+            });
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/message", self.config.url))
+            .header("X-Gotify-Key", &self.config.token)
+            .json(&body)
+            .send()
+            .await?;
+
+        checked("gotify", response)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn NotifierContract> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{contract::NotifierContract, notification::Notification};
+use error::AppResult;
+
+#[derive(Clone)]
+pub struct MockNotifier {
+    sent_titles: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockNotifier {
+    pub fn new() -> Self {
+        Self {
+            sent_titles: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl Default for MockNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifierContract for MockNotifier {
+    async fn notify(&self, notification: &Notification) -> AppResult<()> {
+        self.sent_titles
+            .lock()
+            .unwrap()
+            .push(notification.title.clone());
+
+        Ok(())
+    }
+
+    fn has(&self, title: &str) -> bool {
+        self.sent_titles
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|sent| sent == title)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn NotifierContract> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,21 @@
+pub mod gotify;
+pub mod ntfy;
+pub mod webhook;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+use error::{AppResult, Error};
+use reqwest::Response;
+
+/// Turn the unsuccessful response of the transport into the error
+pub(crate) fn checked(transport: &str, response: Response) -> AppResult<()> {
+    match response.status().is_success() {
+        true => Ok(()),
+        false => Err(Error::InternalError(format!(
+            "{}_status:{}",
+            transport,
+            response.status().as_u16()
+        ))),
+    }
+}
@@ -0,0 +1,64 @@
+use config::notify::NtfyConfig;
+use error::AppResult;
+use reqwest::Client;
+
+use super::checked;
+use crate::{
+    contract::NotifierContract,
+    notification::{Notification, Priority},
+};
+
+/// Publishes the notifications to the ntfy topic of the user
+#[derive(Clone)]
+pub struct NtfyNotifier {
+    client: Client,
+    config: NtfyConfig,
+}
+
+impl NtfyNotifier {
+    pub fn new(config: &NtfyConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: config.clone(),
+        }
+    }
+
+    /// Topic the user subscribes to in the ntfy app
+    pub fn topic(&self, notification: &Notification) -> String {
+        format!("{}-{}", self.config.topic_prefix, notification.user_id)
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifierContract for NtfyNotifier {
+    async fn notify(&self, notification: &Notification) -> AppResult<()> {
+        let priority = match notification.priority {
+            Priority::Default => 3,
+            Priority::High => 5,
+        };
+
+        let mut body = serde_json::json!({
+            "topic": self.topic(notification),
+            "title": notification.title,
+            "message": notification.message,
+            "priority": priority,
+        });
+
+        if let Some(link) = notification.link.as_deref() {
+            body["click"] = link.into();
+        }
+
+        // Publishing as JSON is done on the root URL, the topic is in the body
+        let mut request = self.client.post(&self.config.url).json(&body);
+
+        if let Some(token) = self.config.token.as_deref() {
+            request = request.bearer_auth(token);
+        }
+
+        checked("ntfy", request.send().await?)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn NotifierContract> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,39 @@
+use config::notify::WebhookConfig;
+use error::AppResult;
+use reqwest::Client;
+
+use super::checked;
+use crate::{contract::NotifierContract, notification::Notification};
+
+/// Posts the notifications as they are to the webhook
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    client: Client,
+    config: WebhookConfig,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: &WebhookConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifierContract for WebhookNotifier {
+    async fn notify(&self, notification: &Notification) -> AppResult<()> {
+        let mut request = self.client.post(&self.config.url).json(notification);
+
+        if let Some(secret) = self.config.secret.as_deref() {
+            request = request.bearer_auth(secret);
+        }
+
+        checked("webhook", request.send().await?)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn NotifierContract> {
+        Box::new(self.clone())
+    }
+}
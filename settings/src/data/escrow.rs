@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Escrow {
+    enabled: bool,
+    public_key: Option<String>,
+}
+
+impl Escrow {
+    /// Enterprise mode where the key of every file is additionally encrypted
+    /// with the organization recovery key, so the data can be recovered
+    /// when the user leaves the organization.
+    pub fn enabled(&self) -> bool {
+        self.enabled && self.public_key.is_some()
+    }
+
+    /// Public RSA key of the organization the file keys are escrowed to,
+    /// only given when the escrow is enabled.
+    pub fn public_key(&self) -> Option<&str> {
+        match self.enabled {
+            true => self.public_key.as_deref(),
+            false => None,
+        }
+    }
+
+    /// Public key as it was set by the admin, regardless if escrow is enabled.
+    pub fn configured_public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+}
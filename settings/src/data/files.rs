@@ -0,0 +1,72 @@
+use crate::rule::{Rule, RuleValidate};
+
+use super::{Blacklist, Whitelist};
+use error::{AppResult, Error};
+use serde::{Deserialize, Serialize};
+
+/// Maximum size of the files with the mime type matching the rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeLimit {
+    mime: Rule,
+    max_bytes: u64,
+}
+
+/// Restrictions on the files users can store, or anyone can upload through
+/// the public file requests.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Files {
+    mime_whitelist: Option<Whitelist>,
+    mime_blacklist: Option<Blacklist>,
+    #[serde(default)]
+    max_sizes: Vec<SizeLimit>,
+}
+
+impl Files {
+    /// Check if the mime type is allowed, when the whitelist is set
+    /// only the whitelisted mime types are allowed.
+    pub fn mime_allowed(&self, mime: &str) -> bool {
+        let mime = normalize(mime);
+
+        if let Some(ref whitelist) = self.mime_whitelist {
+            if !whitelist.valid(&mime) {
+                return false;
+            }
+        }
+
+        if let Some(ref blacklist) = self.mime_blacklist {
+            return blacklist.valid(&mime);
+        }
+
+        true
+    }
+
+    /// Maximum size of the file with the given mime type, the first matching limit applies.
+    pub fn max_size(&self, mime: &str) -> Option<u64> {
+        let mime = normalize(mime);
+
+        self.max_sizes
+            .iter()
+            .find(|limit| limit.mime.valid(&mime))
+            .map(|limit| limit.max_bytes)
+    }
+
+    /// Throw a validation error if the file can't be stored.
+    pub fn check(&self, mime: &str, size: i64) -> AppResult<()> {
+        if !self.mime_allowed(mime) {
+            return Err(Error::as_validation("mime", "mime_not_allowed"));
+        }
+
+        if let Some(max_bytes) = self.max_size(mime) {
+            if size > max_bytes as i64 {
+                return Err(Error::as_validation("size", &format!("max:{}", max_bytes)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Mime types are matched without the parameters and regardless of the case.
+fn normalize(mime: &str) -> String {
+    mime.split(';').next().unwrap_or("").trim().to_lowercase()
+}
@@ -0,0 +1,84 @@
+use error::{AppResult, Error};
+use serde::{Deserialize, Serialize};
+
+/// Name the instance goes by until the admin gives it another one
+const DEFAULT_NAME: &str = "Hoodik";
+
+/// Profile of the instance the frontend is branded with, so the white-label
+/// deployments don't have to fork it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instance {
+    name: String,
+    logo_url: Option<String>,
+    accent_color: Option<String>,
+    terms_url: Option<String>,
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_NAME.to_string(),
+            logo_url: None,
+            accent_color: None,
+            terms_url: None,
+        }
+    }
+}
+
+impl Instance {
+    /// Name of the instance shown in place of the application name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Address of the logo shown in place of the application logo.
+    pub fn logo_url(&self) -> Option<&str> {
+        self.logo_url.as_deref()
+    }
+
+    /// Color of the buttons and links, in the `#rrggbb` or `#rgb` form.
+    pub fn accent_color(&self) -> Option<&str> {
+        self.accent_color.as_deref()
+    }
+
+    /// Address of the terms of the service the users agree to when registering.
+    pub fn terms_url(&self) -> Option<&str> {
+        self.terms_url.as_deref()
+    }
+
+    /// Throw a validation error if the profile can't be shown by the frontend.
+    pub fn validate(&self) -> AppResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(Error::as_validation("instance.name", "required"));
+        }
+
+        if !self.logo_url.as_deref().map_or(true, is_url) {
+            return Err(Error::as_validation("instance.logo_url", "invalid_url"));
+        }
+
+        if !self.terms_url.as_deref().map_or(true, is_url) {
+            return Err(Error::as_validation("instance.terms_url", "invalid_url"));
+        }
+
+        if !self.accent_color.as_deref().map_or(true, is_color) {
+            return Err(Error::as_validation(
+                "instance.accent_color",
+                "invalid_color",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Only the web addresses can be linked from the frontend.
+fn is_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+fn is_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
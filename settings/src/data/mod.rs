@@ -1,8 +1,14 @@
 mod blacklist;
+mod escrow;
+mod files;
+mod instance;
 mod users;
 mod whitelist;
 
 pub use blacklist::Blacklist;
+pub use escrow::Escrow;
+pub use files::{Files, SizeLimit};
+pub use instance::Instance;
 pub use users::Users;
 pub use whitelist::Whitelist;
 
@@ -12,6 +18,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Data {
     pub users: Users,
+    /// Settings written before the escrow existed don't have it
+    #[serde(default)]
+    pub escrow: Escrow,
+    /// Mime types and sizes of the files that can be stored
+    #[serde(default)]
+    pub files: Files,
+    /// Branding of the instance shown by the frontend
+    #[serde(default)]
+    pub instance: Instance,
 }
 
 impl Data {
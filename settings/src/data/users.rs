@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Users {
     quota_bytes: Option<u64>,
+    transfer_bytes: Option<u64>,
     allow_register: bool,
     enforce_email_activation: bool,
     email_whitelist: Option<Whitelist>,
@@ -17,6 +18,7 @@ impl Default for Users {
     fn default() -> Self {
         Self {
             quota_bytes: None,
+            transfer_bytes: None,
             allow_register: true,
             enforce_email_activation: false,
             email_whitelist: None,
@@ -31,6 +33,12 @@ impl Users {
         self.quota_bytes
     }
 
+    /// Daily limit of the content each user can upload and download together, in bytes.
+    /// It is separate from the quota, the transfers count even when the files are removed after.
+    pub fn transfer_bytes(&self) -> Option<u64> {
+        self.transfer_bytes
+    }
+
     /// Allow users to register freely. If false, only whitelisted emails can register,
     /// or the ones that were invited.
     pub fn allow_register(&self) -> bool {
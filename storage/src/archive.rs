@@ -0,0 +1,354 @@
+//! # Cold archive
+//!
+//! Chunks of the files nobody has downloaded for the ARCHIVE_AFTER_MONTHS are moved to
+//! the S3 bucket with the archive storage class, Glacier and the likes, where they cost
+//! a fraction of the disk, but can't be read until they are restored.
+//!
+//! The sweeper looks for the cold files every hour and archives them one by one, the
+//! chunks are uploaded first and removed from the storage only once the file is marked
+//! as archived. Downloading the archived file answers with `202 Accepted` and queues
+//! the restore job instead, the job asks S3 to restore the chunks and checks back until
+//! all of them are readable, then copies them back to the storage and lets the user
+//! who tried to download the file know it is ready.
+
+use std::time::Duration;
+
+use actix_web::HttpResponse;
+use chrono::Utc;
+use config::archive::{ArchiveBucket, ArchiveConfig};
+use context::Context;
+use entity::{file_lifecycles, files, users, EntityTrait, Uuid};
+use error::AppResult;
+use fs::prelude::*;
+use reqwest::{Method, StatusCode};
+
+use crate::{
+    data::external_exports::Credentials,
+    emails,
+    external::s3::{checked, S3},
+    jobs::{RestoreArchive, RESTORE_ARCHIVED},
+    repository::lifecycles,
+};
+
+/// How often the sweeper looks for the cold files
+const SWEEP_EVERY: Duration = Duration::from_secs(60 * 60);
+
+/// How many cold files are loaded at the time
+const SWEEP_BATCH: u64 = 100;
+
+/// Month as it is counted for the ARCHIVE_AFTER_MONTHS
+const MONTH: i64 = 30 * 24 * 60 * 60;
+
+/// How long to wait before checking on the restore again, restoring takes hours
+pub(crate) const RESTORE_POLL: i64 = 15 * 60;
+
+/// State of the archived chunk in the bucket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Restore {
+    /// Chunk can't be read, the restore has to be requested
+    Archived,
+    /// Restore was requested and S3 is still working on it
+    Restoring,
+    /// Chunk can be read
+    Ready,
+}
+
+/// Bucket the chunks are archived to
+pub(crate) struct Archive {
+    s3: S3,
+    bucket: ArchiveBucket,
+}
+
+impl Archive {
+    /// Create the archive if the bucket is configured
+    pub(crate) fn new(context: &Context) -> AppResult<Option<Self>> {
+        let bucket = match &context.config.archive {
+            ArchiveConfig::Bucket(bucket) => bucket.clone(),
+            ArchiveConfig::None => return Ok(None),
+        };
+
+        let s3 = S3::new(
+            &bucket.url,
+            Credentials {
+                region: Some(bucket.region.clone()),
+                access_key_id: Some(bucket.access_key_id.clone()),
+                secret_access_key: Some(bucket.secret_access_key.clone()),
+                ..Default::default()
+            },
+        )?;
+
+        Ok(Some(Self { s3, bucket }))
+    }
+
+    /// Store the chunk with the archive storage class
+    async fn put(&self, path: &str, content: Vec<u8>) -> AppResult<()> {
+        let storage_class = [("x-amz-storage-class", self.bucket.storage_class.as_str())];
+
+        let response = self
+            .s3
+            .signed(Method::PUT, path, &[], &storage_class)
+            .body(content)
+            .send()
+            .await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    /// Read the chunk, it has to be restored first
+    async fn get(&self, path: &str) -> AppResult<Vec<u8>> {
+        let response = self.s3.signed(Method::GET, path, &[], &[]).send().await?;
+
+        Ok(checked(response).await?.bytes().await?.to_vec())
+    }
+
+    async fn delete(&self, path: &str) -> AppResult<()> {
+        let response = self
+            .s3
+            .signed(Method::DELETE, path, &[], &[])
+            .send()
+            .await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    /// Ask S3 to make the readable copy of the chunk
+    async fn restore(&self, path: &str) -> AppResult<()> {
+        let body = format!(
+            "<RestoreRequest><Days>{}</Days><GlacierJobParameters><Tier>{}</Tier>\
+            </GlacierJobParameters></RestoreRequest>",
+            self.bucket.restore_days, self.bucket.restore_tier
+        );
+
+        let response = self
+            .s3
+            .signed(Method::POST, path, &[("restore", "")], &[])
+            .body(body)
+            .send()
+            .await?;
+
+        // Restore that was already requested is refused as the conflict
+        if response.status() == StatusCode::CONFLICT {
+            return Ok(());
+        }
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    /// Check whether the chunk can be read, S3 tells it in the `x-amz-restore` header
+    async fn status(&self, path: &str) -> AppResult<Restore> {
+        let response = self.s3.signed(Method::HEAD, path, &[], &[]).send().await?;
+        let response = checked(response).await?;
+        let headers = response.headers();
+
+        let restore = headers
+            .get("x-amz-restore")
+            .and_then(|value| value.to_str().ok());
+        let storage_class = headers
+            .get("x-amz-storage-class")
+            .and_then(|value| value.to_str().ok());
+
+        Ok(match (restore, storage_class) {
+            (Some(restore), _) if restore.contains("ongoing-request=\"true\"") => {
+                Restore::Restoring
+            }
+            (Some(_), _) => Restore::Ready,
+            // Providers without the archive tiers keep the chunk as any other object
+            (None, None | Some("STANDARD")) => Restore::Ready,
+            (None, _) => Restore::Archived,
+        })
+    }
+}
+
+/// Looks for the cold files in the background and archives them
+pub struct Sweeper {
+    context: Context,
+    archive: Archive,
+}
+
+impl Sweeper {
+    /// Create the sweeper if the archive is configured
+    pub fn new(context: Context) -> Option<Self> {
+        match Archive::new(&context) {
+            Ok(Some(archive)) => Some(Self { context, archive }),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!(
+                    "Failed creating the archive, files won't be archived: {}",
+                    e
+                );
+
+                None
+            }
+        }
+    }
+
+    /// Start sweeping in the background, the first sweep runs right away
+    pub fn spawn(self) {
+        actix_web::rt::spawn(async move {
+            loop {
+                match sweep(&self.context, &self.archive).await {
+                    Ok(0) => {}
+                    Ok(archived) => log::info!("Archived {} cold files", archived),
+                    Err(e) => log::error!("Failed sweeping the cold files: {}", e),
+                }
+
+                actix_web::rt::time::sleep(SWEEP_EVERY).await;
+            }
+        });
+    }
+}
+
+/// Archive the files nobody has touched for the configured months, returns how many
+/// were archived. Files that fail are left where they are for the next sweep.
+pub(crate) async fn sweep(context: &Context, archive: &Archive) -> AppResult<u64> {
+    let before = Utc::now().timestamp() - archive.bucket.after_months as i64 * MONTH;
+    let mut archived = 0;
+
+    loop {
+        let files = lifecycles::cold(&context.db, before, SWEEP_BATCH).await?;
+        let loaded = files.len() as u64;
+        let mut failed = false;
+
+        for file in files.iter() {
+            match archive_file(context, archive, file).await {
+                Ok(()) => archived += 1,
+                Err(e) => {
+                    log::error!("Failed archiving the file {}: {}", file.id, e);
+                    failed = true;
+                }
+            }
+        }
+
+        // Failed files would be loaded again, so they wait for the next sweep
+        if loaded < SWEEP_BATCH || failed {
+            return Ok(archived);
+        }
+    }
+}
+
+/// Move the chunks of the file to the archive
+async fn archive_file(context: &Context, archive: &Archive, file: &files::Model) -> AppResult<()> {
+    let fs = Fs::new(&context.config);
+
+    for chunk in 0..file.chunks.unwrap_or(0) {
+        let content = fs.pull(file, chunk).await?;
+        archive.put(&path(file, chunk)?, content).await?;
+    }
+
+    let accessed_at = file.finished_upload_at.unwrap_or(file.created_at);
+    lifecycles::archived(&context.db, file.id, accessed_at).await?;
+
+    fs.purge(file).await
+}
+
+/// Answer for the download of the archived file, the restore of the file is queued
+/// and the user is notified once it is done. Downloads of the files that are in the
+/// storage get `None` and are recorded, so the file isn't archived while in use.
+pub async fn restoring(
+    context: &Context,
+    file_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<Option<HttpResponse>> {
+    let lifecycle = lifecycles::find(&context.db, file_id).await?;
+
+    if !lifecycle.as_ref().map_or(false, |l| l.is_archived()) {
+        lifecycles::touch(&context.db, lifecycle.as_ref(), file_id).await?;
+
+        return Ok(None);
+    }
+
+    if lifecycles::request_restore(&context.db, file_id, user_id).await? {
+        jobs::repository::Repository::new(&context.db)
+            .push(RESTORE_ARCHIVED, &RestoreArchive { id: file_id })
+            .await?;
+    }
+
+    Ok(Some(
+        HttpResponse::Accepted().json(serde_json::json!({ "status": "restoring" })),
+    ))
+}
+
+/// Restore the chunks of the archived file, returns false while S3 is still restoring them
+pub(crate) async fn restore(
+    context: &Context,
+    archive: &Archive,
+    lifecycle: &file_lifecycles::Model,
+) -> AppResult<bool> {
+    let file = match files::Entity::find_by_id(lifecycle.file_id)
+        .one(&context.db)
+        .await?
+    {
+        Some(file) => file,
+        None => return Ok(true),
+    };
+
+    let chunks = 0..file.chunks.unwrap_or(0);
+    let mut ready = true;
+
+    for chunk in chunks.clone() {
+        let path = path(&file, chunk)?;
+
+        match archive.status(&path).await? {
+            Restore::Archived => {
+                archive.restore(&path).await?;
+                ready = false;
+            }
+            Restore::Restoring => ready = false,
+            Restore::Ready => {}
+        }
+    }
+
+    if !ready {
+        return Ok(false);
+    }
+
+    let fs = Fs::new(&context.config);
+
+    for chunk in chunks.clone() {
+        let content = archive.get(&path(&file, chunk)?).await?;
+        fs.push(&file, chunk, &content).await?;
+    }
+
+    lifecycles::restored(&context.db, lifecycle).await?;
+
+    // Chunks are back in the storage, the archived copies would only be billed
+    for chunk in chunks {
+        if let Err(e) = archive.delete(&path(&file, chunk)?).await {
+            log::warn!(
+                "Failed deleting the chunk {} of the file {} from the archive: {}",
+                chunk,
+                file.id,
+                e
+            );
+        }
+    }
+
+    let user = match lifecycle.restore_requested_by {
+        Some(user_id) => users::Entity::find_by_id(user_id).one(&context.db).await?,
+        None => None,
+    };
+
+    // The file is already restored, failing here would not send it again
+    if let Some(user) = user {
+        if let Err(e) = emails::restored::send(context, &user, &file).await {
+            log::warn!(
+                "Failed notifying about the restored file {}: {}",
+                file.id,
+                e
+            );
+        }
+    }
+
+    Ok(true)
+}
+
+/// Chunks are archived under the same name they have in the storage
+fn path(file: &files::Model, chunk: i64) -> AppResult<String> {
+    Ok(file.filename()?.with_chunk(chunk).to_string())
+}
@@ -0,0 +1,144 @@
+//! # Authorization of the storage operations
+//!
+//! Every decision whether the user can do something with the file or the folder is made
+//! here, the routes and the repositories only say what they are about to do. A new kind
+//! of access is then a change of the policy below instead of a change of every route.
+//!
+//! The user only ever gets the files that are theirs, shared with them or in the spaces
+//! of their groups, so the policy decides what they can do with the file they already got:
+//!  - the owner can do anything with their files,
+//!  - the users the file or the folder is shared with read it, change it with the write
+//!    permission and share it further with the manage permission,
+//!  - the members of the group change the files in its spaces according to their role,
+//!  - the admins can also read the folders shared with them, nothing more, the content
+//!    is encrypted for the owner and the users it is shared with.
+//!
+//! Links are authorized by the links themselves, with the link key and the password.
+use auth::data::claims::Claims;
+use entity::{group_members::Role, ConnectionTrait, Uuid};
+use error::{AppResult, Error};
+
+use crate::{data::app_file::AppFile, repository::Repository};
+
+/// Access the user needs to the file or the folder for what they are about to do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// See the metadata of the file or the folder and download the file
+    Read,
+    /// Change the file, its content, its name, its place or its versions
+    Write,
+    /// Share the file or the folder further with other users
+    Manage,
+    /// Act as the owner, manage the shares or work with the whole folder tree
+    Own,
+}
+
+/// User the operation is authorized for
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Subject {
+    pub(crate) user_id: Uuid,
+    pub(crate) admin: bool,
+}
+
+impl From<&Claims> for Subject {
+    fn from(claims: &Claims) -> Self {
+        Self {
+            user_id: claims.sub,
+            admin: claims.role.as_deref() == Some("admin"),
+        }
+    }
+}
+
+impl From<Uuid> for Subject {
+    fn from(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            admin: false,
+        }
+    }
+}
+
+/// Make sure the user has the access to the file or the folder. Files the user
+/// can't see are not found, files in the spaces the user can only read are forbidden.
+pub(crate) async fn authorize<T: ConnectionTrait>(
+    connection: &T,
+    subject: impl Into<Subject>,
+    access: Access,
+    file: &AppFile,
+) -> AppResult<()> {
+    let subject = subject.into();
+
+    // Only the changes depend on the space the file is in
+    let role = match access {
+        Access::Write => {
+            Repository::new(connection)
+                .spaces(subject.user_id)
+                .role(file.id)
+                .await?
+        }
+        Access::Read | Access::Manage | Access::Own => None,
+    };
+
+    decide(&subject, access, file, role)
+}
+
+/// Same as [authorize], for the access that only a stored file can be given,
+/// the folders and the virtual files are not found
+pub(crate) async fn file<T: ConnectionTrait>(
+    connection: &T,
+    subject: impl Into<Subject>,
+    access: Access,
+    file: &AppFile,
+) -> AppResult<()> {
+    if !file.is_file() {
+        return Err(Error::NotFound("file_not_found".to_string()));
+    }
+
+    authorize(connection, subject, access, file).await
+}
+
+/// Same as [authorize], for the access that only a folder can be given
+pub(crate) async fn directory<T: ConnectionTrait>(
+    connection: &T,
+    subject: impl Into<Subject>,
+    access: Access,
+    folder: &AppFile,
+) -> AppResult<()> {
+    if !folder.is_dir() {
+        return Err(Error::NotFound("directory_not_found".to_string()));
+    }
+
+    authorize(connection, subject, access, folder).await
+}
+
+/// The policy, given the role of the user in the space the file is in
+/// and the permission of the share the user got the file with
+pub(crate) fn decide(
+    subject: &Subject,
+    access: Access,
+    file: &AppFile,
+    role: Option<Role>,
+) -> AppResult<()> {
+    let owner = file.is_owner && file.user_id == subject.user_id;
+    let shared = !file.is_owner && file.user_id == subject.user_id;
+
+    let allowed = match access {
+        Access::Read => owner || shared || file.is_file() || subject.admin,
+        Access::Write => match role {
+            Some(role) if role.can_write() => true,
+            Some(_) => return Err(Error::Forbidden("space_read_only".to_string())),
+            None => owner || (shared && file.permission.can_write()),
+        },
+        Access::Manage => owner || (shared && file.permission.can_manage()),
+        Access::Own => owner,
+    };
+
+    match allowed {
+        true => Ok(()),
+        false if access == Access::Read => {
+            Err(Error::NotFound("file_or_dir_not_found".to_string()))
+        }
+        false if file.is_dir() => Err(Error::NotFound("directory_not_found".to_string())),
+        false => Err(Error::NotFound("file_not_found".to_string())),
+    }
+}
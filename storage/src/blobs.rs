@@ -0,0 +1,243 @@
+//! # Blobs of the content addressed storage
+//!
+//! With the STORAGE_CONTENT_ADDRESSED the storage provider only keeps the blobs, the
+//! lists of the blobs the files are made of are kept in the `file_chunks` and the number
+//! of the chunks pointing to each blob in the `blobs`, see [fs::cas].
+//!
+//! The counts are changed in the same transaction as the chunks pointing to the blobs.
+//! The blob left without references stays in the table until the provider has purged its
+//! content, the purge runs while the blob is locked, and only if it is still unreferenced,
+//! so the chunk written meanwhile either waits for it or takes its reference before it.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use context::Context;
+use entity::{
+    blobs, file_chunks, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, Expr, OnConflict,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use error::AppResult;
+use fs::cas::{ChunkBlob, ChunkIndex};
+use futures::future::BoxFuture;
+
+/// Index of the blobs in the database, registered when the server starts
+pub struct BlobIndex {
+    context: Context,
+}
+
+impl BlobIndex {
+    pub fn new(context: Context) -> Self {
+        Self { context }
+    }
+
+    /// Register the index for the storage if it is content addressed
+    pub fn register(context: &Context) {
+        if context.config.server.content_addressed {
+            fs::cas::register(Self::new(context.clone()));
+        }
+    }
+}
+
+#[async_trait]
+impl ChunkIndex for BlobIndex {
+    async fn chunks(&self, name: &str) -> AppResult<Vec<ChunkBlob>> {
+        chunks(&self.context.db, name).await
+    }
+
+    async fn insert(&self, name: &str, blob: &ChunkBlob) -> AppResult<Vec<String>> {
+        let connection = self.context.db.begin().await?;
+
+        let existing = file_chunks::Entity::find_by_id((name.to_string(), blob.chunk))
+            .one(&connection)
+            .await?;
+
+        if existing.as_ref().map(|chunk| &chunk.hash) == Some(&blob.hash) {
+            connection.commit().await?;
+
+            return Ok(vec![]);
+        }
+
+        reference(&connection, &blob.hash, blob.size as i64).await?;
+
+        let chunk = file_chunks::ActiveModel {
+            name: ActiveValue::Set(name.to_string()),
+            chunk: ActiveValue::Set(blob.chunk),
+            hash: ActiveValue::Set(blob.hash.clone()),
+        };
+
+        let unused = match existing {
+            Some(existing) => {
+                file_chunks::Entity::update(chunk).exec(&connection).await?;
+
+                release(&connection, vec![existing.hash]).await?
+            }
+            None => {
+                file_chunks::Entity::insert(chunk)
+                    .exec_without_returning(&connection)
+                    .await?;
+
+                vec![]
+            }
+        };
+
+        connection.commit().await?;
+
+        Ok(unused)
+    }
+
+    async fn link(&self, from: &str, to: &str) -> AppResult<Vec<i64>> {
+        let connection = self.context.db.begin().await?;
+        let blobs = chunks(&connection, from).await?;
+
+        for blob in blobs.iter() {
+            reference(&connection, &blob.hash, blob.size as i64).await?;
+
+            file_chunks::Entity::insert(file_chunks::ActiveModel {
+                name: ActiveValue::Set(to.to_string()),
+                chunk: ActiveValue::Set(blob.chunk),
+                hash: ActiveValue::Set(blob.hash.clone()),
+            })
+            .exec_without_returning(&connection)
+            .await?;
+        }
+
+        connection.commit().await?;
+
+        Ok(blobs.into_iter().map(|blob| blob.chunk).collect())
+    }
+
+    async fn remove(&self, name: &str) -> AppResult<Vec<String>> {
+        let connection = self.context.db.begin().await?;
+
+        let hashes = chunks(&connection, name)
+            .await?
+            .into_iter()
+            .map(|blob| blob.hash)
+            .collect::<Vec<_>>();
+
+        file_chunks::Entity::delete_many()
+            .filter(file_chunks::Column::Name.eq(name))
+            .exec(&connection)
+            .await?;
+
+        let unused = release(&connection, hashes).await?;
+        connection.commit().await?;
+
+        Ok(unused)
+    }
+
+    async fn reference(&self, blob: &ChunkBlob) -> AppResult<()> {
+        reference(&self.context.db, &blob.hash, blob.size as i64).await
+    }
+
+    async fn dereference(&self, hashes: Vec<String>) -> AppResult<Vec<String>> {
+        let connection = self.context.db.begin().await?;
+        let unused = release(&connection, hashes).await?;
+        connection.commit().await?;
+
+        Ok(unused)
+    }
+
+    async fn forget(&self, hash: &str, purge: BoxFuture<'_, AppResult<()>>) -> AppResult<bool> {
+        let connection = self.context.db.begin().await?;
+
+        let unused = blobs::Entity::find_by_id(hash.to_string())
+            .filter(blobs::Column::ReferenceCount.lte(0))
+            .lock_exclusive()
+            .one(&connection)
+            .await?;
+
+        if unused.is_none() {
+            connection.commit().await?;
+
+            return Ok(false);
+        }
+
+        purge.await?;
+
+        blobs::Entity::delete_by_id(hash.to_string())
+            .exec(&connection)
+            .await?;
+        connection.commit().await?;
+
+        Ok(true)
+    }
+}
+
+/// Blobs of the file ordered by the chunk number
+async fn chunks<T: ConnectionTrait>(connection: &T, name: &str) -> AppResult<Vec<ChunkBlob>> {
+    let chunks = file_chunks::Entity::find()
+        .filter(file_chunks::Column::Name.eq(name))
+        .find_also_related(blobs::Entity)
+        .order_by_asc(file_chunks::Column::Chunk)
+        .all(connection)
+        .await?;
+
+    Ok(chunks
+        .into_iter()
+        .map(|(chunk, blob)| ChunkBlob {
+            chunk: chunk.chunk,
+            hash: chunk.hash,
+            size: blob.map(|blob| blob.size as u64).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Add the reference to the blob, the blob is added with the first one
+async fn reference<T: ConnectionTrait>(connection: &T, hash: &str, size: i64) -> AppResult<()> {
+    blobs::Entity::insert(blobs::ActiveModel {
+        hash: ActiveValue::Set(hash.to_string()),
+        size: ActiveValue::Set(size),
+        reference_count: ActiveValue::Set(1),
+        created_at: ActiveValue::Set(Utc::now().timestamp()),
+    })
+    .on_conflict(
+        OnConflict::column(blobs::Column::Hash)
+            .value(
+                blobs::Column::ReferenceCount,
+                Expr::col((blobs::Entity, blobs::Column::ReferenceCount)).add(1),
+            )
+            .to_owned(),
+    )
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove the references to the blobs, one for each time the hash is given. Hashes of
+/// the blobs left without any are returned for purging, they are forgotten afterwards.
+async fn release<T: ConnectionTrait>(
+    connection: &T,
+    hashes: Vec<String>,
+) -> AppResult<Vec<String>> {
+    let mut counts = HashMap::<String, i64>::new();
+
+    for hash in hashes {
+        *counts.entry(hash).or_default() += 1;
+    }
+
+    for (hash, count) in counts.iter() {
+        blobs::Entity::update_many()
+            .col_expr(
+                blobs::Column::ReferenceCount,
+                Expr::col(blobs::Column::ReferenceCount).sub(*count),
+            )
+            .filter(blobs::Column::Hash.eq(hash.as_str()))
+            .exec(connection)
+            .await?;
+    }
+
+    let unused = blobs::Entity::find()
+        .filter(blobs::Column::Hash.is_in(counts.into_keys()))
+        .filter(blobs::Column::ReferenceCount.lte(0))
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|blob| blob.hash)
+        .collect::<Vec<_>>();
+
+    Ok(unused)
+}
@@ -0,0 +1,48 @@
+//! # Access keys
+//!
+//! Access keys are created by the user for the S3 tools, the tools sign their requests
+//! with the secret of the key, see [crate::s3].
+use ::error::AppResult;
+use entity::access_keys;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum length of the name of the access key
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// Create the access key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateAccessKey {
+    /// Name of the tool or the device the key is for
+    pub name: Option<String>,
+}
+
+impl Validation for CreateAccessKey {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(name),
+            rule_length_max!(name, MAX_NAME_LENGTH),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(name)]
+    }
+}
+
+impl CreateAccessKey {
+    /// Validated name of the access key
+    pub fn into_value(self) -> AppResult<String> {
+        let data = self.validate()?;
+
+        Ok(data.name.unwrap())
+    }
+}
+
+/// Created access key, the secret is only shown this once
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreatedAccessKey {
+    #[serde(flatten)]
+    pub access_key: access_keys::Model,
+    pub secret_access_key: String,
+}
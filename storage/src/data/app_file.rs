@@ -1,13 +1,21 @@
-use entity::{files, links, user_files, DbErr, FromQueryResult, QueryResult, Uuid};
+use entity::{
+    files, links,
+    user_files::{self, Permission},
+    DbErr, FromQueryResult, JsonValue, QueryResult, Uuid,
+};
 use error::{AppResult, Error};
 use fs::prelude::{Filename, IntoFilename};
 use serde::{Deserialize, Serialize};
 
+use super::virtual_file::VIRTUAL_MIME_PREFIX;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppFile {
     pub id: Uuid,
     pub user_id: Uuid,
     pub is_owner: bool,
+    /// What the user can do with the file shared with them
+    pub permission: Permission,
     pub encrypted_key: String,
     pub encrypted_name: String,
     pub encrypted_thumbnail: Option<String>,
@@ -20,6 +28,14 @@ pub struct AppFile {
     pub file_modified_at: i64,
     pub created_at: i64,
     pub finished_upload_at: Option<i64>,
+    pub sha256: Option<String>,
+    pub conflict_of: Option<Uuid>,
+    pub conflict_device: Option<String>,
+    pub legal_hold: bool,
+    pub crypto_version: i32,
+    pub encrypted_payload: Option<String>,
+    pub deleted_at: Option<i64>,
+    pub attributes: Option<JsonValue>,
     pub is_new: bool,
     pub uploaded_chunks: Option<Vec<i64>>,
     pub link: Option<links::Model>,
@@ -33,6 +49,12 @@ impl IntoFilename for AppFile {
             ));
         }
 
+        if self.is_virtual() {
+            return Err(Error::BadRequest(
+                "cannot_get_filename_from_virtual_file".to_string(),
+            ));
+        }
+
         Ok(Filename::new(self.id).with_timestamp(self.created_at))
     }
 }
@@ -44,14 +66,20 @@ impl PartialEq for AppFile {
 }
 
 impl AppFile {
+    /// File with the content stored in chunks
     pub fn is_file(&self) -> bool {
-        !self.is_dir()
+        !self.is_dir() && !self.is_virtual()
     }
 
     pub fn is_dir(&self) -> bool {
         &self.mime == "dir"
     }
 
+    /// Bookmark or a note, the whole content is in the encrypted payload
+    pub fn is_virtual(&self) -> bool {
+        self.mime.starts_with(VIRTUAL_MIME_PREFIX)
+    }
+
     pub fn is_new(mut self, is_new: bool) -> Self {
         self.is_new = is_new;
 
@@ -69,6 +97,7 @@ impl FromQueryResult for AppFile {
             id: file.id,
             user_id: user_file.user_id,
             is_owner: user_file.is_owner,
+            permission: user_file.permission,
             encrypted_key: user_file.encrypted_key,
             name_hash: file.name_hash,
             encrypted_name: file.encrypted_name,
@@ -81,6 +110,14 @@ impl FromQueryResult for AppFile {
             file_modified_at: file.file_modified_at,
             created_at: file.created_at,
             finished_upload_at: file.finished_upload_at,
+            sha256: file.sha256,
+            conflict_of: file.conflict_of,
+            conflict_device: file.conflict_device,
+            legal_hold: file.legal_hold,
+            crypto_version: file.crypto_version,
+            encrypted_payload: file.encrypted_payload,
+            deleted_at: file.deleted_at,
+            attributes: user_file.attributes,
             is_new: false,
             uploaded_chunks: None,
             link,
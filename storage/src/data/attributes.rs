@@ -0,0 +1,81 @@
+//! Custom attributes the users attach to their files, ratings, workflow status and alike.
+//!
+//! Unlike the encrypted metadata, the attributes are stored in plain JSON so they can be
+//! used to filter the search results. They are kept on the users own copy of the file,
+//! so every user the file is shared with has their own attributes on it.
+use ::error::{AppResult, Error};
+use entity::JsonValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Map;
+use validr::*;
+
+/// Maximum length of all the attributes of the file serialized into JSON
+pub const MAX_ATTRIBUTES_LENGTH: usize = 16 * 1024;
+
+/// Maximum length of the name of the attribute
+pub const MAX_ATTRIBUTE_KEY_LENGTH: usize = 64;
+
+/// Name of the attribute can only have letters, numbers, `_`, `-` and `.`
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_ATTRIBUTE_KEY_LENGTH
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateAttributes {
+    /// Attributes to set on the file, the ones set to `null` are removed
+    /// and the ones that are not mentioned are left as they are
+    pub attributes: Option<Map<String, JsonValue>>,
+}
+
+impl Validation for UpdateAttributes {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(attributes),
+            Rule::new("attributes", |obj: &UpdateAttributes, error| {
+                if let Some(attributes) = obj.attributes.as_ref() {
+                    if !attributes.keys().all(|key| is_valid_key(key)) {
+                        error.add("invalid_key");
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl UpdateAttributes {
+    /// Apply the changes to the current attributes of the file,
+    /// no attributes are left as `None` instead of an empty object.
+    pub fn apply(self, current: Option<JsonValue>) -> AppResult<Option<JsonValue>> {
+        let data = self.validate()?;
+
+        let mut attributes = match current {
+            Some(JsonValue::Object(attributes)) => attributes,
+            _ => Map::new(),
+        };
+
+        for (key, value) in data.attributes.unwrap_or_default() {
+            match value {
+                JsonValue::Null => attributes.remove(&key),
+                value => attributes.insert(key, value),
+            };
+        }
+
+        if attributes.is_empty() {
+            return Ok(None);
+        }
+
+        let attributes = JsonValue::Object(attributes);
+
+        if serde_json::to_string(&attributes)?.len() > MAX_ATTRIBUTES_LENGTH {
+            let error = format!("max:{}", MAX_ATTRIBUTES_LENGTH);
+
+            return Err(Error::as_validation("attributes", &error));
+        }
+
+        Ok(Some(attributes))
+    }
+}
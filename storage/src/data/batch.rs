@@ -0,0 +1,142 @@
+//! Run many delete, move and copy operations in a single request
+use ::error::{AppResult, Error, ErrorResponse};
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_file::AppFile;
+
+/// Maximum number of the ids in all of the actions of a single batch
+pub const MAX_BATCH_ITEMS: usize = 1000;
+
+/// Actions run in the order they are sent, each id of the action on its own
+///
+/// Request: [Batch]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Batch {
+    pub actions: Option<Vec<BatchAction>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchAction {
+    /// One of: `delete`, `move`, `copy`
+    pub action: Option<String>,
+    /// List of file and folder ids the action is run for
+    pub ids: Option<Vec<Uuid>>,
+    /// Destination folder id of the move and the copy (empty for root)
+    pub file_id: Option<Uuid>,
+}
+
+/// Operation run for a single file or folder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Delete,
+    Move(Option<Uuid>),
+    Copy(Option<Uuid>),
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Delete => "delete",
+            Operation::Move(_) => "move",
+            Operation::Copy(_) => "copy",
+        }
+    }
+}
+
+impl Validation for Batch {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("actions", |obj: &Batch, error| {
+            let actions = match obj.actions.as_ref() {
+                Some(actions) if !actions.is_empty() => actions,
+                _ => return error.add("required"),
+            };
+
+            let mut items = 0;
+
+            for action in actions {
+                match action.action.as_deref() {
+                    Some("delete" | "move" | "copy") => {}
+                    _ => return error.add("invalid_action"),
+                }
+
+                match action.ids.as_ref() {
+                    Some(ids) if !ids.is_empty() => items += ids.len(),
+                    _ => return error.add("ids_required"),
+                }
+            }
+
+            if items > MAX_BATCH_ITEMS {
+                error.add("too_many_items");
+            }
+        })]
+    }
+}
+
+impl Batch {
+    /// Validate the batch and flatten it into the operations for each id
+    pub fn into_value(self) -> AppResult<Vec<(Operation, Uuid)>> {
+        let data = self.validate()?;
+        let mut operations = vec![];
+
+        for action in data.actions.unwrap_or_default() {
+            let operation = match action.action.as_deref() {
+                Some("delete") => Operation::Delete,
+                Some("move") => Operation::Move(action.file_id),
+                Some("copy") => Operation::Copy(action.file_id),
+                _ => return Err(Error::as_validation("actions", "invalid_action")),
+            };
+
+            operations.extend(
+                action
+                    .ids
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|id| (operation, id)),
+            );
+        }
+
+        Ok(operations)
+    }
+}
+
+/// Result of the operation for a single file or folder, failed ones
+/// have the status and the error the single request would have failed with.
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    pub action: &'static str,
+    pub id: Uuid,
+    /// Moved file or the copy, nothing for the deleted ones
+    pub file: Option<AppFile>,
+    pub status: Option<u16>,
+    pub error: Option<ErrorResponse>,
+}
+
+impl BatchResult {
+    pub fn done(operation: Operation, id: Uuid, file: Option<AppFile>) -> Self {
+        Self {
+            action: operation.name(),
+            id,
+            file,
+            status: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(operation: Operation, id: Uuid, error: &Error) -> Self {
+        let error = ErrorResponse::from(error);
+
+        Self {
+            action: operation.name(),
+            id,
+            file: None,
+            status: Some(error.status),
+            error: Some(error),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
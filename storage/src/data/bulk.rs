@@ -0,0 +1,66 @@
+//! Bulk upload of many small files in a single multipart request.
+//!
+//! The first part of the request is the `files` part with the JSON of this struct,
+//! followed by one part per file named by the index of the file in the list,
+//! holding the whole encrypted content of the file as its only chunk.
+use std::collections::HashSet;
+
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::create_file::{CreateFile, CreateFileData};
+
+/// Maximum number of files that can be sent in a single bulk upload
+pub const MAX_BULK_FILES: usize = 1000;
+
+/// Maximum size of the `files` part with the metadata of all the files
+pub const MAX_BULK_METADATA_BYTES: usize = 32 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BulkUpload {
+    /// Files to create, each of them has to fit into a single chunk
+    pub files: Option<Vec<CreateFile>>,
+}
+
+impl Validation for BulkUpload {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("files", |obj: &BulkUpload, error| {
+            let files = match obj.files.as_ref() {
+                Some(files) if !files.is_empty() => files,
+                _ => return error.add("required"),
+            };
+
+            if files.len() > MAX_BULK_FILES {
+                return error.add(format!("max:{}", MAX_BULK_FILES).as_str());
+            }
+
+            if files.iter().any(|file| file.mime.as_deref() == Some("dir")) {
+                return error.add("not_for_dir");
+            }
+
+            if files.iter().any(|file| file.chunks != Some(1)) {
+                return error.add("single_chunk_only");
+            }
+
+            let mut names = HashSet::new();
+            for file in files {
+                if !names.insert((file.name_hash.as_ref(), file.file_id.as_ref())) {
+                    return error.add("duplicate_name");
+                }
+            }
+        })]
+    }
+}
+
+impl BulkUpload {
+    pub fn into_active_models(self) -> AppResult<Vec<CreateFileData>> {
+        let data = self.validate()?;
+
+        data.files
+            .unwrap_or_default()
+            .into_iter()
+            .map(CreateFile::into_active_model)
+            .collect()
+    }
+}
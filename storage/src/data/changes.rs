@@ -0,0 +1,72 @@
+pub use entity::activities::Action;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_file::AppFile;
+
+/// Maximum number of changes returned in one page
+pub const MAX_CHANGES_LIMIT: u64 = 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct Changes {
+    /// Cursor from the previous response, changes after it will be returned
+    pub since: Option<i64>,
+
+    /// How many changes to load at most, defaults to [MAX_CHANGES_LIMIT]
+    pub limit: Option<u64>,
+}
+
+impl Validation for Changes {}
+
+impl Changes {
+    /// Get the cursor and the limit clamped to [MAX_CHANGES_LIMIT]
+    pub fn into_value(self) -> (i64, u64) {
+        let limit = self
+            .limit
+            .unwrap_or(MAX_CHANGES_LIMIT)
+            .clamp(1, MAX_CHANGES_LIMIT);
+
+        (self.since.unwrap_or_default().max(0), limit)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Change {
+    /// Cursor of the latest activity on the file
+    pub cursor: i64,
+
+    pub file_id: Uuid,
+
+    pub action: Action,
+
+    pub created_at: i64,
+
+    /// Current state of the file, empty when the file was deleted
+    /// or moved out of the directory
+    pub file: Option<AppFile>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangesResponse {
+    /// Cursor to send in the next request to get the changes after this page
+    pub cursor: i64,
+
+    /// There are more changes after the cursor, load them right away
+    pub has_more: bool,
+
+    /// Latest change of each of the files, ordered by the cursor
+    pub changes: Vec<Change>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalResponse {
+    /// Cursor to send in the next request to get the entries after this page
+    pub cursor: i64,
+
+    /// There are more entries after the cursor, load them right away
+    pub has_more: bool,
+
+    /// Every recorded activity of the user, ordered by the sequence number
+    pub entries: Vec<entity::activities::Model>,
+}
@@ -0,0 +1,84 @@
+//! Quick editing of the small files, the whole encrypted content of the file
+//! is read or replaced in a single request instead of chunk by chunk.
+use ::error::{AppResult, Error};
+use config::Config;
+use fs::MAX_CHUNK_SIZE_BYTES;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_file::AppFile;
+
+/// Largest file that can be edited in a single request, it is never more than a single chunk
+pub fn max_size(config: &Config) -> u64 {
+    config.server.quick_edit_max_size.min(MAX_CHUNK_SIZE_BYTES)
+}
+
+/// Make sure the whole content of the file fits into a single request, that is
+/// a finished file stored in a single chunk and not bigger than the limit.
+pub fn check(file: &AppFile, max_size: u64) -> AppResult<()> {
+    if !file.is_file() || file.finished_upload_at.is_none() || file.chunks != Some(1) {
+        return Err(Error::BadRequest("file_not_editable".to_string()));
+    }
+
+    if file.size.unwrap_or(0) as u64 > max_size {
+        return Err(Error::BadRequest("file_too_large".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Replace the content of the file, the body of the request is the new encrypted content
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WriteContent {
+    /// Size of the new content before it was encrypted
+    pub size: Option<i64>,
+    /// Hash of the new content, so the sync clients can compare it with the local copy
+    pub sha256: Option<String>,
+    /// Version of the file the client has edited, the write is refused
+    /// if the file was changed in the meantime
+    pub version: Option<i64>,
+}
+
+impl Validation for WriteContent {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            Rule::new("size", |obj: &WriteContent, error| match obj.size {
+                Some(v) if v <= 0 => error.add("min:1"),
+                Some(_) => {}
+                None => error.add("required"),
+            }),
+            Rule::new("version", |obj: &WriteContent, error| {
+                if obj.version.is_none() {
+                    error.add("required")
+                }
+            }),
+            Rule::new("sha256", |obj: &WriteContent, error| {
+                if let Some(v) = &obj.sha256 {
+                    if v.len() != 64 || !v.chars().all(|c| c.is_ascii_hexdigit()) {
+                        error.add("invalid_sha256")
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_lowercase!(sha256)]
+    }
+}
+
+impl WriteContent {
+    pub fn into_tuple(self) -> AppResult<(i64, Option<String>, i64)> {
+        let data = self.validate()?;
+
+        Ok((data.size.unwrap(), data.sha256, data.version.unwrap()))
+    }
+}
+
+/// File after its content was replaced, with the version it has now
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Content {
+    #[serde(flatten)]
+    pub file: AppFile,
+    pub version: i64,
+}
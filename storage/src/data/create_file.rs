@@ -12,8 +12,13 @@ use entity::{files::ActiveModel as ActiveModelFile, option_string_to_uuid, Activ
 use serde::{Deserialize, Serialize};
 use validr::*;
 
+use crate::sniff::{self, MAX_MAGIC_BYTES};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CreateFile {
+    /// Id of the file generated by the client, so the client can know the id before
+    /// the file is created and safely repeat the request if it never got the response
+    pub id: Option<Uuid>,
     /// File key encrypted with users RSA key
     pub encrypted_key: Option<String>,
     /// Name of the file hashed so we can guard
@@ -26,8 +31,13 @@ pub struct CreateFile {
     /// Tokens by which this file will be searchable broken down
     /// into tokens using the tokenizing methods
     pub search_tokens_hashed: Option<Vec<String>>,
-    /// Mime type of the file or "dir" for directory
+    /// Mime type of the file or "dir" for directory, when it is empty or generic
+    /// the type is sniffed from the `magic`, see [crate::sniff]
     pub mime: Option<String>,
+    /// First bytes of the plain content in hex, up to [MAX_MAGIC_BYTES],
+    /// so the type of the file can be told from them when the client can't
+    #[serde(default)]
+    pub magic: Option<String>,
     /// Total size of the file
     pub size: Option<i64>,
     /// Total number of chunks, no limitations, frontend can decide
@@ -36,6 +46,29 @@ pub struct CreateFile {
     pub file_id: Option<String>,
     /// Date of the file creation from the disk, if not provided we set it to now
     pub file_modified_at: Option<String>,
+    /// Hash of the file content, so the sync clients can compare it with the local copy
+    pub sha256: Option<String>,
+    /// Name hash of the conflicted copy, used when a file with the same name
+    /// and different content already exists in the directory
+    pub conflict_name_hash: Option<String>,
+    /// Name of the conflicted copy encrypted with the AES file key
+    pub encrypted_conflict_name: Option<String>,
+    /// Name of the device uploading the file, stored with the conflicted copy
+    pub device: Option<String>,
+    /// Version of the scheme the client encrypted the file with,
+    /// files are created with the current version when not provided
+    pub crypto_version: Option<i32>,
+    /// File key encrypted with the organization recovery key,
+    /// required when the key escrow is enabled by the admin
+    pub escrow_key: Option<String>,
+}
+
+/// Name the client wants to give to the file if it conflicts with an existing one
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub name_hash: String,
+    pub encrypted_name: String,
+    pub device: Option<String>,
 }
 
 impl Validation for CreateFile {
@@ -45,6 +78,18 @@ impl Validation for CreateFile {
             rule_required!(name_hash),
             rule_required!(encrypted_name),
             rule_required!(mime),
+            Rule::new("id", |obj: &CreateFile, error| {
+                if obj.id.map(|id| id.is_nil()).unwrap_or(false) {
+                    error.add("invalid_id")
+                }
+            }),
+            Rule::new("file_id", |obj: &CreateFile, error| {
+                if let Some(v) = &obj.file_id {
+                    if Uuid::parse_str(v).is_err() {
+                        error.add("invalid_uuid")
+                    }
+                }
+            }),
             Rule::new("size", |obj: &CreateFile, error| {
                 let dir_mime = Some("dir".to_string());
 
@@ -103,6 +148,51 @@ impl Validation for CreateFile {
                     error.add("required")
                 }
             }),
+            Rule::new("sha256", |obj: &CreateFile, error| {
+                if let Some(v) = &obj.sha256 {
+                    if v.len() != 64 || !v.chars().all(|c| c.is_ascii_hexdigit()) {
+                        error.add("invalid_sha256")
+                    }
+                }
+            }),
+            Rule::new("conflict_name_hash", |obj: &CreateFile, error| {
+                if obj.conflict_name_hash.is_none() && obj.encrypted_conflict_name.is_none() {
+                    return;
+                }
+
+                if obj.mime.as_deref() == Some("dir") {
+                    return error.add("not_for_dir");
+                }
+
+                if obj.conflict_name_hash.is_none() || obj.encrypted_conflict_name.is_none() {
+                    return error.add("incomplete_conflict_name");
+                }
+
+                if obj.sha256.is_none() {
+                    error.add("sha256_required")
+                }
+            }),
+            Rule::new("device", |obj: &CreateFile, error| {
+                if let Some(v) = &obj.device {
+                    if v.len() > 255 {
+                        error.add("max:255")
+                    }
+                }
+            }),
+            Rule::new("crypto_version", |obj: &CreateFile, error| {
+                if let Some(v) = obj.crypto_version {
+                    if !cryptfns::scheme::is_supported(v) {
+                        error.add("unsupported_crypto_version")
+                    }
+                }
+            }),
+            Rule::new("magic", |obj: &CreateFile, error| {
+                if let Some(v) = &obj.magic {
+                    if v.len() > MAX_MAGIC_BYTES * 2 || cryptfns::hex::decode(v).is_err() {
+                        error.add("invalid_magic")
+                    }
+                }
+            }),
             Rule::new("file_modified_at", |obj: &CreateFile, error| {
                 if let Some(v) = &obj.file_modified_at {
                     if util::datetime::parse_into_naive_datetime(v, Some("file_modified_at"))
@@ -116,13 +206,38 @@ impl Validation for CreateFile {
     }
 
     fn modifiers(&self) -> Vec<Modifier<Self>> {
-        vec![]
+        vec![
+            modifier_lowercase!(sha256),
+            modifier_trim!(device),
+            Modifier::new("mime", |obj: &mut Self| obj.sniff_mime()),
+        ]
     }
 }
 
 pub type CreateFileData = (ActiveModelFile, String, Vec<String>, i64, Option<Uuid>);
 
 impl CreateFile {
+    /// Normalize the mime, or sniff it from the magic when it is empty or generic
+    pub fn sniff_mime(&mut self) {
+        let magic = self
+            .magic
+            .as_deref()
+            .and_then(|magic| cryptfns::hex::decode(magic).ok());
+
+        if let Some(mime) = self.mime.as_deref() {
+            self.mime = Some(sniff::resolve(mime, magic.as_deref()));
+        }
+    }
+
+    /// Name for the conflicted copy of the file, if the client sent one
+    pub fn conflict(&self) -> Option<Conflict> {
+        Some(Conflict {
+            name_hash: self.conflict_name_hash.clone()?,
+            encrypted_name: self.encrypted_conflict_name.clone()?,
+            device: self.device.clone().filter(|d| !d.is_empty()),
+        })
+    }
+
     pub fn into_active_model(self) -> AppResult<CreateFileData> {
         let data = self.validate()?;
         let now = Utc::now().naive_utc();
@@ -137,7 +252,7 @@ impl CreateFile {
 
         Ok((
             ActiveModelFile {
-                id: ActiveValue::Set(entity::Uuid::new_v4()),
+                id: ActiveValue::Set(data.id.unwrap_or_else(entity::Uuid::new_v4)),
                 name_hash: ActiveValue::Set(data.name_hash.unwrap()),
                 encrypted_name: ActiveValue::Set(data.encrypted_name.unwrap()),
                 encrypted_thumbnail: ActiveValue::Set(data.encrypted_thumbnail),
@@ -157,6 +272,15 @@ impl CreateFile {
                 ),
                 created_at: ActiveValue::Set(now.timestamp()),
                 finished_upload_at: ActiveValue::Set(None),
+                sha256: ActiveValue::Set(data.sha256),
+                conflict_of: ActiveValue::Set(None),
+                conflict_device: ActiveValue::Set(None),
+                legal_hold: ActiveValue::Set(false),
+                crypto_version: ActiveValue::Set(
+                    data.crypto_version.unwrap_or(cryptfns::scheme::CURRENT),
+                ),
+                encrypted_payload: ActiveValue::Set(None),
+                deleted_at: ActiveValue::Set(None),
             },
             data.encrypted_key.unwrap(),
             data.search_tokens_hashed.unwrap_or_default(),
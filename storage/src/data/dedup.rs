@@ -0,0 +1,73 @@
+//! Hash first upload, the client asks if the content it is about to upload is
+//! already stored before sending any of the chunks. If it is, the new file is
+//! created from the stored content and the upload is skipped entirely.
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::create_file::CreateFile;
+
+/// Content the client is about to upload
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Lookup {
+    /// Hash of the file content, same as the one sent when creating the file
+    pub sha256: Option<String>,
+    /// Total size of the file
+    pub size: Option<i64>,
+}
+
+impl Validation for Lookup {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(sha256),
+            rule_required!(size),
+            Rule::new("sha256", |obj: &Lookup, error| {
+                if let Some(v) = &obj.sha256 {
+                    if v.len() != 64 || !v.chars().all(|c| c.is_ascii_hexdigit()) {
+                        error.add("invalid_sha256")
+                    }
+                }
+            }),
+            Rule::new("size", |obj: &Lookup, error| {
+                if obj.size.map(|size| size < 0) == Some(true) {
+                    error.add("min:0")
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_lowercase!(sha256)]
+    }
+}
+
+impl Lookup {
+    pub fn into_values(self) -> AppResult<(String, i64)> {
+        let data = self.validate()?;
+
+        Ok((data.sha256.unwrap(), data.size.unwrap()))
+    }
+}
+
+/// Stored file with the same content the upload can be skipped for
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DedupMatch {
+    pub id: Uuid,
+    pub chunks: Option<i64>,
+    /// File key encrypted with the RSA key of the user, the new file has to be
+    /// encrypted with the same key. Not set for the content of other users,
+    /// their clients derive the key from the content.
+    pub encrypted_key: Option<String>,
+    /// Version of the scheme the content is encrypted with, the new file keeps it
+    pub crypto_version: i32,
+}
+
+/// Create the file from the content that is already stored
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateDeduplicated {
+    /// Id of the stored file returned by the lookup
+    pub source_id: Uuid,
+    #[serde(flatten)]
+    pub file: CreateFile,
+}
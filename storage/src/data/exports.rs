@@ -0,0 +1,122 @@
+//! Export of all the data of the user, so they can take it with them.
+//!
+//! The archive is an uncompressed tar with `metadata.json` describing everything the user
+//! has on the platform, and optionally the encrypted chunks of their files under
+//! `files/<file_id>/<chunk>`. The chunks stay encrypted, the file keys in the metadata
+//! are encrypted with the key pair of the user, so the private key is needed to read them.
+use ::error::AppResult;
+use entity::{
+    activities, exports, file_requests, files, group_members, links, user_files, users, Uuid,
+};
+use fs::prelude::*;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// How long the finished archive can be downloaded
+pub const EXPORT_EXPIRES_IN_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Request the export of the user data
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CreateExport {
+    /// Put the encrypted content of the files into the archive next to the metadata
+    pub include_files: Option<bool>,
+}
+
+impl Validation for CreateExport {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![]
+    }
+}
+
+impl CreateExport {
+    pub fn into_value(self) -> AppResult<bool> {
+        let data = self.validate()?;
+
+        Ok(data.include_files.unwrap_or(false))
+    }
+}
+
+/// Archive of the export in the storage provider, it is stored
+/// in chunks the same way the files are.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportArchive {
+    pub id: Uuid,
+    pub created_at: i64,
+}
+
+impl From<&exports::Model> for ExportArchive {
+    fn from(export: &exports::Model) -> Self {
+        Self {
+            id: export.id,
+            created_at: export.created_at,
+        }
+    }
+}
+
+impl IntoFilename for ExportArchive {
+    fn filename(&self) -> AppResult<Filename> {
+        Ok(Filename::new(self.id).with_timestamp(self.created_at))
+    }
+}
+
+/// Everything the platform knows about the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportMetadata {
+    pub exported_at: i64,
+    pub user: ExportUser,
+    /// Files and folders the user owns or has shared with them
+    pub files: Vec<ExportFile>,
+    /// Shares of the files of the user with other users
+    pub shares: Vec<user_files::Model>,
+    pub links: Vec<links::Model>,
+    pub activities: Vec<activities::Model>,
+    pub file_requests: Vec<file_requests::Model>,
+    pub groups: Vec<group_members::Model>,
+}
+
+/// Account of the user, without the password and the two factor secret
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportUser {
+    pub id: Uuid,
+    pub email: String,
+    pub role: Option<String>,
+    pub quota: Option<i64>,
+    pub pubkey: String,
+    pub fingerprint: String,
+    /// Private key of the user encrypted with their passphrase
+    pub encrypted_private_key: Option<String>,
+    pub email_verified_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub crypto_version: i32,
+}
+
+impl From<users::Model> for ExportUser {
+    fn from(user: users::Model) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            role: user.role,
+            quota: user.quota,
+            pubkey: user.pubkey,
+            fingerprint: user.fingerprint,
+            encrypted_private_key: user.encrypted_private_key,
+            email_verified_at: user.email_verified_at,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            crypto_version: user.crypto_version,
+        }
+    }
+}
+
+/// File with the key the user has for it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportFile {
+    #[serde(flatten)]
+    pub file: files::Model,
+    pub is_owner: bool,
+    /// File key encrypted with the public key of the user
+    pub encrypted_key: String,
+    pub starts_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
@@ -0,0 +1,229 @@
+//! Export of a folder of the user to an S3 bucket or a WebDAV server,
+//! to archive the files outside of Hoodik or to take them along when leaving.
+//!
+//! In the encrypted mode the server copies the encrypted chunks as they are, next to the
+//! metadata with the encrypted keys and names, the export stays readable only with the
+//! private key of the user. In the decrypted mode the client decrypts the keys and the
+//! names of the files and sends them with the request, the server then decrypts the
+//! content on the way. Keys and credentials are kept only until the export is over.
+use std::collections::HashMap;
+
+use ::error::{AppResult, Error};
+use entity::{
+    external_exports::{Mode, Target},
+    Uuid,
+};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// How many of the failed files are listed with the export, the rest are only counted
+pub const MAX_EXTERNAL_EXPORT_ERRORS: usize = 100;
+
+/// Region used for signing the S3 requests when none is given, most S3 compatible
+/// providers don't care about the region and accept this one.
+pub const DEFAULT_S3_REGION: &str = "us-east-1";
+
+/// Start the export of the folder to an external provider
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateExternalExport {
+    /// Provider the files are exported to
+    pub target: Option<Target>,
+    /// URL of the S3 bucket in the path style (`https://s3.example.com/bucket/prefix`),
+    /// or the URL of the WebDAV folder
+    pub location: Option<String>,
+    /// S3 region, [DEFAULT_S3_REGION] if not set
+    pub region: Option<String>,
+    /// S3 access key id
+    pub access_key_id: Option<String>,
+    /// S3 secret access key
+    pub secret_access_key: Option<String>,
+    /// WebDAV username
+    pub username: Option<String>,
+    /// WebDAV password, for Nextcloud it should be the app password
+    pub password: Option<String>,
+    /// Encrypted copy or the decrypted files, encrypted if not set
+    pub mode: Option<Mode>,
+    /// Decrypted keys and names of the files, required in the decrypted mode
+    pub keys: Option<Vec<FileKey>>,
+    /// Folder that is exported, all the files of the user if not set
+    pub file_id: Option<Uuid>,
+}
+
+impl Validation for CreateExternalExport {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(target),
+            rule_required!(location),
+            Rule::new("location", |obj: &CreateExternalExport, error| {
+                let url = match obj.location.as_deref().map(reqwest::Url::parse) {
+                    Some(Ok(url)) => url,
+                    Some(Err(_)) => return error.add("invalid_url"),
+                    None => return,
+                };
+
+                if !matches!(url.scheme(), "http" | "https") {
+                    return error.add("invalid_url");
+                }
+
+                // Path style URL has the name of the bucket as its first segment
+                if obj.target == Some(Target::S3) && url.path().trim_matches('/').is_empty() {
+                    error.add("bucket_required");
+                }
+            }),
+            Rule::new("access_key_id", |obj: &CreateExternalExport, error| {
+                if obj.target == Some(Target::S3) && is_blank(&obj.access_key_id) {
+                    error.add("required")
+                }
+            }),
+            Rule::new("secret_access_key", |obj: &CreateExternalExport, error| {
+                if obj.target == Some(Target::S3) && is_blank(&obj.secret_access_key) {
+                    error.add("required")
+                }
+            }),
+            Rule::new("keys", |obj: &CreateExternalExport, error| {
+                if obj.mode != Some(Mode::Decrypted) {
+                    return;
+                }
+
+                let keys = match obj.keys.as_ref() {
+                    Some(keys) if !keys.is_empty() => keys,
+                    _ => return error.add("required"),
+                };
+
+                let valid = keys.iter().all(|key| {
+                    !key.name.is_empty()
+                        && !key.name.contains('/')
+                        && cryptfns::hex::decode(&key.key).is_ok()
+                });
+
+                if !valid {
+                    error.add("invalid_key")
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![
+            modifier_trim!(location),
+            modifier_trim!(region),
+            modifier_trim!(access_key_id),
+            modifier_trim!(username),
+        ]
+    }
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().unwrap_or_default().is_empty()
+}
+
+impl CreateExternalExport {
+    /// Target, its location and the credentials, the mode with the keys of
+    /// the files and the folder that is exported
+    pub fn into_tuple(
+        self,
+    ) -> AppResult<(
+        Target,
+        String,
+        Credentials,
+        Mode,
+        Option<Keys>,
+        Option<Uuid>,
+    )> {
+        let data = self.validate()?;
+        let mode = data.mode.unwrap_or(Mode::Encrypted);
+
+        let keys = match mode {
+            Mode::Encrypted => None,
+            Mode::Decrypted => Some(
+                data.keys
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|key| (key.id, key))
+                    .collect(),
+            ),
+        };
+
+        let credentials = Credentials {
+            region: data.region.filter(|region| !region.is_empty()),
+            access_key_id: data.access_key_id,
+            secret_access_key: data.secret_access_key,
+            username: data.username,
+            password: data.password,
+        };
+
+        Ok((
+            data.target.unwrap(),
+            data.location.unwrap(),
+            credentials,
+            mode,
+            keys,
+            data.file_id,
+        ))
+    }
+}
+
+/// Key and the name of the file decrypted by the client
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileKey {
+    pub id: Uuid,
+    /// AES key of the file in hex
+    pub key: String,
+    /// Name of the file or the folder
+    pub name: String,
+}
+
+impl FileKey {
+    pub fn key(&self) -> AppResult<Vec<u8>> {
+        cryptfns::hex::decode(&self.key).map_err(Error::from)
+    }
+}
+
+/// Decrypted keys and names by the id of the file
+pub type Keys = HashMap<Uuid, FileKey>;
+
+/// Credentials for the target, kept with the export while it is running
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Credentials {
+    pub region: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// File that could not be exported
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExportError {
+    /// Path of the file in the target
+    pub path: String,
+    pub error: String,
+}
+
+/// Progress of the running export
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub exported_files: i64,
+    pub exported_bytes: i64,
+    pub failed_files: i64,
+    pub errors: Vec<ExportError>,
+}
+
+impl Progress {
+    pub fn exported(&mut self, size: i64) {
+        self.exported_files += 1;
+        self.exported_bytes += size;
+    }
+
+    /// Count the failed file, only the first [MAX_EXTERNAL_EXPORT_ERRORS] are listed
+    pub fn failed(&mut self, path: &str, error: String) {
+        self.failed_files += 1;
+
+        if self.errors.len() < MAX_EXTERNAL_EXPORT_ERRORS {
+            self.errors.push(ExportError {
+                path: path.to_string(),
+                error,
+            });
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! File requests, links through which anyone can upload files into a folder of the owner.
+//!
+//! The uploader encrypts the file key with the public key of the owner, so the received
+//! files are readable only by the owner, the same way as the files they upload themselves.
+use ::error::AppResult;
+use chrono::Utc;
+use entity::{file_requests, Uuid};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Create a file request for the folder
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateFileRequest {
+    /// Folder of the user the files are uploaded into
+    pub file_id: Option<Uuid>,
+    /// Timestamp after which the request stops accepting uploads, open until closed if not set
+    pub deadline: Option<i64>,
+}
+
+impl Validation for CreateFileRequest {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(file_id),
+            Rule::new("deadline", |obj: &CreateFileRequest, error| {
+                if let Some(deadline) = obj.deadline {
+                    if deadline <= Utc::now().timestamp() {
+                        error.add("deadline_in_the_past")
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl CreateFileRequest {
+    pub fn into_value(self) -> AppResult<(Uuid, Option<i64>)> {
+        let data = self.validate()?;
+
+        Ok((data.file_id.unwrap(), data.deadline))
+    }
+}
+
+/// What was received through the file request
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Summary {
+    /// Number of the received files
+    pub files: i64,
+    /// Number of the received files that were fully uploaded
+    pub finished: i64,
+    /// Total size of the received files
+    pub size: i64,
+}
+
+/// File request as the owner sees it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileRequestResponse {
+    #[serde(flatten)]
+    pub request: file_requests::Model,
+    /// Request is still accepting uploads
+    pub open: bool,
+    pub received: Summary,
+}
+
+/// File request as the uploader sees it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublicFileRequest {
+    pub id: Uuid,
+    /// Public key of the owner, the uploader encrypts the file keys with it
+    pub pubkey: String,
+    pub deadline: Option<i64>,
+    /// Request is still accepting uploads
+    pub open: bool,
+}
@@ -0,0 +1,173 @@
+//! Folder templates, folder structures the user saved to create them again elsewhere.
+//!
+//! Names of the folders are encrypted with their own keys, so the server cannot build
+//! the folders from the template by itself. The template is stored encrypted as the
+//! client sent it, and to use it the client sends the folders of the template with
+//! the new keys, all of them are created in a single transaction.
+use std::collections::HashSet;
+
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::create_file::{CreateFile, CreateFileData};
+
+/// Maximum length of the encrypted structure of the template
+pub const MAX_TEMPLATE_TREE_LENGTH: usize = 256 * 1024;
+
+/// Maximum number of the folders created from the template at once
+pub const MAX_TEMPLATE_FOLDERS: usize = 1000;
+
+/// Save the folder structure as a template
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SaveTemplate {
+    /// Name of the template encrypted by the client
+    pub encrypted_name: Option<String>,
+    /// Names and nesting of the folders encrypted by the client
+    pub encrypted_tree: Option<String>,
+}
+
+impl Validation for SaveTemplate {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(encrypted_name),
+            rule_required!(encrypted_tree),
+            Rule::new("encrypted_tree", |obj: &SaveTemplate, error| {
+                if let Some(v) = &obj.encrypted_tree {
+                    if v.len() > MAX_TEMPLATE_TREE_LENGTH {
+                        error.add(format!("max:{}", MAX_TEMPLATE_TREE_LENGTH).as_str())
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl SaveTemplate {
+    pub fn into_tuple(self) -> AppResult<(String, String)> {
+        let data = self.validate()?;
+
+        Ok((data.encrypted_name.unwrap(), data.encrypted_tree.unwrap()))
+    }
+}
+
+/// Folder of the template that is created
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TemplateFolder {
+    /// Id of the new folder generated by the client, so the other folders can be put in it
+    pub id: Option<Uuid>,
+    /// Folder from the same request the folder is created in,
+    /// the folder the template is used in if not set
+    pub parent_id: Option<Uuid>,
+    /// Folder key encrypted with users RSA key
+    pub encrypted_key: Option<String>,
+    /// Name of the folder hashed so we can guard against duplicates
+    pub name_hash: Option<String>,
+    /// Folder name encrypted with the AES folder key
+    pub encrypted_name: Option<String>,
+    /// Tokens by which this folder will be searchable
+    pub search_tokens_hashed: Option<Vec<String>>,
+    /// Folder key encrypted with the organization recovery key,
+    /// required when the key escrow is enabled by the admin
+    pub escrow_key: Option<String>,
+}
+
+/// Create the folders of the template in the folder of the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UseTemplate {
+    /// Folder the template is created in, the root if not set
+    pub file_id: Option<Uuid>,
+    /// Folders of the template, each one after the folder it is in
+    pub folders: Option<Vec<TemplateFolder>>,
+}
+
+impl Validation for UseTemplate {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("folders", |obj: &UseTemplate, error| {
+            let folders = match obj.folders.as_ref() {
+                Some(folders) if !folders.is_empty() => folders,
+                _ => return error.add("required"),
+            };
+
+            if folders.len() > MAX_TEMPLATE_FOLDERS {
+                return error.add(format!("max:{}", MAX_TEMPLATE_FOLDERS).as_str());
+            }
+
+            let mut ids = HashSet::new();
+            let mut names = HashSet::new();
+
+            for folder in folders {
+                let id = match folder.id {
+                    Some(id) if !id.is_nil() => id,
+                    _ => return error.add("id_required"),
+                };
+
+                if folder.encrypted_key.is_none()
+                    || folder.name_hash.is_none()
+                    || folder.encrypted_name.is_none()
+                {
+                    return error.add("incomplete_folder");
+                }
+
+                if let Some(parent_id) = folder.parent_id {
+                    if !ids.contains(&parent_id) {
+                        return error.add("parent_not_before_folder");
+                    }
+                }
+
+                if !names.insert((folder.parent_id, folder.name_hash.clone())) {
+                    return error.add("duplicate_name");
+                }
+
+                if !ids.insert(id) {
+                    return error.add("duplicate_id");
+                }
+            }
+        })]
+    }
+}
+
+impl UseTemplate {
+    /// Folder the template is used in and the data to create each of the folders
+    /// with, the escrow keys of the folders are returned alongside.
+    pub fn into_value(self) -> AppResult<(Option<Uuid>, Vec<(CreateFileData, Option<String>)>)> {
+        let data = self.validate()?;
+        let target = data.file_id;
+
+        let folders = data
+            .folders
+            .unwrap_or_default()
+            .into_iter()
+            .map(|folder| {
+                let escrow_key = folder.escrow_key.clone();
+                let parent_id = folder.parent_id.or(target);
+
+                let create_file = CreateFile {
+                    id: folder.id,
+                    encrypted_key: folder.encrypted_key,
+                    name_hash: folder.name_hash,
+                    encrypted_name: folder.encrypted_name,
+                    encrypted_thumbnail: None,
+                    search_tokens_hashed: folder.search_tokens_hashed,
+                    mime: Some("dir".to_string()),
+                    size: None,
+                    chunks: None,
+                    file_id: parent_id.map(|id| id.to_string()),
+                    file_modified_at: None,
+                    sha256: None,
+                    conflict_name_hash: None,
+                    encrypted_conflict_name: None,
+                    device: None,
+                    crypto_version: None,
+                    escrow_key: None,
+                    magic: None,
+                };
+
+                Ok((create_file.into_active_model()?, escrow_key))
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        Ok((target, folders))
+    }
+}
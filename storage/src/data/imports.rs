@@ -0,0 +1,148 @@
+//! Import of the files from another service, a WebDAV server (Nextcloud, ownCloud)
+//! or Dropbox, into a folder of the user.
+//!
+//! The server reads the files from the source, so it sees their content while they are
+//! imported. Every imported file gets its own new key, the key is encrypted with the
+//! public key of the user the same way the clients do it, so once imported the files are
+//! just like the ones the user uploaded. Credentials for the source are kept only
+//! until the import is over.
+use ::error::AppResult;
+use entity::{imports::Source, Uuid};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// How many of the failed files are listed with the import, the rest are only counted
+pub const MAX_IMPORT_ERRORS: usize = 100;
+
+/// Start the import of the files from another service
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateImport {
+    /// Service the files are imported from
+    pub source: Option<Source>,
+    /// URL of the WebDAV folder, or the path of the Dropbox folder, the whole
+    /// Dropbox is imported if not set
+    pub location: Option<String>,
+    /// WebDAV username
+    pub username: Option<String>,
+    /// WebDAV password, for Nextcloud it should be the app password
+    pub password: Option<String>,
+    /// Dropbox access token
+    pub token: Option<String>,
+    /// Folder of the user the files are imported into, the root if not set
+    pub file_id: Option<Uuid>,
+}
+
+impl Validation for CreateImport {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(source),
+            Rule::new("location", |obj: &CreateImport, error| {
+                if obj.source != Some(Source::Webdav) {
+                    return;
+                }
+
+                let url = match obj.location.as_deref() {
+                    Some(url) => url,
+                    None => return error.add("required"),
+                };
+
+                match reqwest::Url::parse(url) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                    _ => error.add("invalid_url"),
+                }
+            }),
+            Rule::new("token", |obj: &CreateImport, error| {
+                if obj.source == Some(Source::Dropbox)
+                    && obj.token.as_deref().unwrap_or_default().is_empty()
+                {
+                    error.add("required")
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(location), modifier_trim!(username)]
+    }
+}
+
+impl CreateImport {
+    /// Source, its location and the credentials, and the folder the files are imported into
+    pub fn into_tuple(self) -> AppResult<(Source, String, Credentials, Option<Uuid>)> {
+        let data = self.validate()?;
+        let source = data.source.unwrap();
+
+        let location = match source {
+            Source::Webdav => data.location.unwrap_or_default(),
+            Source::Dropbox => dropbox_path(data.location.as_deref().unwrap_or_default()),
+        };
+
+        let credentials = Credentials {
+            username: data.username,
+            password: data.password,
+            token: data.token,
+        };
+
+        Ok((source, location, credentials, data.file_id))
+    }
+}
+
+/// Dropbox API refers to the root folder with the empty path,
+/// every other path starts with a slash and has none at the end
+fn dropbox_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+
+    match path.is_empty() {
+        true => String::new(),
+        false => format!("/{}", path),
+    }
+}
+
+/// Credentials for the source, kept with the import while it is running
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+}
+
+/// File that could not be imported
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportError {
+    /// Path of the file in the source
+    pub path: String,
+    pub error: String,
+}
+
+/// Progress of the running import
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub imported_files: i64,
+    pub imported_bytes: i64,
+    pub skipped_files: i64,
+    pub failed_files: i64,
+    pub errors: Vec<ImportError>,
+}
+
+impl Progress {
+    pub fn imported(&mut self, size: i64) {
+        self.imported_files += 1;
+        self.imported_bytes += size;
+    }
+
+    pub fn skipped(&mut self) {
+        self.skipped_files += 1;
+    }
+
+    /// Count the failed file, only the first [MAX_IMPORT_ERRORS] are listed
+    pub fn failed(&mut self, path: &str, error: String) {
+        self.failed_files += 1;
+
+        if self.errors.len() < MAX_IMPORT_ERRORS {
+            self.errors.push(ImportError {
+                path: path.to_string(),
+                error,
+            });
+        }
+    }
+}
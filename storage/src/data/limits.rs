@@ -0,0 +1,156 @@
+//! Limits of the encrypted metadata the clients send along with the files.
+//!
+//! Server can't read the encrypted names, keys and thumbnails, but it can refuse the ones
+//! that are too long or not in the shape the clients encode them in, so a malicious client
+//! can't stuff megabytes of anything into the metadata columns. The caps are configured
+//! in the [config::server::ServerConfig], name hashes always have the length of sha256.
+use ::error::{AppResult, Error};
+use config::server::ServerConfig;
+use validr::error::{ValidationError, ValidationErrors};
+
+use super::{create_file::CreateFile, rename::Rename, virtual_file::CreateVirtualFile};
+
+/// Name hash is the hex encoded sha256 of the name
+pub const NAME_HASH_LENGTH: usize = 64;
+
+/// Longest single search token, hex encoded sha256 with its weight
+pub const SEARCH_TOKEN_MAX_LENGTH: usize = 128;
+
+/// Collects the problems of all the checked fields, so the client gets all of them at once
+pub struct Limits<'config> {
+    config: &'config ServerConfig,
+    errors: ValidationErrors,
+}
+
+impl<'config> Limits<'config> {
+    pub fn new(config: &'config ServerConfig) -> Self {
+        Self {
+            config,
+            errors: ValidationErrors::new(),
+        }
+    }
+
+    /// Hex encoded sha256 of the name
+    pub fn name_hash(mut self, field: &str, value: Option<&str>) -> Self {
+        if let Some(value) = value {
+            if value.len() != NAME_HASH_LENGTH || !is_hex(value) {
+                self.add(field, "invalid_name_hash");
+            }
+        }
+
+        self
+    }
+
+    /// Name encrypted with the file key, hex encoded
+    pub fn encrypted_name(self, field: &str, value: Option<&str>) -> Self {
+        let max = self.config.metadata_max_length;
+
+        self.hex(field, value, max)
+    }
+
+    /// Thumbnail encrypted with the file key, hex encoded
+    pub fn encrypted_thumbnail(self, field: &str, value: Option<&str>) -> Self {
+        let max = self.config.thumbnail_max_length;
+
+        self.hex(field, value, max)
+    }
+
+    /// File key encrypted with the RSA key, the clients encode it with base64 or hex
+    pub fn encrypted_key(mut self, field: &str, value: Option<&str>) -> Self {
+        if let Some(value) = value {
+            if value.len() > self.config.metadata_max_length {
+                let error = format!("max_length:{}", self.config.metadata_max_length);
+                self.add(field, &error);
+            } else if !is_hex(value) && cryptfns::base64::decode(value).is_err() {
+                self.add(field, "invalid_encoding");
+            }
+        }
+
+        self
+    }
+
+    /// Hashed tokens the file is searched by
+    pub fn search_tokens(mut self, value: Option<&[String]>) -> Self {
+        if let Some(tokens) = value {
+            if tokens.len() > self.config.search_tokens_max {
+                let error = format!("max:{}", self.config.search_tokens_max);
+                self.add("search_tokens_hashed", &error);
+            }
+
+            if tokens.iter().any(|t| t.len() > SEARCH_TOKEN_MAX_LENGTH) {
+                let error = format!("max_length:{}", SEARCH_TOKEN_MAX_LENGTH);
+                self.add("search_tokens_hashed", &error);
+            }
+        }
+
+        self
+    }
+
+    pub fn check(self) -> AppResult<()> {
+        if self.errors.has_errors() {
+            return Err(Error::Validation(self.errors));
+        }
+
+        Ok(())
+    }
+
+    fn hex(mut self, field: &str, value: Option<&str>, max: usize) -> Self {
+        if let Some(value) = value {
+            if value.len() > max {
+                self.add(field, &format!("max_length:{}", max));
+            } else if !is_hex(value) {
+                self.add(field, "invalid_hex");
+            }
+        }
+
+        self
+    }
+
+    fn add(&mut self, field: &str, message: &str) {
+        let mut error = ValidationError::new();
+        error.set_field_name(field);
+        error.add(message);
+        self.errors.add(error);
+    }
+}
+
+/// Check the metadata of the file that is being created
+pub fn check_file(config: &ServerConfig, file: &CreateFile) -> AppResult<()> {
+    Limits::new(config)
+        .name_hash("name_hash", file.name_hash.as_deref())
+        .name_hash("conflict_name_hash", file.conflict_name_hash.as_deref())
+        .encrypted_name("encrypted_name", file.encrypted_name.as_deref())
+        .encrypted_name(
+            "encrypted_conflict_name",
+            file.encrypted_conflict_name.as_deref(),
+        )
+        .encrypted_thumbnail("encrypted_thumbnail", file.encrypted_thumbnail.as_deref())
+        .encrypted_key("encrypted_key", file.encrypted_key.as_deref())
+        .encrypted_key("escrow_key", file.escrow_key.as_deref())
+        .search_tokens(file.search_tokens_hashed.as_deref())
+        .check()
+}
+
+/// Check the new name of the file
+pub fn check_rename(config: &ServerConfig, rename: &Rename) -> AppResult<()> {
+    Limits::new(config)
+        .name_hash("name_hash", rename.name_hash.as_deref())
+        .encrypted_name("encrypted_name", rename.encrypted_name.as_deref())
+        .search_tokens(rename.search_tokens_hashed.as_deref())
+        .check()
+}
+
+/// Check the metadata of the virtual file, its payload has its own limit
+pub fn check_virtual_file(config: &ServerConfig, file: &CreateVirtualFile) -> AppResult<()> {
+    Limits::new(config)
+        .name_hash("name_hash", file.name_hash.as_deref())
+        .encrypted_name("encrypted_name", file.encrypted_name.as_deref())
+        .encrypted_key("encrypted_key", file.encrypted_key.as_deref())
+        .encrypted_key("escrow_key", file.escrow_key.as_deref())
+        .search_tokens(file.search_tokens_hashed.as_deref())
+        .check()
+}
+
+fn is_hex(value: &str) -> bool {
+    value.len() % 2 == 0 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -0,0 +1,106 @@
+//! Manifest of the folders the sync client wants to keep a local replica of
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// List of folder ids to build the manifest for, with everything inside them
+    pub ids: Option<Vec<Uuid>>,
+}
+
+impl Validation for Manifest {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("ids", |obj: &Manifest, error| {
+            if let Some(ids) = obj.ids.as_ref() {
+                if ids.is_empty() {
+                    error.add("required")
+                }
+            } else {
+                error.add("required")
+            }
+        })]
+    }
+}
+
+impl Manifest {
+    pub fn into_value(self) -> AppResult<Vec<Uuid>> {
+        let data = self.validate()?;
+
+        Ok(data.ids.unwrap_or_default())
+    }
+}
+
+/// Compact entry of the manifest, just enough to diff it against the local replica
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub id: Uuid,
+
+    /// Directory the file is in, empty for the root directory
+    pub parent: Option<Uuid>,
+
+    pub name_hash: String,
+
+    /// Size of the file, empty for directories
+    pub size: Option<i64>,
+
+    /// Hash of the file content if the client has sent it when creating the file
+    pub sha256: Option<String>,
+
+    /// Sequence number of the latest change of the file from the change journal,
+    /// the file changed since the last sync when this number is different.
+    pub version: i64,
+}
+
+/// Checksums of the file as it is stored, for the verification tools to check the
+/// downloaded chunks, or the whole downloaded file, before it is decrypted.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileManifest {
+    pub id: Uuid,
+
+    /// Name the whole encrypted file is downloaded under
+    pub filename: String,
+
+    /// Size of the file before it was encrypted
+    pub size: Option<i64>,
+
+    /// Hash of the encrypted content, all the chunks one after another
+    pub encrypted_sha256: String,
+
+    /// Hash of the file content if the client has sent it when creating the file,
+    /// it can only be checked after the file is decrypted.
+    pub sha256: Option<String>,
+
+    pub chunks: Vec<ChunkChecksum>,
+}
+
+/// Checksum of a single stored chunk of the file
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChunkChecksum {
+    pub chunk: i64,
+
+    /// Name the chunk is downloaded under with the `?chunk=` query
+    pub filename: String,
+
+    /// Size of the encrypted chunk in bytes
+    pub size: u64,
+
+    pub sha256: String,
+}
+
+impl FileManifest {
+    /// Manifest in the format of the `sha256sum` output, so the downloaded chunks and
+    /// the whole encrypted file can be checked with `sha256sum -c`.
+    pub fn sha256sum(&self) -> String {
+        self.chunks
+            .iter()
+            .map(|chunk| (chunk.sha256.as_str(), chunk.filename.as_str()))
+            .chain(std::iter::once((
+                self.encrypted_sha256.as_str(),
+                self.filename.as_str(),
+            )))
+            .map(|(hash, filename)| format!("{}  {}\n", hash, filename))
+            .collect()
+    }
+}
@@ -1,10 +1,40 @@
+pub mod access_keys;
 pub mod app_file;
+pub mod attributes;
+pub mod batch;
+pub mod bulk;
+pub mod changes;
+pub mod content;
 pub mod create_file;
+pub mod dedup;
 pub mod delete_many;
+pub mod exports;
+pub mod external_exports;
+pub mod file_requests;
+pub mod folder_templates;
+pub mod imports;
+pub mod limits;
+pub mod manifest;
 pub mod meta;
 pub mod move_many;
+pub mod name_hash;
+pub mod net_test;
+pub mod policy;
 pub mod query;
 pub mod rename;
 pub mod response;
+pub mod resume;
+pub mod rewrap;
+pub mod s3;
 pub mod search;
+pub mod shares;
+pub mod simple_upload;
+pub mod snapshot;
+pub mod socket;
+pub mod spaces;
 pub mod stats;
+pub mod thumbnail;
+pub mod transfer;
+pub mod tree;
+pub mod tus;
+pub mod virtual_file;
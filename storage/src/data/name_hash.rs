@@ -0,0 +1,77 @@
+//! Look up many files by their name hashes at once, sync clients check whether
+//! thousands of their local files exist in the folder before uploading them.
+//!
+//! Creating the file with the name that is already taken in the folder is refused
+//! or it becomes a conflicted copy, but the folders can still hold more files with
+//! the same name hash, the ones created before the check or by concurrent requests.
+//! In that case the oldest file is the one the name resolves to, and the others are
+//! reported as its collisions so the client can resolve them.
+use std::collections::HashSet;
+
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum number of name hashes looked up in one request
+pub const MAX_NAME_HASH_LOOKUP: usize = 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NameHashLookup {
+    /// Folder the files are looked up in, the root if not set
+    pub parent_id: Option<Uuid>,
+    /// Name hashes of the files
+    pub name_hashes: Option<Vec<String>>,
+}
+
+impl Validation for NameHashLookup {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new(
+            "name_hashes",
+            |obj: &NameHashLookup, error| match obj.name_hashes.as_ref() {
+                Some(hashes) if hashes.is_empty() => error.add("required"),
+                Some(hashes) if hashes.len() > MAX_NAME_HASH_LOOKUP => {
+                    error.add(format!("max:{}", MAX_NAME_HASH_LOOKUP).as_str())
+                }
+                Some(hashes) if hashes.iter().any(|hash| hash.is_empty()) => {
+                    error.add("empty_name_hash")
+                }
+                Some(_) => {}
+                None => error.add("required"),
+            },
+        )]
+    }
+}
+
+impl NameHashLookup {
+    /// Folder and the unique name hashes in the order they were sent
+    pub fn into_tuple(self) -> AppResult<(Option<Uuid>, Vec<String>)> {
+        let data = self.validate()?;
+        let mut seen = HashSet::new();
+
+        let name_hashes = data
+            .name_hashes
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|hash| seen.insert(hash.clone()))
+            .collect();
+
+        Ok((data.parent_id, name_hashes))
+    }
+}
+
+/// Result of the lookup for one name hash
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NameHashMatch {
+    pub name_hash: String,
+    /// File the name resolves to, the oldest one with the name hash in the folder
+    pub id: Option<Uuid>,
+    /// Other files with the same name hash in the folder, oldest first
+    pub collisions: Vec<Uuid>,
+}
+
+impl NameHashMatch {
+    pub fn exists(&self) -> bool {
+        self.id.is_some()
+    }
+}
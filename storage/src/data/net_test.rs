@@ -0,0 +1,70 @@
+//! Throwaway transfers the clients use to measure the bandwidth of their connection,
+//! so they can tune the chunk size and the number of parallel uploads and downloads.
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum number of bytes transferred in a single bandwidth test
+pub const MAX_NET_TEST_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Number of bytes downloaded when the client doesn't ask for a specific size
+pub const DEFAULT_NET_TEST_BYTES: u64 = fs::MAX_CHUNK_SIZE_BYTES;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NetTest {
+    /// Number of bytes the server should send in the download test
+    pub size: Option<u64>,
+}
+
+impl Validation for NetTest {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("size", |obj: &NetTest, error| {
+            if let Some(v) = obj.size {
+                if v == 0 {
+                    error.add("min:1")
+                }
+
+                if v > MAX_NET_TEST_BYTES {
+                    error.add(format!("max:{}", MAX_NET_TEST_BYTES).as_str())
+                }
+            }
+        })]
+    }
+}
+
+impl NetTest {
+    pub fn into_value(self) -> AppResult<u64> {
+        let data = self.validate()?;
+
+        Ok(data.size.unwrap_or(DEFAULT_NET_TEST_BYTES))
+    }
+}
+
+/// Result of the upload test as measured by the server
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetTestResult {
+    /// Number of bytes received
+    pub bytes: u64,
+
+    /// Time between the first and the last received byte in milliseconds
+    pub duration_ms: u64,
+
+    /// Received bytes per second, zero if the upload was too fast to measure
+    pub bytes_per_second: u64,
+}
+
+impl NetTestResult {
+    pub fn new(bytes: u64, duration: std::time::Duration) -> Self {
+        let micros = duration.as_micros() as u64;
+        let bytes_per_second = match micros {
+            0 => 0,
+            micros => bytes.saturating_mul(1_000_000) / micros,
+        };
+
+        Self {
+            bytes,
+            duration_ms: duration.as_millis() as u64,
+            bytes_per_second,
+        }
+    }
+}
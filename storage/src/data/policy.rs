@@ -0,0 +1,145 @@
+//! Sharing policy of a folder, it applies to the folder and everything inside of it.
+//!
+//! Policy limits the public links to the folder, how long they last and whether they
+//! need the password, and it can keep the users the files are shared with from sharing
+//! them any further. When the folders above each other have their own policies,
+//! the strictest combination of all of them is applied.
+use ::error::{AppResult, Error};
+use chrono::Utc;
+use entity::folder_policies;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// The longest expiry that can be set by the folder policy
+pub const MAX_LINK_EXPIRES_IN_DAYS: i32 = 3650;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SetPolicy {
+    /// Public links cannot be created for anything inside of the folder
+    pub links_disabled: Option<bool>,
+
+    /// Links have to expire in at most this many days
+    pub link_expires_in_days: Option<i32>,
+
+    /// Links can only be created with a password
+    pub links_require_password: Option<bool>,
+
+    /// Only the owner can share the files, even with the users who manage them
+    pub resharing_disabled: Option<bool>,
+}
+
+impl Validation for SetPolicy {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new(
+            "link_expires_in_days",
+            |obj: &SetPolicy, error| {
+                if let Some(v) = obj.link_expires_in_days {
+                    if v < 1 {
+                        error.add("min:1")
+                    }
+
+                    if v > MAX_LINK_EXPIRES_IN_DAYS {
+                        error.add(format!("max:{}", MAX_LINK_EXPIRES_IN_DAYS).as_str())
+                    }
+                }
+            },
+        )]
+    }
+}
+
+impl SetPolicy {
+    pub fn into_value(self) -> AppResult<Policy> {
+        let data = self.validate()?;
+
+        Ok(Policy {
+            links_disabled: data.links_disabled.unwrap_or(false),
+            link_expires_in_days: data.link_expires_in_days,
+            links_require_password: data.links_require_password.unwrap_or(false),
+            resharing_disabled: data.resharing_disabled.unwrap_or(false),
+        })
+    }
+}
+
+/// Policy that is in effect for a file, combined from all the folders above it
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Policy {
+    pub links_disabled: bool,
+    pub link_expires_in_days: Option<i32>,
+    pub links_require_password: bool,
+    pub resharing_disabled: bool,
+}
+
+impl Policy {
+    /// Combine the policies of the folders, keeping the strictest of each of the rules
+    pub fn combine<'a, I>(policies: I) -> Self
+    where
+        I: IntoIterator<Item = &'a folder_policies::Model>,
+    {
+        policies
+            .into_iter()
+            .fold(Self::default(), |policy, folder| Self {
+                links_disabled: policy.links_disabled || folder.links_disabled,
+                link_expires_in_days: match (
+                    policy.link_expires_in_days,
+                    folder.link_expires_in_days,
+                ) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                },
+                links_require_password: policy.links_require_password
+                    || folder.links_require_password,
+                resharing_disabled: policy.resharing_disabled || folder.resharing_disabled,
+            })
+    }
+
+    /// Get the expiry date of a new link, the requested date is checked against the
+    /// policy and when it is not given the latest date the policy allows is used.
+    pub fn link_expires_at(&self, requested: Option<i64>) -> AppResult<Option<i64>> {
+        if self.links_disabled {
+            return Err(Error::Forbidden("links_disabled_by_policy".to_string()));
+        }
+
+        let days = match self.link_expires_in_days {
+            Some(days) => days as i64,
+            None => return Ok(requested),
+        };
+
+        let latest = Utc::now().timestamp() + days * 24 * 60 * 60;
+
+        match requested {
+            Some(expires_at) if expires_at > latest => {
+                let error = format!("max:{}", latest);
+
+                Err(Error::as_validation("expires_at", &error))
+            }
+            Some(expires_at) => Ok(Some(expires_at)),
+            None => Ok(Some(latest)),
+        }
+    }
+
+    /// Link without the password is refused when the policy requires one,
+    /// the password can't be removed from the existing link either.
+    pub fn check_link_password(&self, has_password: bool) -> AppResult<()> {
+        if self.links_require_password && !has_password {
+            return Err(Error::as_validation("password", "required_by_policy"));
+        }
+
+        Ok(())
+    }
+
+    /// Only the owner can share the file when the policy disables resharing
+    pub fn check_reshare(&self, is_owner: bool) -> AppResult<()> {
+        if self.resharing_disabled && !is_owner {
+            return Err(Error::Forbidden("resharing_disabled_by_policy".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Policy set on the folder itself and the one in effect with the folders above it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyResponse {
+    pub policy: Option<folder_policies::Model>,
+    pub effective: Policy,
+}
@@ -1,13 +1,75 @@
+use ::error::AppResult;
+use entity::Uuid;
 use serde::{Deserialize, Serialize};
 use validr::*;
 
+/// Columns the listings and the search can be ordered by
+pub const ORDER_BY: [&str; 4] = ["created_at", "modified_at", "size", "name_hash"];
+
+/// Longest mime type the files can be filtered by
+pub const MAX_MIME_LENGTH: usize = 255;
+
+/// Listing of the files and folders, in the root or inside of the directory.
+/// Files that are equal by the ordering are listed from the oldest one.
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Query {
     pub dir_id: Option<String>,
+    /// Direction of the ordering, `asc` or `desc`
     pub order: Option<String>,
+    /// Column the files are ordered by, one of the [ORDER_BY]
+    #[serde(alias = "sort")]
     pub order_by: Option<String>,
     pub dirs_only: Option<bool>,
     pub is_owner: Option<bool>,
+    /// Only the files of the mime type, `image/*` lists all the images
+    pub mime: Option<String>,
+    /// Page of the listing, everything is listed if omitted
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+impl Validation for Query {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            Rule::new("dir_id", |obj: &Query, error| {
+                if let Some(v) = &obj.dir_id {
+                    if Uuid::parse_str(v).is_err() {
+                        error.add("invalid_uuid")
+                    }
+                }
+            }),
+            rule_in!(
+                order,
+                Into::<Vec<String>>::into(["asc".to_string(), "desc".to_string()])
+            ),
+            rule_in!(
+                order_by,
+                ORDER_BY
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect::<Vec<_>>()
+            ),
+            rule_length_max!(mime, MAX_MIME_LENGTH),
+            Rule::new("limit", |obj: &Query, error| {
+                if obj.limit == Some(0) {
+                    error.add("min:1")
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![
+            modifier_lowercase!(order),
+            modifier_lowercase!(order_by),
+            modifier_trim!(mime),
+            modifier_lowercase!(mime),
+        ]
+    }
 }
 
-impl Validation for Query {}
+impl Query {
+    pub fn into_value(self) -> AppResult<Self> {
+        Ok(self.validate()?)
+    }
+}
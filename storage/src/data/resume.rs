@@ -0,0 +1,99 @@
+//! Resume the upload of the file from another device of the same user.
+//!
+//! The device that started the upload asks for the resume token of the file, the token
+//! is short lived and signed with the same secret as the sessions. The other device
+//! redeems it for the upload state of the file and continues uploading the missing
+//! chunks with its own session, the token itself doesn't give access to the file.
+use ::error::{AppResult, Error};
+use chrono::Utc;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_file::AppFile;
+
+/// Seconds the resume token can be redeemed for
+pub const RESUME_TOKEN_TTL: i64 = 15 * 60;
+
+/// Claims signed in the resume token
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResumeClaims {
+    /// Owner of the file, only they can redeem the token
+    pub sub: Uuid,
+    /// File that is being uploaded
+    pub file_id: Uuid,
+    /// Device the upload was started on
+    pub device: Uuid,
+    /// Expires at
+    pub exp: i64,
+}
+
+impl ResumeClaims {
+    pub fn new(sub: Uuid, file_id: Uuid, device: Uuid) -> Self {
+        Self {
+            sub,
+            file_id,
+            device,
+            exp: Utc::now().timestamp() + RESUME_TOKEN_TTL,
+        }
+    }
+
+    /// Sign the claims into the token
+    pub fn encode(&self, secret: &str) -> AppResult<String> {
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            self,
+            &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .map_err(Error::from)
+    }
+
+    /// Verify the token and read the claims, expired tokens are rejected
+    pub fn decode(token: &str, secret: &str) -> AppResult<Self> {
+        let mut validator = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
+        validator.leeway = 0;
+
+        jsonwebtoken::decode::<Self>(
+            token,
+            &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
+            &validator,
+        )
+        .map(|data| data.claims)
+        .map_err(|_| Error::BadRequest("invalid_resume_token".to_string()))
+    }
+}
+
+/// Token to redeem on the other device
+///
+/// Request: [Resume]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Resume {
+    pub token: Option<String>,
+}
+
+impl Validation for Resume {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![rule_required!(token)]
+    }
+}
+
+impl Resume {
+    pub fn into_value(self) -> AppResult<String> {
+        let data = self.validate()?;
+
+        Ok(data.token.unwrap())
+    }
+}
+
+/// Upload state of the file together with the token to resume it with
+#[derive(Clone, Debug, Serialize)]
+pub struct ResumeState {
+    /// File with the chunks that were already uploaded
+    pub file: AppFile,
+    /// Chunks that are still missing
+    pub missing_chunks: Vec<i64>,
+    /// Device the upload was started on
+    pub device: Uuid,
+    pub token: String,
+    pub expires_at: i64,
+}
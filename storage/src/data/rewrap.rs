@@ -0,0 +1,94 @@
+//! Re-wrapping of the file keys, when the user rotates their key pair or the
+//! share of a file is revoked, the keys in `user_files.encrypted_key` have to be
+//! encrypted again by the client, the server only keeps track of what is left.
+use ::error::AppResult;
+use entity::{rewrap_jobs, DbErr, FromQueryResult, QueryResult, Uuid};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Maximum number of the keys that can be sent back in a single request
+pub const MAX_REWRAP_ENTRIES: usize = 1000;
+
+/// Number of the keys given to the client in a single batch of the worklist
+pub const REWRAP_BATCH_SIZE: u64 = 100;
+
+/// Re-wrap job with the number of the keys that still have to be re-wrapped
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RewrapJob {
+    #[serde(flatten)]
+    pub job: rewrap_jobs::Model,
+    pub pending: i64,
+}
+
+/// Key of the file that has to be re-wrapped for the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RewrapEntry {
+    pub user_file_id: Uuid,
+    pub file_id: Uuid,
+    /// User the key belongs to
+    pub user_id: Uuid,
+    /// Public key the re-wrapped key has to be encrypted with
+    pub pubkey: String,
+    /// Current encrypted key of the file
+    pub encrypted_key: String,
+}
+
+impl FromQueryResult for RewrapEntry {
+    fn from_query_result(res: &QueryResult, _pre: &str) -> Result<Self, DbErr> {
+        Ok(Self {
+            user_file_id: res.try_get_by("user_file_id")?,
+            file_id: res.try_get_by("file_id")?,
+            user_id: res.try_get_by("user_id")?,
+            pubkey: res.try_get_by("pubkey")?,
+            encrypted_key: res.try_get_by("encrypted_key")?,
+        })
+    }
+}
+
+/// Job with the next batch of the keys the client has to re-wrap
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Worklist {
+    #[serde(flatten)]
+    pub job: RewrapJob,
+    pub entries: Vec<RewrapEntry>,
+}
+
+/// Key of the file encrypted again by the client
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rewrapped {
+    pub user_file_id: Uuid,
+    pub encrypted_key: String,
+}
+
+/// Keys re-wrapped by the client, the entries not in the job are ignored
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompleteRewrap {
+    pub entries: Option<Vec<Rewrapped>>,
+}
+
+impl Validation for CompleteRewrap {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![Rule::new("entries", |obj: &CompleteRewrap, error| {
+            let entries = match obj.entries.as_ref() {
+                Some(entries) if !entries.is_empty() => entries,
+                _ => return error.add("required"),
+            };
+
+            if entries.len() > MAX_REWRAP_ENTRIES {
+                return error.add(format!("max:{}", MAX_REWRAP_ENTRIES).as_str());
+            }
+
+            if entries.iter().any(|entry| entry.encrypted_key.is_empty()) {
+                error.add("encrypted_key_required")
+            }
+        })]
+    }
+}
+
+impl CompleteRewrap {
+    pub fn into_value(self) -> AppResult<Vec<Rewrapped>> {
+        let data = self.validate()?;
+
+        Ok(data.entries.unwrap_or_default())
+    }
+}
@@ -0,0 +1,76 @@
+//! # S3 gateway data
+//!
+//! Query of the listing of the objects, see [crate::s3].
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Most objects listed at once, the same limit the S3 has
+pub const MAX_KEYS: usize = 1000;
+
+/// ListObjectsV2, or the ListObjects when the `list-type` isn't `2`.
+/// The `location` is only there for the GetBucketLocation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListObjects {
+    pub list_type: Option<String>,
+    pub prefix: Option<String>,
+    pub delimiter: Option<String>,
+    pub max_keys: Option<usize>,
+    /// Continue the V2 listing, the token is the last key or prefix of the previous page
+    pub continuation_token: Option<String>,
+    pub start_after: Option<String>,
+    /// Continue the V1 listing
+    pub marker: Option<String>,
+    /// `url` to get the keys in the response percent encoded
+    pub encoding_type: Option<String>,
+    pub location: Option<String>,
+}
+
+impl Validation for ListObjects {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_in!(
+                encoding_type,
+                Into::<Vec<String>>::into(["url".to_string()])
+            ),
+            Rule::new("continuation-token", |obj: &ListObjects, error| {
+                if let Some(token) = &obj.continuation_token {
+                    if cryptfns::base64::decode(token).is_err() {
+                        error.add("invalid_continuation_token")
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![Modifier::new("max_keys", |obj: &mut ListObjects| {
+            obj.max_keys = Some(obj.max_keys.unwrap_or(MAX_KEYS).min(MAX_KEYS));
+        })]
+    }
+}
+
+impl ListObjects {
+    pub fn into_value(self) -> AppResult<Self> {
+        Ok(self.validate()?)
+    }
+
+    pub fn is_v2(&self) -> bool {
+        self.list_type.as_deref() == Some("2")
+    }
+
+    /// Only the keys after this one are listed
+    pub fn after(&self) -> Option<String> {
+        let token = self
+            .continuation_token
+            .as_deref()
+            .and_then(|token| cryptfns::base64::decode(token).ok())
+            .and_then(|token| String::from_utf8(token).ok());
+
+        match self.is_v2() {
+            true => token.or_else(|| self.start_after.clone()),
+            false => self.marker.clone(),
+        }
+    }
+}
@@ -1,24 +1,258 @@
-use entity::{option_string_to_uuid, Uuid};
+use ::error::{AppResult, Error};
+use entity::{option_string_to_uuid, JsonValue, Uuid};
 use serde::{Deserialize, Serialize};
+use serde_json::Map;
 use validr::*;
 
+use super::{
+    app_file::AppFile,
+    attributes::is_valid_key,
+    query::{MAX_MIME_LENGTH, ORDER_BY},
+};
+
+/// Kinds of the files the search can be narrowed to, each of them covers a few mime types
+pub const KINDS: [&str; 5] = ["image", "video", "audio", "document", "dir"];
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Search {
     pub dir_id: Option<String>,
     pub search_tokens_hashed: Option<Vec<String>>,
+    /// Only the files with all the given attributes set to the given values,
+    /// the values can be strings or numbers.
+    pub attributes: Option<Map<String, JsonValue>>,
+    /// Only the files of the mime type, `image/*` finds all the images
+    pub mime: Option<String>,
+    /// Only the files of the kind, one of the [KINDS]
+    pub kind: Option<String>,
+    /// Only the files uploaded at or after the time
+    pub uploaded_after: Option<i64>,
+    /// Only the files uploaded at or before the time
+    pub uploaded_before: Option<i64>,
+    /// Only the files of at least this many bytes
+    pub min_size: Option<i64>,
+    /// Only the files of at most this many bytes
+    pub max_size: Option<i64>,
+    /// Direction of the ordering, `asc` or `desc`
+    pub order: Option<String>,
+    /// Column the results are ordered by, one of the [ORDER_BY],
+    /// the best matches are first if omitted
+    #[serde(alias = "sort")]
+    pub order_by: Option<String>,
     pub limit: Option<u64>,
+    #[serde(alias = "offset")]
     pub skip: Option<u64>,
+    /// Where the previous page ended, the `next_cursor` from its response. Pages
+    /// are then found by the position instead of skipping the results before them,
+    /// the search has to be ordered by `created_at` for it and can't have the skip.
+    pub cursor: Option<String>,
+}
+
+impl Validation for Search {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            Rule::new("dir_id", |obj: &Search, error| {
+                if let Some(v) = &obj.dir_id {
+                    if Uuid::parse_str(v).is_err() {
+                        error.add("invalid_uuid")
+                    }
+                }
+            }),
+            Rule::new("attributes", |obj: &Search, error| {
+                for (key, value) in obj.attributes.iter().flatten() {
+                    if !is_valid_key(key) {
+                        return error.add("invalid_key");
+                    }
+
+                    if !value.is_string() && !value.is_number() {
+                        return error.add("invalid_value");
+                    }
+                }
+            }),
+            rule_length_max!(mime, MAX_MIME_LENGTH),
+            rule_in!(
+                kind,
+                KINDS
+                    .iter()
+                    .map(|kind| kind.to_string())
+                    .collect::<Vec<_>>()
+            ),
+            Rule::new("uploaded_before", |obj: &Search, error| {
+                if let (Some(after), Some(before)) = (obj.uploaded_after, obj.uploaded_before) {
+                    if before < after {
+                        error.add("before_uploaded_after")
+                    }
+                }
+            }),
+            Rule::new("min_size", |obj: &Search, error| {
+                if obj.min_size.map_or(false, |size| size < 0) {
+                    error.add("min:0")
+                }
+            }),
+            Rule::new("max_size", |obj: &Search, error| {
+                if obj.max_size.map_or(false, |size| size < 0) {
+                    return error.add("min:0");
+                }
+
+                if let (Some(min), Some(max)) = (obj.min_size, obj.max_size) {
+                    if max < min {
+                        error.add("less_than_min_size")
+                    }
+                }
+            }),
+            rule_in!(
+                order,
+                Into::<Vec<String>>::into(["asc".to_string(), "desc".to_string()])
+            ),
+            rule_in!(
+                order_by,
+                ORDER_BY
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect::<Vec<_>>()
+            ),
+            Rule::new("limit", |obj: &Search, error| {
+                if obj.limit == Some(0) {
+                    error.add("min:1")
+                }
+            }),
+            Rule::new("cursor", |obj: &Search, error| {
+                if let Some(v) = &obj.cursor {
+                    if Cursor::decode(v).is_err() {
+                        return error.add("invalid_cursor");
+                    }
+
+                    if obj.order_by.as_deref() != Some("created_at") {
+                        return error.add("order_by_created_at_required");
+                    }
+
+                    if obj.skip.is_some() {
+                        error.add("skip_not_allowed")
+                    }
+                }
+            }),
+        ]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![
+            modifier_trim!(mime),
+            modifier_lowercase!(mime),
+            modifier_trim!(kind),
+            modifier_lowercase!(kind),
+            modifier_lowercase!(order),
+            modifier_lowercase!(order_by),
+        ]
+    }
 }
 
-impl Validation for Search {}
+/// Attribute filter, name of the attribute and its value as text
+pub type AttributeFilter = (String, String);
 
 impl Search {
-    pub fn into_tuple(self) -> (Option<Uuid>, Vec<String>, Option<u64>, Option<u64>) {
-        (
-            option_string_to_uuid(self.dir_id),
-            self.search_tokens_hashed.unwrap_or_default(),
-            self.limit,
-            self.skip,
-        )
+    /// Validate the search before the results are streamed, so the client
+    /// gets the validation errors instead of the stream that breaks off
+    pub fn into_value(self) -> AppResult<Self> {
+        Ok(self.validate()?)
     }
+
+    pub fn into_tuple(
+        self,
+    ) -> AppResult<(
+        Option<Uuid>,
+        Vec<String>,
+        Vec<AttributeFilter>,
+        Option<u64>,
+        Option<u64>,
+    )> {
+        let data = self.validate()?;
+
+        let attributes = data
+            .attributes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| match value {
+                JsonValue::String(value) => (key, value),
+                value => (key, value.to_string()),
+            })
+            .collect();
+
+        Ok((
+            option_string_to_uuid(data.dir_id),
+            data.search_tokens_hashed.unwrap_or_default(),
+            attributes,
+            data.limit,
+            data.skip,
+        ))
+    }
+}
+
+impl Search {
+    /// Search narrows the files by something other than the tokens and the attributes,
+    /// it finds the files by the filters alone
+    pub fn has_filters(&self) -> bool {
+        self.mime.is_some()
+            || self.kind.is_some()
+            || self.uploaded_after.is_some()
+            || self.uploaded_before.is_some()
+            || self.min_size.is_some()
+            || self.max_size.is_some()
+    }
+
+    /// Cursor of the page after the given one, only the full pages of the search
+    /// ordered by `created_at` have it, the last page has no page after it
+    pub fn next_cursor(&self, files: &[AppFile]) -> Option<String> {
+        if self.order_by.as_deref() != Some("created_at") {
+            return None;
+        }
+
+        match (self.limit, files.last()) {
+            (Some(limit), Some(last)) if files.len() as u64 >= limit => Some(
+                Cursor {
+                    created_at: last.created_at,
+                    id: last.id,
+                }
+                .encode(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Position of the last file of the page in the search ordered by `created_at`,
+/// the files with the same time are ordered by their ids. It is opaque to the clients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: i64,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        cryptfns::base64::encode(format!("{}:{}", self.created_at, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> AppResult<Self> {
+        let invalid = || Error::BadRequest("invalid_cursor".to_string());
+
+        let decoded = String::from_utf8(cryptfns::base64::decode(cursor).map_err(|_| invalid())?)
+            .map_err(|_| invalid())?;
+        let (created_at, id) = decoded.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self {
+            created_at: created_at.parse().map_err(|_| invalid())?,
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Page of the search results
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub files: Vec<AppFile>,
+
+    /// How many files match the search, all of the pages together
+    pub total: u64,
+
+    /// Cursor of the next page, see [Search::cursor]
+    pub next_cursor: Option<String>,
 }
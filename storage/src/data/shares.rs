@@ -0,0 +1,223 @@
+//! Shares of the files with other users on the platform, the owner can
+//! limit when and how long the user has access to the shared file.
+//!
+//! The server never sees the keys of the files, the client of the owner encrypts them
+//! with the public key of the user, for the shared file and everything inside of it.
+use ::error::AppResult;
+use chrono::Utc;
+use entity::{
+    user_files::{self, Permission},
+    users, Uuid,
+};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Share the file or the folder with another user
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CreateShare {
+    /// User the file is shared with
+    pub user_id: Option<Uuid>,
+    /// Key of the file encrypted with the public key of the user
+    pub encrypted_key: Option<String>,
+    /// Keys of everything inside of the shared folder, encrypted the same way
+    pub keys: Option<Vec<SharedKey>>,
+    /// What the user can do with the shared file, only read it if not set
+    pub permission: Option<Permission>,
+}
+
+/// Key of the file inside of the shared folder
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SharedKey {
+    pub id: Uuid,
+    pub encrypted_key: String,
+}
+
+impl Validation for CreateShare {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(user_id),
+            rule_required!(encrypted_key),
+            Rule::new("keys", |obj: &CreateShare, error| {
+                let keys = obj.keys.as_deref().unwrap_or_default();
+
+                if keys.iter().any(|key| key.encrypted_key.is_empty()) {
+                    error.add("empty_key")
+                }
+            }),
+        ]
+    }
+}
+
+impl CreateShare {
+    pub fn into_value(self) -> AppResult<(Uuid, String, Vec<SharedKey>, Permission)> {
+        let data = self.validate()?;
+
+        Ok((
+            data.user_id.unwrap(),
+            data.encrypted_key.unwrap(),
+            data.keys.unwrap_or_default(),
+            data.permission.unwrap_or_default(),
+        ))
+    }
+}
+
+/// Share the file or the folder with every member of the group
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CreateGroupShare {
+    /// Group the user is a member of
+    pub group_id: Option<Uuid>,
+    /// Keys encrypted for each of the other members of the group
+    pub members: Option<Vec<MemberKeys>>,
+    /// What the members can do with the shared file, only read it if not set
+    pub permission: Option<Permission>,
+}
+
+/// Keys of the shared file and everything inside of it, encrypted for the member
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MemberKeys {
+    pub user_id: Uuid,
+    pub encrypted_key: String,
+    #[serde(default)]
+    pub keys: Vec<SharedKey>,
+}
+
+impl Validation for CreateGroupShare {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(group_id),
+            rule_required!(members),
+            Rule::new("members", |obj: &CreateGroupShare, error| {
+                let members = obj.members.as_deref().unwrap_or_default();
+
+                let empty = members.iter().any(|member| {
+                    member.encrypted_key.is_empty()
+                        || member.keys.iter().any(|key| key.encrypted_key.is_empty())
+                });
+
+                if empty {
+                    error.add("empty_key")
+                }
+            }),
+        ]
+    }
+}
+
+impl CreateGroupShare {
+    pub fn into_value(self) -> AppResult<(Uuid, Vec<MemberKeys>, Permission)> {
+        let data = self.validate()?;
+
+        Ok((
+            data.group_id.unwrap(),
+            data.members.unwrap(),
+            data.permission.unwrap_or_default(),
+        ))
+    }
+}
+
+/// Look up the user the file is going to be shared with
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FindRecipient {
+    pub email: Option<String>,
+}
+
+impl Validation for FindRecipient {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![rule_required!(email), rule_email!(email)]
+    }
+
+    fn modifiers(&self) -> Vec<Modifier<Self>> {
+        vec![modifier_trim!(email), modifier_lowercase!(email)]
+    }
+}
+
+impl FindRecipient {
+    pub fn into_value(self) -> AppResult<String> {
+        Ok(self.validate()?.email.unwrap())
+    }
+}
+
+/// User the file can be shared with, the client encrypts the keys with their public key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recipient {
+    pub id: Uuid,
+    pub email: String,
+    pub pubkey: String,
+    pub fingerprint: String,
+}
+
+impl From<users::Model> for Recipient {
+    fn from(user: users::Model) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            pubkey: user.pubkey,
+            fingerprint: user.fingerprint,
+        }
+    }
+}
+
+/// Set or remove the dates when the access of the user to the shared file starts and ends
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SetSchedule {
+    /// Timestamp before which the user cannot access the file, right away if not set
+    pub starts_at: Option<i64>,
+    /// Timestamp after which the user cannot access the file, never if not set
+    pub expires_at: Option<i64>,
+}
+
+impl Validation for SetSchedule {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            Rule::new("expires_at", |obj: &SetSchedule, error| {
+                if let Some(expires_at) = obj.expires_at {
+                    if expires_at <= Utc::now().timestamp() {
+                        error.add("expires_at_in_the_past")
+                    }
+                }
+            }),
+            Rule::new("starts_at", |obj: &SetSchedule, error| {
+                if let (Some(starts_at), Some(expires_at)) = (obj.starts_at, obj.expires_at) {
+                    if starts_at >= expires_at {
+                        error.add("starts_at_after_expires_at")
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl SetSchedule {
+    pub fn into_values(self) -> AppResult<(Option<i64>, Option<i64>)> {
+        let data = self.validate()?;
+
+        Ok((data.starts_at, data.expires_at))
+    }
+}
+
+/// User the file is shared with
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Share {
+    pub id: Uuid,
+    pub file_id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub permission: Permission,
+    pub created_at: i64,
+    pub starts_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
+
+impl Share {
+    pub fn new(share: user_files::Model, user: users::Model) -> Self {
+        Self {
+            id: share.id,
+            file_id: share.file_id,
+            user_id: share.user_id,
+            email: user.email,
+            permission: share.permission,
+            created_at: share.created_at,
+            starts_at: share.starts_at,
+            expires_at: share.expires_at,
+        }
+    }
+}
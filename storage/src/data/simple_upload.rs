@@ -0,0 +1,61 @@
+//! Upload of a small file in a single request, meant for the share sheets of the
+//! mobile apps where every round trip costs the latency and the battery.
+//!
+//! The first part of the multipart request is the `file` part with the JSON of this
+//! struct, followed by the `content` part holding the whole encrypted content of the
+//! file as its only chunk.
+use ::error::AppResult;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::{create_file::CreateFile, meta::Meta};
+
+/// Maximum size of the `file` part with the metadata of the file
+pub const MAX_SIMPLE_METADATA_BYTES: usize = 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimpleUpload {
+    #[serde(flatten)]
+    pub file: CreateFile,
+    /// Checksum of the encrypted content, verified before the file is created
+    pub checksum: Option<String>,
+    /// Tells us what checksum function was used to generate the checksum
+    pub checksum_function: Option<String>,
+}
+
+impl Validation for SimpleUpload {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_in!(
+                checksum_function,
+                Into::<Vec<String>>::into(["crc16".to_string(), "sha256".to_string()])
+            ),
+            Rule::new("mime", |obj: &SimpleUpload, error| {
+                if obj.file.mime.as_deref() == Some("dir") {
+                    error.add("not_for_dir")
+                }
+            }),
+            Rule::new("chunks", |obj: &SimpleUpload, error| {
+                if obj.file.chunks != Some(1) {
+                    error.add("single_chunk_only")
+                }
+            }),
+        ]
+    }
+}
+
+impl SimpleUpload {
+    /// File to create and the meta of its only chunk
+    pub fn into_values(self) -> AppResult<(CreateFile, Meta)> {
+        let data = self.validate()?;
+
+        let meta = Meta {
+            chunk: Some(0),
+            checksum: data.checksum,
+            checksum_function: data.checksum_function,
+            key_hex: None,
+        };
+
+        Ok((data.file, meta))
+    }
+}
@@ -0,0 +1,195 @@
+//! Integrity snapshot of the folder and everything inside of it.
+//!
+//! Every folder gets a digest of its children, the name hash, size and content hash of
+//! the files and the digests of the subfolders, so the digest of the folder changes when
+//! anything below it changes. Backup clients keep the snapshot and compare the digest
+//! before walking the tree, and only walk the folders whose digest is different.
+use std::collections::{HashMap, HashSet};
+
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_file::AppFile;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Folder the snapshot was made for
+    pub id: Uuid,
+
+    /// Digest of the whole folder, same as its entry in the folders
+    pub digest: String,
+
+    /// Digests of the folder and all of its subfolders
+    pub folders: Vec<FolderDigest>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FolderDigest {
+    pub id: Uuid,
+
+    /// Folder this folder is in, empty for the folder the snapshot was made for
+    pub parent: Option<Uuid>,
+
+    pub digest: String,
+}
+
+/// What the snapshot is made of for every file in the folder
+#[derive(Clone, Debug)]
+pub struct SnapshotEntry {
+    pub id: Uuid,
+    pub parent: Option<Uuid>,
+    pub name_hash: String,
+    pub size: Option<i64>,
+    pub sha256: Option<String>,
+    pub is_dir: bool,
+}
+
+impl From<AppFile> for SnapshotEntry {
+    fn from(file: AppFile) -> Self {
+        Self {
+            is_dir: file.is_dir(),
+            id: file.id,
+            parent: file.file_id,
+            name_hash: file.name_hash,
+            size: file.size,
+            sha256: file.sha256,
+        }
+    }
+}
+
+/// Previous snapshot of the folder the client wants to compare with the current one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompareSnapshot {
+    pub digest: Option<String>,
+    pub folders: Option<Vec<FolderDigest>>,
+}
+
+impl Validation for CompareSnapshot {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![rule_required!(digest), rule_required!(folders)]
+    }
+}
+
+impl CompareSnapshot {
+    pub fn into_value(self) -> AppResult<(String, Vec<FolderDigest>)> {
+        let data = self.validate()?;
+
+        Ok((data.digest.unwrap(), data.folders.unwrap()))
+    }
+}
+
+/// Difference between the previous and the current snapshot of the folder
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub id: Uuid,
+
+    /// Digest of the folder as it is now
+    pub digest: String,
+
+    /// Anything in the folder changed since the previous snapshot
+    pub changed: bool,
+
+    /// Folders that are new or have something changed inside of them,
+    /// only these have to be walked to find the changes.
+    pub folders: Vec<Uuid>,
+
+    /// Folders from the previous snapshot that are no longer in the folder
+    pub removed: Vec<Uuid>,
+}
+
+impl Snapshot {
+    /// Build the snapshot from the entries of the folder and everything inside of it
+    pub fn build(id: Uuid, entries: &[SnapshotEntry]) -> Self {
+        let mut children: HashMap<Uuid, Vec<&SnapshotEntry>> = HashMap::new();
+
+        for entry in entries.iter().filter(|entry| entry.id != id) {
+            if let Some(parent) = entry.parent {
+                children.entry(parent).or_default().push(entry);
+            }
+        }
+
+        let mut folders = vec![];
+        let digest = digest(id, None, &children, &mut folders);
+
+        Self {
+            id,
+            digest,
+            folders,
+        }
+    }
+
+    /// Compare the previous snapshot of the folder with this one
+    pub fn diff(&self, digest: &str, previous: &[FolderDigest]) -> SnapshotDiff {
+        let previous = previous
+            .iter()
+            .map(|folder| (folder.id, folder.digest.as_str()))
+            .collect::<HashMap<_, _>>();
+        let current = self
+            .folders
+            .iter()
+            .map(|folder| folder.id)
+            .collect::<HashSet<_>>();
+
+        SnapshotDiff {
+            id: self.id,
+            digest: self.digest.clone(),
+            changed: self.digest != digest,
+            folders: self
+                .folders
+                .iter()
+                .filter(|folder| previous.get(&folder.id) != Some(&folder.digest.as_str()))
+                .map(|folder| folder.id)
+                .collect(),
+            removed: previous
+                .keys()
+                .filter(|id| !current.contains(id))
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+/// Digest of the folder from the sorted lines of its children, subfolders are
+/// digested first so their digests can be a part of the line.
+fn digest(
+    id: Uuid,
+    parent: Option<Uuid>,
+    children: &HashMap<Uuid, Vec<&SnapshotEntry>>,
+    folders: &mut Vec<FolderDigest>,
+) -> String {
+    let index = folders.len();
+    folders.push(FolderDigest {
+        id,
+        parent,
+        digest: String::new(),
+    });
+
+    let mut lines = children
+        .get(&id)
+        .map(|list| list.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|child| {
+            let subfolder = match child.is_dir {
+                true => digest(child.id, Some(id), children, folders),
+                false => String::new(),
+            };
+
+            format!(
+                "{}\t{}\t{}\t{}\n",
+                child.name_hash,
+                child.size.map(|size| size.to_string()).unwrap_or_default(),
+                child.sha256.as_deref().unwrap_or_default(),
+                subfolder
+            )
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+
+    let digest = cryptfns::sha256::digest(lines.concat());
+    folders[index].digest = digest.clone();
+
+    digest
+}
@@ -0,0 +1,53 @@
+//! Upload of the chunks over the WebSocket.
+//!
+//! The client opens one connection for the file and sends its chunks one after another,
+//! each chunk is a text message with the [crate::data::meta::Meta] of the chunk followed
+//! by a binary message with its content. The server stores the chunk the same as with the
+//! chunk upload and answers with the [SocketAck], the client can send the next chunk
+//! without waiting for it, the acknowledgements come in the order the chunks were sent.
+//!
+//! Chunk that fails is only acknowledged with the error, the connection stays open
+//! and the client can send the chunk again. Connections that go without any progress
+//! are closed by the same timeouts as the chunk uploads, clients can keep them open
+//! with the pings.
+use ::error::{Error, ErrorResponse};
+use serde::Serialize;
+
+use super::app_file::AppFile;
+
+/// Answer of the server to the chunk sent over the socket
+#[derive(Debug, Serialize)]
+pub struct SocketAck {
+    /// Number of the chunk, empty if the metadata of the chunk couldn't be read
+    pub chunk: Option<i64>,
+
+    /// File with its upload progress after the chunk was stored
+    pub file: Option<AppFile>,
+
+    /// Status the same chunk upload would have failed with over the HTTP
+    pub status: Option<u16>,
+
+    pub error: Option<ErrorResponse>,
+}
+
+impl SocketAck {
+    pub fn stored(chunk: i64, file: AppFile) -> Self {
+        Self {
+            chunk: Some(chunk),
+            file: Some(file),
+            status: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(chunk: Option<i64>, error: &Error) -> Self {
+        let error = ErrorResponse::from(error);
+
+        Self {
+            chunk,
+            file: None,
+            status: Some(error.status),
+            error: Some(error),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Spaces, the root folders that belong to a group instead of a single user
+use ::error::AppResult;
+use entity::{group_members::Role, spaces, Uuid};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Turn the root folder of the user into the space of the group
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateSpace {
+    /// Group the space will belong to, the user has to be its owner
+    pub group_id: Option<Uuid>,
+    /// Root folder of the user that becomes the space
+    pub file_id: Option<Uuid>,
+}
+
+impl Validation for CreateSpace {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![rule_required!(group_id), rule_required!(file_id)]
+    }
+}
+
+impl CreateSpace {
+    pub fn into_value(self) -> AppResult<(Uuid, Uuid)> {
+        let data = self.validate()?;
+
+        Ok((data.group_id.unwrap(), data.file_id.unwrap()))
+    }
+}
+
+/// Space the user has access to as a member of the group
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpaceResponse {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub file_id: Uuid,
+    pub role: Role,
+    pub created_at: i64,
+}
+
+impl SpaceResponse {
+    pub fn new(space: spaces::Model, group_name: String, role: Role) -> Self {
+        Self {
+            id: space.id,
+            group_id: space.group_id,
+            group_name,
+            file_id: space.file_id,
+            role,
+            created_at: space.created_at,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use entity::{numeric::Numeric, DbErr, FromQueryResult, QueryResult};
+use entity::{numeric::Numeric, transfers, DbErr, FromQueryResult, QueryResult, Uuid};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,9 +22,38 @@ impl FromQueryResult for Stats {
     }
 }
 
+/// Storage pooled by the group the user is member of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSpace {
+    pub group_id: Uuid,
+    pub name: String,
+    pub quota: i64,
+    /// Space used by all the members together
+    pub used_space: i64,
+}
+
+impl FromQueryResult for GroupSpace {
+    fn from_query_result(res: &QueryResult, _pre: &str) -> Result<Self, DbErr> {
+        let used_space: Numeric = res.try_get_by("used_space")?;
+
+        Ok(Self {
+            group_id: res.try_get_by("group_id")?,
+            name: res.try_get_by("name")?,
+            quota: res.try_get_by("quota")?,
+            used_space: used_space.into(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Response {
     pub stats: Vec<Stats>,
     pub used_space: i64,
     pub quota: Option<u64>,
+    /// Quotas of the groups the user is in
+    pub groups: Vec<GroupSpace>,
+    /// Uploads and downloads of the last days, the newest first
+    pub transfers: Vec<transfers::Model>,
+    /// Daily limit of the uploads and downloads together
+    pub transfer_limit: Option<u64>,
 }
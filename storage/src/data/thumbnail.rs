@@ -0,0 +1,78 @@
+//! Thumbnails of the files, encrypted by the client
+//!
+//! The content of the files is end-to-end encrypted, so the server can't make the
+//! thumbnails itself. Clients upload them already encrypted with the file key and
+//! the server only stores them next to the chunks of the file, under the name of the
+//! file with the size of the thumbnail, so they are purged together with the file.
+use std::{fmt::Display, str::FromStr};
+
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::prelude::{Filename, IntoFilename};
+
+/// Sizes of the thumbnails the clients can upload for each file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThumbnailSize {
+    #[default]
+    Small,
+    Medium,
+}
+
+impl ThumbnailSize {
+    pub const ALL: [ThumbnailSize; 2] = [ThumbnailSize::Small, ThumbnailSize::Medium];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThumbnailSize::Small => "small",
+            ThumbnailSize::Medium => "medium",
+        }
+    }
+}
+
+impl Display for ThumbnailSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ThumbnailSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "small" => Ok(ThumbnailSize::Small),
+            "medium" => Ok(ThumbnailSize::Medium),
+            _ => Err(Error::as_validation("size", "invalid_thumbnail_size")),
+        }
+    }
+}
+
+/// Thumbnail of the file, stored in the provider as the only chunk of its own
+#[derive(Clone, Debug)]
+pub struct Thumbnail {
+    pub id: Uuid,
+    pub created_at: i64,
+    pub size: ThumbnailSize,
+}
+
+impl Thumbnail {
+    /// Chunk the thumbnail is stored as
+    pub const CHUNK: i64 = 0;
+
+    pub fn new(id: Uuid, created_at: i64, size: ThumbnailSize) -> Self {
+        Self {
+            id,
+            created_at,
+            size,
+        }
+    }
+}
+
+impl IntoFilename for Thumbnail {
+    fn filename(&self) -> AppResult<Filename> {
+        Ok(
+            Filename::new(format!("{}.thumbnail-{}", self.id, self.size))
+                .with_timestamp(self.created_at),
+        )
+    }
+}
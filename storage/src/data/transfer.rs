@@ -0,0 +1,25 @@
+//! Move or copy a file or a folder into another folder
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transfer {
+    /// Destination folder id (empty for root)
+    pub file_id: Option<Uuid>,
+}
+
+impl Validation for Transfer {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![]
+    }
+}
+
+impl Transfer {
+    pub fn into_value(self) -> AppResult<Option<Uuid>> {
+        let data = self.validate()?;
+
+        Ok(data.file_id)
+    }
+}
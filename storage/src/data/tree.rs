@@ -0,0 +1,61 @@
+use ::error::AppResult;
+use entity::Uuid;
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::app_file::AppFile;
+
+/// Deepest the tree can be loaded at once
+pub const MAX_TREE_DEPTH: i64 = 64;
+
+/// Everything inside of the directory, or in the root, down to the given depth
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct Tree {
+    /// Directory the tree starts in, the root of the user if omitted
+    pub root: Option<String>,
+    /// How many levels are loaded, `1` is only what is directly in the root,
+    /// [MAX_TREE_DEPTH] if omitted
+    pub depth: Option<i64>,
+}
+
+impl Validation for Tree {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            Rule::new("root", |obj: &Tree, error| {
+                if let Some(v) = &obj.root {
+                    if Uuid::parse_str(v).is_err() {
+                        error.add("invalid_uuid")
+                    }
+                }
+            }),
+            Rule::new("depth", |obj: &Tree, error| {
+                if let Some(depth) = obj.depth {
+                    if !(1..=MAX_TREE_DEPTH).contains(&depth) {
+                        error.add(&format!("range:1-{}", MAX_TREE_DEPTH))
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl Tree {
+    pub fn into_tuple(self) -> AppResult<(Option<Uuid>, i64)> {
+        let data = self.validate()?;
+
+        Ok((
+            data.root.map(|root| Uuid::parse_str(&root)).transpose()?,
+            data.depth.unwrap_or(MAX_TREE_DEPTH),
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Response {
+    /// Directory the tree starts in, nothing for the root of the user
+    pub root: Option<AppFile>,
+
+    /// Files and directories in the tree level by level, each one is
+    /// inside of the directory its `file_id` points to
+    pub files: Vec<AppFile>,
+}
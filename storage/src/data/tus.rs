@@ -0,0 +1,183 @@
+//! Uploads over the [tus](https://tus.io) resumable upload protocol.
+//!
+//! Every tus upload is one chunk of the file created with the regular create route,
+//! the client says which one in the `Upload-Metadata`. Received bytes are kept aside
+//! until the whole chunk arrives, then it is stored the same as with the chunk upload.
+use std::{
+    collections::HashMap,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use ::error::{AppResult, Error};
+use actix_web::{http::header::HttpDate, HttpRequest};
+use entity::{tus_uploads, Uuid};
+use fs::{prelude::*, MAX_CHUNK_SIZE_BYTES};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+/// Version of the protocol the server speaks, sent back in every response
+pub const TUS_RESUMABLE: &str = "1.0.0";
+
+/// Extensions of the protocol the server supports
+pub const TUS_EXTENSIONS: &str = "creation,expiration";
+
+/// Unfinished uploads are removed a day after they were created
+pub const UPLOAD_EXPIRES_IN_SECONDS: i64 = 60 * 60 * 24;
+
+/// Largest chunk that can be uploaded, with the same room for the encryption
+/// overhead as [crate::routes::upload::validate_chunk_size] gives it.
+pub fn max_upload_length() -> i64 {
+    (MAX_CHUNK_SIZE_BYTES + MAX_CHUNK_SIZE_BYTES / 100) as i64
+}
+
+/// Create the upload for the chunk of the file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CreateUpload {
+    /// File the chunk belongs to, from the `file_id` metadata
+    pub file_id: Option<Uuid>,
+    /// Number of the chunk, from the `chunk` metadata
+    pub chunk: Option<i64>,
+    /// Size of the chunk in bytes, from the `Upload-Length` header
+    pub length: Option<i64>,
+    /// Checksum of the chunk, from the `checksum` metadata
+    pub checksum: Option<String>,
+    /// Function the checksum was made with, from the `checksum_function` metadata
+    pub checksum_function: Option<String>,
+}
+
+impl Validation for CreateUpload {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(file_id),
+            rule_required!(chunk),
+            rule_required!(length),
+            rule_in!(
+                checksum_function,
+                Into::<Vec<String>>::into(["crc16".to_string(), "sha256".to_string()])
+            ),
+            Rule::new("chunk", |obj: &Self, error| {
+                if obj.chunk.map(|chunk| chunk < 0).unwrap_or(false) {
+                    error.add("min:0")
+                }
+            }),
+            Rule::new("length", |obj: &Self, error| {
+                if let Some(length) = obj.length {
+                    if length <= 0 {
+                        error.add("min:1")
+                    }
+
+                    if length > max_upload_length() {
+                        error.add(&format!("max:{}", max_upload_length()))
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl CreateUpload {
+    /// Read the upload from the `Upload-Length` and the `Upload-Metadata` headers,
+    /// metadata the server doesn't know about (filename etc.) is ignored.
+    pub fn from_request(req: &HttpRequest) -> AppResult<Self> {
+        let length = match header(req, "Upload-Length") {
+            Some(length) => Some(
+                length
+                    .parse()
+                    .map_err(|_| Error::as_validation("length", "invalid_upload_length"))?,
+            ),
+            None => None,
+        };
+
+        let metadata = parse_metadata(header(req, "Upload-Metadata").unwrap_or_default())?;
+
+        Ok(Self {
+            file_id: match metadata.get("file_id") {
+                Some(id) => Some(
+                    Uuid::parse_str(id)
+                        .map_err(|_| Error::as_validation("file_id", "invalid_file_id"))?,
+                ),
+                None => None,
+            },
+            chunk: match metadata.get("chunk") {
+                Some(chunk) => Some(
+                    chunk
+                        .parse()
+                        .map_err(|_| Error::as_validation("chunk", "invalid_chunk"))?,
+                ),
+                None => None,
+            },
+            length,
+            checksum: metadata.get("checksum").cloned(),
+            checksum_function: metadata.get("checksum_function").cloned(),
+        })
+    }
+
+    pub fn into_model(self, user_id: Uuid, now: i64) -> AppResult<tus_uploads::Model> {
+        let data = self.validate()?;
+
+        Ok(tus_uploads::Model {
+            id: Uuid::new_v4(),
+            user_id,
+            file_id: data.file_id.unwrap(),
+            chunk: data.chunk.unwrap(),
+            length: data.length.unwrap(),
+            offset: 0,
+            checksum: data.checksum,
+            checksum_function: data.checksum_function,
+            created_at: now,
+            expires_at: now + UPLOAD_EXPIRES_IN_SECONDS,
+        })
+    }
+}
+
+/// Parse the `Upload-Metadata` header, comma separated pairs of the key
+/// and the base64 encoded value, the value can be left out.
+pub fn parse_metadata(header: &str) -> AppResult<HashMap<String, String>> {
+    let mut metadata = HashMap::new();
+
+    for pair in header.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once(' ').unwrap_or((pair, ""));
+        let value = cryptfns::base64::decode(value.trim())
+            .ok()
+            .and_then(|value| String::from_utf8(value).ok())
+            .ok_or_else(|| Error::as_validation("metadata", "invalid_upload_metadata"))?;
+
+        metadata.insert(key.to_string(), value);
+    }
+
+    Ok(metadata)
+}
+
+/// Value of the header as a string, missing and invalid headers are the same
+pub fn header<'req>(req: &'req HttpRequest, name: &str) -> Option<&'req str> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Time in the format of the `Upload-Expires` header
+pub fn http_date(timestamp: i64) -> String {
+    HttpDate::from(UNIX_EPOCH + Duration::from_secs(timestamp.max(0) as u64)).to_string()
+}
+
+/// Bytes of the chunk received so far, kept in the storage provider
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TusStaging {
+    pub id: Uuid,
+    pub created_at: i64,
+}
+
+impl From<&tus_uploads::Model> for TusStaging {
+    fn from(upload: &tus_uploads::Model) -> Self {
+        Self {
+            id: upload.id,
+            created_at: upload.created_at,
+        }
+    }
+}
+
+impl IntoFilename for TusStaging {
+    fn filename(&self) -> AppResult<Filename> {
+        Ok(Filename::new(self.id).with_timestamp(self.created_at))
+    }
+}
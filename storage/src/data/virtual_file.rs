@@ -0,0 +1,141 @@
+//! Virtual files, bookmarks and notes the users keep in their folders next to the files.
+//!
+//! Virtual file has no chunks, its whole content is the small payload encrypted with
+//! the file key, it is stored with the file and returned along with it, so the clients
+//! can render it without downloading anything.
+use ::error::AppResult;
+use chrono::Utc;
+use entity::{files::ActiveModel as ActiveModelFile, option_string_to_uuid, ActiveValue, Uuid};
+use serde::{Deserialize, Serialize};
+use validr::*;
+
+use super::create_file::CreateFileData;
+
+/// Prefix of the mime type of all the virtual files
+pub const VIRTUAL_MIME_PREFIX: &str = "virtual/";
+
+/// Maximum length of the encrypted payload of the virtual file
+pub const MAX_VIRTUAL_PAYLOAD_LENGTH: usize = 64 * 1024;
+
+/// What the virtual file holds, the clients render each kind differently
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualKind {
+    /// Link to a web page
+    Url,
+    /// Short text note
+    Note,
+}
+
+impl VirtualKind {
+    /// Mime type the virtual file of the kind is stored with
+    pub fn mime(&self) -> String {
+        let kind = match self {
+            Self::Url => "url",
+            Self::Note => "note",
+        };
+
+        format!("{}{}", VIRTUAL_MIME_PREFIX, kind)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateVirtualFile {
+    /// Id of the file generated by the client, repeating the request
+    /// with the same id returns the already created file
+    pub id: Option<Uuid>,
+    /// What the virtual file holds
+    pub kind: Option<VirtualKind>,
+    /// File key encrypted with users RSA key
+    pub encrypted_key: Option<String>,
+    /// Name of the file hashed so we can guard
+    /// against duplicate files in directories
+    pub name_hash: Option<String>,
+    /// File name encrypted with the AES file key
+    pub encrypted_name: Option<String>,
+    /// URL or the text of the note encrypted with the AES file key
+    pub encrypted_payload: Option<String>,
+    /// Tokens by which this file will be searchable
+    pub search_tokens_hashed: Option<Vec<String>>,
+    /// ID of the directory the file is located in
+    pub file_id: Option<String>,
+    /// Version of the scheme the client encrypted the payload with,
+    /// the current version is used when not provided
+    pub crypto_version: Option<i32>,
+    /// File key encrypted with the organization recovery key,
+    /// required when the key escrow is enabled by the admin
+    pub escrow_key: Option<String>,
+}
+
+impl Validation for CreateVirtualFile {
+    fn rules(&self) -> Vec<Rule<Self>> {
+        vec![
+            rule_required!(kind),
+            rule_required!(encrypted_key),
+            rule_required!(name_hash),
+            rule_required!(encrypted_name),
+            rule_required!(encrypted_payload),
+            Rule::new("id", |obj: &CreateVirtualFile, error| {
+                if obj.id.map(|id| id.is_nil()).unwrap_or(false) {
+                    error.add("invalid_id")
+                }
+            }),
+            Rule::new("encrypted_payload", |obj: &CreateVirtualFile, error| {
+                if let Some(v) = &obj.encrypted_payload {
+                    if v.len() > MAX_VIRTUAL_PAYLOAD_LENGTH {
+                        error.add(format!("max:{}", MAX_VIRTUAL_PAYLOAD_LENGTH).as_str())
+                    }
+                }
+            }),
+            Rule::new("crypto_version", |obj: &CreateVirtualFile, error| {
+                if let Some(v) = obj.crypto_version {
+                    if !cryptfns::scheme::is_supported(v) {
+                        error.add("unsupported_crypto_version")
+                    }
+                }
+            }),
+        ]
+    }
+}
+
+impl CreateVirtualFile {
+    /// Data of the file in the same shape as the regular file is created from,
+    /// the size of the virtual file is the size of its payload.
+    pub fn into_active_model(self) -> AppResult<CreateFileData> {
+        let data = self.validate()?;
+        let now = Utc::now().timestamp();
+        let payload = data.encrypted_payload.unwrap();
+        let size = payload.len() as i64;
+        let file_id = option_string_to_uuid(data.file_id);
+
+        Ok((
+            ActiveModelFile {
+                id: ActiveValue::Set(data.id.unwrap_or_else(Uuid::new_v4)),
+                name_hash: ActiveValue::Set(data.name_hash.unwrap()),
+                encrypted_name: ActiveValue::Set(data.encrypted_name.unwrap()),
+                encrypted_thumbnail: ActiveValue::Set(None),
+                mime: ActiveValue::Set(data.kind.unwrap().mime()),
+                size: ActiveValue::Set(Some(size)),
+                chunks: ActiveValue::Set(None),
+                chunks_stored: ActiveValue::Set(None),
+                file_id: ActiveValue::Set(file_id),
+                file_modified_at: ActiveValue::Set(now),
+                created_at: ActiveValue::Set(now),
+                finished_upload_at: ActiveValue::Set(Some(now)),
+                sha256: ActiveValue::Set(None),
+                conflict_of: ActiveValue::Set(None),
+                conflict_device: ActiveValue::Set(None),
+                legal_hold: ActiveValue::Set(false),
+                crypto_version: ActiveValue::Set(
+                    data.crypto_version.unwrap_or(cryptfns::scheme::CURRENT),
+                ),
+                encrypted_payload: ActiveValue::Set(Some(payload)),
+                deleted_at: ActiveValue::Set(None),
+            },
+            data.encrypted_key.unwrap(),
+            data.search_tokens_hashed.unwrap_or_default(),
+            size,
+            file_id,
+        ))
+    }
+}
@@ -0,0 +1,256 @@
+//! # WebDAV
+//!
+//! Lets the users mount their drive with the WebDAV clients (Finder, Explorer, rclone)
+//! under `/api/dav/`, the routes are in [crate::routes::dav].
+//!
+//! Names and the content of the files are encrypted by the clients, the server can't read
+//! them. So every file and folder is listed by its id, and the paths can be written
+//! with the ids or with the plain names, the plain name is found by its name hash.
+//! Content is served the way it is stored, encrypted. Files and folders that are created
+//! through WebDAV are encrypted here for the user, the same way the imports do it.
+use chrono::NaiveDateTime;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use percent_encoding::percent_decode_str;
+use quick_xml::escape::escape;
+
+use crate::{
+    data::{app_file::AppFile, query::Query},
+    repository::Repository,
+};
+
+/// Where the WebDAV paths start
+pub(crate) const PREFIX: &str = "/api/dav";
+
+/// What the path points to
+#[derive(Debug)]
+pub(crate) enum Resource {
+    /// Root of the drive of the user
+    Root,
+    File(AppFile),
+    /// Nothing is there yet, the folder it would be created in exists
+    Missing {
+        parent_id: Option<Uuid>,
+        name: String,
+    },
+}
+
+/// Decoded segments of the path after the [PREFIX], accepts the full URL as well,
+/// the way it is sent in the `Destination` header.
+pub(crate) fn segments(path: &str) -> AppResult<Vec<String>> {
+    let invalid = || Error::BadRequest("invalid_dav_path".to_string());
+
+    let path = match path.find("://") {
+        Some(scheme) => {
+            let rest = &path[scheme + 3..];
+            rest.find('/').map(|start| &rest[start..]).unwrap_or("/")
+        }
+        None => path,
+    };
+
+    let path = path.strip_prefix(PREFIX).ok_or_else(invalid)?;
+
+    if !path.is_empty() && !path.starts_with('/') {
+        return Err(invalid());
+    }
+
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let segment = percent_decode_str(segment)
+                .decode_utf8()
+                .map_err(|_| invalid())?;
+
+            match segment.as_ref() {
+                "." | ".." => Err(invalid()),
+                _ => Ok(segment.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Find what the path points to, every segment is either the id of the file
+/// or the folder or its plain name. Only the last one can be missing.
+pub(crate) async fn resolve(
+    context: &Context,
+    user_id: Uuid,
+    segments: &[String],
+) -> AppResult<Resource> {
+    let repository = Repository::new(&context.db);
+    let mut current: Option<AppFile> = None;
+
+    for (index, segment) in segments.iter().enumerate() {
+        let parent_id = match current.as_ref() {
+            Some(parent) if !parent.is_dir() => {
+                return Err(Error::NotFound("file_not_found".to_string()))
+            }
+            Some(parent) => Some(parent.id),
+            None => None,
+        };
+
+        // Items shared with the user are in the folders the user doesn't have,
+        // so in the root they are found by the id alone
+        let found = match Uuid::parse_str(segment) {
+            Ok(id) => repository.by_id(id, user_id).await.and_then(|file| {
+                match parent_id.is_none() || file.file_id == parent_id {
+                    true => Ok(file),
+                    false => Err(Error::NotFound("file_not_found".to_string())),
+                }
+            }),
+            Err(_) => {
+                repository
+                    .manage(user_id)
+                    .by_name(name_hash(segment), parent_id)
+                    .await
+            }
+        };
+
+        current = match found {
+            Ok(file) => Some(file),
+            Err(e) if e.is_not_found() && index + 1 == segments.len() => {
+                return Ok(Resource::Missing {
+                    parent_id,
+                    name: segment.clone(),
+                })
+            }
+            Err(e) => return Err(e),
+        };
+    }
+
+    Ok(current.map(Resource::File).unwrap_or(Resource::Root))
+}
+
+/// Files and folders in the folder, or in the root, files that are not
+/// uploaded yet are left out
+pub(crate) async fn children(
+    context: &Context,
+    user_id: Uuid,
+    parent_id: Option<Uuid>,
+) -> AppResult<Vec<AppFile>> {
+    let query = Query {
+        dir_id: parent_id.map(|id| id.to_string()),
+        ..Default::default()
+    };
+
+    let response = Repository::new(&context.db)
+        .manage(user_id)
+        .find(query)
+        .await?;
+
+    Ok(response
+        .children
+        .into_iter()
+        .filter(|file| file.is_dir() || file.finished_upload_at.is_some())
+        .collect())
+}
+
+/// Name hash of the plain name, the same one the clients compute
+pub(crate) fn name_hash(name: &str) -> String {
+    cryptfns::sha256::digest(name.as_bytes())
+}
+
+/// Properties of the file, the folder or the root in the listing
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Entry {
+    pub(crate) href: String,
+    pub(crate) name: String,
+    pub(crate) is_collection: bool,
+    pub(crate) mime: Option<String>,
+    /// Size of the encrypted content, the same one the download has
+    pub(crate) size: Option<u64>,
+    pub(crate) created_at: Option<i64>,
+    pub(crate) modified_at: Option<i64>,
+}
+
+impl Entry {
+    pub(crate) fn root(href: &str) -> Self {
+        Self {
+            href: href.to_string(),
+            is_collection: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn file(href: &str, file: &AppFile, size: Option<u64>) -> Self {
+        Self {
+            href: href.to_string(),
+            name: file.id.to_string(),
+            is_collection: file.is_dir(),
+            mime: (!file.is_dir()).then(|| file.mime.clone()),
+            size,
+            created_at: Some(file.created_at),
+            modified_at: Some(file.file_modified_at),
+        }
+    }
+}
+
+/// `207 Multi-Status` body of the `PROPFIND`
+#[derive(Default)]
+pub(crate) struct Multistatus {
+    entries: Vec<Entry>,
+}
+
+impl Multistatus {
+    pub(crate) fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let mut xml =
+            String::from(r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">"#);
+
+        for entry in self.entries.iter() {
+            xml.push_str("<d:response><d:href>");
+            xml.push_str(&escape(entry.href.as_str()));
+            xml.push_str("</d:href><d:propstat><d:prop>");
+            xml.push_str(&format!(
+                "<d:displayname>{}</d:displayname>",
+                escape(entry.name.as_str())
+            ));
+
+            match entry.is_collection {
+                true => xml.push_str("<d:resourcetype><d:collection/></d:resourcetype>"),
+                false => xml.push_str("<d:resourcetype/>"),
+            }
+
+            if let Some(mime) = entry.mime.as_deref() {
+                xml.push_str(&format!(
+                    "<d:getcontenttype>{}</d:getcontenttype>",
+                    escape(mime)
+                ));
+            }
+
+            if let Some(size) = entry.size {
+                xml.push_str(&format!(
+                    "<d:getcontentlength>{}</d:getcontentlength>",
+                    size
+                ));
+            }
+
+            if let Some(date) = entry.created_at.and_then(date) {
+                xml.push_str(&format!(
+                    "<d:creationdate>{}</d:creationdate>",
+                    date.format("%Y-%m-%dT%H:%M:%SZ")
+                ));
+            }
+
+            if let Some(date) = entry.modified_at.and_then(date) {
+                xml.push_str(&format!(
+                    "<d:getlastmodified>{}</d:getlastmodified>",
+                    date.format("%a, %d %b %Y %H:%M:%S GMT")
+                ));
+            }
+
+            xml.push_str("</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>");
+        }
+
+        xml.push_str("</d:multistatus>");
+
+        xml
+    }
+}
+
+fn date(timestamp: i64) -> Option<NaiveDateTime> {
+    NaiveDateTime::from_timestamp_opt(timestamp, 0)
+}
@@ -0,0 +1,77 @@
+use context::{Context, SenderContract};
+use entity::{file_requests, users};
+use error::AppResult;
+use notify::notification::{Kind, Notification};
+
+use crate::data::file_requests::Summary;
+
+/// Send the summary of the files received through the closed file request to its owner
+pub(crate) async fn send(
+    context: &Context,
+    owner: &users::Model,
+    request: &file_requests::Model,
+    summary: &Summary,
+) -> AppResult<()> {
+    let link = format!("{}/{}", context.config.get_client_url(), request.file_id);
+
+    if let Some(notifier) = &context.notifier {
+        let message = format!(
+            "Your file request has received {} file(s), open the folder to see them",
+            summary.files
+        );
+
+        notifier
+            .send(
+                Notification::new(
+                    owner.id,
+                    &owner.email,
+                    Kind::FileRequest,
+                    "File request closed",
+                    &message,
+                )
+                .link(&link),
+            )
+            .await;
+    }
+
+    let sender = match &context.sender {
+        Some(s) => s,
+        None => {
+            log::warn!("No sender configured, skipping file request summary sending");
+
+            return Ok(());
+        }
+    };
+
+    let content = r#"
+    <h1>Your file request is closed</h1>
+    <p>
+        The request has received {{files}} file(s) with the total size of {{size}} bytes,
+        {{finished}} of them were fully uploaded.
+    </p>
+    <p>
+        <a href="{{link}}" class="btn-primary">Open the folder</a>
+    </p>
+    "#
+    .to_string();
+
+    let mut template = sender.template(
+        "File request closed",
+        format!(
+            "Your file request has received {} file(s), open the folder to see them: {}",
+            summary.files, &link
+        )
+        .as_str(),
+    )?;
+
+    template.add_template_var("link", &link);
+    template.add_template_var("files", summary.files);
+    template.add_template_var("finished", summary.finished);
+    template.add_template_var("size", summary.size);
+    template.register_content_template(content.as_str())?;
+
+    sender
+        .send(vec![template.to(&owner.email)?])
+        .await
+        .map(|_| ())
+}
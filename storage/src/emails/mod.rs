@@ -0,0 +1,3 @@
+pub(crate) mod file_request;
+pub(crate) mod restored;
+pub(crate) mod share_expiry;
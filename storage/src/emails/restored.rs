@@ -0,0 +1,60 @@
+use context::{Context, SenderContract};
+use entity::{files, users};
+use error::AppResult;
+use notify::notification::{Kind, Notification};
+
+/// Let the user know the file they tried to download was restored from the archive
+pub(crate) async fn send(
+    context: &Context,
+    user: &users::Model,
+    file: &files::Model,
+) -> AppResult<()> {
+    let link = format!("{}/{}", context.config.get_client_url(), file.id);
+    let message =
+        "The file you tried to download was restored from the archive, it can be downloaded now";
+
+    if let Some(notifier) = &context.notifier {
+        notifier
+            .send(
+                Notification::new(
+                    user.id,
+                    &user.email,
+                    Kind::Restore,
+                    "File restored from the archive",
+                    message,
+                )
+                .link(&link),
+            )
+            .await;
+    }
+
+    let sender = match &context.sender {
+        Some(s) => s,
+        None => {
+            log::warn!("No sender configured, skipping restored file notice sending");
+
+            return Ok(());
+        }
+    };
+
+    let content = r#"
+    <h1>Your file is ready</h1>
+    <p>
+        The file you tried to download was restored from the archive, it can be downloaded now.
+    </p>
+    <p>
+        <a href="{{link}}" class="btn-primary">Open the file</a>
+    </p>
+    "#
+    .to_string();
+
+    let mut template = sender.template("File restored from the archive", message)?;
+
+    template.add_template_var("link", &link);
+    template.register_content_template(content.as_str())?;
+
+    sender
+        .send(vec![template.to(&user.email)?])
+        .await
+        .map(|_| ())
+}
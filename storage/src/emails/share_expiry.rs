@@ -0,0 +1,79 @@
+use context::{Context, SenderContract};
+use entity::{user_files, users};
+use error::AppResult;
+use notify::notification::{Kind, Notification};
+
+/// Remind the user their access to the shared file is about to end
+pub(crate) async fn send(
+    context: &Context,
+    user: &users::Model,
+    owner: Option<&users::Model>,
+    share: &user_files::Model,
+) -> AppResult<()> {
+    let link = format!("{}/{}", context.config.get_client_url(), share.file_id);
+    let owner = owner
+        .map(|owner| owner.email.clone())
+        .unwrap_or_else(|| "the owner".to_string());
+    let expires_at = util::datetime::from_timestamp(share.expires_at.unwrap_or_default())
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+
+    if let Some(notifier) = &context.notifier {
+        let message = format!(
+            "The file {} has shared with you will no longer be available after {}",
+            &owner, &expires_at
+        );
+
+        notifier
+            .send(
+                Notification::new(
+                    user.id,
+                    &user.email,
+                    Kind::Share,
+                    "Access to a shared file is ending",
+                    &message,
+                )
+                .link(&link),
+            )
+            .await;
+    }
+
+    let sender = match &context.sender {
+        Some(s) => s,
+        None => {
+            log::warn!("No sender configured, skipping share expiry reminder sending");
+
+            return Ok(());
+        }
+    };
+
+    let content = r#"
+    <h1>Your access to a shared file is ending</h1>
+    <p>
+        The file {{owner}} has shared with you will no longer be available after: {{expires_at}}
+    </p>
+    <p>
+        <a href="{{link}}" class="btn-primary">Open the file</a>
+    </p>
+    "#
+    .to_string();
+
+    let mut template = sender.template(
+        "Access to a shared file is ending",
+        format!(
+            "The file {} has shared with you will no longer be available after {}",
+            &owner, &expires_at
+        )
+        .as_str(),
+    )?;
+
+    template.add_template_var("link", &link);
+    template.add_template_var("owner", &owner);
+    template.add_template_var("expires_at", &expires_at);
+    template.register_content_template(content.as_str())?;
+
+    sender
+        .send(vec![template.to(&user.email)?])
+        .await
+        .map(|_| ())
+}
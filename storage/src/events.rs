@@ -0,0 +1,136 @@
+//! # Storage events
+//!
+//! Publishes the file events from the activity journal to the MQTT broker, so the
+//! automation tools can react to the files landing in a folder without polling the API.
+//!
+//! The journal is followed instead of publishing from the routes, this way only
+//! the changes that were actually committed are ever published.
+
+use std::time::Duration;
+
+use config::mqtt::{MqttBroker, MqttConfig};
+use context::Context;
+use entity::{activities, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use error::AppResult;
+use rumqttc::{AsyncClient, EventLoop, MqttOptions, QoS};
+
+/// How often the journal is checked for new activities
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many activities are published on each run
+const BATCH_SIZE: u64 = 500;
+
+/// Topic the activity is published to: `<prefix>/<user_id>/<folder_id>`
+pub fn topic(prefix: &str, activity: &activities::Model) -> String {
+    let folder = activity
+        .parent_id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "root".to_string());
+
+    format!("{}/{}/{}", prefix, activity.user_id, folder)
+}
+
+/// Publisher of the journal activities to the MQTT broker
+pub struct Publisher {
+    context: Context,
+    broker: MqttBroker,
+}
+
+impl Publisher {
+    /// Create the publisher if the broker is configured
+    pub fn new(context: Context) -> Option<Self> {
+        match context.config.mqtt.clone() {
+            MqttConfig::Broker(broker) => Some(Self { context, broker }),
+            MqttConfig::None => None,
+        }
+    }
+
+    /// Start following the journal in the background, only the
+    /// activities recorded after the start are published.
+    pub fn spawn(self) {
+        let mut options = MqttOptions::new(
+            self.broker.client_id.clone(),
+            self.broker.host.clone(),
+            self.broker.port,
+        );
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let Some(username) = self.broker.username.clone() {
+            options.set_credentials(username, self.broker.password.clone().unwrap_or_default());
+        }
+
+        let (client, eventloop) = AsyncClient::new(options, BATCH_SIZE as usize);
+
+        actix_web::rt::spawn(poll_eventloop(eventloop));
+        actix_web::rt::spawn(async move {
+            let mut cursor = loop {
+                match self.latest().await {
+                    Ok(cursor) => break cursor,
+                    Err(e) => log::error!("Failed loading the latest activity: {}", e),
+                }
+
+                actix_web::rt::time::sleep(POLL_INTERVAL).await;
+            };
+
+            loop {
+                match self.publish(&client, cursor).await {
+                    Ok(latest) => cursor = latest,
+                    Err(e) => log::error!("Failed publishing storage events: {}", e),
+                }
+
+                actix_web::rt::time::sleep(POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Publish the activities after the cursor, returns the new cursor
+    pub async fn publish(&self, client: &AsyncClient, cursor: i64) -> AppResult<i64> {
+        let activities = activities::Entity::find()
+            .filter(activities::Column::Id.gt(cursor))
+            .order_by_asc(activities::Column::Id)
+            .limit(BATCH_SIZE)
+            .all(&self.context.db)
+            .await?;
+
+        let mut cursor = cursor;
+
+        for activity in activities {
+            let payload = serde_json::to_vec(&activity)?;
+            let topic = topic(&self.broker.topic_prefix, &activity);
+
+            if let Err(e) = client
+                .publish(topic, QoS::AtLeastOnce, false, payload)
+                .await
+            {
+                log::error!("Failed publishing storage event {}: {}", activity.id, e);
+                break;
+            }
+
+            cursor = activity.id;
+        }
+
+        Ok(cursor)
+    }
+
+    /// Sequence number of the latest recorded activity
+    async fn latest(&self) -> AppResult<i64> {
+        let latest = activities::Entity::find()
+            .select_only()
+            .column_as(activities::Column::Id.max(), "id")
+            .into_tuple::<Option<i64>>()
+            .one(&self.context.db)
+            .await?;
+
+        Ok(latest.flatten().unwrap_or(0))
+    }
+}
+
+/// Drive the connection to the broker, it reconnects on the next poll after an error
+async fn poll_eventloop(mut eventloop: EventLoop) {
+    loop {
+        if let Err(e) = eventloop.poll().await {
+            log::warn!("MQTT connection error: {}", e);
+            actix_web::rt::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
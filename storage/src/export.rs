@@ -0,0 +1,117 @@
+//! # User data export
+//!
+//! Builds the tar archive with all the data of the user. The archive is written into
+//! the storage provider in chunks as it is built, the same way the uploaded files are
+//! stored, so neither the archive nor the files in it are ever held in memory as a whole.
+
+use chrono::Utc;
+use config::Config;
+use context::Context;
+use entity::{exports, files, ColumnTrait, EntityTrait, QueryFilter};
+use error::{AppResult, Error};
+use fs::{prelude::*, MAX_CHUNK_SIZE_BYTES};
+
+use crate::{data::exports::ExportArchive, repository::exports::metadata};
+
+/// Tar archive that is written into the storage provider chunk by chunk
+pub(crate) struct Archive<'ctx> {
+    fs: Fs<'ctx>,
+    target: ExportArchive,
+    buffer: Vec<u8>,
+    chunk: i64,
+    size: u64,
+}
+
+impl<'ctx> Archive<'ctx> {
+    pub(crate) fn new(config: &'ctx Config, target: ExportArchive) -> Self {
+        Self {
+            fs: Fs::new(config),
+            target,
+            buffer: vec![],
+            chunk: 0,
+            size: 0,
+        }
+    }
+
+    /// Add the file with the given content to the archive
+    pub(crate) async fn add(&mut self, path: &str, content: &[u8]) -> AppResult<()> {
+        let header = util::tar::header(path, content.len() as u64, Utc::now().timestamp())
+            .ok_or_else(|| Error::InternalError(format!("invalid_archive_path:{}", path)))?;
+
+        self.buffer.extend_from_slice(&header);
+        self.buffer.extend_from_slice(content);
+        self.buffer.resize(
+            self.buffer.len() + util::tar::padding(content.len() as u64),
+            0,
+        );
+
+        while self.buffer.len() >= MAX_CHUNK_SIZE_BYTES as usize {
+            let rest = self.buffer.split_off(MAX_CHUNK_SIZE_BYTES as usize);
+            let chunk = std::mem::replace(&mut self.buffer, rest);
+            self.push(&chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Close the archive and return its total size
+    pub(crate) async fn finish(mut self) -> AppResult<u64> {
+        self.buffer.extend_from_slice(&util::tar::END);
+
+        let chunk = std::mem::take(&mut self.buffer);
+        self.push(&chunk).await?;
+
+        Ok(self.size)
+    }
+
+    async fn push(&mut self, chunk: &[u8]) -> AppResult<()> {
+        self.fs.push(&self.target, self.chunk, chunk).await?;
+        self.chunk += 1;
+        self.size += chunk.len() as u64;
+
+        Ok(())
+    }
+}
+
+/// Build the archive of the export, the metadata goes first and the
+/// encrypted chunks of the finished files owned by the user after it.
+pub(crate) async fn build(context: &Context, export: &exports::Model) -> AppResult<u64> {
+    let fs = Fs::new(&context.config);
+    let metadata = metadata(&context.db, export.user_id).await?;
+
+    let mut archive = Archive::new(&context.config, ExportArchive::from(export));
+    archive
+        .add("metadata.json", &serde_json::to_vec_pretty(&metadata)?)
+        .await?;
+
+    if export.include_files {
+        let owned = metadata
+            .files
+            .iter()
+            .filter(|file| file.is_owner)
+            .map(|file| file.file.id)
+            .collect::<Vec<_>>();
+
+        // Files are loaded again, only the finished ones have all of their chunks
+        let files = files::Entity::find()
+            .filter(files::Column::Id.is_in(owned))
+            .filter(files::Column::FinishedUploadAt.is_not_null())
+            .filter(files::Column::Mime.ne("dir"))
+            .all(&context.db)
+            .await?;
+
+        for file in files {
+            let filename = Filename::new(file.id).with_timestamp(file.created_at);
+
+            for chunk in 0..file.chunks.unwrap_or(0) {
+                let content = fs.pull(&filename, chunk).await?;
+
+                archive
+                    .add(&format!("files/{}/{}", file.id, chunk), &content)
+                    .await?;
+            }
+        }
+    }
+
+    archive.finish().await
+}
@@ -0,0 +1,284 @@
+//! # Export of the files to external providers
+//!
+//! Copies the folder of the user with everything inside of it into an S3 bucket or onto
+//! a WebDAV server. In the encrypted mode the chunks are copied as they are stored, with
+//! the `metadata.json` next to them that holds the encrypted keys and names, the same
+//! layout the archive of the user data export has. In the decrypted mode the folders and
+//! files are recreated under their names and the content is decrypted on the way with
+//! the keys the client sent.
+//!
+//! Files are read chunk by chunk, so the file is never held in memory as a whole.
+
+pub(crate) mod s3;
+pub(crate) mod webdav;
+
+use std::{collections::HashMap, pin::Pin};
+
+use async_trait::async_trait;
+use context::Context;
+use cryptfns::scheme::Scheme;
+use entity::{external_exports, Uuid};
+use error::{AppResult, Error};
+use fs::prelude::*;
+use futures::{Stream, StreamExt};
+
+use crate::{
+    data::{
+        app_file::AppFile,
+        external_exports::{Credentials, FileKey, Keys, Progress},
+    },
+    repository::{self, Repository},
+};
+
+/// Content of the file as it is read from the storage
+pub(crate) type Chunks<'a> = Pin<Box<dyn Stream<Item = AppResult<Vec<u8>>> + Send + 'a>>;
+
+/// Provider the files are exported to
+#[async_trait]
+pub(crate) trait Target: Send + Sync {
+    /// Create the folder, the folders above it are always created before
+    async fn create_folder(&self, path: &str) -> AppResult<()>;
+
+    /// Store the content under the path
+    async fn put(&self, path: &str, content: Vec<u8>) -> AppResult<()>;
+
+    /// Store the content of the given size that is read chunk by chunk
+    async fn upload(&self, path: &str, size: u64, chunks: Chunks<'_>) -> AppResult<()>;
+}
+
+/// Target of the export with its credentials
+pub(crate) fn target(export: &external_exports::Model) -> AppResult<Box<dyn Target>> {
+    let credentials: Credentials = match export.credentials.as_deref() {
+        Some(credentials) => serde_json::from_str(credentials)?,
+        None => return Err(Error::BadRequest("export_credentials_missing".to_string())),
+    };
+
+    Ok(match export.target {
+        external_exports::Target::S3 => Box::new(s3::S3::new(&export.location, credentials)?),
+        external_exports::Target::Webdav => {
+            Box::new(webdav::WebDav::new(&export.location, credentials)?)
+        }
+    })
+}
+
+/// Export the folder to the target, the files that fail are recorded with the export.
+/// Returns an error only when the export can't be done at all.
+pub(crate) async fn run(
+    context: &Context,
+    export: &external_exports::Model,
+    target: &dyn Target,
+) -> AppResult<Progress> {
+    let repository = Repository::new(&context.db);
+
+    let files = match export.file_id {
+        Some(id) => repository.manage(export.user_id).file_tree(id).await?,
+        None => {
+            repository
+                .selector(export.user_id, true)
+                .into_model::<AppFile>()
+                .all(&context.db)
+                .await?
+        }
+    };
+
+    let exporter = Exporter {
+        context,
+        export,
+        target,
+        fs: Fs::new(&context.config),
+    };
+
+    match export.mode {
+        external_exports::Mode::Encrypted => exporter.encrypted(files).await,
+        external_exports::Mode::Decrypted => {
+            let keys: Keys = match export.keys.as_deref() {
+                Some(keys) => serde_json::from_str(keys)?,
+                None => return Err(Error::BadRequest("export_keys_missing".to_string())),
+            };
+
+            exporter.decrypted(files, keys).await
+        }
+    }
+}
+
+struct Exporter<'ctx> {
+    context: &'ctx Context,
+    export: &'ctx external_exports::Model,
+    target: &'ctx dyn Target,
+    fs: Fs<'ctx>,
+}
+
+impl<'ctx> Exporter<'ctx> {
+    /// Copy the metadata and the encrypted chunks of the finished files
+    async fn encrypted(&self, files: Vec<AppFile>) -> AppResult<Progress> {
+        let mut progress = Progress::default();
+
+        self.target
+            .put("metadata.json", serde_json::to_vec_pretty(&files)?)
+            .await?;
+
+        self.target.create_folder("files").await?;
+
+        for file in files.iter().filter(|file| is_exported(file)) {
+            if !self.is_running().await? {
+                return Ok(progress);
+            }
+
+            let path = format!("files/{}", file.id);
+
+            match self.copy_chunks(file, &path).await {
+                Ok(size) => progress.exported(size),
+                Err(e) => progress.failed(&path, e.to_string()),
+            }
+
+            self.progress(&progress).await?;
+        }
+
+        Ok(progress)
+    }
+
+    async fn copy_chunks(&self, file: &AppFile, path: &str) -> AppResult<i64> {
+        self.target.create_folder(path).await?;
+
+        let mut size = 0;
+
+        for chunk in 0..file.chunks.unwrap_or(0) {
+            let content = self.fs.pull(file, chunk).await?;
+            size += content.len() as i64;
+
+            self.target
+                .put(&format!("{}/{}", path, chunk), content)
+                .await?;
+        }
+
+        Ok(size)
+    }
+
+    /// Recreate the folders and decrypt the files into them
+    async fn decrypted(&self, files: Vec<AppFile>, keys: Keys) -> AppResult<Progress> {
+        let mut progress = Progress::default();
+        let paths = Paths::new(&files, &keys, self.export.file_id);
+
+        let mut folders = files
+            .iter()
+            .filter(|file| file.is_dir() && Some(file.id) != self.export.file_id)
+            .map(|folder| (paths.get(folder.id), folder))
+            .collect::<Vec<_>>();
+
+        // Folders above always come before the folders inside of them
+        folders.sort_by_key(|(path, _)| path.as_ref().map(|p| p.matches('/').count()).ok());
+
+        for (path, folder) in folders {
+            let result = match path {
+                Ok(path) => self.target.create_folder(&path).await,
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                progress.failed(&folder.id.to_string(), e.to_string());
+            }
+        }
+
+        for file in files.iter().filter(|file| is_exported(file)) {
+            if !self.is_running().await? {
+                return Ok(progress);
+            }
+
+            let result = match (paths.get(file.id), keys.get(&file.id)) {
+                (Ok(path), Some(key)) => {
+                    self.decrypt(file, &path, key).await.map_err(|e| (path, e))
+                }
+                (Ok(path), None) => Err((path, Error::BadRequest("file_key_missing".to_string()))),
+                (Err(e), _) => Err((file.id.to_string(), e)),
+            };
+
+            match result {
+                Ok(size) => progress.exported(size),
+                Err((path, e)) => progress.failed(&path, e.to_string()),
+            }
+
+            self.progress(&progress).await?;
+        }
+
+        Ok(progress)
+    }
+
+    async fn decrypt(&self, file: &AppFile, path: &str, key: &FileKey) -> AppResult<i64> {
+        let key = key.key()?;
+        let scheme = Scheme::from_version(file.crypto_version)?;
+        let size = file.size.unwrap_or(0);
+
+        let chunks = futures::stream::iter(0..file.chunks.unwrap_or(0)).then(move |chunk| {
+            let key = key.clone();
+
+            async move {
+                let content = self.fs.pull(file, chunk).await?;
+
+                scheme.decrypt(key, content).map_err(Error::from)
+            }
+        });
+
+        self.target
+            .upload(path, size as u64, Box::pin(chunks))
+            .await?;
+
+        Ok(size)
+    }
+
+    async fn is_running(&self) -> AppResult<bool> {
+        repository::external_exports::is_running(&self.context.db, self.export.id).await
+    }
+
+    async fn progress(&self, progress: &Progress) -> AppResult<()> {
+        repository::external_exports::progress(&self.context.db, self.export.id, progress).await
+    }
+}
+
+/// Only the files with all of their chunks stored have something to export
+fn is_exported(file: &AppFile) -> bool {
+    file.is_file() && file.finished_upload_at.is_some() && file.chunks.is_some()
+}
+
+/// Paths of the decrypted files and folders in the target, relative to the exported folder
+struct Paths<'a> {
+    files: HashMap<Uuid, &'a AppFile>,
+    keys: &'a Keys,
+    root: Option<Uuid>,
+}
+
+impl<'a> Paths<'a> {
+    fn new(files: &'a [AppFile], keys: &'a Keys, root: Option<Uuid>) -> Self {
+        Self {
+            files: files.iter().map(|file| (file.id, file)).collect(),
+            keys,
+            root,
+        }
+    }
+
+    fn get(&self, id: Uuid) -> AppResult<String> {
+        let mut names = vec![];
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            let name = self
+                .keys
+                .get(&id)
+                .map(|key| key.name.clone())
+                .ok_or_else(|| Error::BadRequest(format!("file_key_missing:{}", id)))?;
+            names.push(name);
+
+            current = match self.files.get(&id).and_then(|file| file.file_id) {
+                Some(parent) if Some(parent) == self.root => None,
+                // Parent is outside of the export, the file can't be placed anywhere
+                Some(parent) if !self.files.contains_key(&parent) => {
+                    return Err(Error::BadRequest(format!("file_parent_missing:{}", id)))
+                }
+                parent => parent,
+            };
+        }
+
+        names.reverse();
+
+        Ok(names.join("/"))
+    }
+}
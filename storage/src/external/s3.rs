@@ -0,0 +1,239 @@
+//! S3 target, works with any S3 compatible provider that accepts the path style URLs.
+//!
+//! Requests are signed with the AWS signature version 4, the payload is not signed
+//! so the content can be sent without reading it twice.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use error::{AppResult, Error};
+use fs::s3::{encode_path, host, UNSIGNED_PAYLOAD};
+use futures::StreamExt;
+use reqwest::{Client, Method, RequestBuilder, Response, Url};
+
+use super::{Chunks, Target};
+use crate::data::external_exports::{Credentials, DEFAULT_S3_REGION};
+
+pub(crate) use fs::s3::{xml_text, Signer};
+
+/// S3 refuses the parts of the multipart upload smaller than this, except the last one
+pub(crate) const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+pub(crate) struct S3 {
+    client: Client,
+    /// URL of the bucket with the prefix the objects are stored under
+    root: Url,
+    signer: Signer,
+}
+
+impl S3 {
+    pub(crate) fn new(location: &str, credentials: Credentials) -> AppResult<Self> {
+        let mut root =
+            Url::parse(location).map_err(|_| Error::as_validation("location", "invalid_url"))?;
+        root.set_path(root.path().trim_end_matches('/'));
+        root.set_query(None);
+
+        Ok(Self {
+            client: Client::new(),
+            root,
+            signer: Signer {
+                access_key_id: credentials.access_key_id.unwrap_or_default(),
+                secret_access_key: credentials.secret_access_key.unwrap_or_default(),
+                region: credentials
+                    .region
+                    .unwrap_or_else(|| DEFAULT_S3_REGION.to_string()),
+            },
+        })
+    }
+
+    fn request(&self, method: Method, path: &str, query: &[(&str, &str)]) -> RequestBuilder {
+        self.signed(method, path, query, &[])
+    }
+
+    /// Request with the additional headers, the `x-amz-*` ones have to be signed
+    pub(crate) fn signed(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        extra: &[(&str, &str)],
+    ) -> RequestBuilder {
+        let mut url = self.root.clone();
+        url.set_path(&format!("{}/{}", self.root.path(), encode_path(path)));
+
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = BTreeMap::from([
+            ("host".to_string(), host(&url)),
+            (
+                "x-amz-content-sha256".to_string(),
+                UNSIGNED_PAYLOAD.to_string(),
+            ),
+            ("x-amz-date".to_string(), amz_date),
+        ]);
+
+        for (name, value) in extra {
+            headers.insert(name.to_lowercase(), value.to_string());
+        }
+
+        let authorization = self.signer.authorization(method.as_str(), &url, &headers);
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("Authorization", authorization);
+
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+
+        request
+    }
+
+    async fn upload_parts(
+        &self,
+        path: &str,
+        upload_id: &str,
+        mut chunks: Chunks<'_>,
+    ) -> AppResult<Vec<String>> {
+        let mut etags = vec![];
+        let mut buffer = Vec::with_capacity(MIN_PART_SIZE);
+
+        loop {
+            let chunk = chunks.next().await.transpose()?;
+            let done = chunk.is_none();
+
+            if let Some(chunk) = chunk {
+                buffer.extend_from_slice(&chunk);
+            }
+
+            if buffer.len() >= MIN_PART_SIZE || (done && !buffer.is_empty()) {
+                let part = (etags.len() + 1).to_string();
+                let query = [("partNumber", part.as_str()), ("uploadId", upload_id)];
+
+                let response = self
+                    .request(Method::PUT, path, &query)
+                    .body(std::mem::take(&mut buffer))
+                    .send()
+                    .await?;
+
+                let etag = checked(response)
+                    .await?
+                    .headers()
+                    .get("ETag")
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(|etag| etag.to_string())
+                    .ok_or_else(|| Error::BadRequest("s3_etag_missing".to_string()))?;
+
+                etags.push(etag);
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(etags)
+    }
+}
+
+#[async_trait]
+impl Target for S3 {
+    /// Folders are just the prefixes of the objects in S3
+    async fn create_folder(&self, _path: &str) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn put(&self, path: &str, content: Vec<u8>) -> AppResult<()> {
+        let response = self
+            .request(Method::PUT, path, &[])
+            .body(content)
+            .send()
+            .await?;
+
+        checked(response).await?;
+
+        Ok(())
+    }
+
+    async fn upload(&self, path: &str, size: u64, mut chunks: Chunks<'_>) -> AppResult<()> {
+        if size < MIN_PART_SIZE as u64 {
+            let mut content = Vec::with_capacity(size as usize);
+
+            while let Some(chunk) = chunks.next().await {
+                content.extend_from_slice(&chunk?);
+            }
+
+            return self.put(path, content).await;
+        }
+
+        let response = self
+            .request(Method::POST, path, &[("uploads", "")])
+            .send()
+            .await?;
+        let body = checked(response).await?.text().await?;
+        let upload_id = xml_text(&body, "UploadId")
+            .ok_or_else(|| Error::BadRequest("s3_upload_id_missing".to_string()))?;
+
+        let etags = match self.upload_parts(path, &upload_id, chunks).await {
+            Ok(etags) => etags,
+            Err(e) => {
+                // Parts uploaded so far would be billed until the upload is aborted
+                let abort = self
+                    .request(Method::DELETE, path, &[("uploadId", &upload_id)])
+                    .send()
+                    .await;
+
+                if let Err(abort) = abort {
+                    log::warn!("Failed aborting the S3 upload {}: {}", upload_id, abort);
+                }
+
+                return Err(e);
+            }
+        };
+
+        let parts = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    i + 1,
+                    etag
+                )
+            })
+            .collect::<String>();
+
+        let response = self
+            .request(Method::POST, path, &[("uploadId", &upload_id)])
+            .body(format!(
+                "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                parts
+            ))
+            .send()
+            .await?;
+        let body = checked(response).await?.text().await?;
+
+        // Completing can fail after the response has already started with 200
+        match xml_text(&body, "Code") {
+            Some(code) => Err(Error::BadRequest(format!("s3_error:{}", code))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// S3 explains what went wrong in the body, it is the error of the export
+pub(crate) async fn checked(response: Response) -> AppResult<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    let code = xml_text(&body, "Code").unwrap_or_default();
+
+    Err(Error::BadRequest(format!("s3_status:{}:{}", status, code)))
+}
@@ -0,0 +1,116 @@
+//! WebDAV target, Nextcloud and ownCloud serve the files of the user through it
+//! under `/remote.php/dav/files/<username>/`.
+
+use async_trait::async_trait;
+use error::{AppResult, Error};
+use fs::s3::encode_path;
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use reqwest::{header, Body, Client, Method, RequestBuilder, Response, Url};
+
+use super::{Chunks, Target};
+use crate::data::external_exports::Credentials;
+
+pub(crate) struct WebDav {
+    client: Client,
+    root: Url,
+    credentials: Credentials,
+}
+
+impl WebDav {
+    pub(crate) fn new(location: &str, credentials: Credentials) -> AppResult<Self> {
+        let mut root =
+            Url::parse(location).map_err(|_| Error::as_validation("location", "invalid_url"))?;
+
+        // Without the trailing slash the folder would be replaced when joining the paths
+        if !root.path().ends_with('/') {
+            root.set_path(&format!("{}/", root.path()));
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            root,
+            credentials,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> AppResult<RequestBuilder> {
+        let url = self
+            .root
+            .join(&encode_path(path))
+            .map_err(|_| Error::BadRequest(format!("invalid_webdav_path:{}", path)))?;
+
+        let request = self.client.request(method, url);
+
+        Ok(match self.credentials.username.as_deref() {
+            Some(username) => request.basic_auth(username, self.credentials.password.as_deref()),
+            None => request,
+        })
+    }
+}
+
+#[async_trait]
+impl Target for WebDav {
+    async fn create_folder(&self, path: &str) -> AppResult<()> {
+        let method = Method::from_bytes(b"MKCOL").unwrap();
+        let response = self.request(method, &format!("{}/", path))?.send().await?;
+
+        // Folder is already there from the previous export
+        if response.status().as_u16() == 405 {
+            return Ok(());
+        }
+
+        checked(response)?;
+
+        Ok(())
+    }
+
+    async fn put(&self, path: &str, content: Vec<u8>) -> AppResult<()> {
+        let response = self
+            .request(Method::PUT, path)?
+            .body(content)
+            .send()
+            .await?;
+
+        checked(response)?;
+
+        Ok(())
+    }
+
+    async fn upload(&self, path: &str, size: u64, mut chunks: Chunks<'_>) -> AppResult<()> {
+        let (mut sender, receiver) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(1);
+
+        let request = self
+            .request(Method::PUT, path)?
+            .header(header::CONTENT_LENGTH, size)
+            .body(Body::wrap_stream(receiver))
+            .send();
+
+        // Chunks are read while they are being sent, the request ends
+        // early when reading fails and the sender is dropped
+        let forward = async move {
+            while let Some(chunk) = chunks.next().await {
+                if sender.send(Ok(chunk?)).await.is_err() {
+                    break;
+                }
+            }
+
+            Ok::<_, Error>(())
+        };
+
+        let (response, forwarded) = futures::join!(request, forward);
+        forwarded?;
+        checked(response?)?;
+
+        Ok(())
+    }
+}
+
+fn checked(response: Response) -> AppResult<Response> {
+    match response.status().is_success() {
+        true => Ok(response),
+        false => Err(Error::BadRequest(format!(
+            "webdav_status:{}",
+            response.status().as_u16()
+        ))),
+    }
+}
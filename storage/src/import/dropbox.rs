@@ -0,0 +1,169 @@
+//! Dropbox source, reads the files through the Dropbox HTTP API with the access token of the user.
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use error::{AppResult, Error};
+use reqwest::{header, Client, Response};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{body, RemoteBody, RemoteEntry, Source};
+use crate::data::imports::Credentials;
+
+const LIST_FOLDER_URL: &str = "https://api.dropboxapi.com/2/files/list_folder";
+const LIST_FOLDER_CONTINUE_URL: &str = "https://api.dropboxapi.com/2/files/list_folder/continue";
+const DOWNLOAD_URL: &str = "https://content.dropboxapi.com/2/files/download";
+
+pub(crate) struct Dropbox {
+    client: Client,
+    root: String,
+    token: String,
+}
+
+impl Dropbox {
+    pub(crate) fn new(location: &str, credentials: Credentials) -> Self {
+        Self {
+            client: Client::new(),
+            root: location.to_string(),
+            token: credentials.token.unwrap_or_default(),
+        }
+    }
+
+    async fn post(&self, url: &str, body: serde_json::Value) -> AppResult<ListFolder> {
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .json(&body)
+            .send()
+            .await?;
+
+        parse_list_folder(&checked(response).await?.text().await?)
+    }
+}
+
+#[async_trait]
+impl Source for Dropbox {
+    fn root(&self) -> String {
+        self.root.clone()
+    }
+
+    async fn list(&self, path: &str) -> AppResult<Vec<RemoteEntry>> {
+        let mut page = self.post(LIST_FOLDER_URL, json!({ "path": path })).await?;
+        let mut entries = page.entries();
+
+        while page.has_more {
+            let cursor = json!({ "cursor": page.cursor });
+            page = self.post(LIST_FOLDER_CONTINUE_URL, cursor).await?;
+            entries.extend(page.entries());
+        }
+
+        Ok(entries)
+    }
+
+    async fn download(&self, entry: &RemoteEntry) -> AppResult<RemoteBody> {
+        let response = self
+            .client
+            .post(DOWNLOAD_URL)
+            .bearer_auth(&self.token)
+            .header("Dropbox-API-Arg", api_arg(&entry.path))
+            .send()
+            .await?;
+
+        Ok(body(checked(response).await?))
+    }
+}
+
+/// Dropbox explains what went wrong in the body, it is the error of the import
+async fn checked(response: Response) -> AppResult<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status().as_u16();
+    let summary = response
+        .json::<ApiError>()
+        .await
+        .ok()
+        .and_then(|error| error.error_summary)
+        .unwrap_or_default();
+
+    Err(Error::BadRequest(format!(
+        "dropbox_status:{}:{}",
+        status, summary
+    )))
+}
+
+/// Argument of the download in the header, headers can only hold ASCII so the rest is escaped
+fn api_arg(path: &str) -> header::HeaderValue {
+    let arg = json!({ "path": path })
+        .to_string()
+        .chars()
+        .map(|c| match c.is_ascii() {
+            true => c.to_string(),
+            false => c
+                .encode_utf16(&mut [0; 2])
+                .iter()
+                .map(|unit| format!("\\u{:04x}", unit))
+                .collect(),
+        })
+        .collect::<String>();
+
+    header::HeaderValue::from_str(&arg).unwrap()
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    error_summary: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListFolder {
+    entries: Vec<ListFolderEntry>,
+    cursor: String,
+    has_more: bool,
+}
+
+impl ListFolder {
+    pub(crate) fn entries(&self) -> Vec<RemoteEntry> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let is_dir = match entry.tag.as_str() {
+                    "folder" => true,
+                    "file" => false,
+                    // Deleted files are listed only when asked for, but just in case
+                    _ => return None,
+                };
+
+                Some(RemoteEntry {
+                    path: entry.path_display.clone(),
+                    name: entry.name.clone(),
+                    is_dir,
+                    size: entry.size,
+                    mime: None,
+                    modified_at: entry
+                        .client_modified
+                        .as_deref()
+                        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                        .map(|date| date.timestamp()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListFolderEntry {
+    #[serde(rename = ".tag")]
+    tag: String,
+    name: String,
+    path_display: String,
+    size: Option<i64>,
+    client_modified: Option<String>,
+}
+
+/// Parse the response of the `list_folder` and `list_folder/continue` requests
+pub(crate) fn parse_list_folder(json: &str) -> AppResult<ListFolder> {
+    Ok(serde_json::from_str(json)?)
+}
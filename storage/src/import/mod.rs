@@ -0,0 +1,437 @@
+//! # Import of the files from other services
+//!
+//! Walks the folder tree of the source breadth first and recreates it in the folder of
+//! the user. Folders that already exist are reused and files that already exist are
+//! skipped, so the import that was interrupted can simply run again.
+//!
+//! Every file and folder is encrypted here the same way the clients encrypt them: with its
+//! own new key that is encrypted with the public key of the user, and escrowed when the
+//! escrow is enabled. The content is read from the source and encrypted chunk by chunk,
+//! so the file is never held in memory as a whole.
+
+pub(crate) mod dropbox;
+pub(crate) mod webdav;
+
+use std::{collections::VecDeque, pin::Pin};
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use context::Context;
+use cryptfns::scheme::Scheme;
+use entity::{imports, users, EntityTrait, Uuid};
+use error::{AppResult, Error};
+use fs::{prelude::*, MAX_CHUNK_SIZE_BYTES};
+use futures::{Stream, StreamExt};
+
+use crate::{
+    data::{
+        app_file::AppFile,
+        create_file::CreateFile,
+        imports::{Credentials, Progress},
+    },
+    jobs::queue_purge,
+    repository::{
+        self, cached,
+        escrow::{self, RecoveryKey},
+        Repository,
+    },
+};
+
+/// Content of the remote file as it is downloaded
+pub(crate) type RemoteBody = Pin<Box<dyn Stream<Item = AppResult<Bytes>> + Send>>;
+
+/// File or folder in the source
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct RemoteEntry {
+    /// Path the source knows the entry by, used to list or download it
+    pub(crate) path: String,
+    pub(crate) name: String,
+    pub(crate) is_dir: bool,
+    pub(crate) size: Option<i64>,
+    pub(crate) mime: Option<String>,
+    pub(crate) modified_at: Option<i64>,
+}
+
+/// Service the files are imported from
+#[async_trait]
+pub(crate) trait Source: Send + Sync {
+    /// Path of the folder the import starts in
+    fn root(&self) -> String;
+
+    /// List the files and folders directly in the folder
+    async fn list(&self, path: &str) -> AppResult<Vec<RemoteEntry>>;
+
+    /// Download the content of the file
+    async fn download(&self, entry: &RemoteEntry) -> AppResult<RemoteBody>;
+}
+
+/// Body of the response as it arrives, read chunk by chunk
+pub(crate) fn body(response: reqwest::Response) -> RemoteBody {
+    Box::pin(futures::stream::try_unfold(
+        response,
+        |mut response| async move { Ok(response.chunk().await?.map(|chunk| (chunk, response))) },
+    ))
+}
+
+/// Source of the import with its credentials
+pub(crate) fn source(import: &imports::Model) -> AppResult<Box<dyn Source>> {
+    let credentials: Credentials = match import.credentials.as_deref() {
+        Some(credentials) => serde_json::from_str(credentials)?,
+        None => return Err(Error::BadRequest("import_credentials_missing".to_string())),
+    };
+
+    Ok(match import.source {
+        imports::Source::Webdav => Box::new(webdav::WebDav::new(&import.location, credentials)?),
+        imports::Source::Dropbox => Box::new(dropbox::Dropbox::new(&import.location, credentials)),
+    })
+}
+
+/// Import the whole tree from the source, the files that fail are recorded with the import.
+/// Returns an error only when the source can't be read at all, or the import was stopped.
+pub(crate) async fn run(
+    context: &Context,
+    import: &imports::Model,
+    source: &dyn Source,
+) -> AppResult<Progress> {
+    let user = users::Entity::find_by_id(import.user_id)
+        .one(&context.db)
+        .await?
+        .ok_or_else(|| Error::NotFound("user_not_found".to_string()))?;
+
+    let importer = Importer {
+        sealer: Sealer::new(context, user).await?,
+        source,
+    };
+
+    let mut progress = Progress::default();
+    let mut queue = VecDeque::from([(source.root(), import.file_id)]);
+    let mut is_root = true;
+
+    while let Some((path, parent_id)) = queue.pop_front() {
+        let entries = match source.list(&path).await {
+            Ok(entries) => entries,
+            // Nothing can be imported when the import folder itself can't be read
+            Err(e) if is_root => return Err(e),
+            Err(e) => {
+                progress.failed(&path, e.to_string());
+                continue;
+            }
+        };
+        is_root = false;
+
+        for entry in entries {
+            if !repository::imports::is_running(&context.db, import.id).await? {
+                return Ok(progress);
+            }
+
+            let result = match entry.is_dir {
+                true => importer.folder(&entry, parent_id).await.map(|id| {
+                    queue.push_back((entry.path.clone(), Some(id)));
+                }),
+                false => importer
+                    .file(&entry, parent_id)
+                    .await
+                    .map(|imported| match imported {
+                        Some(size) => progress.imported(size),
+                        None => progress.skipped(),
+                    }),
+            };
+
+            if let Err(e) = result {
+                progress.failed(&entry.path, e.to_string());
+            }
+
+            repository::imports::progress(&context.db, import.id, &progress).await?;
+        }
+    }
+
+    Ok(progress)
+}
+
+/// Key of the imported file or folder and its name, encrypted the same way the clients do it
+pub(crate) struct Sealed {
+    pub(crate) key: Vec<u8>,
+    pub(crate) encrypted_key: String,
+    pub(crate) escrow_key: Option<String>,
+    pub(crate) name_hash: String,
+    pub(crate) encrypted_name: String,
+    pub(crate) search_tokens_hashed: Vec<String>,
+}
+
+impl Sealed {
+    pub(crate) fn new(
+        name: &str,
+        public_key: &str,
+        recovery_key: Option<&RecoveryKey>,
+    ) -> AppResult<Self> {
+        let key = cryptfns::aes::generate_key()?;
+        let key_hex = cryptfns::hex::encode(&key);
+
+        let escrow_key = match recovery_key {
+            Some(recovery_key) => Some(cryptfns::rsa::public::encrypt(
+                &key_hex,
+                &recovery_key.public_key,
+            )?),
+            None => None,
+        };
+
+        let encrypted_name = cryptfns::aes::encrypt(key.clone(), name.as_bytes().to_vec())?;
+
+        Ok(Self {
+            encrypted_key: cryptfns::rsa::public::encrypt(&key_hex, public_key)?,
+            escrow_key,
+            name_hash: cryptfns::sha256::digest(name.as_bytes()),
+            encrypted_name: cryptfns::hex::encode(encrypted_name),
+            search_tokens_hashed: cryptfns::tokenizer::into_hashed_tokens(name)?
+                .into_iter()
+                .map(|token| token.to_string())
+                .collect(),
+            key,
+        })
+    }
+}
+
+struct Importer<'ctx> {
+    sealer: Sealer<'ctx>,
+    source: &'ctx dyn Source,
+}
+
+impl<'ctx> Importer<'ctx> {
+    /// Create the folder, or reuse the existing one with the same name
+    async fn folder(&self, entry: &RemoteEntry, parent_id: Option<Uuid>) -> AppResult<Uuid> {
+        let sealed = self.sealer.seal(&entry.name)?;
+
+        if let Ok(existing) = self.sealer.existing(&sealed, parent_id).await {
+            return match existing.is_dir() {
+                true => Ok(existing.id),
+                false => Err(Error::BadRequest("file_or_directory_exists".to_string())),
+            };
+        }
+
+        let folder = self.sealer.folder(&sealed, parent_id).await?;
+
+        Ok(folder.id)
+    }
+
+    /// Create the file and store its content, returns the size of the imported
+    /// file or nothing when the file with the same name already exists.
+    async fn file(&self, entry: &RemoteEntry, parent_id: Option<Uuid>) -> AppResult<Option<i64>> {
+        let size = match entry.size {
+            Some(0) => return Ok(None),
+            Some(size) => size,
+            None => return Err(Error::BadRequest("unknown_file_size".to_string())),
+        };
+
+        let sealed = self.sealer.seal(&entry.name)?;
+
+        if self.sealer.existing(&sealed, parent_id).await.is_ok() {
+            return Ok(None);
+        }
+
+        let mime = entry
+            .mime
+            .clone()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        let body = self.source.download(entry).await?;
+
+        self.sealer
+            .file(&sealed, parent_id, mime, size, entry.modified_at, body)
+            .await?;
+
+        Ok(Some(size))
+    }
+}
+
+/// Creates the files and folders of the user on the server, encrypted for the user
+/// the same way the clients would do it. Used by the imports and the WebDAV uploads.
+pub(crate) struct Sealer<'ctx> {
+    context: &'ctx Context,
+    user: users::Model,
+    recovery_key: Option<RecoveryKey>,
+}
+
+impl<'ctx> Sealer<'ctx> {
+    pub(crate) async fn new(context: &'ctx Context, user: users::Model) -> AppResult<Self> {
+        Ok(Self {
+            context,
+            user,
+            recovery_key: escrow::recovery_key(context).await?,
+        })
+    }
+
+    /// Generate the key for the new file or folder and encrypt its name with it
+    pub(crate) fn seal(&self, name: &str) -> AppResult<Sealed> {
+        Sealed::new(name, &self.user.pubkey, self.recovery_key.as_ref())
+    }
+
+    /// Existing file or folder of the user with the same name in the folder
+    async fn existing(&self, sealed: &Sealed, parent_id: Option<Uuid>) -> AppResult<AppFile> {
+        Repository::new(&self.context.db)
+            .manage(self.user.id)
+            .by_name(&sealed.name_hash, parent_id)
+            .await
+    }
+
+    /// Create the folder
+    pub(crate) async fn folder(
+        &self,
+        sealed: &Sealed,
+        parent_id: Option<Uuid>,
+    ) -> AppResult<AppFile> {
+        self.create(sealed, parent_id, Some("dir".to_string()), None, None)
+            .await
+    }
+
+    /// Create the file and store its content read from the body, the file is removed
+    /// again when the body doesn't have exactly the given size.
+    pub(crate) async fn file<S>(
+        &self,
+        sealed: &Sealed,
+        parent_id: Option<Uuid>,
+        mime: String,
+        size: i64,
+        modified_at: Option<i64>,
+        body: S,
+    ) -> AppResult<AppFile>
+    where
+        S: Stream<Item = AppResult<Bytes>> + Unpin,
+    {
+        let repository = Repository::new(&self.context.db);
+        let manage = repository.manage(self.user.id);
+
+        let quota = match self.user.quota {
+            Some(quota) => Some(quota as u64),
+            None => self.context.settings.inner().await.users.quota_bytes(),
+        };
+
+        repository
+            .query(self.user.id)
+            .check_quota(quota, size)
+            .await?;
+
+        let file = self
+            .create(sealed, parent_id, Some(mime), Some(size), modified_at)
+            .await?;
+
+        let stored = match self.store(&file, &sealed.key, body).await {
+            Ok(()) => manage.finish(&file).await,
+            Err(e) => {
+                let files = manage.delete_many(vec![file.id]).await?;
+                queue_purge(&self.context.db, &files).await?;
+
+                Err(e)
+            }
+        };
+
+        cached::invalidate(self.user.id, &[file.id]).await;
+
+        stored
+    }
+
+    async fn create(
+        &self,
+        sealed: &Sealed,
+        parent_id: Option<Uuid>,
+        mime: Option<String>,
+        size: Option<i64>,
+        modified_at: Option<i64>,
+    ) -> AppResult<AppFile> {
+        let chunks = size
+            .map(|size| ((size as u64 + MAX_CHUNK_SIZE_BYTES - 1) / MAX_CHUNK_SIZE_BYTES) as i64);
+
+        let create_file = CreateFile {
+            id: None,
+            encrypted_key: Some(sealed.encrypted_key.clone()),
+            name_hash: Some(sealed.name_hash.clone()),
+            encrypted_name: Some(sealed.encrypted_name.clone()),
+            encrypted_thumbnail: None,
+            search_tokens_hashed: Some(sealed.search_tokens_hashed.clone()),
+            mime,
+            size,
+            chunks,
+            file_id: parent_id.map(|id| id.to_string()),
+            file_modified_at: modified_at
+                .and_then(|timestamp| NaiveDateTime::from_timestamp_opt(timestamp, 0))
+                .map(|date| date.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+            sha256: None,
+            conflict_name_hash: None,
+            encrypted_conflict_name: None,
+            device: None,
+            crypto_version: None,
+            escrow_key: None,
+            magic: None,
+        };
+
+        let (create_file, encrypted_key, hashed_tokens, _, _) = create_file.into_active_model()?;
+
+        let repository = Repository::new(&self.context.db);
+        let file = repository
+            .manage(self.user.id)
+            .create(create_file, &encrypted_key, hashed_tokens)
+            .await?;
+
+        escrow::escrow(
+            &self.context.db,
+            self.recovery_key.as_ref(),
+            file.id,
+            sealed.escrow_key.as_deref(),
+        )
+        .await?;
+
+        let mut changed = vec![file.id];
+        changed.extend(parent_id);
+        cached::invalidate(self.user.id, &changed).await;
+
+        Ok(file)
+    }
+
+    /// Read the content from the body and store it encrypted in chunks
+    async fn store<S>(&self, file: &AppFile, key: &[u8], mut body: S) -> AppResult<()>
+    where
+        S: Stream<Item = AppResult<Bytes>> + Unpin,
+    {
+        let fs = Fs::new(&self.context.config);
+        let scheme = Scheme::from_version(file.crypto_version)?;
+        let chunk_size = MAX_CHUNK_SIZE_BYTES as usize;
+
+        let mut buffer = Vec::with_capacity(chunk_size);
+        let mut chunk = 0;
+        let mut size = 0;
+
+        loop {
+            let bytes = body.next().await.transpose()?;
+            let done = bytes.is_none();
+
+            if let Some(bytes) = bytes {
+                size += bytes.len() as i64;
+                buffer.extend_from_slice(&bytes);
+            }
+
+            // Body is bigger than the file was said to be
+            if size > file.size.unwrap_or(0) {
+                return Err(Error::BadRequest("file_size_mismatch".to_string()));
+            }
+
+            while buffer.len() >= chunk_size || (done && !buffer.is_empty()) {
+                let rest = buffer.split_off(buffer.len().min(chunk_size));
+                let plaintext = std::mem::replace(&mut buffer, rest);
+
+                fs.push(file, chunk, &scheme.encrypt(key.to_vec(), plaintext)?)
+                    .await?;
+                chunk += 1;
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        if Some(size) != file.size || Some(chunk) != file.chunks {
+            return Err(Error::BadRequest("file_size_mismatch".to_string()));
+        }
+
+        Ok(())
+    }
+}
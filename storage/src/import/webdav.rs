@@ -0,0 +1,192 @@
+//! WebDAV source, Nextcloud and ownCloud serve the files of the user through it
+//! under `/remote.php/dav/files/<username>/`.
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use error::{AppResult, Error};
+use percent_encoding::percent_decode_str;
+use quick_xml::{events::Event, Reader};
+use reqwest::{header, Client, Method, RequestBuilder, Url};
+
+use super::{body, RemoteBody, RemoteEntry, Source};
+use crate::data::imports::Credentials;
+
+/// Properties of the files and folders that are asked for when listing a folder
+const PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getcontenttype/>
+    <d:getlastmodified/>
+  </d:prop>
+</d:propfind>"#;
+
+pub(crate) struct WebDav {
+    client: Client,
+    root: Url,
+    credentials: Credentials,
+}
+
+impl WebDav {
+    pub(crate) fn new(location: &str, credentials: Credentials) -> AppResult<Self> {
+        let mut root =
+            Url::parse(location).map_err(|_| Error::as_validation("location", "invalid_url"))?;
+
+        // Without the trailing slash the folder would be replaced when joining the paths
+        if !root.path().ends_with('/') {
+            root.set_path(&format!("{}/", root.path()));
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            root,
+            credentials,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> AppResult<RequestBuilder> {
+        let url = self
+            .root
+            .join(path)
+            .map_err(|_| Error::BadRequest(format!("invalid_webdav_path:{}", path)))?;
+
+        let request = self.client.request(method, url);
+
+        Ok(match self.credentials.username.as_deref() {
+            Some(username) => request.basic_auth(username, self.credentials.password.as_deref()),
+            None => request,
+        })
+    }
+}
+
+#[async_trait]
+impl Source for WebDav {
+    fn root(&self) -> String {
+        self.root.path().to_string()
+    }
+
+    async fn list(&self, path: &str) -> AppResult<Vec<RemoteEntry>> {
+        let method = Method::from_bytes(b"PROPFIND").unwrap();
+
+        let response = self
+            .request(method, path)?
+            .header("Depth", "1")
+            .header(header::CONTENT_TYPE, "application/xml")
+            .body(PROPFIND)
+            .send()
+            .await?;
+
+        if response.status().as_u16() != 207 {
+            return Err(Error::BadRequest(format!(
+                "webdav_status:{}",
+                response.status().as_u16()
+            )));
+        }
+
+        let folder = decode(path);
+        let entries = parse_multistatus(&response.text().await?)?
+            .into_iter()
+            .filter(|entry| decode(&entry.path) != folder)
+            .collect();
+
+        Ok(entries)
+    }
+
+    async fn download(&self, entry: &RemoteEntry) -> AppResult<RemoteBody> {
+        let response = self.request(Method::GET, &entry.path)?.send().await?;
+
+        if !response.status().is_success() {
+            return Err(Error::BadRequest(format!(
+                "webdav_status:{}",
+                response.status().as_u16()
+            )));
+        }
+
+        Ok(body(response))
+    }
+}
+
+/// Decoded path without the trailing slash, servers differ in how they encode the paths
+fn decode(path: &str) -> String {
+    percent_decode_str(path.trim_end_matches('/'))
+        .decode_utf8_lossy()
+        .to_string()
+}
+
+/// Parse the response to the `PROPFIND` request, the namespace
+/// prefixes differ between the servers so they are ignored.
+pub(crate) fn parse_multistatus(xml: &str) -> AppResult<Vec<RemoteEntry>> {
+    let invalid = |e: quick_xml::Error| Error::BadRequest(format!("invalid_webdav_response:{}", e));
+
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut entries = vec![];
+    let mut entry: Option<RemoteEntry> = None;
+    let mut field = String::new();
+
+    loop {
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"response" => entry = Some(RemoteEntry::default()),
+                b"collection" => entry.iter_mut().for_each(|entry| entry.is_dir = true),
+                name => field = String::from_utf8_lossy(name).to_string(),
+            },
+            Event::Empty(e) => {
+                if e.local_name().as_ref() == b"collection" {
+                    entry.iter_mut().for_each(|entry| entry.is_dir = true);
+                }
+            }
+            Event::Text(text) => {
+                let value = text.unescape().map_err(invalid)?;
+
+                if let Some(entry) = entry.as_mut() {
+                    match field.as_str() {
+                        "href" => entry.path = href_path(&value),
+                        "getcontentlength" => entry.size = value.parse().ok(),
+                        "getcontenttype" => entry.mime = Some(value.to_string()),
+                        "getlastmodified" => {
+                            entry.modified_at = DateTime::parse_from_rfc2822(&value)
+                                .ok()
+                                .map(|date| date.timestamp())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"response" {
+                    if let Some(mut entry) = entry.take() {
+                        entry.name = decode(&entry.path)
+                            .rsplit('/')
+                            .next()
+                            .unwrap_or_default()
+                            .to_string();
+
+                        if entry.is_dir {
+                            entry.size = None;
+                            entry.mime = None;
+                        }
+
+                        entries.push(entry);
+                    }
+                }
+
+                field.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Some servers send the whole URL in the `href`, only its path is used
+fn href_path(href: &str) -> String {
+    match Url::parse(href) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => href.to_string(),
+    }
+}
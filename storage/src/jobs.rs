@@ -0,0 +1,606 @@
+//! # Storage background jobs
+//!
+//! Removing the file chunks from the storage provider can take a while for
+//! big files and directories, so it is done by the background worker.
+//!
+//! The worker also closes the file requests once their deadline passes
+//! and lets the owner know what was received through them, and it removes
+//! the expired shares after reminding their users a few days before, the owner
+//! then gets the re-wrap job for the key of the file the same as when revoking.
+//!
+//! Exports of the user data are built here as well, packing the encrypted
+//! files into the archive takes as long as reading all of them.
+//!
+//! Chunks that were served from the replica because they were damaged
+//! are copied back to the default storage provider by the worker too, and so is
+//! the new content of the file that was committed but couldn't be written in place.
+//!
+//! Imports of the files from other services run here, one file after another
+//! for as long as it takes to read them all from the source, and so do
+//! the exports of the folders to the external providers.
+//!
+//! Rebalances of the chunks between the shards started by the admin
+//! are run here too, moving the chunks for as long as it takes.
+//!
+//! Restores of the archived files wait here for S3 to make the chunks
+//! readable, the job checks back on them until they can be copied back.
+//!
+//! Uploads over the tus protocol that were never finished are removed
+//! here once they expire, with the bytes that were received for them.
+//!
+//! Files and folders that stay in the trash for longer than the trash
+//! retention are deleted for good here as well.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use context::Context;
+use entity::{
+    exports, file_requests, files, tus_uploads, user_files, users, ActiveValue, ColumnTrait,
+    ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait, Uuid,
+};
+use error::{AppResult, Error};
+use fs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive,
+    data::{
+        app_file::AppFile,
+        changes::Action,
+        exports::ExportArchive,
+        thumbnail::{Thumbnail, ThumbnailSize},
+        tus::TusStaging,
+    },
+    emails, export, external, import, rebalance,
+    repository::{self, cached, file_requests::summary, lifecycles, Repository},
+};
+
+/// Kind of the job that purges file chunks from the storage provider
+pub const PURGE_FILES: &str = "storage.purge_files";
+
+/// Kind of the job that closes the file request and sends the summary to its owner
+pub const CLOSE_FILE_REQUEST: &str = "storage.close_file_request";
+
+/// Kind of the job that removes the share once it expires
+pub const EXPIRE_SHARE: &str = "storage.expire_share";
+
+/// Kind of the job that reminds the user their access to the shared file is ending
+pub const REMIND_SHARE: &str = "storage.remind_share";
+
+/// Kind of the job that builds the archive with the data of the user
+pub const EXPORT_USER_DATA: &str = "storage.export_user_data";
+
+/// Kind of the job that restores the damaged chunks from the replica
+pub const REPAIR_CHUNKS: &str = "storage.repair_chunks";
+
+/// Kind of the job that writes the committed content of the file over its chunk
+pub const SWAP_CONTENT: &str = "storage.swap_content";
+
+/// Kind of the job that imports the files from another service
+pub const IMPORT_FILES: &str = "storage.import_files";
+
+/// Kind of the job that exports the folder to an external provider
+pub const EXPORT_EXTERNAL: &str = "storage.export_external";
+
+/// Kind of the job that moves the chunks between the shards
+pub const REBALANCE_STORAGE: &str = "storage.rebalance_storage";
+
+/// Kind of the job that restores the chunks of the archived file
+pub const RESTORE_ARCHIVED: &str = "storage.restore_archived";
+
+/// Kind of the job that removes the unfinished tus upload once it expires
+pub const EXPIRE_TUS_UPLOAD: &str = "storage.expire_tus_upload";
+
+/// Kind of the job that deletes the files in the trash for good once the retention passes
+pub const EMPTY_TRASH: &str = "storage.empty_trash";
+
+/// File that was deleted from the database and still has chunks in the storage provider
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurgeFile {
+    pub id: Uuid,
+    pub created_at: i64,
+}
+
+impl IntoFilename for PurgeFile {
+    fn filename(&self) -> AppResult<Filename> {
+        Ok(Filename::new(self.id).with_timestamp(self.created_at))
+    }
+}
+
+/// Queue the chunks of the deleted files for purging, directories are skipped.
+/// Should be called in the same transaction the files were deleted in.
+pub(crate) async fn queue_purge<T: ConnectionTrait>(
+    connection: &T,
+    files: &[AppFile],
+) -> AppResult<()> {
+    let files = files
+        .iter()
+        .filter(|file| file.is_file())
+        .map(|file| PurgeFile {
+            id: file.id,
+            created_at: file.created_at,
+        })
+        .collect::<Vec<_>>();
+
+    if !files.is_empty() {
+        jobs::repository::Repository::new(connection)
+            .push(PURGE_FILES, &files)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Purge the chunks and the thumbnails of deleted files from the storage provider
+pub struct PurgeFiles;
+
+#[async_trait]
+impl jobs::worker::Handler for PurgeFiles {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let files: Vec<PurgeFile> = serde_json::from_str(payload)?;
+        let fs = Fs::new(&context.config);
+
+        // Purging the file twice is harmless, so the whole job is simply retried on error
+        for file in files.iter() {
+            fs.purge(file).await?;
+
+            for size in ThumbnailSize::ALL {
+                fs.purge(&Thumbnail::new(file.id, file.created_at, size))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// File request that should be closed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CloseFileRequest {
+    pub id: Uuid,
+}
+
+/// Close the file requests after their deadline and send the owner
+/// the summary of the files that were received.
+pub struct CloseFileRequests;
+
+#[async_trait]
+impl jobs::worker::Handler for CloseFileRequests {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: CloseFileRequest = serde_json::from_str(payload)?;
+        let now = Utc::now().timestamp();
+
+        // Request was deleted with its folder in the meantime
+        let request = match file_requests::Entity::find_by_id(payload.id)
+            .one(&context.db)
+            .await?
+        {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+
+        if request.notified_at.is_some() || request.is_open(now) {
+            return Ok(());
+        }
+
+        let closed_at = request.closed_at.or(request.deadline).unwrap_or(now);
+
+        file_requests::Entity::update(file_requests::ActiveModel {
+            id: ActiveValue::Set(request.id),
+            closed_at: ActiveValue::Set(Some(closed_at)),
+            ..Default::default()
+        })
+        .exec(&context.db)
+        .await?;
+
+        let owner = users::Entity::find_by_id(request.user_id)
+            .one(&context.db)
+            .await?
+            .ok_or_else(|| Error::NotFound("user_not_found".to_string()))?;
+
+        let summary = summary(&context.db, request.id).await?;
+
+        // Sending fails the job, so it is retried and the summary is sent only once
+        emails::file_request::send(context, &owner, &request, &summary).await?;
+
+        file_requests::Entity::update(file_requests::ActiveModel {
+            id: ActiveValue::Set(request.id),
+            notified_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .exec(&context.db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Share with the expiration it was scheduled for, the job does nothing
+/// if the expiration was changed or removed in the meantime.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShareExpiration {
+    pub id: Uuid,
+    pub expires_at: i64,
+}
+
+impl ShareExpiration {
+    /// Load the share if it still expires at the scheduled time
+    async fn share(&self, context: &Context) -> AppResult<Option<user_files::Model>> {
+        let share = user_files::Entity::find_by_id(self.id)
+            .one(&context.db)
+            .await?
+            .filter(|share| !share.is_owner && share.expires_at == Some(self.expires_at));
+
+        Ok(share)
+    }
+}
+
+/// Remove the shares after they expire, the user has already lost the access
+/// to the file when the share expired, this only cleans up after it.
+pub struct ExpireShares;
+
+#[async_trait]
+impl jobs::worker::Handler for ExpireShares {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: ShareExpiration = serde_json::from_str(payload)?;
+
+        let share = match payload.share(context).await? {
+            Some(share) if payload.expires_at <= Utc::now().timestamp() => share,
+            _ => return Ok(()),
+        };
+
+        let parent_id = files::Entity::find_by_id(share.file_id)
+            .one(&context.db)
+            .await?
+            .and_then(|file| file.file_id);
+
+        user_files::Entity::delete_by_id(share.id)
+            .exec(&context.db)
+            .await?;
+
+        Repository::new(&context.db)
+            .activities(share.user_id)
+            .record(Action::Unshared, [(share.file_id, parent_id)])
+            .await?;
+
+        repository::rewrap::plan_revocation(&context.db, share.file_id).await?;
+
+        cached::invalidate(share.user_id, &[share.file_id]).await;
+
+        Ok(())
+    }
+}
+
+/// Let the users know their access to the shared file is about to end
+pub struct RemindShares;
+
+#[async_trait]
+impl jobs::worker::Handler for RemindShares {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: ShareExpiration = serde_json::from_str(payload)?;
+
+        let share = match payload.share(context).await? {
+            Some(share) => share,
+            None => return Ok(()),
+        };
+
+        let user = users::Entity::find_by_id(share.user_id)
+            .one(&context.db)
+            .await?
+            .ok_or_else(|| Error::NotFound("user_not_found".to_string()))?;
+
+        let owner = user_files::Entity::find()
+            .filter(user_files::Column::FileId.eq(share.file_id))
+            .filter(user_files::Column::IsOwner.eq(true))
+            .find_also_related(users::Entity)
+            .one(&context.db)
+            .await?
+            .and_then(|(_, owner)| owner);
+
+        emails::share_expiry::send(context, &user, owner.as_ref(), &share).await
+    }
+}
+
+/// Export of the user data that should be built
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserExport {
+    pub id: Uuid,
+}
+
+/// Build the archives of the user data exports
+pub struct ExportUserData;
+
+#[async_trait]
+impl jobs::worker::Handler for ExportUserData {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: UserExport = serde_json::from_str(payload)?;
+
+        // Export was replaced with a new one in the meantime
+        let export = match exports::Entity::find_by_id(payload.id)
+            .one(&context.db)
+            .await?
+        {
+            Some(export) if export.status == exports::Status::Pending => export,
+            _ => return Ok(()),
+        };
+
+        let result = export::build(context, &export).await;
+
+        // Failed export is not retried, the user can simply request a new one
+        if let Err(e) = result.as_ref() {
+            log::error!("Failed building the export {}: {}", export.id, e);
+
+            let archive = ExportArchive::from(&export);
+            Fs::new(&context.config).purge(&archive).await?;
+        }
+
+        repository::exports::finish(&context.db, export.id, result).await
+    }
+}
+
+/// Chunks of the file that should be restored from the replica
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RepairChunk {
+    pub id: Uuid,
+    pub created_at: i64,
+    pub chunks: Vec<i64>,
+}
+
+impl IntoFilename for RepairChunk {
+    fn filename(&self) -> AppResult<Filename> {
+        Ok(Filename::new(self.id).with_timestamp(self.created_at))
+    }
+}
+
+/// Restore the damaged chunks in the default storage provider from the replica
+pub struct RepairChunks;
+
+#[async_trait]
+impl jobs::worker::Handler for RepairChunks {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: RepairChunk = serde_json::from_str(payload)?;
+        let fs = Fs::new(&context.config);
+
+        // Chunk could have been restored by the job queued from another download
+        let damaged = fs.damaged(&payload, &payload.chunks).await?;
+
+        for chunk in damaged {
+            fs.restore(&payload, chunk).await?;
+            log::info!(
+                "Restored chunk {} of the file {} from the replica",
+                chunk,
+                payload.id
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Content of the file that was committed and is still staged, the chunk
+/// of the file has the previous content until it is written over
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContentSwap {
+    pub file: PurgeFile,
+    pub staged: PurgeFile,
+}
+
+/// Write the staged content over the chunk of the file, the push replaces the chunk at
+/// once so the previous content stays with the version it is linked to.
+pub struct SwapContent;
+
+#[async_trait]
+impl jobs::worker::Handler for SwapContent {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: ContentSwap = serde_json::from_str(payload)?;
+        let fs = Fs::new(&context.config);
+
+        // File could have been deleted in the meantime, with its chunks
+        let exists = files::Entity::find_by_id(payload.file.id)
+            .one(&context.db)
+            .await?
+            .is_some();
+
+        if exists {
+            let content = fs.pull(&payload.staged, 0).await?;
+            fs.push(&payload.file, 0, &content).await?;
+        }
+
+        fs.purge(&payload.staged).await
+    }
+}
+
+/// Import of the files that should be run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub id: Uuid,
+}
+
+/// Import the files from other services
+pub struct ImportFiles;
+
+#[async_trait]
+impl jobs::worker::Handler for ImportFiles {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: ImportJob = serde_json::from_str(payload)?;
+
+        // Import was cancelled before it started
+        let import = match repository::imports::start(&context.db, payload.id).await? {
+            Some(import) => import,
+            None => return Ok(()),
+        };
+
+        let result = match import::source(&import) {
+            Ok(source) => import::run(context, &import, source.as_ref()).await,
+            Err(e) => Err(e),
+        };
+
+        // Failed import is not retried, running it again skips the files that were imported
+        if let Err(e) = result.as_ref() {
+            log::error!("Failed importing the files for {}: {}", import.id, e);
+        }
+
+        repository::imports::finish(&context.db, import.id, result.map(|_| ())).await
+    }
+}
+
+/// External export that should be run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExternalExportJob {
+    pub id: Uuid,
+}
+
+/// Export the folders to the external providers
+pub struct ExportExternal;
+
+#[async_trait]
+impl jobs::worker::Handler for ExportExternal {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: ExternalExportJob = serde_json::from_str(payload)?;
+
+        // Export was cancelled before it started
+        let export = match repository::external_exports::start(&context.db, payload.id).await? {
+            Some(export) => export,
+            None => return Ok(()),
+        };
+
+        let result = match external::target(&export) {
+            Ok(target) => external::run(context, &export, target.as_ref()).await,
+            Err(e) => Err(e),
+        };
+
+        // Failed export is not retried, the user can start a new one
+        if let Err(e) = result.as_ref() {
+            log::error!("Failed exporting the files for {}: {}", export.id, e);
+        }
+
+        repository::external_exports::finish(&context.db, export.id, result.map(|_| ())).await
+    }
+}
+
+/// Rebalance that should be run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RebalanceJob {
+    pub id: Uuid,
+}
+
+/// Move the chunks between the shards by the layout of the rebalance
+pub struct RebalanceStorage;
+
+#[async_trait]
+impl jobs::worker::Handler for RebalanceStorage {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: RebalanceJob = serde_json::from_str(payload)?;
+
+        // Rebalance was cancelled before it started
+        let rebalance = match repository::rebalances::start(&context.db, payload.id).await? {
+            Some(rebalance) => rebalance,
+            None => return Ok(()),
+        };
+
+        let result = rebalance::run(context, &rebalance).await;
+
+        // Failed rebalance is not retried, the admin can resume it
+        if let Err(e) = result.as_ref() {
+            log::error!("Failed rebalancing the storage for {}: {}", rebalance.id, e);
+        }
+
+        repository::rebalances::finish(&context.db, rebalance.id, result).await
+    }
+}
+
+/// Archived file that should be restored
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RestoreArchive {
+    pub id: Uuid,
+}
+
+/// Restore the chunks of the archived files, the job is queued again
+/// until S3 has restored all of them.
+pub struct RestoreArchived;
+
+#[async_trait]
+impl jobs::worker::Handler for RestoreArchived {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: RestoreArchive = serde_json::from_str(payload)?;
+
+        // File was deleted, or restored by the job queued before
+        let lifecycle = match lifecycles::find(&context.db, payload.id).await? {
+            Some(lifecycle) if lifecycle.is_archived() => lifecycle,
+            _ => return Ok(()),
+        };
+
+        let archive = archive::Archive::new(context)?
+            .ok_or_else(|| Error::StorageError("archive_not_configured".to_string()))?;
+
+        if !archive::restore(context, &archive, &lifecycle).await? {
+            jobs::repository::Repository::new(&context.db)
+                .push_at(
+                    RESTORE_ARCHIVED,
+                    &payload,
+                    Utc::now().timestamp() + archive::RESTORE_POLL,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Tus upload that should be removed if it wasn't finished
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExpireTusUpload {
+    pub id: Uuid,
+    pub created_at: i64,
+}
+
+/// Remove the tus uploads that weren't finished before they expired. The received
+/// bytes are removed even if the upload is already gone with its file.
+pub struct ExpireTusUploads;
+
+#[async_trait]
+impl jobs::worker::Handler for ExpireTusUploads {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: ExpireTusUpload = serde_json::from_str(payload)?;
+
+        tus_uploads::Entity::delete_by_id(payload.id)
+            .exec(&context.db)
+            .await?;
+
+        Fs::new(&context.config)
+            .purge(&TusStaging {
+                id: payload.id,
+                created_at: payload.created_at,
+            })
+            .await
+    }
+}
+
+/// Files and folders moved to the trash together, the job does nothing for the ones
+/// that were restored or moved to the trash again in the meantime.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrashedFiles {
+    pub user_id: Uuid,
+    pub ids: Vec<Uuid>,
+    pub deleted_at: i64,
+}
+
+/// Delete the files in the trash for good once their retention passes,
+/// the chunks are purged by the next job the same as for any other deletion.
+pub struct EmptyTrash;
+
+#[async_trait]
+impl jobs::worker::Handler for EmptyTrash {
+    async fn handle(&self, context: &Context, payload: &str) -> AppResult<()> {
+        let payload: TrashedFiles = serde_json::from_str(payload)?;
+
+        let connection = context.db.begin().await?;
+        let files = repository::trash::expire(&connection, &payload).await?;
+        connection.commit().await?;
+
+        let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+        cached::invalidate(payload.user_id, &ids).await;
+
+        Ok(())
+    }
+}
@@ -1,7 +1,29 @@
+pub(crate) mod authorize;
+pub(crate) mod dav;
+pub(crate) mod emails;
+pub(crate) mod export;
+pub(crate) mod external;
+pub(crate) mod import;
+pub(crate) mod rebalance;
 pub(crate) mod repository;
+pub(crate) mod s3;
+pub(crate) mod sniff;
 
+pub mod archive;
+pub mod blobs;
 pub mod data;
+#[cfg(feature = "mqtt")]
+pub mod events;
+pub mod jobs;
 pub mod routes;
+pub mod tiering;
+pub mod transfers;
+
+pub use repository::cached::{invalidate, invalidate_files, invalidate_listings};
+pub use repository::escrow::{escrow_if_missing, recovery_fingerprint, recovery_key, RecoveryKey};
+pub use repository::holds::{audit, guard_delete};
+pub use repository::policies::effective_policy;
+pub use repository::verdicts::{guard_download, verdicts};
 
 #[cfg(test)]
 mod test;
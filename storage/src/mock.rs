@@ -30,6 +30,7 @@ pub async fn create_file<'ctx>(
             .collect::<Vec<_>>();
 
     let file = CreateFile {
+        id: None,
         encrypted_key: Some(name.to_string()),
         encrypted_name: Some(name.to_string()),
         encrypted_thumbnail: None,
@@ -40,6 +41,13 @@ pub async fn create_file<'ctx>(
         chunks,
         file_id: file_id.map(|f| f.to_string()),
         file_modified_at: None,
+        sha256: None,
+        conflict_name_hash: None,
+        encrypted_conflict_name: None,
+        device: None,
+        crypto_version: None,
+        escrow_key: None,
+        magic: None,
     };
 
     let (am, _, tokens, _, _) = file.into_active_model()?;
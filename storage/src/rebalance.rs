@@ -0,0 +1,134 @@
+//! # Storage rebalance
+//!
+//! Moves the chunks between the shard directories until each of them holds its share
+//! of all the chunks by the weights in the layout of the rebalance. Without the layout
+//! every shard is filled to the same percentage of its disk.
+//!
+//! Nothing is planned ahead, the shards are measured on each run, so the rebalance
+//! that was interrupted or cancelled simply continues from where the chunks are.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use context::Context;
+use entity::rebalances;
+use error::{AppResult, Error};
+use fs::{prelude::*, shards};
+
+use crate::repository;
+
+/// Run the rebalance, it returns early when it gets cancelled
+pub(crate) async fn run(context: &Context, rebalance: &rebalances::Model) -> AppResult<()> {
+    let dirs = Fs::new(&context.config).shard_dirs();
+
+    let removed = shards::cleanup(&dirs).await?;
+    if removed > 0 {
+        log::info!(
+            "Removed {} chunks left over from the previous moves",
+            removed
+        );
+    }
+
+    let mut usage = vec![];
+    for dir in dirs.iter() {
+        usage.push(shards::usage(dir).await?);
+    }
+
+    let weights = weights(rebalance, &usage)?;
+    let mut used = usage.iter().map(|shard| shard.used).collect::<Vec<_>>();
+    let targets = shards::targets(used.iter().sum(), &weights);
+    let mut throttle = Throttle::new(rebalance.bytes_per_second);
+
+    for source in 0..dirs.len() {
+        if used[source] <= targets[source] {
+            continue;
+        }
+
+        for chunk in shards::chunks(&dirs[source]).await? {
+            if used[source] <= targets[source] {
+                break;
+            }
+
+            // Shard that is missing the most, the chunk must not push it over its target
+            let target = (0..dirs.len())
+                .filter(|i| used[*i] + chunk.size <= targets[*i])
+                .max_by_key(|i| targets[*i] - used[*i]);
+
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+
+            shards::move_chunk(&dirs[source], &dirs[target], &chunk).await?;
+            used[source] -= chunk.size;
+            used[target] += chunk.size;
+
+            if !repository::rebalances::progress(&context.db, rebalance.id, chunk.size).await? {
+                log::info!("Rebalance {} was cancelled", rebalance.id);
+
+                return Ok(());
+            }
+
+            throttle.wait(chunk.size).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Weight of each of the shards, in the same order as the shards
+fn weights(rebalance: &rebalances::Model, usage: &[shards::ShardUsage]) -> AppResult<Vec<u64>> {
+    let layout = match rebalance.layout.clone() {
+        Some(layout) => serde_json::from_value::<BTreeMap<String, u64>>(layout)?,
+        None => return Ok(usage.iter().map(|s| s.used + s.available).collect()),
+    };
+
+    // Shards could have been reconfigured since the rebalance was started
+    if let Some(dir) = layout
+        .keys()
+        .find(|dir| !usage.iter().any(|shard| shard.dir == **dir))
+    {
+        return Err(Error::StorageError(format!("unknown_shard:{}", dir)));
+    }
+
+    Ok(usage
+        .iter()
+        .map(|shard| layout.get(&shard.dir).copied().unwrap_or(0))
+        .collect())
+}
+
+/// Keeps the moving of the chunks under the given number of bytes per second
+struct Throttle {
+    bytes_per_second: Option<u64>,
+    started: Instant,
+    bytes: u64,
+}
+
+impl Throttle {
+    fn new(bytes_per_second: Option<i64>) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.map(|b| b.max(1) as u64),
+            started: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Wait until moving the given bytes is within the limit
+    async fn wait(&mut self, bytes: u64) {
+        self.bytes += bytes;
+
+        let bytes_per_second = match self.bytes_per_second {
+            Some(bytes_per_second) => bytes_per_second,
+            None => return,
+        };
+
+        let expected = Duration::from_secs_f64(self.bytes as f64 / bytes_per_second as f64);
+        let elapsed = self.started.elapsed();
+
+        if expected > elapsed {
+            actix_web::rt::time::sleep(expected - elapsed).await;
+        }
+    }
+}
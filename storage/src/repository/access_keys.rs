@@ -0,0 +1,123 @@
+//! Repository module for the access keys of the S3 gateway.
+
+use chrono::Utc;
+use entity::{
+    access_keys, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+
+/// How many access keys a single user can have
+const MAX_ACCESS_KEYS: u64 = 20;
+
+pub(crate) struct AccessKeys<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> AccessKeys<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the access keys of the user, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<access_keys::Model>> {
+        let access_keys = access_keys::Entity::find()
+            .filter(access_keys::Column::UserId.eq(self.user_id))
+            .order_by_desc(access_keys::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(access_keys)
+    }
+
+    /// Get the access key of the user
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<access_keys::Model> {
+        access_keys::Entity::find_by_id(id)
+            .filter(access_keys::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("access_key_not_found".to_string()))
+    }
+
+    /// Create the access key, returns it along with its secret
+    pub(crate) async fn create(&self, name: String) -> AppResult<(access_keys::Model, String)> {
+        let count = access_keys::Entity::find()
+            .filter(access_keys::Column::UserId.eq(self.user_id))
+            .count(self.repository.connection())
+            .await?;
+
+        if count >= MAX_ACCESS_KEYS {
+            return Err(Error::as_validation(
+                "name",
+                &format!("max_access_keys:{}", MAX_ACCESS_KEYS),
+            ));
+        }
+
+        let id = Uuid::new_v4();
+        let secret_access_key = util::generate::generate_secret();
+
+        let access_key = access_keys::Model {
+            id,
+            user_id: self.user_id,
+            name,
+            access_key_id: format!("HK{}", &id.simple().to_string()[..18]).to_uppercase(),
+            secret_access_key: secret_access_key.clone(),
+            created_at: Utc::now().timestamp(),
+            last_used_at: None,
+        };
+
+        access_keys::Entity::insert(access_keys::ActiveModel::from(access_key.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        Ok((access_key, secret_access_key))
+    }
+
+    /// Delete the access key, the objects uploaded with it stay in the folders
+    /// of the user as the regular files, the S3 tools just can't reach them anymore.
+    pub(crate) async fn delete(&self, id: Uuid) -> AppResult<()> {
+        self.get(id).await?;
+
+        access_keys::Entity::delete_by_id(id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Find the access key the request was signed with
+pub(crate) async fn by_access_key_id<T: ConnectionTrait>(
+    connection: &T,
+    access_key_id: &str,
+) -> AppResult<Option<access_keys::Model>> {
+    let access_key = access_keys::Entity::find()
+        .filter(access_keys::Column::AccessKeyId.eq(access_key_id))
+        .one(connection)
+        .await?;
+
+    Ok(access_key)
+}
+
+/// Remember when the access key was last used
+pub(crate) async fn touch<T: ConnectionTrait>(connection: &T, id: Uuid) -> AppResult<()> {
+    access_keys::Entity::update_many()
+        .set(access_keys::ActiveModel {
+            last_used_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(access_keys::Column::Id.eq(id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
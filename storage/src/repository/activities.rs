@@ -0,0 +1,206 @@
+//! Repository module for the activity journal of the user files.
+//!
+//! Every change of a file is recorded with a monotonic id, so the sync clients can
+//! ask only for the changes after the last id they have seen instead of listing everything again.
+//!
+//! The ids become visible in the order they were taken, an id is never committed after
+//! a greater one was already seen, see `m20240304_080000_add_activities_sequence`.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use entity::{
+    activities, files, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Uuid,
+};
+use error::AppResult;
+
+use crate::data::{
+    app_file::AppFile,
+    changes::{Action, Change, ChangesResponse, JournalResponse},
+};
+
+use super::Repository;
+
+pub(crate) struct Activities<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Activities<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// Record the same action for many files, each of the files is given with
+    /// the id of the directory the change happened in.
+    pub(crate) async fn record<I>(&self, action: Action, files: I) -> AppResult<()>
+    where
+        I: IntoIterator<Item = (Uuid, Option<Uuid>)>,
+    {
+        let created_at = Utc::now().timestamp();
+
+        let models = files
+            .into_iter()
+            .map(|(file_id, parent_id)| activities::ActiveModel {
+                id: ActiveValue::NotSet,
+                user_id: ActiveValue::Set(self.user_id),
+                file_id: ActiveValue::Set(file_id),
+                parent_id: ActiveValue::Set(parent_id),
+                action: ActiveValue::Set(action),
+                created_at: ActiveValue::Set(created_at),
+            })
+            .collect::<Vec<_>>();
+
+        if models.is_empty() {
+            return Ok(());
+        }
+
+        activities::Entity::insert_many(models)
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the changes of the files in the directory after the given cursor.
+    ///
+    /// Only the latest change of each file is returned, with the current state of the file,
+    /// a file that was moved out of the directory is returned as deleted.
+    pub(crate) async fn changes(
+        &self,
+        dir_id: Option<Uuid>,
+        since: i64,
+        limit: u64,
+    ) -> AppResult<ChangesResponse> {
+        let mut query = activities::Entity::find()
+            .filter(activities::Column::UserId.eq(self.user_id))
+            .filter(activities::Column::Id.gt(since));
+
+        query = match dir_id {
+            Some(dir_id) => query.filter(activities::Column::ParentId.eq(dir_id)),
+            None => query.filter(activities::Column::ParentId.is_null()),
+        };
+
+        let mut activities = query
+            .order_by_asc(activities::Column::Id)
+            .limit(limit + 1)
+            .all(self.repository.connection())
+            .await?;
+
+        let has_more = activities.len() as u64 > limit;
+        activities.truncate(limit as usize);
+
+        let cursor = activities.last().map(|a| a.id).unwrap_or(since);
+
+        // Keep only the latest activity of each file, in the order they happened
+        let mut latest: Vec<activities::Model> = vec![];
+        for activity in activities.into_iter().rev() {
+            if !latest.iter().any(|a| a.file_id == activity.file_id) {
+                latest.push(activity);
+            }
+        }
+        latest.reverse();
+
+        let ids = latest
+            .iter()
+            .filter(|a| a.action != Action::Deleted)
+            .map(|a| a.file_id)
+            .collect::<Vec<_>>();
+
+        let mut files = vec![];
+        if !ids.is_empty() {
+            let mut selector = self
+                .repository
+                .selector(self.user_id, false)
+                .filter(files::Column::Id.is_in(ids));
+
+            selector = match dir_id {
+                Some(dir_id) => selector.filter(files::Column::FileId.eq(dir_id)),
+                None => selector.filter(files::Column::FileId.is_null()),
+            };
+
+            files = selector
+                .into_model::<AppFile>()
+                .all(self.repository.connection())
+                .await?;
+        }
+
+        let changes = latest
+            .into_iter()
+            .map(|activity| {
+                let file = files.iter().find(|f| f.id == activity.file_id).cloned();
+
+                // Recorded as changed, but it is not in the directory anymore,
+                // sharing only changes the file as far as the directory is concerned.
+                let action = match (file.is_some(), activity.action) {
+                    (false, _) => Action::Deleted,
+                    (true, Action::Shared | Action::Unshared) => Action::Modified,
+                    (true, action) => action,
+                };
+
+                Change {
+                    cursor: activity.id,
+                    file_id: activity.file_id,
+                    action,
+                    created_at: activity.created_at,
+                    file,
+                }
+            })
+            .collect();
+
+        Ok(ChangesResponse {
+            cursor,
+            has_more,
+            changes,
+        })
+    }
+
+    /// Page through every activity of the user after the given cursor, in the order
+    /// they were recorded, this covers the changes of all the files and their shares.
+    pub(crate) async fn journal(&self, since: i64, limit: u64) -> AppResult<JournalResponse> {
+        let mut entries = activities::Entity::find()
+            .filter(activities::Column::UserId.eq(self.user_id))
+            .filter(activities::Column::Id.gt(since))
+            .order_by_asc(activities::Column::Id)
+            .limit(limit + 1)
+            .all(self.repository.connection())
+            .await?;
+
+        let has_more = entries.len() as u64 > limit;
+        entries.truncate(limit as usize);
+
+        Ok(JournalResponse {
+            cursor: entries.last().map(|a| a.id).unwrap_or(since),
+            has_more,
+            entries,
+        })
+    }
+
+    /// Get the sequence number of the latest activity for each of the given files,
+    /// files without any recorded activity are left out.
+    pub(crate) async fn versions(&self, ids: &[Uuid]) -> AppResult<HashMap<Uuid, i64>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let versions = activities::Entity::find()
+            .select_only()
+            .column(activities::Column::FileId)
+            .column_as(activities::Column::Id.max(), "version")
+            .filter(activities::Column::UserId.eq(self.user_id))
+            .filter(activities::Column::FileId.is_in(ids.to_vec()))
+            .group_by(activities::Column::FileId)
+            .into_tuple::<(Uuid, i64)>()
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(versions.into_iter().collect())
+    }
+}
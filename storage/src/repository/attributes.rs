@@ -0,0 +1,51 @@
+//! Repository module for the custom attributes of the users files
+
+use entity::{
+    user_files, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::data::{app_file::AppFile, attributes::UpdateAttributes};
+
+pub(crate) struct Attributes<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Attributes<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// Update the attributes on the users copy of the file, the file
+    /// can be either owned by the user or shared with them.
+    pub(crate) async fn update(&self, file_id: Uuid, data: UpdateAttributes) -> AppResult<AppFile> {
+        self.repository.by_id(file_id, self.user_id).await?;
+
+        let user_file = user_files::Entity::find()
+            .filter(user_files::Column::FileId.eq(file_id))
+            .filter(user_files::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("file_not_found:{}", file_id)))?;
+
+        let attributes = data.apply(user_file.attributes)?;
+
+        user_files::Entity::update(user_files::ActiveModel {
+            id: ActiveValue::Set(user_file.id),
+            attributes: ActiveValue::Set(attributes),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        self.repository.by_id(file_id, self.user_id).await
+    }
+}
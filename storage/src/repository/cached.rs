@@ -1,27 +1,212 @@
 //! # Cached repository
 //! This is a cached repository that uses the `cached` crate to cache the results of the queries.
 //! All the functions in here are shortcuts to the functions in the `Repository` struct.
+//!
+//! The cache is only used when `STORAGE_CACHE` is enabled, otherwise every call goes
+//! straight to the database. Every route that mutates files has to call one of the
+//! `invalidate_*` functions after the changes are committed, and the entries are
+//! dropped after [LIFESPAN] anyway, so a change nobody invalidated is not served for long.
+
+use std::time::{Duration, Instant};
 
 use cached::proc_macro::cached;
-use cached::SizedCache;
+use cached::{Cached, SizedCache};
 use context::Context;
 use entity::Uuid;
+use error::AppResult;
 
-use crate::data::app_file::AppFile;
+use crate::data::{app_file::AppFile, query::Query, response::Response};
 
 use super::Repository;
 
+/// How long the cached entries are used before they are loaded again
+pub(crate) const LIFESPAN: Duration = Duration::from_secs(60);
+
 /// Get a file from the database
+pub(crate) async fn get_file(context: &Context, owner_id: Uuid, file_id: Uuid) -> Option<AppFile> {
+    if !context.config.server.cache {
+        return load_file(context, owner_id, file_id).await;
+    }
+
+    let (cached_at, file) = cached_file(context, owner_id, file_id).await;
+
+    if cached_at.elapsed() < LIFESPAN {
+        return file;
+    }
+
+    REPOSITORY_GET_FILE
+        .lock()
+        .await
+        .cache_remove(&(owner_id, file_id));
+
+    cached_file(context, owner_id, file_id).await.1
+}
+
 #[cached(
     name = "REPOSITORY_GET_FILE",
-    type = "SizedCache<Uuid, Option<AppFile>>",
+    type = "SizedCache<(Uuid, Uuid), (Instant, Option<AppFile>)>",
     create = "{ SizedCache::with_size(100) }",
-    convert = r#"{ file_id }"#
+    convert = r#"{ (owner_id, file_id) }"#
 )]
-pub(crate) async fn get_file(context: &Context, owner_id: Uuid, file_id: Uuid) -> Option<AppFile> {
+async fn cached_file(
+    context: &Context,
+    owner_id: Uuid,
+    file_id: Uuid,
+) -> (Instant, Option<AppFile>) {
+    (Instant::now(), load_file(context, owner_id, file_id).await)
+}
+
+async fn load_file(context: &Context, owner_id: Uuid, file_id: Uuid) -> Option<AppFile> {
     Repository::new(&context.db)
         .manage(owner_id)
         .file(file_id)
         .await
         .ok()
 }
+
+/// Get any kind of file or directory metadata the user has access to
+pub(crate) async fn get_metadata(
+    context: &Context,
+    user_id: Uuid,
+    file_id: Uuid,
+) -> AppResult<AppFile> {
+    if !context.config.server.cache {
+        return load_metadata(context, user_id, file_id).await;
+    }
+
+    let (cached_at, file) = cached_metadata(context, user_id, file_id).await?;
+
+    if cached_at.elapsed() < LIFESPAN {
+        return Ok(file);
+    }
+
+    REPOSITORY_GET_METADATA
+        .lock()
+        .await
+        .cache_remove(&(user_id, file_id));
+
+    cached_metadata(context, user_id, file_id)
+        .await
+        .map(|(_, file)| file)
+}
+
+#[cached(
+    name = "REPOSITORY_GET_METADATA",
+    type = "SizedCache<(Uuid, Uuid), (Instant, AppFile)>",
+    create = "{ SizedCache::with_size(1000) }",
+    convert = r#"{ (user_id, file_id) }"#,
+    result = true
+)]
+async fn cached_metadata(
+    context: &Context,
+    user_id: Uuid,
+    file_id: Uuid,
+) -> AppResult<(Instant, AppFile)> {
+    let file = load_metadata(context, user_id, file_id).await?;
+
+    Ok((Instant::now(), file))
+}
+
+async fn load_metadata(context: &Context, user_id: Uuid, file_id: Uuid) -> AppResult<AppFile> {
+    Repository::new(&context.db)
+        .query(user_id)
+        .get(file_id)
+        .await
+}
+
+/// List the files in a directory, the key is the serialized query so the
+/// same folder opened with different ordering is cached separately.
+pub(crate) async fn find(context: &Context, owner_id: Uuid, query: Query) -> AppResult<Response> {
+    if !context.config.server.cache {
+        return load_listing(context, owner_id, query).await;
+    }
+
+    let (cached_at, response) = cached_listing(context, owner_id, query.clone()).await?;
+
+    if cached_at.elapsed() < LIFESPAN {
+        return Ok(response);
+    }
+
+    REPOSITORY_FIND
+        .lock()
+        .await
+        .cache_remove(&listing_key(owner_id, &query));
+
+    cached_listing(context, owner_id, query)
+        .await
+        .map(|(_, response)| response)
+}
+
+#[cached(
+    name = "REPOSITORY_FIND",
+    type = "SizedCache<(Uuid, String), (Instant, Response)>",
+    create = "{ SizedCache::with_size(500) }",
+    convert = r#"{ listing_key(owner_id, &query) }"#,
+    result = true
+)]
+async fn cached_listing(
+    context: &Context,
+    owner_id: Uuid,
+    query: Query,
+) -> AppResult<(Instant, Response)> {
+    let response = load_listing(context, owner_id, query).await?;
+
+    Ok((Instant::now(), response))
+}
+
+async fn load_listing(context: &Context, owner_id: Uuid, query: Query) -> AppResult<Response> {
+    Repository::new(&context.db)
+        .manage(owner_id)
+        .find(query)
+        .await
+}
+
+fn listing_key(owner_id: Uuid, query: &Query) -> (Uuid, String) {
+    (owner_id, serde_json::to_string(query).unwrap_or_default())
+}
+
+/// Drop all the cached entries holding any of the given files
+pub async fn invalidate_files(ids: &[Uuid]) {
+    REPOSITORY_GET_FILE
+        .lock()
+        .await
+        .retain(|(_, file_id), _| !ids.contains(file_id));
+
+    REPOSITORY_GET_METADATA
+        .lock()
+        .await
+        .retain(|(_, file_id), _| !ids.contains(file_id));
+}
+
+/// Drop the cached directory listings of the user and every other listing
+/// that shows one of the given files, shared directories are listed by many users.
+pub async fn invalidate_listings(owner_id: Uuid, ids: &[Uuid]) {
+    REPOSITORY_FIND
+        .lock()
+        .await
+        .retain(|(user_id, _), (_, response)| {
+            *user_id != owner_id
+                && !response
+                    .parents
+                    .iter()
+                    .chain(response.children.iter())
+                    .any(|file| ids.contains(&file.id))
+        });
+}
+
+/// Drop every cached entry related to the changed files and the listings they are in.
+///
+/// When a file is created or moved, the id of its parent directory should be given as well,
+/// otherwise listings of the directory held by other users won't be refreshed.
+pub async fn invalidate(owner_id: Uuid, ids: &[Uuid]) {
+    invalidate_files(ids).await;
+    invalidate_listings(owner_id, ids).await;
+}
+
+/// Drop all the cached entries, for the rare changes that reach too many files
+/// and users to list them, like a folder joining or leaving the space of a group.
+pub(crate) async fn clear() {
+    REPOSITORY_GET_FILE.lock().await.cache_clear();
+    REPOSITORY_GET_METADATA.lock().await.cache_clear();
+    REPOSITORY_FIND.lock().await.cache_clear();
+}
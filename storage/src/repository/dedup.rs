@@ -0,0 +1,105 @@
+//! Lookup of the stored content for the hash first upload.
+//!
+//! By default only the files the user can already decrypt are considered, those are
+//! their own files and the files shared with them. With the global deduplication
+//! enabled the files of all the users are considered as well.
+
+use chrono::Utc;
+use entity::{
+    files, user_files, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use crate::data::dedup::DedupMatch;
+
+/// Find the finished file with the same content
+pub(crate) async fn lookup<T: ConnectionTrait>(
+    connection: &T,
+    user_id: Uuid,
+    sha256: &str,
+    size: i64,
+    global: bool,
+) -> AppResult<Option<DedupMatch>> {
+    let own = user_files::Entity::find()
+        .filter(accessible(user_id))
+        .find_also_related(files::Entity)
+        .filter(files::Column::Sha256.eq(sha256))
+        .filter(files::Column::Size.eq(size))
+        .filter(files::Column::FinishedUploadAt.is_not_null())
+        .order_by_asc(files::Column::CreatedAt)
+        .one(connection)
+        .await?;
+
+    if let Some((user_file, Some(file))) = own {
+        return Ok(Some(DedupMatch {
+            id: file.id,
+            chunks: file.chunks,
+            encrypted_key: Some(user_file.encrypted_key),
+            crypto_version: file.crypto_version,
+        }));
+    }
+
+    if !global {
+        return Ok(None);
+    }
+
+    let file = files::Entity::find()
+        .filter(files::Column::Sha256.eq(sha256))
+        .filter(files::Column::Size.eq(size))
+        .filter(files::Column::FinishedUploadAt.is_not_null())
+        .order_by_asc(files::Column::CreatedAt)
+        .one(connection)
+        .await?;
+
+    Ok(file.map(|file| DedupMatch {
+        id: file.id,
+        chunks: file.chunks,
+        encrypted_key: None,
+        crypto_version: file.crypto_version,
+    }))
+}
+
+/// Get the file the content of the new file is taken from
+pub(crate) async fn source<T: ConnectionTrait>(
+    connection: &T,
+    user_id: Uuid,
+    id: Uuid,
+    global: bool,
+) -> AppResult<files::Model> {
+    let file = files::Entity::find_by_id(id)
+        .filter(files::Column::FinishedUploadAt.is_not_null())
+        .filter(files::Column::Mime.ne("dir"))
+        .one(connection)
+        .await?
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    if global {
+        return Ok(file);
+    }
+
+    user_files::Entity::find()
+        .filter(accessible(user_id))
+        .filter(user_files::Column::FileId.eq(file.id))
+        .one(connection)
+        .await?
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    Ok(file)
+}
+
+/// Files the user has the key for
+fn accessible(user_id: Uuid) -> Condition {
+    Condition::all()
+        .add(user_files::Column::UserId.eq(user_id))
+        .add(
+            Condition::any()
+                .add(user_files::Column::StartsAt.is_null())
+                .add(user_files::Column::StartsAt.lte(Utc::now().timestamp())),
+        )
+        .add(
+            Condition::any()
+                .add(user_files::Column::ExpiresAt.is_null())
+                .add(user_files::Column::ExpiresAt.gt(Utc::now().timestamp())),
+        )
+}
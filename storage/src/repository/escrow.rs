@@ -0,0 +1,131 @@
+//! Key escrow of the organization.
+//!
+//! When the admin enables it, the key of every new file has to be sent encrypted with
+//! the organization recovery public key as well, so the organization can recover the data
+//! of the users that leave. The clients encrypt the keys, the server only makes sure
+//! they are sent and keeps them. Files created before the escrow was enabled get their
+//! key escrowed the next time they are shared.
+
+use chrono::Utc;
+use context::Context;
+use entity::{escrow_keys, ActiveValue, ConnectionTrait, EntityTrait, Uuid};
+use error::{AppResult, Error};
+
+/// Public key of the organization the file keys are escrowed to
+#[derive(Clone, Debug)]
+pub struct RecoveryKey {
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// Fingerprint of the public RSA key, fails if the key is not a valid one
+pub fn recovery_fingerprint(public_key: &str) -> AppResult<String> {
+    let public_key = cryptfns::rsa::public::from_str(public_key)?;
+
+    Ok(cryptfns::rsa::fingerprint(public_key)?)
+}
+
+/// Get the recovery key of the organization, only set when the escrow is enabled
+pub async fn recovery_key(context: &Context) -> AppResult<Option<RecoveryKey>> {
+    let public_key = match context.settings.inner().await.escrow.public_key() {
+        Some(public_key) => public_key.to_string(),
+        None => return Ok(None),
+    };
+
+    Ok(Some(RecoveryKey {
+        fingerprint: recovery_fingerprint(&public_key)?,
+        public_key,
+    }))
+}
+
+/// Store the escrowed key of the newly created file, the key
+/// is required when the escrow is enabled and ignored otherwise.
+pub async fn escrow<T: ConnectionTrait>(
+    connection: &T,
+    recovery_key: Option<&RecoveryKey>,
+    file_id: Uuid,
+    encrypted_key: Option<&str>,
+) -> AppResult<()> {
+    let recovery_key = match recovery_key {
+        Some(recovery_key) => recovery_key,
+        None => return Ok(()),
+    };
+
+    let encrypted_key = encrypted_key
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| Error::as_validation("escrow_key", "required"))?;
+
+    let existing = escrow_keys::Entity::find_by_id(file_id)
+        .one(connection)
+        .await?;
+
+    let escrow_key = escrow_keys::ActiveModel {
+        file_id: ActiveValue::Set(file_id),
+        encrypted_key: ActiveValue::Set(encrypted_key.to_string()),
+        fingerprint: ActiveValue::Set(recovery_key.fingerprint.clone()),
+        created_at: ActiveValue::Set(Utc::now().timestamp()),
+    };
+
+    match existing {
+        Some(_) => {
+            escrow_keys::Entity::update(escrow_key)
+                .exec(connection)
+                .await?;
+        }
+        None => {
+            escrow_keys::Entity::insert(escrow_key)
+                .exec_without_returning(connection)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Escrow the key of the file that is being shared if it isn't escrowed
+/// to the current recovery key yet, otherwise the sent key is ignored.
+pub async fn escrow_if_missing<T: ConnectionTrait>(
+    connection: &T,
+    recovery_key: Option<&RecoveryKey>,
+    file_id: Uuid,
+    encrypted_key: Option<&str>,
+) -> AppResult<()> {
+    let recovery_key = match recovery_key {
+        Some(recovery_key) => recovery_key,
+        None => return Ok(()),
+    };
+
+    let escrowed = escrow_keys::Entity::find_by_id(file_id)
+        .one(connection)
+        .await?
+        .map(|escrow_key| escrow_key.fingerprint == recovery_key.fingerprint)
+        .unwrap_or(false);
+
+    if escrowed {
+        return Ok(());
+    }
+
+    escrow(connection, Some(recovery_key), file_id, encrypted_key).await
+}
+
+/// Escrow the key of the copy the same as the key of the original, they share the key
+pub async fn copy<T: ConnectionTrait>(connection: &T, from: Uuid, to: Uuid) -> AppResult<()> {
+    let escrow_key = match escrow_keys::Entity::find_by_id(from)
+        .one(connection)
+        .await?
+    {
+        Some(escrow_key) => escrow_key,
+        None => return Ok(()),
+    };
+
+    escrow_keys::Entity::insert(escrow_keys::ActiveModel {
+        file_id: ActiveValue::Set(to),
+        encrypted_key: ActiveValue::Set(escrow_key.encrypted_key),
+        fingerprint: ActiveValue::Set(escrow_key.fingerprint),
+        created_at: ActiveValue::Set(Utc::now().timestamp()),
+    })
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
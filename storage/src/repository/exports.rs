@@ -0,0 +1,204 @@
+//! Repository module for the exports of the user data.
+
+use chrono::Utc;
+use entity::{
+    activities, exports, file_requests, files, group_members, links, user_files, users,
+    ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::{
+    data::exports::{ExportFile, ExportMetadata},
+    jobs::{PurgeFile, UserExport, EXPORT_USER_DATA, PURGE_FILES},
+};
+
+pub(crate) struct Exports<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Exports<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the exports of the user, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<exports::Model>> {
+        let exports = exports::Entity::find()
+            .filter(exports::Column::UserId.eq(self.user_id))
+            .order_by_desc(exports::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(exports)
+    }
+
+    /// Get the export of the user
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<exports::Model> {
+        exports::Entity::find_by_id(id)
+            .filter(exports::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("export_not_found".to_string()))
+    }
+
+    /// Queue the new export, the archives of the previous exports are removed
+    /// so the user keeps at most one copy of their data on the server.
+    pub(crate) async fn create(&self, include_files: bool) -> AppResult<exports::Model> {
+        let previous = self.find().await?;
+
+        if previous
+            .iter()
+            .any(|export| export.status == exports::Status::Pending)
+        {
+            return Err(Error::BadRequest("export_in_progress".to_string()));
+        }
+
+        let jobs = jobs::repository::Repository::new(self.repository.connection());
+
+        if !previous.is_empty() {
+            exports::Entity::delete_many()
+                .filter(exports::Column::UserId.eq(self.user_id))
+                .exec(self.repository.connection())
+                .await?;
+
+            let archives = previous
+                .iter()
+                .filter(|export| export.status == exports::Status::Ready)
+                .map(|export| PurgeFile {
+                    id: export.id,
+                    created_at: export.created_at,
+                })
+                .collect::<Vec<_>>();
+
+            if !archives.is_empty() {
+                jobs.push(PURGE_FILES, &archives).await?;
+            }
+        }
+
+        let export = exports::Model {
+            id: Uuid::new_v4(),
+            user_id: self.user_id,
+            status: exports::Status::Pending,
+            include_files,
+            size: None,
+            error: None,
+            created_at: Utc::now().timestamp(),
+            finished_at: None,
+        };
+
+        exports::Entity::insert(exports::ActiveModel::from(export.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        jobs.push(EXPORT_USER_DATA, &UserExport { id: export.id })
+            .await?;
+
+        Ok(export)
+    }
+}
+
+/// Mark the export as finished, either with the size of the archive or with the error
+pub(crate) async fn finish<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    result: AppResult<u64>,
+) -> AppResult<()> {
+    let (status, size, error) = match result {
+        Ok(size) => (exports::Status::Ready, Some(size as i64), None),
+        Err(e) => (exports::Status::Failed, None, Some(e.to_string())),
+    };
+
+    exports::Entity::update(exports::ActiveModel {
+        id: ActiveValue::Set(id),
+        status: ActiveValue::Set(status),
+        size: ActiveValue::Set(size),
+        error: ActiveValue::Set(error),
+        finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Collect everything the platform knows about the user
+pub(crate) async fn metadata<T: ConnectionTrait>(
+    connection: &T,
+    user_id: Uuid,
+) -> AppResult<ExportMetadata> {
+    let user = users::Entity::find_by_id(user_id)
+        .one(connection)
+        .await?
+        .ok_or_else(|| Error::NotFound("user_not_found".to_string()))?;
+
+    let files = user_files::Entity::find()
+        .filter(user_files::Column::UserId.eq(user_id))
+        .find_also_related(files::Entity)
+        .order_by_asc(user_files::Column::CreatedAt)
+        .all(connection)
+        .await?
+        .into_iter()
+        .filter_map(|(user_file, file)| {
+            file.map(|file| ExportFile {
+                file,
+                is_owner: user_file.is_owner,
+                encrypted_key: user_file.encrypted_key,
+                starts_at: user_file.starts_at,
+                expires_at: user_file.expires_at,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let owned = files
+        .iter()
+        .filter(|file| file.is_owner)
+        .map(|file| file.file.id)
+        .collect::<Vec<_>>();
+
+    let shares = user_files::Entity::find()
+        .filter(user_files::Column::FileId.is_in(owned))
+        .filter(user_files::Column::IsOwner.eq(false))
+        .all(connection)
+        .await?;
+
+    let links = links::Entity::find()
+        .filter(links::Column::UserId.eq(user_id))
+        .all(connection)
+        .await?;
+
+    let activities = activities::Entity::find()
+        .filter(activities::Column::UserId.eq(user_id))
+        .order_by_asc(activities::Column::Id)
+        .all(connection)
+        .await?;
+
+    let file_requests = file_requests::Entity::find()
+        .filter(file_requests::Column::UserId.eq(user_id))
+        .all(connection)
+        .await?;
+
+    let groups = group_members::Entity::find()
+        .filter(group_members::Column::UserId.eq(user_id))
+        .all(connection)
+        .await?;
+
+    Ok(ExportMetadata {
+        exported_at: Utc::now().timestamp(),
+        user: user.into(),
+        files,
+        shares,
+        links,
+        activities,
+        file_requests,
+        groups,
+    })
+}
@@ -0,0 +1,230 @@
+//! Repository module for the exports of the folders to external providers.
+
+use chrono::Utc;
+use entity::{
+    external_exports, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::{
+    authorize::{self, Access},
+    data::external_exports::{CreateExternalExport, Progress},
+    jobs::{ExternalExportJob, EXPORT_EXTERNAL},
+};
+
+pub(crate) struct ExternalExports<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> ExternalExports<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the external exports of the user, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<external_exports::Model>> {
+        let exports = external_exports::Entity::find()
+            .filter(external_exports::Column::UserId.eq(self.user_id))
+            .order_by_desc(external_exports::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(exports)
+    }
+
+    /// Get the external export of the user
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<external_exports::Model> {
+        external_exports::Entity::find_by_id(id)
+            .filter(external_exports::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("external_export_not_found".to_string()))
+    }
+
+    /// Queue the new export, the user can run only one export at the time
+    pub(crate) async fn create(
+        &self,
+        data: CreateExternalExport,
+    ) -> AppResult<external_exports::Model> {
+        let (target, location, credentials, mode, keys, file_id) = data.into_tuple()?;
+
+        if let Some(file_id) = file_id {
+            let folder = self.repository.by_id(file_id, self.user_id).await?;
+
+            authorize::directory(
+                self.repository.connection(),
+                self.user_id,
+                Access::Own,
+                &folder,
+            )
+            .await?;
+        }
+
+        if self.find().await?.iter().any(|e| e.status.is_active()) {
+            return Err(Error::BadRequest("external_export_in_progress".to_string()));
+        }
+
+        let keys = match keys {
+            Some(keys) => Some(serde_json::to_string(&keys)?),
+            None => None,
+        };
+
+        let export = external_exports::Model {
+            id: Uuid::new_v4(),
+            user_id: self.user_id,
+            file_id,
+            target,
+            location,
+            credentials: Some(serde_json::to_string(&credentials)?),
+            mode,
+            keys,
+            status: external_exports::Status::Pending,
+            exported_files: 0,
+            exported_bytes: 0,
+            failed_files: 0,
+            errors: None,
+            error: None,
+            created_at: Utc::now().timestamp(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        external_exports::Entity::insert(external_exports::ActiveModel::from(export.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        jobs::repository::Repository::new(self.repository.connection())
+            .push(EXPORT_EXTERNAL, &ExternalExportJob { id: export.id })
+            .await?;
+
+        Ok(export)
+    }
+
+    /// Stop the export, the files exported so far are left in the target
+    pub(crate) async fn cancel(&self, id: Uuid) -> AppResult<external_exports::Model> {
+        let export = self.get(id).await?;
+
+        if !export.status.is_active() {
+            return Err(Error::BadRequest("external_export_not_running".to_string()));
+        }
+
+        external_exports::Entity::update_many()
+            .set(external_exports::ActiveModel {
+                status: ActiveValue::Set(external_exports::Status::Cancelled),
+                credentials: ActiveValue::Set(None),
+                keys: ActiveValue::Set(None),
+                finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+                ..Default::default()
+            })
+            .filter(external_exports::Column::Id.eq(id))
+            .filter(external_exports::Column::Status.is_in([
+                external_exports::Status::Pending,
+                external_exports::Status::Running,
+            ]))
+            .exec(self.repository.connection())
+            .await?;
+
+        self.get(id).await
+    }
+}
+
+/// Mark the export as running, the export that was stopped in the meantime is not started
+pub(crate) async fn start<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+) -> AppResult<Option<external_exports::Model>> {
+    let results = external_exports::Entity::update_many()
+        .set(external_exports::ActiveModel {
+            status: ActiveValue::Set(external_exports::Status::Running),
+            started_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(external_exports::Column::Id.eq(id))
+        .filter(external_exports::Column::Status.is_in([
+            external_exports::Status::Pending,
+            external_exports::Status::Running,
+        ]))
+        .exec(connection)
+        .await?;
+
+    if results.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    Ok(external_exports::Entity::find_by_id(id)
+        .one(connection)
+        .await?)
+}
+
+/// The export is still running, it could have been cancelled in the meantime
+pub(crate) async fn is_running<T: ConnectionTrait>(connection: &T, id: Uuid) -> AppResult<bool> {
+    let export = external_exports::Entity::find_by_id(id)
+        .one(connection)
+        .await?;
+
+    Ok(matches!(export, Some(e) if e.status == external_exports::Status::Running))
+}
+
+/// Store the progress of the running export
+pub(crate) async fn progress<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    progress: &Progress,
+) -> AppResult<()> {
+    let errors = match progress.errors.is_empty() {
+        true => None,
+        false => Some(serde_json::to_value(&progress.errors)?),
+    };
+
+    external_exports::Entity::update_many()
+        .set(external_exports::ActiveModel {
+            exported_files: ActiveValue::Set(progress.exported_files),
+            exported_bytes: ActiveValue::Set(progress.exported_bytes),
+            failed_files: ActiveValue::Set(progress.failed_files),
+            errors: ActiveValue::Set(errors),
+            ..Default::default()
+        })
+        .filter(external_exports::Column::Id.eq(id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+/// Mark the running export as finished, or failed with the error that stopped it.
+/// The credentials for the target and the keys of the files are removed either way.
+pub(crate) async fn finish<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    result: AppResult<()>,
+) -> AppResult<()> {
+    let (status, error) = match result {
+        Ok(()) => (external_exports::Status::Finished, None),
+        Err(e) => (external_exports::Status::Failed, Some(e.to_string())),
+    };
+
+    external_exports::Entity::update_many()
+        .set(external_exports::ActiveModel {
+            status: ActiveValue::Set(status),
+            error: ActiveValue::Set(error),
+            credentials: ActiveValue::Set(None),
+            keys: ActiveValue::Set(None),
+            finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(external_exports::Column::Id.eq(id))
+        .filter(external_exports::Column::Status.eq(external_exports::Status::Running))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Repository module for the file requests, folders anyone with the link can upload into.
+//!
+//! The request stops accepting uploads once it is closed or its deadline has passed,
+//! closing it schedules the summary of the received files for the owner.
+
+use chrono::Utc;
+use entity::{
+    file_request_files, file_requests, files, ActiveValue, ColumnTrait, ConnectionTrait,
+    EntityTrait, QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::{
+    authorize::{self, Access},
+    data::file_requests::{FileRequestResponse, Summary},
+    jobs::{CloseFileRequest, CLOSE_FILE_REQUEST},
+};
+
+pub(crate) struct FileRequests<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    owner_id: Uuid,
+}
+
+impl<'repository, T> FileRequests<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, owner_id: Uuid) -> Self {
+        Self {
+            repository,
+            owner_id,
+        }
+    }
+
+    /// List the file requests of the owner, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<FileRequestResponse>> {
+        let requests = file_requests::Entity::find()
+            .filter(file_requests::Column::UserId.eq(self.owner_id))
+            .order_by_desc(file_requests::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        let mut responses = vec![];
+
+        for request in requests {
+            responses.push(self.response(request).await?);
+        }
+
+        Ok(responses)
+    }
+
+    /// Create the file request for the folder of the owner, the request
+    /// with a deadline is closed by the background worker once it passes.
+    pub(crate) async fn create(
+        &self,
+        file_id: Uuid,
+        deadline: Option<i64>,
+    ) -> AppResult<FileRequestResponse> {
+        let folder = self.repository.by_id(file_id, self.owner_id).await?;
+
+        authorize::directory(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Own,
+            &folder,
+        )
+        .await?;
+
+        let request = file_requests::Model {
+            id: Uuid::new_v4(),
+            user_id: self.owner_id,
+            file_id,
+            deadline,
+            closed_at: None,
+            notified_at: None,
+            created_at: Utc::now().timestamp(),
+        };
+
+        file_requests::Entity::insert(file_requests::ActiveModel::from(request.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        if let Some(deadline) = deadline {
+            jobs::repository::Repository::new(self.repository.connection())
+                .push_at(
+                    CLOSE_FILE_REQUEST,
+                    &CloseFileRequest { id: request.id },
+                    deadline,
+                )
+                .await?;
+        }
+
+        self.response(request).await
+    }
+
+    /// Close the request before its deadline, the owner gets the summary right away
+    pub(crate) async fn close(&self, id: Uuid) -> AppResult<FileRequestResponse> {
+        let mut request = self.request(id).await?;
+
+        if request.closed_at.is_none() {
+            let closed_at = Utc::now().timestamp();
+
+            file_requests::Entity::update(file_requests::ActiveModel {
+                id: ActiveValue::Set(request.id),
+                closed_at: ActiveValue::Set(Some(closed_at)),
+                ..Default::default()
+            })
+            .exec(self.repository.connection())
+            .await?;
+
+            jobs::repository::Repository::new(self.repository.connection())
+                .push(CLOSE_FILE_REQUEST, &CloseFileRequest { id: request.id })
+                .await?;
+
+            request.closed_at = Some(closed_at);
+        }
+
+        self.response(request).await
+    }
+
+    async fn request(&self, id: Uuid) -> AppResult<file_requests::Model> {
+        file_requests::Entity::find_by_id(id)
+            .filter(file_requests::Column::UserId.eq(self.owner_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("file_request_not_found".to_string()))
+    }
+
+    async fn response(&self, request: file_requests::Model) -> AppResult<FileRequestResponse> {
+        let received = summary(self.repository.connection(), request.id).await?;
+
+        Ok(FileRequestResponse {
+            open: request.is_open(Utc::now().timestamp()),
+            request,
+            received,
+        })
+    }
+}
+
+/// Get the request that is still accepting uploads
+pub(crate) async fn open<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+) -> AppResult<file_requests::Model> {
+    let request = file_requests::Entity::find_by_id(id)
+        .one(connection)
+        .await?
+        .ok_or_else(|| Error::NotFound("file_request_not_found".to_string()))?;
+
+    if !request.is_open(Utc::now().timestamp()) {
+        return Err(Error::Forbidden("file_request_closed".to_string()));
+    }
+
+    Ok(request)
+}
+
+/// Remember the file was received through the request
+pub(crate) async fn receive<T: ConnectionTrait>(
+    connection: &T,
+    request_id: Uuid,
+    file_id: Uuid,
+) -> AppResult<()> {
+    let existing = file_request_files::Entity::find_by_id((request_id, file_id))
+        .one(connection)
+        .await?;
+
+    if existing.is_none() {
+        file_request_files::Entity::insert(file_request_files::ActiveModel {
+            file_request_id: ActiveValue::Set(request_id),
+            file_id: ActiveValue::Set(file_id),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+        })
+        .exec_without_returning(connection)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Make sure the file was received through the request, uploaders
+/// can only upload the chunks of the files they have created.
+pub(crate) async fn received<T: ConnectionTrait>(
+    connection: &T,
+    request_id: Uuid,
+    file_id: Uuid,
+) -> AppResult<()> {
+    file_request_files::Entity::find_by_id((request_id, file_id))
+        .one(connection)
+        .await?
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    Ok(())
+}
+
+/// Summary of the files received through the request
+pub(crate) async fn summary<T: ConnectionTrait>(connection: &T, id: Uuid) -> AppResult<Summary> {
+    let files = file_request_files::Entity::find()
+        .filter(file_request_files::Column::FileRequestId.eq(id))
+        .find_also_related(files::Entity)
+        .all(connection)
+        .await?;
+
+    let summary = files.into_iter().filter_map(|(_, file)| file).fold(
+        Summary::default(),
+        |mut summary, file| {
+            summary.files += 1;
+            summary.size += file.size.unwrap_or(0);
+
+            if file.finished_upload_at.is_some() {
+                summary.finished += 1;
+            }
+
+            summary
+        },
+    );
+
+    Ok(summary)
+}
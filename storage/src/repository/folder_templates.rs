@@ -0,0 +1,151 @@
+//! Repository module for the folder templates of the user.
+//!
+//! The template itself is only stored for the client, creating the folders from
+//! it goes through the same checks as creating each of the folders one by one.
+
+use chrono::Utc;
+use entity::{
+    folder_templates, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::data::{app_file::AppFile, create_file::CreateFileData};
+
+pub(crate) struct FolderTemplates<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> FolderTemplates<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the templates of the user, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<folder_templates::Model>> {
+        let templates = folder_templates::Entity::find()
+            .filter(folder_templates::Column::UserId.eq(self.user_id))
+            .order_by_desc(folder_templates::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(templates)
+    }
+
+    /// Save the new template
+    pub(crate) async fn create(
+        &self,
+        encrypted_name: String,
+        encrypted_tree: String,
+    ) -> AppResult<folder_templates::Model> {
+        let now = Utc::now().timestamp();
+
+        let template = folder_templates::Model {
+            id: Uuid::new_v4(),
+            user_id: self.user_id,
+            encrypted_name,
+            encrypted_tree,
+            created_at: now,
+            updated_at: now,
+        };
+
+        folder_templates::Entity::insert(folder_templates::ActiveModel::from(template.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        Ok(template)
+    }
+
+    /// Replace the name and the structure of the template
+    pub(crate) async fn update(
+        &self,
+        id: Uuid,
+        encrypted_name: String,
+        encrypted_tree: String,
+    ) -> AppResult<folder_templates::Model> {
+        let template = self.get(id).await?;
+        let updated_at = Utc::now().timestamp();
+
+        folder_templates::Entity::update(folder_templates::ActiveModel {
+            id: ActiveValue::Set(template.id),
+            encrypted_name: ActiveValue::Set(encrypted_name.clone()),
+            encrypted_tree: ActiveValue::Set(encrypted_tree.clone()),
+            updated_at: ActiveValue::Set(updated_at),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        Ok(folder_templates::Model {
+            encrypted_name,
+            encrypted_tree,
+            updated_at,
+            ..template
+        })
+    }
+
+    /// Delete the template, the folders created from it are kept
+    pub(crate) async fn delete(&self, id: Uuid) -> AppResult<()> {
+        self.get(id).await?;
+
+        folder_templates::Entity::delete_by_id(id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Create the folders of the template in the given folder, they have to be
+    /// ordered so each folder comes after the folder it is created in.
+    pub(crate) async fn instantiate(
+        &self,
+        id: Uuid,
+        target: Option<Uuid>,
+        folders: Vec<CreateFileData>,
+    ) -> AppResult<Vec<AppFile>> {
+        self.get(id).await?;
+
+        let manage = self.repository.manage(self.user_id);
+        let mut created = Vec::with_capacity(folders.len());
+
+        for (create_file, encrypted_key, hashed_tokens, _, parent_id) in folders {
+            if parent_id == target {
+                let name_hash = create_file
+                    .name_hash
+                    .clone()
+                    .into_value()
+                    .unwrap()
+                    .unwrap::<String>();
+
+                if manage.by_name(&name_hash, parent_id).await.is_ok() {
+                    return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+                }
+            }
+
+            created.push(
+                manage
+                    .create(create_file, &encrypted_key, hashed_tokens)
+                    .await?,
+            );
+        }
+
+        Ok(created)
+    }
+
+    /// Get the template of the user
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<folder_templates::Model> {
+        folder_templates::Entity::find_by_id(id)
+            .filter(folder_templates::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("folder_template_not_found".to_string()))
+    }
+}
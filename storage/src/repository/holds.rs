@@ -0,0 +1,175 @@
+//! Legal hold of the files and folders.
+//!
+//! File under the legal hold cannot be deleted, neither can the folders it is in,
+//! and a folder under the legal hold keeps everything inside of it, nothing can be
+//! moved out of it either. Every refused
+//! attempt is recorded in the audit log, so it has to be recorded outside of the
+//! transaction the deletion would run in, otherwise it would be rolled back with it.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use entity::{audit_logs, files, ActiveValue, ConnectionTrait, EntityTrait, Statement, Uuid};
+use error::{AppResult, Error};
+
+/// Get the ids of the files under the legal hold that would be affected by deleting
+/// the given files, those are the files themselves, everything inside of them
+/// and all the folders they are in.
+pub async fn held<T: ConnectionTrait>(connection: &T, ids: &[Uuid]) -> AppResult<Vec<Uuid>> {
+    let sql = r#"
+        WITH RECURSIVE children(id) AS (
+            SELECT id FROM files WHERE id = $1
+            UNION ALL
+            SELECT child.id FROM files child
+            JOIN children parent ON parent.id = child.file_id
+        ),
+        parents(id, file_id) AS (
+            SELECT id, file_id FROM files WHERE id = $1
+            UNION ALL
+            SELECT f.id, f.file_id FROM files f
+            JOIN parents a ON a.file_id = f.id
+        )
+        SELECT * FROM files
+        WHERE legal_hold = $2
+            AND (id IN (SELECT id FROM children) OR id IN (SELECT id FROM parents));
+    "#;
+
+    let mut held = vec![];
+    let mut seen = HashSet::new();
+
+    for id in ids {
+        let files = files::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                connection.get_database_backend(),
+                sql,
+                [(*id).into(), true.into()],
+            ))
+            .all(connection)
+            .await?;
+
+        for file in files {
+            if seen.insert(file.id) {
+                held.push(file.id);
+            }
+        }
+    }
+
+    Ok(held)
+}
+
+/// Refuse deleting the given files if any of them is affected by the legal hold,
+/// the attempt is recorded in the audit log for each of the held files.
+///
+/// Must be called with a connection that is not in the deleting transaction.
+pub async fn guard_delete<T: ConnectionTrait>(
+    connection: &T,
+    actor_id: Uuid,
+    ids: &[Uuid],
+) -> AppResult<()> {
+    let held = held(connection, ids).await?;
+
+    if held.is_empty() {
+        return Ok(());
+    }
+
+    audit(
+        connection,
+        actor_id,
+        &held,
+        audit_logs::Action::DeleteBlocked,
+    )
+    .await?;
+
+    Err(Error::Forbidden("file_under_legal_hold".to_string()))
+}
+
+/// Refuse moving the given files into the folder when that would take any of them
+/// out of the folder under the legal hold, otherwise the file could be moved out
+/// and deleted. The attempt is recorded in the audit log for each of the held folders.
+///
+/// Must be called with a connection that is not in the moving transaction.
+pub async fn guard_move<T: ConnectionTrait>(
+    connection: &T,
+    actor_id: Uuid,
+    ids: &[Uuid],
+    file_id: Option<Uuid>,
+) -> AppResult<()> {
+    let kept = match file_id {
+        Some(file_id) => held_parents(connection, file_id).await?,
+        None => vec![],
+    };
+
+    let mut held = vec![];
+
+    for id in ids {
+        for parent in held_parents(connection, *id).await? {
+            if parent != *id && !kept.contains(&parent) && !held.contains(&parent) {
+                held.push(parent);
+            }
+        }
+    }
+
+    if held.is_empty() {
+        return Ok(());
+    }
+
+    audit(connection, actor_id, &held, audit_logs::Action::MoveBlocked).await?;
+
+    Err(Error::Forbidden("file_under_legal_hold".to_string()))
+}
+
+/// Get the ids of the folders under the legal hold the file is in, with the file itself
+async fn held_parents<T: ConnectionTrait>(connection: &T, id: Uuid) -> AppResult<Vec<Uuid>> {
+    let sql = r#"
+        WITH RECURSIVE parents(id, file_id) AS (
+            SELECT id, file_id FROM files WHERE id = $1
+            UNION ALL
+            SELECT f.id, f.file_id FROM files f
+            JOIN parents a ON a.file_id = f.id
+        )
+        SELECT * FROM files
+        WHERE legal_hold = $2 AND id IN (SELECT id FROM parents);
+    "#;
+
+    let files = files::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            connection.get_database_backend(),
+            sql,
+            [id.into(), true.into()],
+        ))
+        .all(connection)
+        .await?;
+
+    Ok(files.into_iter().map(|file| file.id).collect())
+}
+
+/// Record the action on the files in the audit log
+pub async fn audit<T: ConnectionTrait>(
+    connection: &T,
+    actor_id: Uuid,
+    ids: &[Uuid],
+    action: audit_logs::Action,
+) -> AppResult<()> {
+    let created_at = Utc::now().timestamp();
+
+    let models = ids
+        .iter()
+        .map(|file_id| audit_logs::ActiveModel {
+            id: ActiveValue::NotSet,
+            actor_id: ActiveValue::Set(actor_id),
+            file_id: ActiveValue::Set(*file_id),
+            action: ActiveValue::Set(action),
+            created_at: ActiveValue::Set(created_at),
+        })
+        .collect::<Vec<_>>();
+
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    audit_logs::Entity::insert_many(models)
+        .exec_without_returning(connection)
+        .await?;
+
+    Ok(())
+}
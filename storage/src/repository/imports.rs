@@ -0,0 +1,211 @@
+//! Repository module for the imports of the files from other services.
+
+use chrono::Utc;
+use entity::{
+    imports, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::{
+    authorize::{self, Access},
+    data::imports::{CreateImport, Progress},
+    jobs::{ImportJob, IMPORT_FILES},
+};
+
+pub(crate) struct Imports<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Imports<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the imports of the user, newest first
+    pub(crate) async fn find(&self) -> AppResult<Vec<imports::Model>> {
+        let imports = imports::Entity::find()
+            .filter(imports::Column::UserId.eq(self.user_id))
+            .order_by_desc(imports::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(imports)
+    }
+
+    /// Get the import of the user
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<imports::Model> {
+        imports::Entity::find_by_id(id)
+            .filter(imports::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("import_not_found".to_string()))
+    }
+
+    /// Queue the new import, the user can run only one import at the time
+    pub(crate) async fn create(&self, data: CreateImport) -> AppResult<imports::Model> {
+        let (source, location, credentials, file_id) = data.into_tuple()?;
+
+        if let Some(file_id) = file_id {
+            let folder = self.repository.by_id(file_id, self.user_id).await?;
+
+            authorize::directory(
+                self.repository.connection(),
+                self.user_id,
+                Access::Own,
+                &folder,
+            )
+            .await?;
+        }
+
+        if self.find().await?.iter().any(|i| i.status.is_active()) {
+            return Err(Error::BadRequest("import_in_progress".to_string()));
+        }
+
+        let import = imports::Model {
+            id: Uuid::new_v4(),
+            user_id: self.user_id,
+            file_id,
+            source,
+            location,
+            credentials: Some(serde_json::to_string(&credentials)?),
+            status: imports::Status::Pending,
+            imported_files: 0,
+            imported_bytes: 0,
+            skipped_files: 0,
+            failed_files: 0,
+            errors: None,
+            error: None,
+            created_at: Utc::now().timestamp(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        imports::Entity::insert(imports::ActiveModel::from(import.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        jobs::repository::Repository::new(self.repository.connection())
+            .push(IMPORT_FILES, &ImportJob { id: import.id })
+            .await?;
+
+        Ok(import)
+    }
+
+    /// Stop the import, the files imported so far are kept
+    pub(crate) async fn cancel(&self, id: Uuid) -> AppResult<imports::Model> {
+        let import = self.get(id).await?;
+
+        if !import.status.is_active() {
+            return Err(Error::BadRequest("import_not_running".to_string()));
+        }
+
+        imports::Entity::update_many()
+            .set(imports::ActiveModel {
+                status: ActiveValue::Set(imports::Status::Cancelled),
+                credentials: ActiveValue::Set(None),
+                finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+                ..Default::default()
+            })
+            .filter(imports::Column::Id.eq(id))
+            .filter(
+                imports::Column::Status.is_in([imports::Status::Pending, imports::Status::Running]),
+            )
+            .exec(self.repository.connection())
+            .await?;
+
+        self.get(id).await
+    }
+}
+
+/// Mark the import as running, the import that was stopped in the meantime is not started
+pub(crate) async fn start<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+) -> AppResult<Option<imports::Model>> {
+    let results = imports::Entity::update_many()
+        .set(imports::ActiveModel {
+            status: ActiveValue::Set(imports::Status::Running),
+            started_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(imports::Column::Id.eq(id))
+        .filter(imports::Column::Status.is_in([imports::Status::Pending, imports::Status::Running]))
+        .exec(connection)
+        .await?;
+
+    if results.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    Ok(imports::Entity::find_by_id(id).one(connection).await?)
+}
+
+/// The import is still running, it could have been cancelled or removed with its folder
+pub(crate) async fn is_running<T: ConnectionTrait>(connection: &T, id: Uuid) -> AppResult<bool> {
+    let import = imports::Entity::find_by_id(id).one(connection).await?;
+
+    Ok(matches!(import, Some(i) if i.status == imports::Status::Running))
+}
+
+/// Store the progress of the running import
+pub(crate) async fn progress<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    progress: &Progress,
+) -> AppResult<()> {
+    let errors = match progress.errors.is_empty() {
+        true => None,
+        false => Some(serde_json::to_value(&progress.errors)?),
+    };
+
+    imports::Entity::update_many()
+        .set(imports::ActiveModel {
+            imported_files: ActiveValue::Set(progress.imported_files),
+            imported_bytes: ActiveValue::Set(progress.imported_bytes),
+            skipped_files: ActiveValue::Set(progress.skipped_files),
+            failed_files: ActiveValue::Set(progress.failed_files),
+            errors: ActiveValue::Set(errors),
+            ..Default::default()
+        })
+        .filter(imports::Column::Id.eq(id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+/// Mark the running import as finished, or failed with the error that stopped it.
+/// The credentials for the source are removed either way.
+pub(crate) async fn finish<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    result: AppResult<()>,
+) -> AppResult<()> {
+    let (status, error) = match result {
+        Ok(()) => (imports::Status::Finished, None),
+        Err(e) => (imports::Status::Failed, Some(e.to_string())),
+    };
+
+    imports::Entity::update_many()
+        .set(imports::ActiveModel {
+            status: ActiveValue::Set(status),
+            error: ActiveValue::Set(error),
+            credentials: ActiveValue::Set(None),
+            finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(imports::Column::Id.eq(id))
+        .filter(imports::Column::Status.eq(imports::Status::Running))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
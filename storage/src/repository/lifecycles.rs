@@ -0,0 +1,212 @@
+//! Lifecycle of the file content, when it was last downloaded and whether
+//! its chunks were moved to the cold archive and are being restored from it,
+//! or moved to the remote tier of the tiered storage.
+
+use chrono::Utc;
+use entity::{
+    file_lifecycles, files, ActiveValue, ColumnTrait, Condition, ConnectionTrait, EntityTrait,
+    OnConflict, Query, QueryFilter, QueryOrder, QuerySelect, Uuid,
+};
+use error::AppResult;
+
+/// Downloads are recorded at most once a day for the file
+const TOUCH_EVERY: i64 = 24 * 60 * 60;
+
+/// Get the lifecycle of the file, files that were never downloaded don't have it
+pub(crate) async fn find<T: ConnectionTrait>(
+    connection: &T,
+    file_id: Uuid,
+) -> AppResult<Option<file_lifecycles::Model>> {
+    Ok(file_lifecycles::Entity::find_by_id(file_id)
+        .one(connection)
+        .await?)
+}
+
+/// Record the download of the file, unless it was already recorded today
+pub(crate) async fn touch<T: ConnectionTrait>(
+    connection: &T,
+    lifecycle: Option<&file_lifecycles::Model>,
+    file_id: Uuid,
+) -> AppResult<()> {
+    let now = Utc::now().timestamp();
+
+    if lifecycle.map_or(false, |lifecycle| lifecycle.accessed_at > now - TOUCH_EVERY) {
+        return Ok(());
+    }
+
+    file_lifecycles::Entity::insert(file_lifecycles::ActiveModel {
+        file_id: ActiveValue::Set(file_id),
+        accessed_at: ActiveValue::Set(now),
+        archived_at: ActiveValue::Set(None),
+        restore_requested_at: ActiveValue::Set(None),
+        restore_requested_by: ActiveValue::Set(None),
+        demoted_at: ActiveValue::Set(None),
+    })
+    .on_conflict(
+        OnConflict::column(file_lifecycles::Column::FileId)
+            .update_column(file_lifecycles::Column::AccessedAt)
+            .to_owned(),
+    )
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Files with the chunks that nobody has touched since the given time, the
+/// oldest first. Virtual files and the unfinished uploads have nothing to archive.
+pub(crate) async fn cold<T: ConnectionTrait>(
+    connection: &T,
+    before: i64,
+    limit: u64,
+) -> AppResult<Vec<files::Model>> {
+    let touched = Query::select()
+        .column(file_lifecycles::Column::FileId)
+        .from(file_lifecycles::Entity)
+        .cond_where(
+            Condition::any()
+                .add(file_lifecycles::Column::AccessedAt.gte(before))
+                .add(file_lifecycles::Column::ArchivedAt.is_not_null()),
+        )
+        .to_owned();
+
+    let files = files::Entity::find()
+        .filter(files::Column::Mime.ne("dir"))
+        .filter(files::Column::FinishedUploadAt.lt(before))
+        .filter(files::Column::Chunks.gt(0))
+        .filter(files::Column::EncryptedPayload.is_null())
+        .filter(files::Column::Id.not_in_subquery(touched))
+        .order_by_asc(files::Column::FinishedUploadAt)
+        .limit(limit)
+        .all(connection)
+        .await?;
+
+    Ok(files)
+}
+
+/// Mark the chunks of the file as moved to the archive, the file that was never
+/// downloaded was last touched when its upload was finished.
+pub(crate) async fn archived<T: ConnectionTrait>(
+    connection: &T,
+    file_id: Uuid,
+    accessed_at: i64,
+) -> AppResult<()> {
+    file_lifecycles::Entity::insert(file_lifecycles::ActiveModel {
+        file_id: ActiveValue::Set(file_id),
+        accessed_at: ActiveValue::Set(accessed_at),
+        archived_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+        restore_requested_at: ActiveValue::Set(None),
+        restore_requested_by: ActiveValue::Set(None),
+        demoted_at: ActiveValue::Set(None),
+    })
+    .on_conflict(
+        OnConflict::column(file_lifecycles::Column::FileId)
+            .update_column(file_lifecycles::Column::ArchivedAt)
+            .to_owned(),
+    )
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark the restore of the archived file as requested by the user, returns false
+/// when the file isn't archived or its restore was already requested by someone.
+pub(crate) async fn request_restore<T: ConnectionTrait>(
+    connection: &T,
+    file_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<bool> {
+    let results = file_lifecycles::Entity::update_many()
+        .set(file_lifecycles::ActiveModel {
+            restore_requested_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            restore_requested_by: ActiveValue::Set(Some(user_id)),
+            ..Default::default()
+        })
+        .filter(file_lifecycles::Column::FileId.eq(file_id))
+        .filter(file_lifecycles::Column::ArchivedAt.is_not_null())
+        .filter(file_lifecycles::Column::RestoreRequestedAt.is_null())
+        .exec(connection)
+        .await?;
+
+    Ok(results.rows_affected == 1)
+}
+
+/// Chunks of the file are back in the storage, restoring counts as touching the file
+/// so it isn't archived again right away. Restored chunks are written to the local disk,
+/// so the file of the tiered storage is demoted again once it cools down.
+pub(crate) async fn restored<T: ConnectionTrait>(
+    connection: &T,
+    lifecycle: &file_lifecycles::Model,
+) -> AppResult<()> {
+    file_lifecycles::Entity::update(file_lifecycles::ActiveModel {
+        file_id: ActiveValue::Set(lifecycle.file_id),
+        accessed_at: ActiveValue::Set(Utc::now().timestamp()),
+        archived_at: ActiveValue::Set(None),
+        restore_requested_at: ActiveValue::Set(None),
+        restore_requested_by: ActiveValue::Set(None),
+        demoted_at: ActiveValue::Set(None),
+    })
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Files of the tiered storage that nobody has touched since the given time and still
+/// have the chunks on the local disk, the oldest first. Archived files have no chunks.
+pub(crate) async fn hot<T: ConnectionTrait>(
+    connection: &T,
+    before: i64,
+    limit: u64,
+) -> AppResult<Vec<files::Model>> {
+    let touched = Query::select()
+        .column(file_lifecycles::Column::FileId)
+        .from(file_lifecycles::Entity)
+        .cond_where(
+            Condition::any()
+                .add(file_lifecycles::Column::AccessedAt.gte(before))
+                .add(file_lifecycles::Column::ArchivedAt.is_not_null())
+                .add(file_lifecycles::Column::DemotedAt.is_not_null()),
+        )
+        .to_owned();
+
+    let files = files::Entity::find()
+        .filter(files::Column::Mime.ne("dir"))
+        .filter(files::Column::FinishedUploadAt.lt(before))
+        .filter(files::Column::Chunks.gt(0))
+        .filter(files::Column::EncryptedPayload.is_null())
+        .filter(files::Column::Id.not_in_subquery(touched))
+        .order_by_asc(files::Column::FinishedUploadAt)
+        .limit(limit)
+        .all(connection)
+        .await?;
+
+    Ok(files)
+}
+
+/// Mark the chunks of the file as moved to the remote tier, the file that was never
+/// downloaded was last touched when its upload was finished.
+pub(crate) async fn demoted<T: ConnectionTrait>(
+    connection: &T,
+    file_id: Uuid,
+    accessed_at: i64,
+) -> AppResult<()> {
+    file_lifecycles::Entity::insert(file_lifecycles::ActiveModel {
+        file_id: ActiveValue::Set(file_id),
+        accessed_at: ActiveValue::Set(accessed_at),
+        archived_at: ActiveValue::Set(None),
+        restore_requested_at: ActiveValue::Set(None),
+        restore_requested_by: ActiveValue::Set(None),
+        demoted_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+    })
+    .on_conflict(
+        OnConflict::column(file_lifecycles::Column::FileId)
+            .update_column(file_lifecycles::Column::DemotedAt)
+            .to_owned(),
+    )
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
@@ -1,19 +1,37 @@
 //! Repository module for manipulating with files in the database
 //! this module should only be used by the owner of the file
-use std::{cmp::Ordering, fmt::Display, str::FromStr};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+    sync::Arc,
+};
 
 use chrono::Utc;
+use context::Context;
 use entity::{
-    files, user_files, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
-    Order, QueryFilter, QueryOrder, Statement, Uuid, Value,
+    files, user_files, users, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait,
+    EntityTrait, Expr, JoinType, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, Statement, Uuid, Value,
 };
 use error::{AppResult, Error};
 
-use super::Repository;
+use super::{filter_mime, sort, tie_break, Repository};
+use crate::authorize::{self, authorize, Access};
 use crate::data::{
-    app_file::AppFile, query::Query as RequestQuery, rename::Rename, response::Response,
+    app_file::AppFile,
+    changes::Action,
+    content::{self, WriteContent},
+    create_file::Conflict,
+    manifest::ManifestEntry,
+    name_hash::NameHashMatch,
+    query::Query as RequestQuery,
+    rename::Rename,
+    response::Response,
+    snapshot::{Snapshot, SnapshotEntry},
 };
-use futures::future::try_join_all;
+use futures::{future::try_join_all, Stream, StreamExt};
 
 pub(crate) struct Manage<'repository, T: ConnectionTrait> {
     repository: &'repository Repository<'repository, T>,
@@ -44,13 +62,23 @@ where
 
     /// Find all files and folders that are shared with the user
     pub(crate) async fn find(&self, request_query: RequestQuery) -> AppResult<Response> {
+        let request_query = request_query.into_value()?;
         let mut parents = vec![];
 
         let user_id = self.owner_id;
-        let mut selector = self
-            .repository
-            .selector(user_id, true)
-            .filter(user_files::Column::IsOwner.eq(request_query.is_owner.unwrap_or(true)));
+
+        // Inside of the directory the shared files are listed together with the owned
+        // ones, unless asked otherwise, while the root only has the owned files.
+        let is_owner = match request_query.dir_id {
+            Some(_) => request_query.is_owner,
+            None => Some(request_query.is_owner.unwrap_or(true)),
+        };
+
+        let mut selector = self.repository.selector(user_id, is_owner == Some(true));
+
+        if let Some(is_owner) = is_owner {
+            selector = selector.filter(user_files::Column::IsOwner.eq(is_owner));
+        }
 
         if let Some(dir_id) = request_query.dir_id.as_ref() {
             let file_id = Uuid::from_str(dir_id)?;
@@ -58,6 +86,19 @@ where
             parents = self.dir_tree(file_id).await?;
 
             selector = selector.filter(files::Column::FileId.eq(file_id));
+        } else if is_owner == Some(false) {
+            // Shared with the user, the files from inside of the shared folders are in them
+            selector = selector.filter(
+                files::Column::FileId
+                    .is_null()
+                    .or(files::Column::FileId.not_in_subquery(
+                        entity::Query::select()
+                            .column(user_files::Column::FileId)
+                            .from(user_files::Entity)
+                            .and_where(user_files::Column::UserId.eq(user_id))
+                            .to_owned(),
+                    )),
+            );
         } else {
             selector = selector.filter(files::Column::FileId.is_null());
         }
@@ -66,29 +107,41 @@ where
             selector = selector.filter(files::Column::Mime.eq("dir"));
         }
 
-        let mut order = Order::Asc;
-        if let Some(ord) = &request_query.order {
-            if ord == "desc" {
-                order = Order::Desc;
-            }
+        if let Some(mime) = request_query.mime.as_deref() {
+            selector = filter_mime(selector, mime);
         }
 
-        if let Some(order_by) = request_query.order_by.as_ref() {
-            let column = match order_by.as_str() {
-                "modified_at" => files::Column::FileModifiedAt,
-                "size" => files::Column::Size,
-                _ => return Err(Error::BadRequest("invalid_order_by".to_string())),
-            };
+        // Counted only for the pages, otherwise all of the files are in the response
+        let paginated = request_query.limit.is_some() || request_query.offset.is_some();
+        let total = match paginated {
+            true => Some(selector.clone().count(self.repository.connection()).await?),
+            false => None,
+        };
 
-            selector = selector.order_by(column, order);
+        if let Some(order_by) = request_query.order_by.as_deref() {
+            selector = sort(selector, order_by, request_query.order.as_deref());
         }
 
-        selector
+        let mut selector = tie_break(selector);
+
+        if let Some(limit) = request_query.limit {
+            selector = selector.limit(limit);
+        }
+
+        if let Some(offset) = request_query.offset {
+            selector = selector.offset(offset);
+        }
+
+        let children = selector
             .into_model::<AppFile>()
             .all(self.repository.connection())
-            .await
-            .map(|children| Response { parents, children })
-            .map_err(Error::from)
+            .await?;
+
+        Ok(Response {
+            parents,
+            total: total.unwrap_or(children.len() as u64),
+            children,
+        })
     }
 
     /// Get the directory tree for the owner,
@@ -151,6 +204,36 @@ where
     /// Get the file or a directory, if we get a directory we will also
     /// recursively get all the files and directories inside it
     pub(crate) async fn file_tree(&self, id: Uuid) -> AppResult<Vec<AppFile>> {
+        let ids = self.tree_ids(id).await?;
+
+        let user_id = self.owner_id;
+
+        let mut results = self
+            .repository
+            .selector(user_id, true)
+            .filter(files::Column::Id.is_in(ids))
+            .into_model::<AppFile>()
+            .all(self.repository.connection())
+            .await
+            .map_err(Error::from)?;
+
+        results.sort_by(|a, b| {
+            if a.file_id.is_none() || a.file_id == Some(b.id) {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        });
+
+        if results.is_empty() {
+            return Err(Error::NotFound("directory_not_found".to_string()));
+        }
+
+        Ok(results)
+    }
+
+    /// Get the ids of the file or the directory and everything inside of it, recursively
+    pub(crate) async fn tree_ids(&self, id: Uuid) -> AppResult<Vec<Uuid>> {
         let sql = r#"
             WITH RECURSIVE file_tree(id, file_id) AS (
             SELECT id, file_id FROM files WHERE id = $1
@@ -181,30 +264,159 @@ where
             })
             .collect::<Vec<Uuid>>();
 
-        let user_id = self.owner_id;
+        Ok(ids)
+    }
 
-        let mut results = self
-            .repository
-            .selector(user_id, true)
-            .filter(files::Column::Id.is_in(ids))
-            .into_model::<AppFile>()
+    /// Files and directories inside of the directory, or in the root of the owner, down to
+    /// the given depth. The whole tree is found with a single recursive query and loaded
+    /// level by level, the files whose directory the user can't see are left out.
+    pub(crate) async fn descendants(
+        &self,
+        root: Option<Uuid>,
+        depth: i64,
+    ) -> AppResult<Vec<AppFile>> {
+        let (start, mut values): (&str, Vec<Value>) = match root {
+            Some(root) => ("file_id = $1", vec![root.into()]),
+            None => (
+                "file_id IS NULL AND id IN (SELECT file_id FROM user_files WHERE user_id = $1 AND is_owner = $2)",
+                vec![self.owner_id.into(), true.into()],
+            ),
+        };
+        values.push(depth.into());
+
+        let sql = format!(
+            r#"
+            WITH RECURSIVE file_tree(id, depth) AS (
+            SELECT id, 1 FROM files WHERE {} AND deleted_at IS NULL
+            UNION ALL
+            SELECT child.id, parent.depth + 1 FROM files child
+            JOIN file_tree parent ON parent.id = child.file_id
+            WHERE parent.depth < ${} AND child.deleted_at IS NULL
+            )
+            SELECT id, depth FROM file_tree;
+        "#,
+            start,
+            values.len()
+        );
+
+        let levels = files::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                self.repository.connection().get_database_backend(),
+                &sql,
+                values,
+            ))
+            .into_json()
             .all(self.repository.connection())
-            .await
-            .map_err(Error::from)?;
+            .await?
+            .into_iter()
+            .filter_map(|json| {
+                let id = Uuid::from_str(json.get("id")?.as_str()?).ok()?;
+                let depth = json.get("depth")?.as_i64()?;
 
-        results.sort_by(|a, b| {
-            if a.file_id.is_none() || a.file_id == Some(b.id) {
-                Ordering::Greater
-            } else {
-                Ordering::Less
+                Some((id, depth))
+            })
+            .collect::<HashMap<Uuid, i64>>();
+
+        let mut files = tie_break(
+            self.repository
+                .selector(self.owner_id, false)
+                .filter(files::Column::Id.is_in(levels.keys().cloned().collect::<Vec<_>>())),
+        )
+        .into_model::<AppFile>()
+        .all(self.repository.connection())
+        .await?;
+
+        files.sort_by_key(|file| levels.get(&file.id).cloned().unwrap_or_default());
+
+        let mut reachable = root.into_iter().collect::<HashSet<Uuid>>();
+        files.retain(|file| {
+            let visible = match file.file_id {
+                Some(parent_id) => reachable.contains(&parent_id),
+                None => root.is_none(),
+            };
+
+            if visible {
+                reachable.insert(file.id);
             }
+
+            visible
         });
 
-        if results.is_empty() {
-            return Err(Error::NotFound("directory_not_found".to_string()));
+        Ok(files)
+    }
+
+    /// Build the manifest entries of the given files, only the files owned by the user are returned
+    pub(crate) async fn manifest(&self, ids: &[Uuid]) -> AppResult<Vec<ManifestEntry>> {
+        let files = self
+            .repository
+            .selector(self.owner_id, true)
+            .filter(files::Column::Id.is_in(ids.to_vec()))
+            .order_by(files::Column::Id, Order::Asc)
+            .into_model::<AppFile>()
+            .all(self.repository.connection())
+            .await?;
+
+        let versions = self
+            .repository
+            .activities(self.owner_id)
+            .versions(ids)
+            .await?;
+
+        Ok(files
+            .into_iter()
+            .map(|file| ManifestEntry {
+                version: versions.get(&file.id).copied().unwrap_or_default(),
+                id: file.id,
+                parent: file.file_id,
+                name_hash: file.name_hash,
+                size: file.size,
+                sha256: file.sha256,
+            })
+            .collect())
+    }
+
+    /// Load the folder and everything inside of it, only the files owned by the user
+    pub(crate) async fn tree(&self, id: Uuid) -> AppResult<Vec<AppFile>> {
+        self.load_tree(id, false).await
+    }
+
+    /// Same as [Manage::tree], only the files in the trash are there as well
+    pub(crate) async fn tree_with_trash(&self, id: Uuid) -> AppResult<Vec<AppFile>> {
+        self.load_tree(id, true).await
+    }
+
+    async fn load_tree(&self, id: Uuid, with_trash: bool) -> AppResult<Vec<AppFile>> {
+        let ids = self.tree_ids(id).await?;
+        let mut files = Vec::with_capacity(ids.len());
+
+        for batch in ids.chunks(MANIFEST_STREAM_BATCH_SIZE) {
+            let selector = match with_trash {
+                true => self.repository.selector_with_trash(self.owner_id, true),
+                false => self.repository.selector(self.owner_id, true),
+            };
+
+            files.extend(
+                selector
+                    .filter(files::Column::Id.is_in(batch.to_vec()))
+                    .into_model::<AppFile>()
+                    .all(self.repository.connection())
+                    .await?,
+            );
         }
 
-        Ok(results)
+        Ok(files)
+    }
+
+    /// Build the integrity snapshot of the folder and everything inside of it
+    pub(crate) async fn snapshot(&self, id: Uuid) -> AppResult<Snapshot> {
+        let entries = self
+            .tree(id)
+            .await?
+            .into_iter()
+            .map(SnapshotEntry::from)
+            .collect::<Vec<_>>();
+
+        Ok(Snapshot::build(id, &entries))
     }
 
     /// Load the file from the database by its name hash and by its parent id
@@ -230,6 +442,8 @@ where
         selector
             .filter(user_files::Column::UserId.eq(user_id))
             .filter(user_files::Column::IsOwner.eq(true))
+            .order_by_asc(files::Column::CreatedAt)
+            .order_by_asc(files::Column::Id)
             .into_model::<AppFile>()
             .one(self.repository.connection())
             .await
@@ -237,6 +451,63 @@ where
             .ok_or_else(|| Error::NotFound("file_not_found".to_string()))
     }
 
+    /// Look up the files by many name hashes in the folder at once, the result is
+    /// in the order of the given name hashes. When there are more files with the same
+    /// name hash, the oldest one is the one [Manage::by_name] finds.
+    pub(crate) async fn by_names(
+        &self,
+        hashes: Vec<String>,
+        parent_id: Option<Uuid>,
+    ) -> AppResult<Vec<NameHashMatch>> {
+        let mut query = files::Entity::find()
+            .select_only()
+            .column(files::Column::Id)
+            .column(files::Column::NameHash)
+            .join(JoinType::InnerJoin, files::Relation::UserFiles.def())
+            .filter(user_files::Column::UserId.eq(self.owner_id))
+            .filter(user_files::Column::IsOwner.eq(true))
+            .filter(files::Column::NameHash.is_in(hashes.clone()))
+            .filter(files::Column::DeletedAt.is_null());
+
+        query = match parent_id {
+            Some(parent_id) => query.filter(files::Column::FileId.eq(parent_id)),
+            None => query.filter(files::Column::FileId.is_null()),
+        };
+
+        let found = query
+            .order_by_asc(files::Column::CreatedAt)
+            .order_by_asc(files::Column::Id)
+            .into_tuple::<(Uuid, String)>()
+            .all(self.repository.connection())
+            .await?;
+
+        let mut matches = hashes
+            .into_iter()
+            .map(|name_hash| NameHashMatch {
+                name_hash,
+                id: None,
+                collisions: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        let positions = matches
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.name_hash.clone(), i))
+            .collect::<HashMap<_, _>>();
+
+        for (id, name_hash) in found {
+            if let Some(m) = positions.get(&name_hash).map(|i| &mut matches[*i]) {
+                match m.id {
+                    Some(_) => m.collisions.push(id),
+                    None => m.id = Some(id),
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
     /// Move multiple files and folders to a new parent directory
     pub(crate) async fn move_many(
         &self,
@@ -252,16 +523,22 @@ where
             }
         }
 
-        let existing_file_ids = self
+        let mut affected = ids.clone();
+        affected.extend(file_id);
+        self.repository
+            .spaces(self.owner_id)
+            .check_write(&affected)
+            .await?;
+
+        let existing_files = self
             .repository
             .selector(self.owner_id, true)
             .filter(files::Column::Id.is_in(ids))
             .into_model::<AppFile>()
             .all(self.repository.connection())
-            .await?
-            .into_iter()
-            .map(|f| f.id)
-            .collect::<Vec<_>>();
+            .await?;
+
+        let existing_file_ids = existing_files.iter().map(|f| f.id).collect::<Vec<_>>();
 
         let active_model = files::ActiveModel {
             file_id: ActiveValue::Set(file_id),
@@ -274,21 +551,193 @@ where
             .exec(self.repository.connection())
             .await?;
 
+        // For the sync clients, moved file is gone from the old directory
+        // and it appeared in the new one.
+        let moved = existing_files
+            .iter()
+            .filter(|f| f.file_id != file_id)
+            .collect::<Vec<_>>();
+
+        let activities = self.repository.activities(self.owner_id);
+        activities
+            .record(Action::Deleted, moved.iter().map(|f| (f.id, f.file_id)))
+            .await?;
+        activities
+            .record(Action::Created, moved.iter().map(|f| (f.id, file_id)))
+            .await?;
+
         Ok(results.rows_affected)
     }
 
+    /// Move a single file or a folder with everything inside of it into a new parent
+    /// directory, the name must not be taken in the destination and folders can't be
+    /// moved into themselves or anything inside of them.
+    pub(crate) async fn move_file(&self, id: Uuid, file_id: Option<Uuid>) -> AppResult<AppFile> {
+        let file = self.repository.by_id(id, self.owner_id).await?;
+
+        authorize(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Own,
+            &file,
+        )
+        .await?;
+
+        self.destination(&file, file_id).await?;
+
+        if file.file_id != file_id && self.by_name(file.name_hash.clone(), file_id).await.is_ok() {
+            return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+        }
+
+        self.move_many(vec![file.id], file_id).await?;
+
+        self.repository.by_id(file.id, self.owner_id).await
+    }
+
+    /// Copy a file or a folder with everything inside of it into the destination directory.
+    ///
+    /// Copies keep the names and the keys of the originals, so the content doesn't have
+    /// to be encrypted again, files that are not uploaded yet are left out. Returns pairs
+    /// of the original and its copy, the folder (or the file) that was copied comes first.
+    /// The chunks are not copied here, that is up to the storage provider.
+    pub(crate) async fn copy(
+        &self,
+        id: Uuid,
+        file_id: Option<Uuid>,
+    ) -> AppResult<Vec<(AppFile, AppFile)>> {
+        let tree = self.tree(id).await?;
+        let root = tree
+            .iter()
+            .find(|file| file.id == id)
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        self.destination(root, file_id).await?;
+
+        if self.by_name(root.name_hash.clone(), file_id).await.is_ok() {
+            return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+        }
+
+        let mut children: HashMap<Uuid, Vec<&AppFile>> = HashMap::new();
+        for file in tree.iter().filter(|file| file.id != id) {
+            if let Some(parent) = file.file_id {
+                children.entry(parent).or_default().push(file);
+            }
+        }
+
+        // Parents are always copied before their content
+        let mut queue = vec![(root, file_id)];
+        let mut copies = vec![];
+        let tokens = self.repository.tokens(self.owner_id);
+
+        while let Some((file, parent)) = queue.pop() {
+            if file.is_file() && file.finished_upload_at.is_none() {
+                continue;
+            }
+
+            let create_file = files::ActiveModel {
+                id: ActiveValue::Set(Uuid::new_v4()),
+                name_hash: ActiveValue::Set(file.name_hash.clone()),
+                encrypted_name: ActiveValue::Set(file.encrypted_name.clone()),
+                encrypted_thumbnail: ActiveValue::Set(file.encrypted_thumbnail.clone()),
+                mime: ActiveValue::Set(file.mime.clone()),
+                size: ActiveValue::Set(file.size),
+                chunks: ActiveValue::Set(file.chunks),
+                chunks_stored: ActiveValue::Set(file.chunks_stored),
+                file_id: ActiveValue::Set(parent),
+                file_modified_at: ActiveValue::Set(file.file_modified_at),
+                created_at: ActiveValue::Set(Utc::now().timestamp()),
+                finished_upload_at: ActiveValue::Set(file.finished_upload_at),
+                sha256: ActiveValue::Set(file.sha256.clone()),
+                conflict_of: ActiveValue::Set(None),
+                conflict_device: ActiveValue::Set(None),
+                legal_hold: ActiveValue::Set(false),
+                crypto_version: ActiveValue::Set(file.crypto_version),
+                encrypted_payload: ActiveValue::Set(file.encrypted_payload.clone()),
+                deleted_at: ActiveValue::Set(None),
+            };
+
+            let hashed_tokens = tokens
+                .get_tokens(file.id)
+                .await?
+                .iter()
+                .map(|token| token.to_string())
+                .collect::<Vec<_>>();
+
+            let copy = self
+                .create(create_file, &file.encrypted_key, hashed_tokens)
+                .await?;
+
+            super::escrow::copy(self.repository.connection(), file.id, copy.id).await?;
+
+            if let Some(content) = children.get(&file.id) {
+                queue.extend(content.iter().map(|child| (*child, Some(copy.id))));
+            }
+
+            copies.push((file.clone(), copy));
+        }
+
+        Ok(copies)
+    }
+
+    /// Make sure the file can be moved or copied into the destination directory
+    async fn destination(&self, file: &AppFile, file_id: Option<Uuid>) -> AppResult<()> {
+        let file_id = match file_id {
+            Some(file_id) => file_id,
+            None => return Ok(()),
+        };
+
+        if file.is_dir() && self.tree_ids(file.id).await?.contains(&file_id) {
+            return Err(Error::BadRequest("cannot_move_to_itself".to_string()));
+        }
+
+        let parent = self.repository.by_id(file_id, self.owner_id).await?;
+        let spaces = self.repository.spaces(self.owner_id);
+
+        if !parent.is_dir() || !spaces.can_write(&parent).await? {
+            return Err(Error::BadRequest("parent_directory_not_found".to_string()));
+        }
+
+        Ok(())
+    }
+
     /// Rename a file or directory for the owner
     pub(crate) async fn rename(&self, id: Uuid, data: Rename) -> AppResult<AppFile> {
         let (active_model, hashed_tokens, name_hash) = data.into_active_model(id)?;
 
         let file = self.repository.by_id(id, self.owner_id).await?;
 
-        if self.by_name(&name_hash, file.file_id).await.is_ok() {
-            return Err(Error::BadRequest("file_already_exists".to_string()));
+        if !self
+            .repository
+            .spaces(self.owner_id)
+            .can_write(&file)
+            .await?
+        {
+            return Err(Error::NotFound("file_not_found".to_string()));
+        }
+
+        // Renames in the same directory wait for each other until the transaction is done,
+        // so two files can't take the same name at once.
+        match file.file_id {
+            Some(parent) => {
+                files::Entity::find_by_id(parent)
+                    .lock_exclusive()
+                    .one(self.repository.connection())
+                    .await?;
+            }
+            None => {
+                users::Entity::find_by_id(self.owner_id)
+                    .lock_exclusive()
+                    .one(self.repository.connection())
+                    .await?;
+            }
         }
 
-        if !file.is_owner || file.user_id != self.owner_id {
-            return Err(Error::NotFound("file_not_found".to_string()));
+        // Same name can be sent again, only encrypted differently
+        match self.by_name(&name_hash, file.file_id).await {
+            Ok(existing) if existing.id != file.id => {
+                return Err(Error::BadRequest("file_already_exists".to_string()));
+            }
+            _ => {}
         }
 
         active_model.update(self.repository.connection()).await?;
@@ -298,12 +747,83 @@ where
             .rename(id, hashed_tokens)
             .await?;
 
+        self.repository
+            .activities(self.owner_id)
+            .record(Action::Modified, [(file.id, file.file_id)])
+            .await?;
+
         self.repository.by_id(file.id, file.user_id).await
     }
 
-    /// Delete many files or directories for the owner
+    /// Replace the content of the small file, the file is given the new size and hash
+    /// and it gets a new version. Returns the file as it was before with the file after,
+    /// the caller keeps the previous content and swaps the chunk once this is committed.
+    pub(crate) async fn replace_content(
+        &self,
+        id: Uuid,
+        data: WriteContent,
+        max_size: u64,
+        quota: Option<u64>,
+    ) -> AppResult<(AppFile, AppFile)> {
+        let (size, sha256, version) = data.into_tuple()?;
+        let file = self.repository.by_id(id, self.owner_id).await?;
+
+        content::check(&file, max_size)?;
+
+        if size as u64 > max_size {
+            return Err(Error::as_validation("size", &format!("max:{}", max_size)));
+        }
+
+        if !self
+            .repository
+            .spaces(self.owner_id)
+            .can_write(&file)
+            .await?
+        {
+            return Err(Error::NotFound("file_not_found".to_string()));
+        }
+
+        // Previous content is kept as the version, so the new one takes up space of its own
+        self.repository
+            .query(self.owner_id)
+            .check_quota(quota, size)
+            .await?;
+
+        let activities = self.repository.activities(self.owner_id);
+        let current = activities.versions(&[file.id]).await?;
+
+        if current.get(&file.id).copied().unwrap_or_default() != version {
+            return Err(Error::Conflict("version_conflict".to_string()));
+        }
+
+        files::Entity::update(files::ActiveModel {
+            id: ActiveValue::Set(file.id),
+            size: ActiveValue::Set(Some(size)),
+            sha256: ActiveValue::Set(sha256),
+            file_modified_at: ActiveValue::Set(Utc::now().timestamp()),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        activities
+            .record(Action::Modified, [(file.id, file.file_id)])
+            .await?;
+
+        let replaced = self.repository.by_id(file.id, self.owner_id).await?;
+
+        Ok((file, replaced))
+    }
+
+    /// Delete many files or directories for the owner, together with the previous versions of the files
     pub(crate) async fn delete_many(&self, ids: Vec<Uuid>) -> AppResult<Vec<AppFile>> {
-        let mut files = try_join_all(ids.into_iter().map(|id| self.file_tree(id)))
+        self.repository
+            .spaces(self.owner_id)
+            .check_write(&ids)
+            .await?;
+
+        // Files trashed on their own inside of the folders go with them for good
+        let mut files = try_join_all(ids.into_iter().map(|id| self.tree_with_trash(id)))
             .await?
             .into_iter()
             .flatten()
@@ -315,16 +835,124 @@ where
         files.sort_by(|a, b| a.id.cmp(&b.id));
         files.dedup_by(|a, b| a.id == b.id);
 
-        let ids: Vec<Uuid> = files.iter().map(|f| f.id).collect();
+        let mut ids: Vec<Uuid> = files.iter().map(|f| f.id).collect();
+
+        // Previous versions of the files are gone together with them
+        let versions = self.repository.versions(self.owner_id).of(&ids).await?;
+        ids.extend(versions.iter().map(|v| v.id));
 
         files::Entity::delete_many()
             .filter(files::Column::Id.is_in(ids))
             .exec(self.repository.connection())
             .await?;
 
+        // Files in the trash are already gone for the sync clients
+        self.repository
+            .activities(self.owner_id)
+            .record(
+                Action::Deleted,
+                files
+                    .iter()
+                    .filter(|f| f.deleted_at.is_none())
+                    .map(|f| (f.id, f.file_id)),
+            )
+            .await?;
+
+        files.extend(versions);
+
         Ok(files)
     }
 
+    /// Look for the file the client has already created with its own id.
+    ///
+    /// When the file exists with one of the given names in the same directory the create
+    /// request is a repeated one and the existing file is returned, another file of the user
+    /// with that id is a conflict because the client ids have to be unique. Ids of the files
+    /// of the other users are answered the same as the unused ones.
+    pub(crate) async fn reserved(
+        &self,
+        id: Uuid,
+        name_hashes: &[&str],
+        parent_id: Option<Uuid>,
+    ) -> AppResult<Option<AppFile>> {
+        let file = match self.repository.by_id(id, self.owner_id).await {
+            Ok(file) if file.is_owner => file,
+            _ => return Ok(None),
+        };
+
+        if name_hashes.contains(&file.name_hash.as_str()) && file.file_id == parent_id {
+            return Ok(Some(file));
+        }
+
+        Err(Error::BadRequest("file_id_conflict".to_string()))
+    }
+
+    /// Get the file a concurrent request with the same client id created after [Manage::reserved]
+    /// found nothing, when the insert of ours failed with the given error. The id taken by
+    /// anyone else is refused the same way as any other invalid id.
+    pub(crate) async fn reserved_meanwhile(
+        &self,
+        id: Uuid,
+        name_hashes: &[&str],
+        parent_id: Option<Uuid>,
+        error: Error,
+    ) -> AppResult<AppFile> {
+        if let Some(file) = self.reserved(id, name_hashes, parent_id).await? {
+            return Ok(file);
+        }
+
+        let taken = files::Entity::find_by_id(id)
+            .one(self.repository.connection())
+            .await?
+            .is_some();
+
+        match taken {
+            true => Err(Error::as_validation("id", "invalid_id")),
+            false => Err(error),
+        }
+    }
+
+    /// Resolve a new file whose name is already taken by an existing file in the directory.
+    ///
+    /// Same content means another client has already uploaded the file, so the existing one
+    /// is returned. Different content turns the new file into a conflicted copy under the name
+    /// the client sent for it, pointing to the existing file, so neither version is lost.
+    pub(crate) async fn conflict(
+        &self,
+        create_file: &mut files::ActiveModel,
+        existing: AppFile,
+        conflict: Option<Conflict>,
+    ) -> AppResult<Option<AppFile>> {
+        let conflict = match conflict {
+            Some(conflict) if existing.is_file() => conflict,
+            _ => return Err(Error::BadRequest("file_or_directory_exists".to_string())),
+        };
+
+        let same_content = match &create_file.sha256 {
+            ActiveValue::Set(Some(sha256)) => existing.sha256.as_ref() == Some(sha256),
+            _ => false,
+        };
+
+        if same_content {
+            return Ok(Some(existing));
+        }
+
+        if self
+            .by_name(conflict.name_hash.clone(), existing.file_id)
+            .await
+            .is_ok()
+        {
+            return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+        }
+
+        create_file.name_hash = ActiveValue::Set(conflict.name_hash);
+        create_file.encrypted_name = ActiveValue::Set(conflict.encrypted_name);
+        create_file.conflict_of = ActiveValue::Set(Some(existing.id));
+        create_file.conflict_device = ActiveValue::Set(conflict.device);
+
+        Ok(None)
+    }
+
     /// Create a file entry in the database and set the owner with the
     /// sent encrypted_key.
     pub(crate) async fn create(
@@ -334,12 +962,13 @@ where
         hashed_tokens: Vec<String>,
     ) -> AppResult<AppFile> {
         // Check if the file_id is set, if it is, check if the parent is directory
-        // and if the current user is the owner of that directory.
+        // and if the current user can write into that directory.
         if let Some(file_id) = create_file.file_id.clone().into_value() {
             if file_id.to_string().as_str() != "NULL" {
                 let parent = self.repository.by_id(file_id, self.owner_id).await?;
+                let spaces = self.repository.spaces(self.owner_id);
 
-                if !parent.is_owner || !parent.is_dir() {
+                if !parent.is_dir() || !spaces.can_write(&parent).await? {
                     return Err(Error::BadRequest("parent_directory_not_found".to_string()));
                 }
             }
@@ -366,38 +995,92 @@ where
             is_owner: ActiveValue::Set(true),
             encrypted_key: ActiveValue::Set(encrypted_key.to_string()),
             created_at: ActiveValue::Set(Utc::now().timestamp()),
+            starts_at: ActiveValue::NotSet,
             expires_at: ActiveValue::NotSet,
+            attributes: ActiveValue::NotSet,
+            permission: ActiveValue::NotSet,
         };
 
         user_files::Entity::insert(user_file)
             .exec_without_returning(self.repository.connection())
             .await?;
 
-        self.repository
+        let file = self
+            .repository
             .by_id(file_id, self.owner_id)
             .await
-            .map(|f| f.is_new(true))
+            .map(|f| f.is_new(true))?;
+
+        self.repository
+            .activities(self.owner_id)
+            .record(Action::Created, [(file.id, file.file_id)])
+            .await?;
+
+        Ok(file)
     }
 
     /// Finish the upload of a file by setting the finished_upload_at field
+    ///
+    /// The update is conditional on the file not being finished already, so when the last
+    /// two chunks arrive at the same time only one of them will actually finish the file.
     pub(crate) async fn finish(&self, file: &AppFile) -> AppResult<AppFile> {
-        if !file.is_owner || file.user_id != self.owner_id || file.is_dir() {
-            return Err(Error::NotFound("file_not_found".to_string()));
-        }
+        authorize::file(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Write,
+            file,
+        )
+        .await?;
 
         let chunks = file
             .chunks
             .ok_or(Error::BadRequest("file_has_no_chunks".to_string()))?;
 
-        files::ActiveModel {
-            id: ActiveValue::Set(file.id),
-            chunks_stored: ActiveValue::Set(Some(chunks)),
-            finished_upload_at: ActiveValue::Set(Some(Utc::now().timestamp())),
-            ..Default::default()
+        let results = files::Entity::update_many()
+            .col_expr(files::Column::ChunksStored, Expr::value(chunks))
+            .col_expr(
+                files::Column::FinishedUploadAt,
+                Expr::value(Utc::now().timestamp()),
+            )
+            .filter(files::Column::Id.eq(file.id))
+            .filter(files::Column::FinishedUploadAt.is_null())
+            .exec(self.repository.connection())
+            .await?;
+
+        if results.rows_affected > 0 {
+            self.repository
+                .activities(self.owner_id)
+                .record(Action::Modified, [(file.id, file.file_id)])
+                .await?;
         }
-        .update(self.repository.connection())
-        .await?;
 
         self.repository.by_id(file.id, file.user_id).await
     }
 }
+
+/// How many files are loaded at once while streaming the manifest
+pub(crate) const MANIFEST_STREAM_BATCH_SIZE: usize = 500;
+
+/// Stream the manifest of the given folders and everything inside of them in batches,
+/// so the whole tree is never held in memory while it is being sent to the client.
+pub(crate) fn manifest_stream(
+    context: Arc<Context>,
+    owner_id: Uuid,
+    ids: Vec<Uuid>,
+) -> impl Stream<Item = AppResult<Vec<ManifestEntry>>> {
+    let batches = ids
+        .chunks(MANIFEST_STREAM_BATCH_SIZE)
+        .map(|batch| batch.to_vec())
+        .collect::<Vec<_>>();
+
+    futures::stream::iter(batches).then(move |batch| {
+        let context = context.clone();
+
+        async move {
+            Repository::new(&context.db)
+                .manage(owner_id)
+                .manifest(&batch)
+                .await
+        }
+    })
+}
@@ -1,14 +1,45 @@
+pub(crate) mod access_keys;
+pub(crate) mod activities;
+pub(crate) mod attributes;
 pub(crate) mod cached;
+pub(crate) mod dedup;
+pub(crate) mod escrow;
+pub(crate) mod exports;
+pub(crate) mod external_exports;
+pub(crate) mod file_requests;
+pub(crate) mod folder_templates;
+pub(crate) mod holds;
+pub(crate) mod imports;
+pub(crate) mod lifecycles;
 pub(crate) mod manage;
+pub(crate) mod policies;
 pub(crate) mod query;
+pub(crate) mod rebalances;
+pub(crate) mod restrictions;
+pub(crate) mod rewrap;
+pub(crate) mod shares;
+pub(crate) mod spaces;
 pub(crate) mod tokens;
+pub(crate) mod transfers;
+pub(crate) mod trash;
+pub(crate) mod tus;
+pub(crate) mod verdicts;
+pub(crate) mod versions;
 
 use crate::data::app_file::AppFile;
 
-use self::{manage::Manage, query::Query, tokens::Tokens};
+use self::{
+    access_keys::AccessKeys, activities::Activities, attributes::Attributes, exports::Exports,
+    external_exports::ExternalExports, file_requests::FileRequests,
+    folder_templates::FolderTemplates, imports::Imports, manage::Manage, policies::Policies,
+    query::Query, rewrap::Rewrap, shares::Shares, spaces::Spaces, tokens::Tokens,
+    transfers::Transfers, trash::Trash, tus::Tus, versions::Versions,
+};
+use chrono::Utc;
 use entity::{
-    files, links, user_files, ColumnTrait, ConnectionTrait, EntityTrait, Expr, IntoCondition,
-    JoinType, QueryFilter, QuerySelect, RelationTrait, Select, Uuid, Value,
+    files, links, user_files, ColumnTrait, Condition, ConnectionTrait, DynIden, EntityTrait, Expr,
+    IntoCondition, JoinType, Order, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Select,
+    SimpleExpr, Uuid, Value,
 };
 use error::{AppResult, Error};
 use std::fmt::Display;
@@ -54,6 +85,161 @@ where
         Tokens::<'repository>::new(self, user_id)
     }
 
+    /// Record and load the activities on the user files
+    pub(crate) fn activities<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> Activities<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Activities::<'repository>::new(self, user_id)
+    }
+
+    /// Custom attributes the user attaches to their files
+    pub(crate) fn attributes<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> Attributes<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Attributes::<'repository>::new(self, user_id)
+    }
+
+    /// Manage the sharing policies of the owners folders
+    pub(crate) fn policies<'repository>(
+        &'repository self,
+        owner_id: Uuid,
+    ) -> Policies<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Policies::<'repository>::new(self, owner_id)
+    }
+
+    /// Re-wrap jobs of the file keys the users client has to do
+    pub(crate) fn rewrap<'repository>(&'repository self, user_id: Uuid) -> Rewrap<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Rewrap::<'repository>::new(self, user_id)
+    }
+
+    /// Shares of the owners files with other users
+    pub(crate) fn shares<'repository>(&'repository self, owner_id: Uuid) -> Shares<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Shares::<'repository>::new(self, owner_id)
+    }
+
+    /// Spaces of the groups the user is member of
+    pub(crate) fn spaces<'repository>(&'repository self, user_id: Uuid) -> Spaces<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Spaces::<'repository>::new(self, user_id)
+    }
+
+    /// Exports of all the data of the user
+    pub(crate) fn exports<'repository>(&'repository self, user_id: Uuid) -> Exports<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Exports::<'repository>::new(self, user_id)
+    }
+
+    /// File requests the owner receives the files through
+    pub(crate) fn file_requests<'repository>(
+        &'repository self,
+        owner_id: Uuid,
+    ) -> FileRequests<'repository, T>
+    where
+        Self: 'repository,
+    {
+        FileRequests::<'repository>::new(self, owner_id)
+    }
+
+    /// Access keys the S3 tools sign their requests with
+    pub(crate) fn access_keys<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> AccessKeys<'repository, T>
+    where
+        Self: 'repository,
+    {
+        AccessKeys::<'repository>::new(self, user_id)
+    }
+
+    /// Folder templates the user creates the folder structures from
+    pub(crate) fn folder_templates<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> FolderTemplates<'repository, T>
+    where
+        Self: 'repository,
+    {
+        FolderTemplates::<'repository>::new(self, user_id)
+    }
+
+    /// Imports of the files from other services into the users folders
+    pub(crate) fn imports<'repository>(&'repository self, user_id: Uuid) -> Imports<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Imports::<'repository>::new(self, user_id)
+    }
+
+    /// Exports of the users folders to external providers
+    pub(crate) fn external_exports<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> ExternalExports<'repository, T>
+    where
+        Self: 'repository,
+    {
+        ExternalExports::<'repository>::new(self, user_id)
+    }
+
+    /// Uploads of the chunks over the tus protocol
+    pub(crate) fn tus<'repository>(&'repository self, user_id: Uuid) -> Tus<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Tus::<'repository>::new(self, user_id)
+    }
+
+    /// Content the user has uploaded and downloaded each day
+    pub(crate) fn transfers<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> Transfers<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Transfers::<'repository>::new(self, user_id)
+    }
+
+    /// Files and folders in the trash of the user
+    pub(crate) fn trash<'repository>(&'repository self, user_id: Uuid) -> Trash<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Trash::<'repository>::new(self, user_id)
+    }
+
+    /// Previous versions of the user's files
+    pub(crate) fn versions<'repository>(
+        &'repository self,
+        user_id: Uuid,
+    ) -> Versions<'repository, T>
+    where
+        Self: 'repository,
+    {
+        Versions::<'repository>::new(self, user_id)
+    }
+
     /// Get the inner database connection
     pub(crate) fn connection(&self) -> &impl ConnectionTrait {
         self.connection
@@ -73,9 +259,39 @@ where
             .ok_or_else(|| Error::NotFound(format!("file_not_found:{}", id)))
     }
 
-    /// Preset the selector for the given user, maybe check if the user is the owner
+    /// Preset the selector for the given user, maybe check if the user is the owner.
+    /// Shares that haven't started yet or have expired don't give the user access to the file.
+    /// Files in the trash are left out, as if they were gone already.
     pub(crate) fn selector(&self, user_id: Uuid, check_is_owner: bool) -> Select<files::Entity> {
+        self.selector_with_trash(user_id, check_is_owner)
+            .filter(files::Column::DeletedAt.is_null())
+    }
+
+    /// Same as [Repository::selector], only the files in the trash are there as well.
+    /// Previous versions of the files are never there, see [Versions].
+    pub(crate) fn selector_with_trash(
+        &self,
+        user_id: Uuid,
+        check_is_owner: bool,
+    ) -> Select<files::Entity> {
+        self.selector_all(user_id, check_is_owner).filter(
+            files::Column::Id.not_in_subquery(
+                entity::Query::select()
+                    .column(entity::versions::Column::Id)
+                    .from(entity::versions::Entity)
+                    .to_owned(),
+            ),
+        )
+    }
+
+    /// Every file the user has access to, together with the trash and the versions
+    pub(crate) fn selector_all(
+        &self,
+        user_id: Uuid,
+        check_is_owner: bool,
+    ) -> Select<files::Entity> {
         let mut selector = files::Entity::find().select_only();
+        let now = Utc::now().timestamp();
 
         entity::join::add_columns_with_prefix::<_, files::Entity>(&mut selector, "file");
         entity::join::add_columns_with_prefix::<_, user_files::Entity>(&mut selector, "user_file");
@@ -88,6 +304,7 @@ where
                     Expr::col((right, user_files::Column::UserId))
                         .eq(user_id)
                         .and(user_files::Column::IsOwner.eq(true))
+                        .and(active(right, now))
                         .into_condition()
                 }),
             false => files::Relation::UserFiles
@@ -95,6 +312,7 @@ where
                 .on_condition(move |_left, right| {
                     Expr::col((right, user_files::Column::UserId))
                         .eq(user_id)
+                        .and(active(right, now))
                         .into_condition()
                 }),
         };
@@ -111,3 +329,86 @@ where
         )
     }
 }
+
+/// The share has already started and it hasn't expired yet
+fn active(table: DynIden, now: i64) -> SimpleExpr {
+    let started = Expr::col((table.clone(), user_files::Column::StartsAt))
+        .is_null()
+        .or(Expr::col((table.clone(), user_files::Column::StartsAt)).lte(now));
+    let not_expired = Expr::col((table.clone(), user_files::Column::ExpiresAt))
+        .is_null()
+        .or(Expr::col((table, user_files::Column::ExpiresAt)).gt(now));
+
+    started.and(not_expired)
+}
+
+/// Order the files that are equal by whatever they were ordered by before, the older
+/// ones first and then by their ids, so the same listing always comes in the same order.
+/// The pages of the listing that is not changing never skip or repeat the files. Only
+/// the listing ordered by the creation time also keeps the files created while the client
+/// is going through the pages last, with any other order the files added or changed in
+/// the meantime can move between the pages.
+pub(crate) fn tie_break(selector: Select<files::Entity>) -> Select<files::Entity> {
+    selector
+        .order_by_asc(files::Column::CreatedAt)
+        .order_by_asc(files::Column::Id)
+}
+
+/// Order the files by the column the listings and the search can be ordered by,
+/// see [crate::data::query::ORDER_BY], ascending unless `desc` is given.
+pub(crate) fn sort(
+    selector: Select<files::Entity>,
+    order_by: &str,
+    order: Option<&str>,
+) -> Select<files::Entity> {
+    let order = match order {
+        Some("desc") => Order::Desc,
+        _ => Order::Asc,
+    };
+
+    let column = match order_by {
+        "created_at" => files::Column::CreatedAt,
+        "size" => files::Column::Size,
+        "name_hash" => files::Column::NameHash,
+        _ => files::Column::FileModifiedAt,
+    };
+
+    selector.order_by(column, order)
+}
+
+/// Only the files of the mime type, the type ending with `/*` matches all of its subtypes
+pub(crate) fn filter_mime(selector: Select<files::Entity>, mime: &str) -> Select<files::Entity> {
+    match mime.strip_suffix('*') {
+        Some(prefix) if prefix.ends_with('/') => {
+            selector.filter(files::Column::Mime.starts_with(prefix))
+        }
+        _ => selector.filter(files::Column::Mime.eq(mime)),
+    }
+}
+
+/// Mime types of the documents, the types starting with any of them
+const DOCUMENT_MIMES: [&str; 8] = [
+    "text/",
+    "application/pdf",
+    "application/rtf",
+    "application/msword",
+    "application/vnd.ms-excel",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.oasis.opendocument.",
+    "application/vnd.openxmlformats-officedocument.",
+];
+
+/// Only the files of the kind, one of the [crate::data::search::KINDS]
+pub(crate) fn filter_kind(selector: Select<files::Entity>, kind: &str) -> Select<files::Entity> {
+    match kind {
+        "dir" => selector.filter(files::Column::Mime.eq("dir")),
+        "document" => selector.filter(
+            DOCUMENT_MIMES
+                .iter()
+                .fold(Condition::any(), |condition, mime| {
+                    condition.add(files::Column::Mime.starts_with(mime))
+                }),
+        ),
+        kind => selector.filter(files::Column::Mime.starts_with(&format!("{}/", kind))),
+    }
+}
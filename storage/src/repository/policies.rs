@@ -0,0 +1,138 @@
+//! Repository module for the sharing policies of the folders
+
+use chrono::Utc;
+use entity::{
+    folder_policies, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    Statement, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::{
+    authorize::{self, Access},
+    data::policy::Policy,
+};
+
+pub(crate) struct Policies<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    owner_id: Uuid,
+}
+
+impl<'repository, T> Policies<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, owner_id: Uuid) -> Self {
+        Self {
+            repository,
+            owner_id,
+        }
+    }
+
+    /// Get the policy set on the folder itself, if any
+    pub(crate) async fn get(&self, folder_id: Uuid) -> AppResult<Option<folder_policies::Model>> {
+        self.folder(folder_id).await?;
+
+        let policy = folder_policies::Entity::find_by_id(folder_id)
+            .one(self.repository.connection())
+            .await?;
+
+        Ok(policy)
+    }
+
+    /// Set the policy of the folder, replacing the previous one
+    pub(crate) async fn set(
+        &self,
+        folder_id: Uuid,
+        policy: Policy,
+    ) -> AppResult<folder_policies::Model> {
+        self.folder(folder_id).await?;
+
+        let now = Utc::now().timestamp();
+        let existing = folder_policies::Entity::find_by_id(folder_id)
+            .one(self.repository.connection())
+            .await?;
+
+        let policy = folder_policies::ActiveModel {
+            file_id: ActiveValue::Set(folder_id),
+            user_id: ActiveValue::Set(self.owner_id),
+            links_disabled: ActiveValue::Set(policy.links_disabled),
+            link_expires_in_days: ActiveValue::Set(policy.link_expires_in_days),
+            links_require_password: ActiveValue::Set(policy.links_require_password),
+            resharing_disabled: ActiveValue::Set(policy.resharing_disabled),
+            created_at: ActiveValue::Set(existing.as_ref().map(|p| p.created_at).unwrap_or(now)),
+            updated_at: ActiveValue::Set(now),
+        };
+
+        match existing {
+            Some(_) => {
+                folder_policies::Entity::update(policy)
+                    .exec(self.repository.connection())
+                    .await?;
+            }
+            None => {
+                folder_policies::Entity::insert(policy)
+                    .exec_without_returning(self.repository.connection())
+                    .await?;
+            }
+        }
+
+        folder_policies::Entity::find_by_id(folder_id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("policy_not_found".to_string()))
+    }
+
+    /// Remove the policy from the folder, the policies of the folders above it still apply
+    pub(crate) async fn delete(&self, folder_id: Uuid) -> AppResult<()> {
+        self.folder(folder_id).await?;
+
+        folder_policies::Entity::delete_many()
+            .filter(folder_policies::Column::FileId.eq(folder_id))
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Make sure the folder exists and the user is its owner
+    async fn folder(&self, folder_id: Uuid) -> AppResult<()> {
+        let folder = self.repository.by_id(folder_id, self.owner_id).await?;
+
+        authorize::directory(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Own,
+            &folder,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Get the policy in effect for the file, combined from the policies
+/// of the file itself (if it is a folder) and all the folders above it.
+pub async fn effective_policy<T: ConnectionTrait>(connection: &T, id: Uuid) -> AppResult<Policy> {
+    let sql = r#"
+        WITH RECURSIVE file_tree(id, file_id) AS (
+            SELECT id, file_id FROM files WHERE id = $1
+            UNION ALL
+            SELECT f.id, f.file_id FROM files f
+            JOIN file_tree a ON a.file_id = f.id
+        )
+        SELECT p.* FROM folder_policies p
+        JOIN file_tree t ON t.id = p.file_id;
+    "#;
+
+    let policies = folder_policies::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            connection.get_database_backend(),
+            sql,
+            [id.into()],
+        ))
+        .all(connection)
+        .await?;
+
+    Ok(Policy::combine(&policies))
+}
@@ -3,11 +3,15 @@
 
 use entity::{
     files, numeric::Numeric, user_files, ColumnTrait, ConnectionTrait, EntityTrait, Expr,
-    IntoCondition, JoinType, QueryFilter, QuerySelect, RelationTrait, Uuid,
+    FromQueryResult, IntoCondition, JoinType, QueryFilter, QuerySelect, RelationTrait, Statement,
+    Uuid,
 };
-use error::AppResult;
+use error::{AppResult, Error};
 
-use crate::data::{app_file::AppFile, stats::Stats};
+use crate::data::{
+    app_file::AppFile,
+    stats::{GroupSpace, Stats},
+};
 
 use super::Repository;
 
@@ -64,6 +68,51 @@ where
             .unwrap_or(0))
     }
 
+    /// Space used together by the members of each group with a quota the user is in
+    pub(crate) async fn group_space(&self) -> AppResult<Vec<GroupSpace>> {
+        let sql = r#"
+            SELECT g.id AS group_id, g.name AS name, g.quota AS quota,
+                COALESCE(SUM(f.size), 0) AS used_space
+            FROM "groups" g
+            JOIN group_members me ON me.group_id = g.id
+            JOIN group_members m ON m.group_id = g.id
+            LEFT JOIN user_files uf ON uf.user_id = m.user_id AND uf.is_owner = $2
+            LEFT JOIN files f ON f.id = uf.file_id
+            WHERE me.user_id = $1 AND g.quota IS NOT NULL
+            GROUP BY g.id, g.name, g.quota;
+        "#;
+
+        let space = GroupSpace::find_by_statement(Statement::from_sql_and_values(
+            self.repository.connection().get_database_backend(),
+            sql,
+            [self.user_id.into(), true.into()],
+        ))
+        .all(self.repository.connection())
+        .await?;
+
+        Ok(space)
+    }
+
+    /// Make sure storing the given number of bytes keeps the user under their own quota
+    /// and each of their groups under the quota pooled by the group members.
+    pub(crate) async fn check_quota(&self, quota: Option<u64>, size: i64) -> AppResult<()> {
+        if let Some(quota) = quota {
+            let used_space = self.used_space().await? + size;
+
+            if used_space > quota as i64 {
+                return Err(Error::BadRequest("quota_exceeded".to_string()));
+            }
+        }
+
+        for group in self.group_space().await? {
+            if group.used_space + size > group.quota {
+                return Err(Error::BadRequest("group_quota_exceeded".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get the stats for the user about the used space and the quota
     pub(crate) async fn stats(&self) -> AppResult<Vec<Stats>> {
         let stats = files::Entity::find()
@@ -0,0 +1,85 @@
+//! Progress of the rebalances that are run by the background worker,
+//! they are started, cancelled and resumed by the admin.
+
+use chrono::Utc;
+use entity::{
+    rebalances, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, Expr, QueryFilter, Uuid,
+};
+use error::AppResult;
+
+/// Mark the rebalance as running, returns `None` if it was cancelled in the meantime.
+/// Rebalance that is already running was interrupted, and it is resumed.
+pub(crate) async fn start<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+) -> AppResult<Option<rebalances::Model>> {
+    let results = rebalances::Entity::update_many()
+        .set(rebalances::ActiveModel {
+            status: ActiveValue::Set(rebalances::Status::Running),
+            started_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(rebalances::Column::Id.eq(id))
+        .filter(
+            rebalances::Column::Status
+                .is_in([rebalances::Status::Pending, rebalances::Status::Running]),
+        )
+        .exec(connection)
+        .await?;
+
+    if results.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    Ok(rebalances::Entity::find_by_id(id).one(connection).await?)
+}
+
+/// Count the moved chunk, returns false when the rebalance
+/// is no longer running because it was cancelled.
+pub(crate) async fn progress<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    bytes: u64,
+) -> AppResult<bool> {
+    let results = rebalances::Entity::update_many()
+        .col_expr(
+            rebalances::Column::MovedChunks,
+            Expr::col(rebalances::Column::MovedChunks).add(1),
+        )
+        .col_expr(
+            rebalances::Column::MovedBytes,
+            Expr::col(rebalances::Column::MovedBytes).add(bytes as i64),
+        )
+        .filter(rebalances::Column::Id.eq(id))
+        .filter(rebalances::Column::Status.eq(rebalances::Status::Running))
+        .exec(connection)
+        .await?;
+
+    Ok(results.rows_affected == 1)
+}
+
+/// Mark the running rebalance as finished, or failed with the error that stopped it.
+pub(crate) async fn finish<T: ConnectionTrait>(
+    connection: &T,
+    id: Uuid,
+    result: AppResult<()>,
+) -> AppResult<()> {
+    let (status, error) = match result {
+        Ok(()) => (rebalances::Status::Finished, None),
+        Err(e) => (rebalances::Status::Failed, Some(e.to_string())),
+    };
+
+    rebalances::Entity::update_many()
+        .set(rebalances::ActiveModel {
+            status: ActiveValue::Set(status),
+            error: ActiveValue::Set(error),
+            finished_at: ActiveValue::Set(Some(Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .filter(rebalances::Column::Id.eq(id))
+        .filter(rebalances::Column::Status.eq(rebalances::Status::Running))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! Restrictions of the files that can be stored, set by the admin in the settings.
+//!
+//! Semi-public instances and the public file requests usually must not accept
+//! executables and the likes, so the mime type of the file can be allowed or denied
+//! and the files of some types can be limited in size. The restrictions are checked
+//! when the file is created, the uploaded chunks must add up to the created size anyway.
+
+use context::Context;
+use error::AppResult;
+
+use crate::data::create_file::CreateFile;
+
+/// Refuse the file the admin doesn't allow to be stored, directories are never restricted
+pub(crate) async fn check(context: &Context, file: &CreateFile) -> AppResult<()> {
+    let mime = match file.mime.as_deref() {
+        Some("dir") | None => return Ok(()),
+        Some(mime) => mime,
+    };
+
+    context
+        .settings
+        .inner()
+        .await
+        .files
+        .check(mime, file.size.unwrap_or(0))
+}
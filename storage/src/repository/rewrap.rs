@@ -0,0 +1,228 @@
+//! Repository module for the re-wrap jobs of the user.
+//!
+//! The job lists the keys in `user_files` the client of the user has to encrypt
+//! again, the client takes the worklist in batches and sends the re-wrapped keys
+//! back, the job is completed once none of its entries are left.
+
+use chrono::Utc;
+use entity::{
+    rewrap_entries, rewrap_jobs, user_files, ActiveValue, ColumnTrait, ConnectionTrait,
+    EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder, Statement, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::data::rewrap::{RewrapEntry, RewrapJob, Rewrapped};
+
+pub(crate) struct Rewrap<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Rewrap<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the jobs of the user that are not completed yet
+    pub(crate) async fn find(&self) -> AppResult<Vec<RewrapJob>> {
+        let jobs = rewrap_jobs::Entity::find()
+            .filter(rewrap_jobs::Column::UserId.eq(self.user_id))
+            .filter(rewrap_jobs::Column::CompletedAt.is_null())
+            .order_by_asc(rewrap_jobs::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?;
+
+        let mut result = Vec::with_capacity(jobs.len());
+
+        for job in jobs {
+            let pending = self.pending(job.id).await?;
+            result.push(RewrapJob { job, pending });
+        }
+
+        Ok(result)
+    }
+
+    /// Get the job of the user
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<RewrapJob> {
+        let job = self.job(id).await?;
+        let pending = self.pending(job.id).await?;
+
+        Ok(RewrapJob { job, pending })
+    }
+
+    /// Next batch of the keys of the job that still have to be re-wrapped
+    pub(crate) async fn entries(&self, id: Uuid, limit: u64) -> AppResult<Vec<RewrapEntry>> {
+        self.job(id).await?;
+
+        let sql = r#"
+            SELECT uf.id AS user_file_id, uf.file_id AS file_id, uf.user_id AS user_id,
+                u.pubkey AS pubkey, uf.encrypted_key AS encrypted_key
+            FROM rewrap_entries e
+            JOIN user_files uf ON uf.id = e.user_file_id
+            JOIN users u ON u.id = uf.user_id
+            WHERE e.job_id = $1 AND e.completed_at IS NULL
+            ORDER BY uf.id
+            LIMIT $2;
+        "#;
+
+        let entries = RewrapEntry::find_by_statement(Statement::from_sql_and_values(
+            self.repository.connection().get_database_backend(),
+            sql,
+            [id.into(), (limit as i64).into()],
+        ))
+        .all(self.repository.connection())
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Ids of the files the re-wrapped keys belong to
+    pub(crate) async fn files(&self, keys: &[Rewrapped]) -> AppResult<Vec<Uuid>> {
+        let files = user_files::Entity::find()
+            .filter(user_files::Column::Id.is_in(keys.iter().map(|key| key.user_file_id)))
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .map(|user_file| user_file.file_id)
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Store the re-wrapped keys and mark their entries as done,
+    /// the job is completed once there are no entries left.
+    pub(crate) async fn complete(&self, id: Uuid, keys: Vec<Rewrapped>) -> AppResult<RewrapJob> {
+        let job = self.job(id).await?;
+
+        if job.completed_at.is_some() {
+            return Err(Error::BadRequest("rewrap_job_completed".to_string()));
+        }
+
+        let now = Utc::now().timestamp();
+
+        for key in keys {
+            let entry = rewrap_entries::Entity::find_by_id((job.id, key.user_file_id))
+                .one(self.repository.connection())
+                .await?;
+
+            match entry {
+                Some(entry) if entry.completed_at.is_none() => {}
+                _ => continue,
+            }
+
+            user_files::Entity::update(user_files::ActiveModel {
+                id: ActiveValue::Set(key.user_file_id),
+                encrypted_key: ActiveValue::Set(key.encrypted_key),
+                ..Default::default()
+            })
+            .exec(self.repository.connection())
+            .await?;
+
+            rewrap_entries::Entity::update(rewrap_entries::ActiveModel {
+                job_id: ActiveValue::Set(job.id),
+                user_file_id: ActiveValue::Set(key.user_file_id),
+                completed_at: ActiveValue::Set(Some(now)),
+            })
+            .exec(self.repository.connection())
+            .await?;
+        }
+
+        let pending = self.pending(job.id).await?;
+        let mut job = job;
+
+        if pending == 0 {
+            rewrap_jobs::Entity::update(rewrap_jobs::ActiveModel {
+                id: ActiveValue::Set(job.id),
+                completed_at: ActiveValue::Set(Some(now)),
+                ..Default::default()
+            })
+            .exec(self.repository.connection())
+            .await?;
+
+            job.completed_at = Some(now);
+        }
+
+        Ok(RewrapJob { job, pending })
+    }
+
+    /// Number of the entries of the job that are not re-wrapped yet
+    async fn pending(&self, id: Uuid) -> AppResult<i64> {
+        let pending = rewrap_entries::Entity::find()
+            .filter(rewrap_entries::Column::JobId.eq(id))
+            .filter(rewrap_entries::Column::CompletedAt.is_null())
+            .count(self.repository.connection())
+            .await?;
+
+        Ok(pending as i64)
+    }
+
+    /// Load the job and make sure it belongs to the user
+    async fn job(&self, id: Uuid) -> AppResult<rewrap_jobs::Model> {
+        rewrap_jobs::Entity::find_by_id(id)
+            .filter(rewrap_jobs::Column::UserId.eq(self.user_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("rewrap_job_not_found".to_string()))
+    }
+}
+
+/// Plan the re-wrapping of the keys of the file after the share was revoked, the
+/// owner has to replace the key of the file for everyone who still has access to it,
+/// so the revoked user cannot use the key they might have kept.
+///
+/// Nothing is planned if the file has no owner anymore.
+pub async fn plan_revocation<T: ConnectionTrait>(
+    connection: &T,
+    file_id: Uuid,
+) -> AppResult<Option<rewrap_jobs::Model>> {
+    let owner = user_files::Entity::find()
+        .filter(user_files::Column::FileId.eq(file_id))
+        .filter(user_files::Column::IsOwner.eq(true))
+        .one(connection)
+        .await?;
+
+    let owner = match owner {
+        Some(owner) => owner,
+        None => return Ok(None),
+    };
+
+    let id = Uuid::new_v4();
+
+    let job = rewrap_jobs::ActiveModel {
+        id: ActiveValue::Set(id),
+        user_id: ActiveValue::Set(owner.user_id),
+        reason: ActiveValue::Set(rewrap_jobs::Reason::ShareRevoked),
+        file_id: ActiveValue::Set(Some(file_id)),
+        previous_encrypted_private_key: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(Utc::now().timestamp()),
+        completed_at: ActiveValue::Set(None),
+    };
+
+    rewrap_jobs::Entity::insert(job)
+        .exec_without_returning(connection)
+        .await?;
+
+    let sql = r#"
+        INSERT INTO rewrap_entries (job_id, user_file_id)
+        SELECT $1, id FROM user_files WHERE file_id = $2;
+    "#;
+
+    connection
+        .execute(Statement::from_sql_and_values(
+            connection.get_database_backend(),
+            sql,
+            [id.into(), file_id.into()],
+        ))
+        .await?;
+
+    let job = rewrap_jobs::Entity::find_by_id(id).one(connection).await?;
+
+    Ok(job)
+}
@@ -0,0 +1,438 @@
+//! Repository module for the shares of the owners files with other users.
+//!
+//! Share that starts later gives the user access to the file only once it starts, so
+//! the owner can prepare the release that becomes available at the set time. Share with
+//! the expiration stops giving the user access to the file once it passes, the background
+//! worker removes it afterwards and reminds the user before it happens.
+//!
+//! Folder is shared together with everything inside of it, every file gets its own key
+//! encrypted for the user. Removing the share plans the re-wrapping of the file key for
+//! everyone who keeps access.
+//!
+//! The share gives the user the permission to only read the file, to also change it, or
+//! to manage it and share it further, unless the sharing policy of the folders it is in
+//! disables the resharing. Sharing with a group shares the file with each of its members
+//! at the time, the members who join later are shared with separately.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use entity::{
+    files, group_members, rewrap_jobs,
+    user_files::{self, Permission},
+    users, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::{policies::effective_policy, rewrap::plan_revocation, Repository};
+use crate::{
+    authorize::{authorize, Access},
+    data::{
+        app_file::AppFile,
+        changes::Action,
+        shares::{MemberKeys, Recipient, Share, SharedKey},
+    },
+    jobs::{ShareExpiration, EXPIRE_SHARE, REMIND_SHARE},
+};
+
+/// How long before the share expires the user gets the reminder
+pub(crate) const SHARE_REMINDER_SECONDS: i64 = 3 * 24 * 60 * 60;
+
+/// How many of the files inside of the shared folder are looked up at once
+const SHARE_BATCH_SIZE: usize = 500;
+
+pub(crate) struct Shares<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    owner_id: Uuid,
+}
+
+impl<'repository, T> Shares<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, owner_id: Uuid) -> Self {
+        Self {
+            repository,
+            owner_id,
+        }
+    }
+
+    /// List the users the file is shared with
+    pub(crate) async fn find(&self, file_id: Uuid) -> AppResult<Vec<Share>> {
+        self.file(file_id).await?;
+
+        let shares = user_files::Entity::find()
+            .filter(user_files::Column::FileId.eq(file_id))
+            .filter(user_files::Column::IsOwner.eq(false))
+            .find_also_related(users::Entity)
+            .order_by_asc(users::Column::Email)
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .filter_map(|(share, user)| user.map(|user| Share::new(share, user)))
+            .collect();
+
+        Ok(shares)
+    }
+
+    /// Share the file with the user, a folder is shared together with everything inside
+    /// of it and the keys of all of its files have to be given, encrypted for the user.
+    /// Besides the owner, the users it is shared with for managing can share it further
+    /// when the sharing policy of the folders it is in allows it.
+    pub(crate) async fn create(
+        &self,
+        file_id: Uuid,
+        user_id: Uuid,
+        encrypted_key: String,
+        keys: Vec<SharedKey>,
+        permission: Permission,
+    ) -> AppResult<Share> {
+        let (file, inside) = self.shareable(file_id).await?;
+
+        if user_id == self.owner_id {
+            return Err(Error::BadRequest("cannot_share_with_yourself".to_string()));
+        }
+
+        let user = users::Entity::find_by_id(user_id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("user_not_found".to_string()))?;
+
+        self.share(&file, &inside, user, encrypted_key, keys, permission)
+            .await?
+            .ok_or_else(|| Error::Conflict("share_exists".to_string()))
+    }
+
+    /// Share the file with every other member of the group the user is in, the keys are
+    /// given for each of them. Members who already have the file are left as they are.
+    pub(crate) async fn create_for_group(
+        &self,
+        file_id: Uuid,
+        group_id: Uuid,
+        members: Vec<MemberKeys>,
+        permission: Permission,
+    ) -> AppResult<Vec<Share>> {
+        let (file, inside) = self.shareable(file_id).await?;
+
+        let mut users = self
+            .members(group_id)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect::<HashMap<_, _>>();
+
+        let mut members = members
+            .into_iter()
+            .map(|member| (member.user_id, member))
+            .collect::<HashMap<_, _>>();
+
+        if members.keys().any(|id| !users.contains_key(id)) {
+            return Err(Error::as_validation("members", "unknown_members"));
+        }
+
+        if users.keys().any(|id| !members.contains_key(id)) {
+            return Err(Error::as_validation("members", "missing_members"));
+        }
+
+        let mut shares = vec![];
+
+        for (id, user) in users.drain() {
+            let member = members.remove(&id).unwrap();
+
+            let share = self
+                .share(
+                    &file,
+                    &inside,
+                    user,
+                    member.encrypted_key,
+                    member.keys,
+                    permission,
+                )
+                .await?;
+
+            shares.extend(share);
+        }
+
+        shares.sort_by(|a, b| a.email.cmp(&b.email));
+
+        Ok(shares)
+    }
+
+    /// Other members of the group the user is in, the file can be shared with them
+    pub(crate) async fn members(&self, group_id: Uuid) -> AppResult<Vec<users::Model>> {
+        group_members::Entity::find_by_id((group_id, self.owner_id))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("group_not_found".to_string()))?;
+
+        let members = group_members::Entity::find()
+            .filter(group_members::Column::GroupId.eq(group_id))
+            .filter(group_members::Column::UserId.ne(self.owner_id))
+            .find_also_related(users::Entity)
+            .order_by_asc(users::Column::Email)
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .filter_map(|(_, user)| user)
+            .collect();
+
+        Ok(members)
+    }
+
+    /// File the user can share, with the ids of everything inside of it the user has
+    async fn shareable(&self, file_id: Uuid) -> AppResult<(AppFile, Vec<Uuid>)> {
+        let file = self.repository.by_id(file_id, self.owner_id).await?;
+
+        authorize(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Manage,
+            &file,
+        )
+        .await?;
+
+        effective_policy(self.repository.connection(), file.id)
+            .await?
+            .check_reshare(file.is_owner)?;
+
+        if !file.is_dir() {
+            return Ok((file, vec![]));
+        }
+
+        let ids = self
+            .repository
+            .manage(self.owner_id)
+            .tree_ids(file.id)
+            .await?
+            .into_iter()
+            .filter(|id| *id != file.id)
+            .collect::<Vec<_>>();
+
+        let mut inside = Vec::with_capacity(ids.len());
+
+        for batch in ids.chunks(SHARE_BATCH_SIZE) {
+            inside.extend(
+                self.repository
+                    .selector(self.owner_id, false)
+                    .filter(files::Column::Id.is_in(batch.to_vec()))
+                    .into_model::<AppFile>()
+                    .all(self.repository.connection())
+                    .await?
+                    .into_iter()
+                    .map(|inner| inner.id),
+            );
+        }
+
+        Ok((file, inside))
+    }
+
+    /// Give the user access to the file and everything inside of it,
+    /// nothing is shared when the user already has the file.
+    async fn share(
+        &self,
+        file: &AppFile,
+        inside: &[Uuid],
+        user: users::Model,
+        encrypted_key: String,
+        keys: Vec<SharedKey>,
+        permission: Permission,
+    ) -> AppResult<Option<Share>> {
+        let mut keys = keys
+            .into_iter()
+            .map(|key| (key.id, key.encrypted_key))
+            .collect::<HashMap<_, _>>();
+
+        let mut shared = vec![(file.id, encrypted_key)];
+
+        for id in inside {
+            let key = keys
+                .remove(id)
+                .ok_or_else(|| Error::as_validation("keys", "missing_keys"))?;
+
+            shared.push((*id, key));
+        }
+
+        if !keys.is_empty() {
+            return Err(Error::as_validation("keys", "unknown_keys"));
+        }
+
+        let existing = user_files::Entity::find()
+            .filter(user_files::Column::UserId.eq(user.id))
+            .filter(user_files::Column::FileId.is_in(shared.iter().map(|(id, _)| *id)))
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .map(|share| share.file_id)
+            .collect::<HashSet<_>>();
+
+        if existing.contains(&file.id) {
+            return Ok(None);
+        }
+
+        let created_at = Utc::now().timestamp();
+        let models = shared
+            .into_iter()
+            .filter(|(id, _)| !existing.contains(id))
+            .map(|(id, encrypted_key)| user_files::Model {
+                id: Uuid::new_v4(),
+                file_id: id,
+                user_id: user.id,
+                is_owner: false,
+                encrypted_key,
+                created_at,
+                starts_at: None,
+                expires_at: None,
+                attributes: None,
+                permission,
+            })
+            .collect::<Vec<_>>();
+
+        user_files::Entity::insert_many(models.iter().cloned().map(user_files::ActiveModel::from))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        self.repository
+            .activities(user.id)
+            .record(Action::Shared, [(file.id, file.file_id)])
+            .await?;
+
+        Ok(Some(Share::new(models[0].clone(), user)))
+    }
+
+    /// Find the user the file can be shared with by their email
+    pub(crate) async fn recipient(&self, email: &str) -> AppResult<Recipient> {
+        users::Entity::find()
+            .filter(users::Column::Email.eq(email))
+            .filter(users::Column::Id.ne(self.owner_id))
+            .one(self.repository.connection())
+            .await?
+            .map(Recipient::from)
+            .ok_or_else(|| Error::NotFound("user_not_found".to_string()))
+    }
+
+    /// Set the dates when the access of the user to the shared file starts and ends, the
+    /// removal of the share and the reminder before it are scheduled right away.
+    pub(crate) async fn set_schedule(
+        &self,
+        file_id: Uuid,
+        user_id: Uuid,
+        starts_at: Option<i64>,
+        expires_at: Option<i64>,
+    ) -> AppResult<Share> {
+        self.file(file_id).await?;
+
+        let (share, user) = user_files::Entity::find()
+            .filter(user_files::Column::FileId.eq(file_id))
+            .filter(user_files::Column::UserId.eq(user_id))
+            .filter(user_files::Column::IsOwner.eq(false))
+            .find_also_related(users::Entity)
+            .one(self.repository.connection())
+            .await?
+            .and_then(|(share, user)| Some((share, user?)))
+            .ok_or_else(|| Error::NotFound("share_not_found".to_string()))?;
+
+        user_files::Entity::update(user_files::ActiveModel {
+            id: ActiveValue::Set(share.id),
+            starts_at: ActiveValue::Set(starts_at),
+            expires_at: ActiveValue::Set(expires_at),
+            ..Default::default()
+        })
+        .exec(self.repository.connection())
+        .await?;
+
+        if let Some(expires_at) = expires_at {
+            let jobs = jobs::repository::Repository::new(self.repository.connection());
+            let payload = ShareExpiration {
+                id: share.id,
+                expires_at,
+            };
+
+            jobs.push_at(EXPIRE_SHARE, &payload, expires_at).await?;
+
+            let remind_at = expires_at - SHARE_REMINDER_SECONDS;
+
+            if remind_at > Utc::now().timestamp() {
+                jobs.push_at(REMIND_SHARE, &payload, remind_at).await?;
+            }
+        }
+
+        Ok(Share::new(
+            user_files::Model {
+                starts_at,
+                expires_at,
+                ..share
+            },
+            user,
+        ))
+    }
+
+    /// Remove the access of the user to the shared file, the owner gets
+    /// the re-wrap job for the keys of the users who still have access.
+    pub(crate) async fn revoke(
+        &self,
+        file_id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<rewrap_jobs::Model> {
+        let file = self.repository.by_id(file_id, self.owner_id).await?;
+
+        authorize(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Own,
+            &file,
+        )
+        .await?;
+
+        let share = user_files::Entity::find()
+            .filter(user_files::Column::FileId.eq(file_id))
+            .filter(user_files::Column::UserId.eq(user_id))
+            .filter(user_files::Column::IsOwner.eq(false))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("share_not_found".to_string()))?;
+
+        user_files::Entity::delete_by_id(share.id)
+            .exec(self.repository.connection())
+            .await?;
+
+        // Everything inside of the shared folder was shared with it
+        if file.is_dir() {
+            let ids = self
+                .repository
+                .manage(self.owner_id)
+                .tree_ids(file.id)
+                .await?;
+
+            user_files::Entity::delete_many()
+                .filter(user_files::Column::UserId.eq(user_id))
+                .filter(user_files::Column::IsOwner.eq(false))
+                .filter(user_files::Column::FileId.is_in(ids))
+                .exec(self.repository.connection())
+                .await?;
+        }
+
+        self.repository
+            .activities(user_id)
+            .record(Action::Unshared, [(file_id, file.file_id)])
+            .await?;
+
+        plan_revocation(self.repository.connection(), file_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("file_not_found:{}", file_id)))
+    }
+
+    /// Make sure the file exists and the user is its owner
+    async fn file(&self, file_id: Uuid) -> AppResult<()> {
+        let file = self.repository.by_id(file_id, self.owner_id).await?;
+
+        authorize(
+            self.repository.connection(),
+            self.owner_id,
+            Access::Own,
+            &file,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
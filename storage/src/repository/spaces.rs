@@ -0,0 +1,201 @@
+//! Repository module for the spaces, root folders that belong to a group.
+//!
+//! Files in a space are changed by the members of the group according to their role,
+//! the files outside of the spaces are only ever changed by their owners.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use entity::{
+    group_members::{self, Role},
+    groups, spaces, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Statement,
+    Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::authorize::{self, Access};
+use crate::data::{app_file::AppFile, spaces::SpaceResponse};
+
+pub(crate) struct Spaces<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Spaces<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// List the spaces of all the groups the user is member of
+    pub(crate) async fn find(&self) -> AppResult<Vec<SpaceResponse>> {
+        let memberships = group_members::Entity::find()
+            .filter(group_members::Column::UserId.eq(self.user_id))
+            .find_also_related(groups::Entity)
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .filter_map(|(member, group)| group.map(|group| (group.id, (group.name, member.role))))
+            .collect::<HashMap<_, _>>();
+
+        let spaces = spaces::Entity::find()
+            .filter(spaces::Column::GroupId.is_in(memberships.keys().copied().collect::<Vec<_>>()))
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(spaces
+            .into_iter()
+            .filter_map(|space| {
+                let (name, role) = memberships.get(&space.group_id)?.clone();
+
+                Some(SpaceResponse::new(space, name, role))
+            })
+            .collect())
+    }
+
+    /// Turn the root folder of the user into the space of the group,
+    /// only the owners of the group can create its spaces.
+    pub(crate) async fn create(&self, group_id: Uuid, file_id: Uuid) -> AppResult<SpaceResponse> {
+        let (group, role) = self.membership(group_id).await?;
+
+        if role != Role::Owner {
+            return Err(Error::Forbidden("space_owner_required".to_string()));
+        }
+
+        let folder = self.repository.by_id(file_id, self.user_id).await?;
+
+        authorize::directory(
+            self.repository.connection(),
+            self.user_id,
+            Access::Own,
+            &folder,
+        )
+        .await?;
+
+        if folder.file_id.is_some() {
+            return Err(Error::NotFound("directory_not_found".to_string()));
+        }
+
+        if self.space(file_id).await?.is_some() {
+            return Err(Error::BadRequest("space_exists".to_string()));
+        }
+
+        let space = spaces::Model {
+            id: Uuid::new_v4(),
+            group_id,
+            file_id,
+            created_at: Utc::now().timestamp(),
+        };
+
+        spaces::Entity::insert(spaces::ActiveModel {
+            id: ActiveValue::Set(space.id),
+            group_id: ActiveValue::Set(space.group_id),
+            file_id: ActiveValue::Set(space.file_id),
+            created_at: ActiveValue::Set(space.created_at),
+        })
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        Ok(SpaceResponse::new(space, group.name, role))
+    }
+
+    /// Turn the space back into a regular folder, its files stay with their owners
+    pub(crate) async fn delete(&self, id: Uuid) -> AppResult<()> {
+        let space = spaces::Entity::find_by_id(id)
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("space_not_found".to_string()))?;
+
+        let (_, role) = self.membership(space.group_id).await?;
+
+        if role != Role::Owner {
+            return Err(Error::Forbidden("space_owner_required".to_string()));
+        }
+
+        spaces::Entity::delete_by_id(id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+
+    /// The user can change the file, as a writing member of the space the file is in,
+    /// or when it is not in a space, as its owner or the user it is shared with for writing.
+    pub(crate) async fn can_write(&self, file: &AppFile) -> AppResult<bool> {
+        match self.role(file.id).await? {
+            Some(role) if role.can_write() => Ok(true),
+            Some(_) => Err(Error::Forbidden("space_read_only".to_string())),
+            None => Ok(file.is_owner || file.permission.can_write()),
+        }
+    }
+
+    /// Make sure none of the files is in a space the user can only read,
+    /// the files outside of the spaces are left to the owner checks.
+    pub(crate) async fn check_write(&self, ids: &[Uuid]) -> AppResult<()> {
+        for id in ids {
+            if let Some(role) = self.role(*id).await? {
+                if !role.can_write() {
+                    return Err(Error::Forbidden("space_read_only".to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Role of the user in the space the file is in, `None` when the file is not
+    /// in a space, files in the spaces of other groups are not found.
+    pub(crate) async fn role(&self, file_id: Uuid) -> AppResult<Option<Role>> {
+        let space = match self.space(file_id).await? {
+            Some(space) => space,
+            None => return Ok(None),
+        };
+
+        let (_, role) = self
+            .membership(space.group_id)
+            .await
+            .map_err(|_| Error::NotFound("file_not_found".to_string()))?;
+
+        Ok(Some(role))
+    }
+
+    /// Find the space the file is in, looking through all the folders above it
+    async fn space(&self, file_id: Uuid) -> AppResult<Option<spaces::Model>> {
+        let sql = r#"
+            WITH RECURSIVE file_tree(id, file_id) AS (
+                SELECT id, file_id FROM files WHERE id = $1
+                UNION ALL
+                SELECT f.id, f.file_id FROM files f
+                JOIN file_tree a ON a.file_id = f.id
+            )
+            SELECT s.* FROM spaces s
+            JOIN file_tree t ON t.id = s.file_id;
+        "#;
+
+        let space = spaces::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                self.repository.connection().get_database_backend(),
+                sql,
+                [file_id.into()],
+            ))
+            .one(self.repository.connection())
+            .await?;
+
+        Ok(space)
+    }
+
+    async fn membership(&self, group_id: Uuid) -> AppResult<(groups::Model, Role)> {
+        group_members::Entity::find_by_id((group_id, self.user_id))
+            .find_also_related(groups::Entity)
+            .one(self.repository.connection())
+            .await?
+            .and_then(|(member, group)| group.map(|group| (group, member.role)))
+            .ok_or_else(|| Error::NotFound("group_not_found".to_string()))
+    }
+}
@@ -1,18 +1,24 @@
 //! Repository module for manipulating the tokens for a file in order to index
 //! it better and enable full text search.
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, sync::Arc};
 
+use context::Context;
 use cryptfns::tokenizer::Token;
 use entity::{
-    file_tokens, files, tokens, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
-    QueryFilter, QueryOrder, QuerySelect, Uuid,
+    file_tokens, files, tokens, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DbBackend,
+    EntityTrait, Expr, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, SimpleExpr,
+    Uuid,
 };
 use error::AppResult;
+use futures::Stream;
 
-use crate::data::{app_file::AppFile, search::Search};
+use crate::data::{
+    app_file::AppFile,
+    search::{Cursor, Search},
+};
 
-use super::Repository;
+use super::{filter_kind, filter_mime, sort, tie_break, Repository};
 
 pub(crate) struct Tokens<'repository, T: ConnectionTrait> {
     repository: &'repository Repository<'repository, T>,
@@ -134,7 +140,6 @@ where
     }
 
     /// Get all tokens for a file
-    #[allow(dead_code)]
     pub(crate) async fn get_tokens(&self, file_id: Uuid) -> AppResult<Vec<Token>> {
         let tokens = file_tokens::Entity::find()
             .inner_join(tokens::Entity)
@@ -161,37 +166,38 @@ where
         Ok(tokens)
     }
 
-    /// Search files based on given tokens and sort by the token weight
+    /// Search files based on given tokens and sort by the token weight, or by the column
+    /// the search asks for, without the tokens the files are only filtered by their attributes,
+    /// their kind, the time they were uploaded at and their size.
+    /// Files with the same weight are always in the same order, see [tie_break].
     pub(crate) async fn search(&self, search: Search) -> AppResult<Vec<AppFile>> {
-        let (file_id, hashed_tokens, limit, skip) = search.into_tuple();
-
-        if hashed_tokens.is_empty() {
-            return Ok(vec![]);
-        }
-
-        let tokens = cryptfns::tokenizer::from_vec(hashed_tokens)?;
+        let search = search.into_value()?;
+        let (limit, skip) = (search.limit, search.skip);
+        let (order_by, order) = (search.order_by.clone(), search.order.clone());
+        let cursor = search.cursor.as_deref().map(Cursor::decode).transpose()?;
+        let ranked = search
+            .search_tokens_hashed
+            .as_ref()
+            .map(|tokens| !tokens.is_empty())
+            .unwrap_or(false);
+
+        let query = match self.filtered(search)? {
+            Some(query) => query,
+            None => return Ok(vec![]),
+        };
 
-        let user_id = self.user_id;
-        let mut query = self
-            .repository
-            .selector(user_id, false)
-            .inner_join(tokens::Entity);
+        let query = match cursor {
+            Some(cursor) => after(query, cursor, order.as_deref()),
+            None => query,
+        };
 
-        if let Some(file_id) = file_id {
-            query = query.filter(files::Column::FileId.eq(file_id));
-        }
+        let query = match order_by.as_deref() {
+            Some(order_by) => sort(query, order_by, order.as_deref()),
+            None if ranked => query.order_by_desc(file_tokens::Column::Weight.sum()),
+            None => query,
+        };
 
-        let mut query = query
-            .filter(
-                tokens::Column::Hash.is_in(
-                    tokens
-                        .iter()
-                        .map(|t| t.token.clone())
-                        .collect::<Vec<String>>(),
-                ),
-            )
-            .group_by(files::Column::Id)
-            .order_by_desc(file_tokens::Column::Weight.sum());
+        let mut query = tie_break(query);
 
         if let Some(limit) = limit {
             query = query.limit(limit);
@@ -208,4 +214,179 @@ where
 
         Ok(results)
     }
+
+    /// How many files the search finds, all of the pages together
+    pub(crate) async fn count(&self, search: Search) -> AppResult<u64> {
+        match self.filtered(search)? {
+            Some(query) => Ok(query.count(self.repository.connection()).await?),
+            None => Ok(0),
+        }
+    }
+
+    /// Files the search matches, nothing when there is nothing to search by
+    fn filtered(&self, search: Search) -> AppResult<Option<Select<files::Entity>>> {
+        let has_filters = search.has_filters();
+        let (mime, kind) = (search.mime.clone(), search.kind.clone());
+        let (uploaded_after, uploaded_before) = (search.uploaded_after, search.uploaded_before);
+        let (min_size, max_size) = (search.min_size, search.max_size);
+        let (file_id, hashed_tokens, attributes, _, _) = search.into_tuple()?;
+
+        if hashed_tokens.is_empty() && attributes.is_empty() && !has_filters {
+            return Ok(None);
+        }
+
+        let user_id = self.user_id;
+        let backend = self.repository.connection().get_database_backend();
+        let mut query = self.repository.selector(user_id, false);
+
+        if let Some(file_id) = file_id {
+            query = query.filter(files::Column::FileId.eq(file_id));
+        }
+
+        if let Some(mime) = mime.as_deref() {
+            query = filter_mime(query, mime);
+        }
+
+        if let Some(kind) = kind.as_deref() {
+            query = filter_kind(query, kind);
+        }
+
+        if let Some(uploaded_after) = uploaded_after {
+            query = query.filter(files::Column::CreatedAt.gte(uploaded_after));
+        }
+
+        if let Some(uploaded_before) = uploaded_before {
+            query = query.filter(files::Column::CreatedAt.lte(uploaded_before));
+        }
+
+        if let Some(min_size) = min_size {
+            query = query.filter(files::Column::Size.gte(min_size));
+        }
+
+        if let Some(max_size) = max_size {
+            query = query.filter(files::Column::Size.lte(max_size));
+        }
+
+        for (key, value) in attributes {
+            query = query.filter(attribute_eq(backend, key, value));
+        }
+
+        if hashed_tokens.is_empty() {
+            return Ok(Some(query));
+        }
+
+        let tokens = cryptfns::tokenizer::from_vec(hashed_tokens)?;
+
+        Ok(Some(
+            query
+                .inner_join(tokens::Entity)
+                .filter(
+                    tokens::Column::Hash.is_in(
+                        tokens
+                            .iter()
+                            .map(|t| t.token.clone())
+                            .collect::<Vec<String>>(),
+                    ),
+                )
+                .group_by(files::Column::Id),
+        ))
+    }
+}
+
+/// Files after the cursor in the search ordered by `created_at`, the ones created at
+/// the same time are always ordered by their ids ascending, see [tie_break]
+fn after(
+    query: Select<files::Entity>,
+    cursor: Cursor,
+    order: Option<&str>,
+) -> Select<files::Entity> {
+    let created_at = match order {
+        Some("desc") => files::Column::CreatedAt.lt(cursor.created_at),
+        _ => files::Column::CreatedAt.gt(cursor.created_at),
+    };
+
+    query.filter(
+        Condition::any().add(created_at).add(
+            Condition::all()
+                .add(files::Column::CreatedAt.eq(cursor.created_at))
+                .add(files::Column::Id.gt(cursor.id)),
+        ),
+    )
+}
+
+/// The attribute of the users file is set to the value, compared as text
+/// since the JSON operators of the databases differ.
+fn attribute_eq(backend: DbBackend, key: String, value: String) -> SimpleExpr {
+    match backend {
+        DbBackend::Postgres => {
+            Expr::cust_with_values(r#""user_files"."attributes" ->> $1 = $2"#, [key, value])
+        }
+        _ => Expr::cust_with_values(
+            r#"CAST(json_extract("user_files"."attributes", $1) AS TEXT) = $2"#,
+            [format!("$.\"{}\"", key), value],
+        ),
+    }
+}
+
+/// How many search results are loaded from the database at once when streaming
+pub(crate) const SEARCH_STREAM_BATCH_SIZE: u64 = 500;
+
+/// Search files the same way as [Tokens::search], but load the results in batches
+/// so they can be sent to the client while the rest of them are still being found.
+///
+/// Limit and skip from the search are respected, every item of the stream
+/// is one batch of at most [SEARCH_STREAM_BATCH_SIZE] files. The search ordered by
+/// `created_at` finds each batch after the cursor of the one before it, instead of
+/// skipping all the results that were already sent.
+pub(crate) fn search_stream(
+    context: Arc<Context>,
+    user_id: Uuid,
+    search: Search,
+) -> impl Stream<Item = AppResult<Vec<AppFile>>> {
+    let remaining = search.limit;
+
+    futures::stream::try_unfold(
+        (context, search, remaining),
+        move |(context, search, remaining)| async move {
+            let limit = match remaining {
+                Some(0) => return Ok(None),
+                Some(remaining) => remaining.min(SEARCH_STREAM_BATCH_SIZE),
+                None => SEARCH_STREAM_BATCH_SIZE,
+            };
+
+            let batch = Search {
+                limit: Some(limit),
+                ..search.clone()
+            };
+
+            let files = Repository::new(&context.db)
+                .tokens(user_id)
+                .search(batch.clone())
+                .await?;
+
+            if files.is_empty() {
+                return Ok(None);
+            }
+
+            let loaded = files.len() as u64;
+            let remaining = match loaded < limit {
+                true => Some(0),
+                false => remaining.map(|r| r - loaded),
+            };
+
+            let next = match search.order_by.as_deref() {
+                Some("created_at") => Search {
+                    skip: None,
+                    cursor: batch.next_cursor(&files),
+                    ..search
+                },
+                _ => Search {
+                    skip: Some(search.skip.unwrap_or(0) + loaded),
+                    ..search
+                },
+            };
+
+            Ok(Some((files, (context, next, remaining))))
+        },
+    )
 }
@@ -0,0 +1,93 @@
+//! Repository module for the daily transfers of the users, see [crate::transfers].
+
+use chrono::Utc;
+use entity::{
+    transfers, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, Expr, OnConflict,
+    QueryFilter, QueryOrder, Uuid,
+};
+use error::AppResult;
+
+use crate::transfers::Direction;
+
+use super::Repository;
+
+/// Seconds in a day, the transfers are counted from the midnight in UTC
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+pub(crate) struct Transfers<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Transfers<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// Add the bytes to the transfers of the user for today
+    pub(crate) async fn record(&self, direction: Direction, bytes: u64) -> AppResult<()> {
+        let bytes = bytes as i64;
+        let (uploaded, downloaded, column) = match direction {
+            Direction::Upload => (bytes, 0, transfers::Column::UploadedBytes),
+            Direction::Download => (0, bytes, transfers::Column::DownloadedBytes),
+        };
+
+        transfers::Entity::insert(transfers::ActiveModel {
+            user_id: ActiveValue::Set(self.user_id),
+            day: ActiveValue::Set(day(Utc::now().timestamp())),
+            uploaded_bytes: ActiveValue::Set(uploaded),
+            downloaded_bytes: ActiveValue::Set(downloaded),
+        })
+        .on_conflict(
+            OnConflict::columns([transfers::Column::UserId, transfers::Column::Day])
+                .value(column, Expr::col((transfers::Entity, column)).add(bytes))
+                .to_owned(),
+        )
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        Ok(())
+    }
+
+    /// Transfers of the user today, zero when nothing was transferred yet
+    pub(crate) async fn today(&self) -> AppResult<transfers::Model> {
+        let today = day(Utc::now().timestamp());
+
+        let transfers = transfers::Entity::find_by_id((self.user_id, today))
+            .one(self.repository.connection())
+            .await?;
+
+        Ok(transfers.unwrap_or(transfers::Model {
+            user_id: self.user_id,
+            day: today,
+            uploaded_bytes: 0,
+            downloaded_bytes: 0,
+        }))
+    }
+
+    /// Transfers of the user in the last days, including today, the newest first.
+    /// Days without any transfers are left out.
+    pub(crate) async fn recent(&self, days: i64) -> AppResult<Vec<transfers::Model>> {
+        let since = day(Utc::now().timestamp()) - (days - 1) * DAY_SECONDS;
+
+        let transfers = transfers::Entity::find()
+            .filter(transfers::Column::UserId.eq(self.user_id))
+            .filter(transfers::Column::Day.gte(since))
+            .order_by_desc(transfers::Column::Day)
+            .all(self.repository.connection())
+            .await?;
+
+        Ok(transfers)
+    }
+}
+
+/// Start of the day the timestamp is in
+pub(crate) fn day(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(DAY_SECONDS)
+}
@@ -0,0 +1,273 @@
+//! Repository module for the trash of the user.
+//!
+//! Deleted files and folders are only marked as deleted together with everything
+//! inside of them, so they can be restored until the trash retention passes. After
+//! that the background worker deletes them for good and purges their chunks.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use entity::{
+    files, ColumnTrait, ConnectionTrait, EntityTrait, Expr, QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::{holds, Repository};
+use crate::{
+    data::{app_file::AppFile, changes::Action},
+    jobs::{queue_purge, TrashedFiles, EMPTY_TRASH},
+};
+
+/// Seconds in a day of the trash retention
+const DAY: i64 = 24 * 60 * 60;
+
+pub(crate) struct Trash<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Trash<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// Move the files and folders with everything inside of them to the trash for the given
+    /// number of days, with no days they are deleted for good right away. Returns the files
+    /// that were moved or deleted.
+    pub(crate) async fn delete(&self, ids: Vec<Uuid>, retention: u64) -> AppResult<Vec<AppFile>> {
+        let manage = self.repository.manage(self.user_id);
+
+        if retention == 0 {
+            let files = manage.delete_many(ids).await?;
+            queue_purge(self.repository.connection(), &files).await?;
+
+            return Ok(files);
+        }
+
+        self.repository
+            .spaces(self.user_id)
+            .check_write(&ids)
+            .await?;
+
+        let mut trashed = vec![];
+        let mut files = vec![];
+
+        for id in ids {
+            let tree = manage.tree(id).await?;
+
+            if tree.iter().any(|file| file.id == id) {
+                trashed.push(id);
+            }
+
+            files.extend(tree);
+        }
+
+        files.sort_by(|a, b| a.id.cmp(&b.id));
+        files.dedup_by(|a, b| a.id == b.id);
+
+        if files.is_empty() {
+            return Ok(files);
+        }
+
+        let deleted_at = Utc::now().timestamp();
+
+        files::Entity::update_many()
+            .col_expr(files::Column::DeletedAt, Expr::value(deleted_at))
+            .filter(files::Column::Id.is_in(files.iter().map(|file| file.id)))
+            .exec(self.repository.connection())
+            .await?;
+
+        self.repository
+            .activities(self.user_id)
+            .record(Action::Deleted, files.iter().map(|f| (f.id, f.file_id)))
+            .await?;
+
+        jobs::repository::Repository::new(self.repository.connection())
+            .push_at(
+                EMPTY_TRASH,
+                &TrashedFiles {
+                    user_id: self.user_id,
+                    ids: trashed,
+                    deleted_at,
+                },
+                deleted_at + retention as i64 * DAY,
+            )
+            .await?;
+
+        Ok(files
+            .into_iter()
+            .map(|file| AppFile {
+                deleted_at: Some(deleted_at),
+                ..file
+            })
+            .collect())
+    }
+
+    /// Files and folders in the trash, the newest first. Files that were deleted
+    /// together with their folder are not listed, they are restored with it.
+    pub(crate) async fn list(&self) -> AppResult<Vec<AppFile>> {
+        let files = self
+            .repository
+            .selector_with_trash(self.user_id, true)
+            .filter(files::Column::DeletedAt.is_not_null())
+            .order_by_desc(files::Column::DeletedAt)
+            .into_model::<AppFile>()
+            .all(self.repository.connection())
+            .await?;
+
+        let deleted = files
+            .iter()
+            .map(|file| (file.id, file.deleted_at))
+            .collect::<HashMap<_, _>>();
+
+        Ok(files
+            .into_iter()
+            .filter(|file| {
+                let parent = file
+                    .file_id
+                    .and_then(|parent| deleted.get(&parent).copied().flatten());
+
+                parent != file.deleted_at
+            })
+            .collect())
+    }
+
+    /// Get the file or the folder in the trash. Files that were deleted together
+    /// with their folder are not found, same as in [Trash::list].
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<AppFile> {
+        let file = self
+            .repository
+            .selector_with_trash(self.user_id, true)
+            .filter(files::Column::Id.eq(id))
+            .filter(files::Column::DeletedAt.is_not_null())
+            .into_model::<AppFile>()
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        if let Some(parent) = file.file_id {
+            let parent = files::Entity::find_by_id(parent)
+                .one(self.repository.connection())
+                .await?;
+
+            if parent.is_some_and(|parent| parent.deleted_at == file.deleted_at) {
+                return Err(Error::NotFound("file_not_found".to_string()));
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Restore the file or the folder with everything that was deleted together with it.
+    /// It goes back to the folder it was in, or to the root if the folder is gone.
+    pub(crate) async fn restore(&self, id: Uuid) -> AppResult<AppFile> {
+        let file = self.get(id).await?;
+        let manage = self.repository.manage(self.user_id);
+
+        let parent = match file.file_id {
+            Some(parent) => self
+                .repository
+                .by_id(parent, self.user_id)
+                .await
+                .ok()
+                .filter(|parent| parent.is_dir())
+                .map(|parent| parent.id),
+            None => None,
+        };
+
+        let mut affected = vec![file.id];
+        affected.extend(parent);
+        self.repository
+            .spaces(self.user_id)
+            .check_write(&affected)
+            .await?;
+
+        if manage.by_name(file.name_hash.clone(), parent).await.is_ok() {
+            return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+        }
+
+        let restored = manage
+            .tree_with_trash(file.id)
+            .await?
+            .into_iter()
+            .filter(|f| f.deleted_at == file.deleted_at)
+            .map(|f| match f.id == file.id {
+                true => (f.id, parent),
+                false => (f.id, f.file_id),
+            })
+            .collect::<Vec<_>>();
+
+        files::Entity::update_many()
+            .col_expr(files::Column::DeletedAt, Expr::value(Option::<i64>::None))
+            .filter(files::Column::Id.is_in(restored.iter().map(|(id, _)| *id)))
+            .exec(self.repository.connection())
+            .await?;
+
+        if parent != file.file_id {
+            files::Entity::update_many()
+                .col_expr(files::Column::FileId, Expr::value(parent))
+                .filter(files::Column::Id.eq(file.id))
+                .exec(self.repository.connection())
+                .await?;
+        }
+
+        self.repository
+            .activities(self.user_id)
+            .record(Action::Created, restored)
+            .await?;
+
+        self.repository.by_id(file.id, self.user_id).await
+    }
+
+    /// Delete the files and folders in the trash for good, their chunks
+    /// are purged by the background worker.
+    pub(crate) async fn purge(&self, ids: Vec<Uuid>) -> AppResult<Vec<AppFile>> {
+        for id in ids.iter() {
+            self.get(*id).await?;
+        }
+
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let files = self
+            .repository
+            .manage(self.user_id)
+            .delete_many(ids)
+            .await?;
+        queue_purge(self.repository.connection(), &files).await?;
+
+        Ok(files)
+    }
+}
+
+/// Delete the files that are in the trash since the given time for good, the ones
+/// restored or deleted again in the meantime are left alone. Files under the legal
+/// hold stay in the trash until they are released and deleted by hand.
+pub(crate) async fn expire<T: ConnectionTrait>(
+    connection: &T,
+    trashed: &TrashedFiles,
+) -> AppResult<Vec<AppFile>> {
+    let repository = Repository::new(connection);
+    let trash = repository.trash(trashed.user_id);
+    let mut ids = vec![];
+
+    for id in trashed.ids.iter() {
+        match trash.get(*id).await {
+            Ok(file) if file.deleted_at == Some(trashed.deleted_at) => {}
+            _ => continue,
+        }
+
+        if holds::held(connection, &[*id]).await?.is_empty() {
+            ids.push(*id);
+        }
+    }
+
+    trash.purge(ids).await
+}
@@ -0,0 +1,110 @@
+//! Repository module for the uploads of the chunks over the tus protocol.
+//!
+//! Unfinished uploads are removed by the background worker once they expire,
+//! together with the bytes that were received for them.
+
+use chrono::Utc;
+use entity::{tus_uploads, ColumnTrait, ConnectionTrait, EntityTrait, Expr, QueryFilter, Uuid};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::{
+    authorize::{self, Access},
+    data::tus::CreateUpload,
+    jobs::{ExpireTusUpload, EXPIRE_TUS_UPLOAD},
+};
+
+pub(crate) struct Tus<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Tus<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// Create the upload for the chunk of the file the user owns, the upload
+    /// is removed by the background worker if it isn't finished before it expires.
+    pub(crate) async fn create(&self, data: CreateUpload) -> AppResult<tus_uploads::Model> {
+        let upload = data.into_model(self.user_id, Utc::now().timestamp())?;
+        let file = self.repository.by_id(upload.file_id, self.user_id).await?;
+
+        authorize::file(
+            self.repository.connection(),
+            self.user_id,
+            Access::Own,
+            &file,
+        )
+        .await?;
+
+        if upload.chunk >= file.chunks.unwrap_or(0) {
+            return Err(Error::as_validation("chunk", "chunk_out_of_range"));
+        }
+
+        tus_uploads::Entity::insert(tus_uploads::ActiveModel::from(upload.clone()))
+            .exec_without_returning(self.repository.connection())
+            .await?;
+
+        jobs::repository::Repository::new(self.repository.connection())
+            .push_at(
+                EXPIRE_TUS_UPLOAD,
+                &ExpireTusUpload {
+                    id: upload.id,
+                    created_at: upload.created_at,
+                },
+                upload.expires_at,
+            )
+            .await?;
+
+        Ok(upload)
+    }
+
+    /// Get the upload of the user that hasn't expired yet
+    pub(crate) async fn get(&self, id: Uuid) -> AppResult<tus_uploads::Model> {
+        tus_uploads::Entity::find_by_id(id)
+            .filter(tus_uploads::Column::UserId.eq(self.user_id))
+            .filter(tus_uploads::Column::ExpiresAt.gt(Utc::now().timestamp()))
+            .one(self.repository.connection())
+            .await?
+            .ok_or_else(|| Error::NotFound("upload_not_found".to_string()))
+    }
+
+    /// Move the offset of the upload forward by the received bytes, only if nobody
+    /// else moved it in the meantime, so the same bytes are never appended twice.
+    pub(crate) async fn advance(
+        &self,
+        upload: &tus_uploads::Model,
+        received: i64,
+    ) -> AppResult<i64> {
+        let offset = upload.offset + received;
+
+        let result = tus_uploads::Entity::update_many()
+            .col_expr(tus_uploads::Column::Offset, Expr::value(offset))
+            .filter(tus_uploads::Column::Id.eq(upload.id))
+            .filter(tus_uploads::Column::Offset.eq(upload.offset))
+            .exec(self.repository.connection())
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(Error::Conflict("upload_offset_mismatch".to_string()));
+        }
+
+        Ok(offset)
+    }
+
+    /// Remove the upload once its chunk is stored or it can't be finished anymore
+    pub(crate) async fn remove(&self, upload: &tus_uploads::Model) -> AppResult<()> {
+        tus_uploads::Entity::delete_by_id(upload.id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+}
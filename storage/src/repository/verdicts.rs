@@ -0,0 +1,57 @@
+//! Verdicts of the scanners and the content policies on the files.
+//!
+//! The storage doesn't scan anything itself, the scanners record their verdicts
+//! through the admin API and the downloads are refused while any of the verdicts
+//! on the file is blocking. Scanners don't know about each other, each of them
+//! keeps its own verdict for the file.
+
+use super::holds::audit;
+use entity::{
+    audit_logs, file_verdicts, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    Uuid,
+};
+use error::{AppResult, Error};
+
+/// Get all the verdicts given to the file, the latest first
+pub async fn verdicts<T: ConnectionTrait>(
+    connection: &T,
+    file_id: Uuid,
+) -> AppResult<Vec<file_verdicts::Model>> {
+    Ok(file_verdicts::Entity::find()
+        .filter(file_verdicts::Column::FileId.eq(file_id))
+        .order_by_desc(file_verdicts::Column::UpdatedAt)
+        .order_by_asc(file_verdicts::Column::Scanner)
+        .all(connection)
+        .await?)
+}
+
+/// Refuse the download of the file that any of the scanners gave the blocking verdict,
+/// the refused attempt is recorded in the audit log.
+pub async fn guard_download<T: ConnectionTrait>(
+    connection: &T,
+    actor_id: Uuid,
+    file_id: Uuid,
+) -> AppResult<()> {
+    let blocking = file_verdicts::Entity::find()
+        .filter(file_verdicts::Column::FileId.eq(file_id))
+        .filter(file_verdicts::Column::Verdict.is_in([
+            file_verdicts::Verdict::Infected,
+            file_verdicts::Verdict::Blocked,
+        ]))
+        .one(connection)
+        .await?;
+
+    if blocking.is_none() {
+        return Ok(());
+    }
+
+    audit(
+        connection,
+        actor_id,
+        &[file_id],
+        audit_logs::Action::DownloadBlocked,
+    )
+    .await?;
+
+    Err(Error::Forbidden("file_blocked_by_verdict".to_string()))
+}
@@ -0,0 +1,380 @@
+//! Repository module for the previous versions of the files.
+//!
+//! When the file is uploaded under the name that is already taken in the folder, the
+//! existing file becomes the version of the new one once its upload is finished. The
+//! existing file stays in the folder until then, so an abandoned upload loses nothing.
+//! The version is left out of the folder and of every listing, it is only reachable
+//! through the file it belongs to.
+//! When the content of the file is replaced in place, the previous content is kept
+//! as the version under the copy of the file.
+//! The number of the versions kept is limited per user, the oldest ones beyond the
+//! limit are deleted and their chunks purged.
+
+use chrono::Utc;
+use entity::{
+    files, user_files, users, versions, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    Expr, QueryFilter, QueryOrder, Uuid,
+};
+use error::{AppResult, Error};
+
+use super::Repository;
+use crate::authorize::{self, authorize, Access};
+use crate::data::{app_file::AppFile, changes::Action};
+
+pub(crate) struct Versions<'repository, T: ConnectionTrait> {
+    repository: &'repository Repository<'repository, T>,
+    user_id: Uuid,
+}
+
+impl<'repository, T> Versions<'repository, T>
+where
+    T: ConnectionTrait,
+{
+    pub(crate) fn new(repository: &'repository Repository<'repository, T>, user_id: Uuid) -> Self {
+        Self {
+            repository,
+            user_id,
+        }
+    }
+
+    /// Number of the versions kept for each file of the user, the server
+    /// default is used unless the user has their own limit.
+    pub(crate) async fn limit(&self, default: u32) -> AppResult<u32> {
+        let user = users::Entity::find_by_id(self.user_id)
+            .one(self.repository.connection())
+            .await?;
+
+        Ok(user
+            .and_then(|user| user.version_limit)
+            .map(|limit| limit.max(0) as u32)
+            .unwrap_or(default))
+    }
+
+    /// Previous versions of the file, the newest first
+    pub(crate) async fn list(&self, file_id: Uuid) -> AppResult<Vec<AppFile>> {
+        let file = self.repository.by_id(file_id, self.user_id).await?;
+
+        authorize::file(
+            self.repository.connection(),
+            self.user_id,
+            Access::Own,
+            &file,
+        )
+        .await?;
+
+        let ids = self.ids(&[file_id]).await?;
+
+        let mut files = self
+            .repository
+            .selector_all(self.user_id, true)
+            .filter(files::Column::Id.is_in(ids.clone()))
+            .into_model::<AppFile>()
+            .all(self.repository.connection())
+            .await?;
+
+        files.sort_by_key(|file| ids.iter().position(|id| *id == file.id));
+
+        Ok(files)
+    }
+
+    /// Get the previous version of the file
+    pub(crate) async fn get(&self, file_id: Uuid, version_id: Uuid) -> AppResult<AppFile> {
+        self.list(file_id)
+            .await?
+            .into_iter()
+            .find(|version| version.id == version_id)
+            .ok_or_else(|| Error::NotFound("version_not_found".to_string()))
+    }
+
+    /// Keep the existing file as the version of the new file that took its name, together
+    /// with the versions the existing file already had. Returns the oldest versions that
+    /// are over the limit, they are deleted and their chunks have to be purged.
+    pub(crate) async fn keep(
+        &self,
+        existing: &AppFile,
+        file_id: Uuid,
+        limit: u32,
+    ) -> AppResult<Vec<AppFile>> {
+        self.swap(existing, file_id).await?;
+
+        self.prune(file_id, existing.id, limit).await
+    }
+
+    /// Keep the finished file with the same name in the same folder as the version of the
+    /// file that was just finished, the new file replaces it only when its upload is done.
+    /// Returns the replaced file and the oldest versions over the limit, same as
+    /// [Versions::keep], or nothing when the file didn't take the name of another one.
+    pub(crate) async fn replace(
+        &self,
+        file: &AppFile,
+        limit: u32,
+    ) -> AppResult<Option<(AppFile, Vec<AppFile>)>> {
+        if limit == 0 || !file.is_file() || file.finished_upload_at.is_none() {
+            return Ok(None);
+        }
+
+        let mut selector = self
+            .repository
+            .selector(self.user_id, true)
+            .filter(files::Column::NameHash.eq(file.name_hash.clone()))
+            .filter(files::Column::Id.ne(file.id))
+            .filter(files::Column::Mime.ne("dir"))
+            .filter(files::Column::FinishedUploadAt.is_not_null())
+            .filter(files::Column::CreatedAt.lte(file.created_at))
+            .filter(user_files::Column::IsOwner.eq(true));
+
+        selector = match file.file_id {
+            Some(parent_id) => selector.filter(files::Column::FileId.eq(parent_id)),
+            None => selector.filter(files::Column::FileId.is_null()),
+        };
+
+        let replaced = selector
+            .order_by_asc(files::Column::CreatedAt)
+            .order_by_asc(files::Column::Id)
+            .into_model::<AppFile>()
+            .one(self.repository.connection())
+            .await?;
+
+        match replaced {
+            Some(replaced) => {
+                let pruned = self.keep(&replaced, file.id, limit).await?;
+
+                Ok(Some((replaced, pruned)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Keep the current content of the file as its version before the content is replaced
+    /// in place. The version is the copy of the file under the given id and creation time,
+    /// the chunk of the file has to be linked to it. Returns the oldest versions that are
+    /// over the limit, same as [Versions::keep].
+    pub(crate) async fn keep_content(
+        &self,
+        file: &AppFile,
+        id: Uuid,
+        created_at: i64,
+        limit: u32,
+    ) -> AppResult<Vec<AppFile>> {
+        let connection = self.repository.connection();
+
+        files::Entity::insert(files::ActiveModel {
+            id: ActiveValue::Set(id),
+            name_hash: ActiveValue::Set(file.name_hash.clone()),
+            encrypted_name: ActiveValue::Set(file.encrypted_name.clone()),
+            encrypted_thumbnail: ActiveValue::Set(file.encrypted_thumbnail.clone()),
+            mime: ActiveValue::Set(file.mime.clone()),
+            size: ActiveValue::Set(file.size),
+            chunks: ActiveValue::Set(file.chunks),
+            chunks_stored: ActiveValue::Set(file.chunks_stored),
+            file_id: ActiveValue::Set(None),
+            file_modified_at: ActiveValue::Set(file.file_modified_at),
+            created_at: ActiveValue::Set(created_at),
+            finished_upload_at: ActiveValue::Set(file.finished_upload_at),
+            sha256: ActiveValue::Set(file.sha256.clone()),
+            conflict_of: ActiveValue::Set(None),
+            conflict_device: ActiveValue::Set(None),
+            legal_hold: ActiveValue::Set(false),
+            crypto_version: ActiveValue::Set(file.crypto_version),
+            encrypted_payload: ActiveValue::Set(file.encrypted_payload.clone()),
+            deleted_at: ActiveValue::Set(None),
+        })
+        .exec_without_returning(connection)
+        .await?;
+
+        user_files::Entity::insert(user_files::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            file_id: ActiveValue::Set(id),
+            user_id: ActiveValue::Set(self.user_id),
+            is_owner: ActiveValue::Set(true),
+            encrypted_key: ActiveValue::Set(file.encrypted_key.clone()),
+            created_at: ActiveValue::Set(created_at),
+            starts_at: ActiveValue::NotSet,
+            expires_at: ActiveValue::NotSet,
+            attributes: ActiveValue::NotSet,
+            permission: ActiveValue::NotSet,
+        })
+        .exec_without_returning(connection)
+        .await?;
+
+        let tokens = self.repository.tokens(self.user_id);
+        let hashed_tokens = tokens
+            .get_tokens(file.id)
+            .await?
+            .iter()
+            .map(|token| token.to_string())
+            .collect::<Vec<_>>();
+        tokens.upsert(id, hashed_tokens).await?;
+
+        super::escrow::copy(connection, file.id, id).await?;
+
+        versions::Entity::insert(versions::ActiveModel {
+            id: ActiveValue::Set(id),
+            file_id: ActiveValue::Set(file.id),
+            user_id: ActiveValue::Set(self.user_id),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+        })
+        .exec_without_returning(connection)
+        .await?;
+
+        self.prune(file.id, id, limit).await
+    }
+
+    /// Delete the oldest versions of the file beyond the limit, the version that
+    /// was just kept is never the one to go, neither are the ones under the legal hold.
+    async fn prune(&self, file_id: Uuid, kept: Uuid, limit: u32) -> AppResult<Vec<AppFile>> {
+        // Versions replaced within the same second can come in any order
+        let mut versions = self.list(file_id).await?;
+        versions.sort_by_key(|version| version.id != kept);
+
+        let over = versions
+            .into_iter()
+            .skip(limit as usize)
+            .collect::<Vec<_>>();
+
+        // Versions under the legal hold are kept even when over the limit, and so are
+        // all the versions of the file that is held itself or is in the held folder.
+        let connection = self.repository.connection();
+        if over.is_empty() || !super::holds::held(connection, &[file_id]).await?.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ids = over.iter().map(|version| version.id).collect::<Vec<_>>();
+        let held = super::holds::held(connection, &ids).await?;
+        let pruned = over
+            .into_iter()
+            .filter(|version| !held.contains(&version.id))
+            .collect::<Vec<_>>();
+
+        if !pruned.is_empty() {
+            files::Entity::delete_many()
+                .filter(files::Column::Id.is_in(pruned.iter().map(|version| version.id)))
+                .exec(self.repository.connection())
+                .await?;
+        }
+
+        Ok(pruned)
+    }
+
+    /// Restore the previous version of the file, the version takes the place of the
+    /// file in the folder and the file becomes one of its versions.
+    pub(crate) async fn restore(&self, file_id: Uuid, version_id: Uuid) -> AppResult<AppFile> {
+        let file = self.repository.by_id(file_id, self.user_id).await?;
+        let version = self.get(file_id, version_id).await?;
+
+        authorize(
+            self.repository.connection(),
+            self.user_id,
+            Access::Write,
+            &file,
+        )
+        .await?;
+
+        // The file could have been renamed since, the version is restored with its own name
+        if let Ok(taken) = self
+            .repository
+            .manage(self.user_id)
+            .by_name(version.name_hash.clone(), file.file_id)
+            .await
+        {
+            if taken.id != file.id {
+                return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+            }
+        }
+
+        versions::Entity::delete_by_id(version.id)
+            .exec(self.repository.connection())
+            .await?;
+
+        files::Entity::update_many()
+            .col_expr(files::Column::FileId, Expr::value(file.file_id))
+            .filter(files::Column::Id.eq(version.id))
+            .exec(self.repository.connection())
+            .await?;
+
+        self.swap(&file, version.id).await?;
+
+        let activities = self.repository.activities(self.user_id);
+        activities
+            .record(Action::Deleted, [(file.id, file.file_id)])
+            .await?;
+        activities
+            .record(Action::Created, [(version.id, file.file_id)])
+            .await?;
+
+        self.repository.by_id(version.id, self.user_id).await
+    }
+
+    /// Delete the previous version of the file, its chunks have to be purged
+    pub(crate) async fn delete(&self, file_id: Uuid, version_id: Uuid) -> AppResult<AppFile> {
+        let version = self.get(file_id, version_id).await?;
+
+        self.repository
+            .spaces(self.user_id)
+            .check_write(&[file_id])
+            .await?;
+
+        files::Entity::delete_by_id(version.id)
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(version)
+    }
+
+    /// All the previous versions of the given files
+    pub(crate) async fn of(&self, ids: &[Uuid]) -> AppResult<Vec<AppFile>> {
+        let ids = self.ids(ids).await?;
+
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .repository
+            .selector_all(self.user_id, true)
+            .filter(files::Column::Id.is_in(ids))
+            .into_model::<AppFile>()
+            .all(self.repository.connection())
+            .await?)
+    }
+
+    /// Ids of the versions of the given files, the newest first
+    async fn ids(&self, ids: &[Uuid]) -> AppResult<Vec<Uuid>> {
+        Ok(versions::Entity::find()
+            .filter(versions::Column::FileId.is_in(ids.to_vec()))
+            .filter(versions::Column::UserId.eq(self.user_id))
+            .order_by_desc(versions::Column::CreatedAt)
+            .all(self.repository.connection())
+            .await?
+            .into_iter()
+            .map(|version| version.id)
+            .collect())
+    }
+
+    /// Turn the file into the version of the other one, together with its own versions.
+    /// The file is taken out of its folder, so it is not in the folder tree anymore.
+    async fn swap(&self, file: &AppFile, file_id: Uuid) -> AppResult<()> {
+        versions::Entity::update_many()
+            .col_expr(versions::Column::FileId, Expr::value(file_id))
+            .filter(versions::Column::FileId.eq(file.id))
+            .exec(self.repository.connection())
+            .await?;
+
+        versions::Entity::insert(versions::ActiveModel {
+            id: ActiveValue::Set(file.id),
+            file_id: ActiveValue::Set(file_id),
+            user_id: ActiveValue::Set(self.user_id),
+            created_at: ActiveValue::Set(Utc::now().timestamp()),
+        })
+        .exec_without_returning(self.repository.connection())
+        .await?;
+
+        files::Entity::update_many()
+            .col_expr(files::Column::FileId, Expr::value(Option::<Uuid>::None))
+            .filter(files::Column::Id.eq(file.id))
+            .exec(self.repository.connection())
+            .await?;
+
+        Ok(())
+    }
+}
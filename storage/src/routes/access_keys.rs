@@ -0,0 +1,67 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{
+    data::access_keys::{CreateAccessKey, CreatedAccessKey},
+    repository::Repository,
+};
+
+/// List the access keys of the user for the S3 gateway, see [crate::s3],
+/// the secrets are not in the list
+///
+/// Response: list of [entity::access_keys::Model]
+#[route("/api/access-keys", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let access_keys = Repository::new(&context.db)
+        .access_keys(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(access_keys))
+}
+
+/// Create the access key, its secret is in the response only this once
+///
+/// Request: [crate::data::access_keys::CreateAccessKey]
+///
+/// Response: [crate::data::access_keys::CreatedAccessKey]
+#[route("/api/access-keys", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateAccessKey>,
+) -> AppResult<HttpResponse> {
+    let name = data.into_inner().into_value()?;
+
+    let (access_key, secret_access_key) = Repository::new(&context.db)
+        .access_keys(claims.sub)
+        .create(name)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(CreatedAccessKey {
+        access_key,
+        secret_access_key,
+    }))
+}
+
+/// Delete the access key, the objects uploaded with it stay in the drive as the regular files
+///
+/// Response: `204 No Content`
+#[route("/api/access-keys/{id}", method = "DELETE")]
+pub(crate) async fn delete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    Repository::new(&context.db)
+        .access_keys(claims.sub)
+        .delete(id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
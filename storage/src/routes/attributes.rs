@@ -0,0 +1,38 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{
+    data::attributes::UpdateAttributes,
+    repository::{cached, Repository},
+};
+
+/// Set or remove the custom attributes of the file, the attributes
+/// are kept for each user separately, also on the shared files.
+///
+/// Request: [crate::data::attributes::UpdateAttributes]
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/{file_id}/attributes", method = "PATCH")]
+pub(crate) async fn update(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<UpdateAttributes>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let connection = context.db.begin().await?;
+
+    let file = Repository::new(&connection)
+        .attributes(claims.sub)
+        .update(file_id, data.into_inner())
+        .await?;
+
+    connection.commit().await?;
+
+    cached::invalidate(claims.sub, &[file.id]).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
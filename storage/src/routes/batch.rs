@@ -0,0 +1,155 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{ConnectionTrait, TransactionTrait, Uuid};
+use error::{AppResult, Error};
+
+use super::transfer::link_copies;
+use crate::{
+    data::{
+        app_file::AppFile,
+        batch::{Batch, BatchResult, Operation},
+    },
+    repository::{cached, holds, Repository},
+};
+
+/// Delete, move and copy many files and folders in a single request
+///
+/// All the operations run in a single transaction, each of them on its own, so the one
+/// that fails is rolled back without the others. The response has the result for each
+/// id in the order they were sent, the failed ones with the error the single request
+/// would have failed with.
+///
+/// Request: [crate::data::batch::Batch]
+///
+/// Response: list of [crate::data::batch::BatchResult]
+#[route("/api/storage/batch", method = "POST")]
+pub(crate) async fn batch(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Batch>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let operations = data.into_inner().into_value()?;
+
+    let results = run(&context, &claims, operations).await?;
+
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Run the operations one after another, each in a savepoint of the same transaction.
+///
+/// The legal holds are checked before the transaction starts, the refused attempts are
+/// recorded in the audit log and the refused operations are left out of the transaction.
+pub(crate) async fn run(
+    context: &Context,
+    claims: &Claims,
+    operations: Vec<(Operation, Uuid)>,
+) -> AppResult<Vec<BatchResult>> {
+    let mut refused = Vec::with_capacity(operations.len());
+
+    for (operation, id) in operations.iter() {
+        refused.push(guard(context, claims, *operation, *id).await.err());
+    }
+
+    let connection = context.db.begin().await?;
+    let mut results = Vec::with_capacity(operations.len());
+    let mut changed = vec![];
+
+    for ((operation, id), refused) in operations.into_iter().zip(refused) {
+        if let Some(e) = refused {
+            results.push(BatchResult::failed(operation, id, &e));
+            continue;
+        }
+
+        let savepoint = connection.begin().await?;
+
+        match execute(context, claims, &savepoint, operation, id).await {
+            Ok((file, ids)) => {
+                savepoint.commit().await?;
+                changed.extend(ids);
+                results.push(BatchResult::done(operation, id, file));
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                results.push(BatchResult::failed(operation, id, &e));
+            }
+        }
+    }
+
+    connection.commit().await?;
+
+    cached::invalidate(claims.sub, &changed).await;
+
+    Ok(results)
+}
+
+/// Refuse the operation on the file under the legal hold, the audit log is written outside
+/// of the transaction of the batch so the refused attempt stays recorded
+async fn guard(
+    context: &Context,
+    claims: &Claims,
+    operation: Operation,
+    id: Uuid,
+) -> AppResult<()> {
+    match operation {
+        Operation::Delete => holds::guard_delete(&context.db, claims.sub, &[id]).await,
+        Operation::Move(file_id) => {
+            holds::guard_move(&context.db, claims.sub, &[id], file_id).await
+        }
+        Operation::Copy(_) => Ok(()),
+    }
+}
+
+/// Run the single operation, returns the resulting file with the ids of the files it changed
+async fn execute<T: ConnectionTrait>(
+    context: &Context,
+    claims: &Claims,
+    connection: &T,
+    operation: Operation,
+    id: Uuid,
+) -> AppResult<(Option<AppFile>, Vec<Uuid>)> {
+    let repository = Repository::new(connection);
+    let manage = repository.manage(claims.sub);
+
+    match operation {
+        Operation::Delete => {
+            repository.by_id(id, claims.sub).await?;
+
+            let files = repository
+                .trash(claims.sub)
+                .delete(vec![id], context.config.server.trash_retention)
+                .await?;
+
+            Ok((None, files.iter().map(|file| file.id).collect()))
+        }
+        Operation::Move(file_id) => {
+            let previous = repository.by_id(id, claims.sub).await?.file_id;
+
+            let file = manage.move_file(id, file_id).await?;
+
+            let changed = [Some(file.id), previous, file_id].into_iter().flatten();
+
+            Ok((Some(file), changed.collect()))
+        }
+        Operation::Copy(file_id) => {
+            let copies = manage.copy(id, file_id).await?;
+
+            // Copies are already counted in the used space
+            repository
+                .query(claims.sub)
+                .check_quota(claims.get_quota(context).await, 0)
+                .await?;
+
+            link_copies(context, &copies).await?;
+
+            let (_, root) = copies
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+            let changed = [Some(root.id), root.file_id].into_iter().flatten();
+
+            Ok((Some(root), changed.collect()))
+        }
+    }
+}
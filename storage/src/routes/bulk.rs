@@ -0,0 +1,246 @@
+use actix_multipart::{Field, Multipart};
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::TransactionTrait;
+use error::{AppResult, Error};
+use fs::{
+    pool::{self, PooledBuffer},
+    prelude::*,
+};
+use futures::TryStreamExt;
+
+use crate::{
+    data::{
+        app_file::AppFile,
+        bulk::{BulkUpload, MAX_BULK_METADATA_BYTES},
+        create_file::CreateFileData,
+        limits,
+    },
+    jobs::PurgeFile,
+    repository::{cached, escrow, restrictions, Repository},
+};
+
+use super::upload::validate_chunk_size;
+
+/// Create and upload many small files in a single request, meant for the photo backups
+/// where the overhead of creating a file and uploading its only chunk adds up quickly.
+///
+/// The content of all the files is stored first, then all the files are created and
+/// finished in a single short transaction. If any of them fails, none of them is created
+/// and the stored content is purged.
+///
+/// Request:
+///  - Content-Type: multipart/form-data
+///  - Parts: `files` with [crate::data::bulk::BulkUpload] JSON, followed by
+///    the encrypted content of each file in a part named by its index in the list
+///
+/// Response: list of [crate::data::app_file::AppFile]
+#[route("/api/storage/bulk", method = "POST")]
+pub(crate) async fn bulk(
+    claims: Claims,
+    context: web::Data<Context>,
+    mut payload: Multipart,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+
+    let metadata = match payload.try_next().await? {
+        Some(field) if field.name() == "files" => {
+            read_field(field, MAX_BULK_METADATA_BYTES).await?
+        }
+        _ => return Err(Error::as_validation("files", "required")),
+    };
+    let upload = serde_json::from_slice::<BulkUpload>(&metadata)?;
+    let escrow_keys = upload
+        .files
+        .iter()
+        .flatten()
+        .map(|file| file.escrow_key.clone())
+        .collect::<Vec<_>>();
+    for file in upload.files.iter().flatten() {
+        limits::check_file(&context.config.server, file)?;
+        restrictions::check(&context, file).await?;
+    }
+
+    let files = upload.into_active_models()?;
+    let filenames = files
+        .iter()
+        .map(|(create_file, _, _, _, file_id)| {
+            claims.check_folder(*file_id)?;
+
+            let id = entity::active_value_to_uuid(create_file.id.clone())
+                .ok_or(Error::as_wrong_id("file"))?;
+            let created_at = create_file
+                .created_at
+                .clone()
+                .into_value()
+                .unwrap()
+                .unwrap::<i64>();
+
+            Ok(PurgeFile { id, created_at })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    // Refuse what we already know would fail before reading all the content
+    let files_size = files.iter().map(|(_, _, _, size, _)| size).sum::<i64>();
+    let quota = claims.get_quota(&context).await;
+    Repository::new(&context.db)
+        .query(claims.sub)
+        .check_quota(quota, files_size)
+        .await?;
+
+    let storage = Fs::new(&context.config);
+
+    let finished = match store_contents(&storage, &filenames, &mut payload).await {
+        Ok(()) => create_files(&context, &claims, files, escrow_keys, files_size).await,
+        Err(e) => Err(e),
+    };
+
+    let finished = match finished {
+        Ok(finished) => finished,
+        Err(e) => {
+            for filename in filenames.iter() {
+                if let Err(e) = storage.purge(filename).await {
+                    log::error!("Failed purging bulk uploaded file {}: {}", filename.id, e);
+                }
+            }
+
+            return Err(e);
+        }
+    };
+
+    let ids = finished
+        .iter()
+        .flat_map(|file| [Some(file.id), file.file_id])
+        .flatten()
+        .collect::<Vec<_>>();
+    cached::invalidate(claims.sub, &ids).await;
+
+    Ok(HttpResponse::Ok().json(finished))
+}
+
+/// Create the files whose content is already stored and finish them right away,
+/// all of them in one transaction.
+async fn create_files(
+    context: &Context,
+    claims: &Claims,
+    files: Vec<CreateFileData>,
+    escrow_keys: Vec<Option<String>>,
+    files_size: i64,
+) -> AppResult<Vec<AppFile>> {
+    let recovery_key = escrow::recovery_key(context).await?;
+    let quota = claims.get_quota(context).await;
+
+    let connection = context.db.begin().await?;
+    let repository = Repository::new(&connection);
+
+    repository
+        .query(claims.sub)
+        .check_quota(quota, files_size)
+        .await?;
+
+    let manage = repository.manage(claims.sub);
+    let mut finished = Vec::with_capacity(files.len());
+
+    for ((create_file, encrypted_key, hashed_tokens, _, file_id), escrow_key) in
+        files.into_iter().zip(escrow_keys)
+    {
+        let name_hash = create_file
+            .name_hash
+            .clone()
+            .into_value()
+            .unwrap()
+            .unwrap::<String>();
+
+        if manage.by_name(&name_hash, file_id).await.is_ok() {
+            return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+        }
+
+        let file = manage
+            .create(create_file, &encrypted_key, hashed_tokens)
+            .await?;
+
+        escrow::escrow(
+            &connection,
+            recovery_key.as_ref(),
+            file.id,
+            escrow_key.as_deref(),
+        )
+        .await?;
+
+        finished.push(manage.finish(&file).await?);
+    }
+
+    connection.commit().await?;
+
+    Ok(finished)
+}
+
+/// Store the content part of each of the files as its only chunk,
+/// every file has to receive its content exactly once.
+async fn store_contents(
+    storage: &Fs<'_>,
+    files: &[PurgeFile],
+    payload: &mut Multipart,
+) -> AppResult<()> {
+    let mut stored = vec![false; files.len()];
+
+    while let Some(mut field) = payload.try_next().await? {
+        let index = field
+            .name()
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index < files.len())
+            .ok_or_else(|| Error::as_validation("files", "unknown_file_part"))?;
+
+        if stored[index] {
+            return Err(Error::as_validation("files", "duplicate_file_part"));
+        }
+
+        let buffer = read_content(&mut field).await?;
+
+        storage.push(&files[index], 0, &buffer).await?;
+        stored[index] = true;
+    }
+
+    if let Some(index) = stored.iter().position(|stored| !stored) {
+        let error = format!("missing_file_data:{}", index);
+
+        return Err(Error::as_validation("files", &error));
+    }
+
+    Ok(())
+}
+
+/// Read the encrypted content of the file from the field into a pooled buffer,
+/// the content has to fit into a single chunk.
+pub(crate) async fn read_content(field: &mut Field) -> AppResult<PooledBuffer<'static>> {
+    let mut buffer = pool::chunks().get();
+
+    while let Some(bytes) = field.try_next().await? {
+        validate_chunk_size(buffer.len() + bytes.len())?;
+        buffer.extend_from_slice(&bytes);
+    }
+
+    if buffer.is_empty() {
+        return Err(Error::BadRequest("no_file_data_received".to_string()));
+    }
+
+    Ok(buffer)
+}
+
+/// Read the whole field into memory, failing once it grows over the given size
+pub(crate) async fn read_field(mut field: Field, max_size: usize) -> AppResult<Vec<u8>> {
+    let name = field.name().to_string();
+    let mut data = vec![];
+
+    while let Some(bytes) = field.try_next().await? {
+        if data.len() + bytes.len() > max_size {
+            return Err(Error::as_validation(&name, "metadata_too_large"));
+        }
+
+        data.extend_from_slice(&bytes);
+    }
+
+    Ok(data)
+}
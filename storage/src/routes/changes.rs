@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{
+    authorize::{self, Access},
+    data::changes::Changes,
+    repository::Repository,
+};
+
+/// List changes of the files in a directory after the given cursor, so the sync clients
+/// don't have to load the whole listing again. Use `root` as the `dir_id` for the root directory.
+///
+/// Query: [crate::data::changes::Changes]
+///
+/// Response: [crate::data::changes::ChangesResponse]
+#[route("/api/storage/{dir_id}/changes", method = "GET")]
+pub(crate) async fn changes(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Query<Changes>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let dir_id: String = util::actix::path_var(&req, "dir_id")?;
+    let (since, limit) = data.into_inner().into_value();
+
+    let repository = Repository::new(&context.db);
+
+    let dir_id = match dir_id.as_str() {
+        "root" => None,
+        dir_id => {
+            let dir = repository
+                .by_id(Uuid::from_str(dir_id)?, claims.sub)
+                .await?;
+
+            authorize::directory(&context.db, &claims, Access::Own, &dir).await?;
+
+            Some(dir.id)
+        }
+    };
+
+    let response = repository
+        .activities(claims.sub)
+        .changes(dir_id, since, limit)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
@@ -0,0 +1,204 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use chrono::Utc;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::{
+        app_file::AppFile,
+        content::{self, Content, WriteContent},
+    },
+    jobs::{queue_purge, ContentSwap, PurgeFile, SWAP_CONTENT},
+    repository::{
+        cached::{self, get_file},
+        holds,
+        verdicts::guard_download,
+        Repository,
+    },
+    routes::upload::read_chunk,
+};
+
+/// Get the whole encrypted content of the small file in a single request
+///
+/// Response: [actix_web::web::Bytes]
+///  - Content-Type: application/octet-stream
+///  - ETag: version of the file, send it back when writing the content
+#[route("/api/storage/{file_id}/content", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let file = get_file(&context, claims.sub, file_id)
+        .await
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    content::check(&file, content::max_size(&context.config))?;
+    guard_download(&context.db, claims.sub, file.id).await?;
+
+    let data = Fs::new(&context.config).pull(&file, 0).await?;
+
+    let version = Repository::new(&context.db)
+        .activities(claims.sub)
+        .versions(&[file.id])
+        .await?
+        .get(&file.id)
+        .copied()
+        .unwrap_or_default();
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .insert_header(("ETag", format!("\"{}\"", version)))
+        .insert_header(("Cache-Control", "no-store"))
+        .body(data))
+}
+
+/// Replace the whole content of the small file in a single request,
+/// the file gets a new version with the new content.
+///
+/// The new content is stored under a staging name and swapped in only after the change
+/// is committed, the previous content is kept as the previous version of the file. The
+/// swap replaces the chunk at once, when it fails it is left to the background worker.
+/// Content of the file under the legal hold cannot be replaced.
+///
+/// Query: [crate::data::content::WriteContent]
+///
+/// Request:
+///  - Content-Type: application/octet-stream
+///  - Body: new content encrypted with the file key
+///
+/// Response: [crate::data::content::Content], `409 Conflict` when the version
+/// the client has edited is not the latest one anymore
+#[route("/api/storage/{file_id}/content", method = "PUT")]
+pub(crate) async fn put(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Query<WriteContent>,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    holds::guard_delete(&context.db, claims.sub, &[file_id]).await?;
+
+    let buffer = read_chunk(&context.config.server, payload).await?;
+    let file = get_file(&context, claims.sub, file_id)
+        .await
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    let created_at = Utc::now().timestamp();
+    let staged = PurgeFile {
+        id: Uuid::new_v4(),
+        created_at,
+    };
+    let kept = PurgeFile {
+        id: Uuid::new_v4(),
+        created_at,
+    };
+
+    let storage = Fs::new(&context.config);
+
+    // Chunks can be hard linked with the other copies of the same content, so the
+    // previous content is linked to the version and the chunk is never written over.
+    let replaced = match storage.push(&staged, 0, &buffer).await {
+        Ok(()) => match storage.link(&file, &kept).await {
+            Ok(_) => replace(&context, &claims, file_id, data.into_inner(), &kept).await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+
+    let (file, version) = match replaced {
+        Ok(replaced) => replaced,
+        Err(e) => {
+            for filename in [&staged, &kept] {
+                if let Err(e) = storage.purge(filename).await {
+                    log::error!("Failed purging replaced content {}: {}", filename.id, e);
+                }
+            }
+
+            return Err(e);
+        }
+    };
+
+    // Change is committed, the chunk of the file is replaced at once and the previous
+    // content stays linked to the version, nothing is purged before it is replaced.
+    match storage.push(&file, 0, &buffer).await {
+        Ok(()) => {
+            if let Err(e) = storage.purge(&staged).await {
+                log::error!("Failed purging replaced content {}: {}", staged.id, e);
+            }
+        }
+        Err(e) => {
+            log::error!(
+                "Failed swapping in the new content of the file {}, queued to retry: {}",
+                file.id,
+                e
+            );
+
+            let swap = ContentSwap {
+                file: PurgeFile {
+                    id: file.id,
+                    created_at: file.created_at,
+                },
+                staged,
+            };
+
+            if let Err(e) = jobs::repository::Repository::new(&context.db)
+                .push(SWAP_CONTENT, &swap)
+                .await
+            {
+                log::error!("Failed queuing the content swap of {}: {}", file.id, e);
+            }
+        }
+    }
+
+    cached::invalidate(claims.sub, &[file.id, kept.id]).await;
+
+    Ok(HttpResponse::Ok().json(Content { file, version }))
+}
+
+/// Replace the content of the file in the database and keep the previous content as
+/// the version, returns the file with its new version once the change is committed.
+async fn replace(
+    context: &Context,
+    claims: &Claims,
+    file_id: Uuid,
+    data: WriteContent,
+    kept: &PurgeFile,
+) -> AppResult<(AppFile, i64)> {
+    let quota = claims.get_quota(context).await;
+
+    let connection = context.db.begin().await?;
+    let repository = Repository::new(&connection);
+
+    let (previous, file) = repository
+        .manage(claims.sub)
+        .replace_content(file_id, data, content::max_size(&context.config), quota)
+        .await?;
+
+    let versions = repository.versions(claims.sub);
+    let limit = versions.limit(context.config.server.versions_max).await?;
+    let pruned = versions
+        .keep_content(&previous, kept.id, kept.created_at, limit)
+        .await?;
+    queue_purge(&connection, &pruned).await?;
+
+    let version = repository
+        .activities(claims.sub)
+        .versions(&[file.id])
+        .await?
+        .get(&file.id)
+        .copied()
+        .unwrap_or_default();
+
+    connection.commit().await?;
+
+    Ok((file, version))
+}
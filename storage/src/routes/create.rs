@@ -1,13 +1,29 @@
 use actix_web::{route, web, HttpResponse};
 use auth::data::claims::Claims;
 use context::Context;
-use entity::TransactionTrait;
-use error::{AppResult, Error};
+use entity::{files, ActiveValue, TransactionTrait, Uuid};
+use error::AppResult;
+use fs::prelude::*;
 
-use crate::{data::create_file::CreateFile, repository::Repository};
+use crate::{
+    data::{app_file::AppFile, create_file::CreateFile, limits},
+    jobs::queue_purge,
+    repository::{cached, escrow, restrictions, Repository},
+};
 
 /// Create a file or get the file context to resume the upload
 ///
+/// The client can send its own id for the file, sending the same request again
+/// will return the already created file instead of failing or creating a duplicate.
+///
+/// When a file with the same name and different content already exists, the file is
+/// created as a conflicted copy under the conflict name sent by the client. Without
+/// the conflict name the existing file is kept as the previous version of the new one
+/// once the upload of the new one is finished, see [crate::routes::upload::store_chunk].
+///
+/// When the key escrow is enabled, the file key encrypted with the organization
+/// recovery key has to be sent along.
+///
 /// Request: [crate::data::create_file::CreateFile]
 ///
 /// Response: [crate::data::app_file::AppFile]
@@ -18,20 +34,34 @@ pub(crate) async fn create(
     data: web::Json<CreateFile>,
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
-    let connection = context.db.begin().await?;
-    let (create_file, encrypted_metadata, hashed_tokens, file_size, file_id) =
-        data.into_inner().into_active_model()?;
+    let file = create_file(&context, &claims, data.into_inner()).await?;
 
-    let repository = Repository::new(&connection);
+    Ok(HttpResponse::Ok().json(file))
+}
 
-    if let Some(quota) = claims.get_quota(&context).await {
-        let used_space = repository.query(claims.sub).used_space().await? + file_size;
+/// Create the file, or get the file that was already created for the same
+/// request with its uploaded chunks, so the upload can continue from there.
+pub(crate) async fn create_file(
+    context: &Context,
+    claims: &Claims,
+    mut data: CreateFile,
+) -> AppResult<AppFile> {
+    // Restrictions are checked against the mime the file is going to be stored with
+    data.sniff_mime();
 
-        if used_space > quota as i64 {
-            return Err(Error::BadRequest("quota_exceeded".to_string()));
-        }
-    }
+    limits::check_file(&context.config.server, &data)?;
+    restrictions::check(context, &data).await?;
+
+    let connection = context.db.begin().await?;
+    let client_id = data.id;
+    let conflict = data.conflict();
+    let escrow_key = data.escrow_key.clone();
+    let (mut create_file, encrypted_metadata, hashed_tokens, file_size, file_id) =
+        data.into_active_model()?;
+
+    claims.check_folder(file_id)?;
 
+    let repository = Repository::new(&connection);
     let manage = repository.manage(claims.sub);
 
     let name_hash = create_file
@@ -41,15 +71,125 @@ pub(crate) async fn create(
         .unwrap()
         .unwrap::<String>();
 
-    if manage.by_name(&name_hash, file_id).await.is_ok() {
-        return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+    let mut name_hashes = vec![name_hash.as_str()];
+    name_hashes.extend(conflict.as_ref().map(|c| c.name_hash.as_str()));
+
+    // Repeated request for the file the client has already created with its own id,
+    // respond with the file and its uploaded chunks so the client can continue from there.
+    if let Some(id) = client_id {
+        if let Some(file) = manage.reserved(id, &name_hashes, file_id).await? {
+            return with_uploaded_chunks(context, file).await;
+        }
     }
 
-    let file = manage
-        .create(create_file, &encrypted_metadata, hashed_tokens)
+    repository
+        .query(claims.sub)
+        .check_quota(claims.get_quota(context).await, file_size)
+        .await?;
+
+    let version_limit = repository
+        .versions(claims.sub)
+        .limit(context.config.server.versions_max)
         .await?;
 
+    // Name is already taken, either the same content was uploaded by another client,
+    // the new file becomes a conflicted copy of the existing one, or without the conflict
+    // name the new file is created next to the existing one and takes its place when
+    // its upload is finished, so the existing content is there until then.
+    if let Ok(existing) = manage.by_name(&name_hash, file_id).await {
+        let finished = existing.is_file() && existing.finished_upload_at.is_some();
+        let replaces = conflict.is_none() && version_limit > 0 && finished;
+
+        if replaces {
+            if existing.sha256.is_some() && existing.sha256 == sha256(&create_file) {
+                return with_uploaded_chunks(context, existing).await;
+            }
+        } else if let Some(file) = manage
+            .conflict(&mut create_file, existing, conflict)
+            .await?
+        {
+            return with_uploaded_chunks(context, file).await;
+        }
+    }
+
+    let file = match manage
+        .create(create_file, &encrypted_metadata, hashed_tokens)
+        .await
+    {
+        Ok(file) => file,
+        // Same request could have been repeated at the same time and the other one got there first
+        Err(e) => match client_id {
+            Some(id) => {
+                connection.rollback().await?;
+
+                let file = Repository::new(&context.db)
+                    .manage(claims.sub)
+                    .reserved_meanwhile(id, &name_hashes, file_id, e)
+                    .await?;
+
+                return with_uploaded_chunks(context, file).await;
+            }
+            None => return Err(e),
+        },
+    };
+
+    let recovery_key = escrow::recovery_key(context).await?;
+    escrow::escrow(
+        &connection,
+        recovery_key.as_ref(),
+        file.id,
+        escrow_key.as_deref(),
+    )
+    .await?;
+
     connection.commit().await?;
 
-    Ok(HttpResponse::Ok().json(file))
+    let ids = [Some(file.id), file.file_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &ids.collect::<Vec<_>>()).await;
+
+    Ok(file)
+}
+
+/// Keep the file the finished one took the name of as its previous version, the new
+/// file is created next to the existing one and only replaces it once it is finished.
+pub(crate) async fn replace_previous(
+    context: &Context,
+    owner_id: Uuid,
+    file: &AppFile,
+) -> AppResult<()> {
+    let connection = context.db.begin().await?;
+    let repository = Repository::new(&connection);
+    let versions = repository.versions(owner_id);
+
+    let limit = versions.limit(context.config.server.versions_max).await?;
+    let (replaced, pruned) = match versions.replace(file, limit).await? {
+        Some(replaced) => replaced,
+        None => return Ok(()),
+    };
+
+    queue_purge(&connection, &pruned).await?;
+    connection.commit().await?;
+
+    cached::invalidate(owner_id, &[file.id, replaced.id]).await;
+
+    Ok(())
+}
+
+/// Checksum of the content the client sent for the new file
+fn sha256(create_file: &files::ActiveModel) -> Option<String> {
+    match &create_file.sha256 {
+        ActiveValue::Set(sha256) => sha256.clone(),
+        _ => None,
+    }
+}
+
+/// Attach the chunks that are already uploaded, so the client can continue the upload
+async fn with_uploaded_chunks(context: &Context, mut file: AppFile) -> AppResult<AppFile> {
+    if file.is_file() {
+        let uploaded_chunks = Fs::new(&context.config).get_uploaded_chunks(&file).await?;
+        file.chunks_stored = Some(uploaded_chunks.len() as i64);
+        file.uploaded_chunks = Some(uploaded_chunks);
+    }
+
+    Ok(file)
 }
@@ -0,0 +1,410 @@
+//! # WebDAV routes
+//!
+//! Drive of the user mounted with the WebDAV clients, see [crate::dav] for how the paths
+//! are resolved. Clients sign in the same way as for the other routes, with the session
+//! token in the `Authorization: Bearer` header (rclone: `--bearer-token`), usually the one
+//! of the app password session. Basic authentication is not supported.
+use actix_web::{
+    http::{header::Range, StatusCode},
+    route, web, HttpRequest, HttpResponse,
+};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{app_passwords::Scope, users, EntityTrait, TransactionTrait, Uuid};
+use error::{AppResult, Error};
+use fs::{prelude::*, watchdog::Watchdog};
+use futures::StreamExt;
+
+use crate::{
+    data::app_file::AppFile,
+    dav::{self, Entry, Multistatus, Resource},
+    import::Sealer,
+    repository::{cached, holds, Repository},
+    routes::download,
+};
+
+/// Methods the WebDAV routes answer to
+const ALLOW: &str = "OPTIONS, PROPFIND, GET, HEAD, PUT, MKCOL, MOVE, DELETE";
+
+/// Tell the client this is a WebDAV server, class 1 (no locking)
+///
+/// Response:
+///  - DAV: 1
+///  - Allow: methods of the WebDAV routes
+#[route("/api/dav/{tail:.*}", method = "OPTIONS")]
+pub(crate) async fn options() -> AppResult<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .insert_header(("DAV", "1"))
+        .insert_header(("Allow", ALLOW))
+        .insert_header(("MS-Author-Via", "DAV"))
+        .finish())
+}
+
+/// List the properties of the file or the folder and, unless `Depth: 0` is asked
+/// for, of everything directly in the folder. `Depth: infinity` is answered as `1`.
+///
+/// The body of the request is not read, all the properties are always sent:
+/// `displayname` is the id, the names are encrypted, `getcontentlength` is the
+/// size of the encrypted content, `getlastmodified` and `creationdate`.
+///
+/// Response: `207 Multi-Status` XML, children are linked by their ids
+#[route("/api/dav/{tail:.*}", method = "PROPFIND")]
+pub(crate) async fn propfind(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let segments = dav::segments(req.path())?;
+    let fs = Fs::new(&context.config);
+    let href = req.path().trim_end_matches('/');
+    let mut multistatus = Multistatus::default();
+
+    let parent_id = match dav::resolve(&context, claims.sub, &segments).await? {
+        Resource::Root => {
+            multistatus.push(Entry::root(&format!("{}/", href)));
+            None
+        }
+        Resource::File(file) if file.is_dir() => {
+            multistatus.push(Entry::file(&format!("{}/", href), &file, None));
+            Some(file.id)
+        }
+        Resource::File(file) => {
+            let size = fs.size(&file, None).await.ok();
+            multistatus.push(Entry::file(href, &file, size));
+
+            return Ok(multi_status(&multistatus));
+        }
+        Resource::Missing { .. } => return Err(Error::NotFound("file_not_found".to_string())),
+    };
+
+    let depth = req
+        .headers()
+        .get("Depth")
+        .and_then(|depth| depth.to_str().ok())
+        .unwrap_or("infinity");
+
+    if depth != "0" {
+        for child in dav::children(&context, claims.sub, parent_id).await? {
+            let entry = match child.is_dir() {
+                true => Entry::file(&format!("{}/{}/", href, child.id), &child, None),
+                false => {
+                    let size = fs.size(&child, None).await.ok();
+                    Entry::file(&format!("{}/{}", href, child.id), &child, size)
+                }
+            };
+
+            multistatus.push(entry);
+        }
+    }
+
+    Ok(multi_status(&multistatus))
+}
+
+/// Download the file, the content is encrypted the way it is stored, only the
+/// clients with the key of the file can read it. Single byte ranges are supported.
+///
+/// Response: [actix_web::web::Bytes], folders answer with `405 Method Not Allowed`
+#[route("/api/dav/{tail:.*}", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    range: Option<web::Header<Range>>,
+) -> AppResult<HttpResponse> {
+    let file = match file(&context, claims.sub, &req).await? {
+        Some(file) => file,
+        None => return Ok(not_allowed()),
+    };
+
+    let range = range.and_then(|range| download::single_range(range.into_inner()));
+
+    download::serve(&context, claims.sub, &file, None, range).await
+}
+
+/// Headers of the download without the content, `Content-Length` is
+/// the size of the encrypted content
+#[route("/api/dav/{tail:.*}", method = "HEAD")]
+pub(crate) async fn head(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file = match file(&context, claims.sub, &req).await? {
+        Some(file) => file,
+        None => return Ok(not_allowed()),
+    };
+
+    let size = Fs::new(&context.config).size(&file, None).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .insert_header(("Accept-Ranges", "bytes"))
+        .no_chunking(size)
+        .streaming(Streamer::empty().stream()))
+}
+
+/// Upload the file, the last segment of the path is its plain name. The content is
+/// sent as it is and encrypted here with the new key of the file, so the server sees
+/// it while it is uploaded. `Content-Length` is required, the file is split into
+/// the chunks by it.
+///
+/// Existing file is replaced only when it is named by its plain name, its new key can't
+/// be made without it. The new file is uploaded first and the old one is moved to the
+/// trash after, with its id, its versions and its shares.
+///
+/// Request:
+///  - Content-Length: size of the file
+///  - Content-Type: mime of the file, `application/octet-stream` if omitted
+///  - Body: content of the file
+///
+/// Response: `201 Created`, or `204 No Content` when the file was replaced
+#[route("/api/dav/{tail:.*}", method = "PUT")]
+pub(crate) async fn put(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    let size = match req
+        .headers()
+        .get("Content-Length")
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<i64>().ok())
+    {
+        Some(size) => size,
+        None => return Ok(HttpResponse::LengthRequired().finish()),
+    };
+
+    let segments = dav::segments(req.path())?;
+
+    let (parent_id, name, replaced) = match dav::resolve(&context, claims.sub, &segments).await? {
+        Resource::Missing { parent_id, name } => (parent_id, name, None),
+        Resource::File(file) if !file.is_dir() => {
+            let name = segments.last().cloned().unwrap_or_default();
+
+            if dav::name_hash(&name) != file.name_hash {
+                return Err(Error::BadRequest("dav_plain_name_required".to_string()));
+            }
+
+            if matches!(&claims.app, Some(app) if !app.scopes.contains(&Scope::Write)) {
+                return Err(Error::Forbidden("app_password_scope".to_string()));
+            }
+
+            (file.file_id, name, Some(file))
+        }
+        _ => return Ok(not_allowed()),
+    };
+
+    claims.check_folder(parent_id)?;
+
+    if let Some(replaced) = replaced.as_ref() {
+        holds::guard_delete(&context.db, claims.sub, &[replaced.id]).await?;
+    }
+
+    let mime = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|mime| mime.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let sealer = Sealer::new(&context, user(&context, claims.sub).await?).await?;
+    let sealed = sealer.seal(&name)?;
+
+    let payload = payload.map(|bytes| bytes.map_err(|e| Error::BadRequest(e.to_string())));
+    let body = Box::pin(Watchdog::new(payload, &context.config.server));
+
+    let file = sealer
+        .file(&sealed, parent_id, mime, size, None, body)
+        .await?;
+
+    let mut changed = vec![file.id];
+    changed.extend(parent_id);
+
+    let mut response = match replaced {
+        Some(replaced) => {
+            changed.extend(trash(&context, claims.sub, replaced.id).await?);
+            HttpResponse::NoContent()
+        }
+        None => HttpResponse::Created(),
+    };
+
+    cached::invalidate(claims.sub, &changed).await;
+
+    Ok(response.finish())
+}
+
+/// Create the folder, the last segment of the path is its plain name
+///
+/// Response: `201 Created`, `405 Method Not Allowed` when it already exists
+#[route("/api/dav/{tail:.*}", method = "MKCOL")]
+pub(crate) async fn mkcol(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let segments = dav::segments(req.path())?;
+
+    let (parent_id, name) = match dav::resolve(&context, claims.sub, &segments).await? {
+        Resource::Missing { parent_id, name } => (parent_id, name),
+        _ => return Ok(not_allowed()),
+    };
+
+    claims.check_folder(parent_id)?;
+
+    let sealer = Sealer::new(&context, user(&context, claims.sub).await?).await?;
+    let folder = sealer.folder(&sealer.seal(&name)?, parent_id).await?;
+
+    let changed = [Some(folder.id), parent_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &changed.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Created().finish())
+}
+
+/// Move the file or the folder into another folder. Renaming is not supported,
+/// the new name would have to be encrypted with the key of the file, so the last
+/// segment of the `Destination` has to be the id or the current name of the file.
+///
+/// Request:
+///  - Destination: URL or the path where the file goes
+///  - Overwrite: `F` to fail when there already is something there
+///
+/// Response: `201 Created`, or `204 No Content` when the file in the destination
+/// was moved to the trash
+#[route("/api/dav/{tail:.*}", method = "MOVE")]
+pub(crate) async fn move_file(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file = match dav::resolve(&context, claims.sub, &dav::segments(req.path())?).await? {
+        Resource::File(file) => file,
+        Resource::Root => return Ok(not_allowed()),
+        Resource::Missing { .. } => return Err(Error::NotFound("file_not_found".to_string())),
+    };
+
+    let destination = req
+        .headers()
+        .get("Destination")
+        .and_then(|destination| destination.to_str().ok())
+        .ok_or_else(|| Error::BadRequest("dav_destination_required".to_string()))?;
+    let destination = dav::segments(destination)?;
+
+    let name = destination
+        .last()
+        .ok_or_else(|| Error::BadRequest("dav_destination_required".to_string()))?;
+
+    if name != &file.id.to_string() && dav::name_hash(name) != file.name_hash {
+        return Err(Error::BadRequest("dav_rename_not_supported".to_string()));
+    }
+
+    let parent_id =
+        match dav::resolve(&context, claims.sub, &destination[..destination.len() - 1]).await {
+            Ok(Resource::Root) => None,
+            Ok(Resource::File(parent)) if parent.is_dir() => Some(parent.id),
+            _ => return Err(Error::Conflict("parent_directory_not_found".to_string())),
+        };
+
+    let overwrite = req
+        .headers()
+        .get("Overwrite")
+        .and_then(|overwrite| overwrite.to_str().ok())
+        != Some("F");
+
+    let existing = match dav::resolve(&context, claims.sub, &destination).await? {
+        Resource::File(existing) if existing.id != file.id => Some(existing),
+        _ => None,
+    };
+
+    holds::guard_move(&context.db, claims.sub, &[file.id], parent_id).await?;
+
+    let mut changed = vec![file.id];
+
+    if let Some(existing) = existing.as_ref() {
+        if !overwrite {
+            return Ok(HttpResponse::PreconditionFailed().finish());
+        }
+
+        holds::guard_delete(&context.db, claims.sub, &[existing.id]).await?;
+        changed.extend(trash(&context, claims.sub, existing.id).await?);
+    }
+
+    let connection = context.db.begin().await?;
+    let moved = Repository::new(&connection)
+        .manage(claims.sub)
+        .move_file(file.id, parent_id)
+        .await?;
+    connection.commit().await?;
+
+    changed.extend([file.file_id, moved.file_id].into_iter().flatten());
+    cached::invalidate(claims.sub, &changed).await;
+
+    Ok(match existing {
+        Some(_) => HttpResponse::NoContent().finish(),
+        None => HttpResponse::Created().finish(),
+    })
+}
+
+/// Move the file or the folder with everything inside of it to the trash,
+/// see [crate::routes::delete::delete]
+///
+/// Response: `204 No Content`
+#[route("/api/dav/{tail:.*}", method = "DELETE")]
+pub(crate) async fn delete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file = match dav::resolve(&context, claims.sub, &dav::segments(req.path())?).await? {
+        Resource::File(file) => file,
+        Resource::Root => return Ok(not_allowed()),
+        Resource::Missing { .. } => return Err(Error::NotFound("file_not_found".to_string())),
+    };
+
+    holds::guard_delete(&context.db, claims.sub, &[file.id]).await?;
+
+    let changed = trash(&context, claims.sub, file.id).await?;
+    cached::invalidate(claims.sub, &changed).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// File the path points to, nothing for the folders and the root
+async fn file(context: &Context, user_id: Uuid, req: &HttpRequest) -> AppResult<Option<AppFile>> {
+    match dav::resolve(context, user_id, &dav::segments(req.path())?).await? {
+        Resource::File(file) if !file.is_dir() => Ok(Some(file)),
+        Resource::Missing { .. } => Err(Error::NotFound("file_not_found".to_string())),
+        _ => Ok(None),
+    }
+}
+
+/// Move the file to the trash, returns the ids of everything that was moved there
+async fn trash(context: &Context, user_id: Uuid, id: Uuid) -> AppResult<Vec<Uuid>> {
+    let connection = context.db.begin().await?;
+    let files = Repository::new(&connection)
+        .trash(user_id)
+        .delete(vec![id], context.config.server.trash_retention)
+        .await?;
+    connection.commit().await?;
+
+    Ok(files.iter().map(|file| file.id).collect())
+}
+
+async fn user(context: &Context, user_id: Uuid) -> AppResult<users::Model> {
+    users::Entity::find_by_id(user_id)
+        .one(&context.db)
+        .await?
+        .ok_or_else(|| Error::NotFound("user_not_found".to_string()))
+}
+
+fn multi_status(multistatus: &Multistatus) -> HttpResponse {
+    HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type("application/xml; charset=utf-8")
+        .body(multistatus.render())
+}
+
+fn not_allowed() -> HttpResponse {
+    HttpResponse::MethodNotAllowed()
+        .insert_header(("Allow", ALLOW))
+        .finish()
+}
@@ -0,0 +1,124 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::TransactionTrait;
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::{
+        dedup::{CreateDeduplicated, Lookup},
+        limits,
+    },
+    repository::{cached, dedup, escrow, restrictions, Repository},
+};
+
+/// Check if the content the client is about to upload is already stored,
+/// responds with `404` if it isn't and the client has to upload it.
+///
+/// Request: [crate::data::dedup::Lookup]
+///
+/// Response: [crate::data::dedup::DedupMatch]
+#[route("/api/storage/dedup", method = "POST")]
+pub(crate) async fn lookup(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Lookup>,
+) -> AppResult<HttpResponse> {
+    let (sha256, size) = data.into_inner().into_values()?;
+    let global = context.config.server.global_dedup;
+
+    let found = dedup::lookup(&context.db, claims.sub, &sha256, size, global)
+        .await?
+        .ok_or_else(|| Error::NotFound("content_not_found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(found))
+}
+
+/// Create the file from the content that is already stored, the chunks of the stored
+/// file are linked to the new file and it is finished right away without any upload.
+///
+/// The file has to be encrypted with the same key as the stored file and its
+/// content hash and size have to match the stored ones.
+///
+/// Request: [crate::data::dedup::CreateDeduplicated]
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/dedup/files", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateDeduplicated>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let CreateDeduplicated { source_id, file } = data.into_inner();
+    let escrow_key = file.escrow_key.clone();
+    let global = context.config.server.global_dedup;
+
+    limits::check_file(&context.config.server, &file)?;
+    restrictions::check(&context, &file).await?;
+
+    let connection = context.db.begin().await?;
+    let source = dedup::source(&connection, claims.sub, source_id, global).await?;
+
+    if file.sha256.is_none() || file.sha256 != source.sha256 || file.size != source.size {
+        return Err(Error::BadRequest("content_mismatch".to_string()));
+    }
+
+    let (mut create_file, encrypted_metadata, hashed_tokens, file_size, file_id) =
+        file.into_active_model()?;
+    create_file.chunks = entity::ActiveValue::Set(source.chunks);
+    create_file.crypto_version = entity::ActiveValue::Set(source.crypto_version);
+
+    claims.check_folder(file_id)?;
+
+    let repository = Repository::new(&connection);
+    let manage = repository.manage(claims.sub);
+
+    repository
+        .query(claims.sub)
+        .check_quota(claims.get_quota(&context).await, file_size)
+        .await?;
+
+    let name_hash = create_file
+        .name_hash
+        .clone()
+        .into_value()
+        .unwrap()
+        .unwrap::<String>();
+
+    if manage.by_name(&name_hash, file_id).await.is_ok() {
+        return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+    }
+
+    let file = manage
+        .create(create_file, &encrypted_metadata, hashed_tokens)
+        .await?;
+
+    let recovery_key = escrow::recovery_key(&context).await?;
+    escrow::escrow(
+        &connection,
+        recovery_key.as_ref(),
+        file.id,
+        escrow_key.as_deref(),
+    )
+    .await?;
+
+    let fs = Fs::new(&context.config);
+    let linked = fs.link(&source, &file).await?;
+
+    if Some(linked.len() as i64) != source.chunks {
+        fs.purge(&file).await?;
+
+        return Err(Error::StorageError("source_incomplete".to_string()));
+    }
+
+    let file = manage.finish(&file).await?;
+
+    connection.commit().await?;
+
+    let ids = [Some(file.id), file.file_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &ids.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
@@ -3,12 +3,16 @@ use auth::data::claims::Claims;
 use context::Context;
 use entity::{TransactionTrait, Uuid};
 use error::AppResult;
-use fs::prelude::*;
 
-use crate::repository::Repository;
+use crate::repository::{cached, holds, Repository};
 
 /// Delete a file or directory by its id
 /// Also, deletes recursively all files and directories inside the directory
+///
+/// Files are moved to the trash, where they can be restored from until the trash
+/// retention passes, see [crate::routes::trash]. Without the retention they are removed
+/// from the database right away and their chunks are purged from the storage provider
+/// by the background job, hence the `202 Accepted` response.
 #[route("/api/storage/{file_id}", method = "DELETE")]
 pub(crate) async fn delete(
     req: HttpRequest,
@@ -18,19 +22,17 @@ pub(crate) async fn delete(
     let context = context.into_inner();
     let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
 
-    let fs = Fs::new(&context.config);
+    holds::guard_delete(&context.db, claims.sub, &[file_id]).await?;
+
     let connection = context.db.begin().await?;
-    let mut files = Repository::new(&connection)
-        .manage(claims.sub)
-        .delete_many(vec![file_id])
+    let files = Repository::new(&connection)
+        .trash(claims.sub)
+        .delete(vec![file_id], context.config.server.trash_retention)
         .await?;
     connection.commit().await?;
 
-    for file in files.iter_mut() {
-        if file.is_file() {
-            fs.purge(file).await?;
-        }
-    }
+    let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+    cached::invalidate(claims.sub, &ids).await;
 
-    Ok(HttpResponse::NoContent().finish())
+    Ok(HttpResponse::Accepted().finish())
 }
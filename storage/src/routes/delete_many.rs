@@ -3,13 +3,18 @@ use auth::data::claims::Claims;
 use context::Context;
 use entity::TransactionTrait;
 use error::AppResult;
-use fs::prelude::*;
 
-use crate::{data::delete_many::DeleteMany, repository::Repository};
+use crate::{
+    data::delete_many::DeleteMany,
+    repository::{cached, holds, Repository},
+};
 
 /// Delete many files and folders with their children recursively
 /// all at once.
 ///
+/// Same as with the single delete, the files are moved to the trash
+/// or their chunks are purged in the background.
+///
 /// Request: [crate::data::delete_many::DeleteMany]
 #[route("/api/storage/delete-many", method = "POST")]
 pub(crate) async fn delete_many(
@@ -20,19 +25,17 @@ pub(crate) async fn delete_many(
     let context = context.into_inner();
     let ids = data.into_inner().into_value()?;
 
-    let fs = Fs::new(&context.config);
+    holds::guard_delete(&context.db, claims.sub, &ids).await?;
+
     let connection = context.db.begin().await?;
-    let mut files = Repository::new(&connection)
-        .manage(claims.sub)
-        .delete_many(ids)
+    let files = Repository::new(&connection)
+        .trash(claims.sub)
+        .delete(ids, context.config.server.trash_retention)
         .await?;
     connection.commit().await?;
 
-    for file in files.iter_mut() {
-        if file.is_file() {
-            fs.purge(file).await?;
-        }
-    }
+    let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+    cached::invalidate(claims.sub, &ids).await;
 
-    Ok(HttpResponse::NoContent().finish())
+    Ok(HttpResponse::Accepted().finish())
 }
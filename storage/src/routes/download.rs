@@ -1,13 +1,22 @@
 use std::str::FromStr;
 
-use actix_web::{route, web, HttpRequest, HttpResponse};
+use actix_web::{
+    http::header::{ByteRangeSpec, Range},
+    route, web, HttpRequest, HttpResponse,
+};
 use auth::data::claims::Claims;
 use context::Context;
 use entity::Uuid;
 use error::{AppResult, Error};
-use fs::prelude::*;
+use fs::{prelude::*, watchdog::Watchdog};
+use futures::StreamExt;
 
-use crate::repository::cached::get_file;
+use crate::{
+    archive,
+    data::app_file::AppFile,
+    jobs::{RepairChunk, REPAIR_CHUNKS},
+    repository::{cached::get_file, verdicts::guard_download},
+};
 
 /// Get file content by its id
 ///
@@ -16,11 +25,28 @@ use crate::repository::cached::get_file;
 ///
 /// Response: [actix_web::web::Bytes]
 ///  - Content-Type: application/octet-stream
+///
+/// The `Link` header points to the checksums of the file, see [crate::routes::manifest::file].
+///
+/// Requests with a single byte `Range` header, including the suffix ranges (`bytes=-500`),
+/// get only the requested bytes with `206 Partial Content`, the range is mapped onto
+/// the chunk files it spans. This lets the browsers seek in the video and the audio,
+/// and the clients resume the interrupted downloads.
+///
+/// If any of the requested chunks is damaged and the replica is configured, the
+/// file is served from the replica and the chunks are repaired in the background.
+///
+/// File that any of the scanners gave the blocking verdict is refused with
+/// `403 Forbidden`, see [crate::repository::verdicts].
+///
+/// Chunks of the archived file have to be restored first, the download answers
+/// with `202 Accepted` and `{"status": "restoring"}` until they are back.
 #[route("/api/storage/{file_id}", method = "GET")]
 pub(crate) async fn download(
     req: HttpRequest,
     claims: Claims,
     context: web::Data<Context>,
+    range: Option<web::Header<Range>>,
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
     let file_id: String = util::actix::path_var(&req, "file_id")?;
@@ -31,36 +57,163 @@ pub(crate) async fn download(
         .await
         .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
 
-    let storage = Fs::new(&context.config);
+    let range = range.and_then(|range| single_range(range.into_inner()));
+
+    serve(&context, claims.sub, &file, chunk, range).await
+}
+
+/// Serve the encrypted content of the file, or only its chunk or the range of it.
+/// Files with the blocking verdict are refused, archived files answer with the
+/// restoring status and the damaged chunks are served from the replica while
+/// they are being repaired.
+pub(crate) async fn serve(
+    context: &Context,
+    user_id: Uuid,
+    file: &AppFile,
+    chunk: Option<i64>,
+    range: Option<ByteRangeSpec>,
+) -> AppResult<HttpResponse> {
+    guard_download(&context.db, user_id, file.id).await?;
 
-    let streamer = storage.stream(&file, chunk).await?;
+    if let Some(restoring) = archive::restoring(context, file.id, user_id).await? {
+        return Ok(restoring);
+    }
+
+    let storage = Fs::new(&context.config);
 
     let filename = match chunk {
         Some(chunk) => file.filename()?.with_chunk(chunk).with_extension(".enc"),
         None => file.filename()?.with_extension(".enc"),
     };
 
+    if repair(context, &storage, file, chunk).await? {
+        if let Some(replica) = storage.replica() {
+            return respond(context, &replica, file, chunk, range, filename).await;
+        }
+    }
+
+    respond(context, &storage, file, chunk, range, filename).await
+}
+
+/// Stream the file, or the requested range of it, from the given provider,
+/// clients that read it too slowly are dropped by the [Watchdog].
+async fn respond<P: FsProviderContract>(
+    context: &Context,
+    provider: &P,
+    file: &AppFile,
+    chunk: Option<i64>,
+    range: Option<ByteRangeSpec>,
+    filename: Filename,
+) -> AppResult<HttpResponse> {
+    if let Some(range) = range {
+        let size = provider.size(file, chunk).await?;
+
+        let (start, end) = match range.to_satisfiable_range(size) {
+            Some(range) => range,
+            None => {
+                return Ok(HttpResponse::RangeNotSatisfiable()
+                    .insert_header(("Accept-Ranges", "bytes"))
+                    .insert_header(("Content-Range", format!("bytes */{}", size)))
+                    .finish())
+            }
+        };
+
+        let streamer = provider.stream_range(file, chunk, start, Some(end)).await?;
+
+        return Ok(HttpResponse::PartialContent()
+            .insert_header(("Content-Type", "application/octet-stream"))
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", filename),
+            ))
+            .insert_header(("Accept-Ranges", "bytes"))
+            .insert_header(("Content-Range", format!("bytes {}-{}/{}", start, end, size)))
+            .no_chunking(end - start + 1)
+            .streaming(Watchdog::new(streamer.stream(), &context.config.server)));
+    }
+
+    let read_ahead = context.config.server.read_ahead;
+
+    // Whole file is assembled from the chunks read ahead concurrently, so the
+    // next chunks are already in memory while the current one is being sent.
+    let streamer = match chunk {
+        None if read_ahead > 0 => {
+            let stream = provider
+                .stream_files(std::slice::from_ref(file), read_ahead)
+                .await?
+                .map(|item| item.map(|(_, data)| data));
+
+            Streamer::new(stream)
+        }
+        _ => provider.stream(file, chunk).await?,
+    };
+
     Ok(HttpResponse::Ok()
         .insert_header(("Content-Type", "application/octet-stream"))
         .insert_header((
             "Content-Disposition",
             format!("attachment; filename=\"{}\"", filename),
         ))
-        .streaming(streamer.stream()))
+        .insert_header(("Accept-Ranges", "bytes"))
+        .insert_header(("Link", manifest_link(file)))
+        .streaming(Watchdog::new(streamer.stream(), &context.config.server)))
+}
+
+/// Check the requested chunks against the replica and queue the repair of
+/// the damaged ones, returns true if the file should be served from the replica.
+async fn repair(
+    context: &Context,
+    storage: &Fs<'_>,
+    file: &AppFile,
+    chunk: Option<i64>,
+) -> AppResult<bool> {
+    let chunks = match chunk {
+        Some(chunk) => vec![chunk],
+        None => (0..file.chunks.unwrap_or(0)).collect(),
+    };
+
+    let damaged = storage.damaged(file, &chunks).await?;
+
+    if damaged.is_empty() {
+        return Ok(false);
+    }
+
+    log::warn!(
+        "Serving the file {} from the replica, damaged chunks: {:?}",
+        file.id,
+        damaged
+    );
+
+    jobs::repository::Repository::new(&context.db)
+        .push(
+            REPAIR_CHUNKS,
+            &RepairChunk {
+                id: file.id,
+                created_at: file.created_at,
+                chunks: damaged,
+            },
+        )
+        .await?;
+
+    Ok(true)
 }
 
 /// Get head response for a file this will give all the header
 /// information, but no file content.
+///
+/// `Content-Length` is the size of the stored content, so the clients
+/// (browsers seeking in the video or the audio) can plan their range requests.
+/// Empty stream keeps the length, actix would replace it with 0 for the empty body.
 #[route("/api/storage/{file_id}", method = "HEAD")]
 pub(crate) async fn head(
     req: HttpRequest,
     context: web::Data<Context>,
-    claims: web::Data<Claims>,
+    claims: Claims,
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
     let file_id: String = util::actix::path_var(&req, "file_id")?;
     let file_id = Uuid::from_str(&file_id)?;
-    let chunk = util::actix::query_var::<i32>(&req, "chunk").ok();
+    let chunk = util::actix::query_var::<i64>(&req, "chunk").ok();
 
     let file = get_file(&context, claims.sub, file_id)
         .await
@@ -71,11 +224,30 @@ pub(crate) async fn head(
         None => file.filename()?.with_extension(".enc"),
     };
 
+    let size = Fs::new(&context.config).size(&file, chunk).await?;
+
     Ok(HttpResponse::Ok()
         .insert_header(("Content-Type", "application/octet-stream"))
         .insert_header((
             "Content-Disposition",
             format!("attachment; filename=\"{}\"", filename),
         ))
-        .finish())
+        .insert_header(("Accept-Ranges", "bytes"))
+        .insert_header(("Link", manifest_link(&file)))
+        .no_chunking(size)
+        .streaming(Streamer::empty().stream()))
+}
+
+/// Points the verification tools to the checksums of the downloaded file
+fn manifest_link(file: &AppFile) -> String {
+    format!("</api/storage/{}/manifest>; rel=\"describedby\"", file.id)
+}
+
+/// Take the byte range from the header, multiple ranges are not supported
+/// so the whole content is sent back for them instead.
+pub(crate) fn single_range(range: Range) -> Option<ByteRangeSpec> {
+    match range {
+        Range::Bytes(mut ranges) if ranges.len() == 1 => ranges.pop(),
+        _ => None,
+    }
 }
@@ -0,0 +1,97 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use chrono::{TimeZone, Utc};
+use context::Context;
+use entity::{exports, TransactionTrait, Uuid};
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::exports::{CreateExport, ExportArchive, EXPORT_EXPIRES_IN_SECONDS},
+    repository::Repository,
+};
+
+/// List the exports of the user data
+///
+/// Response: list of [entity::exports::Model]
+#[route("/api/exports", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let exports = Repository::new(&context.db)
+        .exports(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(exports))
+}
+
+/// Request the new export of the user data, the archive is built in the background
+/// and the previous exports of the user are removed.
+///
+/// Request: [crate::data::exports::CreateExport]
+///
+/// Response: [entity::exports::Model]
+#[route("/api/exports", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateExport>,
+) -> AppResult<HttpResponse> {
+    let include_files = data.into_inner().into_value()?;
+    let connection = context.db.begin().await?;
+
+    let export = Repository::new(&connection)
+        .exports(claims.sub)
+        .create(include_files)
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(export))
+}
+
+/// Download the archive of the finished export, it is available for
+/// [crate::data::exports::EXPORT_EXPIRES_IN_SECONDS] after it was built.
+///
+/// Response: [actix_web::web::Bytes]
+///  - Content-Type: application/x-tar
+#[route("/api/exports/{id}/download", method = "GET")]
+pub(crate) async fn download(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    let export = Repository::new(&context.db)
+        .exports(claims.sub)
+        .get(id)
+        .await?;
+
+    let (size, finished_at) = match (export.status, export.size, export.finished_at) {
+        (exports::Status::Ready, Some(size), Some(finished_at)) => (size, finished_at),
+        _ => return Err(Error::BadRequest("export_not_ready".to_string())),
+    };
+
+    if finished_at + EXPORT_EXPIRES_IN_SECONDS < Utc::now().timestamp() {
+        return Err(Error::NotFound("export_expired".to_string()));
+    }
+
+    let date = Utc
+        .timestamp_opt(export.created_at, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+
+    let streamer = Fs::new(&context.config)
+        .stream(&ExportArchive::from(&export), None)
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/x-tar"))
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"export-{}.tar\"", date),
+        ))
+        .no_chunking(size as u64)
+        .streaming(streamer.stream()))
+}
@@ -0,0 +1,82 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{data::external_exports::CreateExternalExport, repository::Repository};
+
+/// List the exports of the folders to external providers
+///
+/// Response: list of [entity::external_exports::Model]
+#[route("/api/external-exports", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let exports = Repository::new(&context.db)
+        .external_exports(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(exports))
+}
+
+/// Start exporting the folder to an S3 bucket or a WebDAV server, the files are
+/// exported in the background and the progress can be followed on the export.
+///
+/// Request: [crate::data::external_exports::CreateExternalExport]
+///
+/// Response: [entity::external_exports::Model]
+#[route("/api/external-exports", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateExternalExport>,
+) -> AppResult<HttpResponse> {
+    let connection = context.db.begin().await?;
+
+    let export = Repository::new(&connection)
+        .external_exports(claims.sub)
+        .create(data.into_inner())
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(export))
+}
+
+/// Get the export with its progress
+///
+/// Response: [entity::external_exports::Model]
+#[route("/api/external-exports/{id}", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    let export = Repository::new(&context.db)
+        .external_exports(claims.sub)
+        .get(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(export))
+}
+
+/// Cancel the export, the files that were exported so far are left in the target
+///
+/// Response: [entity::external_exports::Model]
+#[route("/api/external-exports/{id}", method = "DELETE")]
+pub(crate) async fn cancel(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    let export = Repository::new(&context.db)
+        .external_exports(claims.sub)
+        .cancel(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(export))
+}
@@ -0,0 +1,221 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use chrono::Utc;
+use context::Context;
+use entity::{users, EntityTrait, TransactionTrait, Uuid};
+use error::{AppResult, Error};
+
+use crate::{
+    data::{
+        create_file::CreateFile,
+        file_requests::{CreateFileRequest, PublicFileRequest},
+        limits,
+        meta::Meta,
+    },
+    repository::{cached, escrow, file_requests, restrictions, Repository},
+    routes::upload::{read_chunk, store_chunk},
+};
+
+/// List the file requests of the user with the summary of what they have received
+///
+/// Response: list of [crate::data::file_requests::FileRequestResponse]
+#[route("/api/file-requests", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let requests = Repository::new(&context.db)
+        .file_requests(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(requests))
+}
+
+/// Create the file request for the folder, anyone with its id can upload files
+/// into the folder until the request is closed or its deadline passes.
+///
+/// Request: [crate::data::file_requests::CreateFileRequest]
+///
+/// Response: [crate::data::file_requests::FileRequestResponse]
+#[route("/api/file-requests", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateFileRequest>,
+) -> AppResult<HttpResponse> {
+    let (file_id, deadline) = data.into_inner().into_value()?;
+    let connection = context.db.begin().await?;
+
+    let request = Repository::new(&connection)
+        .file_requests(claims.sub)
+        .create(file_id, deadline)
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(request))
+}
+
+/// Close the file request before its deadline, the summary
+/// of the received files is sent to the owner.
+///
+/// Response: [crate::data::file_requests::FileRequestResponse]
+#[route("/api/file-requests/{id}", method = "DELETE")]
+pub(crate) async fn close(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let connection = context.db.begin().await?;
+
+    let request = Repository::new(&connection)
+        .file_requests(claims.sub)
+        .close(id)
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(request))
+}
+
+/// Get the file request for uploading, no authentication is required.
+///
+/// Response: [crate::data::file_requests::PublicFileRequest]
+#[route("/api/file-requests/{id}", method = "GET")]
+pub(crate) async fn public(
+    req: HttpRequest,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    let (request, owner) = entity::file_requests::Entity::find_by_id(id)
+        .find_also_related(users::Entity)
+        .one(&context.db)
+        .await?
+        .and_then(|(request, owner)| Some((request, owner?)))
+        .ok_or_else(|| Error::NotFound("file_request_not_found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(PublicFileRequest {
+        id: request.id,
+        pubkey: owner.pubkey,
+        deadline: request.deadline,
+        open: request.is_open(Utc::now().timestamp()),
+    }))
+}
+
+/// Create the file in the folder of the request, no authentication is required.
+/// The file key has to be encrypted with the public key of the request owner.
+///
+/// Request: [crate::data::create_file::CreateFile], the `file_id` is always the folder of the request
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/file-requests/{id}/files", method = "POST")]
+pub(crate) async fn create_file(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    data: web::Json<CreateFile>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let request = file_requests::open(&context.db, id).await?;
+
+    let mut data = data.into_inner();
+
+    if data.mime.as_deref() == Some("dir") {
+        return Err(Error::as_validation("mime", "dir_not_allowed"));
+    }
+
+    limits::check_file(&context.config.server, &data)?;
+    restrictions::check(&context, &data).await?;
+
+    data.file_id = Some(request.file_id.to_string());
+    let escrow_key = data.escrow_key.clone();
+
+    let (create_file, encrypted_metadata, hashed_tokens, file_size, parent_id) =
+        data.into_active_model()?;
+
+    let owner = users::Entity::find_by_id(request.user_id)
+        .one(&context.db)
+        .await?
+        .ok_or_else(|| Error::NotFound("file_request_not_found".to_string()))?;
+
+    let quota = match owner.quota {
+        Some(quota) => Some(quota as u64),
+        None => context.settings.inner().await.users.quota_bytes(),
+    };
+
+    let connection = context.db.begin().await?;
+    let repository = Repository::new(&connection);
+    let manage = repository.manage(request.user_id);
+
+    repository
+        .query(request.user_id)
+        .check_quota(quota, file_size)
+        .await?;
+
+    let name_hash = create_file
+        .name_hash
+        .clone()
+        .into_value()
+        .unwrap()
+        .unwrap::<String>();
+
+    if manage.by_name(&name_hash, parent_id).await.is_ok() {
+        return Err(Error::as_validation("name_hash", "file_exists"));
+    }
+
+    let file = manage
+        .create(create_file, &encrypted_metadata, hashed_tokens)
+        .await?;
+
+    let recovery_key = escrow::recovery_key(&context).await?;
+    escrow::escrow(
+        &connection,
+        recovery_key.as_ref(),
+        file.id,
+        escrow_key.as_deref(),
+    )
+    .await?;
+
+    file_requests::receive(&connection, request.id, file.id).await?;
+
+    connection.commit().await?;
+
+    let ids = [Some(file.id), file.file_id].into_iter().flatten();
+    cached::invalidate(request.user_id, &ids.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
+
+/// Upload the chunk of the file created through the request, no authentication is required.
+///
+/// Query: [crate::data::meta::Meta]
+///
+/// Request:
+///  - Content-Type: application/octet-stream (chunk content bytes)
+///  - Body: (chunk content bytes)
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/file-requests/{id}/files/{file_id}", method = "POST")]
+pub(crate) async fn upload(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    meta: web::Query<Meta>,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let request = file_requests::open(&context.db, id).await?;
+    file_requests::received(&context.db, request.id, file_id).await?;
+
+    let buffer = read_chunk(&context.config.server, payload).await?;
+    let file = store_chunk(
+        &context,
+        request.user_id,
+        file_id,
+        meta.into_inner(),
+        &buffer,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(file))
+}
@@ -0,0 +1,128 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{
+    data::folder_templates::{SaveTemplate, UseTemplate},
+    repository::{cached, escrow, Repository},
+};
+
+/// List the folder templates of the user
+///
+/// Response: list of [entity::folder_templates::Model]
+#[route("/api/folder-templates", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let templates = Repository::new(&context.db)
+        .folder_templates(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+/// Save the folder structure as a template
+///
+/// Request: [crate::data::folder_templates::SaveTemplate]
+///
+/// Response: [entity::folder_templates::Model]
+#[route("/api/folder-templates", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<SaveTemplate>,
+) -> AppResult<HttpResponse> {
+    let (encrypted_name, encrypted_tree) = data.into_inner().into_tuple()?;
+
+    let template = Repository::new(&context.db)
+        .folder_templates(claims.sub)
+        .create(encrypted_name, encrypted_tree)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(template))
+}
+
+/// Replace the name and the structure of the template
+///
+/// Request: [crate::data::folder_templates::SaveTemplate]
+///
+/// Response: [entity::folder_templates::Model]
+#[route("/api/folder-templates/{id}", method = "PUT")]
+pub(crate) async fn update(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<SaveTemplate>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let (encrypted_name, encrypted_tree) = data.into_inner().into_tuple()?;
+
+    let template = Repository::new(&context.db)
+        .folder_templates(claims.sub)
+        .update(id, encrypted_name, encrypted_tree)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(template))
+}
+
+/// Delete the template, the folders that were created from it stay
+#[route("/api/folder-templates/{id}", method = "DELETE")]
+pub(crate) async fn delete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    Repository::new(&context.db)
+        .folder_templates(claims.sub)
+        .delete(id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Create the folders of the template in the folder of the user, either all
+/// of them are created or none. The client decrypts the template and sends
+/// every folder with its new key, the same way as creating it on its own.
+///
+/// Request: [crate::data::folder_templates::UseTemplate]
+///
+/// Response: list of [crate::data::app_file::AppFile]
+#[route("/api/folder-templates/{id}/use", method = "POST")]
+pub(crate) async fn instantiate(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<UseTemplate>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let (target, folders) = data.into_inner().into_value()?;
+    let (folders, escrow_keys): (Vec<_>, Vec<_>) = folders.into_iter().unzip();
+
+    let connection = context.db.begin().await?;
+
+    let created = Repository::new(&connection)
+        .folder_templates(claims.sub)
+        .instantiate(id, target, folders)
+        .await?;
+
+    let recovery_key = escrow::recovery_key(&context).await?;
+    for (folder, escrow_key) in created.iter().zip(escrow_keys) {
+        escrow::escrow(
+            &connection,
+            recovery_key.as_ref(),
+            folder.id,
+            escrow_key.as_deref(),
+        )
+        .await?;
+    }
+
+    connection.commit().await?;
+
+    let ids = created.iter().map(|folder| folder.id).chain(target);
+    cached::invalidate(claims.sub, &ids.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(created))
+}
@@ -0,0 +1,82 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{data::imports::CreateImport, repository::Repository};
+
+/// List the imports of the files from other services
+///
+/// Response: list of [entity::imports::Model]
+#[route("/api/imports", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let imports = Repository::new(&context.db)
+        .imports(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(imports))
+}
+
+/// Start importing the files from another service, the files are imported
+/// in the background and the progress can be followed on the import.
+///
+/// Request: [crate::data::imports::CreateImport]
+///
+/// Response: [entity::imports::Model]
+#[route("/api/imports", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateImport>,
+) -> AppResult<HttpResponse> {
+    let connection = context.db.begin().await?;
+
+    let import = Repository::new(&connection)
+        .imports(claims.sub)
+        .create(data.into_inner())
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(import))
+}
+
+/// Get the import with its progress
+///
+/// Response: [entity::imports::Model]
+#[route("/api/imports/{id}", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    let import = Repository::new(&context.db)
+        .imports(claims.sub)
+        .get(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(import))
+}
+
+/// Cancel the import, the files that were imported so far are kept
+///
+/// Response: [entity::imports::Model]
+#[route("/api/imports/{id}", method = "DELETE")]
+pub(crate) async fn cancel(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    let import = Repository::new(&context.db)
+        .imports(claims.sub)
+        .cancel(id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(import))
+}
@@ -4,7 +4,7 @@ use context::Context;
 use error::AppResult;
 use fs::prelude::*;
 
-use crate::{data::query::Query, repository::Repository};
+use crate::{data::query::Query, repository::cached};
 
 /// List files and directories
 ///
@@ -19,18 +19,30 @@ pub(crate) async fn index(
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
 
-    let mut response = Repository::new(&context.db)
-        .manage(claims.sub)
-        .find(data.into_inner())
-        .await?;
+    let mut response = cached::find(&context, claims.sub, data.into_inner()).await?;
 
-    for file in response.children.iter_mut() {
-        if file.is_file() {
-            let chunks = Fs::new(&context.config).get_uploaded_chunks(file).await?;
+    // Finished uploads have all of their chunks, only the unfinished ones
+    // need to be looked up in the storage, and all of them at once.
+    let pending = response
+        .children
+        .iter()
+        .filter(|file| file.is_file() && file.finished_upload_at.is_none())
+        .cloned()
+        .collect::<Vec<_>>();
 
-            file.chunks_stored = Some(chunks.len() as i64);
-            file.uploaded_chunks = Some(chunks);
-        }
+    let mut pending_chunks = Fs::new(&context.config)
+        .get_uploaded_chunks_many(&pending)
+        .await?
+        .into_iter();
+
+    for file in response.children.iter_mut().filter(|file| file.is_file()) {
+        let chunks = match file.finished_upload_at {
+            Some(_) => (0..file.chunks.unwrap_or_default()).collect(),
+            None => pending_chunks.next().unwrap_or_default(),
+        };
+
+        file.chunks_stored = Some(chunks.len() as i64);
+        file.uploaded_chunks = Some(chunks);
     }
 
     Ok(HttpResponse::Ok().json(response))
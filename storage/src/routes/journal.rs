@@ -0,0 +1,28 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use error::AppResult;
+
+use crate::{data::changes::Changes, repository::Repository};
+
+/// Page through the change journal of the whole account, every change of the user files
+/// and their shares is in there with a strictly increasing sequence number.
+///
+/// Query: [crate::data::changes::Changes]
+///
+/// Response: [crate::data::changes::JournalResponse]
+#[route("/api/journal", method = "GET")]
+pub(crate) async fn journal(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Query<Changes>,
+) -> AppResult<HttpResponse> {
+    let (since, limit) = data.into_inner().into_value();
+
+    let response = Repository::new(&context.db)
+        .activities(claims.sub)
+        .journal(since, limit)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
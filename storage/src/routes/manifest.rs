@@ -0,0 +1,133 @@
+use std::{collections::HashSet, str::FromStr};
+
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use config::Config;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::prelude::*;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    authorize::{self, Access},
+    data::{
+        app_file::AppFile,
+        manifest::{ChunkChecksum, FileManifest, Manifest},
+    },
+    repository::{cached::get_file, manage::manifest_stream, Repository},
+};
+
+use super::search::{into_ndjson, NDJSON};
+
+/// Get the manifest of the given folders and everything inside of them, so the sync clients
+/// can diff it against their local replica in one round trip.
+///
+/// Request: [crate::data::manifest::Manifest]
+///
+/// Response: newline delimited JSON, one [crate::data::manifest::ManifestEntry] per line
+#[route("/api/storage/manifest", method = "POST")]
+pub(crate) async fn manifest(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Manifest>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let dir_ids = data.into_inner().into_value()?;
+
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(claims.sub);
+
+    let mut ids = vec![];
+    let mut seen = HashSet::new();
+
+    for dir_id in dir_ids {
+        let dir = repository.by_id(dir_id, claims.sub).await?;
+
+        authorize::directory(&context.db, &claims, Access::Own, &dir).await?;
+
+        for id in manage.tree_ids(dir.id).await? {
+            if seen.insert(id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    let stream = manifest_stream(context.clone(), claims.sub, ids).map(|batch| into_ndjson(batch?));
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", NDJSON))
+        .streaming(stream))
+}
+
+/// Get the checksums of the stored chunks of the file and of the whole encrypted file,
+/// so the downloads can be verified by the external tools.
+///
+/// Request:
+///  - Query: format: "sha256sum" - the manifest is sent in the `sha256sum` output format
+///
+/// Response: [crate::data::manifest::FileManifest]
+#[route("/api/storage/{file_id}/manifest", method = "GET")]
+pub(crate) async fn file(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let file_id: String = util::actix::path_var(&req, "file_id")?;
+    let file_id = Uuid::from_str(&file_id)?;
+    let format = util::actix::query_var::<String>(&req, "format").ok();
+
+    let file = get_file(&context, claims.sub, file_id)
+        .await
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    let manifest = checksums(&context.config, &file).await?;
+
+    match format.as_deref() {
+        Some("sha256sum") => Ok(HttpResponse::Ok()
+            .insert_header(("Content-Type", "text/plain; charset=utf-8"))
+            .body(manifest.sha256sum())),
+        Some(_) => Err(Error::BadRequest("unsupported_manifest_format".to_string())),
+        None => Ok(HttpResponse::Ok().json(manifest)),
+    }
+}
+
+/// Read the stored chunks of the file one by one and hash them, the whole file
+/// hash is calculated along the way, so each chunk is read only once.
+pub(crate) async fn checksums(config: &Config, file: &AppFile) -> AppResult<FileManifest> {
+    if !file.is_file() {
+        return Err(Error::BadRequest("file_has_no_content".to_string()));
+    }
+
+    let fs = Fs::new(config);
+    let filename = file.filename()?;
+    let mut hasher = Sha256::new();
+    let mut chunks = vec![];
+
+    for chunk in fs.get_uploaded_chunks(file).await? {
+        let data = fs.pull(file, chunk).await?;
+        hasher.update(&data);
+
+        chunks.push(ChunkChecksum {
+            chunk,
+            filename: filename
+                .clone()
+                .with_chunk(chunk)
+                .with_extension(".enc")
+                .to_string(),
+            size: data.len() as u64,
+            sha256: cryptfns::sha256::digest(data.as_slice()),
+        });
+    }
+
+    Ok(FileManifest {
+        id: file.id,
+        filename: filename.with_extension(".enc").to_string(),
+        size: file.size,
+        encrypted_sha256: cryptfns::hex::encode(hasher.finalize()),
+        sha256: file.sha256.clone(),
+        chunks,
+    })
+}
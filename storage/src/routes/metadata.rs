@@ -2,11 +2,14 @@ use actix_web::{route, web, HttpRequest, HttpResponse};
 use auth::data::claims::Claims;
 use context::Context;
 use entity::Uuid;
-use error::{AppResult, Error};
+use error::AppResult;
 use fs::prelude::*;
 use std::str::FromStr;
 
-use crate::repository::Repository;
+use crate::{
+    authorize::{authorize, Access},
+    repository::cached,
+};
 
 /// Get file metadata by its id
 ///
@@ -21,17 +24,15 @@ pub(crate) async fn metadata(
     let file_id: String = util::actix::path_var(&req, "file_id")?;
     let file_id = Uuid::from_str(&file_id)?;
 
-    let mut file = Repository::new(&context.db)
-        .query(claims.sub)
-        .get(file_id)
-        .await?;
+    let mut file = cached::get_metadata(&context, claims.sub, file_id).await?;
 
-    if file.is_dir() && !file.is_owner {
-        return Err(Error::NotFound("file_or_dir_not_found".to_string()));
-    }
+    authorize(&context.db, &claims, Access::Read, &file).await?;
 
     if file.is_file() && file.finished_upload_at.is_none() {
-        file.uploaded_chunks = Some(Fs::new(&context.config).get_uploaded_chunks(&file).await?);
+        let chunks = Fs::new(&context.config).get_uploaded_chunks(&file).await?;
+
+        file.chunks_stored = Some(chunks.len() as i64);
+        file.uploaded_chunks = Some(chunks);
     }
 
     Ok(HttpResponse::Ok().json(file))
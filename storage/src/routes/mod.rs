@@ -6,33 +6,168 @@
 //! TODO: This module exposes routes for sharing files with other users
 //! on the platform.
 
+pub mod access_keys;
+pub mod attributes;
+pub mod batch;
+pub mod bulk;
+pub mod changes;
+pub mod content;
 pub mod create;
+pub mod dav;
+pub mod dedup;
 pub mod delete;
 pub mod delete_many;
 pub mod download;
+pub mod exports;
+pub mod external_exports;
+pub mod file_requests;
+pub mod folder_templates;
+pub mod imports;
 pub mod index;
+pub mod journal;
+pub mod manifest;
 pub mod metadata;
 pub mod move_many;
 pub mod name_hash;
+pub mod net_test;
+pub mod policy;
 pub mod rename;
+pub mod resume;
+pub mod rewrap;
+pub mod s3;
 pub mod search;
+pub mod shares;
+pub mod simple_upload;
+pub mod snapshot;
+pub mod socket;
+pub mod spaces;
 pub mod stats;
+pub mod thumbnail;
+pub mod transfer;
+pub mod trash;
+pub mod tree;
+pub mod tus;
 pub mod upload;
+pub mod verdicts;
+pub mod versions;
+pub mod virtual_file;
+pub mod zip;
 
 /// Register the storage routes
 /// on to the application server
 pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(access_keys::index);
+    cfg.service(access_keys::create);
+    cfg.service(access_keys::delete);
+    cfg.service(attributes::update);
+    cfg.service(batch::batch);
+    cfg.service(bulk::bulk);
+    cfg.service(changes::changes);
+    cfg.service(content::get);
+    cfg.service(content::put);
     cfg.service(create::create);
+    cfg.service(dav::options);
+    cfg.service(dav::propfind);
+    cfg.service(dav::get);
+    cfg.service(dav::head);
+    cfg.service(dav::put);
+    cfg.service(dav::mkcol);
+    cfg.service(dav::move_file);
+    cfg.service(dav::delete);
+    cfg.service(dedup::lookup);
+    cfg.service(dedup::create);
     cfg.service(delete_many::delete_many);
+    // Registered before the download, it would take the `tree` as the file id
+    cfg.service(tree::tree);
+    // Registered before the download and the delete, they would take the `trash` as the file id
+    cfg.service(trash::index);
+    cfg.service(trash::restore);
+    cfg.service(trash::purge);
+    cfg.service(trash::empty);
     cfg.service(delete::delete);
     cfg.service(download::download);
     cfg.service(download::head);
+    cfg.service(exports::index);
+    cfg.service(exports::create);
+    cfg.service(exports::download);
+    cfg.service(external_exports::index);
+    cfg.service(external_exports::create);
+    cfg.service(external_exports::get);
+    cfg.service(external_exports::cancel);
+    cfg.service(file_requests::index);
+    cfg.service(file_requests::create);
+    cfg.service(file_requests::close);
+    cfg.service(file_requests::public);
+    cfg.service(file_requests::create_file);
+    cfg.service(file_requests::upload);
+    cfg.service(folder_templates::index);
+    cfg.service(folder_templates::create);
+    cfg.service(folder_templates::update);
+    cfg.service(folder_templates::delete);
+    cfg.service(folder_templates::instantiate);
+    cfg.service(imports::index);
+    cfg.service(imports::create);
+    cfg.service(imports::get);
+    cfg.service(imports::cancel);
     cfg.service(index::index);
+    cfg.service(journal::journal);
+    cfg.service(manifest::manifest);
+    cfg.service(manifest::file);
     cfg.service(metadata::metadata);
     cfg.service(move_many::move_many);
     cfg.service(name_hash::name_hash);
+    // Registered before the upload, it would take the `name-hashes` as the file id
+    cfg.service(name_hash::lookup);
+    cfg.service(net_test::download);
+    cfg.service(net_test::upload);
+    cfg.service(policy::get);
+    cfg.service(policy::set);
+    cfg.service(policy::delete);
     cfg.service(rename::rename);
+    cfg.service(rename::rename_file);
+    cfg.service(resume::token);
+    // Registered before the upload, it would take the `resume` as the file id
+    cfg.service(resume::redeem);
+    cfg.service(rewrap::index);
+    cfg.service(rewrap::worklist);
+    cfg.service(rewrap::complete);
+    cfg.service(s3::get);
+    cfg.service(s3::head);
+    cfg.service(s3::put);
+    cfg.service(s3::delete);
     cfg.service(search::search);
+    cfg.service(shares::received);
+    cfg.service(shares::recipient);
+    cfg.service(shares::members);
+    cfg.service(shares::index);
+    cfg.service(shares::create);
+    cfg.service(shares::create_for_group);
+    cfg.service(shares::schedule);
+    cfg.service(shares::revoke);
+    cfg.service(snapshot::snapshot);
+    cfg.service(snapshot::diff);
+    cfg.service(socket::socket);
+    cfg.service(spaces::index);
+    cfg.service(spaces::create);
+    cfg.service(spaces::delete);
     cfg.service(stats::stats);
+    cfg.service(thumbnail::upload);
+    cfg.service(thumbnail::download);
+    cfg.service(transfer::move_file);
+    cfg.service(transfer::copy);
+    cfg.service(tus::options);
+    // Registered before the upload, it would take the `tus` as the file id
+    cfg.service(tus::create);
+    cfg.service(tus::head);
+    cfg.service(tus::patch);
+    cfg.service(zip::zip);
+    // Registered before the upload, it would take the `virtual` as the file id
+    cfg.service(virtual_file::create);
+    // Registered before the upload, it would take the `simple-upload` as the file id
+    cfg.service(simple_upload::simple_upload);
     cfg.service(upload::upload);
+    cfg.service(verdicts::index);
+    cfg.service(versions::index);
+    cfg.service(versions::restore);
+    cfg.service(versions::delete);
 }
@@ -4,7 +4,10 @@ use context::Context;
 use entity::TransactionTrait;
 use error::AppResult;
 
-use crate::{data::move_many::MoveMany, repository::Repository};
+use crate::{
+    data::move_many::MoveMany,
+    repository::{cached, holds, Repository},
+};
 
 /// Moves many files and folders into a new parent folder
 ///
@@ -17,6 +20,10 @@ pub(crate) async fn move_many(
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
     let (ids, file_id) = data.into_inner().into_value()?;
+    let mut changed = ids.clone();
+    changed.extend(file_id);
+
+    holds::guard_move(&context.db, claims.sub, &ids, file_id).await?;
 
     let connection = context.db.begin().await?;
     Repository::new(&connection)
@@ -25,5 +32,7 @@ pub(crate) async fn move_many(
         .await?;
     connection.commit().await?;
 
+    cached::invalidate(claims.sub, &changed).await;
+
     Ok(HttpResponse::NoContent().finish())
 }
@@ -5,10 +5,11 @@ use entity::Uuid;
 use error::AppResult;
 use fs::prelude::*;
 
-use crate::repository::Repository;
+use crate::{data::name_hash::NameHashLookup, repository::Repository};
 
 /// Get file metadata by name hash and directory id user can only
-/// query his own files this way.
+/// query his own files this way. When there are more files with the
+/// same name hash in the directory the oldest one is returned.
 ///
 /// Response: [crate::data::app_file::AppFile]
 #[route("/api/storage/{name_hash}/name-hash", method = "GET")]
@@ -32,3 +33,25 @@ pub(crate) async fn name_hash(
 
     Ok(HttpResponse::Ok().json(file))
 }
+
+/// Look up many files by their name hashes in the directory at once,
+/// only the ids are returned so the client can check what exists.
+///
+/// Request: [crate::data::name_hash::NameHashLookup]
+///
+/// Response: list of [crate::data::name_hash::NameHashMatch]
+#[route("/api/storage/name-hashes", method = "POST")]
+pub(crate) async fn lookup(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<NameHashLookup>,
+) -> AppResult<HttpResponse> {
+    let (parent_id, name_hashes) = data.into_inner().into_tuple()?;
+
+    let matches = Repository::new(&context.db)
+        .manage(claims.sub)
+        .by_names(name_hashes, parent_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(matches))
+}
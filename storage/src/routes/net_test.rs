@@ -0,0 +1,80 @@
+use std::time::Instant;
+
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use error::{AppResult, Error};
+use futures::StreamExt;
+
+use crate::data::net_test::{NetTest, NetTestResult, MAX_NET_TEST_BYTES};
+
+/// Size of the block of random bytes that is repeated in the download test
+const NET_TEST_BLOCK_BYTES: usize = 64 * 1024;
+
+/// Download the given number of throwaway bytes, the client measures the time it took.
+///
+/// The bytes are random, so the measurement isn't skewed by the compression on the way.
+///
+/// Query: [crate::data::net_test::NetTest]
+///
+/// Response: `application/octet-stream` body of the requested size
+#[route("/api/net-test", method = "GET")]
+pub(crate) async fn download(
+    _claims: Claims,
+    data: web::Query<NetTest>,
+) -> AppResult<HttpResponse> {
+    let size = data.into_inner().into_value()?;
+
+    let key = cryptfns::chacha::generate_key()?;
+    let block = web::Bytes::from(cryptfns::chacha::encrypt(
+        key,
+        vec![0; NET_TEST_BLOCK_BYTES],
+    )?);
+
+    let blocks = (0..size).step_by(NET_TEST_BLOCK_BYTES).map(move |sent| {
+        let len = (size - sent).min(NET_TEST_BLOCK_BYTES as u64) as usize;
+
+        Ok::<_, Error>(block.slice(..len))
+    });
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/octet-stream"))
+        .insert_header(("Cache-Control", "no-store"))
+        .no_chunking(size)
+        .streaming(futures::stream::iter(blocks)))
+}
+
+/// Upload throwaway bytes, they are discarded as they arrive and the server
+/// responds with the number of bytes it received and how long it took.
+///
+/// Request:
+///  - Content-Type: application/octet-stream
+///  - Body: up to [crate::data::net_test::MAX_NET_TEST_BYTES] of anything
+///
+/// Response: [crate::data::net_test::NetTestResult]
+#[route("/api/net-test", method = "POST")]
+pub(crate) async fn upload(_claims: Claims, mut payload: web::Payload) -> AppResult<HttpResponse> {
+    let mut bytes = 0;
+    let mut started = None;
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| Error::BadRequest(e.to_string()))?;
+        started.get_or_insert_with(Instant::now);
+        bytes += chunk.len() as u64;
+
+        if bytes > MAX_NET_TEST_BYTES {
+            let error = format!("max:{}", MAX_NET_TEST_BYTES);
+
+            return Err(Error::as_validation("body", &error));
+        }
+    }
+
+    if bytes == 0 {
+        return Err(Error::BadRequest("no_data_received".to_string()));
+    }
+
+    let duration = started.map(|s| s.elapsed()).unwrap_or_default();
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .json(NetTestResult::new(bytes, duration)))
+}
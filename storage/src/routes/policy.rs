@@ -0,0 +1,72 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{
+    data::policy::{PolicyResponse, SetPolicy},
+    repository::{policies::effective_policy, Repository},
+};
+
+/// Get the sharing policy set on the folder and the one in effect
+/// when the policies of the folders above it are included.
+///
+/// Response: [crate::data::policy::PolicyResponse]
+#[route("/api/storage/{file_id}/policy", method = "GET")]
+pub(crate) async fn get(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let repository = Repository::new(&context.db);
+    let policy = repository.policies(claims.sub).get(file_id).await?;
+    let effective = effective_policy(&context.db, file_id).await?;
+
+    Ok(HttpResponse::Ok().json(PolicyResponse { policy, effective }))
+}
+
+/// Set the sharing policy of the folder, it is enforced when the links or the shares
+/// are created for the folder or anything inside of it.
+///
+/// Request: [crate::data::policy::SetPolicy]
+///
+/// Response: [crate::data::policy::PolicyResponse]
+#[route("/api/storage/{file_id}/policy", method = "PUT")]
+pub(crate) async fn set(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<SetPolicy>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let policy = data.into_inner().into_value()?;
+
+    let repository = Repository::new(&context.db);
+    let policy = repository.policies(claims.sub).set(file_id, policy).await?;
+    let effective = effective_policy(&context.db, file_id).await?;
+
+    Ok(HttpResponse::Ok().json(PolicyResponse {
+        policy: Some(policy),
+        effective,
+    }))
+}
+
+/// Remove the sharing policy from the folder
+#[route("/api/storage/{file_id}/policy", method = "DELETE")]
+pub(crate) async fn delete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    Repository::new(&context.db)
+        .policies(claims.sub)
+        .delete(file_id)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -4,7 +4,10 @@ use context::Context;
 use entity::{TransactionTrait, Uuid};
 use error::AppResult;
 
-use crate::{data::rename::Rename, repository::Repository};
+use crate::{
+    data::{limits, rename::Rename},
+    repository::{cached, Repository},
+};
 
 /// Rename a file or a folder
 ///
@@ -17,18 +20,47 @@ pub(crate) async fn rename(
     claims: Claims,
     context: web::Data<Context>,
     data: web::Json<Rename>,
+) -> AppResult<HttpResponse> {
+    handle(req, claims, context, data).await
+}
+
+/// Rename a file or a folder, the encrypted name and the name hash are replaced
+/// together in a single transaction and the new name must not be taken in the folder.
+/// Same as [rename], only with its own path so it can't be mistaken for another update.
+///
+/// Request: [crate::data::rename::Rename]
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/{file_id}/rename", method = "PUT")]
+pub(crate) async fn rename_file(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Rename>,
+) -> AppResult<HttpResponse> {
+    handle(req, claims, context, data).await
+}
+
+async fn handle(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Rename>,
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
+    let data = data.into_inner();
+
+    limits::check_rename(&context.config.server, &data)?;
+
     let connection = context.db.begin().await?;
     let repository = Repository::new(&connection);
     let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
 
-    let file = repository
-        .manage(claims.sub)
-        .rename(file_id, data.into_inner())
-        .await?;
+    let file = repository.manage(claims.sub).rename(file_id, data).await?;
 
     connection.commit().await?;
 
+    cached::invalidate(claims.sub, &[file.id]).await;
+
     Ok(HttpResponse::Ok().json(file))
 }
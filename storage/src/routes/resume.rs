@@ -0,0 +1,86 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    authorize::{self, Access},
+    data::resume::{Resume, ResumeClaims, ResumeState},
+    repository::cached,
+};
+
+/// Get the upload state of the file together with the token to resume
+/// the upload with from another device of the user.
+///
+/// Response: [crate::data::resume::ResumeState]
+#[route("/api/storage/{file_id}/resume", method = "POST")]
+pub(crate) async fn token(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let resume = ResumeClaims::new(claims.sub, file_id, claims.device);
+    let state = state(&context, &claims, resume).await?;
+
+    Ok(HttpResponse::Ok().json(state))
+}
+
+/// Redeem the resume token on the other device, the upload state is the same
+/// as on the device that started the upload. Missing chunks are uploaded with
+/// the regular upload routes.
+///
+/// Request: [crate::data::resume::Resume]
+///
+/// Response: [crate::data::resume::ResumeState]
+#[route("/api/storage/resume", method = "POST")]
+pub(crate) async fn redeem(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Resume>,
+) -> AppResult<HttpResponse> {
+    let token = data.into_inner().into_value()?;
+    let resume = ResumeClaims::decode(&token, &context.config.auth.jwt_secret)?;
+
+    if resume.sub != claims.sub {
+        return Err(Error::BadRequest("invalid_resume_token".to_string()));
+    }
+
+    let state = state(&context, &claims, resume).await?;
+
+    Ok(HttpResponse::Ok().json(state))
+}
+
+/// Load the file with the chunks that are already uploaded, files that finished
+/// uploading have nothing to resume.
+async fn state(context: &Context, claims: &Claims, resume: ResumeClaims) -> AppResult<ResumeState> {
+    let mut file = cached::get_metadata(context, claims.sub, resume.file_id).await?;
+
+    authorize::file(&context.db, claims, Access::Own, &file).await?;
+
+    // Apps limited to a folder can only resume the files they could upload
+    claims.check_folder(file.file_id)?;
+
+    if file.finished_upload_at.is_some() {
+        return Err(Error::BadRequest("file_upload_finished".to_string()));
+    }
+
+    let uploaded = Fs::new(&context.config).get_uploaded_chunks(&file).await?;
+    let missing_chunks = (0..file.chunks.unwrap_or_default())
+        .filter(|chunk| !uploaded.contains(chunk))
+        .collect();
+
+    file.chunks_stored = Some(uploaded.len() as i64);
+    file.uploaded_chunks = Some(uploaded);
+
+    Ok(ResumeState {
+        token: resume.encode(&context.config.auth.jwt_secret)?,
+        expires_at: resume.exp,
+        device: resume.device,
+        missing_chunks,
+        file,
+    })
+}
@@ -0,0 +1,70 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{
+    data::rewrap::{CompleteRewrap, Worklist, REWRAP_BATCH_SIZE},
+    repository::{cached, Repository},
+};
+
+/// List the re-wrap jobs of the user that are not completed yet
+///
+/// Response: list of [crate::data::rewrap::RewrapJob]
+#[route("/api/rewrap-jobs", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let jobs = Repository::new(&context.db)
+        .rewrap(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(jobs))
+}
+
+/// Get the job with the next batch of the keys the client has to re-wrap
+///
+/// Response: [crate::data::rewrap::Worklist]
+#[route("/api/rewrap-jobs/{id}", method = "GET")]
+pub(crate) async fn worklist(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let repository = Repository::new(&context.db);
+    let rewrap = repository.rewrap(claims.sub);
+
+    let job = rewrap.get(id).await?;
+    let entries = rewrap.entries(id, REWRAP_BATCH_SIZE).await?;
+
+    Ok(HttpResponse::Ok().json(Worklist { job, entries }))
+}
+
+/// Send the re-wrapped keys back, the job is completed once all of them are sent
+///
+/// Request: [crate::data::rewrap::CompleteRewrap]
+///
+/// Response: [crate::data::rewrap::RewrapJob]
+#[route("/api/rewrap-jobs/{id}", method = "POST")]
+pub(crate) async fn complete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CompleteRewrap>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+    let keys = data.into_inner().into_value()?;
+    let connection = context.db.begin().await?;
+
+    let repository = Repository::new(&connection);
+    let rewrap = repository.rewrap(claims.sub);
+    let changed = rewrap.files(&keys).await?;
+    let job = rewrap.complete(id, keys).await?;
+
+    connection.commit().await?;
+
+    cached::invalidate(claims.sub, &changed).await;
+
+    Ok(HttpResponse::Ok().json(job))
+}
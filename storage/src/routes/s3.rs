@@ -0,0 +1,240 @@
+//! # S3 gateway routes
+//!
+//! S3 compatible API for the tools like rclone and restic, see [crate::s3]. The requests
+//! are signed with the access key instead of the session token, and the errors are
+//! answered with the S3 error XML the tools understand.
+use actix_web::{http::header::Range, route, web, HttpRequest, HttpResponse, HttpResponseBuilder};
+use context::Context;
+use error::{AppResult, Error};
+use fs::watchdog::Watchdog;
+use futures::StreamExt;
+
+use crate::{
+    data::{app_file::AppFile, s3::ListObjects},
+    repository::verdicts::guard_download,
+    routes::download,
+    s3::{self, xml, Gateway},
+};
+
+/// List the objects in the bucket (ListObjectsV2 with `list-type=2`), or get
+/// the object when the path has the key (GetObject). `?location` answers with
+/// the empty location of the bucket.
+///
+/// Request:
+///  - Query: [crate::data::s3::ListObjects] for the listing
+///  - Range: single byte range of the object
+///
+/// Response: `ListBucketResult` XML, or the content of the object
+#[route("/api/s3/{tail:.*}", method = "GET")]
+pub(crate) async fn get(req: HttpRequest, context: web::Data<Context>) -> HttpResponse {
+    xml::respond(get_inner(&req, &context).await)
+}
+
+async fn get_inner(req: &HttpRequest, context: &Context) -> AppResult<HttpResponse> {
+    let (gateway, _) = Gateway::authenticate(context, req).await?;
+    let (bucket_name, key) = s3::location(req.path())?;
+    let bucket = gateway.bucket(&bucket_name).await?;
+
+    let key = match key {
+        Some(key) => key,
+        None => {
+            let query = web::Query::<ListObjects>::from_query(req.query_string())
+                .map_err(|e| Error::BadRequest(e.to_string()))?
+                .into_inner();
+
+            if query.location.is_some() {
+                return Ok(xml_response(xml::location_constraint()));
+            }
+
+            let query = query.into_value()?;
+            let listing = gateway.list(&bucket, &query).await?;
+
+            return Ok(xml_response(xml::list_bucket_result(
+                &bucket_name,
+                &query,
+                &listing,
+            )));
+        }
+    };
+
+    let (object, file) = gateway.object(&bucket, &key).await?;
+    guard_download(&context.db, file.user_id, file.id).await?;
+
+    let size = file.size.unwrap_or(0) as u64;
+
+    let range = req
+        .headers()
+        .get("Range")
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.parse::<Range>().ok())
+        .and_then(download::single_range);
+
+    let range = match range.map(|range| range.to_satisfiable_range(size)) {
+        Some(Some(range)) => Some(range),
+        Some(None) => {
+            return Ok(HttpResponse::RangeNotSatisfiable()
+                .insert_header(("Content-Range", format!("bytes */{}", size)))
+                .finish())
+        }
+        None => None,
+    };
+
+    let mut response = match range {
+        Some((start, end)) => {
+            let mut response = HttpResponse::PartialContent();
+            response.insert_header(("Content-Range", format!("bytes {}-{}/{}", start, end, size)));
+            response
+        }
+        None => HttpResponse::Ok(),
+    };
+    headers(&mut response, &file);
+
+    let (start, end) = match range.or_else(|| (size > 0).then(|| (0, size - 1))) {
+        Some(range) => range,
+        None => return Ok(response.finish()),
+    };
+
+    let content = gateway.content(file, &object, start, end)?;
+
+    Ok(response
+        .no_chunking(end - start + 1)
+        .streaming(Watchdog::new(Box::pin(content), &context.config.server)))
+}
+
+/// Check the bucket exists (HeadBucket), or get the headers of the object
+/// without its content when the path has the key (HeadObject)
+///
+/// Response: `200 OK`, `Content-Length` is the size of the object
+#[route("/api/s3/{tail:.*}", method = "HEAD")]
+pub(crate) async fn head(req: HttpRequest, context: web::Data<Context>) -> HttpResponse {
+    // HEAD responses have no body, the status is all the tools get
+    match head_inner(&req, &context).await {
+        Ok(response) => response,
+        Err(e) => HttpResponse::build(xml::respond(Err(e)).status()).finish(),
+    }
+}
+
+async fn head_inner(req: &HttpRequest, context: &Context) -> AppResult<HttpResponse> {
+    let (gateway, _) = Gateway::authenticate(context, req).await?;
+    let (bucket_name, key) = s3::location(req.path())?;
+    let bucket = gateway.bucket(&bucket_name).await?;
+
+    let key = match key {
+        Some(key) => key,
+        None => return Ok(HttpResponse::Ok().finish()),
+    };
+
+    let (_, file) = gateway.object(&bucket, &key).await?;
+
+    let mut response = HttpResponse::Ok();
+    headers(&mut response, &file);
+
+    Ok(response.no_chunking(file.size.unwrap_or(0) as u64).finish())
+}
+
+/// Upload the object (PutObject), the object with the same key is replaced.
+/// Without the key in the path the bucket is created (CreateBucket).
+///
+/// Request:
+///  - Content-Length: size of the object
+///  - Content-Type: mime of the object, `application/octet-stream` if omitted
+///  - x-amz-content-sha256: SHA-256 of the body, or `UNSIGNED-PAYLOAD`
+///  - Body: content of the object
+///
+/// Response: `200 OK` with the `ETag` of the object
+#[route("/api/s3/{tail:.*}", method = "PUT")]
+pub(crate) async fn put(
+    req: HttpRequest,
+    context: web::Data<Context>,
+    payload: web::Payload,
+) -> HttpResponse {
+    xml::respond(put_inner(&req, &context, payload).await)
+}
+
+async fn put_inner(
+    req: &HttpRequest,
+    context: &Context,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    let (gateway, hash) = Gateway::authenticate(context, req).await?;
+    let (bucket_name, key) = s3::location(req.path())?;
+
+    let key = match key {
+        Some(key) => key,
+        None => {
+            gateway.create_bucket(&bucket_name).await?;
+
+            return Ok(HttpResponse::Ok()
+                .insert_header(("Location", format!("/{}", bucket_name)))
+                .finish());
+        }
+    };
+
+    if req.headers().contains_key("x-amz-copy-source") {
+        return Err(Error::BadRequest("copy_not_supported".to_string()));
+    }
+
+    let size = match req
+        .headers()
+        .get("Content-Length")
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<i64>().ok())
+    {
+        Some(size) => size,
+        None => return Ok(HttpResponse::LengthRequired().finish()),
+    };
+
+    let mime = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|mime| mime.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let bucket = gateway.bucket(&bucket_name).await?;
+
+    let payload = payload.map(|bytes| bytes.map_err(|e| Error::BadRequest(e.to_string())));
+    let body = Box::pin(Watchdog::new(payload, &context.config.server));
+
+    let file = gateway.put(&bucket, &key, mime, size, body, hash).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("ETag", xml::etag(file.id)))
+        .finish())
+}
+
+/// Move the object to the trash (DeleteObject), deleting the object
+/// that doesn't exist succeeds as well
+///
+/// Response: `204 No Content`
+#[route("/api/s3/{tail:.*}", method = "DELETE")]
+pub(crate) async fn delete(req: HttpRequest, context: web::Data<Context>) -> HttpResponse {
+    xml::respond(delete_inner(&req, &context).await)
+}
+
+async fn delete_inner(req: &HttpRequest, context: &Context) -> AppResult<HttpResponse> {
+    let (gateway, _) = Gateway::authenticate(context, req).await?;
+    let (bucket_name, key) = s3::location(req.path())?;
+
+    let key = key.ok_or_else(|| Error::BadRequest("delete_bucket_not_supported".to_string()))?;
+    let bucket = gateway.bucket(&bucket_name).await?;
+
+    gateway.delete(&bucket, &key).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Headers of the object, the same for the GetObject and the HeadObject
+fn headers(response: &mut HttpResponseBuilder, file: &AppFile) {
+    response
+        .insert_header(("Content-Type", file.mime.as_str()))
+        .insert_header(("Accept-Ranges", "bytes"))
+        .insert_header(("ETag", xml::etag(file.id)))
+        .insert_header(("Last-Modified", xml::http_date(file.created_at)));
+}
+
+fn xml_response(body: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/xml")
+        .body(body)
+}
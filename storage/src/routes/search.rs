@@ -1,29 +1,84 @@
-use actix_web::{route, web, HttpResponse};
+use actix_web::{http::header, route, web, HttpRequest, HttpResponse};
 use auth::data::claims::Claims;
 use context::Context;
 use error::AppResult;
+use futures::StreamExt;
+use serde::Serialize;
 
-use crate::{data::search::Search, repository::Repository};
+use crate::{
+    data::search::{Search, SearchResponse},
+    repository::{tokens::search_stream, Repository},
+};
 
-/// List files and directories
+/// Content type of the streamed response, one JSON encoded file per line
+pub(crate) const NDJSON: &str = "application/x-ndjson";
+
+/// Search files and directories
 ///
 /// Request: [crate::data::search::Search]
 ///
-/// Response: [Vec<crate::data::app_file::AppFile>]
+/// Response: [crate::data::search::SearchResponse]
+///
+/// Files can be found by the filters alone, without the tokens: their `kind`, the time
+/// they were uploaded at (`uploaded_after`, `uploaded_before`) and their size
+/// (`min_size`, `max_size`).
+///
+/// Search ordered by `created_at` with the `limit` responds with the `next_cursor`,
+/// sending it with the next request continues right after the last file of the page.
+///
+/// When the request is sent with `Accept: application/x-ndjson` header, the results
+/// are streamed as newline delimited JSON while they are being loaded from the database,
+/// one [crate::data::app_file::AppFile] per line.
 #[route("/api/storage/search", method = "POST")]
 pub(crate) async fn search(
+    req: HttpRequest,
     claims: Claims,
     context: web::Data<Context>,
     data: web::Json<Search>,
 ) -> AppResult<HttpResponse> {
     let context = context.into_inner();
 
-    let data = data.into_inner();
+    let data = data.into_inner().into_value()?;
+
+    if accepts_ndjson(&req) {
+        let stream = search_stream(context, claims.sub, data).map(|batch| into_ndjson(batch?));
+
+        return Ok(HttpResponse::Ok()
+            .insert_header(("Content-Type", NDJSON))
+            .streaming(stream));
+    }
+
+    let repository = Repository::new(&context.db);
+    let tokens = repository.tokens(claims.sub);
+
+    let total = tokens.count(data.clone()).await?;
+    let files = tokens.search(data.clone()).await?;
+    let next_cursor = data.next_cursor(&files);
+
+    Ok(HttpResponse::Ok().json(SearchResponse {
+        files,
+        total,
+        next_cursor,
+    }))
+}
+
+/// Check if the client asked for the streamed response
+fn accepts_ndjson(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.contains(NDJSON))
+        .unwrap_or(false)
+}
+
+/// Serialize a batch of items into newline delimited JSON
+pub(crate) fn into_ndjson<T: Serialize>(items: Vec<T>) -> AppResult<web::Bytes> {
+    let mut bytes = vec![];
 
-    let file = Repository::new(&context.db)
-        .tokens(claims.sub)
-        .search(data)
-        .await?;
+    for item in items {
+        serde_json::to_writer(&mut bytes, &item)?;
+        bytes.push(b'\n');
+    }
 
-    Ok(HttpResponse::Ok().json(file))
+    Ok(web::Bytes::from(bytes))
 }
@@ -0,0 +1,209 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{
+    data::{
+        query::Query,
+        shares::{CreateGroupShare, CreateShare, FindRecipient, Recipient, SetSchedule},
+    },
+    repository::{cached, Repository},
+};
+
+/// Files and folders other users have shared with the user, the files
+/// inside of the shared folders are listed in them.
+///
+/// Response: [crate::data::response::Response]
+#[route("/api/shares", method = "GET")]
+pub(crate) async fn received(
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let query = Query {
+        is_owner: Some(false),
+        ..Default::default()
+    };
+
+    let response = cached::find(&context, claims.sub, query).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Find the user to share the file with, the client encrypts
+/// the keys of the shared files with their public key.
+///
+/// Query: [crate::data::shares::FindRecipient]
+///
+/// Response: [crate::data::shares::Recipient]
+#[route("/api/shares/recipient", method = "GET")]
+pub(crate) async fn recipient(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Query<FindRecipient>,
+) -> AppResult<HttpResponse> {
+    let email = data.into_inner().into_value()?;
+
+    let recipient = Repository::new(&context.db)
+        .shares(claims.sub)
+        .recipient(&email)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(recipient))
+}
+
+/// Other members of the group the user is in, the client encrypts
+/// the keys of the file shared with the group for each of them.
+///
+/// Response: list of [crate::data::shares::Recipient]
+#[route("/api/shares/groups/{group_id}", method = "GET")]
+pub(crate) async fn members(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let group_id: Uuid = util::actix::path_var(&req, "group_id")?;
+
+    let members = Repository::new(&context.db)
+        .shares(claims.sub)
+        .members(group_id)
+        .await?
+        .into_iter()
+        .map(Recipient::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(members))
+}
+
+/// List the users the file is shared with and when their access ends
+///
+/// Response: list of [crate::data::shares::Share]
+#[route("/api/storage/{file_id}/shares", method = "GET")]
+pub(crate) async fn index(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let shares = Repository::new(&context.db)
+        .shares(claims.sub)
+        .find(file_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(shares))
+}
+
+/// Share the file or the folder with the user, the owner can share it and the users it
+/// is shared with for managing, unless the sharing policy of the folders disables it.
+///
+/// Request: [crate::data::shares::CreateShare]
+///
+/// Response: [crate::data::shares::Share]
+#[route("/api/storage/{file_id}/shares", method = "POST")]
+pub(crate) async fn create(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateShare>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let (user_id, encrypted_key, keys, permission) = data.into_inner().into_value()?;
+    let connection = context.db.begin().await?;
+
+    let share = Repository::new(&connection)
+        .shares(claims.sub)
+        .create(file_id, user_id, encrypted_key, keys, permission)
+        .await?;
+
+    connection.commit().await?;
+
+    cached::invalidate(user_id, &[file_id]).await;
+
+    Ok(HttpResponse::Ok().json(share))
+}
+
+/// Share the file or the folder with every other member of the group
+///
+/// Request: [crate::data::shares::CreateGroupShare]
+///
+/// Response: list of [crate::data::shares::Share], without the members who already had it
+#[route("/api/storage/{file_id}/shares/groups", method = "POST")]
+pub(crate) async fn create_for_group(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateGroupShare>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let (group_id, members, permission) = data.into_inner().into_value()?;
+    let connection = context.db.begin().await?;
+
+    let shares = Repository::new(&connection)
+        .shares(claims.sub)
+        .create_for_group(file_id, group_id, members, permission)
+        .await?;
+
+    connection.commit().await?;
+
+    for share in shares.iter() {
+        cached::invalidate(share.user_id, &[file_id]).await;
+    }
+
+    Ok(HttpResponse::Ok().json(shares))
+}
+
+/// Set or remove the dates when the access of the user to the shared file starts and ends,
+/// the user is reminded a few days before the end and the share is removed after it.
+///
+/// Request: [crate::data::shares::SetSchedule]
+///
+/// Response: [crate::data::shares::Share]
+#[route("/api/storage/{file_id}/shares/{user_id}", method = "PUT")]
+pub(crate) async fn schedule(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<SetSchedule>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let user_id: Uuid = util::actix::path_var(&req, "user_id")?;
+    let (starts_at, expires_at) = data.into_inner().into_values()?;
+    let connection = context.db.begin().await?;
+
+    let share = Repository::new(&connection)
+        .shares(claims.sub)
+        .set_schedule(file_id, user_id, starts_at, expires_at)
+        .await?;
+
+    connection.commit().await?;
+
+    Ok(HttpResponse::Ok().json(share))
+}
+
+/// Revoke the access of the user to the shared file, the owner gets the
+/// re-wrap job for the keys of everyone who still has access to it.
+///
+/// Response: [entity::rewrap_jobs::Model]
+#[route("/api/storage/{file_id}/shares/{user_id}", method = "DELETE")]
+pub(crate) async fn revoke(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let user_id: Uuid = util::actix::path_var(&req, "user_id")?;
+    let connection = context.db.begin().await?;
+
+    let job = Repository::new(&connection)
+        .shares(claims.sub)
+        .revoke(file_id, user_id)
+        .await?;
+
+    connection.commit().await?;
+
+    cached::invalidate(user_id, &[file_id]).await;
+
+    Ok(HttpResponse::Ok().json(job))
+}
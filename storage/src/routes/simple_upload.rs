@@ -0,0 +1,67 @@
+use actix_multipart::Multipart;
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use error::{AppResult, Error};
+use futures::TryStreamExt;
+
+use crate::data::simple_upload::{SimpleUpload, MAX_SIMPLE_METADATA_BYTES};
+
+use super::{
+    bulk::{read_content, read_field},
+    create::create_file,
+    upload::{store_chunk, validate_checksum},
+};
+
+/// Create the small file and upload its content in a single request, the file is
+/// finished right away. Meant for the share sheets of the mobile apps, where creating
+/// the file and uploading its only chunk would take two round trips.
+///
+/// The client can send its own id for the file, sending the same request again
+/// returns the already uploaded file instead of creating a duplicate.
+///
+/// Request:
+///  - Content-Type: multipart/form-data
+///  - Parts: `file` with [crate::data::simple_upload::SimpleUpload] JSON, followed by
+///    the `content` part with the encrypted content of the file
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/simple-upload", method = "POST")]
+pub(crate) async fn simple_upload(
+    claims: Claims,
+    context: web::Data<Context>,
+    mut payload: Multipart,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+
+    let metadata = match payload.try_next().await? {
+        Some(field) if field.name() == "file" => {
+            read_field(field, MAX_SIMPLE_METADATA_BYTES).await?
+        }
+        _ => return Err(Error::as_validation("file", "required")),
+    };
+    let (file, meta) = serde_json::from_slice::<SimpleUpload>(&metadata)?.into_values()?;
+
+    let content = match payload.try_next().await? {
+        Some(mut field) if field.name() == "content" => read_content(&mut field).await?,
+        _ => return Err(Error::as_validation("content", "required")),
+    };
+
+    // Nothing is created for the content that didn't arrive as it was sent
+    validate_checksum(
+        meta.checksum.clone(),
+        meta.checksum_function.clone(),
+        &content,
+    )?;
+
+    let file = create_file(&context, &claims, file).await?;
+
+    // Repeated request for the file that was already uploaded
+    if file.finished_upload_at.is_some() {
+        return Ok(HttpResponse::Ok().json(file));
+    }
+
+    let file = store_chunk(&context, claims.sub, file.id, meta, &content).await?;
+
+    Ok(HttpResponse::Ok().json(file))
+}
@@ -0,0 +1,57 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{
+    authorize::{self, Access},
+    data::snapshot::{CompareSnapshot, Snapshot},
+    repository::Repository,
+};
+
+/// Get the integrity snapshot of the folder, the digest of the folder changes when
+/// anything inside of it changes, so the backup clients can check it before walking the tree.
+///
+/// Response: [crate::data::snapshot::Snapshot]
+#[route("/api/storage/{file_id}/snapshot", method = "GET")]
+pub(crate) async fn snapshot(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let snapshot = build(&context, &claims, file_id).await?;
+
+    Ok(HttpResponse::Ok().json(snapshot))
+}
+
+/// Compare the previous snapshot of the folder with the current one, the response
+/// has the folders that changed so only those have to be walked.
+///
+/// Request: [crate::data::snapshot::CompareSnapshot]
+///
+/// Response: [crate::data::snapshot::SnapshotDiff]
+#[route("/api/storage/{file_id}/snapshot/diff", method = "POST")]
+pub(crate) async fn diff(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CompareSnapshot>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let (digest, folders) = data.into_inner().into_value()?;
+    let snapshot = build(&context, &claims, file_id).await?;
+
+    Ok(HttpResponse::Ok().json(snapshot.diff(&digest, &folders)))
+}
+
+/// Snapshot of the folder the user owns
+async fn build(context: &Context, claims: &Claims, id: Uuid) -> AppResult<Snapshot> {
+    let repository = Repository::new(&context.db);
+    let dir = repository.by_id(id, claims.sub).await?;
+
+    authorize::directory(&context.db, claims, Access::Own, &dir).await?;
+
+    repository.manage(claims.sub).snapshot(dir.id).await
+}
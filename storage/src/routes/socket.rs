@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{hash_key, verify_handshake, Codec, Frame, Item, Message};
+use actix_web::{
+    http::header::{HeaderValue, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY},
+    route, web, HttpRequest, HttpResponse,
+};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::watchdog::Watchdog;
+use futures::{
+    channel::{mpsc, oneshot},
+    future, SinkExt, StreamExt,
+};
+
+use crate::{
+    data::{meta::Meta, socket::SocketAck, tus::max_upload_length},
+    repository::cached::get_file,
+    routes::upload::{store_chunk, validate_chunk_size},
+};
+
+/// Upload the chunks of the file over the WebSocket, the file itself is created with
+/// [crate::routes::create::create] the same as for the chunk upload.
+///
+/// Messages: see [crate::data::socket]
+///
+/// Response: `101 Switching Protocols`
+#[route("/api/storage/{file_id}/socket", method = "GET")]
+pub(crate) async fn socket(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let file = get_file(&context, claims.sub, file_id)
+        .await
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    // Apps limited to a folder can only upload the files they could create
+    claims.check_folder(file.file_id)?;
+
+    verify_handshake(req.head())
+        .map_err(|_| Error::BadRequest("invalid_websocket_handshake".to_string()))?;
+
+    // Key is there, the handshake was verified
+    let key = hash_key(req.headers().get(SEC_WEBSOCKET_KEY).unwrap().as_bytes());
+    let accept = HeaderValue::from_bytes(&key).map_err(|e| Error::InternalError(e.to_string()))?;
+
+    let (sender, receiver) = mpsc::channel(16);
+    let (connected, disconnected) = oneshot::channel::<()>();
+
+    // Response is dropped together with the connection, the session is dropped with it,
+    // so the chunk that is still being stored doesn't keep running for nobody.
+    let session = session(context, claims.sub, file.id, payload, sender);
+    actix_web::rt::spawn(future::select(Box::pin(session), disconnected));
+
+    let mut codec = Codec::new();
+    let messages = receiver.map(move |message| {
+        let _connected = &connected;
+        let mut bytes = web::BytesMut::new();
+        codec
+            .encode(message, &mut bytes)
+            .map_err(|e| Error::InternalError(e.to_string()))?;
+
+        Ok::<_, Error>(bytes.freeze())
+    });
+
+    Ok(HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        .insert_header((SEC_WEBSOCKET_ACCEPT, accept))
+        .streaming(messages))
+}
+
+/// Read the messages from the client until it closes the connection, each chunk is
+/// stored once its content is received and acknowledged before the next one is read.
+async fn session(
+    context: Arc<Context>,
+    owner_id: Uuid,
+    file_id: Uuid,
+    payload: web::Payload,
+    mut sender: mpsc::Sender<Message>,
+) {
+    let payload = payload.map(|bytes| bytes.map_err(|e| Error::BadRequest(e.to_string())));
+    let mut payload = Box::pin(Watchdog::new(payload, &context.config.server));
+    let mut codec = Codec::new().max_size(max_upload_length() as usize);
+    let mut buffer = web::BytesMut::new();
+
+    let mut meta: Option<Meta> = None;
+    let mut content: Vec<u8> = vec![];
+
+    loop {
+        let frame = match codec.decode(&mut buffer) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => match payload.next().await {
+                Some(Ok(bytes)) => {
+                    buffer.extend_from_slice(&bytes);
+                    continue;
+                }
+                _ => break,
+            },
+            Err(e) => {
+                log::debug!("Closing the upload socket of {}: {}", file_id, e);
+                break;
+            }
+        };
+
+        let complete = match frame {
+            Frame::Text(text) => {
+                match serde_json::from_slice::<Meta>(&text) {
+                    Ok(data) => meta = Some(data),
+                    Err(e) => {
+                        let error = Error::as_validation("meta", &e.to_string());
+                        let _ = send(&mut sender, SocketAck::failed(None, &error)).await;
+                    }
+                }
+
+                continue;
+            }
+            Frame::Binary(bytes) | Frame::Continuation(Item::Last(bytes)) => {
+                content.extend_from_slice(&bytes);
+                true
+            }
+            Frame::Continuation(Item::FirstBinary(bytes) | Item::Continue(bytes)) => {
+                content.extend_from_slice(&bytes);
+                false
+            }
+            Frame::Continuation(Item::FirstText(_)) => {
+                let error = Error::BadRequest("fragmented_text_not_supported".to_string());
+                let _ = send(&mut sender, SocketAck::failed(None, &error)).await;
+                break;
+            }
+            Frame::Ping(bytes) => {
+                let _ = sender.send(Message::Pong(bytes)).await;
+                continue;
+            }
+            Frame::Pong(_) => continue,
+            Frame::Close(reason) => {
+                let _ = sender.send(Message::Close(reason)).await;
+                break;
+            }
+        };
+
+        // Fragments of the chunk are collected up to the size the chunk can have
+        if let Err(error) = validate_chunk_size(content.len()) {
+            let chunk = meta.as_ref().and_then(|meta| meta.chunk);
+            let _ = send(&mut sender, SocketAck::failed(chunk, &error)).await;
+            break;
+        }
+
+        if !complete {
+            continue;
+        }
+
+        let data = std::mem::take(&mut content);
+
+        let ack = match meta.take() {
+            Some(meta) => {
+                let chunk = meta.chunk;
+
+                match store_chunk(&context, owner_id, file_id, meta, &data).await {
+                    Ok(file) => SocketAck::stored(chunk.unwrap_or_default(), file),
+                    Err(error) => SocketAck::failed(chunk, &error),
+                }
+            }
+            None => SocketAck::failed(None, &Error::BadRequest("chunk_meta_missing".to_string())),
+        };
+
+        if send(&mut sender, ack).await.is_err() {
+            break;
+        }
+    }
+
+    sender.close_channel();
+}
+
+async fn send(sender: &mut mpsc::Sender<Message>, ack: SocketAck) -> AppResult<()> {
+    let text = serde_json::to_string(&ack)?;
+
+    sender
+        .send(Message::Text(text.into()))
+        .await
+        .map_err(|e| Error::InternalError(e.to_string()))
+}
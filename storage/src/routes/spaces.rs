@@ -0,0 +1,68 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::AppResult;
+
+use crate::{
+    data::spaces::CreateSpace,
+    repository::{cached, Repository},
+};
+
+/// List the spaces of the groups the user is member of, together with the role
+/// of the user in them.
+///
+/// Response: list of [crate::data::spaces::SpaceResponse]
+#[route("/api/spaces", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let spaces = Repository::new(&context.db)
+        .spaces(claims.sub)
+        .find()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(spaces))
+}
+
+/// Turn the root folder of the user into the space of the group, the members
+/// of the group work on it according to their roles in the group.
+///
+/// Request: [crate::data::spaces::CreateSpace]
+///
+/// Response: [crate::data::spaces::SpaceResponse]
+#[route("/api/spaces", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateSpace>,
+) -> AppResult<HttpResponse> {
+    let (group_id, file_id) = data.into_inner().into_value()?;
+
+    let space = Repository::new(&context.db)
+        .spaces(claims.sub)
+        .create(group_id, file_id)
+        .await?;
+
+    // Access of every member of the group to everything in the folder changed
+    cached::clear().await;
+
+    Ok(HttpResponse::Ok().json(space))
+}
+
+/// Turn the space back into a regular folder of the user who created it
+#[route("/api/spaces/{id}", method = "DELETE")]
+pub(crate) async fn delete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let id: Uuid = util::actix::path_var(&req, "id")?;
+
+    Repository::new(&context.db)
+        .spaces(claims.sub)
+        .delete(id)
+        .await?;
+
+    cached::clear().await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -5,7 +5,11 @@ use error::AppResult;
 
 use crate::{data::stats::Response, repository::Repository};
 
-/// Get the users stats about the files and storage quota
+/// How many days of the transfers are in the stats
+const TRANSFER_DAYS: i64 = 30;
+
+/// Get the users stats about the files and storage quota, along with
+/// the uploads and downloads of the last 30 days and their daily limit
 ///
 /// Response: [crate::data::stats::Response]
 #[route("/api/storage/stats", method = "POST")]
@@ -14,10 +18,18 @@ pub(crate) async fn stats(claims: Claims, context: web::Data<Context>) -> AppRes
     let repository = Repository::new(&context.db);
     let stats = repository.query(claims.sub).stats().await?;
     let used_space = repository.query(claims.sub).used_space().await?;
+    let groups = repository.query(claims.sub).group_space().await?;
+    let transfers = repository
+        .transfers(claims.sub)
+        .recent(TRANSFER_DAYS)
+        .await?;
 
     Ok(HttpResponse::Ok().json(Response {
         stats,
         used_space,
         quota: claims.get_quota(&context).await,
+        groups,
+        transfers,
+        transfer_limit: context.settings.inner().await.users.transfer_bytes(),
     }))
 }
@@ -0,0 +1,130 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::{prelude::*, watchdog::Watchdog};
+use futures::StreamExt;
+
+use crate::{
+    authorize::{authorize, Access},
+    data::{
+        app_file::AppFile,
+        thumbnail::{Thumbnail, ThumbnailSize},
+    },
+    repository::cached::get_file,
+};
+
+/// Upload the encrypted thumbnail of the file, the one already uploaded is replaced
+///
+/// Request:
+///  - Query: size: `small` (default) or `medium`
+///  - Content-Type: application/octet-stream
+///  - Body: thumbnail encrypted with the file key, at most `STORAGE_THUMBNAIL_MAX_LENGTH` bytes
+///
+/// Response: 204 No Content
+#[route("/api/storage/{file_id}/thumbnail", method = "POST")]
+pub(crate) async fn upload(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let size = thumbnail_size(&req)?;
+
+    let file = thumbnail_file(&context, &claims, file_id).await?;
+
+    claims.check_folder(file.file_id)?;
+    authorize(&context.db, &claims, Access::Write, &file).await?;
+
+    let data = read_thumbnail(&context, payload).await?;
+
+    Fs::new(&context.config)
+        .push(
+            &Thumbnail::new(file.id, file.created_at, size),
+            Thumbnail::CHUNK,
+            &data,
+        )
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get the encrypted thumbnail of the file
+///
+/// Request:
+///  - Query: size: `small` (default) or `medium`
+///
+/// Response: [actix_web::web::Bytes]
+///  - Content-Type: application/octet-stream
+#[route("/api/storage/{file_id}/thumbnail", method = "GET")]
+pub(crate) async fn download(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let size = thumbnail_size(&req)?;
+
+    let file = thumbnail_file(&context, &claims, file_id).await?;
+    let thumbnail = Thumbnail::new(file.id, file.created_at, size);
+    let storage = Fs::new(&context.config);
+
+    if !storage.exists(&thumbnail, Thumbnail::CHUNK).await? {
+        return Err(Error::NotFound("thumbnail_not_found".to_string()));
+    }
+
+    let data = storage.pull(&thumbnail, Thumbnail::CHUNK).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .body(data))
+}
+
+/// Requested size of the thumbnail, the small one unless asked otherwise
+fn thumbnail_size(req: &HttpRequest) -> AppResult<ThumbnailSize> {
+    match util::actix::query_var::<String>(req, "size") {
+        Ok(size) => size.parse(),
+        Err(_) => Ok(ThumbnailSize::default()),
+    }
+}
+
+/// Only the stored files have the thumbnails, not the folders or the virtual files
+async fn thumbnail_file(context: &Context, claims: &Claims, file_id: Uuid) -> AppResult<AppFile> {
+    let file = get_file(context, claims.sub, file_id)
+        .await
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    if !file.is_file() || file.is_virtual() {
+        return Err(Error::BadRequest("file_has_no_thumbnail".to_string()));
+    }
+
+    Ok(file)
+}
+
+/// Read the thumbnail from the request payload, refusing it as soon as it gets too long
+async fn read_thumbnail(context: &Context, payload: web::Payload) -> AppResult<Vec<u8>> {
+    let max = context.config.server.thumbnail_max_length;
+    let payload = payload.map(|bytes| bytes.map_err(|e| Error::BadRequest(e.to_string())));
+    let mut payload = Box::pin(Watchdog::new(payload, &context.config.server));
+    let mut data = vec![];
+
+    while let Some(bytes) = payload.next().await {
+        let bytes = bytes?;
+
+        if data.len() + bytes.len() > max {
+            return Err(Error::as_validation("thumbnail", "thumbnail_too_long"));
+        }
+
+        data.extend_from_slice(&bytes);
+    }
+
+    if data.is_empty() {
+        return Err(Error::BadRequest("no_thumbnail_data_received".to_string()));
+    }
+
+    Ok(data)
+}
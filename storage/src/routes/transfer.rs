@@ -0,0 +1,113 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::{app_file::AppFile, transfer::Transfer},
+    repository::{cached, holds, Repository},
+};
+
+/// Move the file or the folder with everything inside of it into another folder
+///
+/// Request: [crate::data::transfer::Transfer]
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/{file_id}/move", method = "POST")]
+pub(crate) async fn move_file(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Transfer>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let file_id = data.into_inner().into_value()?;
+
+    holds::guard_move(&context.db, claims.sub, &[id], file_id).await?;
+
+    let connection = context.db.begin().await?;
+    let repository = Repository::new(&connection);
+    let previous = repository.by_id(id, claims.sub).await?.file_id;
+    let file = repository.manage(claims.sub).move_file(id, file_id).await?;
+    connection.commit().await?;
+
+    let changed = [Some(file.id), previous, file_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &changed.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
+
+/// Copy the file or the folder with everything inside of it into another folder,
+/// the chunks of the files are copied by the storage provider.
+///
+/// Request: [crate::data::transfer::Transfer]
+///
+/// Response: [crate::data::app_file::AppFile] copy of the file or the folder
+#[route("/api/storage/{file_id}/copy", method = "POST")]
+pub(crate) async fn copy(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<Transfer>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let file_id = data.into_inner().into_value()?;
+
+    let connection = context.db.begin().await?;
+    let repository = Repository::new(&connection);
+    let copies = repository.manage(claims.sub).copy(id, file_id).await?;
+
+    // Copies are already counted in the used space
+    repository
+        .query(claims.sub)
+        .check_quota(claims.get_quota(&context).await, 0)
+        .await?;
+
+    link_copies(&context, &copies).await?;
+
+    connection.commit().await?;
+
+    let (_, root) = copies
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+    let changed = [Some(root.id), root.file_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &changed.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(root))
+}
+
+/// Copy the chunks of the copied files with the storage provider, the copies
+/// made so far are all purged again when any of the files is incomplete or
+/// could not be linked.
+pub(crate) async fn link_copies(context: &Context, copies: &[(AppFile, AppFile)]) -> AppResult<()> {
+    let fs = Fs::new(&context.config);
+    let files = copies
+        .iter()
+        .filter(|(file, _)| file.is_file())
+        .collect::<Vec<_>>();
+
+    for (position, (file, copy)) in files.iter().enumerate() {
+        let error = match fs.link(file, copy).await {
+            Ok(linked) if Some(linked.len() as i64) == file.chunks => continue,
+            Ok(_) => Error::StorageError("source_incomplete".to_string()),
+            Err(e) => e,
+        };
+
+        // The copy that failed can have some of its chunks linked as well
+        for (_, copy) in files.iter().take(position + 1) {
+            if let Err(e) = fs.purge(copy).await {
+                log::error!("Failed purging copied file {}: {}", copy.id, e);
+            }
+        }
+
+        return Err(error);
+    }
+
+    Ok(())
+}
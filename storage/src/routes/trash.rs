@@ -0,0 +1,97 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::repository::{cached, holds, Repository};
+
+/// List the files and folders in the trash of the user, the newest first.
+/// Files deleted together with their folder are only restored with it.
+///
+/// Response: list of [crate::data::app_file::AppFile]
+#[route("/api/storage/trash", method = "GET")]
+pub(crate) async fn index(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let files = Repository::new(&context.db)
+        .trash(claims.sub)
+        .list()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(files))
+}
+
+/// Restore the file or the folder from the trash, it goes back to the folder
+/// it was deleted from, or to the root if the folder is no longer there.
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/trash/{file_id}/restore", method = "POST")]
+pub(crate) async fn restore(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let connection = context.db.begin().await?;
+    let file = Repository::new(&connection)
+        .trash(claims.sub)
+        .restore(file_id)
+        .await?;
+    connection.commit().await?;
+
+    let changed = [Some(file.id), file.file_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &changed.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
+
+/// Delete the file or the folder in the trash for good, chunks
+/// are purged in the background.
+#[route("/api/storage/trash/{file_id}", method = "DELETE")]
+pub(crate) async fn purge(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    holds::guard_delete(&context.db, claims.sub, &[file_id]).await?;
+
+    let connection = context.db.begin().await?;
+    let files = Repository::new(&connection)
+        .trash(claims.sub)
+        .purge(vec![file_id])
+        .await?;
+    connection.commit().await?;
+
+    let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+    cached::invalidate(claims.sub, &ids).await;
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Delete everything in the trash for good
+#[route("/api/storage/trash", method = "DELETE")]
+pub(crate) async fn empty(claims: Claims, context: web::Data<Context>) -> AppResult<HttpResponse> {
+    let ids = Repository::new(&context.db)
+        .trash(claims.sub)
+        .list()
+        .await?
+        .into_iter()
+        .map(|file| file.id)
+        .collect::<Vec<_>>();
+
+    holds::guard_delete(&context.db, claims.sub, &ids).await?;
+
+    let connection = context.db.begin().await?;
+    let files = Repository::new(&connection)
+        .trash(claims.sub)
+        .purge(ids)
+        .await?;
+    connection.commit().await?;
+
+    let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+    cached::invalidate(claims.sub, &ids).await;
+
+    Ok(HttpResponse::Accepted().finish())
+}
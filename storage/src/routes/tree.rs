@@ -0,0 +1,45 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use error::{AppResult, Error};
+
+use crate::{
+    data::tree::{Response, Tree},
+    repository::Repository,
+};
+
+/// Everything inside of the directory, or in the root, down to the given depth at once,
+/// so the clients can show the whole folder tree without listing every folder on its own.
+///
+/// Request: [crate::data::tree::Tree]
+///
+/// Response: [crate::data::tree::Response]
+#[route("/api/storage/tree", method = "GET")]
+pub(crate) async fn tree(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Query<Tree>,
+) -> AppResult<HttpResponse> {
+    let (root, depth) = data.into_inner().into_tuple()?;
+    let repository = Repository::new(&context.db);
+
+    let root = match root {
+        Some(root) => {
+            let root = repository.by_id(root, claims.sub).await?;
+
+            if !root.is_dir() {
+                return Err(Error::BadRequest("not_a_directory".to_string()));
+            }
+
+            Some(root)
+        }
+        None => None,
+    };
+
+    let files = repository
+        .manage(claims.sub)
+        .descendants(root.as_ref().map(|root| root.id), depth)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(Response { root, files }))
+}
@@ -0,0 +1,201 @@
+use actix_web::{route, web, HttpRequest, HttpResponse, HttpResponseBuilder};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{tus_uploads, Uuid};
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::{
+        meta::Meta,
+        tus::{
+            header, http_date, max_upload_length, CreateUpload, TusStaging, TUS_EXTENSIONS,
+            TUS_RESUMABLE,
+        },
+    },
+    repository::{cached::get_file, Repository},
+    routes::upload::{read_chunk, store_chunk},
+};
+
+/// Tell the tus clients what the server supports
+///
+/// Response:
+///  - Tus-Version: 1.0.0
+///  - Tus-Extension: creation,expiration
+///  - Tus-Max-Size: largest chunk that can be uploaded
+#[route("/api/storage/tus", method = "OPTIONS")]
+pub(crate) async fn options() -> AppResult<HttpResponse> {
+    Ok(HttpResponse::NoContent()
+        .insert_header(("Tus-Resumable", TUS_RESUMABLE))
+        .insert_header(("Tus-Version", TUS_RESUMABLE))
+        .insert_header(("Tus-Extension", TUS_EXTENSIONS))
+        .insert_header(("Tus-Max-Size", max_upload_length().to_string()))
+        .finish())
+}
+
+/// Create the upload for a single chunk of the file, the file itself is
+/// created with [crate::routes::create::create] the same as for the chunk upload.
+///
+/// Request:
+///  - Upload-Length: size of the chunk in bytes
+///  - Upload-Metadata: `file_id`, `chunk` and optionally `checksum` and `checksum_function`
+///
+/// Response: `201 Created`
+///  - Location: url the chunk is uploaded to
+///  - Upload-Expires: unfinished upload is removed after this time
+#[route("/api/storage/tus", method = "POST")]
+pub(crate) async fn create(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    tus_resumable(&req)?;
+
+    let data = CreateUpload::from_request(&req)?;
+
+    if let Some(file_id) = data.file_id {
+        check_folder(&context, &claims, file_id).await?;
+    }
+
+    let upload = Repository::new(&context.db)
+        .tus(claims.sub)
+        .create(data)
+        .await?;
+
+    Ok(HttpResponse::Created()
+        .insert_header(("Location", format!("/api/storage/tus/{}", upload.id)))
+        .insert_header(("Tus-Resumable", TUS_RESUMABLE))
+        .insert_header(("Upload-Expires", http_date(upload.expires_at)))
+        .finish())
+}
+
+/// How much of the chunk the server has received, the client continues from there
+///
+/// Response:
+///  - Upload-Offset: number of bytes received
+///  - Upload-Length: size of the chunk
+#[route("/api/storage/tus/{id}", method = "HEAD")]
+pub(crate) async fn head(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    tus_resumable(&req)?;
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let upload = Repository::new(&context.db).tus(claims.sub).get(id).await?;
+
+    Ok(progress(HttpResponse::Ok(), &upload)
+        .insert_header(("Upload-Length", upload.length.to_string()))
+        .insert_header(("Cache-Control", "no-store"))
+        .finish())
+}
+
+/// Append the bytes to the chunk at the `Upload-Offset`, once all of the chunk is received
+/// it is verified with the checksum from the metadata and stored as any other chunk.
+///
+/// Request:
+///  - Content-Type: application/offset+octet-stream
+///  - Upload-Offset: number of bytes the server has received
+///
+/// Response: `204 No Content`
+///  - Upload-Offset: number of bytes received
+///
+/// Offset that doesn't match the received bytes is refused with `409 Conflict`.
+#[route("/api/storage/tus/{id}", method = "PATCH")]
+pub(crate) async fn patch(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+    payload: web::Payload,
+) -> AppResult<HttpResponse> {
+    tus_resumable(&req)?;
+
+    if header(&req, "Content-Type") != Some("application/offset+octet-stream") {
+        return Err(Error::BadRequest("invalid_content_type".to_string()));
+    }
+
+    let id = util::actix::path_var::<Uuid>(&req, "id")?;
+    let offset = header(&req, "Upload-Offset")
+        .and_then(|offset| offset.parse::<i64>().ok())
+        .ok_or_else(|| Error::BadRequest("invalid_upload_offset".to_string()))?;
+
+    let repository = Repository::new(&context.db);
+    let tus = repository.tus(claims.sub);
+    let mut upload = tus.get(id).await?;
+
+    if offset != upload.offset {
+        return Err(Error::Conflict("upload_offset_mismatch".to_string()));
+    }
+
+    let buffer = read_chunk(&context.config.server, payload).await?;
+
+    if upload.offset + buffer.len() as i64 > upload.length {
+        return Err(Error::BadRequest("upload_length_exceeded".to_string()));
+    }
+
+    let storage = Fs::new(&context.config);
+    let staging = TusStaging::from(&upload);
+
+    // Bytes past the offset were written by the request that failed to move it
+    let mut data = match upload.offset {
+        0 => vec![],
+        _ => storage.pull(&staging, 0).await?,
+    };
+    data.truncate(upload.offset as usize);
+    data.extend_from_slice(&buffer);
+
+    if data.len() as i64 == upload.length {
+        // Chunk that doesn't pass the checks can't be finished anymore
+        let meta = Meta {
+            chunk: Some(upload.chunk),
+            checksum: upload.checksum.clone(),
+            checksum_function: upload.checksum_function.clone(),
+            key_hex: None,
+        };
+        let stored = store_chunk(&context, claims.sub, upload.file_id, meta, &data).await;
+
+        tus.remove(&upload).await?;
+        storage.purge(&staging).await?;
+        stored?;
+
+        upload.offset = upload.length;
+    } else {
+        storage.push(&staging, 0, &data).await?;
+        upload.offset = tus.advance(&upload, buffer.len() as i64).await?;
+    }
+
+    Ok(progress(HttpResponse::NoContent(), &upload).finish())
+}
+
+/// Headers every response about the existing upload has
+fn progress(mut response: HttpResponseBuilder, upload: &tus_uploads::Model) -> HttpResponseBuilder {
+    response
+        .insert_header(("Tus-Resumable", TUS_RESUMABLE))
+        .insert_header(("Upload-Offset", upload.offset.to_string()))
+        .insert_header(("Upload-Expires", http_date(upload.expires_at)));
+
+    response
+}
+
+/// Only the version of the protocol we speak is accepted
+fn tus_resumable(req: &HttpRequest) -> AppResult<()> {
+    if header(req, "Tus-Resumable") != Some(TUS_RESUMABLE) {
+        return Err(Error::BadRequest("unsupported_tus_version".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Apps limited to a folder can only upload the files they could create
+async fn check_folder(context: &Context, claims: &Claims, file_id: Uuid) -> AppResult<()> {
+    if claims.app.as_ref().and_then(|app| app.folder_id).is_some() {
+        let file = get_file(context, claims.sub, file_id)
+            .await
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        claims.check_folder(file.file_id)?;
+    }
+
+    Ok(())
+}
@@ -2,14 +2,26 @@ use std::str::FromStr;
 
 use actix_web::{route, web, HttpRequest, HttpResponse};
 use auth::data::claims::Claims;
+use config::server::ServerConfig;
 use context::Context;
+use cryptfns::scheme::Scheme;
 use entity::Uuid;
 use error::{AppResult, Error};
-use fs::{prelude::*, MAX_CHUNK_SIZE_BYTES};
+use fs::{
+    pool::{self, PooledBuffer},
+    prelude::*,
+    watchdog::Watchdog,
+    MAX_CHUNK_SIZE_BYTES,
+};
+use futures::StreamExt;
 
 use crate::{
     data::{app_file::AppFile, meta::Meta},
-    repository::{cached::get_file, Repository},
+    repository::{
+        cached::{self, get_file},
+        Repository,
+    },
+    routes::create::replace_previous,
 };
 
 /// Method to upload file chunks to the server
@@ -32,30 +44,77 @@ pub(crate) async fn upload(
     claims: Claims,
     context: web::Data<Context>,
     meta: web::Query<Meta>,
-    mut request_body: web::Bytes,
+    payload: web::Payload,
 ) -> AppResult<HttpResponse> {
-    if request_body.is_empty() {
-        return Err(Error::BadRequest("no_file_data_received".to_string()));
-    }
+    let buffer = read_chunk(&context.config.server, payload).await?;
 
     let context = context.into_inner();
     let file_id: String = util::actix::path_var(&req, "file_id")?;
     let file_id = Uuid::from_str(&file_id)?;
-    let (chunk, checksum, checksum_function, key_hex) = meta.into_inner().into_tuple()?;
 
-    validate_checksum(checksum, checksum_function, &request_body)?;
+    // Apps limited to a folder can only upload the files they could create
+    if claims.app.as_ref().and_then(|app| app.folder_id).is_some() {
+        let file = get_file(&context, claims.sub, file_id)
+            .await
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        claims.check_folder(file.file_id)?;
+    }
+
+    let file = store_chunk(&context, claims.sub, file_id, meta.into_inner(), &buffer).await?;
 
-    if let Some(key) = key_hex {
-        request_body = encrypt_request_body(&key, request_body)?;
+    Ok(HttpResponse::Ok().json(file))
+}
+
+/// Read the chunk from the request payload into a pooled buffer, so we are not allocating
+/// a new one for each of the uploaded chunks. Clients that send it too slowly are dropped.
+pub(crate) async fn read_chunk(
+    config: &ServerConfig,
+    payload: web::Payload,
+) -> AppResult<PooledBuffer<'static>> {
+    let mut buffer = pool::chunks().get();
+    let payload = payload.map(|bytes| bytes.map_err(|e| Error::BadRequest(e.to_string())));
+    let mut payload = Box::pin(Watchdog::new(payload, config));
+
+    while let Some(bytes) = payload.next().await {
+        let bytes = bytes?;
+        validate_chunk_size(buffer.len() + bytes.len())?;
+        buffer.extend_from_slice(&bytes);
+    }
+
+    if buffer.is_empty() {
+        return Err(Error::BadRequest("no_file_data_received".to_string()));
     }
 
+    Ok(buffer)
+}
+
+/// Store the chunk of the file owned by the given user and finish
+/// the file once all of its chunks are stored.
+pub(crate) async fn store_chunk(
+    context: &Context,
+    owner_id: Uuid,
+    file_id: Uuid,
+    meta: Meta,
+    buffer: &[u8],
+) -> AppResult<AppFile> {
+    let (chunk, checksum, checksum_function, key_hex) = meta.into_tuple()?;
+
+    validate_checksum(checksum, checksum_function, buffer)?;
+
     let storage = Fs::new(&context.config);
 
-    let mut file = get_file(&context, claims.sub, file_id)
+    let mut file = get_file(context, owner_id, file_id)
         .await
         .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
 
-    validate_chunk_size(&file, chunk, request_body.len())?;
+    let encrypted = match key_hex {
+        Some(key) => Some(encrypt_request_body(file.crypto_version, &key, buffer)?),
+        None => None,
+    };
+    let request_body = encrypted.as_deref().unwrap_or(buffer);
+
+    validate_chunk_size(request_body.len())?;
 
     let chunks = file
         .chunks
@@ -69,32 +128,35 @@ pub(crate) async fn upload(
         return Err(Error::as_validation("chunk", "chunk_already_exists"));
     }
 
-    storage.push(&file, chunk, &request_body).await?;
+    storage.push(&file, chunk, request_body).await?;
 
-    if file.is_file() {
-        let chunks = storage.get_uploaded_chunks(&file).await?;
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(owner_id);
 
-        file.chunks_stored = Some(chunks.len() as i64);
-        file.uploaded_chunks = Some(chunks);
-    }
+    // Chunks in the storage provider are the upload progress,
+    // there is no counter on the file row for parallel uploads to fight over.
+    let uploaded_chunks = storage.get_uploaded_chunks(&file).await?;
+
+    file.chunks_stored = Some(uploaded_chunks.len() as i64);
+    file.uploaded_chunks = Some(uploaded_chunks);
 
     if file.chunks == file.chunks_stored {
-        let mut finished_file = Repository::new(&context.db)
-            .manage(claims.sub)
-            .finish(&file)
-            .await?;
+        let mut finished_file = manage.finish(&file).await?;
+        replace_previous(context, owner_id, &finished_file).await?;
 
         finished_file.chunks_stored = file.chunks_stored;
         finished_file.uploaded_chunks = file.uploaded_chunks;
         file = finished_file;
+
+        cached::invalidate(owner_id, &[file.id]).await;
     }
 
-    Ok(HttpResponse::Ok().json(file))
+    Ok(file)
 }
 
 /// Run the checksum validation based on the given function
 /// and checksum from the request data.
-fn validate_checksum(
+pub(crate) fn validate_checksum(
     checksum: Option<String>,
     checksum_function: Option<String>,
     data: &[u8],
@@ -128,19 +190,20 @@ fn validate_checksum(
 ///
 /// This is less secure option that might be used in case uploader is
 /// uploading data from a toaster or something else less performant.
-fn encrypt_request_body(key: &str, request_body: web::Bytes) -> AppResult<web::Bytes> {
+///
+/// The data is encrypted with the scheme the file was created with.
+fn encrypt_request_body(crypto_version: i32, key: &str, request_body: &[u8]) -> AppResult<Vec<u8>> {
     let key = cryptfns::hex::decode(key)?;
-    let encrypted = cryptfns::aes::encrypt(key, request_body.to_vec())?;
 
-    Ok(web::Bytes::from(encrypted))
+    Scheme::from_version(crypto_version)?
+        .encrypt(key, request_body.to_vec())
+        .map_err(Error::from)
 }
 
 /// Validate the chunk size of the uploaded chunk.
-/// This is done by comparing the size of the chunk to the size of the file
-/// and the number of chunks the file should have.
-/// If the chunk size is not equal to the size of the file divided by the number of chunks
-/// then we know that the chunk is not the last chunk and we can validate the size.
-fn validate_chunk_size(_file: &AppFile, _chunk: i64, data_len: usize) -> AppResult<()> {
+/// The chunk can be at most [MAX_CHUNK_SIZE_BYTES] with a bit of room for the encryption overhead,
+/// it is checked while the chunk is still being received so we never read more than that.
+pub(crate) fn validate_chunk_size(data_len: usize) -> AppResult<()> {
     let max_size = MAX_CHUNK_SIZE_BYTES as f64 + (MAX_CHUNK_SIZE_BYTES as f64 * 0.01);
 
     if data_len as f64 > max_size {
@@ -0,0 +1,33 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+
+use crate::repository::{verdicts::verdicts, Repository};
+
+/// List the verdicts the scanners gave the file, so the owner can see why
+/// the file can't be downloaded. Verdicts are recorded through the admin routes.
+///
+/// Response: list of [entity::file_verdicts::Model]
+#[route("/api/storage/{file_id}/verdicts", method = "GET")]
+pub(crate) async fn index(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let file = Repository::new(&context.db)
+        .manage(claims.sub)
+        .file(file_id)
+        .await?;
+
+    if !file.is_owner {
+        return Err(Error::NotFound("file_not_found".to_string()));
+    }
+
+    let verdicts = verdicts(&context.db, file.id).await?;
+
+    Ok(HttpResponse::Ok().json(verdicts))
+}
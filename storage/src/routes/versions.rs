@@ -0,0 +1,85 @@
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{TransactionTrait, Uuid};
+use error::AppResult;
+
+use crate::{
+    jobs::queue_purge,
+    repository::{cached, holds, Repository},
+};
+
+/// List the previous versions of the file, the newest first
+///
+/// Response: list of [crate::data::app_file::AppFile]
+#[route("/api/storage/{file_id}/versions", method = "GET")]
+pub(crate) async fn index(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let versions = Repository::new(&context.db)
+        .versions(claims.sub)
+        .list(file_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(versions))
+}
+
+/// Restore the previous version of the file, it takes the place of the file
+/// in the folder and the file is kept as one of its versions.
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route(
+    "/api/storage/{file_id}/versions/{version_id}/restore",
+    method = "POST"
+)]
+pub(crate) async fn restore(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let version_id: Uuid = util::actix::path_var(&req, "version_id")?;
+
+    let connection = context.db.begin().await?;
+    let file = Repository::new(&connection)
+        .versions(claims.sub)
+        .restore(file_id, version_id)
+        .await?;
+    connection.commit().await?;
+
+    let changed = [Some(file_id), Some(file.id), file.file_id];
+    cached::invalidate(
+        claims.sub,
+        &changed.into_iter().flatten().collect::<Vec<_>>(),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
+
+/// Delete the previous version of the file, its chunks are purged in the background
+#[route("/api/storage/{file_id}/versions/{version_id}", method = "DELETE")]
+pub(crate) async fn delete(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+    let version_id: Uuid = util::actix::path_var(&req, "version_id")?;
+
+    holds::guard_delete(&context.db, claims.sub, &[file_id]).await?;
+
+    let connection = context.db.begin().await?;
+    let version = Repository::new(&connection)
+        .versions(claims.sub)
+        .delete(file_id, version_id)
+        .await?;
+    queue_purge(&connection, &[version]).await?;
+    connection.commit().await?;
+
+    Ok(HttpResponse::Accepted().finish())
+}
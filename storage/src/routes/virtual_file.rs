@@ -0,0 +1,97 @@
+use actix_web::{route, web, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::TransactionTrait;
+use error::{AppResult, Error};
+
+use crate::{
+    data::{limits, virtual_file::CreateVirtualFile},
+    repository::{cached, escrow, Repository},
+};
+
+/// Create a virtual file, bookmark or a note, in the folder of the user.
+///
+/// Virtual file has no chunks to upload, the encrypted payload is its whole content
+/// and it is returned with the file. Sending the same request with the client id
+/// again returns the already created file.
+///
+/// Request: [crate::data::virtual_file::CreateVirtualFile]
+///
+/// Response: [crate::data::app_file::AppFile]
+#[route("/api/storage/virtual", method = "POST")]
+pub(crate) async fn create(
+    claims: Claims,
+    context: web::Data<Context>,
+    data: web::Json<CreateVirtualFile>,
+) -> AppResult<HttpResponse> {
+    let data = data.into_inner();
+
+    limits::check_virtual_file(&context.config.server, &data)?;
+
+    let connection = context.db.begin().await?;
+    let client_id = data.id;
+    let escrow_key = data.escrow_key.clone();
+    let (create_file, encrypted_key, hashed_tokens, size, file_id) = data.into_active_model()?;
+
+    let repository = Repository::new(&connection);
+    let manage = repository.manage(claims.sub);
+
+    let name_hash = create_file
+        .name_hash
+        .clone()
+        .into_value()
+        .unwrap()
+        .unwrap::<String>();
+
+    if let Some(id) = client_id {
+        if let Some(file) = manage.reserved(id, &[name_hash.as_str()], file_id).await? {
+            return Ok(HttpResponse::Ok().json(file));
+        }
+    }
+
+    if manage.by_name(&name_hash, file_id).await.is_ok() {
+        return Err(Error::BadRequest("file_or_directory_exists".to_string()));
+    }
+
+    repository
+        .query(claims.sub)
+        .check_quota(claims.get_quota(&context).await, size)
+        .await?;
+
+    let file = match manage
+        .create(create_file, &encrypted_key, hashed_tokens)
+        .await
+    {
+        Ok(file) => file,
+        // Same request could have been repeated at the same time and the other one got there first
+        Err(e) => match client_id {
+            Some(id) => {
+                connection.rollback().await?;
+
+                let file = Repository::new(&context.db)
+                    .manage(claims.sub)
+                    .reserved_meanwhile(id, &[name_hash.as_str()], file_id, e)
+                    .await?;
+
+                return Ok(HttpResponse::Ok().json(file));
+            }
+            None => return Err(e),
+        },
+    };
+
+    let recovery_key = escrow::recovery_key(&context).await?;
+    escrow::escrow(
+        &connection,
+        recovery_key.as_ref(),
+        file.id,
+        escrow_key.as_deref(),
+    )
+    .await?;
+
+    connection.commit().await?;
+
+    let ids = [Some(file.id), file.file_id].into_iter().flatten();
+    cached::invalidate(claims.sub, &ids.collect::<Vec<_>>()).await;
+
+    Ok(HttpResponse::Ok().json(file))
+}
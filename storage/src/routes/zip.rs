@@ -0,0 +1,202 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use actix_web::{route, web, HttpRequest, HttpResponse};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::Uuid;
+use error::{AppResult, Error};
+use fs::{prelude::*, watchdog::Watchdog};
+use futures::StreamExt;
+use util::zip::Writer;
+
+use crate::{
+    archive,
+    authorize::{self, Access},
+    data::app_file::AppFile,
+    repository::{verdicts::guard_download, Repository},
+};
+
+/// Download the folder with everything inside of it as a single zip archive.
+///
+/// The archive is stored without compression and streamed as the chunks are read,
+/// the files are in it the same as they are downloaded one by one, still encrypted.
+/// Server doesn't know the names, so the folders are named by their ids and the files
+/// by the name they are downloaded under, the client maps them to the decrypted names.
+///
+/// Files that are not uploaded yet are left out. If any of the files is archived its
+/// restore is queued and the download answers with `202 Accepted` until it is back.
+/// Folder with any file the scanners gave the blocking verdict can't be downloaded.
+///
+/// Response: [actix_web::web::Bytes]
+///  - Content-Type: application/zip
+#[route("/api/storage/{file_id}/zip", method = "GET")]
+pub(crate) async fn zip(
+    req: HttpRequest,
+    claims: Claims,
+    context: web::Data<Context>,
+) -> AppResult<HttpResponse> {
+    let context = context.into_inner();
+    let file_id: Uuid = util::actix::path_var(&req, "file_id")?;
+
+    let repository = Repository::new(&context.db);
+    let dir = repository.by_id(file_id, claims.sub).await?;
+
+    authorize::directory(&context.db, &claims, Access::Own, &dir).await?;
+
+    let tree = repository.manage(claims.sub).tree(dir.id).await?;
+    let entries = entries(dir.id, &tree)?;
+
+    let mut restoring = None;
+
+    for entry in entries.iter().filter_map(|entry| entry.file.as_ref()) {
+        guard_download(&context.db, claims.sub, entry.id).await?;
+
+        if let Some(response) = archive::restoring(&context, entry.id, claims.sub).await? {
+            restoring = Some(response);
+        }
+    }
+
+    if let Some(restoring) = restoring {
+        return Ok(restoring);
+    }
+
+    let files = entries
+        .iter()
+        .filter_map(|entry| entry.file.clone())
+        .collect::<Vec<_>>();
+    let chunks = Fs::new(&context.config)
+        .stream_files(&files, context.config.server.read_ahead)
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/zip"))
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.zip\"", dir.id),
+        ))
+        .streaming(Watchdog::new(
+            assemble(entries, chunks),
+            &context.config.server,
+        )))
+}
+
+/// Entry of the archive, folders have no file
+pub(crate) struct ZipEntry {
+    pub(crate) path: String,
+    pub(crate) modified_at: i64,
+    pub(crate) file: Option<AppFile>,
+}
+
+/// Entries of the archive in the order they are written, every folder comes right
+/// before its content. The folder the archive is made of is not a part of the paths.
+pub(crate) fn entries(root: Uuid, tree: &[AppFile]) -> AppResult<Vec<ZipEntry>> {
+    let parents = tree
+        .iter()
+        .map(|file| (file.id, file.file_id))
+        .collect::<HashMap<_, _>>();
+
+    let mut entries = vec![];
+
+    for file in tree.iter().filter(|file| file.id != root) {
+        let mut path = match file.is_dir() {
+            true => format!("{}/", file.id),
+            false if file.is_file() && file.finished_upload_at.is_some() => {
+                file.filename()?.with_extension(".enc").to_string()
+            }
+            false => continue,
+        };
+
+        let mut parent = file.file_id;
+
+        while let Some(id) = parent.filter(|id| *id != root) {
+            path = format!("{}/{}", id, path);
+            parent = parents.get(&id).copied().flatten();
+        }
+
+        entries.push(ZipEntry {
+            path,
+            modified_at: file.file_modified_at,
+            file: file.is_file().then(|| file.clone()),
+        });
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(entries)
+}
+
+/// Stream of the archive, the chunks of the files are written as they come and the
+/// headers of the entries are written before the first chunk of the entry. Entries
+/// without any chunks are written before the next chunk or at the end of the archive.
+pub(crate) fn assemble(
+    entries: Vec<ZipEntry>,
+    chunks: FilesStream,
+) -> impl futures::Stream<Item = AppResult<web::Bytes>> {
+    // Position of the entry for each of the files, they are streamed in the same order
+    let positions = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.file.is_some())
+        .map(|(position, _)| position)
+        .collect::<Vec<_>>();
+
+    let archive = Rc::new(RefCell::new(Archive {
+        writer: Writer::new(),
+        entries,
+        next: 0,
+    }));
+    let end = archive.clone();
+
+    chunks
+        .map(move |chunk| {
+            let (index, data) = chunk?;
+            let mut archive = archive.borrow_mut();
+            let mut bytes = archive.start_until(positions[index])?;
+
+            archive.writer.write(&data);
+
+            match bytes.is_empty() {
+                true => Ok(data),
+                false => {
+                    bytes.extend_from_slice(&data);
+                    Ok(web::Bytes::from(bytes))
+                }
+            }
+        })
+        .chain(futures::stream::once(async move {
+            let mut archive = end.borrow_mut();
+            let last = archive.entries.len();
+            let mut bytes = archive.start_until(last)?;
+            bytes.extend(archive.writer.finish());
+
+            Ok(web::Bytes::from(bytes))
+        }))
+}
+
+struct Archive {
+    writer: Writer,
+    entries: Vec<ZipEntry>,
+    next: usize,
+}
+
+impl Archive {
+    /// Start the entries up to the given one, the next chunk belongs to it
+    fn start_until(&mut self, position: usize) -> AppResult<Vec<u8>> {
+        let mut bytes = vec![];
+
+        while self.next < self.entries.len() && self.next <= position {
+            let entry = &self.entries[self.next];
+            let header = self
+                .writer
+                .start(&entry.path, entry.modified_at)
+                .ok_or_else(|| {
+                    Error::InternalError(format!("invalid_archive_path:{}", entry.path))
+                })?;
+
+            bytes.extend(header);
+            self.next += 1;
+        }
+
+        Ok(bytes)
+    }
+}
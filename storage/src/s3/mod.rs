@@ -0,0 +1,447 @@
+//! # S3 gateway
+//!
+//! Minimal S3 compatible API under `/api/s3/`, so the tools like rclone and restic can
+//! use the instance as their S3 storage. The routes are in [crate::routes::s3].
+//! Only the path style requests are supported (`/api/s3/{bucket}/{key}`), the tools
+//! need the endpoint with the path (`https://hoodik.example.com/api/s3`) or a reverse proxy
+//! that maps the bare host onto it.
+//!
+//! Supported operations are ListObjectsV2 (and the older ListObjects), GetObject,
+//! HeadObject, PutObject and DeleteObject, along with CreateBucket, HeadBucket and
+//! GetBucketLocation the tools call before they upload.
+//! Presigned URLs, multipart uploads, chunked payload signatures (`STREAMING-*`) and
+//! copying of the objects are not.
+//!
+//! Requests are signed with the AWS signature version 4 and the access key of the user,
+//! see [crate::routes::access_keys]. Buckets are the folders in the root of the drive,
+//! found by their names, and every object is a file in its bucket named by the whole
+//! key. Each access key sees only the objects it has uploaded itself.
+//!
+//! The S3 tools send the content as it is and expect to get it back the same way, so the
+//! objects are encrypted here, like the imports do it, and the key of each file is kept
+//! encrypted with the secret of the access key. The server can therefore read the objects
+//! for as long as the access key exists, tools that should keep the content private have
+//! to encrypt it themselves (restic does, rclone with the crypt remote).
+pub(crate) mod signature;
+pub(crate) mod xml;
+
+use actix_web::{web::Bytes, HttpRequest};
+use context::Context;
+use cryptfns::scheme::Scheme;
+use entity::{
+    access_keys, files, s3_objects, users, ColumnTrait, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait, Uuid,
+};
+use error::{AppResult, Error};
+use fs::{prelude::*, MAX_CHUNK_SIZE_BYTES};
+use futures::Stream;
+use percent_encoding::percent_decode_str;
+
+use crate::{
+    data::{app_file::AppFile, s3::ListObjects},
+    dav,
+    import::Sealer,
+    repository::{access_keys as keys, cached, holds, Repository},
+};
+
+use self::signature::{Authorization, Verified};
+
+/// Where the S3 paths start
+pub(crate) const PREFIX: &str = "/api/s3";
+
+/// How many objects are loaded from the database at once when listing
+const LIST_BATCH_SIZE: u64 = 1000;
+
+/// Bucket and the key of the object from the path of the request, the key is
+/// missing for the requests to the bucket itself
+pub(crate) fn location(path: &str) -> AppResult<(String, Option<String>)> {
+    let invalid = || Error::BadRequest("invalid_s3_path".to_string());
+    let decode = |value: &str| {
+        percent_decode_str(value)
+            .decode_utf8()
+            .map(|value| value.to_string())
+            .map_err(|_| invalid())
+    };
+
+    let path = path
+        .strip_prefix(PREFIX)
+        .and_then(|path| path.strip_prefix('/'))
+        .ok_or_else(invalid)?;
+
+    let (bucket, key) = match path.split_once('/') {
+        Some((bucket, key)) if !key.is_empty() => (decode(bucket)?, Some(decode(key)?)),
+        Some((bucket, _)) => (decode(bucket)?, None),
+        None => (decode(path)?, None),
+    };
+
+    if bucket.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok((bucket, key))
+}
+
+/// Page of the listing of the objects
+#[derive(Debug, Default)]
+pub(crate) struct Listing {
+    pub(crate) objects: Vec<(s3_objects::Model, files::Model)>,
+    /// Keys that were rolled up into the common prefixes by the delimiter
+    pub(crate) prefixes: Vec<String>,
+    pub(crate) truncated: bool,
+    /// Last key or prefix of the page, the next one starts after it
+    pub(crate) next: Option<String>,
+}
+
+/// Requests of the S3 tools signed with the access key of the user
+pub(crate) struct Gateway<'ctx> {
+    context: &'ctx Context,
+    access_key: access_keys::Model,
+    user: users::Model,
+}
+
+impl<'ctx> Gateway<'ctx> {
+    pub(crate) fn new(
+        context: &'ctx Context,
+        access_key: access_keys::Model,
+        user: users::Model,
+    ) -> Self {
+        Self {
+            context,
+            access_key,
+            user,
+        }
+    }
+
+    /// Find the access key the request was signed with and verify the signature,
+    /// returns the gateway along with the signed SHA-256 of the payload, if any.
+    pub(crate) async fn authenticate(
+        context: &'ctx Context,
+        req: &HttpRequest,
+    ) -> AppResult<(Self, Option<String>)> {
+        let authorization = req
+            .headers()
+            .get("Authorization")
+            .and_then(|header| header.to_str().ok())
+            .ok_or_else(|| Error::Unauthorized("access_denied".to_string()))?;
+        let authorization = Authorization::parse(authorization)?;
+
+        let access_key = keys::by_access_key_id(&context.db, &authorization.access_key_id)
+            .await?
+            .ok_or_else(|| Error::Forbidden("invalid_access_key_id".to_string()))?;
+
+        let payload = authorization.verify(req, &access_key.secret_access_key)?;
+
+        let user = users::Entity::find_by_id(access_key.user_id)
+            .one(&context.db)
+            .await?
+            .ok_or_else(|| Error::Forbidden("invalid_access_key_id".to_string()))?;
+
+        keys::touch(&context.db, access_key.id).await?;
+
+        Ok((Self::new(context, access_key, user), payload))
+    }
+
+    /// Folder in the root of the drive with the name of the bucket
+    pub(crate) async fn bucket(&self, name: &str) -> AppResult<AppFile> {
+        let not_found = || Error::NotFound("bucket_not_found".to_string());
+
+        let bucket = Repository::new(&self.context.db)
+            .manage(self.user.id)
+            .by_name(dav::name_hash(name), None)
+            .await
+            .map_err(|e| match e.is_not_found() {
+                true => not_found(),
+                false => e,
+            })?;
+
+        match bucket.is_dir() {
+            true => Ok(bucket),
+            false => Err(not_found()),
+        }
+    }
+
+    /// Create the folder for the bucket in the root of the drive, the bucket
+    /// that already exists is simply returned
+    pub(crate) async fn create_bucket(&self, name: &str) -> AppResult<AppFile> {
+        match self.bucket(name).await {
+            Err(e) if e.is_not_found() => {}
+            bucket => return bucket,
+        }
+
+        let sealer = Sealer::new(self.context, self.user.clone()).await?;
+        let bucket = sealer.folder(&sealer.seal(name)?, None).await?;
+        cached::invalidate(self.user.id, &[bucket.id]).await;
+
+        Ok(bucket)
+    }
+
+    /// Objects in the bucket ordered by their keys, keys with the delimiter after
+    /// the prefix are rolled up into the common prefixes the way S3 does it
+    pub(crate) async fn list(&self, bucket: &AppFile, query: &ListObjects) -> AppResult<Listing> {
+        let prefix = query.prefix.clone().unwrap_or_default();
+        let delimiter = query.delimiter.clone().filter(|d| !d.is_empty());
+        let max_keys = query.max_keys.unwrap_or(crate::data::s3::MAX_KEYS);
+        let after = query.after();
+
+        let mut listing = Listing::default();
+        let mut cursor = after.clone();
+        let mut count = 0;
+
+        loop {
+            let mut select = s3_objects::Entity::find()
+                .find_also_related(files::Entity)
+                .filter(s3_objects::Column::AccessKeyId.eq(self.access_key.id))
+                .filter(s3_objects::Column::BucketId.eq(bucket.id))
+                .filter(files::Column::DeletedAt.is_null())
+                .filter(files::Column::FinishedUploadAt.is_not_null())
+                .order_by_asc(s3_objects::Column::Key)
+                .limit(LIST_BATCH_SIZE);
+
+            if !prefix.is_empty() {
+                select = select.filter(s3_objects::Column::Key.starts_with(&prefix));
+            }
+
+            if let Some(cursor) = cursor.as_ref() {
+                select = select.filter(s3_objects::Column::Key.gt(cursor.as_str()));
+            }
+
+            let batch = select.all(&self.context.db).await?;
+            let loaded = batch.len() as u64;
+
+            for (object, file) in batch {
+                cursor = Some(object.key.clone());
+
+                let file = match file {
+                    Some(file) if object.key.starts_with(&prefix) => file,
+                    _ => continue,
+                };
+
+                let common = delimiter.as_deref().and_then(|delimiter| {
+                    object.key[prefix.len()..]
+                        .find(delimiter)
+                        .map(|end| object.key[..prefix.len() + end + delimiter.len()].to_string())
+                });
+
+                if let Some(common) = common.as_ref() {
+                    let listed = listing.prefixes.last() == Some(common)
+                        || matches!(after.as_ref(), Some(after) if common <= after);
+
+                    if listed {
+                        continue;
+                    }
+                }
+
+                if count == max_keys {
+                    listing.truncated = true;
+                    return Ok(listing);
+                }
+
+                count += 1;
+
+                match common {
+                    Some(common) => {
+                        listing.next = Some(common.clone());
+                        listing.prefixes.push(common);
+                    }
+                    None => {
+                        listing.next = Some(object.key.clone());
+                        listing.objects.push((object, file));
+                    }
+                }
+            }
+
+            if loaded < LIST_BATCH_SIZE {
+                return Ok(listing);
+            }
+        }
+    }
+
+    /// Object with the key in the bucket, along with its file
+    pub(crate) async fn object(
+        &self,
+        bucket: &AppFile,
+        key: &str,
+    ) -> AppResult<(s3_objects::Model, AppFile)> {
+        let not_found = || Error::NotFound("object_not_found".to_string());
+
+        let object = s3_objects::Entity::find()
+            .filter(s3_objects::Column::AccessKeyId.eq(self.access_key.id))
+            .filter(s3_objects::Column::BucketId.eq(bucket.id))
+            .filter(s3_objects::Column::Key.eq(key))
+            .one(&self.context.db)
+            .await?
+            .ok_or_else(not_found)?;
+
+        let file = Repository::new(&self.context.db)
+            .by_id(object.file_id, self.user.id)
+            .await
+            .map_err(|_| not_found())?;
+
+        match file.finished_upload_at.is_some() {
+            true => Ok((object, file)),
+            false => Err(not_found()),
+        }
+    }
+
+    /// Upload the object, the existing object with the same key is moved to the trash
+    /// after the new one is stored. Files in the bucket that were not uploaded as the
+    /// objects of this access key are never replaced.
+    pub(crate) async fn put<S>(
+        &self,
+        bucket: &AppFile,
+        key: &str,
+        mime: String,
+        size: i64,
+        body: S,
+        payload: Option<String>,
+    ) -> AppResult<AppFile>
+    where
+        S: Stream<Item = AppResult<Bytes>> + Unpin,
+    {
+        let replaced = match self.object(bucket, key).await {
+            Ok((_, file)) => Some(file),
+            Err(e) if e.is_not_found() => None,
+            Err(e) => return Err(e),
+        };
+
+        let sealer = Sealer::new(self.context, self.user.clone()).await?;
+        let sealed = sealer.seal(key)?;
+
+        match Repository::new(&self.context.db)
+            .manage(self.user.id)
+            .by_name(&sealed.name_hash, Some(bucket.id))
+            .await
+        {
+            Ok(existing) if Some(existing.id) != replaced.as_ref().map(|file| file.id) => {
+                return Err(Error::Conflict("file_already_exists".to_string()))
+            }
+            Err(e) if !e.is_not_found() => return Err(e),
+            _ => {}
+        }
+
+        if let Some(replaced) = replaced.as_ref() {
+            holds::guard_delete(&self.context.db, self.user.id, &[replaced.id]).await?;
+        }
+
+        let body = Verified::new(body, payload);
+        let file = sealer
+            .file(&sealed, Some(bucket.id), mime, size, None, body)
+            .await?;
+
+        let encrypted_key = cryptfns::aes::encrypt(self.wrapping_key(file.id)?, sealed.key)?;
+
+        let connection = self.context.db.begin().await?;
+        s3_objects::Entity::delete_many()
+            .filter(s3_objects::Column::AccessKeyId.eq(self.access_key.id))
+            .filter(s3_objects::Column::BucketId.eq(bucket.id))
+            .filter(s3_objects::Column::Key.eq(key))
+            .exec(&connection)
+            .await?;
+        s3_objects::Entity::insert(s3_objects::ActiveModel::from(s3_objects::Model {
+            file_id: file.id,
+            access_key_id: self.access_key.id,
+            bucket_id: bucket.id,
+            key: key.to_string(),
+            encrypted_key: cryptfns::hex::encode(encrypted_key),
+        }))
+        .exec_without_returning(&connection)
+        .await?;
+        connection.commit().await?;
+
+        let mut changed = vec![file.id, bucket.id];
+
+        if let Some(replaced) = replaced {
+            changed.extend(self.trash(replaced.id).await?);
+        }
+
+        cached::invalidate(self.user.id, &changed).await;
+
+        Ok(file)
+    }
+
+    /// Move the object to the trash, its file can still be restored from there
+    /// but it is not an object anymore. Deleting the missing object is not an error.
+    pub(crate) async fn delete(&self, bucket: &AppFile, key: &str) -> AppResult<()> {
+        let (object, file) = match self.object(bucket, key).await {
+            Ok(found) => found,
+            Err(e) if e.is_not_found() => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        holds::guard_delete(&self.context.db, self.user.id, &[file.id]).await?;
+
+        s3_objects::Entity::delete_by_id(object.file_id)
+            .exec(&self.context.db)
+            .await?;
+
+        let mut changed = self.trash(file.id).await?;
+        changed.push(bucket.id);
+        cached::invalidate(self.user.id, &changed).await;
+
+        Ok(())
+    }
+
+    /// Decrypted content of the object from the `start` to the `end` byte (inclusive),
+    /// only the chunks the range spans are read
+    pub(crate) fn content(
+        &self,
+        file: AppFile,
+        object: &s3_objects::Model,
+        start: u64,
+        end: u64,
+    ) -> AppResult<impl Stream<Item = AppResult<Bytes>> + 'static> {
+        let key = cryptfns::hex::decode(&object.encrypted_key)?;
+        let key = cryptfns::aes::decrypt(self.wrapping_key(file.id)?, key)?;
+        let scheme = Scheme::from_version(file.crypto_version)?;
+        let config = self.context.config.clone();
+
+        let last = end / MAX_CHUNK_SIZE_BYTES;
+
+        Ok(futures::stream::try_unfold(
+            start / MAX_CHUNK_SIZE_BYTES,
+            move |chunk| {
+                let (config, file, key) = (config.clone(), file.clone(), key.clone());
+
+                async move {
+                    if chunk > last {
+                        return Ok(None);
+                    }
+
+                    let encrypted = Fs::new(&config).pull(&file, chunk as i64).await?;
+                    let plaintext = scheme.decrypt(key, encrypted)?;
+
+                    let offset = chunk * MAX_CHUNK_SIZE_BYTES;
+                    let to = ((end + 1 - offset) as usize).min(plaintext.len());
+                    let from = (start.saturating_sub(offset) as usize).min(to);
+
+                    Ok(Some((
+                        Bytes::copy_from_slice(&plaintext[from..to]),
+                        chunk + 1,
+                    )))
+                }
+            },
+        ))
+    }
+
+    /// Key the key of the file is encrypted with, made from the secret of the access key
+    /// and the id of the file, so every object has its own
+    fn wrapping_key(&self, file_id: Uuid) -> AppResult<Vec<u8>> {
+        let seed = format!("{}:{}", self.access_key.secret_access_key, file_id);
+
+        Ok(cryptfns::hex::decode(cryptfns::sha256::digest(
+            seed.as_str(),
+        ))?)
+    }
+
+    /// Move the file to the trash, returns the ids of everything that was moved there
+    async fn trash(&self, id: Uuid) -> AppResult<Vec<Uuid>> {
+        let connection = self.context.db.begin().await?;
+        let files = Repository::new(&connection)
+            .trash(self.user.id)
+            .delete(vec![id], self.context.config.server.trash_retention)
+            .await?;
+        connection.commit().await?;
+
+        Ok(files.iter().map(|file| file.id).collect())
+    }
+}
@@ -0,0 +1,210 @@
+//! Verification of the AWS signature version 4 the S3 tools sign the requests with,
+//! see [fs::s3::Signer] that signs them the same way.
+use std::{
+    collections::BTreeMap,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use actix_web::{web::Bytes, HttpRequest};
+use chrono::{NaiveDateTime, Utc};
+use error::{AppResult, Error};
+use fs::s3::{Signer, UNSIGNED_PAYLOAD};
+use futures::Stream;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+/// How far the time of the request can be from the time on the server
+const MAX_SKEW_SECONDS: i64 = 15 * 60;
+
+/// Headers that have to be covered by the signature, without them the request
+/// could be replayed against another host, at another time or with another body
+const REQUIRED_SIGNED_HEADERS: [&str; 3] = ["host", "x-amz-content-sha256", "x-amz-date"];
+
+/// Parsed `Authorization` header of the request
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Authorization {
+    pub(crate) access_key_id: String,
+    pub(crate) date: String,
+    pub(crate) region: String,
+    pub(crate) signed_headers: Vec<String>,
+    pub(crate) signature: String,
+}
+
+impl Authorization {
+    /// Parse the `AWS4-HMAC-SHA256 Credential=.., SignedHeaders=.., Signature=..` header
+    pub(crate) fn parse(header: &str) -> AppResult<Self> {
+        let invalid = || Error::Unauthorized("invalid_authorization".to_string());
+
+        let parameters = header
+            .strip_prefix("AWS4-HMAC-SHA256")
+            .ok_or_else(invalid)?;
+
+        let mut credential = None;
+        let mut signed_headers = None;
+        let mut signature = None;
+
+        for parameter in parameters.split(',') {
+            match parameter.trim().split_once('=') {
+                Some(("Credential", value)) => credential = Some(value),
+                Some(("SignedHeaders", value)) => signed_headers = Some(value),
+                Some(("Signature", value)) => signature = Some(value),
+                _ => {}
+            }
+        }
+
+        let credential = credential
+            .ok_or_else(invalid)?
+            .split('/')
+            .collect::<Vec<_>>();
+
+        match credential.as_slice() {
+            [access_key_id, date, region, "s3", "aws4_request"] => Ok(Self {
+                access_key_id: access_key_id.to_string(),
+                date: date.to_string(),
+                region: region.to_string(),
+                signed_headers: signed_headers
+                    .ok_or_else(invalid)?
+                    .split(';')
+                    .map(|name| name.to_string())
+                    .collect(),
+                signature: signature.ok_or_else(invalid)?.to_string(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Check the request was signed with the secret, returns the SHA-256 of the
+    /// payload when it was signed, the body has to be checked against it.
+    ///
+    /// The host, the date and the payload hash must all be among the signed headers.
+    pub(crate) fn verify(&self, req: &HttpRequest, secret: &str) -> AppResult<Option<String>> {
+        let unsigned = REQUIRED_SIGNED_HEADERS.iter().any(|required| {
+            !self
+                .signed_headers
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(required))
+        });
+
+        if unsigned {
+            return Err(Error::Forbidden("required_header_not_signed".to_string()));
+        }
+
+        let amz_date = header(req, "x-amz-date")
+            .ok_or_else(|| Error::BadRequest("x_amz_date_required".to_string()))?;
+
+        let time = NaiveDateTime::parse_from_str(&amz_date, "%Y%m%dT%H%M%SZ")
+            .map_err(|_| Error::BadRequest("x_amz_date_required".to_string()))?;
+
+        if !amz_date.starts_with(&self.date)
+            || (Utc::now().naive_utc() - time).num_seconds().abs() > MAX_SKEW_SECONDS
+        {
+            return Err(Error::Forbidden("request_time_too_skewed".to_string()));
+        }
+
+        let payload = header(req, "x-amz-content-sha256")
+            .ok_or_else(|| Error::BadRequest("x_amz_content_sha256_required".to_string()))?;
+
+        if payload.starts_with("STREAMING-") {
+            return Err(Error::BadRequest(
+                "streaming_payload_not_supported".to_string(),
+            ));
+        }
+
+        let mut headers = BTreeMap::new();
+
+        for name in self.signed_headers.iter() {
+            let value = header(req, name).unwrap_or_default();
+            headers.insert(name.to_lowercase(), value);
+        }
+
+        let url = req
+            .uri()
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/");
+        let url = Url::parse(&format!("http://localhost{}", url))
+            .map_err(|_| Error::BadRequest("invalid_url".to_string()))?;
+
+        let signer = Signer {
+            access_key_id: self.access_key_id.clone(),
+            secret_access_key: secret.to_string(),
+            region: self.region.clone(),
+        };
+
+        let expected = signer.authorization(req.method().as_str(), &url, &headers);
+        let expected = expected
+            .rsplit_once("Signature=")
+            .map(|(_, signature)| signature)
+            .unwrap_or_default();
+
+        if !constant_time_eq(expected.as_bytes(), self.signature.as_bytes()) {
+            return Err(Error::Forbidden("signature_does_not_match".to_string()));
+        }
+
+        Ok(Some(payload).filter(|payload| payload != UNSIGNED_PAYLOAD))
+    }
+}
+
+/// Body of the request that fails at its end when its SHA-256 isn't the signed one
+pub(crate) struct Verified<S> {
+    body: S,
+    hasher: Option<Sha256>,
+    expected: Option<String>,
+}
+
+impl<S> Verified<S> {
+    pub(crate) fn new(body: S, expected: Option<String>) -> Self {
+        Self {
+            body,
+            hasher: Some(Sha256::new()),
+            expected,
+        }
+    }
+}
+
+impl<S> Stream for Verified<S>
+where
+    S: Stream<Item = AppResult<Bytes>> + Unpin,
+{
+    type Item = AppResult<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let poll = Pin::new(&mut self.body).poll_next(cx);
+
+        match poll {
+            Poll::Ready(Some(Ok(bytes))) => {
+                if let Some(hasher) = self.hasher.as_mut() {
+                    hasher.update(&bytes);
+                }
+
+                Poll::Ready(Some(Ok(bytes)))
+            }
+            Poll::Ready(None) => {
+                let digest = self.hasher.take().map(|hasher| hasher.finalize());
+
+                match (digest, self.expected.as_deref()) {
+                    (Some(digest), Some(expected))
+                        if !cryptfns::hex::encode(digest).eq_ignore_ascii_case(expected) =>
+                    {
+                        Poll::Ready(Some(Err(Error::BadRequest("bad_digest".to_string()))))
+                    }
+                    _ => Poll::Ready(None),
+                }
+            }
+            poll => poll,
+        }
+    }
+}
+
+fn header(req: &HttpRequest, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// Compare the signatures without leaking how much of them matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
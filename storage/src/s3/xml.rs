@@ -0,0 +1,193 @@
+//! XML bodies of the S3 gateway responses, the listings and the errors
+use actix_web::{http::StatusCode, HttpResponse};
+use chrono::NaiveDateTime;
+use error::{AppResult, Error};
+use quick_xml::escape::escape;
+
+use crate::data::s3::ListObjects;
+
+use super::Listing;
+
+const DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const NAMESPACE: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+/// `ListBucketResult` of the ListObjectsV2, or of the ListObjects when
+/// the `list-type=2` wasn't asked for
+pub(crate) fn list_bucket_result(bucket: &str, query: &ListObjects, listing: &Listing) -> String {
+    let url = query.encoding_type.as_deref() == Some("url");
+    let encode = |key: &str| match url {
+        true => fs::s3::encode_path(key),
+        false => escape(key).to_string(),
+    };
+
+    let mut xml = format!(
+        r#"{}<ListBucketResult xmlns="{}"><Name>{}</Name><Prefix>{}</Prefix>"#,
+        DECLARATION,
+        NAMESPACE,
+        escape(bucket),
+        encode(query.prefix.as_deref().unwrap_or_default())
+    );
+
+    if let Some(delimiter) = query.delimiter.as_deref() {
+        xml.push_str(&format!("<Delimiter>{}</Delimiter>", encode(delimiter)));
+    }
+
+    xml.push_str(&format!(
+        "<MaxKeys>{}</MaxKeys><IsTruncated>{}</IsTruncated>",
+        query.max_keys.unwrap_or(crate::data::s3::MAX_KEYS),
+        listing.truncated
+    ));
+
+    if let Some(encoding_type) = query.encoding_type.as_deref() {
+        xml.push_str(&format!(
+            "<EncodingType>{}</EncodingType>",
+            escape(encoding_type)
+        ));
+    }
+
+    let next = listing.next.as_deref().filter(|_| listing.truncated);
+
+    match query.is_v2() {
+        true => {
+            xml.push_str(&format!(
+                "<KeyCount>{}</KeyCount>",
+                listing.objects.len() + listing.prefixes.len()
+            ));
+
+            if let Some(token) = query.continuation_token.as_deref() {
+                xml.push_str(&format!(
+                    "<ContinuationToken>{}</ContinuationToken>",
+                    escape(token)
+                ));
+            }
+
+            if let Some(start_after) = query.start_after.as_deref() {
+                xml.push_str(&format!("<StartAfter>{}</StartAfter>", encode(start_after)));
+            }
+
+            if let Some(next) = next {
+                xml.push_str(&format!(
+                    "<NextContinuationToken>{}</NextContinuationToken>",
+                    cryptfns::base64::encode(next)
+                ));
+            }
+        }
+        false => {
+            xml.push_str(&format!(
+                "<Marker>{}</Marker>",
+                encode(query.marker.as_deref().unwrap_or_default())
+            ));
+
+            if let Some(next) = next {
+                xml.push_str(&format!("<NextMarker>{}</NextMarker>", encode(next)));
+            }
+        }
+    }
+
+    for (object, file) in listing.objects.iter() {
+        xml.push_str(&format!(
+            "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+            encode(&object.key),
+            last_modified(file.created_at),
+            escape(&etag(file.id)),
+            file.size.unwrap_or(0)
+        ));
+    }
+
+    for prefix in listing.prefixes.iter() {
+        xml.push_str(&format!(
+            "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+            encode(prefix)
+        ));
+    }
+
+    xml.push_str("</ListBucketResult>");
+
+    xml
+}
+
+/// Answer of the GetBucketLocation, the buckets are not in any region
+pub(crate) fn location_constraint() -> String {
+    format!(
+        r#"{}<LocationConstraint xmlns="{}"></LocationConstraint>"#,
+        DECLARATION, NAMESPACE
+    )
+}
+
+/// ETag of the object is the id of its file, it isn't the MD5 of the content,
+/// which the tools recognize by its length and don't compare the content to
+pub(crate) fn etag(file_id: entity::Uuid) -> String {
+    format!("\"{}\"", file_id)
+}
+
+/// Time in the format of the listings, `2024-02-13T08:00:00.000Z`
+pub(crate) fn last_modified(timestamp: i64) -> String {
+    NaiveDateTime::from_timestamp_opt(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+/// Time in the format of the headers, `Tue, 13 Feb 2024 08:00:00 GMT`
+pub(crate) fn http_date(timestamp: i64) -> String {
+    NaiveDateTime::from_timestamp_opt(timestamp, 0)
+        .unwrap_or_default()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// The S3 tools expect the errors as the XML with the S3 error codes,
+/// so the errors of the routes are mapped onto them
+pub(crate) fn respond(response: AppResult<HttpResponse>) -> HttpResponse {
+    let error = match response {
+        Ok(response) => return response,
+        Err(error) => error,
+    };
+
+    let (status, code) = match &error {
+        Error::NotFound(message) if message.starts_with("bucket") => {
+            (StatusCode::NOT_FOUND, "NoSuchBucket")
+        }
+        Error::NotFound(_) => (StatusCode::NOT_FOUND, "NoSuchKey"),
+        Error::Unauthorized(message) | Error::Forbidden(message) => (
+            StatusCode::FORBIDDEN,
+            match message.as_str() {
+                "invalid_access_key_id" => "InvalidAccessKeyId",
+                "signature_does_not_match" => "SignatureDoesNotMatch",
+                "request_time_too_skewed" => "RequestTimeTooSkewed",
+                _ => "AccessDenied",
+            },
+        ),
+        Error::BadRequest(message) if message == "bad_digest" => {
+            (StatusCode::BAD_REQUEST, "BadDigest")
+        }
+        Error::BadRequest(message) if message == "streaming_payload_not_supported" => {
+            (StatusCode::NOT_IMPLEMENTED, "NotImplemented")
+        }
+        Error::BadRequest(_) | Error::Validation(_) => (StatusCode::BAD_REQUEST, "InvalidArgument"),
+        Error::Conflict(_) => (StatusCode::CONFLICT, "OperationAborted"),
+        _ => {
+            log::error!("S3 gateway error: {}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, "InternalError")
+        }
+    };
+
+    let message = match &error {
+        Error::NotFound(message)
+        | Error::Unauthorized(message)
+        | Error::Forbidden(message)
+        | Error::BadRequest(message)
+        | Error::Conflict(message) => message.as_str(),
+        Error::Validation(_) => "invalid_argument",
+        _ => "internal_error",
+    };
+
+    HttpResponse::build(status)
+        .content_type("application/xml")
+        .body(format!(
+            "{}<Error><Code>{}</Code><Message>{}</Message></Error>",
+            DECLARATION,
+            code,
+            escape(message)
+        ))
+}
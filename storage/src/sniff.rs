@@ -0,0 +1,92 @@
+//! # Mime sniffing
+//!
+//! Content is encrypted by the clients, the server never sees it. When the client can't
+//! tell the type of the file it sends an empty or a generic mime, and optionally the first
+//! bytes of the plain content as a hint. The type is then found from the magic number in
+//! them, so the files can be filtered and previewed by their type anyway.
+//!
+//! Only the generic mime is replaced, the one the client has given is only normalized.
+
+/// Most bytes of the content the client can send as the hint
+pub const MAX_MAGIC_BYTES: usize = 32;
+
+/// Mime of the files the type of which is not known
+pub const GENERIC_MIME: &str = "application/octet-stream";
+
+/// Mime types that don't tell anything about the file
+const GENERIC: [&str; 5] = [
+    GENERIC_MIME,
+    "binary/octet-stream",
+    "application/binary",
+    "application/unknown",
+    "application/x-unknown",
+];
+
+/// Magic numbers, the offset they are at in the content and the type they are for
+const SIGNATURES: [(usize, &[u8], &str); 26] = [
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"BM", "image/bmp"),
+    (0, b"II*\x00", "image/tiff"),
+    (0, b"MM\x00*", "image/tiff"),
+    (4, b"ftypheic", "image/heic"),
+    (4, b"ftypqt", "video/quicktime"),
+    (4, b"ftyp", "video/mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "video/webm"),
+    (8, b"AVI ", "video/x-msvideo"),
+    (8, b"WAVE", "audio/wav"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"Rar!\x1a\x07", "application/vnd.rar"),
+    (0, b"\x7fELF", "application/x-executable"),
+    (0, b"MZ", "application/x-msdownload"),
+    (0, b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (0, b"{\\rtf", "application/rtf"),
+];
+
+/// Lowercase mime without the parameters, `Text/Plain; charset=utf-8` is `text/plain`
+pub(crate) fn normalize(mime: &str) -> String {
+    mime.split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// The mime is empty or it doesn't tell anything about the file
+pub(crate) fn is_generic(mime: &str) -> bool {
+    mime.is_empty() || GENERIC.contains(&mime)
+}
+
+/// Type of the content by the magic number at its start
+pub(crate) fn sniff(magic: &[u8]) -> Option<&'static str> {
+    SIGNATURES
+        .iter()
+        .find(|(offset, signature, _)| {
+            magic
+                .get(*offset..*offset + signature.len())
+                .map(|bytes| bytes == *signature)
+                .unwrap_or(false)
+        })
+        .map(|(_, _, mime)| *mime)
+}
+
+/// Mime the file is stored with, the normalized one from the client unless it is generic,
+/// then the one sniffed from the hint, or the generic one if nothing matches
+pub(crate) fn resolve(mime: &str, magic: Option<&[u8]>) -> String {
+    let mime = normalize(mime);
+
+    if !is_generic(&mime) {
+        return mime;
+    }
+
+    magic.and_then(sniff).unwrap_or(GENERIC_MIME).to_string()
+}
@@ -0,0 +1,96 @@
+use chrono::Utc;
+use context::Context;
+use entity::{files, jobs, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+
+use crate::{archive, jobs::RESTORE_ARCHIVED, mock::create_file, repository::lifecycles};
+
+#[actix_web::test]
+async fn cold_files_are_archived_and_restored_on_download() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "archive@test.com", None).await;
+    let now = Utc::now().timestamp();
+    let before = now - 100;
+
+    let cold = create_file(&context, &user, "cold.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let used = create_file(&context, &user, "used.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    create_file(&context, &user, "folder", None, Some("dir"))
+        .await
+        .unwrap();
+
+    // Unfinished uploads have nothing to archive yet
+    assert!(lifecycles::cold(&context.db, now + 100, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    files::Entity::update_many()
+        .set(files::ActiveModel {
+            finished_upload_at: ActiveValue::Set(Some(now - 1000)),
+            ..Default::default()
+        })
+        .filter(files::Column::Id.is_in([cold.id, used.id]))
+        .exec(&context.db)
+        .await
+        .unwrap();
+
+    let ids = |files: Vec<files::Model>| files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    let found = ids(lifecycles::cold(&context.db, before, 10).await.unwrap());
+    assert_eq!(found.len(), 2);
+
+    // Downloading the file that is in the storage records the access
+    let response = archive::restoring(&context, used.id, user.id)
+        .await
+        .unwrap();
+    assert!(response.is_none());
+
+    let found = ids(lifecycles::cold(&context.db, before, 10).await.unwrap());
+    assert_eq!(found, vec![cold.id]);
+
+    lifecycles::archived(&context.db, cold.id, now - 1000)
+        .await
+        .unwrap();
+    assert!(lifecycles::cold(&context.db, before, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Downloading the archived file queues the restore only once
+    for _ in 0..2 {
+        let response = archive::restoring(&context, cold.id, user.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status(), actix_web::http::StatusCode::ACCEPTED);
+    }
+
+    let queued = jobs::Entity::find()
+        .filter(jobs::Column::Kind.eq(RESTORE_ARCHIVED))
+        .count(&context.db)
+        .await
+        .unwrap();
+    assert_eq!(queued, 1);
+
+    let lifecycle = lifecycles::find(&context.db, cold.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lifecycle.restore_requested_by, Some(user.id));
+
+    // Restored file counts as touched and is not archived again right away
+    lifecycles::restored(&context.db, &lifecycle).await.unwrap();
+
+    let lifecycle = lifecycles::find(&context.db, cold.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!lifecycle.is_archived());
+    assert!(lifecycle.restore_requested_at.is_none());
+    assert!(lifecycles::cold(&context.db, before, 10)
+        .await
+        .unwrap()
+        .is_empty());
+}
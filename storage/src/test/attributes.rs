@@ -0,0 +1,165 @@
+use context::Context;
+use entity::{user_files, ActiveValue, EntityTrait, JsonValue, Uuid};
+use serde_json::{json, Map};
+
+use crate::{
+    data::{attributes::UpdateAttributes, search::Search},
+    mock::create_file,
+    repository::Repository,
+};
+
+fn attributes(value: JsonValue) -> UpdateAttributes {
+    UpdateAttributes {
+        attributes: value.as_object().cloned(),
+    }
+}
+
+fn filter(value: JsonValue) -> Option<Map<String, JsonValue>> {
+    value.as_object().cloned()
+}
+
+#[actix_web::test]
+async fn attributes_are_merged_and_removed() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "attributes@test.com", None).await;
+
+    let file = create_file(&context, &user, "report", None, Some("text/plain"))
+        .await
+        .unwrap();
+    assert!(file.attributes.is_none());
+
+    let file = repository
+        .attributes(user.id)
+        .update(file.id, attributes(json!({"rating": 4, "status": "draft"})))
+        .await
+        .unwrap();
+    assert_eq!(
+        file.attributes,
+        Some(json!({"rating": 4, "status": "draft"}))
+    );
+
+    let file = repository
+        .attributes(user.id)
+        .update(
+            file.id,
+            attributes(json!({"rating": null, "status": "done"})),
+        )
+        .await
+        .unwrap();
+    assert_eq!(file.attributes, Some(json!({"status": "done"})));
+
+    let file = repository
+        .attributes(user.id)
+        .update(file.id, attributes(json!({"status": null})))
+        .await
+        .unwrap();
+    assert!(file.attributes.is_none());
+
+    assert!(repository
+        .attributes(user.id)
+        .update(file.id, attributes(json!({"not valid": 1})))
+        .await
+        .is_err());
+}
+
+#[actix_web::test]
+async fn search_filters_files_by_attributes_of_the_user() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let done = create_file(&context, &user, "hello done", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let draft = create_file(&context, &user, "hello draft", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    repository
+        .attributes(user.id)
+        .update(done.id, attributes(json!({"status": "done", "rating": 5})))
+        .await
+        .unwrap();
+    repository
+        .attributes(user.id)
+        .update(
+            draft.id,
+            attributes(json!({"status": "draft", "rating": 5})),
+        )
+        .await
+        .unwrap();
+
+    let results = repository
+        .tokens(user.id)
+        .search(Search {
+            attributes: filter(json!({"status": "done"})),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, done.id);
+
+    let results = repository
+        .tokens(user.id)
+        .search(Search {
+            search_tokens_hashed: Some(vec!["hello:1".to_string()]),
+            attributes: filter(json!({"rating": 5})),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+
+    let results = repository
+        .tokens(user.id)
+        .search(Search {
+            attributes: filter(json!({"status": "done", "rating": 4})),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+
+    // Only strings and numbers can be filtered by
+    assert!(repository
+        .tokens(user.id)
+        .search(Search {
+            attributes: filter(json!({"status": ["done"]})),
+            ..Default::default()
+        })
+        .await
+        .is_err());
+
+    // The user the file is shared with doesn't see the owners attributes
+    user_files::Entity::insert(user_files::ActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        file_id: ActiveValue::Set(done.id),
+        user_id: ActiveValue::Set(other.id),
+        encrypted_key: ActiveValue::Set("key".to_string()),
+        is_owner: ActiveValue::Set(false),
+        created_at: ActiveValue::Set(0),
+        starts_at: ActiveValue::Set(None),
+        expires_at: ActiveValue::Set(None),
+        attributes: ActiveValue::NotSet,
+        permission: ActiveValue::NotSet,
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+
+    let shared = repository.query(other.id).get(done.id).await.unwrap();
+    assert!(shared.attributes.is_none());
+
+    let results = repository
+        .tokens(other.id)
+        .search(Search {
+            attributes: filter(json!({"status": "done"})),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+}
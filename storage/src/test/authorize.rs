@@ -0,0 +1,108 @@
+use context::Context;
+use entity::{group_members::Role, user_files::Permission, Uuid};
+use error::Error;
+
+use crate::{
+    authorize::{decide, Access, Subject},
+    mock::create_file,
+};
+
+#[actix_web::test]
+async fn policy_decides_by_ownership_and_role() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "authorize@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "a.txt", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+
+    let owner = Subject::from(user.id);
+    let stranger = Subject::from(Uuid::new_v4());
+    let admin = Subject {
+        user_id: stranger.user_id,
+        admin: true,
+    };
+
+    for access in [Access::Read, Access::Write, Access::Manage, Access::Own] {
+        assert!(decide(&owner, access, &file, None).is_ok());
+        assert!(decide(&owner, access, &dir, None).is_ok());
+    }
+
+    // Files are readable, the folders only for the users they are shared with and the admins
+    let mut shared = file.clone();
+    shared.is_owner = false;
+    let mut shared_dir = dir.clone();
+    shared_dir.is_owner = false;
+
+    assert!(decide(&stranger, Access::Read, &shared, None).is_ok());
+    assert!(matches!(
+        decide(&stranger, Access::Write, &shared, None),
+        Err(Error::NotFound(e)) if e == "file_not_found"
+    ));
+    assert!(matches!(
+        decide(&stranger, Access::Read, &shared_dir, None),
+        Err(Error::NotFound(e)) if e == "file_or_dir_not_found"
+    ));
+    assert!(decide(&admin, Access::Read, &shared_dir, None).is_ok());
+
+    let mut shared_with_stranger = shared_dir.clone();
+    shared_with_stranger.user_id = stranger.user_id;
+    shared_with_stranger.permission = Permission::Read;
+    assert!(decide(&stranger, Access::Read, &shared_with_stranger, None).is_ok());
+    assert!(matches!(
+        decide(&stranger, Access::Write, &shared_with_stranger, None),
+        Err(Error::NotFound(e)) if e == "directory_not_found"
+    ));
+    assert!(decide(&admin, Access::Own, &shared_dir, None).is_err());
+
+    // In the spaces the role decides the changes
+    assert!(decide(&stranger, Access::Write, &shared, Some(Role::Editor)).is_ok());
+    assert!(matches!(
+        decide(&stranger, Access::Write, &shared, Some(Role::Viewer)),
+        Err(Error::Forbidden(e)) if e == "space_read_only"
+    ));
+    assert!(decide(&stranger, Access::Own, &shared, Some(Role::Owner)).is_err());
+}
+
+#[actix_web::test]
+async fn policy_follows_the_permission_of_the_share() {
+    let context = Context::mock_sqlite().await;
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let user = entity::mock::create_user(&context.db, "user@test.com", None).await;
+
+    let file = create_file(&context, &owner, "a.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let subject = Subject::from(user.id);
+    let shared = |permission| {
+        let mut shared = file.clone();
+        shared.user_id = user.id;
+        shared.is_owner = false;
+        shared.permission = permission;
+        shared
+    };
+
+    let read = shared(Permission::Read);
+    assert!(decide(&subject, Access::Read, &read, None).is_ok());
+    assert!(decide(&subject, Access::Write, &read, None).is_err());
+    assert!(decide(&subject, Access::Manage, &read, None).is_err());
+
+    let write = shared(Permission::Write);
+    assert!(decide(&subject, Access::Write, &write, None).is_ok());
+    assert!(decide(&subject, Access::Manage, &write, None).is_err());
+
+    let manage = shared(Permission::Manage);
+    assert!(decide(&subject, Access::Write, &manage, None).is_ok());
+    assert!(decide(&subject, Access::Manage, &manage, None).is_ok());
+    assert!(decide(&subject, Access::Own, &manage, None).is_err());
+
+    // The role in the space comes before the permission of the share
+    assert!(matches!(
+        decide(&subject, Access::Write, &manage, Some(Role::Viewer)),
+        Err(Error::Forbidden(e)) if e == "space_read_only"
+    ));
+}
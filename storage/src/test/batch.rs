@@ -0,0 +1,136 @@
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{audit_logs, files, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, Uuid};
+
+use crate::{
+    data::batch::{Batch, BatchAction, Operation},
+    mock::create_file,
+    repository::Repository,
+    routes::batch::run,
+};
+
+fn claims(user_id: Uuid) -> Claims {
+    Claims {
+        iss: "test".to_string(),
+        sub: user_id,
+        exp: 0,
+        iat: 0,
+        device: Uuid::new_v4(),
+        role: None,
+        quota: None,
+        app: None,
+    }
+}
+
+#[test]
+fn batch_is_flattened_into_operations() {
+    let (first, second, dir) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let batch = Batch {
+        actions: Some(vec![
+            BatchAction {
+                action: Some("delete".to_string()),
+                ids: Some(vec![first]),
+                file_id: None,
+            },
+            BatchAction {
+                action: Some("move".to_string()),
+                ids: Some(vec![first, second]),
+                file_id: Some(dir),
+            },
+        ]),
+    };
+
+    assert_eq!(
+        batch.into_value().unwrap(),
+        vec![
+            (Operation::Delete, first),
+            (Operation::Move(Some(dir)), first),
+            (Operation::Move(Some(dir)), second),
+        ]
+    );
+
+    let invalid = Batch {
+        actions: Some(vec![BatchAction {
+            action: Some("rename".to_string()),
+            ids: Some(vec![first]),
+            file_id: None,
+        }]),
+    };
+    assert!(invalid.into_value().is_err());
+}
+
+#[actix_web::test]
+async fn failed_operation_does_not_stop_the_batch() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "batch@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let first = create_file(&context, &user, "first.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let second = create_file(&context, &user, "second.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let missing = Uuid::new_v4();
+
+    let operations = vec![
+        (Operation::Move(Some(dir.id)), first.id),
+        (Operation::Delete, missing),
+        (Operation::Delete, second.id),
+    ];
+    let results = run(&context, &claims(user.id), operations).await.unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    assert_eq!(results[0].file.as_ref().unwrap().file_id, Some(dir.id));
+    assert!(!results[1].is_ok());
+    assert_eq!(results[1].status, Some(404));
+    assert!(results[2].is_ok());
+
+    let repository = Repository::new(&context.db);
+    let moved = repository.by_id(first.id, user.id).await.unwrap();
+    assert_eq!(moved.file_id, Some(dir.id));
+    assert!(repository.by_id(second.id, user.id).await.is_err());
+}
+
+#[actix_web::test]
+async fn refused_operation_is_recorded_in_the_audit_log() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "batch@test.com", None).await;
+
+    let held = create_file(&context, &user, "held.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let other = create_file(&context, &user, "other.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(held.id),
+        legal_hold: ActiveValue::Set(true),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    let operations = vec![(Operation::Delete, held.id), (Operation::Delete, other.id)];
+    let results = run(&context, &claims(user.id), operations).await.unwrap();
+
+    assert_eq!(results[0].status, Some(403));
+    assert!(results[1].is_ok());
+
+    let logs = audit_logs::Entity::find()
+        .filter(audit_logs::Column::FileId.eq(held.id))
+        .all(&context.db)
+        .await
+        .unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].action, audit_logs::Action::DeleteBlocked);
+
+    let repository = Repository::new(&context.db);
+    assert!(repository.by_id(held.id, user.id).await.is_ok());
+}
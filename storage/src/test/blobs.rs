@@ -0,0 +1,93 @@
+use context::Context;
+use error::Error;
+use fs::cas::{ChunkBlob, ChunkIndex};
+
+use crate::blobs::BlobIndex;
+
+fn blob(chunk: i64, hash: &str) -> ChunkBlob {
+    ChunkBlob {
+        chunk,
+        hash: hash.to_string(),
+        size: 10,
+    }
+}
+
+#[actix_web::test]
+async fn blobs_are_released_with_the_last_reference() {
+    let context = Context::mock_sqlite().await;
+    let index = BlobIndex::new(context);
+
+    // Same content in two chunks of the file is stored once
+    assert!(index
+        .insert("file", &blob(0, "aaa"))
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(index
+        .insert("file", &blob(1, "aaa"))
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(index
+        .insert("file", &blob(2, "bbb"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let chunks = index.chunks("file").await.unwrap();
+    assert_eq!(chunks, vec![blob(0, "aaa"), blob(1, "aaa"), blob(2, "bbb")]);
+
+    // Copy only points to the same blobs
+    assert_eq!(index.link("file", "copy").await.unwrap(), vec![0, 1, 2]);
+    assert!(index.remove("file").await.unwrap().is_empty());
+    assert!(index.chunks("file").await.unwrap().is_empty());
+    assert_eq!(index.chunks("copy").await.unwrap().len(), 3);
+
+    // Chunk written again with another content releases the previous one
+    assert_eq!(
+        index.insert("copy", &blob(2, "ccc")).await.unwrap(),
+        vec!["bbb".to_string()]
+    );
+    assert!(index
+        .insert("copy", &blob(2, "ccc"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let mut unused = index.remove("copy").await.unwrap();
+    unused.sort();
+    assert_eq!(unused, vec!["aaa".to_string(), "ccc".to_string()]);
+}
+
+#[actix_web::test]
+async fn blob_referenced_again_is_not_forgotten() {
+    let context = Context::mock_sqlite().await;
+    let index = BlobIndex::new(context);
+
+    index.insert("file", &blob(0, "aaa")).await.unwrap();
+    let unused = index.remove("file").await.unwrap();
+    assert_eq!(unused, vec!["aaa".to_string()]);
+
+    // Chunk written meanwhile takes the reference before the blob is purged
+    index.reference(&blob(0, "aaa")).await.unwrap();
+    let purged = index
+        .forget("aaa", Box::pin(async { Err::<(), _>(Error::from("purged_referenced_blob")) }))
+        .await
+        .unwrap();
+    assert!(!purged);
+
+    index.insert("other", &blob(0, "aaa")).await.unwrap();
+    assert!(index
+        .dereference(vec!["aaa".to_string()])
+        .await
+        .unwrap()
+        .is_empty());
+
+    let unused = index.remove("other").await.unwrap();
+    assert_eq!(unused, vec!["aaa".to_string()]);
+    assert!(index
+        .forget("aaa", Box::pin(async { Ok::<(), Error>(()) }))
+        .await
+        .unwrap());
+    assert!(index.chunks("other").await.unwrap().is_empty());
+}
@@ -0,0 +1,103 @@
+use crate::{
+    data::query::Query,
+    mock::create_file,
+    repository::{cached, Repository},
+};
+use context::Context;
+
+#[actix_web::test]
+async fn cached_listing_is_invalidated_on_changes() {
+    let mut context = Context::mock_sqlite().await;
+    context.config.server.cache = true;
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let query = Query {
+        dir_id: Some(dir.id.to_string()),
+        ..Default::default()
+    };
+
+    let response = cached::find(&context, user.id, query.clone())
+        .await
+        .unwrap();
+    assert!(response.children.is_empty());
+
+    let file = create_file(
+        &context,
+        &user,
+        "file.json",
+        Some(dir.id),
+        Some("application/json"),
+    )
+    .await
+    .unwrap();
+
+    // Nothing told the cache about the new file yet
+    let response = cached::find(&context, user.id, query.clone())
+        .await
+        .unwrap();
+    assert!(response.children.is_empty());
+
+    cached::invalidate(user.id, &[file.id, dir.id]).await;
+
+    let response = cached::find(&context, user.id, query.clone())
+        .await
+        .unwrap();
+    assert_eq!(response.children.len(), 1);
+
+    let metadata = cached::get_metadata(&context, user.id, file.id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.id, file.id);
+
+    Repository::new(&context.db)
+        .manage(user.id)
+        .delete_many(vec![file.id])
+        .await
+        .unwrap();
+
+    cached::invalidate(user.id, &[file.id]).await;
+
+    let response = cached::find(&context, user.id, query).await.unwrap();
+    assert!(response.children.is_empty());
+    assert!(cached::get_metadata(&context, user.id, file.id)
+        .await
+        .is_err());
+}
+
+#[actix_web::test]
+async fn disabled_cache_reads_the_database() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "second@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let query = Query {
+        dir_id: Some(dir.id.to_string()),
+        ..Default::default()
+    };
+
+    let response = cached::find(&context, user.id, query.clone())
+        .await
+        .unwrap();
+    assert!(response.children.is_empty());
+
+    create_file(
+        &context,
+        &user,
+        "file.json",
+        Some(dir.id),
+        Some("application/json"),
+    )
+    .await
+    .unwrap();
+
+    // Nothing was invalidated, but nothing was cached either
+    let response = cached::find(&context, user.id, query).await.unwrap();
+    assert_eq!(response.children.len(), 1);
+}
@@ -0,0 +1,196 @@
+use context::Context;
+
+use crate::{
+    data::{changes::Action, rename::Rename},
+    mock::create_file,
+    repository::Repository,
+};
+
+#[actix_web::test]
+async fn list_changes_in_directory_since_cursor() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let other = create_file(&context, &user, "other", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let first = create_file(&context, &user, "first", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    let second = create_file(&context, &user, "second", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+
+    let activities = repository.activities(user.id);
+
+    let response = activities.changes(Some(dir.id), 0, 1000).await.unwrap();
+    assert_eq!(response.changes.len(), 2);
+    assert!(!response.has_more);
+    assert!(response
+        .changes
+        .iter()
+        .all(|c| c.action == Action::Created && c.file.is_some()));
+
+    let cursor = response.cursor;
+    let response = activities
+        .changes(Some(dir.id), cursor, 1000)
+        .await
+        .unwrap();
+    assert!(response.changes.is_empty());
+    assert_eq!(response.cursor, cursor);
+
+    repository
+        .manage(user.id)
+        .rename(
+            first.id,
+            Rename {
+                name_hash: Some("renamed".to_string()),
+                encrypted_name: Some("renamed".to_string()),
+                search_tokens_hashed: Some(vec!["renamed".to_string()]),
+            },
+        )
+        .await
+        .unwrap();
+
+    repository
+        .manage(user.id)
+        .move_many(vec![second.id], Some(other.id))
+        .await
+        .unwrap();
+
+    let response = activities.changes(Some(dir.id), cursor, 1).await.unwrap();
+    assert_eq!(response.changes.len(), 1);
+    assert!(response.has_more);
+    assert_eq!(response.changes[0].file_id, first.id);
+    assert_eq!(response.changes[0].action, Action::Modified);
+    assert_eq!(
+        response.changes[0].file.as_ref().unwrap().name_hash,
+        "renamed"
+    );
+
+    let response = activities
+        .changes(Some(dir.id), response.cursor, 1000)
+        .await
+        .unwrap();
+    assert_eq!(response.changes.len(), 1);
+    assert_eq!(response.changes[0].file_id, second.id);
+    assert_eq!(response.changes[0].action, Action::Deleted);
+    assert!(response.changes[0].file.is_none());
+
+    let response = activities
+        .changes(Some(other.id), cursor, 1000)
+        .await
+        .unwrap();
+    assert_eq!(response.changes.len(), 1);
+    assert_eq!(response.changes[0].action, Action::Created);
+
+    let cursor = response.cursor;
+    repository
+        .manage(user.id)
+        .delete_many(vec![dir.id])
+        .await
+        .unwrap();
+
+    let response = activities.changes(None, cursor, 1000).await.unwrap();
+    assert_eq!(response.changes.len(), 1);
+    assert_eq!(response.changes[0].file_id, dir.id);
+    assert_eq!(response.changes[0].action, Action::Deleted);
+}
+
+#[actix_web::test]
+async fn page_through_account_journal() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "second@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    create_file(&context, &other, "other", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    repository
+        .manage(user.id)
+        .move_many(vec![file.id], None)
+        .await
+        .unwrap();
+
+    let activities = repository.activities(user.id);
+
+    let first = activities.journal(0, 2).await.unwrap();
+    assert!(first.has_more);
+    assert_eq!(first.entries.len(), 2);
+
+    let second = activities.journal(first.cursor, 1000).await.unwrap();
+    assert!(!second.has_more);
+
+    let entries = first
+        .entries
+        .into_iter()
+        .chain(second.entries)
+        .collect::<Vec<_>>();
+
+    assert!(entries.windows(2).all(|w| w[0].id < w[1].id));
+    assert!(entries.iter().all(|e| e.user_id == user.id));
+    assert_eq!(
+        entries.iter().map(|e| e.action).collect::<Vec<_>>(),
+        vec![
+            Action::Created,
+            Action::Created,
+            Action::Deleted,
+            Action::Created
+        ]
+    );
+    assert_eq!(entries[2].parent_id, Some(dir.id));
+    assert_eq!(entries[3].parent_id, None);
+}
+
+#[actix_web::test]
+async fn manifest_versions_follow_the_journal() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+
+    let manage = repository.manage(user.id);
+    let ids = manage.tree_ids(dir.id).await.unwrap();
+    assert_eq!(ids.len(), 2);
+
+    let manifest = manage.manifest(&ids).await.unwrap();
+    let entry = manifest.iter().find(|e| e.id == file.id).unwrap();
+    assert_eq!(entry.parent, Some(dir.id));
+    assert_eq!(entry.size, Some(100));
+    assert!(entry.version > 0);
+
+    manage
+        .rename(
+            file.id,
+            Rename {
+                name_hash: Some("renamed".to_string()),
+                encrypted_name: Some("renamed".to_string()),
+                search_tokens_hashed: Some(vec!["renamed".to_string()]),
+            },
+        )
+        .await
+        .unwrap();
+
+    let renamed = manage.manifest(&[file.id]).await.unwrap();
+    assert_eq!(renamed[0].name_hash, "renamed");
+    assert!(renamed[0].version > entry.version);
+}
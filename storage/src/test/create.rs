@@ -1,7 +1,8 @@
 use context::Context;
+use error::Error;
 
 use crate::{
-    data::{app_file::AppFile, query::Query},
+    data::{app_file::AppFile, create_file::CreateFile, query::Query},
     mock::create_file,
     repository::Repository,
 };
@@ -213,3 +214,180 @@ async fn get_file_tree_with_right_ordering() {
 
     assert_eq!(response.iter().next().unwrap().id, dir3_id);
 }
+
+#[actix_web::test]
+async fn create_file_with_client_id_is_idempotent() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let user2 = entity::mock::create_user(&context.db, "second@test.com", None).await;
+
+    let id = entity::Uuid::new_v4();
+    let manage = repository.manage(user.id);
+
+    assert!(manage
+        .reserved(id, &["file"], None)
+        .await
+        .unwrap()
+        .is_none());
+
+    let file = CreateFile {
+        id: Some(id),
+        encrypted_key: Some("key".to_string()),
+        encrypted_name: Some("file".to_string()),
+        encrypted_thumbnail: None,
+        search_tokens_hashed: None,
+        mime: Some("text/plain".to_string()),
+        name_hash: Some("file".to_string()),
+        size: Some(100),
+        chunks: Some(1),
+        file_id: None,
+        file_modified_at: None,
+        sha256: None,
+        conflict_name_hash: None,
+        encrypted_conflict_name: None,
+        device: None,
+        crypto_version: None,
+        escrow_key: None,
+        magic: None,
+    };
+
+    let (am, key, tokens, _, _) = file.clone().into_active_model().unwrap();
+    let created = manage.create(am, &key, tokens).await.unwrap();
+    assert_eq!(created.id, id);
+
+    let reserved = manage.reserved(id, &["file"], None).await.unwrap().unwrap();
+    assert_eq!(reserved.id, id);
+
+    assert!(manage.reserved(id, &["other"], None).await.is_err());
+
+    // Ids of the other users look the same as the unused ones
+    let other = repository.manage(user2.id);
+    assert!(other.reserved(id, &["file"], None).await.unwrap().is_none());
+
+    // Concurrent request with the same id fails on the insert and gets the file instead
+    let (am, key, tokens, _, _) = file.into_active_model().unwrap();
+    let error = manage.create(am, &key, tokens).await.unwrap_err();
+
+    let reserved = manage
+        .reserved_meanwhile(id, &["file"], None, error)
+        .await
+        .unwrap();
+    assert_eq!(reserved.id, id);
+
+    let error = other
+        .reserved_meanwhile(id, &["file"], None, Error::from("insert_failed"))
+        .await
+        .unwrap_err();
+    assert_eq!(error, Error::as_validation("id", "invalid_id"));
+}
+
+#[actix_web::test]
+async fn create_conflicted_copy_of_file_with_different_content() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let manage = repository.manage(user.id);
+
+    let new_file = |sha256: &str| CreateFile {
+        id: None,
+        encrypted_key: Some("key".to_string()),
+        encrypted_name: Some("file".to_string()),
+        encrypted_thumbnail: None,
+        search_tokens_hashed: None,
+        mime: Some("text/plain".to_string()),
+        name_hash: Some("file".to_string()),
+        size: Some(100),
+        chunks: Some(1),
+        file_id: None,
+        file_modified_at: None,
+        sha256: Some(sha256.repeat(64)),
+        conflict_name_hash: Some("file-conflict".to_string()),
+        encrypted_conflict_name: Some("file (conflicted copy)".to_string()),
+        device: Some("laptop".to_string()),
+        crypto_version: None,
+        escrow_key: None,
+        magic: None,
+    };
+
+    let (am, key, tokens, _, _) = new_file("a").into_active_model().unwrap();
+    let original = manage.create(am, &key, tokens).await.unwrap();
+
+    // Same content uploaded by another client resolves to the existing file
+    let (mut am, _, _, _, _) = new_file("a").into_active_model().unwrap();
+    let existing = manage.by_name("file", None).await.unwrap();
+    let same = manage.conflict(&mut am, existing, None).await;
+    assert!(same.is_err());
+
+    let existing = manage.by_name("file", None).await.unwrap();
+    let conflict = new_file("a").conflict();
+    let same = manage.conflict(&mut am, existing, conflict).await.unwrap();
+    assert_eq!(same.unwrap().id, original.id);
+
+    // Different content becomes a conflicted copy of the existing file
+    let (mut am, key, tokens, _, _) = new_file("b").into_active_model().unwrap();
+    let existing = manage.by_name("file", None).await.unwrap();
+    let conflict = new_file("b").conflict();
+    assert!(manage
+        .conflict(&mut am, existing, conflict)
+        .await
+        .unwrap()
+        .is_none());
+
+    let copy = manage.create(am, &key, tokens).await.unwrap();
+    assert_eq!(copy.name_hash, "file-conflict");
+    assert_eq!(copy.conflict_of, Some(original.id));
+    assert_eq!(copy.conflict_device.as_deref(), Some("laptop"));
+
+    // Conflict name is taken as well
+    let (mut am, _, _, _, _) = new_file("c").into_active_model().unwrap();
+    let existing = manage.by_name("file", None).await.unwrap();
+    let conflict = new_file("c").conflict();
+    assert!(manage.conflict(&mut am, existing, conflict).await.is_err());
+}
+
+#[actix_web::test]
+async fn create_file_with_the_crypto_version() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let manage = repository.manage(user.id);
+
+    let new_file = |name: &str, crypto_version| CreateFile {
+        id: None,
+        encrypted_key: Some("key".to_string()),
+        encrypted_name: Some(name.to_string()),
+        encrypted_thumbnail: None,
+        search_tokens_hashed: None,
+        mime: Some("text/plain".to_string()),
+        name_hash: Some(name.to_string()),
+        size: Some(100),
+        chunks: Some(1),
+        file_id: None,
+        file_modified_at: None,
+        sha256: None,
+        conflict_name_hash: None,
+        encrypted_conflict_name: None,
+        device: None,
+        crypto_version,
+        escrow_key: None,
+        magic: None,
+    };
+
+    // Files created without the version get the current one
+    let (am, key, tokens, _, _) = new_file("current", None).into_active_model().unwrap();
+    let file = manage.create(am, &key, tokens).await.unwrap();
+    assert_eq!(file.crypto_version, cryptfns::scheme::CURRENT);
+
+    for version in cryptfns::scheme::SUPPORTED {
+        let name = format!("file-{}", version);
+        let (am, key, tokens, _, _) = new_file(&name, Some(*version)).into_active_model().unwrap();
+        let file = manage.create(am, &key, tokens).await.unwrap();
+        assert_eq!(file.crypto_version, *version);
+    }
+
+    assert!(new_file("unknown", Some(0)).into_active_model().is_err());
+    assert!(new_file("future", Some(cryptfns::scheme::CURRENT + 1))
+        .into_active_model()
+        .is_err());
+}
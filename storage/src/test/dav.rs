@@ -0,0 +1,146 @@
+use actix_web::web::Bytes;
+use context::Context;
+use cryptfns::scheme::Scheme;
+use error::AppResult;
+use fs::prelude::*;
+
+use crate::{
+    dav::{self, Entry, Multistatus, Resource},
+    import::Sealer,
+};
+
+#[test]
+fn paths_are_decoded_into_the_segments() {
+    assert!(dav::segments("/api/dav").unwrap().is_empty());
+    assert!(dav::segments("/api/dav/").unwrap().is_empty());
+    assert_eq!(
+        dav::segments("/api/dav/My%20Documents/notes.txt").unwrap(),
+        vec!["My Documents", "notes.txt"]
+    );
+    assert_eq!(
+        dav::segments("https://hoodik.example.com/api/dav/notes.txt").unwrap(),
+        vec!["notes.txt"]
+    );
+
+    assert!(dav::segments("/api/storage/notes.txt").is_err());
+    assert!(dav::segments("/api/davnotes.txt").is_err());
+    assert!(dav::segments("/api/dav/Documents/../notes.txt").is_err());
+}
+
+#[test]
+fn multistatus_escapes_the_values() {
+    let mut multistatus = Multistatus::default();
+    multistatus.push(Entry::root("/api/dav/"));
+    multistatus.push(Entry {
+        href: "/api/dav/a&b".to_string(),
+        name: "a<b>".to_string(),
+        mime: Some("text/plain".to_string()),
+        size: Some(42),
+        modified_at: Some(1_600_000_000),
+        ..Default::default()
+    });
+
+    let xml = multistatus.render();
+    assert!(xml.contains("<d:href>/api/dav/</d:href>"));
+    assert!(xml.contains("<d:resourcetype><d:collection/></d:resourcetype>"));
+    assert!(xml.contains("<d:href>/api/dav/a&amp;b</d:href>"));
+    assert!(xml.contains("<d:displayname>a&lt;b&gt;</d:displayname>"));
+    assert!(xml.contains("<d:getcontentlength>42</d:getcontentlength>"));
+    assert!(xml.contains("<d:getlastmodified>Sun, 13 Sep 2020 12:26:40 GMT</d:getlastmodified>"));
+}
+
+#[actix_web::test]
+async fn uploaded_files_are_sealed_and_resolved_by_their_names() {
+    let context = Context::mock_sqlite().await;
+    let fs = Fs::new(&context.config);
+
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let public_key = cryptfns::rsa::public::to_string(&public_key).unwrap();
+    let private_key = cryptfns::rsa::private::to_string(&private_key).unwrap();
+
+    let user = entity::mock::create_user(&context.db, "dav@test.com", Some(public_key)).await;
+    let sealer = Sealer::new(&context, user.clone()).await.unwrap();
+
+    let folder = sealer
+        .folder(&sealer.seal("Documents").unwrap(), None)
+        .await
+        .unwrap();
+
+    let pieces: Vec<AppResult<Bytes>> = vec![
+        Ok(Bytes::from_static(b"hello ")),
+        Ok(Bytes::from_static(b"from the mount")),
+    ];
+    let file = sealer
+        .file(
+            &sealer.seal("notes.txt").unwrap(),
+            Some(folder.id),
+            "text/plain".to_string(),
+            20,
+            None,
+            futures::stream::iter(pieces),
+        )
+        .await
+        .unwrap();
+    assert!(file.finished_upload_at.is_some());
+
+    let segments = dav::segments("/api/dav/Documents/notes.txt").unwrap();
+    match dav::resolve(&context, user.id, &segments).await.unwrap() {
+        Resource::File(resolved) => assert_eq!(resolved.id, file.id),
+        resource => panic!("Expected the file, got {:?}", resource),
+    }
+
+    // Ids and names can be mixed in the path
+    let segments = dav::segments(&format!("/api/dav/{}/{}", folder.id, file.id)).unwrap();
+    match dav::resolve(&context, user.id, &segments).await.unwrap() {
+        Resource::File(resolved) => assert_eq!(resolved.id, file.id),
+        resource => panic!("Expected the file, got {:?}", resource),
+    }
+
+    let segments = dav::segments("/api/dav/Documents/todo.txt").unwrap();
+    match dav::resolve(&context, user.id, &segments).await.unwrap() {
+        Resource::Missing { parent_id, name } => {
+            assert_eq!(parent_id, Some(folder.id));
+            assert_eq!(name, "todo.txt");
+        }
+        resource => panic!("Expected nothing, got {:?}", resource),
+    }
+
+    let segments = dav::segments("/api/dav/Pictures/cat.jpg").unwrap();
+    assert!(dav::resolve(&context, user.id, &segments).await.is_err());
+
+    // Files of the other users are not there
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+    let segments = dav::segments(&format!("/api/dav/{}", folder.id)).unwrap();
+    assert!(matches!(
+        dav::resolve(&context, other.id, &segments).await.unwrap(),
+        Resource::Missing { .. }
+    ));
+
+    // The user can decrypt the content with their private key
+    let key = cryptfns::rsa::private::decrypt(&file.encrypted_key, &private_key).unwrap();
+    let key = cryptfns::hex::decode(key).unwrap();
+    let chunk = fs.pull(&file, 0).await.unwrap();
+    let scheme = Scheme::from_version(file.crypto_version).unwrap();
+    assert_eq!(scheme.decrypt(key, chunk).unwrap(), b"hello from the mount");
+
+    // Body that is shorter than the announced size leaves no file behind
+    let pieces: Vec<AppResult<Bytes>> = vec![Ok(Bytes::from_static(b"short"))];
+    assert!(sealer
+        .file(
+            &sealer.seal("short.txt").unwrap(),
+            None,
+            "text/plain".to_string(),
+            10,
+            None,
+            futures::stream::iter(pieces),
+        )
+        .await
+        .is_err());
+
+    let children = dav::children(&context, user.id, None).await.unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].id, folder.id);
+
+    fs.purge(&file).await.unwrap();
+}
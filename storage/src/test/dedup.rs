@@ -0,0 +1,74 @@
+use context::Context;
+use entity::{files, ActiveValue, EntityTrait};
+
+use crate::{mock::create_file, repository::dedup};
+
+#[actix_web::test]
+async fn stored_content_is_found_by_its_hash() {
+    let context = Context::mock_sqlite().await;
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+    let sha256 = "a".repeat(64);
+
+    let file = create_file(&context, &owner, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(file.id),
+        sha256: ActiveValue::Set(Some(sha256.clone())),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    // Unfinished upload can't be used
+    let found = dedup::lookup(&context.db, owner.id, &sha256, 100, false)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(file.id),
+        finished_upload_at: ActiveValue::Set(Some(file.created_at)),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    let found = dedup::lookup(&context.db, owner.id, &sha256, 100, false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, file.id);
+    assert_eq!(found.encrypted_key, Some(file.encrypted_key.clone()));
+
+    // Size has to match as well
+    assert!(dedup::lookup(&context.db, owner.id, &sha256, 99, false)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Content of other users is only found with the global deduplication
+    assert!(dedup::lookup(&context.db, other.id, &sha256, 100, false)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(dedup::source(&context.db, other.id, file.id, false)
+        .await
+        .is_err());
+
+    let found = dedup::lookup(&context.db, other.id, &sha256, 100, true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, file.id);
+    assert_eq!(found.encrypted_key, None);
+
+    let source = dedup::source(&context.db, other.id, file.id, true)
+        .await
+        .unwrap();
+    assert_eq!(source.sha256, Some(sha256));
+}
@@ -93,3 +93,51 @@ async fn create_recursive_mess_to_test_delete_many() {
 
     assert_eq!(manual.len(), delete_files.len());
 }
+
+#[actix_web::test]
+async fn deleted_file_chunks_are_purged_by_the_worker() {
+    use fs::prelude::*;
+
+    use crate::jobs::{queue_purge, PurgeFiles, PURGE_FILES};
+
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let fs = Fs::new(&context.config);
+
+    let dir = create_file(&context, &user, "purge.dir", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let file = create_file(
+        &context,
+        &user,
+        "purge.json",
+        Some(dir.id),
+        Some("application/json"),
+    )
+    .await
+    .unwrap();
+
+    fs.push(&file, 0, b"hello world").await.unwrap();
+    assert!(fs.exists(&file, 0).await.unwrap());
+
+    let files = repository
+        .manage(user.id)
+        .delete_many(vec![dir.id])
+        .await
+        .unwrap();
+    queue_purge(&context.db, &files).await.unwrap();
+
+    // Chunks are left in place until the worker picks up the job
+    assert!(fs.exists(&file, 0).await.unwrap());
+
+    let finished = jobs::worker::Worker::new(context.clone())
+        .handler(PURGE_FILES, PurgeFiles)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(finished, 1);
+    assert!(!fs.exists(&file, 0).await.unwrap());
+}
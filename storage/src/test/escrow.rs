@@ -0,0 +1,132 @@
+use context::Context;
+use entity::{escrow_keys, EntityTrait};
+
+use crate::{
+    mock::create_file,
+    repository::escrow::{self, RecoveryKey},
+};
+
+#[actix_web::test]
+async fn file_keys_are_escrowed_when_enabled() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let file = create_file(&context, &user, "file", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    // Disabled escrow doesn't require nor store anything
+    assert!(escrow::recovery_key(&context).await.unwrap().is_none());
+    escrow::escrow(&context.db, None, file.id, None)
+        .await
+        .unwrap();
+    escrow::escrow(&context.db, None, file.id, Some("key"))
+        .await
+        .unwrap();
+    assert!(escrow_keys::Entity::find_by_id(file.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .is_none());
+
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let public_key = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    context.settings.inner().await.escrow = serde_json::from_value(serde_json::json!({
+        "enabled": true,
+        "public_key": public_key,
+    }))
+    .unwrap();
+
+    let recovery_key = escrow::recovery_key(&context).await.unwrap().unwrap();
+    assert_eq!(recovery_key.public_key, public_key);
+    assert_eq!(
+        recovery_key.fingerprint,
+        escrow::recovery_fingerprint(&public_key).unwrap()
+    );
+
+    assert!(
+        escrow::escrow(&context.db, Some(&recovery_key), file.id, None)
+            .await
+            .is_err()
+    );
+    assert!(
+        escrow::escrow(&context.db, Some(&recovery_key), file.id, Some(""))
+            .await
+            .is_err()
+    );
+
+    escrow::escrow(&context.db, Some(&recovery_key), file.id, Some("escrowed"))
+        .await
+        .unwrap();
+
+    let escrowed = escrow_keys::Entity::find_by_id(file.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(escrowed.encrypted_key, "escrowed");
+    assert_eq!(escrowed.fingerprint, recovery_key.fingerprint);
+}
+
+#[actix_web::test]
+async fn shared_file_is_escrowed_only_if_missing() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let file = create_file(&context, &user, "file", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let recovery_key = RecoveryKey {
+        public_key: "old".to_string(),
+        fingerprint: "old-fingerprint".to_string(),
+    };
+
+    // File created before the escrow was enabled needs the key when shared
+    assert!(
+        escrow::escrow_if_missing(&context.db, Some(&recovery_key), file.id, None)
+            .await
+            .is_err()
+    );
+    escrow::escrow_if_missing(&context.db, Some(&recovery_key), file.id, Some("first"))
+        .await
+        .unwrap();
+
+    // Already escrowed, nothing is required and the key is kept
+    escrow::escrow_if_missing(&context.db, Some(&recovery_key), file.id, None)
+        .await
+        .unwrap();
+    escrow::escrow_if_missing(&context.db, Some(&recovery_key), file.id, Some("second"))
+        .await
+        .unwrap();
+
+    let escrowed = escrow_keys::Entity::find_by_id(file.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(escrowed.encrypted_key, "first");
+
+    // Recovery key was replaced, the file has to be escrowed to the new one
+    let recovery_key = RecoveryKey {
+        public_key: "new".to_string(),
+        fingerprint: "new-fingerprint".to_string(),
+    };
+
+    assert!(
+        escrow::escrow_if_missing(&context.db, Some(&recovery_key), file.id, None)
+            .await
+            .is_err()
+    );
+    escrow::escrow_if_missing(&context.db, Some(&recovery_key), file.id, Some("third"))
+        .await
+        .unwrap();
+
+    let escrowed = escrow_keys::Entity::find_by_id(file.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(escrowed.encrypted_key, "third");
+    assert_eq!(escrowed.fingerprint, "new-fingerprint");
+}
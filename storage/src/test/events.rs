@@ -0,0 +1,29 @@
+use entity::{activities, Uuid};
+
+use crate::events::topic;
+
+#[test]
+fn events_are_published_to_the_folder_topic() {
+    let user_id = Uuid::new_v4();
+    let folder_id = Uuid::new_v4();
+
+    let mut activity = activities::Model {
+        id: 1,
+        user_id,
+        file_id: Uuid::new_v4(),
+        parent_id: Some(folder_id),
+        action: activities::Action::Created,
+        created_at: 0,
+    };
+
+    assert_eq!(
+        topic("hoodik", &activity),
+        format!("hoodik/{}/{}", user_id, folder_id)
+    );
+
+    activity.parent_id = None;
+    assert_eq!(
+        topic("hoodik", &activity),
+        format!("hoodik/{}/root", user_id)
+    );
+}
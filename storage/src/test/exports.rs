@@ -0,0 +1,70 @@
+use context::Context;
+use entity::{exports, EntityTrait};
+use fs::prelude::*;
+use jobs::worker::Handler;
+
+use crate::{
+    data::exports::ExportArchive,
+    jobs::{ExportUserData, UserExport},
+    mock::create_file,
+    repository::{exports::metadata, Repository},
+};
+
+#[actix_web::test]
+async fn export_packs_the_metadata_and_the_encrypted_files() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let fs = Fs::new(&context.config);
+    let user = entity::mock::create_user(&context.db, "export@test.com", None).await;
+
+    let file = create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    fs.push(&file, 0, b"encrypted content").await.unwrap();
+    repository.manage(user.id).finish(&file).await.unwrap();
+
+    let metadata = metadata(&context.db, user.id).await.unwrap();
+    assert_eq!(metadata.user.email, "export@test.com");
+    assert_eq!(metadata.files.len(), 1);
+    assert!(metadata.files[0].is_owner);
+
+    let export = repository.exports(user.id).create(true).await.unwrap();
+    assert_eq!(export.status, exports::Status::Pending);
+
+    // Only one export can be built at the time
+    assert!(repository.exports(user.id).create(false).await.is_err());
+
+    let payload = serde_json::to_string(&UserExport { id: export.id }).unwrap();
+    ExportUserData.handle(&context, &payload).await.unwrap();
+
+    let export = exports::Entity::find_by_id(export.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(export.status, exports::Status::Ready);
+    assert!(export.finished_at.is_some());
+
+    let archive = ExportArchive::from(&export);
+    let content = fs.pull(&archive, 0).await.unwrap();
+    assert_eq!(export.size, Some(content.len() as i64));
+    assert_eq!(content.len() % util::tar::BLOCK_SIZE, 0);
+    assert!(content.starts_with(b"metadata.json"));
+
+    let path = format!("files/{}/0", file.id);
+    assert!(content
+        .windows(path.len())
+        .any(|window| window == path.as_bytes()));
+
+    // Running the job again doesn't touch the finished export
+    ExportUserData.handle(&context, &payload).await.unwrap();
+
+    // New export replaces the previous one
+    let next = repository.exports(user.id).create(false).await.unwrap();
+    let exports = repository.exports(user.id).find().await.unwrap();
+    assert_eq!(exports.len(), 1);
+    assert_eq!(exports[0].id, next.id);
+
+    fs.purge(&archive).await.unwrap();
+    fs.purge(&file).await.unwrap();
+}
@@ -0,0 +1,164 @@
+use chrono::Utc;
+use context::Context;
+use entity::{file_requests, ActiveValue, EntityTrait};
+use jobs::worker::Handler;
+
+use crate::{
+    jobs::{CloseFileRequest, CloseFileRequests},
+    mock::create_file,
+    repository::{file_requests as requests, Repository},
+};
+
+#[actix_web::test]
+async fn file_request_stops_accepting_uploads_after_the_deadline() {
+    let context = Context::add_mock_sender(Context::mock_sqlite().await);
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dropbox", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(
+        &context,
+        &user,
+        "received",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    let deadline = Utc::now().timestamp() + 3600;
+    let request = repository
+        .file_requests(user.id)
+        .create(dir.id, Some(deadline))
+        .await
+        .unwrap();
+
+    assert!(request.open);
+    let request = request.request;
+
+    // The close is scheduled for the deadline
+    let scheduled = jobs::repository::Repository::new(&context.db)
+        .due(10)
+        .await
+        .unwrap();
+    assert!(scheduled.is_empty());
+
+    requests::open(&context.db, request.id).await.unwrap();
+    requests::receive(&context.db, request.id, file.id)
+        .await
+        .unwrap();
+    requests::received(&context.db, request.id, file.id)
+        .await
+        .unwrap();
+    assert!(requests::received(&context.db, request.id, dir.id)
+        .await
+        .is_err());
+
+    // The worker does nothing while the request is still open
+    let payload = serde_json::to_string(&CloseFileRequest { id: request.id }).unwrap();
+    CloseFileRequests.handle(&context, &payload).await.unwrap();
+
+    let unchanged = file_requests::Entity::find_by_id(request.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(unchanged.closed_at.is_none());
+    assert!(unchanged.notified_at.is_none());
+
+    file_requests::Entity::update(file_requests::ActiveModel {
+        id: ActiveValue::Set(request.id),
+        deadline: ActiveValue::Set(Some(Utc::now().timestamp() - 1)),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    assert!(requests::open(&context.db, request.id).await.is_err());
+
+    CloseFileRequests.handle(&context, &payload).await.unwrap();
+
+    let closed = file_requests::Entity::find_by_id(request.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(closed.closed_at.is_some());
+    assert!(closed.notified_at.is_some());
+
+    let summary = requests::summary(&context.db, request.id).await.unwrap();
+    assert_eq!(summary.files, 1);
+    assert_eq!(summary.size, 100);
+
+    // Running the job again does not send another summary
+    CloseFileRequests.handle(&context, &payload).await.unwrap();
+
+    let notified = file_requests::Entity::find_by_id(request.id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notified.notified_at, closed.notified_at);
+
+    let listed = repository.file_requests(user.id).find().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(!listed[0].open);
+    assert_eq!(listed[0].received.files, 1);
+}
+
+#[actix_web::test]
+async fn file_request_is_closed_by_the_owner() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dropbox", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    assert!(repository
+        .file_requests(user.id)
+        .create(file.id, None)
+        .await
+        .is_err());
+    assert!(repository
+        .file_requests(other.id)
+        .create(dir.id, None)
+        .await
+        .is_err());
+
+    let request = repository
+        .file_requests(user.id)
+        .create(dir.id, None)
+        .await
+        .unwrap()
+        .request;
+
+    assert!(repository
+        .file_requests(other.id)
+        .close(request.id)
+        .await
+        .is_err());
+
+    let closed = repository
+        .file_requests(user.id)
+        .close(request.id)
+        .await
+        .unwrap();
+    assert!(!closed.open);
+    assert!(requests::open(&context.db, request.id).await.is_err());
+
+    // Summary is sent by the worker right away
+    let due = jobs::repository::Repository::new(&context.db)
+        .due(10)
+        .await
+        .unwrap();
+    assert_eq!(due.len(), 1);
+}
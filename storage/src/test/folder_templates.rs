@@ -0,0 +1,133 @@
+use context::Context;
+use entity::Uuid;
+
+use crate::{
+    data::folder_templates::{TemplateFolder, UseTemplate},
+    mock::create_file,
+    repository::Repository,
+};
+
+fn folder(name: &str, id: Uuid, parent_id: Option<Uuid>) -> TemplateFolder {
+    TemplateFolder {
+        id: Some(id),
+        parent_id,
+        encrypted_key: Some("key".to_string()),
+        name_hash: Some(cryptfns::sha256::digest(name.as_bytes())),
+        encrypted_name: Some(name.to_string()),
+        search_tokens_hashed: None,
+        escrow_key: None,
+    }
+}
+
+fn project(target: Option<Uuid>) -> UseTemplate {
+    let (docs, src) = (Uuid::new_v4(), Uuid::new_v4());
+
+    UseTemplate {
+        file_id: target,
+        folders: Some(vec![
+            folder("docs", docs, None),
+            folder("src", src, None),
+            folder("drafts", Uuid::new_v4(), Some(docs)),
+            folder("tests", Uuid::new_v4(), Some(src)),
+        ]),
+    }
+}
+
+#[actix_web::test]
+async fn instantiate_template_creates_the_tree_in_the_folder() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "templates@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let dir = create_file(&context, &user, "projects", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let template = repository
+        .folder_templates(user.id)
+        .create("project".to_string(), "tree".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository
+            .folder_templates(user.id)
+            .find()
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(repository
+        .folder_templates(other.id)
+        .find()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let (target, folders) = project(Some(dir.id)).into_value().unwrap();
+    let folders = folders.into_iter().map(|(f, _)| f).collect::<Vec<_>>();
+
+    let created = repository
+        .folder_templates(user.id)
+        .instantiate(template.id, target, folders)
+        .await
+        .unwrap();
+
+    assert_eq!(created.len(), 4);
+    assert!(created.iter().all(|f| f.is_dir()));
+    assert_eq!(created[0].file_id, Some(dir.id));
+    assert_eq!(created[1].file_id, Some(dir.id));
+    assert_eq!(created[2].file_id, Some(created[0].id));
+    assert_eq!(created[3].file_id, Some(created[1].id));
+
+    // Using the template again in the same folder clashes with the existing folders
+    let (target, folders) = project(Some(dir.id)).into_value().unwrap();
+    let folders = folders.into_iter().map(|(f, _)| f).collect::<Vec<_>>();
+
+    assert!(repository
+        .folder_templates(user.id)
+        .instantiate(template.id, target, folders)
+        .await
+        .is_err());
+
+    // Other users cannot use the template
+    let (target, folders) = project(None).into_value().unwrap();
+    let folders = folders.into_iter().map(|(f, _)| f).collect::<Vec<_>>();
+
+    assert!(repository
+        .folder_templates(other.id)
+        .instantiate(template.id, target, folders)
+        .await
+        .is_err());
+}
+
+#[test]
+fn template_folders_must_come_after_their_parent() {
+    let (docs, drafts) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let unordered = UseTemplate {
+        file_id: None,
+        folders: Some(vec![
+            folder("drafts", drafts, Some(docs)),
+            folder("docs", docs, None),
+        ]),
+    };
+    assert!(unordered.into_value().is_err());
+
+    let duplicate = UseTemplate {
+        file_id: None,
+        folders: Some(vec![
+            folder("docs", docs, None),
+            folder("docs", drafts, None),
+        ]),
+    };
+    assert!(duplicate.into_value().is_err());
+
+    let empty = UseTemplate {
+        file_id: None,
+        folders: Some(vec![]),
+    };
+    assert!(empty.into_value().is_err());
+}
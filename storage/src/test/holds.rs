@@ -0,0 +1,110 @@
+use context::Context;
+use entity::{audit_logs, files, ActiveValue, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{mock::create_file, repository::holds};
+
+#[actix_web::test]
+async fn legal_hold_blocks_deleting_the_subtree() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let inner = create_file(&context, &user, "inner", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", Some(inner.id), Some("text/plain"))
+        .await
+        .unwrap();
+    let other = create_file(&context, &user, "other", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    holds::guard_delete(&context.db, user.id, &[dir.id, other.id])
+        .await
+        .unwrap();
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(inner.id),
+        legal_hold: ActiveValue::Set(true),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    // Held folder, the file inside of it and the folder above it are all kept
+    for id in [dir.id, inner.id, file.id] {
+        assert!(holds::guard_delete(&context.db, user.id, &[id])
+            .await
+            .is_err());
+    }
+    holds::guard_delete(&context.db, user.id, &[other.id])
+        .await
+        .unwrap();
+
+    let logs = audit_logs::Entity::find()
+        .filter(audit_logs::Column::FileId.eq(inner.id))
+        .all(&context.db)
+        .await
+        .unwrap();
+
+    assert_eq!(logs.len(), 3);
+    assert!(logs
+        .iter()
+        .all(|log| log.action == audit_logs::Action::DeleteBlocked && log.actor_id == user.id));
+}
+
+#[actix_web::test]
+async fn legal_hold_blocks_moving_out_of_the_folder() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let inner = create_file(&context, &user, "inner", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    let other = create_file(&context, &user, "other", None, Some("dir"))
+        .await
+        .unwrap();
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(dir.id),
+        legal_hold: ActiveValue::Set(true),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    // Moving inside of the held folder and moving the held folder itself is fine
+    holds::guard_move(&context.db, user.id, &[file.id], Some(inner.id))
+        .await
+        .unwrap();
+    holds::guard_move(&context.db, user.id, &[dir.id], Some(other.id))
+        .await
+        .unwrap();
+
+    for file_id in [Some(other.id), None] {
+        assert!(holds::guard_move(&context.db, user.id, &[file.id], file_id)
+            .await
+            .is_err());
+    }
+
+    let logs = audit_logs::Entity::find()
+        .filter(audit_logs::Column::FileId.eq(dir.id))
+        .all(&context.db)
+        .await
+        .unwrap();
+
+    assert_eq!(logs.len(), 2);
+    assert!(logs
+        .iter()
+        .all(|log| log.action == audit_logs::Action::MoveBlocked && log.actor_id == user.id));
+}
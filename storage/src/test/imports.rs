@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use context::Context;
+use cryptfns::scheme::Scheme;
+use entity::imports;
+use error::{AppResult, Error};
+use fs::prelude::*;
+
+use crate::{
+    data::imports::CreateImport,
+    import::{
+        dropbox::parse_list_folder, run, webdav::parse_multistatus, RemoteBody, RemoteEntry, Source,
+    },
+    repository::{self, Repository},
+};
+
+/// Source that serves the files from memory
+#[derive(Default)]
+struct MemorySource {
+    folders: HashMap<String, Vec<RemoteEntry>>,
+    content: HashMap<String, Vec<u8>>,
+}
+
+impl MemorySource {
+    fn folder(mut self, parent: &str, name: &str) -> Self {
+        let path = format!("{}/{}", parent.trim_end_matches('/'), name);
+        self.folders.entry(path.clone()).or_default();
+        self.entry(parent, &path, name, true, None)
+    }
+
+    fn file(mut self, parent: &str, name: &str, content: &[u8]) -> Self {
+        let path = format!("{}/{}", parent.trim_end_matches('/'), name);
+        self.content.insert(path.clone(), content.to_vec());
+        self.entry(parent, &path, name, false, Some(content.len() as i64))
+    }
+
+    fn entry(
+        mut self,
+        parent: &str,
+        path: &str,
+        name: &str,
+        is_dir: bool,
+        size: Option<i64>,
+    ) -> Self {
+        self.folders
+            .entry(parent.to_string())
+            .or_default()
+            .push(RemoteEntry {
+                path: path.to_string(),
+                name: name.to_string(),
+                is_dir,
+                size,
+                mime: (!is_dir).then(|| "text/plain".to_string()),
+                modified_at: Some(1_600_000_000),
+            });
+        self
+    }
+}
+
+#[async_trait]
+impl Source for MemorySource {
+    fn root(&self) -> String {
+        "/".to_string()
+    }
+
+    async fn list(&self, path: &str) -> AppResult<Vec<RemoteEntry>> {
+        self.folders
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("folder_not_found:{}", path)))
+    }
+
+    async fn download(&self, entry: &RemoteEntry) -> AppResult<RemoteBody> {
+        let content = self
+            .content
+            .get(&entry.path)
+            .cloned()
+            .ok_or_else(|| Error::NotFound("file_not_found".to_string()))?;
+
+        // Sent in small pieces the way the response would arrive
+        let pieces = content
+            .chunks(3)
+            .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+            .collect::<Vec<_>>();
+
+        Ok(Box::pin(futures::stream::iter(pieces)))
+    }
+}
+
+fn create_import(source: imports::Source) -> CreateImport {
+    CreateImport {
+        source: Some(source),
+        location: Some("https://cloud.example.com/remote.php/dav/files/user".to_string()),
+        username: Some("user".to_string()),
+        password: Some("app-password".to_string()),
+        token: Some("token".to_string()),
+        file_id: None,
+    }
+}
+
+#[actix_web::test]
+async fn import_recreates_the_tree_encrypted_for_the_user() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let fs = Fs::new(&context.config);
+
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let public_key = cryptfns::rsa::public::to_string(&public_key).unwrap();
+    let private_key = cryptfns::rsa::private::to_string(&private_key).unwrap();
+
+    let user =
+        entity::mock::create_user(&context.db, "import@test.com", Some(public_key.clone())).await;
+
+    let source = MemorySource::default()
+        .folder("/", "Documents")
+        .file("/", "readme.txt", b"hello from the other cloud")
+        .file("/", "empty.txt", b"")
+        .file("/Documents", "notes.txt", b"notes");
+
+    let import = repository
+        .imports(user.id)
+        .create(create_import(imports::Source::Webdav))
+        .await
+        .unwrap();
+    assert_eq!(import.status, imports::Status::Pending);
+
+    // Only one import can run at the time
+    assert!(repository
+        .imports(user.id)
+        .create(create_import(imports::Source::Webdav))
+        .await
+        .is_err());
+
+    let import = repository::imports::start(&context.db, import.id)
+        .await
+        .unwrap()
+        .unwrap();
+    let progress = run(&context, &import, &source).await.unwrap();
+    assert_eq!(progress.imported_files, 2);
+    assert_eq!(progress.imported_bytes, 31);
+    assert_eq!(progress.skipped_files, 1);
+    assert_eq!(progress.failed_files, 0);
+
+    repository::imports::finish(&context.db, import.id, Ok(()))
+        .await
+        .unwrap();
+
+    let import = repository.imports(user.id).get(import.id).await.unwrap();
+    assert_eq!(import.status, imports::Status::Finished);
+    assert_eq!(import.imported_files, 2);
+    assert!(import.credentials.is_none());
+    assert!(import.finished_at.is_some());
+
+    let manage = repository.manage(user.id);
+    let folder = manage
+        .by_name(cryptfns::sha256::digest("Documents".as_bytes()), None)
+        .await
+        .unwrap();
+    assert!(folder.is_dir());
+
+    let file = manage
+        .by_name(
+            cryptfns::sha256::digest("notes.txt".as_bytes()),
+            Some(folder.id),
+        )
+        .await
+        .unwrap();
+    assert_eq!(file.size, Some(5));
+    assert_eq!(file.chunks, Some(1));
+    assert_eq!(file.mime, "text/plain");
+    assert_eq!(file.file_modified_at, 1_600_000_000);
+    assert!(file.finished_upload_at.is_some());
+
+    // The user can decrypt the key, the name and the content with their private key
+    let key = cryptfns::rsa::private::decrypt(&file.encrypted_key, &private_key).unwrap();
+    let key = cryptfns::hex::decode(key).unwrap();
+
+    let name = cryptfns::hex::decode(&file.encrypted_name).unwrap();
+    let name = cryptfns::aes::decrypt(key.clone(), name).unwrap();
+    assert_eq!(name, b"notes.txt");
+
+    let chunk = fs.pull(&file, 0).await.unwrap();
+    let scheme = Scheme::from_version(file.crypto_version).unwrap();
+    assert_eq!(scheme.decrypt(key, chunk).unwrap(), b"notes");
+
+    // Running the import again reuses the folders and skips the existing files
+    let import = repository
+        .imports(user.id)
+        .create(create_import(imports::Source::Webdav))
+        .await
+        .unwrap();
+    let import = repository::imports::start(&context.db, import.id)
+        .await
+        .unwrap()
+        .unwrap();
+    let progress = run(&context, &import, &source).await.unwrap();
+    assert_eq!(progress.imported_files, 0);
+    assert_eq!(progress.skipped_files, 3);
+
+    let readme = manage
+        .by_name(cryptfns::sha256::digest("readme.txt".as_bytes()), None)
+        .await
+        .unwrap();
+    fs.purge(&readme).await.unwrap();
+    fs.purge(&file).await.unwrap();
+}
+
+#[actix_web::test]
+async fn failed_files_are_recorded_and_cancelled_import_stops() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let public_key = cryptfns::rsa::public::to_string(&public_key).unwrap();
+    let user = entity::mock::create_user(&context.db, "import@test.com", Some(public_key)).await;
+
+    // Folder that can't be listed and the file without the content
+    let mut source = MemorySource::default().folder("/", "broken");
+    source.folders.remove("/broken");
+    source = source.entry("/", "/missing.txt", "missing.txt", false, Some(10));
+
+    let import = repository
+        .imports(user.id)
+        .create(create_import(imports::Source::Dropbox))
+        .await
+        .unwrap();
+    assert_eq!(import.location, "");
+
+    let import = repository::imports::start(&context.db, import.id)
+        .await
+        .unwrap()
+        .unwrap();
+    let progress = run(&context, &import, &source).await.unwrap();
+    assert_eq!(progress.imported_files, 0);
+    assert_eq!(progress.failed_files, 2);
+    assert_eq!(progress.errors.len(), 2);
+
+    // Failed file is removed, the folder is kept
+    let manage = repository.manage(user.id);
+    assert!(manage
+        .by_name(cryptfns::sha256::digest("missing.txt".as_bytes()), None)
+        .await
+        .is_err());
+    assert!(manage
+        .by_name(cryptfns::sha256::digest("broken".as_bytes()), None)
+        .await
+        .is_ok());
+
+    let cancelled = repository.imports(user.id).cancel(import.id).await.unwrap();
+    assert_eq!(cancelled.status, imports::Status::Cancelled);
+    assert!(cancelled.credentials.is_none());
+
+    // Cancelled import is not started again and no longer runs
+    assert!(repository::imports::start(&context.db, import.id)
+        .await
+        .unwrap()
+        .is_none());
+    let progress = run(&context, &import, &source).await.unwrap();
+    assert_eq!(progress.failed_files, 0);
+
+    // Finishing the cancelled import doesn't change it
+    repository::imports::finish(&context.db, import.id, Err(Error::BadRequest("x".into())))
+        .await
+        .unwrap();
+    let import = repository.imports(user.id).get(import.id).await.unwrap();
+    assert_eq!(import.status, imports::Status::Cancelled);
+
+    // Root folder that can't be listed fails the whole import
+    let import = repository
+        .imports(user.id)
+        .create(create_import(imports::Source::Dropbox))
+        .await
+        .unwrap();
+    assert!(run(&context, &import, &MemorySource::default())
+        .await
+        .is_err());
+}
+
+#[test]
+fn create_import_is_validated() {
+    let mut data = create_import(imports::Source::Webdav);
+    data.location = Some("ftp://cloud.example.com".to_string());
+    assert!(data.into_tuple().is_err());
+
+    let mut data = create_import(imports::Source::Dropbox);
+    data.token = None;
+    assert!(data.into_tuple().is_err());
+
+    let mut data = create_import(imports::Source::Dropbox);
+    data.location = Some(" /Photos/2023/ ".to_string());
+    let (source, location, credentials, _) = data.into_tuple().unwrap();
+    assert_eq!(source, imports::Source::Dropbox);
+    assert_eq!(location, "/Photos/2023");
+    assert_eq!(credentials.token.as_deref(), Some("token"));
+}
+
+#[test]
+fn webdav_listing_is_parsed() {
+    let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+  <d:response>
+    <d:href>/remote.php/dav/files/user/</d:href>
+    <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>https://cloud.example.com/remote.php/dav/files/user/My%20Photos/</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype><d:collection/></d:resourcetype>
+        <d:getlastmodified>Tue, 05 Dec 2023 10:00:00 GMT</d:getlastmodified>
+      </d:prop>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/remote.php/dav/files/user/caf%C3%A9.txt</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype/>
+        <d:getcontentlength>42</d:getcontentlength>
+        <d:getcontenttype>text/plain</d:getcontenttype>
+        <d:getlastmodified>Tue, 05 Dec 2023 10:00:00 GMT</d:getlastmodified>
+      </d:prop>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+    let entries = parse_multistatus(xml).unwrap();
+    assert_eq!(entries.len(), 3);
+
+    assert_eq!(entries[1].path, "/remote.php/dav/files/user/My%20Photos/");
+    assert_eq!(entries[1].name, "My Photos");
+    assert!(entries[1].is_dir);
+    assert_eq!(entries[1].size, None);
+
+    assert_eq!(entries[2].name, "café.txt");
+    assert!(!entries[2].is_dir);
+    assert_eq!(entries[2].size, Some(42));
+    assert_eq!(entries[2].mime.as_deref(), Some("text/plain"));
+    assert_eq!(entries[2].modified_at, Some(1_701_770_400));
+}
+
+#[test]
+fn dropbox_listing_is_parsed() {
+    let json = r#"{
+        "entries": [
+            {".tag": "folder", "name": "Photos", "path_display": "/Photos", "id": "id:a"},
+            {
+                ".tag": "file",
+                "name": "a.jpg",
+                "path_display": "/a.jpg",
+                "size": 1024,
+                "client_modified": "2023-12-05T10:00:00Z"
+            },
+            {".tag": "deleted", "name": "gone.txt", "path_display": "/gone.txt"}
+        ],
+        "cursor": "cursor",
+        "has_more": false
+    }"#;
+
+    let entries = parse_list_folder(json).unwrap().entries();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].is_dir);
+    assert_eq!(entries[0].path, "/Photos");
+    assert_eq!(entries[1].size, Some(1024));
+    assert_eq!(entries[1].modified_at, Some(1_701_770_400));
+}
@@ -0,0 +1,73 @@
+use context::Context;
+use error::{AppResult, Error};
+use serde_json::json;
+
+use crate::data::{create_file::CreateFile, limits};
+
+fn file(value: serde_json::Value) -> CreateFile {
+    let mut file = json!({
+        "encrypted_key": cryptfns::base64::encode("key"),
+        "encrypted_name": cryptfns::hex::encode("file.txt"),
+        "name_hash": cryptfns::sha256::digest("file.txt"),
+        "search_tokens_hashed": ["token:1"],
+        "mime": "text/plain",
+    });
+    file.as_object_mut()
+        .unwrap()
+        .extend(value.as_object().unwrap().clone());
+
+    serde_json::from_value(file).unwrap()
+}
+
+fn messages(result: AppResult<()>, field: &str) -> Vec<String> {
+    match result {
+        Err(Error::Validation(errors)) => errors.get_error(field).unwrap().get_errors(),
+        _ => panic!("expected the validation error"),
+    }
+}
+
+#[actix_web::test]
+async fn file_metadata_is_checked_for_its_shape_and_size() {
+    let context = Context::mock_sqlite().await;
+    let config = &context.config.server;
+
+    limits::check_file(config, &file(json!({}))).unwrap();
+
+    let result = limits::check_file(config, &file(json!({ "name_hash": "file.txt" })));
+    assert_eq!(messages(result, "name_hash"), vec!["invalid_name_hash"]);
+
+    let result = limits::check_file(config, &file(json!({ "encrypted_name": "file.txt" })));
+    assert_eq!(messages(result, "encrypted_name"), vec!["invalid_hex"]);
+
+    let long = "ab".repeat(config.metadata_max_length);
+    let result = limits::check_file(config, &file(json!({ "encrypted_name": long })));
+    assert_eq!(
+        messages(result, "encrypted_name"),
+        vec![format!("max_length:{}", config.metadata_max_length)]
+    );
+
+    let result = limits::check_file(config, &file(json!({ "encrypted_key": "not a key!" })));
+    assert_eq!(messages(result, "encrypted_key"), vec!["invalid_encoding"]);
+
+    let tokens = vec!["token:1"; config.search_tokens_max + 1];
+    let result = limits::check_file(config, &file(json!({ "search_tokens_hashed": tokens })));
+    assert_eq!(
+        messages(result, "search_tokens_hashed"),
+        vec![format!("max:{}", config.search_tokens_max)]
+    );
+}
+
+#[actix_web::test]
+async fn all_of_the_problems_are_reported_at_once() {
+    let context = Context::mock_sqlite().await;
+
+    let result = limits::check_file(
+        &context.config.server,
+        &file(json!({ "name_hash": "file.txt", "encrypted_name": "file.txt" })),
+    );
+
+    match result {
+        Err(Error::Validation(errors)) => assert_eq!(errors.len(), 2),
+        _ => panic!("expected the validation error"),
+    }
+}
@@ -0,0 +1,66 @@
+use context::Context;
+use fs::prelude::*;
+
+use crate::{mock::create_file, routes::manifest::checksums};
+
+#[actix_web::test]
+async fn file_manifest_has_checksums_of_the_stored_chunks() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "manifest@test.com", None).await;
+    let file = create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let fs = Fs::new(&context.config);
+    fs.push(&file, 0, b"first").await.unwrap();
+    fs.push(&file, 1, b"second").await.unwrap();
+
+    let manifest = checksums(&context.config, &file).await.unwrap();
+    let filename = file.filename().unwrap();
+
+    assert_eq!(manifest.id, file.id);
+    assert_eq!(manifest.chunks.len(), 2);
+    assert_eq!(manifest.chunks[1].chunk, 1);
+    assert_eq!(manifest.chunks[1].size, 6);
+    assert_eq!(
+        manifest.chunks[1].sha256,
+        cryptfns::sha256::digest("second".as_bytes())
+    );
+    assert_eq!(
+        manifest.encrypted_sha256,
+        cryptfns::sha256::digest("firstsecond".as_bytes())
+    );
+
+    let sha256sum = manifest.sha256sum();
+    let lines = sha256sum.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+        lines[0],
+        format!(
+            "{}  {}",
+            cryptfns::sha256::digest("first".as_bytes()),
+            filename.clone().with_chunk(0).with_extension(".enc")
+        )
+    );
+    assert_eq!(
+        lines[2],
+        format!(
+            "{}  {}",
+            manifest.encrypted_sha256,
+            filename.with_extension(".enc")
+        )
+    );
+
+    fs.purge(&file).await.unwrap();
+}
+
+#[actix_web::test]
+async fn directories_have_no_manifest() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "manifest-dir@test.com", None).await;
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+
+    assert!(checksums(&context.config, &dir).await.is_err());
+}
@@ -1,5 +1,48 @@
+pub(crate) mod archive;
+pub(crate) mod attributes;
+pub(crate) mod authorize;
+pub(crate) mod batch;
+pub(crate) mod blobs;
+pub(crate) mod cached;
+pub(crate) mod changes;
 pub(crate) mod create;
+pub(crate) mod dav;
+pub(crate) mod dedup;
 pub(crate) mod delete;
+pub(crate) mod escrow;
+#[cfg(feature = "mqtt")]
+pub(crate) mod events;
+pub(crate) mod exports;
+pub(crate) mod external_exports;
+pub(crate) mod file_requests;
+pub(crate) mod folder_templates;
+pub(crate) mod holds;
+pub(crate) mod imports;
+pub(crate) mod limits;
+pub(crate) mod manifest;
 pub(crate) mod move_many;
+pub(crate) mod name_hash;
+pub(crate) mod net_test;
+pub(crate) mod policies;
 pub(crate) mod rename;
+pub(crate) mod repair;
+pub(crate) mod restrictions;
+pub(crate) mod resume;
+pub(crate) mod rewrap;
+pub(crate) mod s3;
 pub(crate) mod search;
+pub(crate) mod shares;
+pub(crate) mod snapshot;
+pub(crate) mod sniff;
+pub(crate) mod spaces;
+pub(crate) mod thumbnails;
+pub(crate) mod tiering;
+pub(crate) mod transfer;
+pub(crate) mod transfers;
+pub(crate) mod trash;
+pub(crate) mod tree;
+pub(crate) mod upload;
+pub(crate) mod verdicts;
+pub(crate) mod versions;
+pub(crate) mod virtual_file;
+pub(crate) mod zip;
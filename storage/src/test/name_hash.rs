@@ -0,0 +1,110 @@
+use context::Context;
+use entity::{files, ActiveValue, EntityTrait};
+
+use crate::{
+    data::name_hash::{NameHashLookup, MAX_NAME_HASH_LOOKUP},
+    mock::create_file,
+    repository::Repository,
+};
+
+fn hash(name: &str) -> String {
+    cryptfns::sha256::digest(name.as_bytes())
+}
+
+#[actix_web::test]
+async fn lookup_many_name_hashes_in_folder() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "names@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let first = create_file(&context, &user, "a.txt", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    let second = create_file(&context, &user, "a.txt", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    let other_file = create_file(&context, &user, "b.txt", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    create_file(&context, &user, "c.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    // The first file is older, the name resolves to it
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(first.id),
+        created_at: ActiveValue::Set(1),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    let (parent_id, name_hashes) = NameHashLookup {
+        parent_id: Some(dir.id),
+        name_hashes: Some(vec![
+            hash("c.txt"),
+            hash("a.txt"),
+            hash("b.txt"),
+            hash("a.txt"),
+        ]),
+    }
+    .into_tuple()
+    .unwrap();
+    assert_eq!(name_hashes.len(), 3);
+
+    let matches = repository
+        .manage(user.id)
+        .by_names(name_hashes, parent_id)
+        .await
+        .unwrap();
+
+    assert_eq!(matches.len(), 3);
+    assert!(!matches[0].exists());
+    assert_eq!(matches[1].id, Some(first.id));
+    assert_eq!(matches[1].collisions, vec![second.id]);
+    assert_eq!(matches[2].id, Some(other_file.id));
+    assert!(matches[2].collisions.is_empty());
+
+    let single = repository
+        .manage(user.id)
+        .by_name(&hash("a.txt"), Some(dir.id))
+        .await
+        .unwrap();
+    assert_eq!(single.id, first.id);
+
+    // Root of the user and the files of other users are not mixed in
+    let matches = repository
+        .manage(user.id)
+        .by_names(vec![hash("c.txt"), hash("a.txt")], None)
+        .await
+        .unwrap();
+    assert!(matches[0].exists());
+    assert!(!matches[1].exists());
+
+    let matches = repository
+        .manage(other.id)
+        .by_names(vec![hash("a.txt")], Some(dir.id))
+        .await
+        .unwrap();
+    assert!(!matches[0].exists());
+}
+
+#[test]
+fn name_hash_lookup_is_limited() {
+    let empty = NameHashLookup {
+        parent_id: None,
+        name_hashes: Some(vec![]),
+    };
+    assert!(empty.into_tuple().is_err());
+
+    let too_many = NameHashLookup {
+        parent_id: None,
+        name_hashes: Some((0..=MAX_NAME_HASH_LOOKUP).map(|i| i.to_string()).collect()),
+    };
+    assert!(too_many.into_tuple().is_err());
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use crate::data::net_test::{NetTest, NetTestResult, DEFAULT_NET_TEST_BYTES, MAX_NET_TEST_BYTES};
+
+#[test]
+fn net_test_size_is_limited() {
+    assert_eq!(
+        NetTest::default().into_value().unwrap(),
+        DEFAULT_NET_TEST_BYTES
+    );
+
+    let size = |size| NetTest { size: Some(size) }.into_value();
+
+    assert_eq!(size(1024).unwrap(), 1024);
+    assert!(size(0).is_err());
+    assert!(size(MAX_NET_TEST_BYTES + 1).is_err());
+}
+
+#[test]
+fn net_test_result_calculates_bytes_per_second() {
+    let result = NetTestResult::new(10 * 1024 * 1024, Duration::from_millis(500));
+
+    assert_eq!(result.duration_ms, 500);
+    assert_eq!(result.bytes_per_second, 20 * 1024 * 1024);
+
+    assert_eq!(NetTestResult::new(100, Duration::ZERO).bytes_per_second, 0);
+}
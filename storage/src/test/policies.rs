@@ -0,0 +1,153 @@
+use context::Context;
+use entity::user_files::Permission;
+
+use crate::{data::policy::Policy, mock::create_file, repository::Repository};
+
+#[actix_web::test]
+async fn folder_policies_are_combined_with_the_folders_above() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let user2 = entity::mock::create_user(&context.db, "second@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let inner = create_file(&context, &user, "inner", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", Some(inner.id), Some("text/plain"))
+        .await
+        .unwrap();
+
+    let policy = crate::effective_policy(&context.db, file.id).await.unwrap();
+    assert_eq!(policy, Policy::default());
+
+    let expiring = |days| Policy {
+        link_expires_in_days: Some(days),
+        ..Default::default()
+    };
+    let disabled = Policy {
+        links_disabled: true,
+        ..Default::default()
+    };
+
+    let policies = repository.policies(user.id);
+    policies.set(dir.id, expiring(30)).await.unwrap();
+    policies.set(inner.id, expiring(7)).await.unwrap();
+
+    // Only folders of the owner can have a policy
+    assert!(policies.set(file.id, disabled.clone()).await.is_err());
+    assert!(repository
+        .policies(user2.id)
+        .set(dir.id, disabled.clone())
+        .await
+        .is_err());
+
+    let policy = crate::effective_policy(&context.db, file.id).await.unwrap();
+    assert!(!policy.links_disabled);
+    assert_eq!(policy.link_expires_in_days, Some(7));
+
+    let expires_at = policy.link_expires_at(None).unwrap().unwrap();
+    assert!(policy.link_expires_at(Some(expires_at + 60)).is_err());
+    assert_eq!(
+        policy.link_expires_at(Some(expires_at - 60)).unwrap(),
+        Some(expires_at - 60)
+    );
+
+    policies.set(dir.id, disabled).await.unwrap();
+
+    let policy = crate::effective_policy(&context.db, file.id).await.unwrap();
+    assert!(policy.links_disabled);
+    assert!(policy.link_expires_at(None).is_err());
+
+    policies.delete(dir.id).await.unwrap();
+    policies.delete(inner.id).await.unwrap();
+
+    assert!(policies.get(dir.id).await.unwrap().is_none());
+    let policy = crate::effective_policy(&context.db, file.id).await.unwrap();
+    assert_eq!(policy, Policy::default());
+}
+
+#[actix_web::test]
+async fn folder_policy_requires_link_passwords_and_stops_resharing() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let manager = entity::mock::create_user(&context.db, "manager@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let dir = create_file(&context, &owner, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let inner = create_file(&context, &owner, "inner", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &owner, "file", Some(inner.id), Some("text/plain"))
+        .await
+        .unwrap();
+
+    repository
+        .shares(owner.id)
+        .create(
+            file.id,
+            manager.id,
+            "key".to_string(),
+            vec![],
+            Permission::Manage,
+        )
+        .await
+        .unwrap();
+
+    let policies = repository.policies(owner.id);
+    policies
+        .set(
+            dir.id,
+            Policy {
+                links_require_password: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    policies
+        .set(
+            inner.id,
+            Policy {
+                resharing_disabled: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let policy = crate::effective_policy(&context.db, file.id).await.unwrap();
+    assert!(policy.links_require_password);
+    assert!(policy.resharing_disabled);
+    assert!(policy.check_link_password(false).is_err());
+    policy.check_link_password(true).unwrap();
+
+    // Managers can't share it further, the owner still can
+    assert!(repository
+        .shares(manager.id)
+        .create(
+            file.id,
+            other.id,
+            "key".to_string(),
+            vec![],
+            Permission::Read
+        )
+        .await
+        .is_err());
+    repository
+        .shares(owner.id)
+        .create(
+            file.id,
+            other.id,
+            "key".to_string(),
+            vec![],
+            Permission::Read,
+        )
+        .await
+        .unwrap();
+}
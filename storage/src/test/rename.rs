@@ -1,4 +1,5 @@
 use context::Context;
+use error::Error;
 
 use crate::{data::rename::Rename, mock::create_file, repository::Repository};
 
@@ -31,3 +32,43 @@ async fn create_file_and_rename_it() {
 
     assert_eq!(renamed.name_hash, "dir2");
 }
+
+#[actix_web::test]
+async fn rename_keeps_names_unique_in_the_directory() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let first = create_file(&context, &user, "first", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    create_file(&context, &user, "second", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let rename = |name: &str| Rename {
+        name_hash: Some(cryptfns::sha256::digest(name.as_bytes())),
+        encrypted_name: Some("encrypted".to_string()),
+        search_tokens_hashed: None,
+    };
+
+    let taken = repository
+        .manage(user.id)
+        .rename(first.id, rename("second"))
+        .await;
+    assert_eq!(
+        taken.unwrap_err(),
+        Error::BadRequest("file_already_exists".to_string())
+    );
+
+    // Its own name can be sent again with the name encrypted differently
+    let renamed = repository
+        .manage(user.id)
+        .rename(first.id, rename("first"))
+        .await
+        .unwrap();
+    assert_eq!(renamed.name_hash, first.name_hash);
+    assert_eq!(renamed.encrypted_name, "encrypted");
+}
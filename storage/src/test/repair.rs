@@ -0,0 +1,116 @@
+use context::Context;
+use fs::prelude::*;
+use jobs::worker::Handler;
+
+use crate::{
+    jobs::{ContentSwap, PurgeFile, RepairChunk, RepairChunks, SwapContent},
+    mock::create_file,
+    repository::Repository,
+};
+
+#[actix_web::test]
+async fn damaged_chunks_are_restored_from_the_replica() {
+    let mut context = Context::mock_sqlite().await;
+    let replica_dir =
+        std::env::temp_dir().join(format!("hoodik-replica-{}", entity::Uuid::new_v4()));
+    std::fs::create_dir_all(&replica_dir).unwrap();
+    context.config.app.replica_dir = Some(replica_dir.to_str().unwrap().to_string());
+
+    let user = entity::mock::create_user(&context.db, "replica@test.com", None).await;
+    let mut file = create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    file.chunks = Some(2);
+
+    let fs = Fs::new(&context.config);
+    let replica = fs.replica().unwrap();
+
+    // Chunks are mirrored to the replica as they are uploaded
+    fs.push(&file, 0, b"first").await.unwrap();
+    fs.push(&file, 1, b"second").await.unwrap();
+    Repository::new(&context.db)
+        .manage(user.id)
+        .finish(&file)
+        .await
+        .unwrap();
+    assert_eq!(replica.pull(&file, 1).await.unwrap(), b"second");
+    assert!(fs.damaged(&file, &[0, 1]).await.unwrap().is_empty());
+
+    // Missing chunk and the chunk that was cut short on the default provider
+    let filename = file.filename().unwrap();
+    std::fs::remove_file(format!(
+        "{}/{}",
+        context.config.app.data_dir,
+        filename.clone().with_chunk(0)
+    ))
+    .unwrap();
+    std::fs::write(
+        format!("{}/{}", context.config.app.data_dir, filename.with_chunk(1)),
+        b"sec",
+    )
+    .unwrap();
+
+    let damaged = fs.damaged(&file, &[0, 1]).await.unwrap();
+    assert_eq!(damaged, vec![0, 1]);
+
+    let payload = serde_json::to_string(&RepairChunk {
+        id: file.id,
+        created_at: file.created_at,
+        chunks: damaged,
+    })
+    .unwrap();
+    RepairChunks.handle(&context, &payload).await.unwrap();
+
+    assert!(fs.damaged(&file, &[0, 1]).await.unwrap().is_empty());
+    assert_eq!(fs.pull(&file, 0).await.unwrap(), b"first");
+    assert_eq!(fs.pull(&file, 1).await.unwrap(), b"second");
+
+    // Purging removes the chunks from both of the providers
+    fs.purge(&file).await.unwrap();
+    assert!(!replica.exists(&file, 0).await.unwrap());
+
+    std::fs::remove_dir_all(replica_dir).unwrap();
+}
+
+#[actix_web::test]
+async fn committed_content_is_swapped_in_by_the_worker() {
+    let context = Context::mock_sqlite().await;
+
+    let user = entity::mock::create_user(&context.db, "swap@test.com", None).await;
+    let file = create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let fs = Fs::new(&context.config);
+    fs.push(&file, 0, b"previous").await.unwrap();
+
+    // Previous content is linked to the version before the change is committed
+    let created_at = chrono::Utc::now().timestamp();
+    let kept = PurgeFile {
+        id: entity::Uuid::new_v4(),
+        created_at,
+    };
+    let staged = PurgeFile {
+        id: entity::Uuid::new_v4(),
+        created_at,
+    };
+    fs.link(&file, &kept).await.unwrap();
+    fs.push(&staged, 0, b"next").await.unwrap();
+
+    let payload = serde_json::to_string(&ContentSwap {
+        file: PurgeFile {
+            id: file.id,
+            created_at: file.created_at,
+        },
+        staged: staged.clone(),
+    })
+    .unwrap();
+    SwapContent.handle(&context, &payload).await.unwrap();
+
+    assert_eq!(fs.pull(&file, 0).await.unwrap(), b"next");
+    assert_eq!(fs.pull(&kept, 0).await.unwrap(), b"previous");
+    assert!(!fs.exists(&staged, 0).await.unwrap());
+
+    fs.purge(&file).await.unwrap();
+    fs.purge(&kept).await.unwrap();
+}
@@ -0,0 +1,70 @@
+use context::Context;
+use serde_json::json;
+
+use crate::{data::create_file::CreateFile, repository::restrictions};
+
+fn file(mime: &str, size: Option<i64>) -> CreateFile {
+    serde_json::from_value(json!({ "mime": mime, "size": size })).unwrap()
+}
+
+#[actix_web::test]
+async fn files_are_restricted_by_mime_and_size() {
+    let context = Context::mock_sqlite().await;
+
+    // Nothing is restricted until the admin sets it
+    restrictions::check(&context, &file("application/x-msdownload", Some(100)))
+        .await
+        .unwrap();
+
+    context.settings.inner().await.files = serde_json::from_value(json!({
+        "mime_blacklist": { "rules": ["application/x-msdownload", "*executable*"] },
+        "max_sizes": [
+            { "mime": "video/*", "max_bytes": 1000 },
+            { "mime": "*", "max_bytes": 100 },
+        ],
+    }))
+    .unwrap();
+
+    for mime in [
+        "application/x-msdownload",
+        "Application/X-Executable; charset=binary",
+    ] {
+        assert!(restrictions::check(&context, &file(mime, Some(10)))
+            .await
+            .is_err());
+    }
+
+    restrictions::check(&context, &file("video/mp4", Some(1000)))
+        .await
+        .unwrap();
+    assert!(
+        restrictions::check(&context, &file("video/mp4", Some(1001)))
+            .await
+            .is_err()
+    );
+    assert!(
+        restrictions::check(&context, &file("text/plain", Some(101)))
+            .await
+            .is_err()
+    );
+
+    // Directories have neither the mime type nor the size to check
+    restrictions::check(&context, &file("dir", None))
+        .await
+        .unwrap();
+
+    context.settings.inner().await.files = serde_json::from_value(json!({
+        "mime_whitelist": { "rules": ["image/*", "application/pdf"] },
+    }))
+    .unwrap();
+
+    restrictions::check(&context, &file("image/png", Some(10)))
+        .await
+        .unwrap();
+    restrictions::check(&context, &file("application/pdf", Some(10)))
+        .await
+        .unwrap();
+    assert!(restrictions::check(&context, &file("text/html", Some(10)))
+        .await
+        .is_err());
+}
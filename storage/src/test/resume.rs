@@ -0,0 +1,34 @@
+use entity::Uuid;
+use error::Error;
+
+use crate::data::resume::ResumeClaims;
+
+#[test]
+fn resume_token_is_only_valid_with_the_secret() {
+    let user_id = Uuid::new_v4();
+    let file_id = Uuid::new_v4();
+    let device = Uuid::new_v4();
+
+    let token = ResumeClaims::new(user_id, file_id, device)
+        .encode("secret")
+        .unwrap();
+
+    let claims = ResumeClaims::decode(&token, "secret").unwrap();
+    assert_eq!(claims.sub, user_id);
+    assert_eq!(claims.file_id, file_id);
+    assert_eq!(claims.device, device);
+
+    let error = ResumeClaims::decode(&token, "other").unwrap_err();
+    assert_eq!(error, Error::BadRequest("invalid_resume_token".to_string()));
+}
+
+#[test]
+fn expired_resume_token_is_rejected() {
+    let mut claims = ResumeClaims::new(Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    claims.exp = chrono::Utc::now().timestamp() - 1;
+
+    let token = claims.encode("secret").unwrap();
+
+    let error = ResumeClaims::decode(&token, "secret").unwrap_err();
+    assert_eq!(error, Error::BadRequest("invalid_resume_token".to_string()));
+}
@@ -0,0 +1,104 @@
+use context::Context;
+use entity::{user_files, EntityTrait};
+
+use crate::{data::rewrap::Rewrapped, mock::create_file, repository::Repository};
+
+#[actix_web::test]
+async fn revoked_share_is_rewrapped_for_remaining_users() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let revoked = entity::mock::create_user(&context.db, "revoked@test.com", None).await;
+    let kept = entity::mock::create_user(&context.db, "kept@test.com", None).await;
+
+    let file = create_file(&context, &owner, "shared", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    entity::mock::create_share(&context.db, file.id, &revoked, "key").await;
+    let kept_share = entity::mock::create_share(&context.db, file.id, &kept, "key")
+        .await
+        .id;
+
+    // Only the owner revokes the shares of the file
+    assert!(repository
+        .shares(kept.id)
+        .revoke(file.id, revoked.id)
+        .await
+        .is_err());
+
+    let job = repository
+        .shares(owner.id)
+        .revoke(file.id, revoked.id)
+        .await
+        .unwrap();
+    assert_eq!(job.user_id, owner.id);
+    assert_eq!(job.file_id, Some(file.id));
+
+    assert!(repository.query(revoked.id).get(file.id).await.is_err());
+
+    // Other users cannot see the job of the owner
+    assert!(repository.rewrap(kept.id).get(job.id).await.is_err());
+
+    let jobs = repository.rewrap(owner.id).find().await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].pending, 2);
+
+    let entries = repository
+        .rewrap(owner.id)
+        .entries(job.id, 100)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|entry| entry.user_id != revoked.id));
+
+    let kept_entry = entries
+        .iter()
+        .find(|entry| entry.user_file_id == kept_share)
+        .unwrap();
+    assert_eq!(kept_entry.pubkey, kept.pubkey);
+
+    let progress = repository
+        .rewrap(owner.id)
+        .complete(
+            job.id,
+            vec![Rewrapped {
+                user_file_id: kept_share,
+                encrypted_key: "rewrapped".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+    assert_eq!(progress.pending, 1);
+    assert!(progress.job.completed_at.is_none());
+
+    let key = user_files::Entity::find_by_id(kept_share)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .unwrap()
+        .encrypted_key;
+    assert_eq!(key, "rewrapped");
+
+    let rest = repository
+        .rewrap(owner.id)
+        .entries(job.id, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| Rewrapped {
+            user_file_id: entry.user_file_id,
+            encrypted_key: "rewrapped".to_string(),
+        })
+        .collect();
+
+    let done = repository
+        .rewrap(owner.id)
+        .complete(job.id, rest)
+        .await
+        .unwrap();
+    assert_eq!(done.pending, 0);
+    assert!(done.job.completed_at.is_some());
+
+    assert!(repository.rewrap(owner.id).find().await.unwrap().is_empty());
+}
@@ -0,0 +1,426 @@
+use std::collections::BTreeMap;
+
+use actix_web::{test::TestRequest, web::Bytes};
+use chrono::{Duration, Utc};
+use context::Context;
+use error::{AppResult, Error};
+use fs::{prelude::*, s3::Signer};
+use futures::{Stream, StreamExt};
+use reqwest::Url;
+
+use crate::{
+    data::{app_file::AppFile, s3::ListObjects},
+    import::Sealer,
+    repository::Repository,
+    s3::{self, signature::Authorization, Gateway},
+};
+
+fn signed(uri: &str, secret: &str, time: chrono::DateTime<Utc>) -> TestRequest {
+    signed_headers(
+        uri,
+        secret,
+        time,
+        &["host", "x-amz-content-sha256", "x-amz-date"],
+    )
+}
+
+/// Request that carries all the headers but only signs the given ones
+fn signed_headers(
+    uri: &str,
+    secret: &str,
+    time: chrono::DateTime<Utc>,
+    names: &[&str],
+) -> TestRequest {
+    let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut headers = BTreeMap::new();
+    headers.insert("host".to_string(), "localhost:5443".to_string());
+    headers.insert(
+        "x-amz-content-sha256".to_string(),
+        fs::s3::UNSIGNED_PAYLOAD.to_string(),
+    );
+    headers.insert("x-amz-date".to_string(), amz_date.clone());
+    headers.retain(|name, _| names.contains(&name.as_str()));
+
+    let signer = Signer {
+        access_key_id: "HKACCESSKEY".to_string(),
+        secret_access_key: secret.to_string(),
+        region: "us-east-1".to_string(),
+    };
+    let url = Url::parse(&format!("http://localhost:5443{}", uri)).unwrap();
+
+    TestRequest::get()
+        .uri(uri)
+        .insert_header(("host", "localhost:5443"))
+        .insert_header(("x-amz-content-sha256", fs::s3::UNSIGNED_PAYLOAD))
+        .insert_header(("x-amz-date", amz_date))
+        .insert_header(("Authorization", signer.authorization("GET", &url, &headers)))
+}
+
+fn authorization(req: &actix_web::HttpRequest) -> Authorization {
+    let header = req
+        .headers()
+        .get("Authorization")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    Authorization::parse(header).unwrap()
+}
+
+fn body(content: &'static [u8]) -> impl Stream<Item = AppResult<Bytes>> + Unpin {
+    futures::stream::iter(vec![Ok(Bytes::from_static(content))])
+}
+
+async fn content<S>(stream: S) -> Vec<u8>
+where
+    S: Stream<Item = AppResult<Bytes>>,
+{
+    let mut content = vec![];
+    let mut stream = Box::pin(stream);
+
+    while let Some(bytes) = stream.next().await {
+        content.extend_from_slice(&bytes.unwrap());
+    }
+
+    content
+}
+
+fn keys(listing: &s3::Listing) -> Vec<&str> {
+    listing
+        .objects
+        .iter()
+        .map(|(object, _)| object.key.as_str())
+        .collect()
+}
+
+#[test]
+fn authorization_is_parsed_and_verified() {
+    let req = signed(
+        "/api/s3/backups?list-type=2&prefix=data%2F",
+        "secret",
+        Utc::now(),
+    )
+    .to_http_request();
+    let parsed = authorization(&req);
+
+    assert_eq!(parsed.access_key_id, "HKACCESSKEY");
+    assert_eq!(parsed.region, "us-east-1");
+    assert_eq!(
+        parsed.signed_headers,
+        vec!["host", "x-amz-content-sha256", "x-amz-date"]
+    );
+
+    // Unsigned payload has nothing to check the body against
+    assert_eq!(parsed.verify(&req, "secret").unwrap(), None);
+    assert_eq!(
+        parsed.verify(&req, "other").unwrap_err(),
+        Error::Forbidden("signature_does_not_match".to_string())
+    );
+
+    let req =
+        signed("/api/s3/backups", "secret", Utc::now() - Duration::hours(1)).to_http_request();
+    assert_eq!(
+        authorization(&req).verify(&req, "secret").unwrap_err(),
+        Error::Forbidden("request_time_too_skewed".to_string())
+    );
+
+    assert!(Authorization::parse("Bearer token").is_err());
+    assert!(Authorization::parse("AWS4-HMAC-SHA256 Credential=KEY/20240213/us-east-1/s3").is_err());
+}
+
+#[test]
+fn host_date_and_payload_hash_have_to_be_signed() {
+    let all = ["host", "x-amz-content-sha256", "x-amz-date"];
+
+    for omitted in all {
+        let names = all
+            .into_iter()
+            .filter(|name| *name != omitted)
+            .collect::<Vec<_>>();
+        let req = signed_headers("/api/s3/backups", "secret", Utc::now(), &names).to_http_request();
+        let parsed = authorization(&req);
+
+        assert_eq!(parsed.signed_headers.len(), 2);
+        assert_eq!(
+            parsed.verify(&req, "secret").unwrap_err(),
+            Error::Forbidden("required_header_not_signed".to_string()),
+            "{} was not signed",
+            omitted
+        );
+    }
+}
+
+#[test]
+fn location_is_decoded_from_the_path() {
+    assert_eq!(
+        s3::location("/api/s3/backups").unwrap(),
+        ("backups".to_string(), None)
+    );
+    assert_eq!(
+        s3::location("/api/s3/backups/").unwrap(),
+        ("backups".to_string(), None)
+    );
+    assert_eq!(
+        s3::location("/api/s3/backups/data/My%20File.txt").unwrap(),
+        ("backups".to_string(), Some("data/My File.txt".to_string()))
+    );
+
+    assert!(s3::location("/api/s3/").is_err());
+    assert!(s3::location("/api/storage/backups").is_err());
+}
+
+#[actix_web::test]
+async fn access_keys_are_managed_by_their_user() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "keys@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let repository = Repository::new(&context.db);
+    let (access_key, secret) = repository
+        .access_keys(user.id)
+        .create("restic".to_string())
+        .await
+        .unwrap();
+
+    assert!(access_key.access_key_id.starts_with("HK"));
+    assert_eq!(access_key.access_key_id.len(), 20);
+    assert_eq!(access_key.secret_access_key, secret);
+
+    let found =
+        crate::repository::access_keys::by_access_key_id(&context.db, &access_key.access_key_id)
+            .await
+            .unwrap();
+    assert_eq!(found.map(|found| found.id), Some(access_key.id));
+
+    // The secret is never listed
+    let listed = repository.access_keys(user.id).find().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(!serde_json::to_string(&listed).unwrap().contains(&secret));
+
+    assert!(repository
+        .access_keys(other.id)
+        .delete(access_key.id)
+        .await
+        .unwrap_err()
+        .is_not_found());
+
+    repository
+        .access_keys(user.id)
+        .delete(access_key.id)
+        .await
+        .unwrap();
+    assert!(repository
+        .access_keys(user.id)
+        .find()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[actix_web::test]
+async fn objects_are_stored_listed_and_deleted() {
+    let context = Context::mock_sqlite().await;
+    let fs = Fs::new(&context.config);
+
+    let private_key = cryptfns::rsa::private::generate().unwrap();
+    let public_key = cryptfns::rsa::public::from_private(&private_key).unwrap();
+    let public_key = cryptfns::rsa::public::to_string(&public_key).unwrap();
+
+    let user = entity::mock::create_user(&context.db, "s3@test.com", Some(public_key)).await;
+    let repository = Repository::new(&context.db);
+
+    let (access_key, _) = repository
+        .access_keys(user.id)
+        .create("rclone".to_string())
+        .await
+        .unwrap();
+    let gateway = Gateway::new(&context, access_key, user.clone());
+
+    assert!(gateway.bucket("backups").await.unwrap_err().is_not_found());
+    let bucket = gateway.create_bucket("backups").await.unwrap();
+    assert_eq!(
+        gateway.create_bucket("backups").await.unwrap().id,
+        bucket.id
+    );
+
+    let mut files: Vec<AppFile> = vec![];
+
+    for (key, content) in [
+        ("index/1", b"index".as_slice()),
+        ("data/b", b"second".as_slice()),
+        ("config", b"hello from the gateway".as_slice()),
+        ("data/a", b"first".as_slice()),
+    ] {
+        let body = futures::stream::iter(vec![Ok(Bytes::copy_from_slice(content))]);
+        let file = gateway
+            .put(
+                &bucket,
+                key,
+                "application/octet-stream".to_string(),
+                content.len() as i64,
+                body,
+                Some(cryptfns::sha256::digest(content)),
+            )
+            .await
+            .unwrap();
+        files.push(file);
+    }
+
+    let query = |query: ListObjects| query.into_value().unwrap();
+
+    let listing = gateway
+        .list(&bucket, &query(ListObjects::default()))
+        .await
+        .unwrap();
+    assert_eq!(
+        keys(&listing),
+        vec!["config", "data/a", "data/b", "index/1"]
+    );
+    assert!(!listing.truncated);
+
+    let listing = gateway
+        .list(
+            &bucket,
+            &query(ListObjects {
+                prefix: Some("data/".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(keys(&listing), vec!["data/a", "data/b"]);
+
+    let listing = gateway
+        .list(
+            &bucket,
+            &query(ListObjects {
+                delimiter: Some("/".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(keys(&listing), vec!["config"]);
+    assert_eq!(listing.prefixes, vec!["data/", "index/"]);
+
+    // Pages of a single key continue after the last key or prefix
+    let mut pages = vec![];
+    let mut token = None;
+
+    loop {
+        let listing = gateway
+            .list(
+                &bucket,
+                &query(ListObjects {
+                    list_type: Some("2".to_string()),
+                    delimiter: Some("/".to_string()),
+                    max_keys: Some(1),
+                    continuation_token: token.clone(),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+
+        pages.push(
+            listing
+                .objects
+                .iter()
+                .map(|(object, _)| object.key.clone())
+                .chain(listing.prefixes.iter().cloned())
+                .collect::<Vec<_>>(),
+        );
+
+        match listing.truncated {
+            true => token = listing.next.map(cryptfns::base64::encode),
+            false => break,
+        }
+    }
+    assert_eq!(pages, vec![vec!["config"], vec!["data/"], vec!["index/"]]);
+
+    let (object, file) = gateway.object(&bucket, "config").await.unwrap();
+    let whole = gateway.content(file.clone(), &object, 0, 21).unwrap();
+    assert_eq!(content(whole).await, b"hello from the gateway");
+    let range = gateway.content(file, &object, 6, 9).unwrap();
+    assert_eq!(content(range).await, b"from");
+
+    // Replaced object is moved to the trash
+    let replaced = gateway
+        .put(
+            &bucket,
+            "config",
+            "text/plain".to_string(),
+            7,
+            body(b"changed"),
+            None,
+        )
+        .await
+        .unwrap();
+    let (object, file) = gateway.object(&bucket, "config").await.unwrap();
+    assert_eq!(file.id, replaced.id);
+    assert_eq!(
+        content(gateway.content(file, &object, 0, 6).unwrap()).await,
+        b"changed"
+    );
+    assert_eq!(repository.trash(user.id).list().await.unwrap().len(), 1);
+
+    // Body that doesn't match the signed digest is not stored
+    let error = gateway
+        .put(
+            &bucket,
+            "tampered",
+            "text/plain".to_string(),
+            7,
+            body(b"changed"),
+            Some(cryptfns::sha256::digest("original")),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error, Error::BadRequest("bad_digest".to_string()));
+    assert!(gateway.object(&bucket, "tampered").await.is_err());
+
+    // Files in the bucket that aren't the objects are left alone
+    let sealer = Sealer::new(&context, user.clone()).await.unwrap();
+    sealer
+        .folder(&sealer.seal("notes").unwrap(), Some(bucket.id))
+        .await
+        .unwrap();
+    let error = gateway
+        .put(
+            &bucket,
+            "notes",
+            "text/plain".to_string(),
+            4,
+            body(b"note"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error, Error::Conflict("file_already_exists".to_string()));
+
+    // Other access keys of the user don't see the objects
+    let (other_key, _) = repository
+        .access_keys(user.id)
+        .create("restic".to_string())
+        .await
+        .unwrap();
+    let other = Gateway::new(&context, other_key, user.clone());
+    let listing = other
+        .list(&bucket, &query(ListObjects::default()))
+        .await
+        .unwrap();
+    assert!(listing.objects.is_empty());
+
+    gateway.delete(&bucket, "data/a").await.unwrap();
+    assert!(gateway
+        .object(&bucket, "data/a")
+        .await
+        .unwrap_err()
+        .is_not_found());
+    gateway.delete(&bucket, "data/a").await.unwrap();
+
+    files.push(replaced);
+    for file in files {
+        fs.purge(&file).await.unwrap();
+    }
+}
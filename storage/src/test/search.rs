@@ -1,6 +1,18 @@
+use std::sync::Arc;
+
 use context::Context;
+use entity::{files, group_members, groups, ActiveValue, EntityTrait, Uuid};
+use futures::TryStreamExt;
 
-use crate::{data::search::Search, mock::create_file, repository::Repository};
+use crate::{
+    data::{
+        app_file::AppFile,
+        query::Query,
+        search::{Cursor, Search},
+    },
+    mock::create_file,
+    repository::{tokens::search_stream, Repository},
+};
 
 #[actix_web::test]
 async fn create_token_and_get_it() {
@@ -65,8 +77,13 @@ async fn create_files_and_try_searching() {
     let search = Search {
         dir_id: None,
         search_tokens_hashed: Some(vec!["hello:1".to_string()]),
+        attributes: None,
+        mime: None,
+        order: None,
+        order_by: None,
         skip: None,
         limit: None,
+        cursor: None,
     };
 
     let mut results = repository.tokens(user.id).search(search).await.unwrap();
@@ -107,3 +124,477 @@ async fn create_files_and_try_getting_total_used_space() {
 
     assert_eq!(total, used_space)
 }
+
+#[actix_web::test]
+async fn group_members_share_the_group_quota() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+    let user2 = entity::mock::create_user(&context.db, "second@test.com", None).await;
+
+    create_file(&context, &user, "first", None, Some("application/json"))
+        .await
+        .unwrap();
+    create_file(&context, &user2, "second", None, Some("application/json"))
+        .await
+        .unwrap();
+
+    repository
+        .query(user.id)
+        .check_quota(None, 100)
+        .await
+        .unwrap();
+
+    let group_id = Uuid::new_v4();
+    groups::Entity::insert(groups::ActiveModel {
+        id: ActiveValue::Set(group_id),
+        name: ActiveValue::Set("team".to_string()),
+        quota: ActiveValue::Set(Some(250)),
+        created_at: ActiveValue::Set(0),
+        updated_at: ActiveValue::Set(0),
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+
+    for member in [&user, &user2] {
+        group_members::Entity::insert(group_members::ActiveModel {
+            group_id: ActiveValue::Set(group_id),
+            user_id: ActiveValue::Set(member.id),
+            role: ActiveValue::Set(group_members::Role::Editor),
+            created_at: ActiveValue::Set(0),
+        })
+        .exec_without_returning(&context.db)
+        .await
+        .unwrap();
+    }
+
+    let space = repository.query(user.id).group_space().await.unwrap();
+    assert_eq!(space.len(), 1);
+    assert_eq!(space[0].used_space, 200);
+
+    // Own quota would fit, the group doesn't have enough space left
+    repository
+        .query(user.id)
+        .check_quota(Some(1000), 50)
+        .await
+        .unwrap();
+    assert!(repository
+        .query(user.id)
+        .check_quota(Some(1000), 100)
+        .await
+        .is_err());
+}
+
+#[actix_web::test]
+async fn stream_search_results_in_batches() {
+    let context = Arc::new(Context::mock_sqlite().await);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    for name in ["hello one", "hello two", "hello three"] {
+        create_file(&context, &user, name, None, Some("dir"))
+            .await
+            .unwrap();
+    }
+
+    let search = Search {
+        search_tokens_hashed: Some(vec!["hello:1".to_string()]),
+        ..Default::default()
+    };
+
+    let batches = search_stream(context.clone(), user.id, search.clone())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(batches.concat().len(), 3);
+
+    let limited = Search {
+        limit: Some(2),
+        skip: Some(1),
+        ..search
+    };
+
+    let expected = Repository::new(&context.db)
+        .tokens(user.id)
+        .search(limited.clone())
+        .await
+        .unwrap();
+
+    let streamed = search_stream(context.clone(), user.id, limited)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .concat();
+
+    assert_eq!(
+        streamed.iter().map(|f| f.id).collect::<Vec<_>>(),
+        expected.iter().map(|f| f.id).collect::<Vec<_>>()
+    );
+}
+
+#[actix_web::test]
+async fn pages_never_skip_or_repeat_the_files() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    // Equal by the weight and by the size, only the creation tells them apart
+    let mut created = vec![];
+
+    for (created_at, name) in [
+        (40, "report four"),
+        (10, "report one"),
+        (30, "report three"),
+    ] {
+        let file = create_file(&context, &user, name, None, Some("text/plain"))
+            .await
+            .unwrap();
+
+        files::Entity::update(files::ActiveModel {
+            id: ActiveValue::Set(file.id),
+            created_at: ActiveValue::Set(created_at),
+            ..Default::default()
+        })
+        .exec(&context.db)
+        .await
+        .unwrap();
+
+        created.push((created_at, file.id));
+    }
+
+    created.sort();
+    let expected = created.iter().map(|(_, id)| *id).collect::<Vec<_>>();
+
+    let page = |skip| Search {
+        search_tokens_hashed: Some(vec!["report:1".to_string()]),
+        limit: Some(2),
+        skip: Some(skip),
+        ..Default::default()
+    };
+
+    let first = repository.tokens(user.id).search(page(0)).await.unwrap();
+
+    // Created while the client is going through the pages, it comes last
+    let latest = create_file(&context, &user, "report two", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let second = repository.tokens(user.id).search(page(2)).await.unwrap();
+
+    assert_eq!(
+        first
+            .iter()
+            .chain(second.iter())
+            .map(|f| f.id)
+            .collect::<Vec<_>>(),
+        [expected.clone(), vec![latest.id]].concat()
+    );
+
+    // Listing ordered by the size has the same order of the equal files
+    let listed = repository
+        .manage(user.id)
+        .find(Query {
+            order_by: Some("size".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        listed.children.iter().map(|f| f.id).collect::<Vec<_>>(),
+        [expected, vec![latest.id]].concat()
+    );
+}
+
+#[actix_web::test]
+async fn listing_and_search_are_paged_sorted_and_filtered() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    create_file(&context, &user, "archive", None, Some("dir"))
+        .await
+        .unwrap();
+    let mut files = vec![];
+    for (name, mime) in [
+        ("report a", "text/plain"),
+        ("report b", "image/png"),
+        ("report c", "image/jpeg"),
+    ] {
+        files.push(
+            create_file(&context, &user, name, None, Some(mime))
+                .await
+                .unwrap(),
+        );
+    }
+
+    let by_name_hash = |files: &[&AppFile]| {
+        let mut files = files.to_vec();
+        files.sort_by(|a, b| a.name_hash.cmp(&b.name_hash));
+        files.iter().map(|f| f.id).collect::<Vec<_>>()
+    };
+
+    let all = repository
+        .manage(user.id)
+        .find(Query::default())
+        .await
+        .unwrap();
+    assert_eq!(all.total, 4);
+    let expected = by_name_hash(&all.children.iter().collect::<Vec<_>>());
+
+    let page = repository
+        .manage(user.id)
+        .find(Query {
+            order_by: Some("name_hash".to_string()),
+            limit: Some(2),
+            offset: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page.total, 4);
+    assert_eq!(
+        page.children.iter().map(|f| f.id).collect::<Vec<_>>(),
+        expected[1..3]
+    );
+
+    let images = repository
+        .manage(user.id)
+        .find(Query {
+            mime: Some("image/*".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(images.total, 2);
+    assert!(images.children.iter().all(|f| f.mime.starts_with("image/")));
+
+    let search = Search {
+        search_tokens_hashed: Some(vec!["report:1".to_string()]),
+        ..Default::default()
+    };
+    let tokens = repository.tokens(user.id);
+    assert_eq!(tokens.count(search.clone()).await.unwrap(), 3);
+
+    let images = Search {
+        mime: Some("image/*".to_string()),
+        order_by: Some("name_hash".to_string()),
+        order: Some("desc".to_string()),
+        ..search.clone()
+    };
+    assert_eq!(tokens.count(images.clone()).await.unwrap(), 2);
+
+    let mut expected = by_name_hash(&[&files[1], &files[2]]);
+    expected.reverse();
+    let found = tokens.search(images).await.unwrap();
+    assert_eq!(found.iter().map(|f| f.id).collect::<Vec<_>>(), expected);
+
+    let exact = Search {
+        mime: Some("text/plain".to_string()),
+        ..search
+    };
+    let found = tokens.search(exact).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, files[0].id);
+
+    let invalid = Query {
+        order_by: Some("name".to_string()),
+        ..Default::default()
+    };
+    assert!(repository.manage(user.id).find(invalid).await.is_err());
+}
+
+#[actix_web::test]
+async fn search_is_paged_with_the_cursor() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let mut created = vec![];
+    for name in ["report a", "report b", "report c", "report d", "report e"] {
+        let file = create_file(&context, &user, name, None, Some("text/plain"))
+            .await
+            .unwrap();
+        created.push((file.created_at, file.id));
+    }
+    created.sort();
+
+    let search = |order: &str, cursor: Option<String>| Search {
+        search_tokens_hashed: Some(vec!["report:1".to_string()]),
+        order_by: Some("created_at".to_string()),
+        order: Some(order.to_string()),
+        limit: Some(2),
+        cursor,
+        ..Default::default()
+    };
+
+    for order in ["asc", "desc"] {
+        let mut expected = created.clone();
+        if order == "desc" {
+            // Files created at the same time are still ordered by their ids ascending
+            expected.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        }
+
+        let mut found = vec![];
+        let mut cursor = None;
+        loop {
+            let page = search(order, cursor);
+            let files = repository
+                .tokens(user.id)
+                .search(page.clone())
+                .await
+                .unwrap();
+            found.extend(files.iter().map(|f| (f.created_at, f.id)));
+
+            cursor = page.next_cursor(&files);
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(found, expected);
+    }
+
+    let cursor = Cursor {
+        created_at: created[0].0,
+        id: created[0].1,
+    };
+    assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+    assert!(Cursor::decode("nope").is_err());
+
+    let invalid = Search {
+        cursor: Some("nope".to_string()),
+        ..search("asc", None)
+    };
+    assert!(invalid.into_value().is_err());
+
+    let unordered = Search {
+        order_by: None,
+        ..search("asc", Some(cursor.encode()))
+    };
+    assert!(unordered.into_value().is_err());
+
+    let skipped = Search {
+        skip: Some(1),
+        ..search("asc", Some(cursor.encode()))
+    };
+    assert!(skipped.into_value().is_err());
+}
+
+#[actix_web::test]
+async fn search_is_filtered_by_kind_upload_time_and_size() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let mut files = vec![];
+    for (name, mime) in [
+        ("photos", "dir"),
+        ("beach", "image/png"),
+        ("trip", "video/mp4"),
+        ("notes", "text/plain"),
+        ("invoice", "application/pdf"),
+        (
+            "sheet",
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        ),
+    ] {
+        files.push(
+            create_file(&context, &user, name, None, Some(mime))
+                .await
+                .unwrap(),
+        );
+    }
+
+    // Each of the files was uploaded a day after the one before and is ten times bigger
+    for (i, file) in files.iter().enumerate() {
+        files::Entity::update(files::ActiveModel {
+            id: ActiveValue::Set(file.id),
+            created_at: ActiveValue::Set(1_000_000 + i as i64 * 86_400),
+            size: ActiveValue::Set(file.size.map(|_| 10_i64.pow(i as u32))),
+            ..Default::default()
+        })
+        .exec(&context.db)
+        .await
+        .unwrap();
+    }
+
+    let tokens = repository.tokens(user.id);
+    let found = |search: Search| {
+        let tokens = &tokens;
+        async move {
+            let mut ids = tokens
+                .search(search)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|f| f.id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        }
+    };
+    let ids = |indexes: &[usize]| {
+        let mut ids = indexes.iter().map(|i| files[*i].id).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    // Filters find the files on their own, without the tokens to search by
+    let kind = |kind: &str| Search {
+        kind: Some(kind.to_string()),
+        ..Default::default()
+    };
+    assert_eq!(found(kind("dir")).await, ids(&[0]));
+    assert_eq!(found(kind("image")).await, ids(&[1]));
+    assert_eq!(found(kind("video")).await, ids(&[2]));
+    assert!(found(kind("audio")).await.is_empty());
+    assert_eq!(found(kind("document")).await, ids(&[3, 4, 5]));
+    assert_eq!(tokens.count(kind("document")).await.unwrap(), 3);
+
+    let uploaded = Search {
+        uploaded_after: Some(1_000_000 + 86_400),
+        uploaded_before: Some(1_000_000 + 3 * 86_400),
+        ..Default::default()
+    };
+    assert_eq!(found(uploaded.clone()).await, ids(&[1, 2, 3]));
+
+    // Folders have no size, so they are never found by it
+    let sized = Search {
+        min_size: Some(100),
+        max_size: Some(10_000),
+        ..Default::default()
+    };
+    assert_eq!(found(sized.clone()).await, ids(&[2, 3, 4]));
+
+    let combined = Search {
+        search_tokens_hashed: Some(vec!["notes:1".to_string()]),
+        kind: Some("document".to_string()),
+        ..uploaded.clone()
+    };
+    assert_eq!(found(combined).await, ids(&[3]));
+
+    // Without anything to search by nothing is found
+    assert!(found(Search::default()).await.is_empty());
+
+    let invalid = Search {
+        kind: Some("spreadsheet".to_string()),
+        ..Default::default()
+    };
+    assert!(invalid.into_value().is_err());
+
+    let invalid = Search {
+        uploaded_after: Some(10),
+        uploaded_before: Some(5),
+        ..Default::default()
+    };
+    assert!(invalid.into_value().is_err());
+
+    let invalid = Search {
+        min_size: Some(10),
+        max_size: Some(5),
+        ..sized
+    };
+    assert!(invalid.into_value().is_err());
+}
@@ -0,0 +1,423 @@
+use chrono::Utc;
+use context::Context;
+use entity::{
+    group_members::{self, Role},
+    groups, jobs as queued,
+    user_files::{self, Permission},
+    ActiveValue, EntityTrait, Uuid,
+};
+use jobs::worker::Handler;
+
+use crate::{
+    data::{
+        query::Query,
+        shares::{MemberKeys, SharedKey},
+    },
+    jobs::{ExpireShares, RemindShares, ShareExpiration, EXPIRE_SHARE, REMIND_SHARE},
+    mock::create_file,
+    repository::Repository,
+};
+
+#[actix_web::test]
+async fn expired_share_gives_no_access_and_is_removed() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let user = entity::mock::create_user(&context.db, "user@test.com", None).await;
+
+    let file = create_file(&context, &owner, "shared", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let share_id = Uuid::new_v4();
+    user_files::Entity::insert(user_files::ActiveModel {
+        id: ActiveValue::Set(share_id),
+        file_id: ActiveValue::Set(file.id),
+        user_id: ActiveValue::Set(user.id),
+        encrypted_key: ActiveValue::Set("key".to_string()),
+        is_owner: ActiveValue::Set(false),
+        created_at: ActiveValue::Set(0),
+        starts_at: ActiveValue::Set(None),
+        expires_at: ActiveValue::Set(None),
+        attributes: ActiveValue::NotSet,
+        permission: ActiveValue::NotSet,
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+
+    repository.query(user.id).get(file.id).await.unwrap();
+
+    // Only the owner manages the shares of the file
+    assert!(repository
+        .shares(user.id)
+        .set_schedule(file.id, user.id, None, None)
+        .await
+        .is_err());
+
+    let expires_at = Utc::now().timestamp() + 7 * 24 * 3600;
+    let share = repository
+        .shares(owner.id)
+        .set_schedule(file.id, user.id, None, Some(expires_at))
+        .await
+        .unwrap();
+    assert_eq!(share.expires_at, Some(expires_at));
+    assert_eq!(share.email, "user@test.com");
+
+    // Removal and the reminder before it are scheduled
+    let kinds = queued::Entity::find()
+        .all(&context.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|job| job.kind)
+        .collect::<Vec<_>>();
+    assert!(kinds.contains(&EXPIRE_SHARE.to_string()));
+    assert!(kinds.contains(&REMIND_SHARE.to_string()));
+
+    let payload = |expires_at| {
+        serde_json::to_string(&ShareExpiration {
+            id: share_id,
+            expires_at,
+        })
+        .unwrap()
+    };
+
+    RemindShares
+        .handle(&context, &payload(expires_at))
+        .await
+        .unwrap();
+
+    let expired_at = Utc::now().timestamp() - 1;
+    user_files::Entity::update(user_files::ActiveModel {
+        id: ActiveValue::Set(share_id),
+        expires_at: ActiveValue::Set(Some(expired_at)),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    assert!(repository.query(user.id).get(file.id).await.is_err());
+    repository.query(owner.id).get(file.id).await.unwrap();
+
+    // Job scheduled for the previous expiration leaves the share alone
+    ExpireShares
+        .handle(&context, &payload(expires_at))
+        .await
+        .unwrap();
+    assert!(user_files::Entity::find_by_id(share_id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .is_some());
+
+    ExpireShares
+        .handle(&context, &payload(expired_at))
+        .await
+        .unwrap();
+    assert!(user_files::Entity::find_by_id(share_id)
+        .one(&context.db)
+        .await
+        .unwrap()
+        .is_none());
+
+    let shares = repository.shares(owner.id).find(file.id).await.unwrap();
+    assert!(shares.is_empty());
+}
+
+#[actix_web::test]
+async fn scheduled_share_gives_access_once_it_starts() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let user = entity::mock::create_user(&context.db, "user@test.com", None).await;
+
+    let file = create_file(&context, &owner, "release", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    user_files::Entity::insert(user_files::ActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        file_id: ActiveValue::Set(file.id),
+        user_id: ActiveValue::Set(user.id),
+        encrypted_key: ActiveValue::Set("key".to_string()),
+        is_owner: ActiveValue::Set(false),
+        created_at: ActiveValue::Set(0),
+        starts_at: ActiveValue::Set(None),
+        expires_at: ActiveValue::Set(None),
+        attributes: ActiveValue::NotSet,
+        permission: ActiveValue::NotSet,
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+
+    let starts_at = Utc::now().timestamp() + 3600;
+    let share = repository
+        .shares(owner.id)
+        .set_schedule(file.id, user.id, Some(starts_at), None)
+        .await
+        .unwrap();
+    assert_eq!(share.starts_at, Some(starts_at));
+
+    assert!(repository.query(user.id).get(file.id).await.is_err());
+    repository.query(owner.id).get(file.id).await.unwrap();
+
+    repository
+        .shares(owner.id)
+        .set_schedule(file.id, user.id, Some(Utc::now().timestamp() - 1), None)
+        .await
+        .unwrap();
+
+    repository.query(user.id).get(file.id).await.unwrap();
+}
+
+#[actix_web::test]
+async fn folder_is_shared_with_everything_inside() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let user = entity::mock::create_user(&context.db, "user@test.com", None).await;
+
+    let dir = create_file(&context, &owner, "photos", None, Some("dir"))
+        .await
+        .unwrap();
+    let photo = create_file(&context, &owner, "photo", Some(dir.id), Some("image/png"))
+        .await
+        .unwrap();
+
+    let recipient = repository
+        .shares(owner.id)
+        .recipient("user@test.com")
+        .await
+        .unwrap();
+    assert_eq!(recipient.id, user.id);
+
+    // Only the owner shares it, and only with the keys of everything inside
+    assert!(repository
+        .shares(user.id)
+        .create(
+            dir.id,
+            owner.id,
+            "key".to_string(),
+            vec![],
+            Permission::Read
+        )
+        .await
+        .is_err());
+    assert!(repository
+        .shares(owner.id)
+        .create(dir.id, user.id, "key".to_string(), vec![], Permission::Read)
+        .await
+        .is_err());
+
+    let keys = vec![SharedKey {
+        id: photo.id,
+        encrypted_key: "photo-key".to_string(),
+    }];
+    let share = repository
+        .shares(owner.id)
+        .create(
+            dir.id,
+            user.id,
+            "dir-key".to_string(),
+            keys.clone(),
+            Permission::Read,
+        )
+        .await
+        .unwrap();
+    assert_eq!(share.user_id, user.id);
+
+    assert!(repository
+        .shares(owner.id)
+        .create(
+            dir.id,
+            user.id,
+            "dir-key".to_string(),
+            keys,
+            Permission::Read
+        )
+        .await
+        .is_err());
+
+    let shared = |dir_id: Option<Uuid>| Query {
+        dir_id: dir_id.map(|id| id.to_string()),
+        is_owner: Some(false),
+        ..Default::default()
+    };
+
+    let received = repository.manage(user.id).find(shared(None)).await.unwrap();
+    assert_eq!(received.children.len(), 1);
+    assert_eq!(received.children[0].id, dir.id);
+
+    let inside = repository
+        .manage(user.id)
+        .find(shared(Some(dir.id)))
+        .await
+        .unwrap();
+    assert_eq!(inside.children.len(), 1);
+    assert_eq!(inside.children[0].encrypted_key, "photo-key");
+
+    // The user can read it but not change it
+    assert!(repository
+        .shares(user.id)
+        .revoke(dir.id, user.id)
+        .await
+        .is_err());
+
+    repository
+        .shares(owner.id)
+        .revoke(dir.id, user.id)
+        .await
+        .unwrap();
+    assert!(repository.query(user.id).get(photo.id).await.is_err());
+}
+
+#[actix_web::test]
+async fn folder_is_shared_with_the_group_by_permission() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let alice = entity::mock::create_user(&context.db, "alice@test.com", None).await;
+    let bob = entity::mock::create_user(&context.db, "bob@test.com", None).await;
+    let stranger = entity::mock::create_user(&context.db, "stranger@test.com", None).await;
+
+    let group_id = Uuid::new_v4();
+    groups::Entity::insert(groups::ActiveModel {
+        id: ActiveValue::Set(group_id),
+        name: ActiveValue::Set("team".to_string()),
+        quota: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(0),
+        updated_at: ActiveValue::Set(0),
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+
+    for user in [&owner, &alice, &bob] {
+        group_members::Entity::insert(group_members::ActiveModel {
+            group_id: ActiveValue::Set(group_id),
+            user_id: ActiveValue::Set(user.id),
+            role: ActiveValue::Set(Role::Editor),
+            created_at: ActiveValue::Set(0),
+        })
+        .exec_without_returning(&context.db)
+        .await
+        .unwrap();
+    }
+
+    let dir = create_file(&context, &owner, "team", None, Some("dir"))
+        .await
+        .unwrap();
+    let photo = create_file(&context, &owner, "photo", Some(dir.id), Some("image/png"))
+        .await
+        .unwrap();
+
+    let members = repository.shares(owner.id).members(group_id).await.unwrap();
+    assert_eq!(
+        members.iter().map(|m| m.id).collect::<Vec<_>>(),
+        [alice.id, bob.id]
+    );
+    assert!(repository
+        .shares(stranger.id)
+        .members(group_id)
+        .await
+        .is_err());
+
+    let keys = |user: &entity::users::Model| MemberKeys {
+        user_id: user.id,
+        encrypted_key: "dir-key".to_string(),
+        keys: vec![SharedKey {
+            id: photo.id,
+            encrypted_key: "photo-key".to_string(),
+        }],
+    };
+
+    // Keys have to be given for every other member
+    assert!(repository
+        .shares(owner.id)
+        .create_for_group(dir.id, group_id, vec![keys(&alice)], Permission::Write)
+        .await
+        .is_err());
+
+    let shares = repository
+        .shares(owner.id)
+        .create_for_group(
+            dir.id,
+            group_id,
+            vec![keys(&alice), keys(&bob)],
+            Permission::Write,
+        )
+        .await
+        .unwrap();
+    assert_eq!(shares.len(), 2);
+    assert!(shares.iter().all(|s| s.permission == Permission::Write));
+
+    // Members who already have it are left out
+    let again = repository
+        .shares(owner.id)
+        .create_for_group(
+            dir.id,
+            group_id,
+            vec![keys(&alice), keys(&bob)],
+            Permission::Write,
+        )
+        .await
+        .unwrap();
+    assert!(again.is_empty());
+
+    // Writing members create the files in the folder, but they don't share it further
+    create_file(&context, &alice, "notes", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    assert!(repository
+        .shares(alice.id)
+        .create(
+            dir.id,
+            stranger.id,
+            "key".to_string(),
+            vec![],
+            Permission::Read
+        )
+        .await
+        .is_err());
+
+    // Managing users share it further, the users they share it with only read it
+    let reports = create_file(&context, &owner, "reports", None, Some("dir"))
+        .await
+        .unwrap();
+
+    repository
+        .shares(owner.id)
+        .create(
+            reports.id,
+            alice.id,
+            "key".to_string(),
+            vec![],
+            Permission::Manage,
+        )
+        .await
+        .unwrap();
+    repository
+        .shares(alice.id)
+        .create(
+            reports.id,
+            stranger.id,
+            "key".to_string(),
+            vec![],
+            Permission::Read,
+        )
+        .await
+        .unwrap();
+
+    assert!(create_file(
+        &context,
+        &stranger,
+        "report",
+        Some(reports.id),
+        Some("text/plain")
+    )
+    .await
+    .is_err());
+}
@@ -0,0 +1,107 @@
+use context::Context;
+use entity::{files, ActiveValue, EntityTrait};
+
+use crate::{mock::create_file, repository::Repository};
+
+#[actix_web::test]
+async fn snapshot_digest_changes_with_the_content_below_the_folder() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "snapshot@test.com", None).await;
+
+    let root = create_file(&context, &user, "root", None, Some("dir"))
+        .await
+        .unwrap();
+    let photos = create_file(&context, &user, "photos", Some(root.id), Some("dir"))
+        .await
+        .unwrap();
+    let notes = create_file(&context, &user, "notes", Some(root.id), Some("dir"))
+        .await
+        .unwrap();
+    let photo = create_file(
+        &context,
+        &user,
+        "photo.jpg",
+        Some(photos.id),
+        Some("image/jpeg"),
+    )
+    .await
+    .unwrap();
+    create_file(
+        &context,
+        &user,
+        "note.txt",
+        Some(notes.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(user.id);
+
+    let first = manage.snapshot(root.id).await.unwrap();
+    assert_eq!(first.id, root.id);
+    assert_eq!(first.folders.len(), 3);
+    assert_eq!(first.folders[0].digest, first.digest);
+
+    // Nothing changed, nothing to walk
+    let second = manage.snapshot(root.id).await.unwrap();
+    assert_eq!(first, second);
+
+    let diff = second.diff(&first.digest, &first.folders);
+    assert!(!diff.changed);
+    assert!(diff.folders.is_empty());
+    assert!(diff.removed.is_empty());
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(photo.id),
+        sha256: ActiveValue::Set(Some("a".repeat(64))),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    // Only the folders on the way to the changed file have to be walked
+    let third = manage.snapshot(root.id).await.unwrap();
+    assert_ne!(third.digest, first.digest);
+
+    let mut diff = third.diff(&first.digest, &first.folders);
+    diff.folders.sort();
+    let mut expected = vec![root.id, photos.id];
+    expected.sort();
+    assert!(diff.changed);
+    assert_eq!(diff.folders, expected);
+
+    files::Entity::delete_by_id(notes.id)
+        .exec(&context.db)
+        .await
+        .unwrap();
+
+    let fourth = manage.snapshot(root.id).await.unwrap();
+    let diff = fourth.diff(&third.digest, &third.folders);
+    assert_eq!(diff.folders, vec![root.id]);
+    assert_eq!(diff.removed, vec![notes.id]);
+}
+
+#[actix_web::test]
+async fn digest_of_the_folder_does_not_depend_on_its_name() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "snapshot@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let other = create_file(&context, &user, "other", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(user.id);
+
+    // Names of the folders are a part of the digest of the folder they are in
+    let first = manage.snapshot(dir.id).await.unwrap();
+    let second = manage.snapshot(other.id).await.unwrap();
+    assert_eq!(first.digest, second.digest);
+    assert_eq!(first.folders.len(), 1);
+}
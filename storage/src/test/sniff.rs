@@ -0,0 +1,88 @@
+use context::Context;
+use entity::ActiveValue;
+use serde_json::json;
+
+use crate::{data::create_file::CreateFile, repository::restrictions, sniff};
+
+fn file(mime: &str, magic: Option<&[u8]>) -> CreateFile {
+    serde_json::from_value(json!({
+        "encrypted_key": cryptfns::base64::encode("key"),
+        "encrypted_name": cryptfns::hex::encode("file"),
+        "name_hash": cryptfns::sha256::digest("file"),
+        "mime": mime,
+        "size": 100,
+        "chunks": 1,
+        "magic": magic.map(cryptfns::hex::encode),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn mime_is_sniffed_from_the_magic_number() {
+    assert_eq!(
+        sniff::sniff(b"\x89PNG\r\n\x1a\n\x00\x00"),
+        Some("image/png")
+    );
+    assert_eq!(sniff::sniff(b"%PDF-1.7"), Some("application/pdf"));
+    assert_eq!(
+        sniff::sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+        Some("image/webp")
+    );
+    assert_eq!(
+        sniff::sniff(b"\x00\x00\x00\x18ftypheic"),
+        Some("image/heic")
+    );
+    assert_eq!(sniff::sniff(b"\x00\x00\x00\x18ftypisom"), Some("video/mp4"));
+    assert_eq!(sniff::sniff(b"plain text"), None);
+    assert_eq!(sniff::sniff(b"RIFF"), None);
+
+    assert_eq!(sniff::normalize(" Text/Plain; charset=utf-8"), "text/plain");
+    assert!(sniff::is_generic(""));
+    assert!(sniff::is_generic("application/octet-stream"));
+    assert!(!sniff::is_generic("text/plain"));
+
+    // Mime from the client is kept when it tells the type
+    assert_eq!(sniff::resolve("Text/Plain", Some(b"%PDF-")), "text/plain");
+    assert_eq!(
+        sniff::resolve("application/octet-stream", Some(b"%PDF-")),
+        "application/pdf"
+    );
+    assert_eq!(sniff::resolve("", None), sniff::GENERIC_MIME);
+    assert_eq!(sniff::resolve("", Some(b"nothing")), sniff::GENERIC_MIME);
+    assert_eq!(sniff::resolve("dir", None), "dir");
+}
+
+#[test]
+fn created_file_is_stored_with_the_sniffed_mime() {
+    let (model, ..) = file("", Some(b"\xff\xd8\xff\xe0"))
+        .into_active_model()
+        .unwrap();
+    assert_eq!(model.mime, ActiveValue::Set("image/jpeg".to_string()));
+
+    let (model, ..) = file("IMAGE/PNG; q=1", None).into_active_model().unwrap();
+    assert_eq!(model.mime, ActiveValue::Set("image/png".to_string()));
+
+    let mut invalid = file("", None);
+    invalid.magic = Some("not hex".to_string());
+    assert!(invalid.into_active_model().is_err());
+
+    let mut long = file("", None);
+    long.magic = Some("00".repeat(sniff::MAX_MAGIC_BYTES + 1));
+    assert!(long.into_active_model().is_err());
+}
+
+#[actix_web::test]
+async fn sniffed_mime_is_restricted() {
+    let context = Context::mock_sqlite().await;
+
+    context.settings.inner().await.files = serde_json::from_value(json!({
+        "mime_blacklist": { "rules": ["application/x-executable"] },
+    }))
+    .unwrap();
+
+    let mut file = file("application/octet-stream", Some(b"\x7fELF\x02\x01"));
+    restrictions::check(&context, &file).await.unwrap();
+
+    file.sniff_mime();
+    assert!(restrictions::check(&context, &file).await.is_err());
+}
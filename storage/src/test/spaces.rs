@@ -0,0 +1,91 @@
+use context::Context;
+use entity::{
+    group_members::{self, Role},
+    groups, users, ActiveValue, EntityTrait, Uuid,
+};
+
+use crate::{mock::create_file, repository::Repository};
+
+async fn add_member(context: &Context, group_id: Uuid, user: &users::Model, role: Role) {
+    group_members::Entity::insert(group_members::ActiveModel {
+        group_id: ActiveValue::Set(group_id),
+        user_id: ActiveValue::Set(user.id),
+        role: ActiveValue::Set(role),
+        created_at: ActiveValue::Set(0),
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+}
+
+#[actix_web::test]
+async fn space_members_work_on_it_by_their_roles() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let owner = entity::mock::create_user(&context.db, "owner@test.com", None).await;
+    let editor = entity::mock::create_user(&context.db, "editor@test.com", None).await;
+    let viewer = entity::mock::create_user(&context.db, "viewer@test.com", None).await;
+
+    let group_id = Uuid::new_v4();
+    groups::Entity::insert(groups::ActiveModel {
+        id: ActiveValue::Set(group_id),
+        name: ActiveValue::Set("team".to_string()),
+        quota: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(0),
+        updated_at: ActiveValue::Set(0),
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+
+    add_member(&context, group_id, &owner, Role::Owner).await;
+    add_member(&context, group_id, &editor, Role::Editor).await;
+    add_member(&context, group_id, &viewer, Role::Viewer).await;
+
+    let dir = create_file(&context, &owner, "team", None, Some("dir"))
+        .await
+        .unwrap();
+
+    // Only the owners of the group create its spaces
+    assert!(repository
+        .spaces(editor.id)
+        .create(group_id, dir.id)
+        .await
+        .is_err());
+    repository
+        .spaces(owner.id)
+        .create(group_id, dir.id)
+        .await
+        .unwrap();
+
+    // Clients share the folder key with the members, the same way they would with any user
+    entity::mock::create_share(&context.db, dir.id, &editor, "key").await;
+    entity::mock::create_share(&context.db, dir.id, &viewer, "key").await;
+
+    let file = create_file(&context, &editor, "notes", Some(dir.id), Some("text/plain"))
+        .await
+        .unwrap();
+    assert!(
+        create_file(&context, &viewer, "other", Some(dir.id), Some("text/plain"))
+            .await
+            .is_err()
+    );
+
+    let spaces = repository.spaces(viewer.id).find().await.unwrap();
+    assert_eq!(spaces.len(), 1);
+    assert_eq!(spaces[0].role, Role::Viewer);
+    assert_eq!(spaces[0].file_id, dir.id);
+
+    // Viewer cannot delete anything in the space
+    assert!(repository
+        .manage(viewer.id)
+        .delete_many(vec![dir.id])
+        .await
+        .is_err());
+    let deleted = repository
+        .manage(editor.id)
+        .delete_many(vec![file.id])
+        .await
+        .unwrap();
+    assert_eq!(deleted.len(), 1);
+}
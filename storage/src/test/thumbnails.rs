@@ -0,0 +1,70 @@
+use context::Context;
+use fs::prelude::*;
+
+use crate::{
+    data::thumbnail::{Thumbnail, ThumbnailSize},
+    jobs::{queue_purge, PurgeFiles, PURGE_FILES},
+    mock::create_file,
+    repository::Repository,
+};
+
+#[test]
+fn thumbnail_is_not_taken_for_the_chunk_of_the_file() {
+    let id = entity::Uuid::new_v4();
+    let thumbnail = Thumbnail::new(id, 1700000000, ThumbnailSize::Medium);
+
+    let filename = thumbnail.filename().unwrap().with_chunk(Thumbnail::CHUNK);
+    assert_eq!(
+        filename.to_string(),
+        format!("1700000000-{}.thumbnail-medium.part.0", id)
+    );
+
+    // Chunks of the file are found by the `{name}.part.` prefix
+    let chunks = Filename::new(id).with_timestamp(1700000000).with_chunk("");
+    assert!(!filename.to_string().starts_with(&chunks.to_string()));
+
+    assert_eq!(
+        "small".parse::<ThumbnailSize>().unwrap(),
+        ThumbnailSize::Small
+    );
+    assert!("large".parse::<ThumbnailSize>().is_err());
+}
+
+#[actix_web::test]
+async fn thumbnails_are_purged_with_the_file() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "thumbnails@test.com", None).await;
+    let fs = Fs::new(&context.config);
+
+    let file = create_file(&context, &user, "photo.jpg", None, Some("image/jpeg"))
+        .await
+        .unwrap();
+    let small = Thumbnail::new(file.id, file.created_at, ThumbnailSize::Small);
+    let medium = Thumbnail::new(file.id, file.created_at, ThumbnailSize::Medium);
+
+    fs.push(&file, 0, b"encrypted photo").await.unwrap();
+    fs.push(&small, Thumbnail::CHUNK, b"small").await.unwrap();
+    fs.push(&medium, Thumbnail::CHUNK, b"medium").await.unwrap();
+
+    // Thumbnails are not the chunks of the file
+    assert_eq!(fs.get_uploaded_chunks(&file).await.unwrap(), vec![0]);
+    assert_eq!(fs.pull(&small, Thumbnail::CHUNK).await.unwrap(), b"small");
+
+    let files = repository
+        .manage(user.id)
+        .delete_many(vec![file.id])
+        .await
+        .unwrap();
+    queue_purge(&context.db, &files).await.unwrap();
+
+    jobs::worker::Worker::new(context.clone())
+        .handler(PURGE_FILES, PurgeFiles)
+        .run()
+        .await
+        .unwrap();
+
+    assert!(!fs.exists(&file, 0).await.unwrap());
+    assert!(!fs.exists(&small, Thumbnail::CHUNK).await.unwrap());
+    assert!(!fs.exists(&medium, Thumbnail::CHUNK).await.unwrap());
+}
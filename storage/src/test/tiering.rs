@@ -0,0 +1,72 @@
+use chrono::Utc;
+use context::Context;
+use entity::{files, ActiveValue, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{archive, mock::create_file, repository::lifecycles, tiering};
+
+#[actix_web::test]
+async fn cooled_down_files_are_demoted_once() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "tiering@test.com", None).await;
+    let now = Utc::now().timestamp();
+    let before = now - 100;
+
+    let cold = create_file(&context, &user, "cold.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let used = create_file(&context, &user, "used.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let archived = create_file(&context, &user, "archived.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    files::Entity::update_many()
+        .set(files::ActiveModel {
+            finished_upload_at: ActiveValue::Set(Some(now - 3 * 24 * 60 * 60)),
+            ..Default::default()
+        })
+        .filter(files::Column::Id.is_in([cold.id, used.id, archived.id]))
+        .exec(&context.db)
+        .await
+        .unwrap();
+
+    let ids = |files: Vec<files::Model>| files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    assert_eq!(
+        lifecycles::hot(&context.db, before, 10)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+
+    // Downloaded file stays on the local disk, archived file has no chunks to move
+    archive::restoring(&context, used.id, user.id)
+        .await
+        .unwrap();
+    lifecycles::archived(&context.db, archived.id, now - 1000)
+        .await
+        .unwrap();
+
+    let found = ids(lifecycles::hot(&context.db, before, 10).await.unwrap());
+    assert_eq!(found, vec![cold.id]);
+
+    assert_eq!(tiering::sweep(&context, 1).await.unwrap(), 1);
+    assert_eq!(tiering::sweep(&context, 1).await.unwrap(), 0);
+
+    let lifecycle = lifecycles::find(&context.db, cold.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(lifecycle.demoted_at.is_some());
+    assert!(!lifecycle.is_archived());
+
+    // Restored chunks are written to the local disk, so the file can be demoted again
+    lifecycles::restored(&context.db, &lifecycle).await.unwrap();
+
+    let lifecycle = lifecycles::find(&context.db, cold.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(lifecycle.demoted_at.is_none());
+}
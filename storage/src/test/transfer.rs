@@ -0,0 +1,188 @@
+use context::Context;
+use entity::{files, ActiveValue, EntityTrait};
+use error::Error;
+use fs::prelude::*;
+
+use crate::{mock::create_file, repository::Repository, routes::transfer::link_copies};
+
+#[actix_web::test]
+async fn folder_is_moved_but_not_into_itself() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "move@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let inner = create_file(&context, &user, "inner", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let target = create_file(&context, &user, "target", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(user.id);
+
+    let error = manage.move_file(dir.id, Some(inner.id)).await.unwrap_err();
+    assert_eq!(
+        error,
+        Error::BadRequest("cannot_move_to_itself".to_string())
+    );
+
+    let error = manage.move_file(dir.id, Some(file.id)).await.unwrap_err();
+    assert_eq!(
+        error,
+        Error::BadRequest("parent_directory_not_found".to_string())
+    );
+
+    let moved = manage.move_file(dir.id, Some(target.id)).await.unwrap();
+    assert_eq!(moved.file_id, Some(target.id));
+
+    let inner = repository.by_id(inner.id, user.id).await.unwrap();
+    assert_eq!(inner.file_id, Some(dir.id));
+
+    // Name is already taken in the destination
+    create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(target.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+    let error = manage
+        .move_file(file.id, Some(target.id))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Error::BadRequest("file_or_directory_exists".to_string())
+    );
+}
+
+#[actix_web::test]
+async fn folder_is_copied_with_its_content() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "copy@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let inner = create_file(&context, &user, "inner", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(inner.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+    // Not uploaded yet, it is left out
+    create_file(
+        &context,
+        &user,
+        "partial.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+    let target = create_file(&context, &user, "target", None, Some("dir"))
+        .await
+        .unwrap();
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(file.id),
+        chunks: ActiveValue::Set(Some(1)),
+        finished_upload_at: ActiveValue::Set(Some(file.created_at)),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    let fs = Fs::new(&context.config);
+    fs.push(&file, 0, b"content").await.unwrap();
+
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(user.id);
+
+    let error = manage.copy(dir.id, Some(inner.id)).await.unwrap_err();
+    assert_eq!(
+        error,
+        Error::BadRequest("cannot_move_to_itself".to_string())
+    );
+
+    let copies = manage.copy(dir.id, Some(target.id)).await.unwrap();
+    assert_eq!(copies.len(), 3);
+
+    let (original, root) = &copies[0];
+    assert_eq!(original.id, dir.id);
+    assert_eq!(root.file_id, Some(target.id));
+    assert_eq!(root.name_hash, dir.name_hash);
+
+    let (original, copy) = copies.iter().find(|(f, _)| f.id == file.id).unwrap();
+    let (_, inner_copy) = copies.iter().find(|(f, _)| f.id == inner.id).unwrap();
+    assert_eq!(copy.file_id, Some(inner_copy.id));
+    assert_eq!(copy.encrypted_key, original.encrypted_key);
+    assert!(copy.finished_upload_at.is_some());
+
+    assert_eq!(fs.link(original, copy).await.unwrap(), vec![0]);
+    assert_eq!(fs.pull(copy, 0).await.unwrap(), b"content".to_vec());
+
+    // Originals are where they were
+    let tree = manage.tree(dir.id).await.unwrap();
+    assert_eq!(tree.len(), 4);
+}
+
+#[actix_web::test]
+async fn linked_copies_are_purged_when_a_source_is_incomplete() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "link@test.com", None).await;
+
+    let mut complete = create_file(&context, &user, "complete.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let mut partial = create_file(&context, &user, "partial.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    complete.chunks = Some(1);
+    partial.chunks = Some(2);
+
+    let fs = Fs::new(&context.config);
+    fs.push(&complete, 0, b"complete").await.unwrap();
+    fs.push(&partial, 0, b"partial").await.unwrap();
+
+    let complete_copy = create_file(&context, &user, "complete copy", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let partial_copy = create_file(&context, &user, "partial copy", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let error = link_copies(
+        &context,
+        &[
+            (complete.clone(), complete_copy.clone()),
+            (partial.clone(), partial_copy.clone()),
+        ],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error, Error::StorageError("source_incomplete".to_string()));
+
+    // Copy that was linked before the incomplete source is gone as well
+    assert!(!fs.exists(&complete_copy, 0).await.unwrap());
+    assert!(!fs.exists(&partial_copy, 0).await.unwrap());
+    assert_eq!(fs.pull(&complete, 0).await.unwrap(), b"complete");
+
+    fs.purge(&complete).await.unwrap();
+    fs.purge(&partial).await.unwrap();
+}
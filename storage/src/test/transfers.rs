@@ -0,0 +1,117 @@
+use actix_web::http::Method;
+use context::Context;
+use entity::Uuid;
+
+use crate::{
+    repository::{transfers::day, Repository},
+    transfers::{direction, Direction},
+};
+
+#[test]
+fn content_routes_have_a_direction() {
+    use Direction::{Download, Upload};
+
+    let id = Uuid::new_v4();
+
+    for (method, path, expected) in [
+        (Method::GET, format!("/api/storage/{}", id), Download),
+        (
+            Method::GET,
+            format!("/api/storage/{}/content", id),
+            Download,
+        ),
+        (Method::GET, format!("/api/storage/{}/zip", id), Download),
+        (
+            Method::GET,
+            format!("/api/storage/{}/thumbnail", id),
+            Download,
+        ),
+        (
+            Method::GET,
+            format!("/api/exports/{}/download", id),
+            Download,
+        ),
+        (Method::GET, "/api/dav/notes.txt".to_string(), Download),
+        (Method::POST, format!("/api/storage/{}", id), Upload),
+        (
+            Method::POST,
+            "/api/storage/simple-upload".to_string(),
+            Upload,
+        ),
+        (Method::POST, "/api/storage/bulk".to_string(), Upload),
+        (Method::POST, "/api/storage/dedup/files".to_string(), Upload),
+        (
+            Method::POST,
+            format!("/api/storage/{}/thumbnail", id),
+            Upload,
+        ),
+        (Method::GET, format!("/api/storage/{}/socket", id), Upload),
+        (Method::PATCH, format!("/api/storage/tus/{}", id), Upload),
+        (Method::PUT, format!("/api/storage/{}/content", id), Upload),
+        (
+            Method::POST,
+            format!("/api/file-requests/{}/files", id),
+            Upload,
+        ),
+        (
+            Method::POST,
+            format!("/api/file-requests/{}/files/{}", id, id),
+            Upload,
+        ),
+        (Method::PUT, "/api/dav/notes.txt".to_string(), Upload),
+    ] {
+        assert_eq!(
+            direction(&method, &path),
+            Some(expected),
+            "{} {} is not metered",
+            method,
+            path
+        );
+    }
+
+    let file = format!("/api/storage/{}", id);
+
+    assert_eq!(direction(&Method::GET, "/api/storage"), None);
+    assert_eq!(direction(&Method::GET, "/api/storage/trash"), None);
+    assert_eq!(direction(&Method::POST, "/api/storage/dedup"), None);
+    assert_eq!(direction(&Method::GET, &format!("{}/metadata", file)), None);
+    assert_eq!(direction(&Method::DELETE, &file), None);
+    assert_eq!(direction(&Method::HEAD, &file), None);
+}
+
+#[test]
+fn days_start_at_midnight() {
+    assert_eq!(day(1_600_000_000), 1_599_955_200);
+    assert_eq!(day(1_599_955_200), 1_599_955_200);
+    assert_eq!(day(-1), -86_400);
+}
+
+#[actix_web::test]
+async fn transfers_are_added_up_per_day() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "transfers@test.com", None).await;
+    let other = entity::mock::create_user(&context.db, "other@test.com", None).await;
+
+    let repository = Repository::new(&context.db);
+    let transfers = repository.transfers(user.id);
+
+    let today = transfers.today().await.unwrap();
+    assert_eq!(today.uploaded_bytes + today.downloaded_bytes, 0);
+    assert!(transfers.recent(30).await.unwrap().is_empty());
+
+    transfers.record(Direction::Upload, 100).await.unwrap();
+    transfers.record(Direction::Upload, 50).await.unwrap();
+    transfers.record(Direction::Download, 20).await.unwrap();
+    repository
+        .transfers(other.id)
+        .record(Direction::Download, 1000)
+        .await
+        .unwrap();
+
+    let today = transfers.today().await.unwrap();
+    assert_eq!(today.uploaded_bytes, 150);
+    assert_eq!(today.downloaded_bytes, 20);
+
+    let recent = transfers.recent(30).await.unwrap();
+    assert_eq!(recent, vec![today]);
+}
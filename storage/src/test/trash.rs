@@ -0,0 +1,144 @@
+use context::Context;
+use error::Error;
+
+use crate::{jobs::TrashedFiles, mock::create_file, repository::Repository};
+
+#[actix_web::test]
+async fn deleted_folder_is_restored_from_the_trash() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "trash@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let trash = repository.trash(user.id);
+
+    let deleted = trash.delete(vec![dir.id], 30).await.unwrap();
+    assert_eq!(deleted.len(), 2);
+    assert!(deleted.iter().all(|f| f.deleted_at.is_some()));
+
+    let error = repository.by_id(file.id, user.id).await.unwrap_err();
+    assert_eq!(error, Error::NotFound(format!("file_not_found:{}", file.id)));
+
+    // Only the folder is listed, the file goes with it
+    let list = trash.list().await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id, dir.id);
+
+    let error = trash.restore(file.id).await.unwrap_err();
+    assert_eq!(error, Error::NotFound("file_not_found".to_string()));
+
+    let restored = trash.restore(dir.id).await.unwrap();
+    assert_eq!(restored.deleted_at, None);
+
+    let file = repository.by_id(file.id, user.id).await.unwrap();
+    assert_eq!(file.file_id, Some(dir.id));
+    assert!(trash.list().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn file_is_restored_to_the_root_when_folder_is_gone() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "trash-root@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let trash = repository.trash(user.id);
+
+    trash.delete(vec![file.id], 30).await.unwrap();
+    trash.delete(vec![dir.id], 30).await.unwrap();
+
+    // File was deleted on its own before the folder, both are listed
+    assert_eq!(trash.list().await.unwrap().len(), 2);
+
+    let restored = trash.restore(file.id).await.unwrap();
+    assert_eq!(restored.file_id, None);
+    assert!(trash.get(dir.id).await.is_ok());
+
+    // Name is taken by the restored file now
+    trash.delete(vec![restored.id], 30).await.unwrap();
+    create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let error = trash.restore(restored.id).await.unwrap_err();
+    assert_eq!(
+        error,
+        Error::BadRequest("file_or_directory_exists".to_string())
+    );
+}
+
+#[actix_web::test]
+async fn expired_trash_skips_restored_files() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "trash-expire@test.com", None).await;
+
+    let kept = create_file(&context, &user, "kept.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+    let purged = create_file(&context, &user, "purged.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let trash = repository.trash(user.id);
+
+    let deleted = trash.delete(vec![kept.id, purged.id], 30).await.unwrap();
+    trash.restore(kept.id).await.unwrap();
+
+    let expired = crate::repository::trash::expire(
+        &context.db,
+        &TrashedFiles {
+            user_id: user.id,
+            ids: vec![kept.id, purged.id],
+            deleted_at: deleted[0].deleted_at.unwrap(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].id, purged.id);
+    assert!(repository.by_id(kept.id, user.id).await.is_ok());
+    assert!(trash.get(purged.id).await.is_err());
+}
+
+#[actix_web::test]
+async fn delete_without_retention_is_immediate() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "trash-none@test.com", None).await;
+
+    let file = create_file(&context, &user, "file.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let trash = repository.trash(user.id);
+
+    trash.delete(vec![file.id], 0).await.unwrap();
+
+    assert!(trash.list().await.unwrap().is_empty());
+    assert!(repository.by_id(file.id, user.id).await.is_err());
+}
@@ -0,0 +1,91 @@
+use context::Context;
+
+use crate::{
+    data::tree::{Tree, MAX_TREE_DEPTH},
+    mock::create_file,
+    repository::Repository,
+};
+
+#[test]
+fn tree_query_is_validated() {
+    let (root, depth) = Tree::default().into_tuple().unwrap();
+    assert!(root.is_none());
+    assert_eq!(depth, MAX_TREE_DEPTH);
+
+    let invalid = Tree {
+        root: Some("not-an-id".to_string()),
+        ..Default::default()
+    };
+    assert!(invalid.into_tuple().is_err());
+
+    for depth in [0, MAX_TREE_DEPTH + 1] {
+        let invalid = Tree {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        assert!(invalid.into_tuple().is_err());
+    }
+}
+
+#[actix_web::test]
+async fn tree_is_loaded_level_by_level() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let nested = create_file(&context, &user, "nested", Some(dir.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(&context, &user, "file", Some(nested.id), None)
+        .await
+        .unwrap();
+    let top = create_file(&context, &user, "top", None, None)
+        .await
+        .unwrap();
+
+    let files = repository
+        .manage(user.id)
+        .descendants(None, MAX_TREE_DEPTH)
+        .await
+        .unwrap();
+    let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+    assert_eq!(ids.len(), 4);
+    assert!(ids[..2].contains(&dir.id));
+    assert!(ids[..2].contains(&top.id));
+    assert_eq!(ids[2..], [nested.id, file.id]);
+
+    // Only the first level
+    let files = repository
+        .manage(user.id)
+        .descendants(None, 1)
+        .await
+        .unwrap();
+    assert_eq!(files.len(), 2);
+
+    // Starting in the directory leaves the directory itself out
+    let files = repository
+        .manage(user.id)
+        .descendants(Some(dir.id), MAX_TREE_DEPTH)
+        .await
+        .unwrap();
+    let ids = files.iter().map(|file| file.id).collect::<Vec<_>>();
+    assert_eq!(ids, [nested.id, file.id]);
+
+    // Files of the other users are not there
+    let other = entity::mock::create_user(&context.db, "second@test.com", None).await;
+    assert!(repository
+        .manage(other.id)
+        .descendants(None, MAX_TREE_DEPTH)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(repository
+        .manage(other.id)
+        .descendants(Some(dir.id), MAX_TREE_DEPTH)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,26 @@
+use crate::{mock::create_file, repository::Repository};
+use context::Context;
+
+#[actix_web::test]
+async fn upload_is_finished_once() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "first@test.com", None).await;
+
+    let mut file = create_file(&context, &user, "file.json", None, Some("application/json"))
+        .await
+        .unwrap();
+
+    file.chunks = Some(2);
+
+    let manage = repository.manage(user.id);
+
+    let finished = manage.finish(&file).await.unwrap();
+    let finished_upload_at = finished.finished_upload_at;
+    assert!(finished_upload_at.is_some());
+
+    // Finished files cannot be finished twice
+
+    let finished = manage.finish(&file).await.unwrap();
+    assert_eq!(finished.finished_upload_at, finished_upload_at);
+}
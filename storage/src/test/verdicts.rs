@@ -0,0 +1,75 @@
+use chrono::Utc;
+use context::Context;
+use entity::{
+    audit_logs,
+    file_verdicts::{self, Verdict},
+    ActiveValue, ColumnTrait, EntityTrait, QueryFilter, Uuid,
+};
+use error::Error;
+
+use crate::{
+    mock::create_file,
+    repository::verdicts::{guard_download, verdicts},
+};
+
+async fn record(context: &Context, file_id: Uuid, scanner: &str, verdict: Verdict) {
+    let now = Utc::now().timestamp();
+
+    file_verdicts::Entity::insert(file_verdicts::ActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        file_id: ActiveValue::Set(file_id),
+        scanner: ActiveValue::Set(scanner.to_string()),
+        verdict: ActiveValue::Set(verdict),
+        details: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+    })
+    .exec_without_returning(&context.db)
+    .await
+    .unwrap();
+}
+
+#[actix_web::test]
+async fn downloads_are_refused_while_any_verdict_is_blocking() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "verdicts@test.com", None).await;
+
+    let file = create_file(&context, &user, "scanned.txt", None, Some("text/plain"))
+        .await
+        .unwrap();
+
+    // Files nobody scanned can be downloaded
+    guard_download(&context.db, user.id, file.id).await.unwrap();
+
+    record(&context, file.id, "clamav", Verdict::Clean).await;
+    record(&context, file.id, "dlp", Verdict::Suspicious).await;
+    guard_download(&context.db, user.id, file.id).await.unwrap();
+
+    // Single blocking verdict is enough, whatever the other scanners say
+    record(&context, file.id, "policy", Verdict::Blocked).await;
+
+    match guard_download(&context.db, user.id, file.id).await {
+        Err(Error::Forbidden(message)) => assert_eq!(message, "file_blocked_by_verdict"),
+        other => panic!("download should be refused, got {:?}", other),
+    }
+
+    let found = verdicts(&context.db, file.id).await.unwrap();
+    assert_eq!(found.len(), 3);
+    assert_eq!(found.iter().filter(|v| v.is_blocking()).count(), 1);
+
+    let refused = audit_logs::Entity::find()
+        .filter(audit_logs::Column::FileId.eq(file.id))
+        .filter(audit_logs::Column::Action.eq(audit_logs::Action::DownloadBlocked))
+        .all(&context.db)
+        .await
+        .unwrap();
+    assert_eq!(refused.len(), 1);
+    assert_eq!(refused[0].actor_id, user.id);
+
+    file_verdicts::Entity::delete_many()
+        .filter(file_verdicts::Column::Scanner.eq("policy"))
+        .exec(&context.db)
+        .await
+        .unwrap();
+    guard_download(&context.db, user.id, file.id).await.unwrap();
+}
@@ -0,0 +1,209 @@
+use context::Context;
+use entity::{files, users, ActiveValue, EntityTrait};
+
+use crate::{mock::create_file, repository::Repository};
+
+#[actix_web::test]
+async fn previous_version_is_kept_and_restored() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "versions@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let first = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+    let second = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let manage = repository.manage(user.id);
+    let versions = repository.versions(user.id);
+
+    let pruned = versions.keep(&first, second.id, 10).await.unwrap();
+    assert!(pruned.is_empty());
+
+    // Only the new file is in the folder, the previous one is its version
+    let tree = manage.tree(dir.id).await.unwrap();
+    assert_eq!(tree.len(), 2);
+    assert!(tree.iter().all(|f| f.id != first.id));
+    assert!(repository.by_id(first.id, user.id).await.is_err());
+
+    let list = versions.list(second.id).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id, first.id);
+
+    // Restored version takes the place of the file, the file becomes its version
+    let restored = versions.restore(second.id, first.id).await.unwrap();
+    assert_eq!(restored.id, first.id);
+    assert_eq!(restored.file_id, Some(dir.id));
+
+    let current = manage.by_name(first.name_hash.clone(), Some(dir.id)).await;
+    assert_eq!(current.unwrap().id, first.id);
+
+    let list = versions.list(first.id).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id, second.id);
+    assert!(versions.list(second.id).await.is_err());
+
+    let deleted = versions.delete(first.id, second.id).await.unwrap();
+    assert_eq!(deleted.id, second.id);
+    assert!(versions.list(first.id).await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn oldest_versions_are_pruned_and_deleted_with_the_file() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "versions-prune@test.com", None).await;
+
+    let repository = Repository::new(&context.db);
+    let versions = repository.versions(user.id);
+
+    let mut files = vec![];
+    for _ in 0..3 {
+        files.push(
+            create_file(&context, &user, "file.txt", None, Some("text/plain"))
+                .await
+                .unwrap(),
+        );
+    }
+
+    versions.keep(&files[0], files[1].id, 1).await.unwrap();
+    let pruned = versions.keep(&files[1], files[2].id, 1).await.unwrap();
+    assert_eq!(pruned.len(), 1);
+    assert_eq!(pruned[0].id, files[0].id);
+
+    let list = versions.list(files[2].id).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id, files[1].id);
+
+    let deleted = repository
+        .manage(user.id)
+        .delete_many(vec![files[2].id])
+        .await
+        .unwrap();
+    assert_eq!(deleted.len(), 2);
+    assert!(deleted.iter().any(|f| f.id == files[1].id));
+}
+
+#[actix_web::test]
+async fn version_limit_of_the_user_overrides_the_default() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "versions-limit@test.com", None).await;
+
+    let repository = Repository::new(&context.db);
+    let versions = repository.versions(user.id);
+    assert_eq!(versions.limit(10).await.unwrap(), 10);
+
+    users::Entity::update(users::ActiveModel {
+        id: ActiveValue::Set(user.id),
+        version_limit: ActiveValue::Set(Some(0)),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    assert_eq!(versions.limit(10).await.unwrap(), 0);
+}
+
+#[actix_web::test]
+async fn replaced_content_is_kept_as_version() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "versions@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    let repository = Repository::new(&context.db);
+    let versions = repository.versions(user.id);
+
+    let id = entity::Uuid::new_v4();
+    let pruned = versions
+        .keep_content(&file, id, file.created_at + 1, 10)
+        .await
+        .unwrap();
+    assert!(pruned.is_empty());
+
+    // File stays where it was, the copy of it is only reachable as its version
+    let tree = repository.manage(user.id).tree(dir.id).await.unwrap();
+    assert_eq!(tree.len(), 2);
+    assert!(tree.iter().any(|f| f.id == file.id));
+
+    let list = versions.list(file.id).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id, id);
+    assert_eq!(list[0].created_at, file.created_at + 1);
+
+    let pruned = versions
+        .keep_content(&file, entity::Uuid::new_v4(), file.created_at + 2, 1)
+        .await
+        .unwrap();
+    assert_eq!(pruned.len(), 1);
+    assert_eq!(pruned[0].id, id);
+}
+
+#[actix_web::test]
+async fn held_versions_are_not_pruned() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "versions-held@test.com", None).await;
+
+    let repository = Repository::new(&context.db);
+    let versions = repository.versions(user.id);
+
+    let mut files = vec![];
+    for _ in 0..4 {
+        files.push(
+            create_file(&context, &user, "file.txt", None, Some("text/plain"))
+                .await
+                .unwrap(),
+        );
+    }
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(files[0].id),
+        legal_hold: ActiveValue::Set(true),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    versions.keep(&files[0], files[1].id, 1).await.unwrap();
+    let pruned = versions.keep(&files[1], files[2].id, 1).await.unwrap();
+    assert!(pruned.is_empty());
+    assert_eq!(versions.list(files[2].id).await.unwrap().len(), 2);
+
+    // Only the versions that are not held are pruned
+    let pruned = versions.keep(&files[2], files[3].id, 1).await.unwrap();
+    assert_eq!(pruned.len(), 1);
+    assert_eq!(pruned[0].id, files[1].id);
+
+    let list = versions.list(files[3].id).await.unwrap();
+    assert_eq!(list.len(), 2);
+    assert!(list.iter().any(|version| version.id == files[0].id));
+}
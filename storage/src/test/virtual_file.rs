@@ -0,0 +1,75 @@
+use context::Context;
+use fs::prelude::IntoFilename;
+
+use crate::{
+    data::virtual_file::{CreateVirtualFile, VirtualKind, MAX_VIRTUAL_PAYLOAD_LENGTH},
+    mock::create_file,
+    repository::Repository,
+};
+
+fn bookmark(name: &str, payload: &str, file_id: Option<String>) -> CreateVirtualFile {
+    CreateVirtualFile {
+        id: None,
+        kind: Some(VirtualKind::Url),
+        encrypted_key: Some("key".to_string()),
+        name_hash: Some(cryptfns::sha256::digest(name.as_bytes())),
+        encrypted_name: Some(name.to_string()),
+        encrypted_payload: Some(payload.to_string()),
+        search_tokens_hashed: None,
+        file_id,
+        crypto_version: None,
+        escrow_key: None,
+    }
+}
+
+#[actix_web::test]
+async fn create_virtual_file_in_folder() {
+    let context = Context::mock_sqlite().await;
+    let repository = Repository::new(&context.db);
+    let user = entity::mock::create_user(&context.db, "virtual@test.com", None).await;
+
+    let dir = create_file(&context, &user, "dir", None, Some("dir"))
+        .await
+        .unwrap();
+
+    let (am, key, tokens, size, _) = bookmark("link", "encrypted-url", Some(dir.id.to_string()))
+        .into_active_model()
+        .unwrap();
+    assert_eq!(size, "encrypted-url".len() as i64);
+
+    let file = repository
+        .manage(user.id)
+        .create(am, &key, tokens)
+        .await
+        .unwrap();
+
+    assert!(file.is_virtual());
+    assert!(!file.is_file());
+    assert_eq!(file.mime, "virtual/url");
+    assert_eq!(file.file_id, Some(dir.id));
+    assert_eq!(file.encrypted_payload.as_deref(), Some("encrypted-url"));
+    assert!(file.chunks.is_none());
+    assert!(file.finished_upload_at.is_some());
+
+    // There is nothing stored for the virtual file to download
+    assert!(file.filename().is_err());
+
+    let file = repository.query(user.id).get(file.id).await.unwrap();
+    assert_eq!(file.encrypted_payload.as_deref(), Some("encrypted-url"));
+}
+
+#[test]
+fn virtual_file_payload_is_required_and_limited() {
+    let mut missing = bookmark("missing", "payload", None);
+    missing.encrypted_payload = None;
+    assert!(missing.into_active_model().is_err());
+
+    let mut no_kind = bookmark("no-kind", "payload", None);
+    no_kind.kind = None;
+    assert!(no_kind.into_active_model().is_err());
+
+    let too_long = "a".repeat(MAX_VIRTUAL_PAYLOAD_LENGTH + 1);
+    assert!(bookmark("too-long", &too_long, None)
+        .into_active_model()
+        .is_err());
+}
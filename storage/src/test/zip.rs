@@ -0,0 +1,106 @@
+use context::Context;
+use entity::{files, ActiveValue, EntityTrait};
+use fs::prelude::*;
+use futures::StreamExt;
+
+use crate::{
+    mock::create_file,
+    repository::Repository,
+    routes::zip::{assemble, entries},
+};
+
+#[actix_web::test]
+async fn folder_is_streamed_as_zip_archive() {
+    let context = Context::mock_sqlite().await;
+    let user = entity::mock::create_user(&context.db, "zip@test.com", None).await;
+
+    let root = create_file(&context, &user, "root", None, Some("dir"))
+        .await
+        .unwrap();
+    let dir = create_file(&context, &user, "dir", Some(root.id), Some("dir"))
+        .await
+        .unwrap();
+    let file = create_file(
+        &context,
+        &user,
+        "file.txt",
+        Some(dir.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+    // Not uploaded yet, it is left out
+    create_file(
+        &context,
+        &user,
+        "partial.txt",
+        Some(root.id),
+        Some("text/plain"),
+    )
+    .await
+    .unwrap();
+
+    files::Entity::update(files::ActiveModel {
+        id: ActiveValue::Set(file.id),
+        finished_upload_at: ActiveValue::Set(Some(file.created_at)),
+        ..Default::default()
+    })
+    .exec(&context.db)
+    .await
+    .unwrap();
+
+    let fs = Fs::new(&context.config);
+    fs.push(&file, 0, b"first chunk ").await.unwrap();
+    fs.push(&file, 1, b"second chunk").await.unwrap();
+
+    let tree = Repository::new(&context.db)
+        .manage(user.id)
+        .tree(root.id)
+        .await
+        .unwrap();
+    let entries = entries(root.id, &tree).unwrap();
+
+    let filename = file.filename().unwrap().with_extension(".enc").to_string();
+    let paths = entries
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![format!("{}/", dir.id), format!("{}/{}", dir.id, filename)]
+    );
+
+    let stored = entries
+        .iter()
+        .filter_map(|entry| entry.file.clone())
+        .collect::<Vec<_>>();
+    let chunks = fs.stream_files(&stored, 2).await.unwrap();
+
+    let mut archive = vec![];
+    let mut stream = Box::pin(assemble(entries, chunks));
+    while let Some(bytes) = stream.next().await {
+        archive.extend_from_slice(&bytes.unwrap());
+    }
+
+    let content = b"first chunk second chunk";
+    let at = archive
+        .windows(content.len())
+        .position(|window| window == content)
+        .unwrap();
+    let crc = util::zip::crc32(0, content);
+
+    // Content is followed by its data descriptor with the checksum and the size
+    assert_eq!(&archive[..4], &[0x50, 0x4b, 0x03, 0x04]);
+    assert_eq!(
+        &archive[at + content.len()..at + content.len() + 4],
+        &[0x50, 0x4b, 0x07, 0x08]
+    );
+    assert_eq!(
+        &archive[at + content.len() + 4..at + content.len() + 8],
+        &crc.to_le_bytes()
+    );
+    assert_eq!(
+        &archive[archive.len() - 22..archive.len() - 18],
+        &[0x50, 0x4b, 0x05, 0x06]
+    );
+}
@@ -0,0 +1,99 @@
+//! # Storage tiers
+//!
+//! With the tiered storage the new chunks are written to the local disk, the sweeper
+//! moves the chunks of the files nobody has downloaded for the STORAGE_TIERED_HOT_DAYS
+//! to the remote storage, where they are still downloaded straight away, only slower.
+//!
+//! The sweeper looks for the files to move every hour and moves them one by one, the file
+//! is marked as moved only once all of its chunks are in the remote storage. Moved files
+//! are not brought back to the local disk when they are downloaded again.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use config::storage::StorageConfig;
+use context::Context;
+use entity::files;
+use error::AppResult;
+use fs::prelude::*;
+
+use crate::repository::lifecycles;
+
+/// How often the sweeper looks for the files to move
+const SWEEP_EVERY: Duration = Duration::from_secs(60 * 60);
+
+/// How many files are loaded at the time
+const SWEEP_BATCH: u64 = 100;
+
+/// Day as it is counted for the STORAGE_TIERED_HOT_DAYS
+const DAY: i64 = 24 * 60 * 60;
+
+/// Moves the files that cooled down from the local disk to the remote storage
+pub struct Sweeper {
+    context: Context,
+    hot_days: u64,
+}
+
+impl Sweeper {
+    /// Create the sweeper if the storage is tiered
+    pub fn new(context: Context) -> Option<Self> {
+        let hot_days = match &context.config.storage {
+            StorageConfig::Tiered(storage) => storage.hot_days,
+            _ => return None,
+        };
+
+        Some(Self { context, hot_days })
+    }
+
+    /// Start sweeping in the background, the first sweep runs right away
+    pub fn spawn(self) {
+        actix_web::rt::spawn(async move {
+            loop {
+                match sweep(&self.context, self.hot_days).await {
+                    Ok(0) => {}
+                    Ok(demoted) => log::info!("Moved {} files to the remote storage", demoted),
+                    Err(e) => log::error!("Failed moving the files to the remote storage: {}", e),
+                }
+
+                actix_web::rt::time::sleep(SWEEP_EVERY).await;
+            }
+        });
+    }
+}
+
+/// Move the files nobody has touched for the given days, returns how many were moved.
+/// Files that fail are left on the local disk for the next sweep.
+pub(crate) async fn sweep(context: &Context, hot_days: u64) -> AppResult<u64> {
+    let before = Utc::now().timestamp() - hot_days as i64 * DAY;
+    let mut demoted = 0;
+
+    loop {
+        let files = lifecycles::hot(&context.db, before, SWEEP_BATCH).await?;
+        let loaded = files.len() as u64;
+        let mut failed = false;
+
+        for file in files.iter() {
+            match demote_file(context, file).await {
+                Ok(()) => demoted += 1,
+                Err(e) => {
+                    log::error!("Failed moving the file {}: {}", file.id, e);
+                    failed = true;
+                }
+            }
+        }
+
+        // Failed files would be loaded again, so they wait for the next sweep
+        if loaded < SWEEP_BATCH || failed {
+            return Ok(demoted);
+        }
+    }
+}
+
+/// Move the chunks of the file to the remote storage
+async fn demote_file(context: &Context, file: &files::Model) -> AppResult<()> {
+    Fs::new(&context.config).demote(file).await?;
+
+    let accessed_at = file.finished_upload_at.unwrap_or(file.created_at);
+
+    lifecycles::demoted(&context.db, file.id, accessed_at).await
+}
@@ -0,0 +1,246 @@
+//! # Transfers
+//!
+//! Counts the content of the files every user uploads and downloads in a day, so the
+//! usage of the bandwidth can be seen next to the usage of the storage in the stats.
+//! The [Metering] middleware wraps the body of the requests that upload the content and
+//! of the responses that download it, and adds the bytes that actually went through to
+//! the transfers of the user once the body is done with, even when it ended early.
+//!
+//! The daily limit from the settings (`users.transfer_bytes`) is checked before the
+//! transfer starts, the transfer that is already running is let to finish. Requests that
+//! are not signed in with the session, like the public links and the S3 gateway, are
+//! not counted.
+use std::{
+    future::{ready, Ready},
+    pin::Pin,
+    rc::Rc,
+    task::{Context as TaskContext, Poll},
+};
+
+use actix_http::BoxedPayloadStream;
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    error::PayloadError,
+    http::Method,
+    web::{self, Bytes},
+    HttpMessage,
+};
+use auth::data::claims::Claims;
+use context::Context;
+use entity::{DbConn, Uuid};
+use error::{AppResult, Error};
+use futures::{future::LocalBoxFuture, Stream};
+
+use crate::repository::Repository;
+
+/// Which way the content goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+/// Routes that move the content of the files and which way they move it. Creating the
+/// file from the content that is already stored is held to the daily limit the same as
+/// the upload it stands in for.
+pub fn direction(method: &Method, path: &str) -> Option<Direction> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let is_id = |segment: &str| segment.parse::<Uuid>().is_ok();
+
+    match (method.as_str(), segments.as_slice()) {
+        ("GET", ["api", "storage", file_id]) if is_id(file_id) => Some(Direction::Download),
+        ("GET", ["api", "storage", file_id, "content" | "zip" | "thumbnail"]) if is_id(file_id) => {
+            Some(Direction::Download)
+        }
+        ("GET", ["api", "exports", _, "download"]) => Some(Direction::Download),
+        ("GET", ["api", "dav", ..]) => Some(Direction::Download),
+        ("POST", ["api", "storage", file_id]) if is_id(file_id) => Some(Direction::Upload),
+        ("POST", ["api", "storage", "simple-upload" | "bulk"]) => Some(Direction::Upload),
+        ("POST", ["api", "storage", "dedup", "files"]) => Some(Direction::Upload),
+        ("POST", ["api", "storage", file_id, "thumbnail"]) if is_id(file_id) => {
+            Some(Direction::Upload)
+        }
+        ("GET", ["api", "storage", file_id, "socket"]) if is_id(file_id) => Some(Direction::Upload),
+        ("PATCH", ["api", "storage", "tus", _]) => Some(Direction::Upload),
+        ("PUT", ["api", "storage", _, "content"]) => Some(Direction::Upload),
+        ("POST", ["api", "file-requests", _, "files", ..]) => Some(Direction::Upload),
+        ("PUT", ["api", "dav", ..]) => Some(Direction::Upload),
+        _ => None,
+    }
+}
+
+/// Middleware that counts the transfers of the users and enforces the daily limit
+pub struct Metering;
+
+impl<S, B> Transform<S, ServiceRequest> for Metering
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = actix_web::Error;
+    type Transform = MeteringMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MeteringMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct MeteringMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for MeteringMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let meter = match start(&req).await {
+                Ok(Some(meter)) => meter,
+                Ok(None) => return Ok(service.call(req).await?.map_into_boxed_body()),
+                Err(e) => return Ok(req.error_response(e)),
+            };
+
+            match meter.direction {
+                Direction::Upload => {
+                    let payload: BoxedPayloadStream = Box::pin(Metered {
+                        inner: req.take_payload(),
+                        meter,
+                    });
+                    req.set_payload(Payload::from(payload));
+
+                    Ok(service.call(req).await?.map_into_boxed_body())
+                }
+                Direction::Download => {
+                    let res = service.call(req).await?.map_into_boxed_body();
+
+                    Ok(res.map_body(|_, body| BoxBody::new(Metered { inner: body, meter })))
+                }
+            }
+        })
+    }
+}
+
+/// Start counting the transfer if the route moves the content and the user is signed in,
+/// fails when the user has already reached the daily limit
+async fn start(req: &ServiceRequest) -> AppResult<Option<Meter>> {
+    let direction = match direction(req.method(), req.path()) {
+        Some(direction) => direction,
+        None => return Ok(None),
+    };
+
+    let (context, claims) = match (
+        req.app_data::<web::Data<Context>>(),
+        Claims::try_from(req.request()),
+    ) {
+        (Some(context), Ok(claims)) => (context, claims),
+        _ => return Ok(None),
+    };
+
+    if let Some(limit) = context.settings.inner().await.users.transfer_bytes() {
+        let today = Repository::new(&context.db)
+            .transfers(claims.sub)
+            .today()
+            .await?;
+
+        if (today.uploaded_bytes + today.downloaded_bytes) as u64 >= limit {
+            return Err(Error::TooManyRequests("transfer_limit_reached".to_string()));
+        }
+    }
+
+    Ok(Some(Meter {
+        db: context.db.clone(),
+        user_id: claims.sub,
+        direction,
+        bytes: 0,
+    }))
+}
+
+/// Bytes of a single transfer, they are recorded when it is dropped
+struct Meter {
+    db: DbConn,
+    user_id: Uuid,
+    direction: Direction,
+    bytes: u64,
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        if self.bytes == 0 {
+            return;
+        }
+
+        let (db, user_id, direction, bytes) =
+            (self.db.clone(), self.user_id, self.direction, self.bytes);
+
+        actix_web::rt::spawn(async move {
+            if let Err(e) = Repository::new(&db)
+                .transfers(user_id)
+                .record(direction, bytes)
+                .await
+            {
+                log::error!(
+                    "Failed to record the transfer of the user {}: {}",
+                    user_id,
+                    e
+                );
+            }
+        });
+    }
+}
+
+/// Body of the request or the response that counts the bytes going through it
+struct Metered<S> {
+    inner: S,
+    meter: Meter,
+}
+
+impl Stream for Metered<Payload> {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let poll = Pin::new(&mut self.inner).poll_next(cx);
+
+        if let Poll::Ready(Some(Ok(bytes))) = &poll {
+            self.meter.bytes += bytes.len() as u64;
+        }
+
+        poll
+    }
+}
+
+impl MessageBody for Metered<BoxBody> {
+    type Error = Box<dyn std::error::Error>;
+
+    fn size(&self) -> BodySize {
+        self.inner.size()
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_next(cx);
+
+        if let Poll::Ready(Some(Ok(bytes))) = &poll {
+            self.meter.bytes += bytes.len() as u64;
+        }
+
+        poll
+    }
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{net::IpAddr, str::FromStr};
 
 use actix_web::{
     http::header::{self, HeaderMap},
@@ -63,39 +63,67 @@ pub fn extract_ip_ua(req: &HttpRequest) -> (String, String) {
     )
 }
 
+/// Address of the client that sent the request. The forwarded headers are honoured only
+/// when the request came from one of the trusted proxies, anyone else could send them
+/// and pretend to be someone else, so for them the address of the peer is used.
+pub fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<String> {
+    let peer = req.peer_addr().map(|addr| addr.ip());
+
+    match peer {
+        Some(peer) if trusted_proxies.contains(&peer) => {
+            Some(forwarded_ip(req.headers()).unwrap_or_else(|| peer.to_string()))
+        }
+        Some(peer) => Some(peer.to_string()),
+        None => None,
+    }
+}
+
 /// Extract ip from headers, try to get all the possible resolutions in order to find real ip address
 pub fn get_ip(headers: &HeaderMap) -> String {
-    let mut ip = "127.0.0.2".to_string();
-
-    if headers.contains_key("cf-connecting-ip") && headers.get("cf-connecting-ip").is_some() {
-        ip = headers
-            .get("cf-connecting-ip")
-            .unwrap()
-            .to_str()
-            .unwrap_or("127.0.0.2")
-            .to_string();
-    } else if headers.contains_key("x-real-ip") && headers.get("x-real-ip").is_some() {
-        ip = headers
-            .get("x-real-ip")
-            .unwrap()
-            .to_str()
-            .unwrap_or("127.0.0.2")
-            .to_string();
-    } else if headers.contains_key("x-forwarded-for") && headers.get("x-forwarded-for").is_some() {
-        let ip_string = headers
-            .get("x-forwarded-for")
-            .unwrap()
-            .to_str()
-            .unwrap_or("127.0.0.2")
-            .to_string();
-
-        ip = ip_string
-            .split(',')
-            .map(|item| item.to_owned())
-            .collect::<Vec<String>>()
-            .pop()
-            .unwrap_or_else(|| "127.0.0.2".to_string());
-    }
+    forwarded_ip(headers).unwrap_or_else(|| "127.0.0.2".to_string())
+}
+
+/// Address of the client the proxy has put in the headers
+fn forwarded_ip(headers: &HeaderMap) -> Option<String> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    header("cf-connecting-ip")
+        .or_else(|| header("x-real-ip"))
+        .or_else(|| {
+            header("x-forwarded-for")?
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .last()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_client_ip_honours_forwarded_headers_only_from_trusted_proxies() {
+        let proxy = "10.0.0.1".parse().unwrap();
 
-    ip
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("x-forwarded-for", "1.1.1.1, 2.2.2.2"))
+            .to_http_request();
+
+        assert_eq!(super::client_ip(&req, &[proxy]).as_deref(), Some("2.2.2.2"));
+        assert_eq!(super::client_ip(&req, &[]).as_deref(), Some("10.0.0.1"));
+
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_http_request();
+
+        assert_eq!(super::client_ip(&req, &[proxy]).as_deref(), Some("10.0.0.1"));
+    }
 }
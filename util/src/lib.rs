@@ -2,5 +2,7 @@ pub mod actix;
 pub mod datetime;
 pub mod generate;
 pub mod password;
+pub mod tar;
 pub mod url;
 pub mod validation;
+pub mod zip;
@@ -1,9 +1,47 @@
+//! Passwords are hashed with Argon2id into the PHC strings, the hashes made with
+//! bcrypt before the switch still verify so the existing passwords keep working.
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
 /// Helper method to create hash of a password
 pub fn hash<T: AsRef<[u8]>>(password: T) -> String {
-    bcrypt::hash(password, bcrypt::DEFAULT_COST).unwrap()
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_ref(), &salt)
+        .unwrap()
+        .to_string()
 }
 
 /// Helper method to verify password hash
 pub fn verify(password: &str, hashed_password: &str) -> bool {
-    bcrypt::verify(password, hashed_password).unwrap_or(false)
+    if !hashed_password.starts_with("$argon2") {
+        return bcrypt::verify(password, hashed_password).unwrap_or(false);
+    }
+
+    PasswordHash::new(hashed_password)
+        .and_then(|hash| Argon2::default().verify_password(password.as_bytes(), &hash))
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_hash_and_verify() {
+        let hashed = super::hash("open sesame");
+
+        assert!(hashed.starts_with("$argon2id$"));
+        assert!(super::verify("open sesame", &hashed));
+        assert!(!super::verify("open barley", &hashed));
+    }
+
+    #[test]
+    fn test_verify_bcrypt_hash() {
+        let hashed = bcrypt::hash("open sesame", 4).unwrap();
+
+        assert!(super::verify("open sesame", &hashed));
+        assert!(!super::verify("open barley", &hashed));
+    }
 }
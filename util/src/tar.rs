@@ -0,0 +1,113 @@
+//! Minimal writer of the uncompressed (ustar) tar archives.
+//!
+//! Only regular files are supported, that is all we need to package the data
+//! of the user. Each entry is a 512 byte header followed by the content padded
+//! to the next 512 bytes, and the archive is closed with two empty blocks.
+
+/// Size of the tar block, headers and the content are aligned to it
+pub const BLOCK_SIZE: usize = 512;
+
+/// Two empty blocks that mark the end of the archive
+pub const END: [u8; BLOCK_SIZE * 2] = [0; BLOCK_SIZE * 2];
+
+/// Longest path that fits into the name and prefix fields of the header
+pub const MAX_PATH_LENGTH: usize = 255;
+
+/// Header of the regular file entry with the given path, size and modification time.
+///
+/// Paths longer than 100 bytes are split on a `/` into the prefix and the name,
+/// `None` is returned if the path cannot be stored in the header.
+pub fn header(path: &str, size: u64, modified_at: i64) -> Option<[u8; BLOCK_SIZE]> {
+    let (prefix, name) = split_path(path)?;
+    let mut header = [0u8; BLOCK_SIZE];
+
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], modified_at.max(0) as u64);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // Checksum is calculated with the checksum field itself filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum = header.iter().map(|b| *b as u64).sum::<u64>();
+    write_octal(&mut header[148..155], checksum);
+    header[155] = b' ';
+
+    Some(header)
+}
+
+/// Number of zero bytes that have to follow the content of the given size
+pub fn padding(size: u64) -> usize {
+    let rest = (size % BLOCK_SIZE as u64) as usize;
+
+    match rest {
+        0 => 0,
+        rest => BLOCK_SIZE - rest,
+    }
+}
+
+fn split_path(path: &str) -> Option<(&str, &str)> {
+    if path.is_empty() || path.len() > MAX_PATH_LENGTH {
+        return None;
+    }
+
+    if path.len() <= 100 {
+        return Some(("", path));
+    }
+
+    path.match_indices('/')
+        .map(|(index, _)| (&path[..index], &path[index + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && !name.is_empty() && name.len() <= 100)
+}
+
+/// Write the number as zero padded octal, terminated with the NUL byte
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    let digits = &digits.as_bytes()[digits.len() - (field.len() - 1)..];
+
+    field[..digits.len()].copy_from_slice(digits);
+    field[field.len() - 1] = 0;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header_has_valid_checksum() {
+        let header = header("metadata.json", 1234, 1_700_000_000).unwrap();
+
+        assert_eq!(&header[..13], b"metadata.json");
+        assert_eq!(&header[124..136], b"00000002322\0");
+        assert_eq!(&header[257..263], b"ustar\0");
+
+        let mut unsigned = header;
+        unsigned[148..156].copy_from_slice(b"        ");
+        let checksum = unsigned.iter().map(|b| *b as u64).sum::<u64>();
+
+        assert_eq!(&header[148..156], format!("{:06o}\0 ", checksum).as_bytes());
+    }
+
+    #[test]
+    fn test_long_paths_are_split_into_prefix() {
+        let path = format!("{}/{}", "a".repeat(120), "b".repeat(50));
+        let header = header(&path, 0, 0).unwrap();
+
+        assert_eq!(&header[..50], "b".repeat(50).as_bytes());
+        assert_eq!(&header[345..465], "a".repeat(120).as_bytes());
+        assert!(super::header(&"a".repeat(300), 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_padding_aligns_to_blocks() {
+        assert_eq!(padding(0), 0);
+        assert_eq!(padding(1), 511);
+        assert_eq!(padding(512), 0);
+        assert_eq!(padding(513), 511);
+    }
+}
@@ -0,0 +1,330 @@
+//! Minimal writer of the uncompressed (stored) zip archives.
+//!
+//! The content is stored as it is, so the archive can be sent out while the content
+//! is still being read. Size and checksum of the entry are not known when its header
+//! is written, they follow the content in the data descriptor and are repeated in the
+//! central directory at the end of the archive. Zip64 records are only written when the
+//! sizes, the offsets or the number of the entries don't fit into the classic ones.
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const DATA_DESCRIPTOR: u32 = 0x08074b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const ZIP64_END: u32 = 0x06064b50;
+const ZIP64_LOCATOR: u32 = 0x07064b50;
+const END: u32 = 0x06054b50;
+
+/// Sizes and checksum come after the content, names are in UTF-8
+const FLAGS: u16 = 0x0008 | 0x0800;
+const VERSION: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+/// Made on unix, so the permissions in the external attributes are used
+const VERSION_MADE_BY: u16 = (3 << 8) | VERSION_ZIP64;
+
+const MAX_U16: u64 = u16::MAX as u64;
+const MAX_U32: u64 = u32::MAX as u64;
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// Writer of the archive, it only builds the bytes of the records around the content,
+/// the content itself is sent by the caller right after the header of its entry.
+#[derive(Default)]
+pub struct Writer {
+    entries: Vec<Entry>,
+    current: Option<Entry>,
+    offset: u64,
+}
+
+struct Entry {
+    path: String,
+    time: u16,
+    date: u16,
+    offset: u64,
+    crc: u32,
+    size: u64,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the entry with the given path, paths ending with `/` are directories.
+    ///
+    /// The previous entry is closed with its data descriptor first, it is a part of
+    /// the returned bytes. `None` is returned if the path can't be stored in the header.
+    pub fn start(&mut self, path: &str, modified_at: i64) -> Option<Vec<u8>> {
+        if path.is_empty() || path.len() as u64 > MAX_U16 {
+            return None;
+        }
+
+        let mut bytes = self.close();
+        let (time, date) = dos_datetime(modified_at);
+        let entry = Entry {
+            path: path.to_string(),
+            time,
+            date,
+            offset: self.offset + bytes.len() as u64,
+            crc: 0,
+            size: 0,
+        };
+
+        put_u32(&mut bytes, LOCAL_HEADER);
+        put_u16(&mut bytes, VERSION);
+        put_u16(&mut bytes, FLAGS);
+        put_u16(&mut bytes, 0);
+        put_u16(&mut bytes, entry.time);
+        put_u16(&mut bytes, entry.date);
+        // Checksum and sizes are in the data descriptor
+        put_u32(&mut bytes, 0);
+        put_u32(&mut bytes, 0);
+        put_u32(&mut bytes, 0);
+        put_u16(&mut bytes, entry.path.len() as u16);
+        put_u16(&mut bytes, 0);
+        bytes.extend_from_slice(entry.path.as_bytes());
+
+        self.offset += bytes.len() as u64;
+        self.current = Some(entry);
+
+        Some(bytes)
+    }
+
+    /// Account for the content of the current entry, the caller sends it as it is
+    pub fn write(&mut self, data: &[u8]) {
+        if let Some(entry) = self.current.as_mut() {
+            entry.crc = crc32(entry.crc, data);
+            entry.size += data.len() as u64;
+            self.offset += data.len() as u64;
+        }
+    }
+
+    /// Close the last entry and write the central directory, this is the end of the archive
+    pub fn finish(&mut self) -> Vec<u8> {
+        let mut bytes = self.close();
+        let start = self.offset + bytes.len() as u64;
+
+        for entry in self.entries.iter() {
+            let zip64 = entry.size >= MAX_U32 || entry.offset >= MAX_U32;
+            let attributes: u32 = match entry.path.ends_with('/') {
+                true => (0o040755 << 16) | 0x10,
+                false => 0o100644 << 16,
+            };
+
+            put_u32(&mut bytes, CENTRAL_HEADER);
+            put_u16(&mut bytes, VERSION_MADE_BY);
+            put_u16(&mut bytes, if zip64 { VERSION_ZIP64 } else { VERSION });
+            put_u16(&mut bytes, FLAGS);
+            put_u16(&mut bytes, 0);
+            put_u16(&mut bytes, entry.time);
+            put_u16(&mut bytes, entry.date);
+            put_u32(&mut bytes, entry.crc);
+            put_u32(&mut bytes, entry.size.min(MAX_U32) as u32);
+            put_u32(&mut bytes, entry.size.min(MAX_U32) as u32);
+            put_u16(&mut bytes, entry.path.len() as u16);
+            put_u16(&mut bytes, if zip64 { 28 } else { 0 });
+            put_u16(&mut bytes, 0);
+            put_u16(&mut bytes, 0);
+            put_u16(&mut bytes, 0);
+            put_u32(&mut bytes, attributes);
+            put_u32(&mut bytes, entry.offset.min(MAX_U32) as u32);
+            bytes.extend_from_slice(entry.path.as_bytes());
+
+            if zip64 {
+                // Every field of the header that is maxed out is in the extra field
+                put_u16(&mut bytes, 0x0001);
+                put_u16(&mut bytes, 24);
+                put_u64(&mut bytes, entry.size);
+                put_u64(&mut bytes, entry.size);
+                put_u64(&mut bytes, entry.offset);
+            }
+        }
+
+        let count = self.entries.len() as u64;
+        let size = self.offset + bytes.len() as u64 - start;
+
+        if count >= MAX_U16 || size >= MAX_U32 || start >= MAX_U32 {
+            let end = self.offset + bytes.len() as u64;
+
+            put_u32(&mut bytes, ZIP64_END);
+            put_u64(&mut bytes, 44);
+            put_u16(&mut bytes, VERSION_MADE_BY);
+            put_u16(&mut bytes, VERSION_ZIP64);
+            put_u32(&mut bytes, 0);
+            put_u32(&mut bytes, 0);
+            put_u64(&mut bytes, count);
+            put_u64(&mut bytes, count);
+            put_u64(&mut bytes, size);
+            put_u64(&mut bytes, start);
+
+            put_u32(&mut bytes, ZIP64_LOCATOR);
+            put_u32(&mut bytes, 0);
+            put_u64(&mut bytes, end);
+            put_u32(&mut bytes, 1);
+        }
+
+        put_u32(&mut bytes, END);
+        put_u16(&mut bytes, 0);
+        put_u16(&mut bytes, 0);
+        put_u16(&mut bytes, count.min(MAX_U16) as u16);
+        put_u16(&mut bytes, count.min(MAX_U16) as u16);
+        put_u32(&mut bytes, size.min(MAX_U32) as u32);
+        put_u32(&mut bytes, start.min(MAX_U32) as u32);
+        put_u16(&mut bytes, 0);
+
+        self.offset += bytes.len() as u64;
+
+        bytes
+    }
+
+    /// Data descriptor of the current entry, sizes are 64 bit only when they have to be.
+    /// The caller accounts for the returned bytes in the offset.
+    fn close(&mut self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        if let Some(entry) = self.current.take() {
+            put_u32(&mut bytes, DATA_DESCRIPTOR);
+            put_u32(&mut bytes, entry.crc);
+
+            if entry.size >= MAX_U32 {
+                put_u64(&mut bytes, entry.size);
+                put_u64(&mut bytes, entry.size);
+            } else {
+                put_u32(&mut bytes, entry.size as u32);
+                put_u32(&mut bytes, entry.size as u32);
+            }
+
+            self.entries.push(entry);
+        }
+
+        bytes
+    }
+}
+
+/// Continue the CRC-32 checksum with the given data, start with zero
+pub fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+
+    for byte in data {
+        crc = CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+
+    !crc
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => 0xedb88320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// Time and date in the MS-DOS format, it can't go before 1980
+fn dos_datetime(timestamp: i64) -> (u16, u16) {
+    let datetime = match NaiveDateTime::from_timestamp_opt(timestamp, 0) {
+        Some(datetime) if datetime.year() >= 1980 && datetime.year() <= 2107 => datetime,
+        _ => return (0, (1 << 5) | 1),
+    };
+
+    let time = (datetime.hour() << 11) | (datetime.minute() << 5) | (datetime.second() / 2);
+    let date = ((datetime.year() as u32 - 1980) << 9) | (datetime.month() << 5) | datetime.day();
+
+    (time as u16, date as u16)
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_crc32_matches_the_check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(crc32(0, b"12345"), b"6789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn test_archive_has_the_records_in_place() {
+        let mut writer = Writer::new();
+        let mut archive = vec![];
+
+        archive.extend(writer.start("dir/", 1_700_000_000).unwrap());
+        archive.extend(writer.start("dir/file.txt", 1_700_000_000).unwrap());
+        writer.write(b"1234");
+        archive.extend_from_slice(b"1234");
+        writer.write(b"56789");
+        archive.extend_from_slice(b"56789");
+        archive.extend(writer.finish());
+
+        // Directory entry and its empty data descriptor
+        assert_eq!(u32_at(&archive, 0), LOCAL_HEADER);
+        assert_eq!(&archive[30..34], b"dir/");
+        assert_eq!(u32_at(&archive, 34), DATA_DESCRIPTOR);
+
+        // File entry, its content and the data descriptor with the checksum
+        let file = 34 + 16;
+        assert_eq!(u32_at(&archive, file), LOCAL_HEADER);
+        assert_eq!(&archive[file + 30..file + 42], b"dir/file.txt");
+        assert_eq!(&archive[file + 42..file + 51], b"123456789");
+        assert_eq!(u32_at(&archive, file + 51), DATA_DESCRIPTOR);
+        assert_eq!(u32_at(&archive, file + 55), 0xcbf43926);
+        assert_eq!(u32_at(&archive, file + 59), 9);
+
+        // End of the central directory points back to it
+        let end = archive.len() - 22;
+        let start = u32_at(&archive, end + 16) as usize;
+        assert_eq!(u32_at(&archive, end), END);
+        assert_eq!(u16_at(&archive, end + 10), 2);
+        assert_eq!(u32_at(&archive, end + 12) as usize, end - start);
+        assert_eq!(u32_at(&archive, start), CENTRAL_HEADER);
+        assert_eq!(u32_at(&archive, start + 42), 0);
+
+        let second = start + 46 + 4;
+        assert_eq!(u32_at(&archive, second), CENTRAL_HEADER);
+        assert_eq!(u32_at(&archive, second + 16), 0xcbf43926);
+        assert_eq!(u32_at(&archive, second + 42) as usize, file);
+    }
+
+    #[test]
+    fn test_dates_before_1980_are_clamped() {
+        assert_eq!(dos_datetime(0), (0, (1 << 5) | 1));
+        assert_eq!(
+            dos_datetime(1_700_000_000),
+            ((22 << 11) | (13 << 5) | 10, (43 << 9) | (11 << 5) | 14)
+        );
+    }
+}
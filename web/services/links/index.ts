@@ -171,8 +171,9 @@ export const store = defineStore('links', () => {
       throw new Error('Failed to update link')
     }
 
+    // Zero removes the expiry, leaving it out would keep it
     await Api.put(`/api/links/${id}`, undefined, {
-      expires_at
+      expires_at: expires_at ?? 0
     })
 
     addItem({ ...link, expires_at })
@@ -180,6 +181,34 @@ export const store = defineStore('links', () => {
     return { ...link, expires_at }
   }
 
+  /**
+   * Give the link a short slug it can be opened by under `/s/{slug}`,
+   * or remove the slug it has when none is given.
+   */
+  async function slug(id: string, slug?: string): Promise<AppLink> {
+    const link = takeItem(id)
+
+    if (!link) {
+      throw new Error('Failed to update link')
+    }
+
+    try {
+      if (slug) {
+        await Api.put(`/api/links/${id}/slug`, undefined, { slug })
+      } else {
+        await Api.delete(`/api/links/${id}/slug`)
+      }
+    } catch (error) {
+      addItem(link)
+      throw error
+    }
+
+    const updated = { ...link, slug: slug?.trim().toLowerCase() || undefined }
+    addItem(updated)
+
+    return updated
+  }
+
   /**
    * Load all the shared links for the user.
    */
@@ -247,6 +276,7 @@ export const store = defineStore('links', () => {
     removeItem,
     selectAll,
     selectOne,
+    slug,
     takeItem,
     updateItem,
     upsertItem,
@@ -225,7 +225,7 @@ export const store = defineStore('files', () => {
 
     fileId.value = parentId
 
-    let response: FileResponse = { children: [], parents: [] }
+    let response: FileResponse = { children: [], parents: [], total: 0 }
     loading.value = showLoading
 
     // We wrap this here so we can somewhat support failing network
@@ -10,6 +10,7 @@ import type {
   KeyPair,
   EncryptedAppFile,
   SearchQuery,
+  SearchResponse,
   AppFileEncryptedPart,
   AppFileUnencryptedPart,
   StorageStatsResponse,
@@ -87,6 +88,7 @@ export async function create(keypair: KeyPair, unencrypted: CreateFile): Promise
     chunks: unencrypted.chunks,
     file_id: unencrypted.file_id,
     file_modified_at: unencrypted.file_modified_at,
+    magic: unencrypted.magic,
     ...encryptedParts
   }
 
@@ -242,13 +244,13 @@ export async function search(input: string, dir_id?: string): Promise<EncryptedA
     skip: 0
   }
 
-  const response = await Api.post<SearchQuery, EncryptedAppFile[]>(
+  const response = await Api.post<SearchQuery, SearchResponse<EncryptedAppFile>>(
     `/api/storage/search`,
     undefined,
     body
   )
 
-  return response.body || []
+  return response.body?.files || []
 }
 
 /**
@@ -225,6 +225,15 @@ export const store = defineStore('upload', () => {
     }
   }
 
+  /**
+   * First bytes of the file, the server tells the mime type from them
+   */
+  async function magic(file: File): Promise<string> {
+    const bytes = await file.slice(0, 32).arrayBuffer()
+
+    return cryptfns.uint8.toHex(new Uint8Array(bytes))
+  }
+
   /**
    * Create new file metadata and add it to the upload queue
    */
@@ -242,7 +251,8 @@ export const store = defineStore('upload', () => {
       file_id: parent_id,
       file_modified_at: utcStringFromLocal(modified),
       search_tokens_hashed,
-      thumbnail
+      thumbnail,
+      magic: file.type ? undefined : await magic(file)
     }
 
     const created = await meta.create(keypair, createFile)
@@ -1,9 +1,13 @@
 export interface Data {
   users: Users
+  escrow: Escrow
+  files: Files
+  instance: Instance
 }
 
 export interface Users {
   quota_bytes?: number
+  transfer_bytes?: number
   allow_register: boolean
   enforce_email_activation: boolean
   email_whitelist: WhitelistOrBlacklist
@@ -13,3 +17,26 @@ export interface Users {
 export interface WhitelistOrBlacklist {
   rules: string[]
 }
+
+export interface Escrow {
+  enabled: boolean
+  public_key?: string
+}
+
+export interface Files {
+  mime_whitelist?: WhitelistOrBlacklist
+  mime_blacklist?: WhitelistOrBlacklist
+  max_sizes: SizeLimit[]
+}
+
+export interface SizeLimit {
+  mime: string
+  max_bytes: number
+}
+
+export interface Instance {
+  name: string
+  logo_url?: string
+  accent_color?: string
+  terms_url?: string
+}
@@ -53,6 +53,12 @@ export interface CreateFile extends AppFileUnencryptedPart {
    * hash each token and load it in this array.
    */
   search_tokens_hashed?: string[]
+
+  /**
+   * First bytes of the unencrypted file in hex, sent when the browser
+   * doesn't know the mime type so the server can tell it from them
+   */
+  magic?: string
 }
 
 export interface EncryptedCreateFile {
@@ -98,4 +104,10 @@ export interface EncryptedCreateFile {
    * When was the file created on disk
    */
   file_modified_at?: string
+
+  /**
+   * First bytes of the unencrypted file in hex, sent when the browser
+   * doesn't know the mime type so the server can tell it from them
+   */
+  magic?: string
 }
@@ -35,21 +35,40 @@ export interface Paginated<T> {
 export interface Parameters extends Query {
   dir_id?: string | null
   order?: 'asc' | 'desc'
-  order_by?: 'created_at' | 'size'
+  order_by?: 'created_at' | 'modified_at' | 'size' | 'name_hash'
   dirs_only?: boolean
   is_owner?: boolean
+  mime?: string
+  limit?: number
+  offset?: number
 }
 
 export interface SearchQuery {
   search_tokens_hashed: string[]
   dir_id?: string
+  mime?: string
+  kind?: 'image' | 'video' | 'audio' | 'document' | 'dir'
+  uploaded_after?: number
+  uploaded_before?: number
+  min_size?: number
+  max_size?: number
+  order?: 'asc' | 'desc'
+  order_by?: 'created_at' | 'modified_at' | 'size' | 'name_hash'
   limit?: number
   skip?: number
+  cursor?: string
+}
+
+export interface SearchResponse<T> {
+  files: T[]
+  total: number
+  next_cursor?: string
 }
 
 export interface FileResponse {
   parents?: AppFile[]
   children: AppFile[]
+  total: number
 }
 
 export interface Stats {
@@ -58,10 +77,18 @@ export interface Stats {
   count: number
 }
 
+export interface Transfer {
+  day: number
+  uploaded_bytes: number
+  downloaded_bytes: number
+}
+
 export interface StorageStatsResponse {
   stats: Stats[]
   used_space: number
   quota?: number
+  transfers: Transfer[]
+  transfer_limit?: number
 }
 
 export interface SingleChunk {
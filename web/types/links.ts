@@ -31,10 +31,30 @@ export interface CreateLink {
    */
   encrypted_file_key: string
 
+  /**
+   * Date when the file can be downloaded through the link
+   */
+  starts_at?: number
+
   /**
    * Expiration date of the link
    */
   expires_at?: string
+
+  /**
+   * Two letter codes of the countries the file can be downloaded from
+   */
+  allowed_countries?: string[]
+
+  /**
+   * Address ranges in the CIDR notation the file can be downloaded from
+   */
+  allowed_networks?: string[]
+
+  /**
+   * Short name the link can be opened by under `/s/{slug}`
+   */
+  slug?: string
 }
 
 export interface AppLink extends EncryptedAppLink {
@@ -52,6 +72,7 @@ export interface EncryptedAppLink {
   owner_pubkey: string
   file_size: number
   file_mime: string
+  file_chunks?: number
   signature: string
   downloads: number
   encrypted_name: string
@@ -59,7 +80,26 @@ export interface EncryptedAppLink {
   encrypted_thumbnail?: string
   created_at: number
   file_modified_at: number
+  starts_at?: number
   expires_at?: number
+  allowed_countries: string[]
+  allowed_networks: string[]
+  slug?: string
+}
+
+export interface ChunkMap {
+  chunks: number
+  chunk_size: number
+  stored: number[]
+}
+
+/**
+ * Everything the landing page needs to decrypt the file in the browser,
+ * the file key is missing for the links with the watermark
+ */
+export interface LandingPage extends EncryptedAppLink {
+  encrypted_file_key?: string
+  chunk_map: ChunkMap
 }
 
 export interface EncryptedLink {